@@ -0,0 +1,22 @@
+# Example client for TogMic's local IPC named pipe. Requires "localIpc": true in the settings.
+#
+#   .\togmic-ipc.ps1 '{"cmd":"getState"}'
+#   .\togmic-ipc.ps1 '{"cmd":"toggle"}'
+#   .\togmic-ipc.ps1 '{"cmd":"set","muted":true}'
+#   .\togmic-ipc.ps1 '{"cmd":"setProfile","id":"Meetings"}'
+#   .\togmic-ipc.ps1 '{"cmd":"listProfiles"}'
+param([string]$Request = '{"cmd":"getState"}')
+
+$pipe = New-Object System.IO.Pipes.NamedPipeClientStream('.', 'togmic', [System.IO.Pipes.PipeDirection]::InOut)
+$pipe.Connect(2000)
+try {
+    $writer = New-Object System.IO.StreamWriter($pipe)
+    $writer.AutoFlush = $true
+    $reader = New-Object System.IO.StreamReader($pipe)
+
+    $writer.WriteLine($Request)
+    $reader.ReadLine() | ConvertFrom-Json | ConvertTo-Json -Depth 10
+}
+finally {
+    $pipe.Dispose()
+}
@@ -0,0 +1,19 @@
+#!/bin/sh
+# Example client for TogMic's local IPC socket. Requires socat and "localIpc": true in the
+# settings.
+#
+#   ./togmic-ipc.sh '{"cmd":"getState"}'
+#   ./togmic-ipc.sh '{"cmd":"toggle"}'
+#   ./togmic-ipc.sh '{"cmd":"set","muted":true}'
+#   ./togmic-ipc.sh '{"cmd":"setProfile","id":"Meetings"}'
+#   ./togmic-ipc.sh '{"cmd":"listProfiles"}'
+set -eu
+
+if [ -n "${XDG_RUNTIME_DIR:-}" ]; then
+    SOCKET="$XDG_RUNTIME_DIR/togmic.sock"
+else
+    SOCKET="${TMPDIR:-/tmp}/togmic-${USER:-${LOGNAME:-}}/togmic.sock"
+fi
+REQUEST="${1:-{\"cmd\":\"getState\"\}}"
+
+printf '%s\n' "$REQUEST" | socat -t 2 - "UNIX-CONNECT:$SOCKET"
@@ -0,0 +1,252 @@
+//! Opt-in local WebSocket control API for dashboards and other external integrations.
+//!
+//! The server only binds to 127.0.0.1 and every connection must present the access token,
+//! either as a `token` query parameter or as an `Authorization: Bearer <token>` header.
+//!
+//! Client → server (one JSON object per text message, optional `id` is echoed back):
+//!   {"command": "toggle"}
+//!   {"command": "set", "muted": true}
+//!   {"command": "getState"}
+//!   {"command": "listProfiles"}
+//!   {"command": "setProfile", "id": "<profile id or name>"}
+//!
+//! Commands are executed by the dispatcher shared with the other control surfaces
+//! (`commands::external::dispatch`), which also accepts `cmd` in place of `command`.
+//!
+//! Server → client:
+//!   {"type": "result", "id": ..., "data": ...}
+//!   {"type": "error", "id": ..., "message": "..."}
+//!   {"type": "stateChanged", "muted": true}
+//!
+//! Connections to `/streamdeck` speak the Stream Deck plugin protocol instead, see
+//! `streamdeck.rs`.
+
+use crate::commands::external::{self, ControlCommand};
+use crate::streamdeck;
+use crate::{AppHandle, MutexExt};
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+struct ServerHandle {
+    port: u16,
+    shutdown: watch::Sender<bool>,
+}
+
+/// The running server, if any. Replaced/cleared by `start()` and `stop()`.
+static SERVER: Lazy<Mutex<Option<ServerHandle>>> = Lazy::new(|| Mutex::new(None));
+
+/// Fan-out of mute state changes to every connected client. Each protocol formats the
+/// push itself.
+static EVENTS: Lazy<broadcast::Sender<bool>> = Lazy::new(|| broadcast::channel(32).0);
+
+#[derive(Debug, Deserialize)]
+struct ApiRequest {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    command: ControlCommand,
+}
+
+/// Generate a random 32-character hex access token.
+pub fn generate_token() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Start (or restart on a different port) the server. A no-op if it is already running on
+/// `port`.
+pub fn start(app: AppHandle, port: u16, token: String) -> Result<(), String> {
+    let mut server = SERVER.lock_safe();
+    if server.as_ref().is_some_and(|s| s.port == port) {
+        return Ok(());
+    }
+    if let Some(previous) = server.take() {
+        let _ = previous.shutdown.send(true);
+    }
+
+    // Bind synchronously so a port conflict is reported to the caller
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to bind control API to port {}: {}", port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure control API socket: {}", e))?;
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    *server = Some(ServerHandle {
+        port,
+        shutdown: shutdown_tx,
+    });
+
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::from_std(listener) {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!("[api] Failed to start listener: {}", e);
+                return;
+            }
+        };
+        accept_loop(app, listener, token, shutdown_rx).await;
+    });
+
+    Ok(())
+}
+
+/// Stop the server and disconnect all clients.
+pub fn stop() {
+    if let Some(server) = SERVER.lock_safe().take() {
+        let _ = server.shutdown.send(true);
+    }
+}
+
+/// Push a state change to all connected clients.
+pub fn publish_state(muted: bool) {
+    // Errors only mean nobody is connected
+    let _ = EVENTS.send(muted);
+}
+
+async fn accept_loop(
+    app: AppHandle,
+    listener: TcpListener,
+    token: String,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                if let Ok((stream, _)) = accepted {
+                    tauri::async_runtime::spawn(handle_connection(
+                        app.clone(),
+                        stream,
+                        token.clone(),
+                        shutdown.clone(),
+                    ));
+                }
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    let query_token = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(str::to_string)
+    });
+    let header_token = request
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    query_token.as_deref() == Some(token) || header_token.as_deref() == Some(token)
+}
+
+async fn handle_connection(
+    app: AppHandle,
+    stream: TcpStream,
+    token: String,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut path = String::new();
+    // The callback's error type is fixed by tungstenite
+    #[allow(clippy::result_large_err)]
+    let auth = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        path = request.uri().path().to_string();
+        if is_authorized(request, &token) {
+            Ok(response)
+        } else {
+            let mut error = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+            *error.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(error)
+        }
+    };
+
+    let Ok(ws) = tokio_tungstenite::accept_hdr_async(stream, auth).await else {
+        return;
+    };
+    let events = EVENTS.subscribe();
+
+    if path == streamdeck::PATH {
+        streamdeck::serve(app, ws, events, shutdown).await;
+        return;
+    }
+
+    let (mut sink, mut source) = ws.split();
+    let mut events = events;
+
+    loop {
+        tokio::select! {
+            incoming = source.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = handle_message(&app, &text).await;
+                if sink.send(Message::Text(reply.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                match event {
+                    Ok(muted) => {
+                        let push = json!({ "type": "stateChanged", "muted": muted });
+                        if sink.send(Message::Text(push.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Missed some pushes; the next one carries the full state anyway
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            _ = shutdown.changed() => {
+                let _ = sink.send(Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+}
+
+async fn handle_message(app: &AppHandle, text: &str) -> Value {
+    let mut value: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(e) => return json!({ "type": "error", "id": null, "message": e.to_string() }),
+    };
+    // The API names the command field "command"; the shared dispatcher calls it "cmd"
+    if let Some(object) = value.as_object_mut() {
+        if let Some(command) = object.remove("command") {
+            object.insert("cmd".to_string(), command);
+        }
+    }
+    let request: ApiRequest = match serde_json::from_value(value) {
+        Ok(r) => r,
+        Err(e) => return json!({ "type": "error", "id": null, "message": e.to_string() }),
+    };
+    let id = request.id;
+
+    // Commands may touch audio devices, so run them off the async executor
+    let app = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        external::dispatch(&app, "api", request.command)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(data)) => json!({ "type": "result", "id": id, "data": data }),
+        Ok(Err(message)) => json!({ "type": "error", "id": id, "message": message }),
+        Err(e) => json!({ "type": "error", "id": id, "message": e.to_string() }),
+    }
+}
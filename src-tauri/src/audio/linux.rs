@@ -1,25 +1,419 @@
-use super::{AudioController, AudioDevice};
+//! PulseAudio / PipeWire backend on top of the `pactl` command line tool.
+//!
+//! Shelling out avoids a build dependency on libpulse and works the same against
+//! PulseAudio and pipewire-pulse. Sources are read from `pactl --format=json list sources`
+//! (pactl 16 and newer); device IDs are the source names, which survive restarts of the
+//! sound server. The polling loop asks for every device's mute state twice a second, so
+//! the parsed list is cached for `CACHE_MAX_AGE` and shared by all controllers instead of
+//! forking pactl for each query. Failures are mapped to messages that say what is wrong
+//! (`NotSupported:` if pactl is missing or too old); a sound server that is restarting
+//! counts as transient.
+
+use super::{AudioController, AudioDevice, DefaultRole, DeviceKind, DeviceState, FormFactor};
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a parsed source list answers mute state queries.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(1);
+/// pactl waits for the sound server without a timeout of its own.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+/// `pa_volume_t` of 100%.
+const VOLUME_NORM: f64 = 65536.0;
+
+/// Prefix of the error while the sound server can't be reached; see `is_transient_error`.
+const CONNECTION_FAILED: &str = "Couldn't connect to the sound server";
+
+/// A source as `pactl --format=json list sources` prints it. PulseAudio and pipewire-pulse
+/// leave out different fields, so everything but the name is optional.
+#[derive(Debug, Clone, Deserialize)]
+struct Source {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    mute: Option<serde_json::Value>,
+    /// Channel name → `{ "value": 65536, "value_percent": "100%", ... }`.
+    #[serde(default)]
+    volume: HashMap<String, serde_json::Value>,
+    /// The sink this source monitors; `"n/a"` (or missing) for real sources.
+    #[serde(default)]
+    monitor_of_sink: Option<String>,
+    #[serde(default)]
+    properties: HashMap<String, serde_json::Value>,
+}
+
+impl Source {
+    fn is_monitor(&self) -> bool {
+        self.monitor_of_sink
+            .as_deref()
+            .is_some_and(|sink| !sink.is_empty() && sink != "n/a")
+            || self.property("device.class") == Some("monitor")
+    }
+
+    /// PulseAudio prints `true`/`false`, some pipewire-pulse versions `"yes"`/`"no"`.
+    fn muted(&self) -> Result<bool, String> {
+        match &self.mute {
+            Some(serde_json::Value::Bool(muted)) => Ok(*muted),
+            Some(serde_json::Value::String(s)) if s == "yes" => Ok(true),
+            Some(serde_json::Value::String(s)) if s == "no" => Ok(false),
+            other => Err(format!(
+                "pactl reported no mute state for '{}' ({:?})",
+                self.name, other
+            )),
+        }
+    }
+
+    /// Average of the channel volumes as a scalar from 0.0 to 1.0 (or above, if boosted).
+    fn volume(&self) -> Result<f32, String> {
+        let values: Vec<f64> = self
+            .volume
+            .values()
+            .filter_map(|channel| channel.get("value").and_then(serde_json::Value::as_f64))
+            .collect();
+        if values.is_empty() {
+            return Err(format!("pactl reported no volume for '{}'", self.name));
+        }
+        let average = values.iter().sum::<f64>() / values.len() as f64;
+        Ok((average / VOLUME_NORM) as f32)
+    }
+
+    fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).and_then(serde_json::Value::as_str)
+    }
+
+    fn kind(&self) -> DeviceKind {
+        match (self.property("device.bus"), self.property("device.api")) {
+            (Some("usb"), _) => DeviceKind::Usb,
+            (Some("bluetooth"), _) | (_, Some("bluez5")) => DeviceKind::Bluetooth,
+            (Some("pci" | "isa"), _) => DeviceKind::Builtin,
+            // Null sinks, loopbacks and filter chains have no hardware behind them
+            (None, None) => DeviceKind::Virtual,
+            _ => DeviceKind::Unknown,
+        }
+    }
+
+    fn form_factor(&self) -> FormFactor {
+        match self.property("device.form_factor") {
+            Some("microphone" | "webcam" | "internal") => FormFactor::Microphone,
+            Some("headset") => FormFactor::Headset,
+            Some("handset") => FormFactor::Handset,
+            _ => FormFactor::Unknown,
+        }
+    }
+}
+
+/// A parsed source list and when it was read.
+type SourceList = (Instant, Vec<Source>);
+
+/// The last parsed source list, for all controllers.
+static SOURCES: Lazy<Mutex<Option<SourceList>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether a device error is likely to clear up on its own: the sound server is
+/// restarting (or pactl timed out waiting for it).
+pub fn is_transient_error(error: &str) -> bool {
+    error.starts_with(CONNECTION_FAILED)
+}
+
+/// Run pactl with `args` and return its standard output.
+fn pactl(args: &[&str]) -> Result<String, String> {
+    let mut child = Command::new("pactl")
+        // English messages, for `pactl_error`
+        .env("LC_ALL", "C")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => "NotSupported: pactl isn't installed (it comes with \
+                 pulseaudio-utils or pipewire-pulse)"
+                .to_string(),
+            _ => format!("Failed to run pactl: {}", e),
+        })?;
+
+    // Read the output while waiting, so a long source list can't fill the pipe
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let out = std::thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stdout.read_to_string(&mut text);
+        text
+    });
+    let err = std::thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text);
+        text
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < COMMAND_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} (pactl timed out)", CONNECTION_FAILED));
+            }
+            Err(e) => return Err(format!("Failed to wait for pactl: {}", e)),
+        }
+    };
+    let stdout = out.join().unwrap_or_default();
+    let stderr = err.join().unwrap_or_default();
+    if status.success() {
+        Ok(stdout)
+    } else {
+        Err(pactl_error(args, stderr.trim()))
+    }
+}
+
+fn pactl_error(args: &[&str], stderr: &str) -> String {
+    if stderr.contains("Connection failure") || stderr.contains("Connection refused") {
+        format!("{}: {}", CONNECTION_FAILED, stderr)
+    } else if stderr.contains("No such entity") {
+        let device = args.get(1).copied().unwrap_or_default();
+        format!("Device not found: {}", device)
+    } else if stderr.contains("--format") || stderr.contains("unrecognized option") {
+        "NotSupported: pactl 16 or newer is required".to_string()
+    } else if stderr.is_empty() {
+        format!("pactl {} failed", args.join(" "))
+    } else {
+        format!("pactl {} failed: {}", args.join(" "), stderr)
+    }
+}
+
+/// The sources in `json`, without monitors.
+fn parse_sources(json: &str) -> Result<Vec<Source>, String> {
+    serde_json::from_str::<Vec<Source>>(json)
+        .map(|sources| sources.into_iter().filter(|s| !s.is_monitor()).collect())
+        .map_err(|e| format!("Unexpected output of pactl list sources: {}", e))
+}
+
+/// The sources without monitors, from the cache if it is fresh enough.
+fn sources(refresh: bool) -> Result<Vec<Source>, String> {
+    if !refresh {
+        if let Some((at, sources)) = SOURCES.lock_safe().as_ref() {
+            if at.elapsed() < CACHE_MAX_AGE {
+                return Ok(sources.clone());
+            }
+        }
+    }
+    let sources = parse_sources(&pactl(&["--format=json", "list", "sources"])?)?;
+    *SOURCES.lock_safe() = Some((Instant::now(), sources.clone()));
+    Ok(sources)
+}
+
+fn source(device_id: &str) -> Result<Source, String> {
+    let find = |sources: Vec<Source>| sources.into_iter().find(|s| s.name == device_id);
+    // A device that just appeared isn't in the cached list yet
+    match find(sources(false)?) {
+        Some(source) => Ok(source),
+        None => find(sources(true)?).ok_or_else(|| format!("Device not found: {}", device_id)),
+    }
+}
+
+/// Apply a change pactl just made to the cached list, so the next poll doesn't report
+/// the old state.
+fn update_cached(device_id: &str, update: impl FnOnce(&mut Source)) {
+    if let Some((_, sources)) = SOURCES.lock_safe().as_mut() {
+        if let Some(source) = sources.iter_mut().find(|s| s.name == device_id) {
+            update(source);
+        }
+    }
+}
+
+fn default_source() -> Option<String> {
+    parse_default_source(&pactl(&["--format=json", "info"]).ok()?)
+}
+
+fn parse_default_source(info: &str) -> Option<String> {
+    let info: serde_json::Value = serde_json::from_str(info).ok()?;
+    info.get("default_source_name")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+}
+
+fn to_device(source: Source, default: Option<&str>) -> AudioDevice {
+    AudioDevice {
+        is_default: default == Some(source.name.as_str()),
+        state: DeviceState::Active,
+        kind: source.kind(),
+        form_factor: source.form_factor(),
+        name: source
+            .description
+            .clone()
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| source.name.clone()),
+        id: source.name,
+        system_name: None,
+    }
+}
 
 pub struct LinuxAudioController;
 
 impl AudioController for LinuxAudioController {
     fn new() -> Result<Self, String> {
-        // TODO: Implement PulseAudio initialization
+        // Fails early if pactl is missing or can't reach the sound server
+        sources(false)?;
         Ok(LinuxAudioController)
     }
 
     fn enumerate_input_devices(&self) -> Result<Vec<AudioDevice>, String> {
-        // TODO: Implement PulseAudio device enumeration
-        Err("Linux audio control not yet implemented".to_string())
+        let sources = sources(true)?;
+        let default = default_source();
+        Ok(sources
+            .into_iter()
+            .map(|source| to_device(source, default.as_deref()))
+            .collect())
+    }
+
+    fn get_mute_state(&self, device_id: &str) -> Result<bool, String> {
+        source(device_id)?.muted()
+    }
+
+    fn set_mute_state(&self, device_id: &str, muted: bool) -> Result<(), String> {
+        pactl(&["set-source-mute", device_id, if muted { "1" } else { "0" }])?;
+        update_cached(device_id, |source| {
+            source.mute = Some(serde_json::Value::Bool(muted));
+        });
+        Ok(())
+    }
+
+    fn get_volume(&self, device_id: &str) -> Result<f32, String> {
+        source(device_id)?.volume()
+    }
+
+    fn set_volume(&self, device_id: &str, level: f32) -> Result<(), String> {
+        let value = (level.clamp(0.0, 1.0) as f64 * VOLUME_NORM).round() as u32;
+        pactl(&["set-source-volume", device_id, &value.to_string()])?;
+        update_cached(device_id, |source| {
+            for channel in source.volume.values_mut() {
+                if let Some(v) = channel.get_mut("value") {
+                    *v = serde_json::Value::from(value);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// PulseAudio has a single default source, whatever the role.
+    fn set_default_input_device(&self, device_id: &str, _role: DefaultRole) -> Result<(), String> {
+        pactl(&["set-default-source", device_id])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIPEWIRE: &str = include_str!("../../tests/fixtures/pactl/sources-pipewire.json");
+    const PULSEAUDIO: &str = include_str!("../../tests/fixtures/pactl/sources-pulseaudio.json");
+    const INFO: &str = include_str!("../../tests/fixtures/pactl/info.json");
+
+    fn parse_devices(json: &str) -> Vec<AudioDevice> {
+        let default = parse_default_source(INFO);
+        parse_sources(json)
+            .unwrap()
+            .into_iter()
+            .map(|source| to_device(source, default.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_sources_without_monitors() {
+        let devices = parse_devices(PIPEWIRE);
+        let ids: Vec<&str> = devices.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "alsa_input.pci-0000_00_1f.3.analog-stereo",
+                "alsa_input.usb-Blue_Microphones_Yeti_Stereo_Microphone-00.analog-stereo",
+                "bluez_input.00_1B_66_AA_BB_CC.0",
+                "noise_suppression_output",
+            ]
+        );
+        assert_eq!(parse_devices(PULSEAUDIO).len(), 1);
+    }
+
+    #[test]
+    fn maps_source_properties() {
+        let devices = parse_devices(PIPEWIRE);
+        let (builtin, usb, bluetooth, filter) =
+            (&devices[0], &devices[1], &devices[2], &devices[3]);
+
+        assert_eq!(builtin.name, "Built-in Audio Analog Stereo");
+        assert_eq!(builtin.kind, DeviceKind::Builtin);
+        assert_eq!(builtin.form_factor, FormFactor::Microphone);
+        assert!(!builtin.is_default);
+
+        assert_eq!(usb.kind, DeviceKind::Usb);
+        assert_eq!(usb.form_factor, FormFactor::Unknown);
+        assert!(usb.is_default);
+
+        // No description falls back to the source name
+        assert_eq!(bluetooth.name, bluetooth.id);
+        assert_eq!(bluetooth.kind, DeviceKind::Bluetooth);
+        assert_eq!(bluetooth.form_factor, FormFactor::Headset);
+
+        assert_eq!(filter.kind, DeviceKind::Virtual);
+    }
+
+    #[test]
+    fn reads_both_mute_formats() {
+        let sources = parse_sources(PIPEWIRE).unwrap();
+        let muted: Vec<bool> = sources.iter().map(|s| s.muted().unwrap()).collect();
+        assert_eq!(muted, [false, true, false, true]);
+        assert!(parse_sources(PULSEAUDIO).unwrap()[0].muted().unwrap());
+
+        let mut source = sources[0].clone();
+        source.mute = None;
+        assert!(source.muted().is_err());
+    }
+
+    #[test]
+    fn averages_channel_volumes() {
+        let sources = parse_sources(PIPEWIRE).unwrap();
+        assert!((sources[0].volume().unwrap() - 0.625).abs() < 1e-6);
+        assert!((sources[2].volume().unwrap() - 1.0).abs() < 1e-6);
+
+        let mut source = sources[0].clone();
+        source.volume.clear();
+        assert!(source.volume().is_err());
     }
 
-    fn get_mute_state(&self, _device_id: &str) -> Result<bool, String> {
-        // TODO: Implement PulseAudio mute state query
-        Err("Linux audio control not yet implemented".to_string())
+    #[test]
+    fn rejects_unexpected_output() {
+        assert!(parse_sources("Source #0\n\tState: SUSPENDED").is_err());
+        assert!(parse_sources("[{\"index\":1}]").is_err());
+        assert_eq!(parse_default_source("{}"), None);
     }
 
-    fn set_mute_state(&self, _device_id: &str, _muted: bool) -> Result<(), String> {
-        // TODO: Implement PulseAudio mute control
-        Err("Linux audio control not yet implemented".to_string())
+    #[test]
+    fn explains_pactl_failures() {
+        let args = ["set-source-mute", "mic", "1"];
+        let error = pactl_error(&args, "Connection failure: Connection refused");
+        assert!(is_transient_error(&error));
+        assert_eq!(
+            pactl_error(&args, "Failure: No such entity"),
+            "Device not found: mic"
+        );
+        assert_eq!(
+            pactl_error(
+                &["--format=json", "info"],
+                "pactl: unrecognized option '--format=json'"
+            ),
+            "NotSupported: pactl 16 or newer is required"
+        );
+        assert_eq!(pactl_error(&args, ""), "pactl set-source-mute mic 1 failed");
+        assert!(!is_transient_error(&pactl_error(&args, "Access denied")));
     }
 }
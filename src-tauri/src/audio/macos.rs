@@ -1,4 +1,75 @@
-use super::{AudioController, AudioDevice};
+//! CoreAudio backend (not implemented yet) and the microphone permission.
+//!
+//! macOS only lets an app use the microphone once the user allowed it (TCC); until then
+//! CoreAudio calls fail with unhelpful errors. Every controller method checks the
+//! permission first and fails with `MIC_ACCESS_DENIED` while it is denied.
+
+use super::{AudioController, AudioDevice, MicPermission, MIC_ACCESS_DENIED};
+use block2::RcBlock;
+use objc2::runtime::{AnyObject, Bool};
+use objc2::{class, msg_send};
+
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {
+    /// `AVMediaType` (an `NSString`) of audio capture devices.
+    static AVMediaTypeAudio: &'static AnyObject;
+}
+
+// AVAuthorizationStatus
+const AUTHORIZATION_NOT_DETERMINED: isize = 0;
+const AUTHORIZATION_RESTRICTED: isize = 1;
+const AUTHORIZATION_DENIED: isize = 2;
+const AUTHORIZATION_AUTHORIZED: isize = 3;
+
+/// `AVCaptureDevice.authorizationStatus(for: .audio)`.
+pub fn check_mic_permission() -> MicPermission {
+    let status: isize = unsafe {
+        msg_send![class!(AVCaptureDevice), authorizationStatusForMediaType: AVMediaTypeAudio]
+    };
+    match status {
+        AUTHORIZATION_AUTHORIZED => MicPermission::Granted,
+        AUTHORIZATION_DENIED => MicPermission::Denied,
+        AUTHORIZATION_RESTRICTED => MicPermission::Restricted,
+        AUTHORIZATION_NOT_DETERMINED => MicPermission::NotDetermined,
+        other => {
+            log::warn!("[permission] unknown authorization status {}", other);
+            MicPermission::NotDetermined
+        }
+    }
+}
+
+/// Show the system prompt if the user hasn't decided yet and wait for the answer. Once
+/// decided, macOS doesn't ask again; only the privacy settings can change it.
+pub fn request_mic_permission() -> MicPermission {
+    let status = check_mic_permission();
+    if status != MicPermission::NotDetermined {
+        return status;
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handler = RcBlock::new(move |_granted: Bool| {
+        let _ = tx.send(());
+    });
+    unsafe {
+        let _: () = msg_send![
+            class!(AVCaptureDevice),
+            requestAccessForMediaType: AVMediaTypeAudio,
+            completionHandler: &*handler,
+        ];
+    }
+    let _ = rx.recv();
+    let status = check_mic_permission();
+    log::info!("[permission] microphone access: {:?}", status);
+    status
+}
+
+/// Fail with `MIC_ACCESS_DENIED` while the user or a policy denies microphone access.
+fn ensure_access() -> Result<(), String> {
+    match check_mic_permission() {
+        MicPermission::Denied | MicPermission::Restricted => Err(MIC_ACCESS_DENIED.to_string()),
+        _ => Ok(()),
+    }
+}
 
 pub struct MacOSAudioController;
 
@@ -9,16 +80,19 @@ impl AudioController for MacOSAudioController {
     }
 
     fn enumerate_input_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        ensure_access()?;
         // TODO: Implement CoreAudio device enumeration
         Err("macOS audio control not yet implemented".to_string())
     }
 
     fn get_mute_state(&self, _device_id: &str) -> Result<bool, String> {
+        ensure_access()?;
         // TODO: Implement CoreAudio mute state query
         Err("macOS audio control not yet implemented".to_string())
     }
 
     fn set_mute_state(&self, _device_id: &str, _muted: bool) -> Result<(), String> {
+        ensure_access()?;
         // TODO: Implement CoreAudio mute control
         Err("macOS audio control not yet implemented".to_string())
     }
@@ -0,0 +1,326 @@
+//! In-memory audio devices, selected as `PlatformAudioController` by the `mock-audio`
+//! feature. Lets the UI and the profile/mute logic run without real hardware, on any
+//! platform.
+//!
+//! The device table is shared by every controller, like the system's devices are. It
+//! comes from `TOGMIC_MOCK_DEVICES`, either a JSON array or the path of a JSON file:
+//!
+//! ```json
+//! [
+//!   { "id": "mic-1", "name": "Desk Mic", "isDefault": true, "muted": false },
+//!   { "id": "mic-2", "name": "Headset", "isDefault": false,
+//!     "failures": [{ "op": "setMute", "error": "transient: busy", "times": 2 }] }
+//! ]
+//! ```
+//!
+//! Without it, two unmuted devices are used. Besides the `AudioDevice` fields, an entry
+//! takes `muted`, `volume`, `peak`, `capturePids` and scripted `failures`; `times: 0`
+//! fails for good. Errors starting with `transient:` count as transient, so the worker's
+//! retries can be exercised. The `mock_audio` command (see `MockAction`) simulates
+//! changes made outside TogMic, such as another application muting a device.
+
+use super::{AudioController, AudioDevice, DefaultRole};
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub const DEVICES_VAR: &str = "TOGMIC_MOCK_DEVICES";
+
+/// Prefix of scripted errors that `is_transient_error` accepts.
+const TRANSIENT_PREFIX: &str = "transient:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MockOp {
+    Enumerate,
+    GetMute,
+    SetMute,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedFailure {
+    pub op: MockOp,
+    pub error: String,
+    /// How many calls fail before the operation works again; 0 fails every call.
+    #[serde(default)]
+    pub times: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockDevice {
+    #[serde(flatten)]
+    pub device: AudioDevice,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+    #[serde(default)]
+    pub peak: f32,
+    #[serde(default)]
+    pub capture_pids: Vec<u32>,
+    #[serde(default)]
+    pub failures: Vec<ScriptedFailure>,
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+impl MockDevice {
+    pub fn new(id: &str, name: &str, is_default: bool) -> Self {
+        Self {
+            device: AudioDevice {
+                id: id.to_string(),
+                name: name.to_string(),
+                is_default,
+                state: Default::default(),
+                kind: Default::default(),
+                form_factor: Default::default(),
+                system_name: None,
+            },
+            muted: false,
+            volume: default_volume(),
+            peak: 0.0,
+            capture_pids: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+static DEVICES: Lazy<Mutex<Vec<MockDevice>>> = Lazy::new(|| Mutex::new(load()));
+
+fn load() -> Vec<MockDevice> {
+    let Ok(value) = std::env::var(DEVICES_VAR) else {
+        return default_devices();
+    };
+    let json = if value.trim_start().starts_with('[') {
+        value
+    } else {
+        match std::fs::read_to_string(&value) {
+            Ok(json) => json,
+            Err(e) => {
+                log::warn!("[mock audio] failed to read {}: {}", value, e);
+                return default_devices();
+            }
+        }
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        log::warn!("[mock audio] invalid {}: {}", DEVICES_VAR, e);
+        default_devices()
+    })
+}
+
+fn default_devices() -> Vec<MockDevice> {
+    vec![
+        MockDevice::new("mock-mic", "Mock Microphone", true),
+        MockDevice::new("mock-headset", "Mock Headset", false),
+    ]
+}
+
+/// Replace the device table, e.g. with a test's fixture.
+pub fn set_devices(devices: Vec<MockDevice>) {
+    *DEVICES.lock_safe() = devices;
+}
+
+/// Snapshot of the device table.
+pub fn devices() -> Vec<MockDevice> {
+    DEVICES.lock_safe().clone()
+}
+
+/// Let the next `times` calls of `op` on `device_id` (every call with 0) fail with `error`.
+pub fn fail(device_id: &str, op: MockOp, error: &str, times: u32) {
+    if let Some(device) = DEVICES
+        .lock_safe()
+        .iter_mut()
+        .find(|d| d.device.id == device_id)
+    {
+        device.failures.push(ScriptedFailure {
+            op,
+            error: error.to_string(),
+            times,
+        });
+    }
+}
+
+/// Change a device's mute state the way another application would.
+pub fn set_external_mute(device_id: &str, muted: bool) -> Result<(), String> {
+    with_device(device_id, |device| {
+        device.muted = muted;
+        Ok(())
+    })
+}
+
+/// Connect a device; one with the same ID is replaced.
+pub fn plug(device: MockDevice) {
+    let mut devices = DEVICES.lock_safe();
+    devices.retain(|d| d.device.id != device.device.id);
+    if device.device.is_default {
+        for other in devices.iter_mut() {
+            other.device.is_default = false;
+        }
+    }
+    devices.push(device);
+}
+
+/// Disconnect a device. The first remaining one becomes the default if it was.
+pub fn unplug(device_id: &str) {
+    let mut devices = DEVICES.lock_safe();
+    let was_default = devices
+        .iter()
+        .any(|d| d.device.id == device_id && d.device.is_default);
+    devices.retain(|d| d.device.id != device_id);
+    if was_default {
+        if let Some(first) = devices.first_mut() {
+            first.device.is_default = true;
+        }
+    }
+}
+
+/// A simulated change, see the `mock_audio` command.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MockAction {
+    SetDevices {
+        devices: Vec<MockDevice>,
+    },
+    SetExternalMute {
+        device_id: String,
+        muted: bool,
+    },
+    Plug {
+        device: MockDevice,
+    },
+    Unplug {
+        device_id: String,
+    },
+    Fail {
+        device_id: String,
+        op: MockOp,
+        error: String,
+        #[serde(default)]
+        times: u32,
+    },
+}
+
+/// Apply `action` and return the resulting device table.
+pub fn apply(action: MockAction) -> Result<Vec<MockDevice>, String> {
+    match action {
+        MockAction::SetDevices { devices } => set_devices(devices),
+        MockAction::SetExternalMute { device_id, muted } => set_external_mute(&device_id, muted)?,
+        MockAction::Plug { device } => plug(device),
+        MockAction::Unplug { device_id } => unplug(&device_id),
+        MockAction::Fail {
+            device_id,
+            op,
+            error,
+            times,
+        } => fail(&device_id, op, &error, times),
+    }
+    Ok(devices())
+}
+
+pub fn is_transient_error(error: &str) -> bool {
+    error.starts_with(TRANSIENT_PREFIX)
+}
+
+/// Take the next scripted failure of `op`, if any.
+fn scripted(device: &mut MockDevice, op: MockOp) -> Result<(), String> {
+    let Some(index) = device.failures.iter().position(|f| f.op == op) else {
+        return Ok(());
+    };
+    let failure = &mut device.failures[index];
+    let error = failure.error.clone();
+    match failure.times {
+        0 => {}
+        1 => {
+            device.failures.remove(index);
+        }
+        _ => failure.times -= 1,
+    }
+    Err(error)
+}
+
+/// Run `f` on the device `device_id` names, `"default-mic"` (or empty) being the default.
+fn with_device<T>(
+    device_id: &str,
+    f: impl FnOnce(&mut MockDevice) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut devices = DEVICES.lock_safe();
+    let device = devices
+        .iter_mut()
+        .find(|d| {
+            d.device.id == device_id
+                || (d.device.is_default && (device_id == "default-mic" || device_id.is_empty()))
+        })
+        .ok_or_else(|| format!("Device not found: {}", device_id))?;
+    f(device)
+}
+
+pub struct MockAudioController;
+
+impl AudioController for MockAudioController {
+    fn new() -> Result<Self, String> {
+        Ok(MockAudioController)
+    }
+
+    fn enumerate_input_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        let mut devices = DEVICES.lock_safe();
+        for device in devices.iter_mut() {
+            scripted(device, MockOp::Enumerate)?;
+        }
+        Ok(devices.iter().map(|d| d.device.clone()).collect())
+    }
+
+    fn get_mute_state(&self, device_id: &str) -> Result<bool, String> {
+        with_device(device_id, |device| {
+            scripted(device, MockOp::GetMute)?;
+            Ok(device.muted)
+        })
+    }
+
+    fn set_mute_state(&self, device_id: &str, muted: bool) -> Result<(), String> {
+        with_device(device_id, |device| {
+            scripted(device, MockOp::SetMute)?;
+            device.muted = muted;
+            Ok(())
+        })
+    }
+
+    fn get_volume(&self, device_id: &str) -> Result<f32, String> {
+        with_device(device_id, |device| Ok(device.volume))
+    }
+
+    fn set_volume(&self, device_id: &str, level: f32) -> Result<(), String> {
+        with_device(device_id, |device| {
+            device.volume = level.clamp(0.0, 1.0);
+            Ok(())
+        })
+    }
+
+    fn get_peak_level(&self, device_id: &str) -> Result<f32, String> {
+        with_device(device_id, |device| Ok(device.peak))
+    }
+
+    fn capture_session_pids(&self, device_id: &str) -> Result<Vec<u32>, String> {
+        with_device(device_id, |device| Ok(device.capture_pids.clone()))
+    }
+
+    /// The mock keeps a single default, so every role moves it.
+    fn set_default_input_device(&self, device_id: &str, _role: DefaultRole) -> Result<(), String> {
+        let mut devices = DEVICES.lock_safe();
+        if !devices.iter().any(|d| d.device.id == device_id) {
+            return Err(format!("Device not found: {}", device_id));
+        }
+        for device in devices.iter_mut() {
+            device.device.is_default = device.device.id == device_id;
+        }
+        Ok(())
+    }
+}
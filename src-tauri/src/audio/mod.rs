@@ -1,31 +1,213 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+// With `mock-audio`, the in-memory devices of `mock` replace the platform backend. The
+// Windows notification listeners still run, but only ever lead to the mock devices.
 #[cfg(target_os = "windows")]
+#[cfg_attr(feature = "mock-audio", allow(dead_code))]
 mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::clear_endpoint_cache;
 #[cfg(target_os = "windows")]
-pub use windows::enumerate_capture_device_ids;
+pub use windows::com_apartments;
+#[cfg(all(target_os = "windows", not(feature = "mock-audio")))]
+pub use windows::is_transient_error;
 #[cfg(target_os = "windows")]
-pub use windows::start_audio_listeners;
+pub use windows::render_samples;
 #[cfg(target_os = "windows")]
+pub use windows::start_audio_listeners;
+#[cfg(all(target_os = "windows", not(feature = "mock-audio")))]
 pub use windows::WindowsAudioController as PlatformAudioController;
 
 #[cfg(target_os = "macos")]
+#[cfg_attr(feature = "mock-audio", allow(dead_code))]
 mod macos;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "mock-audio")))]
 pub use macos::MacOSAudioController as PlatformAudioController;
+#[cfg(target_os = "macos")]
+pub use macos::{check_mic_permission, request_mic_permission};
 
 #[cfg(target_os = "linux")]
+#[cfg_attr(feature = "mock-audio", allow(dead_code))]
 mod linux;
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "mock-audio")))]
+pub use linux::is_transient_error;
+#[cfg(all(target_os = "linux", not(feature = "mock-audio")))]
 pub use linux::LinuxAudioController as PlatformAudioController;
 
+#[cfg(feature = "mock-audio")]
+pub mod mock;
+
+pub mod vad;
+#[cfg(feature = "mock-audio")]
+pub use mock::is_transient_error;
+#[cfg(feature = "mock-audio")]
+pub use mock::MockAudioController as PlatformAudioController;
+
+/// Error of `AudioController::get_peak_level` on platforms without level metering.
+pub const METERING_UNSUPPORTED: &str = "Level metering is not supported on this platform";
+
+/// Error of `AudioController::set_default_input_device` where the backend can't change the
+/// default device.
+pub const DEFAULT_DEVICE_UNSUPPORTED: &str =
+    "NotSupported: changing the default input device is not supported on this platform";
+
+/// Error of `AudioController::capture_session_pids` on platforms without session tracking.
+pub const SESSIONS_UNSUPPORTED: &str = "Capture session tracking is not supported on this platform";
+
+/// Error of the controller while the OS doesn't let TogMic use the microphone (macOS
+/// privacy settings). The frontend recognizes the prefix and links to the settings.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub const MIC_ACCESS_DENIED: &str =
+    "AccessDenied: TogMic isn't allowed to use the microphone in the privacy settings";
+
+/// Whether the OS lets TogMic use the microphone.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MicPermission {
+    Granted,
+    Denied,
+    /// Blocked by a device policy; the user can't grant it.
+    Restricted,
+    /// The user hasn't been asked yet.
+    NotDetermined,
+    /// The platform doesn't gate microphone access.
+    NotRequired,
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn check_mic_permission() -> MicPermission {
+    MicPermission::NotRequired
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn request_mic_permission() -> MicPermission {
+    MicPermission::NotRequired
+}
+
+/// COM apartment an audio thread runs in.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComApartment {
+    Multithreaded,
+    SingleThreaded,
+}
+
+/// How an audio thread initialized COM, for the diagnostics report.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadApartment {
+    pub thread: String,
+    pub apartment: ComApartment,
+    /// Something else initialized the thread in the other apartment first, so the thread
+    /// runs in that one instead of the one it asked for.
+    pub changed_mode: bool,
+}
+
+/// COM apartments of the audio threads; only Windows uses COM.
+#[cfg(not(target_os = "windows"))]
+pub fn com_apartments() -> Vec<ThreadApartment> {
+    Vec::new()
+}
+
+/// Whether a device error is likely to clear up on its own and is worth retrying. The
+/// macOS backend doesn't report any transient errors yet.
+#[cfg(not(any(target_os = "windows", target_os = "linux", feature = "mock-audio")))]
+pub fn is_transient_error(_error: &str) -> bool {
+    false
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AudioDevice {
     pub id: String,
     pub name: String,
     pub is_default: bool,
+    #[serde(default)]
+    pub state: DeviceState,
+    #[serde(default)]
+    pub kind: DeviceKind,
+    #[serde(default)]
+    pub form_factor: FormFactor,
+    /// The name the system reports when `name` is a user-defined alias (see
+    /// `device_aliases`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_name: Option<String>,
+}
+
+impl AudioDevice {
+    /// The name the system reports, whether or not the device has an alias.
+    pub fn system_name(&self) -> &str {
+        self.system_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceState {
+    #[default]
+    Active,
+    Disabled,
+    NotPresent,
+    Unplugged,
+}
+
+/// How the device is attached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceKind {
+    Builtin,
+    Usb,
+    Bluetooth,
+    /// Software devices (virtual cables, voice changers, ...).
+    Virtual,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FormFactor {
+    Microphone,
+    Headset,
+    Handset,
+    LineLevel,
+    Digital,
+    /// Remote network device (e.g. a remote desktop microphone).
+    Remote,
+    #[default]
+    Unknown,
+}
+
+/// Which default input device to change. Windows keeps one for most applications
+/// (`console`) and one for voice chat (`communications`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DefaultRole {
+    Console,
+    Communications,
+    Both,
+}
+
+/// Marks a background audio thread as alive for as long as the guard is held. The flag
+/// is cleared on drop, so it also goes false if the thread returns early or panics.
+pub struct AliveGuard(Arc<AtomicBool>);
+
+impl AliveGuard {
+    pub fn new(flag: Arc<AtomicBool>) -> Self {
+        flag.store(true, Ordering::SeqCst);
+        Self(flag)
+    }
+}
+
+impl Drop for AliveGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
 }
 
 pub trait AudioController {
@@ -47,4 +229,77 @@ pub trait AudioController {
     fn get_mute_state(&self, device_id: &str) -> Result<bool, String>;
 
     fn set_mute_state(&self, device_id: &str, muted: bool) -> Result<(), String>;
+
+    /// Master volume as a scalar from 0.0 to 1.0.
+    fn get_volume(&self, _device_id: &str) -> Result<f32, String> {
+        Err("Volume control is not supported on this platform".to_string())
+    }
+
+    fn set_volume(&self, _device_id: &str, _level: f32) -> Result<(), String> {
+        Err("Volume control is not supported on this platform".to_string())
+    }
+
+    /// Current peak level as a scalar from 0.0 to 1.0. Errors with `METERING_UNSUPPORTED`
+    /// where the platform has no metering.
+    fn get_peak_level(&self, _device_id: &str) -> Result<f32, String> {
+        Err(METERING_UNSUPPORTED.to_string())
+    }
+
+    /// Make the device the system's default input device for `role`. Errors with
+    /// `DEFAULT_DEVICE_UNSUPPORTED` where the backend can't.
+    fn set_default_input_device(&self, _device_id: &str, _role: DefaultRole) -> Result<(), String> {
+        Err(DEFAULT_DEVICE_UNSUPPORTED.to_string())
+    }
+
+    /// Process ids of the applications actively capturing from the device. Errors with
+    /// `SESSIONS_UNSUPPORTED` where the platform can't tell.
+    fn capture_session_pids(&self, _device_id: &str) -> Result<Vec<u32>, String> {
+        Err(SESSIONS_UNSUPPORTED.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn devices_serialize_in_camel_case() {
+        let device = AudioDevice {
+            id: "a".to_string(),
+            name: "Alias".to_string(),
+            is_default: true,
+            state: DeviceState::NotPresent,
+            kind: DeviceKind::Usb,
+            form_factor: FormFactor::LineLevel,
+            system_name: Some("USB Mic".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&device).unwrap(),
+            json!({
+                "id": "a",
+                "name": "Alias",
+                "isDefault": true,
+                "state": "notPresent",
+                "kind": "usb",
+                "formFactor": "lineLevel",
+                "systemName": "USB Mic",
+            })
+        );
+    }
+
+    #[test]
+    fn devices_without_the_new_fields_still_deserialize() {
+        let device: AudioDevice =
+            serde_json::from_value(json!({ "id": "a", "name": "Mic", "isDefault": false }))
+                .unwrap();
+        assert_eq!(device.state, DeviceState::Active);
+        assert_eq!(device.kind, DeviceKind::Unknown);
+        assert_eq!(device.form_factor, FormFactor::Unknown);
+        assert_eq!(device.system_name(), "Mic");
+        assert!(serde_json::to_value(&device)
+            .unwrap()
+            .get("systemName")
+            .is_none());
+    }
 }
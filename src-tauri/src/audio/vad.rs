@@ -0,0 +1,123 @@
+//! Voice activity detection over peak meter readings.
+//!
+//! The detector needs no audio samples: it takes the meter's peak level 10–20 times a
+//! second, keeps the RMS over the last few readings and smooths it with separate attack
+//! and release times. Speech starts once the smoothed level reaches `threshold` and ends
+//! after it has stayed below `release_threshold` for `hold_ms`.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Readings the RMS is taken over.
+const WINDOW: usize = 4;
+const MIN_SAMPLE_RATE_HZ: u32 = 10;
+const MAX_SAMPLE_RATE_HZ: u32 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VadSettings {
+    /// Smoothed level that starts speech.
+    pub threshold: f32,
+    /// Level speech has to fall below to end; at most `threshold`.
+    pub release_threshold: f32,
+    /// Time constant of the smoothing while the level rises.
+    pub attack_ms: u32,
+    /// Time constant of the smoothing while the level falls.
+    pub release_ms: u32,
+    /// How long the level has to stay below `release_threshold` before speech ends.
+    pub hold_ms: u32,
+    /// Meter readings per second, 10 to 20.
+    pub sample_rate_hz: u32,
+}
+
+impl Default for VadSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            release_threshold: 0.06,
+            attack_ms: 50,
+            release_ms: 300,
+            hold_ms: 500,
+            sample_rate_hz: 15,
+        }
+    }
+}
+
+impl VadSettings {
+    pub fn sample_interval(&self) -> Duration {
+        let rate = self
+            .sample_rate_hz
+            .clamp(MIN_SAMPLE_RATE_HZ, MAX_SAMPLE_RATE_HZ);
+        Duration::from_millis(1000 / u64::from(rate))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct VoiceDetector {
+    window: VecDeque<f32>,
+    level: f32,
+    speaking: bool,
+    /// How long the level has been below the release threshold while speaking.
+    quiet_ms: u32,
+}
+
+impl VoiceDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one peak reading taken `elapsed_ms` after the previous one. Returns the new
+    /// state when speech started (`true`) or ended (`false`).
+    pub fn push(&mut self, settings: &VadSettings, peak: f32, elapsed_ms: u32) -> Option<bool> {
+        if self.window.len() == WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(peak.clamp(0.0, 1.0));
+        let rms =
+            (self.window.iter().map(|p| p * p).sum::<f32>() / self.window.len() as f32).sqrt();
+
+        let time_constant = if rms > self.level {
+            settings.attack_ms
+        } else {
+            settings.release_ms
+        };
+        let alpha = if time_constant == 0 {
+            1.0
+        } else {
+            1.0 - (-(elapsed_ms as f32) / time_constant as f32).exp()
+        };
+        self.level += alpha * (rms - self.level);
+
+        if !self.speaking {
+            if self.level >= settings.threshold {
+                self.speaking = true;
+                self.quiet_ms = 0;
+                return Some(true);
+            }
+        } else if self.level < settings.release_threshold.min(settings.threshold) {
+            self.quiet_ms = self.quiet_ms.saturating_add(elapsed_ms);
+            if self.quiet_ms >= settings.hold_ms {
+                self.speaking = false;
+                return Some(false);
+            }
+        } else {
+            self.quiet_ms = 0;
+        }
+        None
+    }
+
+    /// The smoothed level.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Forget the readings, e.g. when the device changed. Returns whether this ended
+    /// speech.
+    pub fn reset(&mut self) -> bool {
+        let was_speaking = self.speaking;
+        *self = Self::default();
+        was_speaking
+    }
+}
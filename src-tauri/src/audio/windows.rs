@@ -1,18 +1,28 @@
-use super::{AudioController, AudioDevice};
+use super::{
+    AliveGuard, AudioController, AudioDevice, ComApartment, DefaultRole, DeviceKind, DeviceState,
+    FormFactor, ThreadApartment,
+};
+use once_cell::sync::Lazy;
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::ptr;
 use std::result::Result as StdResult;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use windows::core::{implement, GUID, HSTRING};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::LocalKey;
+use windows::core::{
+    implement, interface, ComInterface, IUnknown, IUnknown_Vtbl, GUID, HRESULT, HSTRING, PCWSTR,
+};
 use windows::Win32::Foundation::*;
 use windows::Win32::Media::Audio::Endpoints::{
     IAudioEndpointVolume, IAudioEndpointVolumeCallback, IAudioEndpointVolumeCallback_Impl,
+    IAudioMeterInformation,
 };
 use windows::Win32::Media::Audio::{AUDIO_VOLUME_NOTIFICATION_DATA, IMMDeviceEnumerator};
 use windows::Win32::Media::Audio::*;
-use windows::Win32::System::Com::StructuredStorage::{PropVariantClear, PropVariantToStringAlloc};
+use windows::Win32::System::Com::StructuredStorage::{
+    PropVariantClear, PropVariantToStringAlloc, PropVariantToUInt32,
+};
 use windows::Win32::System::Com::*;
 use windows::Win32::UI::Shell::PropertiesSystem::{IPropertyStore, PROPERTYKEY};
 
@@ -20,8 +30,101 @@ use windows::Win32::UI::Shell::PropertiesSystem::{IPropertyStore, PROPERTYKEY};
 thread_local! {
     static THREAD_ENUMERATOR: RefCell<Option<IMMDeviceEnumerator>> = RefCell::new(None);
     static THREAD_ENDPOINT_CACHE: RefCell<HashMap<String, IAudioEndpointVolume>> = RefCell::new(HashMap::new());
+    static THREAD_METER_CACHE: RefCell<HashMap<String, IAudioMeterInformation>> = RefCell::new(HashMap::new());
     // Generation this thread's endpoint cache was last validated against.
     static LOCAL_CACHE_GENERATION: Cell<u64> = const { Cell::new(0) };
+    // This thread's COM initialization, undone when the thread exits.
+    static COM_INIT: RefCell<Option<ComInit>> = const { RefCell::new(None) };
+}
+
+// Apartments of the threads that initialized COM through `ensure_com`, for diagnostics.
+static COM_THREADS: Lazy<Mutex<Vec<ThreadApartment>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+struct ComInit {
+    thread: String,
+    apartment: ComApartment,
+    // Whether our CoInitializeEx succeeded and needs a matching CoUninitialize.
+    owned: bool,
+}
+
+impl Drop for ComInit {
+    fn drop(&mut self) {
+        // Release this thread's interfaces while COM is still up. The caches may already be
+        // gone during thread teardown, hence try_with.
+        let _ = THREAD_ENUMERATOR.try_with(|cell| cell.borrow_mut().take());
+        let _ = THREAD_ENDPOINT_CACHE.try_with(|cache| cache.borrow_mut().clear());
+        let _ = THREAD_METER_CACHE.try_with(|cache| cache.borrow_mut().clear());
+        COM_THREADS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|t| t.thread != self.thread);
+        if self.owned {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
+
+/// Initialize COM on the current thread in `wanted`, once per thread. S_FALSE (already
+/// initialized the same way) counts as success and is balanced like S_OK. If the thread is
+/// already in the other apartment (RPC_E_CHANGED_MODE), it keeps that one; the mode is
+/// recorded, and there is nothing to uninitialize. Returns the thread's apartment.
+fn ensure_com(wanted: ComApartment) -> StdResult<ComApartment, String> {
+    if let Some(apartment) = COM_INIT.with(|cell| cell.borrow().as_ref().map(|i| i.apartment)) {
+        return Ok(apartment);
+    }
+    let flags = match wanted {
+        ComApartment::Multithreaded => COINIT_MULTITHREADED,
+        ComApartment::SingleThreaded => COINIT_APARTMENTTHREADED,
+    };
+    let (apartment, owned) = match unsafe { CoInitializeEx(None, flags) } {
+        Ok(()) => (wanted, true),
+        Err(e) if e.code() == RPC_E_CHANGED_MODE => {
+            let other = match wanted {
+                ComApartment::Multithreaded => ComApartment::SingleThreaded,
+                ComApartment::SingleThreaded => ComApartment::Multithreaded,
+            };
+            (other, false)
+        }
+        Err(e) => return Err(format!("CoInitializeEx failed: {}", e)),
+    };
+
+    let current = std::thread::current();
+    let thread = match current.name() {
+        Some(name) => format!("{} ({:?})", name, current.id()),
+        None => format!("{:?}", current.id()),
+    };
+    if !owned {
+        log::warn!(
+            "[audio] {} already runs in the {:?} apartment, using it instead of {:?}",
+            thread,
+            apartment,
+            wanted
+        );
+    }
+    COM_THREADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(ThreadApartment {
+            thread: thread.clone(),
+            apartment,
+            changed_mode: !owned,
+        });
+    COM_INIT.with(|cell| {
+        *cell.borrow_mut() = Some(ComInit {
+            thread,
+            apartment,
+            owned,
+        })
+    });
+    Ok(apartment)
+}
+
+/// COM apartments of the audio threads that are still running.
+pub fn com_apartments() -> Vec<ThreadApartment> {
+    COM_THREADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
 }
 
 // Global cache generation. Bumped whenever the device topology changes (plug/unplug,
@@ -31,6 +134,20 @@ thread_local! {
 // calling thread, leaving stale `"default-mic"` endpoints in command/worker threads.
 static GLOBAL_CACHE_GENERATION: AtomicU64 = AtomicU64::new(0);
 
+// When the device topology last changed (ms since the Unix epoch). "Element not found"
+// shortly after a change usually means the device is still re-enumerating.
+static LAST_TOPOLOGY_CHANGE_MS: AtomicU64 = AtomicU64::new(0);
+
+// How long after a topology change E_NOTFOUND is still treated as transient
+const TOPOLOGY_SETTLE_MS: u64 = 5000;
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 // Drop this thread's cached endpoints if the global generation has advanced since we
 // last validated. Called before every endpoint lookup.
 fn ensure_cache_fresh() {
@@ -38,6 +155,7 @@ fn ensure_cache_fresh() {
     LOCAL_CACHE_GENERATION.with(|local| {
         if local.get() != global {
             THREAD_ENDPOINT_CACHE.with(|cache| cache.borrow_mut().clear());
+            THREAD_METER_CACHE.with(|cache| cache.borrow_mut().clear());
             local.set(global);
         }
     });
@@ -55,16 +173,115 @@ const PKEY_DEVICE_DESC: PROPERTYKEY = PROPERTYKEY {
     pid: 2,
 };
 
-unsafe fn activate_audio_endpoint(device: &IMMDevice) -> StdResult<IAudioEndpointVolume, String> {
-    device
-        .Activate::<IAudioEndpointVolume>(CLSCTX_ALL, None)
-        .map_err(|e| format!("IMMDevice::Activate failed: {}", e))
+// Bus enumerator of the device ("USB", "BTHENUM", "HDAUDIO", ...)
+const PKEY_DEVICE_ENUMERATOR_NAME: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0xa45c254e_df1c_4efd_8020_67d146a850e0),
+    pid: 24,
+};
+
+// EndpointFormFactor value of the endpoint
+const PKEY_AUDIO_ENDPOINT_FORM_FACTOR: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0x1da5d803_d492_4edd_8c23_e0c0ffee7f0e),
+    pid: 0,
+};
+
+// PolicyConfigClient, the undocumented COM class the Sound settings use to change the
+// default devices. Its interface has been stable since Windows 7.
+const CLSID_POLICY_CONFIG_CLIENT: GUID = GUID::from_u128(0x870af99c_171d_4f9e_af0d_e63df40c2bc9);
+
+// Only `SetDefaultEndpoint` is called; the other methods are declared for the vtable layout.
+#[interface("f8679f50-850a-41cf-9c72-430f290290c8")]
+unsafe trait IPolicyConfig: IUnknown {
+    fn GetMixFormat(&self, device_id: PCWSTR, format: *mut *mut std::ffi::c_void) -> HRESULT;
+    fn GetDeviceFormat(
+        &self,
+        device_id: PCWSTR,
+        default: i32,
+        format: *mut *mut std::ffi::c_void,
+    ) -> HRESULT;
+    fn ResetDeviceFormat(&self, device_id: PCWSTR) -> HRESULT;
+    fn SetDeviceFormat(
+        &self,
+        device_id: PCWSTR,
+        endpoint_format: *mut std::ffi::c_void,
+        mix_format: *mut std::ffi::c_void,
+    ) -> HRESULT;
+    fn GetProcessingPeriod(
+        &self,
+        device_id: PCWSTR,
+        default: i32,
+        default_period: *mut i64,
+        minimum_period: *mut i64,
+    ) -> HRESULT;
+    fn SetProcessingPeriod(&self, device_id: PCWSTR, period: *mut i64) -> HRESULT;
+    fn GetShareMode(&self, device_id: PCWSTR, mode: *mut std::ffi::c_void) -> HRESULT;
+    fn SetShareMode(&self, device_id: PCWSTR, mode: *mut std::ffi::c_void) -> HRESULT;
+    fn GetPropertyValue(
+        &self,
+        device_id: PCWSTR,
+        fx_store: i32,
+        key: *const PROPERTYKEY,
+        value: *mut std::ffi::c_void,
+    ) -> HRESULT;
+    fn SetPropertyValue(
+        &self,
+        device_id: PCWSTR,
+        fx_store: i32,
+        key: *const PROPERTYKEY,
+        value: *mut std::ffi::c_void,
+    ) -> HRESULT;
+    fn SetDefaultEndpoint(&self, device_id: PCWSTR, role: ERole) -> HRESULT;
+    fn SetEndpointVisibility(&self, device_id: PCWSTR, visible: i32) -> HRESULT;
+}
+
+// Activate an interface of a device (endpoint volume, peak meter, session manager) through
+// the generated binding. The error names the interface, so a failing feature is obvious
+// from the message.
+unsafe fn activate<T: ComInterface>(device: &IMMDevice) -> StdResult<T, String> {
+    device.Activate::<T>(CLSCTX_ALL, None).map_err(|e| {
+        format!(
+            "IMMDevice::Activate({}) failed: {}",
+            interface_name::<T>(),
+            e
+        )
+    })
+}
+
+fn interface_name<T>() -> &'static str {
+    std::any::type_name::<T>()
+        .rsplit("::")
+        .next()
+        .unwrap_or("?")
+}
+
+// Get or activate an interface of a device, cached per thread in `cache`
+unsafe fn cached_activation<T: ComInterface>(
+    cache: &'static LocalKey<RefCell<HashMap<String, T>>>,
+    device_id: &str,
+) -> StdResult<T, String> {
+    // Drop any interfaces cached before the last device-topology change so we never
+    // hand out a stale one (notably for "default-mic" after the default capture device
+    // changed).
+    ensure_cache_fresh();
+
+    if let Some(cached) = cache.with(|cache| cache.borrow().get(device_id).cloned()) {
+        return Ok(cached);
+    }
+
+    let device = get_device_for_id(device_id)?;
+    let activated = activate::<T>(&device)?;
+    cache.with(|cache| {
+        cache
+            .borrow_mut()
+            .insert(device_id.to_string(), activated.clone());
+    });
+    Ok(activated)
 }
 
 // Get or create a per-thread IMMDeviceEnumerator
 unsafe fn thread_enumerator() -> StdResult<IMMDeviceEnumerator, String> {
     // Ensure COM is initialized for this thread
-    let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    ensure_com(ComApartment::Multithreaded)?;
 
     THREAD_ENUMERATOR.with(|cell| {
         if let Some(ref en) = *cell.borrow() {
@@ -82,40 +299,28 @@ unsafe fn thread_enumerator() -> StdResult<IMMDeviceEnumerator, String> {
 
 // Get or create a cached endpoint volume for a given device id on this thread
 unsafe fn get_cached_endpoint_for_id(device_id: &str) -> StdResult<IAudioEndpointVolume, String> {
-    // Drop any endpoints cached before the last device-topology change so we never
-    // hand out a stale endpoint (notably the "default-mic" endpoint after the default
-    // capture device changed).
-    ensure_cache_fresh();
-
-    // First try the cache
-    if let Some(ep) = THREAD_ENDPOINT_CACHE.with(|cache| cache.borrow().get(device_id).cloned()) {
-        return Ok(ep);
-    }
+    cached_activation(&THREAD_ENDPOINT_CACHE, device_id)
+}
 
-    // Need to resolve device and activate endpoint
+// Resolve a device id, or "default-mic" for the default capture device
+unsafe fn get_device_for_id(device_id: &str) -> StdResult<IMMDevice, String> {
     let enumerator = thread_enumerator()?;
 
-    let device = if device_id == "default-mic" || device_id.is_empty() {
+    if device_id == "default-mic" || device_id.is_empty() {
         enumerator
             .GetDefaultAudioEndpoint(eCapture, eConsole)
-            .map_err(|e| format!("Failed to get default device: {}", e))?
+            .map_err(|e| format!("Failed to get default device: {}", e))
     } else {
         let id_wide = HSTRING::from(device_id);
         enumerator
             .GetDevice(&id_wide)
-            .map_err(|e| format!("Failed to get device: {}", e))?
-    };
-
-    let endpoint = activate_audio_endpoint(&device)?;
-
-    // Cache the endpoint for subsequent calls on this thread
-    THREAD_ENDPOINT_CACHE.with(|cache| {
-        cache
-            .borrow_mut()
-            .insert(device_id.to_string(), endpoint.clone());
-    });
+            .map_err(|e| format!("Failed to get device: {}", e))
+    }
+}
 
-    Ok(endpoint)
+// Get or create a cached peak meter for a given device id on this thread
+unsafe fn get_cached_meter_for_id(device_id: &str) -> StdResult<IAudioMeterInformation, String> {
+    cached_activation(&THREAD_METER_CACHE, device_id)
 }
 
 unsafe fn read_device_property(store: &IPropertyStore, key: &PROPERTYKEY) -> Option<String> {
@@ -135,11 +340,51 @@ unsafe fn read_device_property(store: &IPropertyStore, key: &PROPERTYKEY) -> Opt
     value
 }
 
-unsafe fn get_device_friendly_name(device: &IMMDevice) -> Option<String> {
-    let store = device.OpenPropertyStore(STGM_READ).ok()?;
+unsafe fn read_device_u32_property(store: &IPropertyStore, key: &PROPERTYKEY) -> Option<u32> {
+    let mut prop = store.GetValue(key).ok()?;
+    let value = PropVariantToUInt32(&prop).ok();
+    let _ = PropVariantClear(&mut prop);
+    value
+}
+
+unsafe fn get_device_friendly_name(store: &IPropertyStore) -> Option<String> {
+    read_device_property(store, &PKEY_DEVICE_FRIENDLY_NAME)
+        .or_else(|| read_device_property(store, &PKEY_DEVICE_DESC))
+}
 
-    read_device_property(&store, &PKEY_DEVICE_FRIENDLY_NAME)
-        .or_else(|| read_device_property(&store, &PKEY_DEVICE_DESC))
+unsafe fn get_device_kind(store: &IPropertyStore) -> DeviceKind {
+    read_device_property(store, &PKEY_DEVICE_ENUMERATOR_NAME)
+        .map_or(DeviceKind::Unknown, |enumerator| {
+            kind_from_enumerator(&enumerator)
+        })
+}
+
+fn kind_from_enumerator(enumerator: &str) -> DeviceKind {
+    match enumerator.to_ascii_uppercase().as_str() {
+        "USB" => DeviceKind::Usb,
+        "BTHENUM" | "BTHHFENUM" | "BTHLEDEVICE" => DeviceKind::Bluetooth,
+        "HDAUDIO" | "INTELAUDIO" | "ACPI" | "PCI" => DeviceKind::Builtin,
+        "SWD" | "ROOT" => DeviceKind::Virtual,
+        _ => DeviceKind::Unknown,
+    }
+}
+
+unsafe fn get_form_factor(store: &IPropertyStore) -> FormFactor {
+    read_device_u32_property(store, &PKEY_AUDIO_ENDPOINT_FORM_FACTOR)
+        .map_or(FormFactor::Unknown, form_factor_from)
+}
+
+fn form_factor_from(value: u32) -> FormFactor {
+    // Values of the EndpointFormFactor enumeration
+    match value {
+        0 => FormFactor::Remote,
+        2 => FormFactor::LineLevel,
+        4 => FormFactor::Microphone,
+        5 => FormFactor::Headset,
+        6 => FormFactor::Handset,
+        7..=9 => FormFactor::Digital,
+        _ => FormFactor::Unknown,
+    }
 }
 
 impl AudioController for WindowsAudioController {
@@ -149,11 +394,8 @@ impl AudioController for WindowsAudioController {
     }
 
     fn init_thread() -> StdResult<(), String> {
-        unsafe {
-            // Initialize COM for the current thread (needed for background polling threads)
-            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
-        }
-        Ok(())
+        // Initialize COM for the current thread (needed for background polling threads)
+        ensure_com(ComApartment::Multithreaded).map(|_| ())
     }
 
     fn enumerate_input_devices(&self) -> StdResult<Vec<AudioDevice>, String> {
@@ -192,8 +434,12 @@ impl AudioController for WindowsAudioController {
                 let id = id_pwstr.to_string().unwrap_or_default();
                 CoTaskMemFree(Some(id_pwstr.0 as *const _));
 
+                let store = device.OpenPropertyStore(STGM_READ).ok();
+
                 // Try to get friendly name, fallback to generic name
-                let name = get_device_friendly_name(&device)
+                let name = store
+                    .as_ref()
+                    .and_then(|store| get_device_friendly_name(store))
                     .unwrap_or_else(|| format!("Microphone {}", i + 1));
 
                 let is_default = default_id.as_ref().map_or(false, |def_id| def_id == &id);
@@ -202,6 +448,15 @@ impl AudioController for WindowsAudioController {
                     id,
                     name,
                     is_default,
+                    // Only active endpoints are enumerated
+                    state: DeviceState::Active,
+                    kind: store
+                        .as_ref()
+                        .map_or(DeviceKind::Unknown, |store| get_device_kind(store)),
+                    form_factor: store
+                        .as_ref()
+                        .map_or(FormFactor::Unknown, |store| get_form_factor(store)),
+                    system_name: None,
                 });
             }
 
@@ -240,44 +495,270 @@ impl AudioController for WindowsAudioController {
             }
         }
     }
+
+    fn get_volume(&self, device_id: &str) -> StdResult<f32, String> {
+        unsafe {
+            let endpoint = get_cached_endpoint_for_id(device_id)?;
+            match endpoint.GetMasterVolumeLevelScalar() {
+                Ok(level) => Ok(level),
+                Err(e) => {
+                    THREAD_ENDPOINT_CACHE.with(|cache| {
+                        cache.borrow_mut().remove(device_id);
+                    });
+                    Err(format!("Device unavailable: {}", e))
+                }
+            }
+        }
+    }
+
+    fn get_peak_level(&self, device_id: &str) -> StdResult<f32, String> {
+        unsafe {
+            let meter = get_cached_meter_for_id(device_id)?;
+            match meter.GetPeakValue() {
+                Ok(peak) => Ok(peak),
+                Err(e) => {
+                    THREAD_METER_CACHE.with(|cache| {
+                        cache.borrow_mut().remove(device_id);
+                    });
+                    Err(format!("Device unavailable: {}", e))
+                }
+            }
+        }
+    }
+
+    fn capture_session_pids(&self, device_id: &str) -> StdResult<Vec<u32>, String> {
+        unsafe {
+            let device = get_device_for_id(device_id)?;
+            let manager = activate::<IAudioSessionManager2>(&device)?;
+            let sessions = manager
+                .GetSessionEnumerator()
+                .map_err(|e| format!("Failed to enumerate sessions: {}", e))?;
+            let count = sessions
+                .GetCount()
+                .map_err(|e| format!("Failed to count sessions: {}", e))?;
+
+            let mut pids = Vec::new();
+            for index in 0..count {
+                let Ok(session) = sessions.GetSession(index) else {
+                    continue;
+                };
+                if session.GetState().ok() != Some(AudioSessionStateActive) {
+                    continue;
+                }
+                let Ok(session) = session.cast::<IAudioSessionControl2>() else {
+                    continue;
+                };
+                // S_OK (not an error) marks the system sounds session
+                if session.IsSystemSoundsSession() == S_OK {
+                    continue;
+                }
+                if let Ok(pid) = session.GetProcessId() {
+                    if pid != 0 && !pids.contains(&pid) {
+                        pids.push(pid);
+                    }
+                }
+            }
+            Ok(pids)
+        }
+    }
+
+    fn set_default_input_device(
+        &self,
+        device_id: &str,
+        role: DefaultRole,
+    ) -> StdResult<(), String> {
+        // Like the Sound settings: the console default also serves multimedia
+        let roles: &[ERole] = match role {
+            DefaultRole::Console => &[eConsole, eMultimedia],
+            DefaultRole::Communications => &[eCommunications],
+            DefaultRole::Both => &[eConsole, eMultimedia, eCommunications],
+        };
+        unsafe {
+            // Fails for unknown ids with a clearer error than the policy interface gives
+            get_device_for_id(device_id)?;
+            let policy: IPolicyConfig =
+                CoCreateInstance(&CLSID_POLICY_CONFIG_CLIENT, None, CLSCTX_ALL)
+                    .map_err(|e| format!("Failed to create policy config: {}", e))?;
+            let id = HSTRING::from(device_id);
+            for &role in roles {
+                policy
+                    .SetDefaultEndpoint(PCWSTR(id.as_ptr()), role)
+                    .ok()
+                    .map_err(|e| format!("Failed to set default device: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_volume(&self, device_id: &str, level: f32) -> StdResult<(), String> {
+        unsafe {
+            let endpoint = get_cached_endpoint_for_id(device_id)?;
+            match endpoint.SetMasterVolumeLevelScalar(level.clamp(0.0, 1.0), ptr::null()) {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    THREAD_ENDPOINT_CACHE.with(|cache| {
+                        cache.borrow_mut().remove(device_id);
+                    });
+                    Err(format!("Device unavailable: {}", e))
+                }
+            }
+        }
+    }
 }
 
 // Invalidate cached endpoints on ALL threads (call when devices change).
 // Bumps the global generation so every thread drops its stale endpoints on next use,
 // and clears the calling thread's cache immediately.
 pub fn clear_endpoint_cache() {
+    LAST_TOPOLOGY_CHANGE_MS.store(now_ms(), Ordering::Relaxed);
     GLOBAL_CACHE_GENERATION.fetch_add(1, Ordering::Release);
     THREAD_ENDPOINT_CACHE.with(|cache| {
         cache.borrow_mut().clear();
     });
+    THREAD_METER_CACHE.with(|cache| {
+        cache.borrow_mut().clear();
+    });
     LOCAL_CACHE_GENERATION.with(|local| {
         local.set(GLOBAL_CACHE_GENERATION.load(Ordering::Acquire));
     });
 }
 
-/// Enumerate active capture device IDs on the current thread.
-/// Uses the cached THREAD_ENUMERATOR — safe to call from the COM STA listener thread
-/// where the enumerator is already initialized by setup_listeners().
-pub fn enumerate_capture_device_ids() -> StdResult<Vec<String>, String> {
+/// Whether a device error is likely to clear up on its own, e.g. an endpoint invalidated
+/// by resume from sleep or a device that is still re-enumerating after a change. Errors
+/// carry the HRESULT as formatted by `windows::core::Error` ("... (0x88890004)").
+/// Anything else, such as access denied, is permanent.
+pub fn is_transient_error(error: &str) -> bool {
+    let code = |hr: HRESULT| format!("{:#010X}", hr.0);
+
+    if error.contains(&code(AUDCLNT_E_DEVICE_INVALIDATED)) {
+        return true;
+    }
+    if error.contains(&code(HRESULT::from_win32(ERROR_NOT_FOUND.0))) {
+        let last_change = LAST_TOPOLOGY_CHANGE_MS.load(Ordering::Relaxed);
+        let since_change = now_ms().saturating_sub(last_change);
+        return since_change < TOPOLOGY_SETTLE_MS;
+    }
+    false
+}
+
+// ---------------------------------------------------------------------------
+// Feedback sounds on a render stream with a category
+// ---------------------------------------------------------------------------
+
+// Size of the shared render buffer, in 100 ns units (100 ms).
+const RENDER_BUFFER_DURATION: i64 = 1_000_000;
+// WAVE_FORMAT_IEEE_FLOAT
+const WAVE_FORMAT_FLOAT: u16 = 3;
+
+/// Play interleaved `samples` on the default output device, tagged with a stream
+/// category so Windows mixes them like other cues of that kind (ducking, routing to the
+/// communications device). `communications` picks the communications device and
+/// category, otherwise the console device and the sound effects category. The stream is
+/// opened for this one sound, so it always goes to the current default device. Blocks
+/// until the sound has played or `stop` is set.
+pub fn render_samples(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    communications: bool,
+    stop: &AtomicBool,
+) -> StdResult<(), String> {
+    if channels == 0 || sample_rate == 0 {
+        return Err("Invalid sound format".to_string());
+    }
     unsafe {
         let enumerator = thread_enumerator()?;
-        let collection = enumerator
-            .EnumAudioEndpoints(eCapture, DEVICE_STATE_ACTIVE)
-            .map_err(|e| format!("EnumAudioEndpoints failed: {}", e))?;
-        let count = collection
-            .GetCount()
-            .map_err(|e| format!("GetCount failed: {}", e))?;
-        let mut ids = Vec::with_capacity(count as usize);
-        for i in 0..count {
-            if let Ok(device) = collection.Item(i) {
-                if let Ok(id_pwstr) = device.GetId() {
-                    let id = id_pwstr.to_string().unwrap_or_default();
-                    CoTaskMemFree(Some(id_pwstr.0 as *const _));
-                    ids.push(id);
+        let role = if communications {
+            eCommunications
+        } else {
+            eConsole
+        };
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eRender, role)
+            .map_err(|e| format!("Failed to get the default output device: {}", e))?;
+        let client = activate::<IAudioClient2>(&device)?;
+
+        let properties = AudioClientProperties {
+            cbSize: std::mem::size_of::<AudioClientProperties>() as u32,
+            bIsOffload: FALSE,
+            eCategory: if communications {
+                AudioCategory_Communications
+            } else {
+                AudioCategory_SoundEffects
+            },
+            Options: AUDCLNT_STREAMOPTIONS_NONE,
+        };
+        client
+            .SetClientProperties(&properties)
+            .map_err(|e| format!("SetClientProperties failed: {}", e))?;
+
+        let block_align = channels * 4;
+        let format = WAVEFORMATEX {
+            wFormatTag: WAVE_FORMAT_FLOAT,
+            nChannels: channels,
+            nSamplesPerSec: sample_rate,
+            nAvgBytesPerSec: sample_rate * block_align as u32,
+            nBlockAlign: block_align,
+            wBitsPerSample: 32,
+            cbSize: 0,
+        };
+        // The audio engine converts to the device's mix format and rate
+        client
+            .Initialize(
+                AUDCLNT_SHAREMODE_SHARED,
+                AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+                RENDER_BUFFER_DURATION,
+                0,
+                &format,
+                None,
+            )
+            .map_err(|e| format!("IAudioClient::Initialize failed: {}", e))?;
+        let buffer_frames = client
+            .GetBufferSize()
+            .map_err(|e| format!("GetBufferSize failed: {}", e))?;
+        let render: IAudioRenderClient = client
+            .GetService()
+            .map_err(|e| format!("GetService(IAudioRenderClient) failed: {}", e))?;
+
+        let channels = channels as usize;
+        let total_frames = samples.len() / channels;
+        let mut written = 0;
+        let mut started = false;
+        while !stop.load(Ordering::SeqCst) {
+            let padding = client
+                .GetCurrentPadding()
+                .map_err(|e| format!("GetCurrentPadding failed: {}", e))?;
+            if written == total_frames {
+                if padding == 0 {
+                    break;
                 }
+            } else {
+                let frames = ((buffer_frames - padding) as usize).min(total_frames - written);
+                if frames > 0 {
+                    let buffer = render
+                        .GetBuffer(frames as u32)
+                        .map_err(|e| format!("GetBuffer failed: {}", e))?;
+                    ptr::copy_nonoverlapping(
+                        samples[written * channels..].as_ptr(),
+                        buffer as *mut f32,
+                        frames * channels,
+                    );
+                    render
+                        .ReleaseBuffer(frames as u32, 0)
+                        .map_err(|e| format!("ReleaseBuffer failed: {}", e))?;
+                    written += frames;
+                }
+            }
+            if !started {
+                client
+                    .Start()
+                    .map_err(|e| format!("IAudioClient::Start failed: {}", e))?;
+                started = true;
             }
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
-        Ok(ids)
+        let _ = client.Stop();
+        Ok(())
     }
 }
 
@@ -287,6 +768,8 @@ pub fn enumerate_capture_device_ids() -> StdResult<Vec<String>, String> {
 
 pub type MuteChangedFn = Arc<dyn Fn(bool) + Send + Sync>;
 pub type DevicesChangedFn = Arc<dyn Fn() + Send + Sync>;
+/// Called with a description of each listener that couldn't be registered.
+pub type ListenerErrorFn = Arc<dyn Fn(String) + Send + Sync>;
 
 #[implement(IAudioEndpointVolumeCallback)]
 struct MuteCallback {
@@ -384,16 +867,24 @@ impl Drop for AudioListenerHandles {
 /// The `on_mute_changed` closure is called from the COM callback thread whenever
 /// any monitored device's mute state changes.
 /// The `on_devices_changed` closure is called when the device list changes.
+/// The `on_error` closure is called for every listener that couldn't be registered; the
+/// registration is tried again on the next device change, or after 30 s at the latest.
+///
+/// `alive` is set while the listener thread is running.
 ///
 /// Returns immediately; the listener thread runs for the lifetime of the process.
 pub fn start_audio_listeners(
     on_mute_changed: MuteChangedFn,
     on_devices_changed: DevicesChangedFn,
+    on_error: ListenerErrorFn,
+    alive: Arc<AtomicBool>,
 ) {
     std::thread::spawn(move || {
+        let _alive = AliveGuard::new(alive);
+
         // STA is required for IMMNotificationClient callbacks on Windows
-        unsafe {
-            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        if let Err(e) = ensure_com(ComApartment::SingleThreaded) {
+            on_error(format!("Failed to initialize COM for the listeners: {}", e));
         }
 
         // Channel used by the DeviceNotificationClient callback to signal this
@@ -409,27 +900,20 @@ pub fn start_audio_listeners(
             })
         };
 
-        // Keep current_handles in scope so registered callbacks live as long as this thread.
-        let mut _handles = match setup_listeners(&on_mute_changed, &on_devices_changed_signaling) {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("[audio] Failed to set up COM listeners: {}", e);
-                return;
+        // Keep the handles in scope so registered callbacks live as long as this thread.
+        // A failed re-registration keeps the previous ones.
+        let mut _handles = None;
+        loop {
+            match setup_listeners(&on_mute_changed, &on_devices_changed_signaling, &on_error) {
+                Ok(new_handles) => _handles = Some(new_handles),
+                Err(e) => on_error(format!("Failed to set up COM listeners: {}", e)),
             }
-        };
 
-        loop {
             // Wait for a device-change signal instead of polling every 2 s.
             // A 30 s timeout acts as a safety net to catch edge cases.
             let _ = rx.recv_timeout(std::time::Duration::from_secs(30));
             // Drain any additional queued signals to avoid redundant re-registrations
             while rx.try_recv().is_ok() {}
-
-            if let Ok(new_handles) =
-                setup_listeners(&on_mute_changed, &on_devices_changed_signaling)
-            {
-                _handles = new_handles;
-            }
         }
     });
 }
@@ -437,10 +921,11 @@ pub fn start_audio_listeners(
 fn setup_listeners(
     on_mute_changed: &MuteChangedFn,
     on_devices_changed: &DevicesChangedFn,
+    on_error: &ListenerErrorFn,
 ) -> StdResult<AudioListenerHandles, String> {
     unsafe {
         // Use thread_enumerator() so the STA thread's THREAD_ENUMERATOR cache is populated.
-        // This means enumerate_capture_device_ids() called from on_devices_changed callbacks
+        // This means enumerate_input_devices() called from on_devices_changed callbacks
         // (on this same thread) reuses the cached enumerator instead of creating a second one.
         let enumerator = thread_enumerator()
             .map_err(|e| format!("CoCreateInstance failed: {}", e))?;
@@ -468,24 +953,31 @@ fn setup_listeners(
         for i in 0..count {
             let device = match collection.Item(i) {
                 Ok(d) => d,
-                Err(_) => continue,
+                Err(e) => {
+                    on_error(format!("Failed to get capture device {}: {}", i, e));
+                    continue;
+                }
             };
-            let endpoint = match activate_audio_endpoint(&device) {
+            let endpoint = match activate::<IAudioEndpointVolume>(&device) {
                 Ok(ep) => ep,
-                Err(_) => continue,
+                Err(e) => {
+                    on_error(e);
+                    continue;
+                }
             };
             let cb: IAudioEndpointVolumeCallback = MuteCallback {
                 on_mute_changed: on_mute_changed.clone(),
             }
             .into();
-            if endpoint.RegisterControlChangeNotify(&cb).is_ok() {
-                endpoint_callbacks.push((endpoint, cb));
+            match endpoint.RegisterControlChangeNotify(&cb) {
+                Ok(()) => endpoint_callbacks.push((endpoint, cb)),
+                Err(e) => on_error(format!("RegisterControlChangeNotify failed: {}", e)),
             }
         }
 
         // Also register on the default device (in case it's not in the collection)
         if let Ok(default_dev) = enumerator.GetDefaultAudioEndpoint(eCapture, eConsole) {
-            if let Ok(endpoint) = activate_audio_endpoint(&default_dev) {
+            if let Ok(endpoint) = activate::<IAudioEndpointVolume>(&default_dev) {
                 let cb: IAudioEndpointVolumeCallback = MuteCallback {
                     on_mute_changed: on_mute_changed.clone(),
                 }
@@ -503,3 +995,115 @@ fn setup_listeners(
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_bus_enumerators_to_kinds() {
+        assert_eq!(kind_from_enumerator("USB"), DeviceKind::Usb);
+        assert_eq!(kind_from_enumerator("usb"), DeviceKind::Usb);
+        assert_eq!(kind_from_enumerator("BTHHFENUM"), DeviceKind::Bluetooth);
+        assert_eq!(kind_from_enumerator("HDAUDIO"), DeviceKind::Builtin);
+        assert_eq!(kind_from_enumerator("SWD"), DeviceKind::Virtual);
+        assert_eq!(kind_from_enumerator("SOMETHING"), DeviceKind::Unknown);
+    }
+
+    #[test]
+    fn maps_endpoint_form_factors() {
+        assert_eq!(form_factor_from(0), FormFactor::Remote);
+        assert_eq!(form_factor_from(4), FormFactor::Microphone);
+        assert_eq!(form_factor_from(5), FormFactor::Headset);
+        assert_eq!(form_factor_from(8), FormFactor::Digital);
+        // Speakers (1) and anything newer are not input form factors we name
+        assert_eq!(form_factor_from(1), FormFactor::Unknown);
+        assert_eq!(form_factor_from(42), FormFactor::Unknown);
+    }
+
+    #[test]
+    fn activation_errors_name_the_interface() {
+        assert_eq!(
+            interface_name::<IAudioEndpointVolume>(),
+            "IAudioEndpointVolume"
+        );
+        assert_eq!(
+            interface_name::<IAudioSessionManager2>(),
+            "IAudioSessionManager2"
+        );
+    }
+
+    fn recorded(thread: &str) -> Option<ThreadApartment> {
+        com_apartments()
+            .into_iter()
+            .find(|t| t.thread.starts_with(thread))
+    }
+
+    #[test]
+    fn initializes_com_once_per_thread() {
+        // Each thread gets its own entry, so a name can be reused once its thread is gone
+        for _ in 0..3 {
+            std::thread::Builder::new()
+                .name("com-test-mta".to_string())
+                .spawn(|| {
+                    assert!(recorded("com-test-mta").is_none());
+                    assert_eq!(
+                        ensure_com(ComApartment::Multithreaded),
+                        Ok(ComApartment::Multithreaded)
+                    );
+                    // Asking again keeps the first apartment
+                    assert_eq!(
+                        ensure_com(ComApartment::SingleThreaded),
+                        Ok(ComApartment::Multithreaded)
+                    );
+                    let entry = recorded("com-test-mta").unwrap();
+                    assert_eq!(entry.apartment, ComApartment::Multithreaded);
+                    assert!(!entry.changed_mode);
+                })
+                .unwrap()
+                .join()
+                .unwrap();
+            // The entry goes away with the thread
+            assert!(recorded("com-test-mta").is_none());
+        }
+    }
+
+    #[test]
+    fn keeps_an_apartment_set_up_by_someone_else() {
+        std::thread::Builder::new()
+            .name("com-test-sta".to_string())
+            .spawn(|| unsafe {
+                CoInitializeEx(None, COINIT_APARTMENTTHREADED).unwrap();
+                assert_eq!(
+                    ensure_com(ComApartment::Multithreaded),
+                    Ok(ComApartment::SingleThreaded)
+                );
+                let entry = recorded("com-test-sta").unwrap();
+                assert!(entry.changed_mode);
+                // Not ours to undo, so the test balances its own initialization
+                COM_INIT.with(|cell| cell.borrow_mut().take());
+                CoUninitialize();
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a capture device"]
+    fn activates_every_interface_on_the_default_device() {
+        ensure_com(ComApartment::Multithreaded).unwrap();
+        unsafe {
+            let device = get_device_for_id("default-mic").unwrap();
+            activate::<IAudioEndpointVolume>(&device).unwrap();
+            activate::<IAudioMeterInformation>(&device).unwrap();
+            activate::<IAudioSessionManager2>(&device).unwrap();
+
+            // The per-thread caches hand out the same interface again
+            let first = get_cached_endpoint_for_id("default-mic").unwrap();
+            let second = get_cached_endpoint_for_id("default-mic").unwrap();
+            assert_eq!(first, second);
+            get_cached_meter_for_id("default-mic").unwrap();
+        }
+    }
+}
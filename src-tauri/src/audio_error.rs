@@ -0,0 +1,111 @@
+//! Background failures reported to the frontend.
+//!
+//! Commands hand their errors back to the caller, but nobody waits for the audio worker,
+//! the polling loop, the device listeners or the tray. `report_error` logs such a failure,
+//! keeps it in `AppState::recent_errors` and emits `audio-error`, so the frontend can show
+//! it. The same error (category and message) is reported at most once per `REPEAT_WINDOW`;
+//! the next report after the window says how often it happened in between, so a flapping
+//! device doesn't flood the event channel.
+
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+pub const AUDIO_ERROR: &str = "audio-error";
+
+const REPEAT_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCategory {
+    /// A device couldn't be muted, queried or enumerated.
+    Device,
+    /// An audio controller or the audio worker couldn't be created.
+    Controller,
+    /// The system notifications for mute and device changes couldn't be registered.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Listener,
+    /// The tray icon, tooltip or menu couldn't be updated.
+    Tray,
+    /// The log file couldn't be set up.
+    Logging,
+}
+
+impl ErrorCategory {
+    /// Source of the entry in `AppState::recent_errors`.
+    fn source(self) -> &'static str {
+        match self {
+            ErrorCategory::Device => "device",
+            ErrorCategory::Controller => "controller",
+            ErrorCategory::Listener => "listener",
+            ErrorCategory::Tray => "tray",
+            ErrorCategory::Logging => "logging",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioErrorEvent {
+    category: ErrorCategory,
+    message: String,
+    /// Details such as the device ID, or null.
+    context: serde_json::Value,
+    /// How often the same error was left out since it was last reported.
+    repeated: u32,
+}
+
+/// When an error was last reported, and how often it was left out since.
+type LastReport = (Instant, u32);
+
+/// The last report of each error.
+static REPORTED: Lazy<Mutex<HashMap<(ErrorCategory, String), LastReport>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Report a background failure; see the module docs. Returns the message, like
+/// `AppState::record_error`.
+pub fn report_error(
+    app: &AppHandle,
+    category: ErrorCategory,
+    message: impl Into<String>,
+    context: Option<serde_json::Value>,
+) -> String {
+    let message = message.into();
+    let repeated = {
+        let mut reported = REPORTED.lock_safe();
+        let now = Instant::now();
+        reported.retain(|_, (at, _)| now.duration_since(*at) < REPEAT_WINDOW * 4);
+        match reported.get_mut(&(category, message.clone())) {
+            Some((at, skipped)) if now.duration_since(*at) < REPEAT_WINDOW => {
+                *skipped += 1;
+                log::debug!("[{}] {} (repeated)", category.source(), message);
+                return message;
+            }
+            Some((at, skipped)) => {
+                *at = now;
+                std::mem::take(skipped)
+            }
+            None => {
+                reported.insert((category, message.clone()), (now, 0));
+                0
+            }
+        }
+    };
+
+    app.state::<AppState>()
+        .record_error(category.source(), message.clone());
+    let _ = app.emit(
+        AUDIO_ERROR,
+        AudioErrorEvent {
+            category,
+            message: message.clone(),
+            context: context.unwrap_or(serde_json::Value::Null),
+            repeated,
+        },
+    );
+    message
+}
@@ -0,0 +1,142 @@
+//! Recovery for an audio controller that failed to initialize.
+//!
+//! During a fast boot TogMic can start before the system audio service is up. Instead of
+//! running without a controller until the next restart, a background thread retries every
+//! `RETRY_INTERVAL_SECS` for up to `MAX_ATTEMPTS` attempts. `reinitialize` (the settings
+//! screen's "Reinitialize audio") makes one immediate attempt and restarts the retries if
+//! that fails too.
+
+use crate::audio::{AudioController, PlatformAudioController};
+use crate::{AppHandle, AppState, MutexExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::Manager;
+
+const RETRY_INTERVAL_SECS: u64 = 2;
+const MAX_ATTEMPTS: u32 = 30;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum AudioInitStatus {
+    Ready,
+    /// Initialization failed and is being retried in the background.
+    #[serde(rename_all = "camelCase")]
+    Initializing {
+        attempts: u32,
+        last_error: String,
+    },
+    /// Retries are exhausted; only `reinitialize` tries again.
+    Unavailable {
+        error: String,
+    },
+}
+
+impl AudioInitStatus {
+    /// Error for commands that need the audio controller while there is none.
+    pub fn error_message(&self) -> String {
+        match self {
+            AudioInitStatus::Ready => "Audio controller not initialized".to_string(),
+            AudioInitStatus::Initializing {
+                attempts,
+                last_error,
+            } => format!(
+                "Audio is still initializing (attempt {}): {}",
+                attempts, last_error
+            ),
+            AudioInitStatus::Unavailable { error } => format!(
+                "Audio is unavailable: {}. Reinitialize audio in the settings to try again",
+                error
+            ),
+        }
+    }
+}
+
+/// Set while a retry thread is running, so there is never more than one.
+static RETRYING: AtomicBool = AtomicBool::new(false);
+
+/// Create a controller and make sure the audio service actually answers. On Windows
+/// `new()` can't fail, so device enumeration is what notices a service that isn't up yet.
+pub fn create_controller() -> Result<PlatformAudioController, String> {
+    let controller = PlatformAudioController::new()?;
+    controller.enumerate_input_devices()?;
+    Ok(controller)
+}
+
+/// Record a failed startup initialization and keep retrying in the background.
+pub fn start_retry(app: AppHandle, error: String) {
+    *app.state::<AppState>().audio_init_status.lock_safe() = AudioInitStatus::Initializing {
+        attempts: 1,
+        last_error: error,
+    };
+    spawn_retry_thread(app);
+}
+
+/// Try to (re)create the audio controller right away. Falls back to background retries if
+/// the attempt fails.
+pub fn reinitialize(app: &AppHandle) -> Result<(), String> {
+    match try_init(app, false) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::warn!("[audio init] reinitialize failed: {}", e);
+            start_retry(app.clone(), e);
+            Err(app
+                .state::<AppState>()
+                .audio_init_status
+                .lock_safe()
+                .error_message())
+        }
+    }
+}
+
+fn spawn_retry_thread(app: AppHandle) {
+    if RETRYING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let _ = PlatformAudioController::init_thread();
+        loop {
+            std::thread::sleep(Duration::from_secs(RETRY_INTERVAL_SECS));
+
+            let state = app.state::<AppState>();
+            // Stop if a manual reinitialize got there first
+            let attempts = match &*state.audio_init_status.lock_safe() {
+                AudioInitStatus::Initializing { attempts, .. } => *attempts + 1,
+                _ => break,
+            };
+
+            let Err(e) = try_init(&app, true) else {
+                break;
+            };
+            log::warn!("[audio init] attempt {} failed: {}", attempts, e);
+            if attempts >= MAX_ATTEMPTS {
+                state.record_error(
+                    "audio_init",
+                    format!("Giving up after {} attempts: {}", attempts, e),
+                );
+                *state.audio_init_status.lock_safe() = AudioInitStatus::Unavailable { error: e };
+                break;
+            }
+            *state.audio_init_status.lock_safe() = AudioInitStatus::Initializing {
+                attempts,
+                last_error: e,
+            };
+        }
+        RETRYING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// One initialization attempt. `apply_start_muted` is set for the automatic retries, which
+/// stand in for the startup sync that couldn't happen.
+fn try_init(app: &AppHandle, apply_start_muted: bool) -> Result<(), String> {
+    let controller = create_controller()?;
+
+    let state = app.state::<AppState>();
+    *state.audio_controller.lock_safe() = Some(controller);
+    *state.audio_init_status.lock_safe() = AudioInitStatus::Ready;
+    log::info!("[audio init] audio controller ready");
+
+    crate::on_audio_controller_ready(app, apply_start_muted);
+    Ok(())
+}
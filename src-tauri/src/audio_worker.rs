@@ -0,0 +1,845 @@
+//! Persistent audio worker: one long-lived thread that owns an `AudioController` and
+//! applies mute changes off the UI/command path.
+//!
+//! Keeping the thread (and its COM apartment on Windows) alive keeps the per-thread
+//! endpoint cache warm across toggles. Requests that queue up while a previous one is still
+//! running are coalesced: for every device only the newest generation is applied.
+//!
+//! Transient device errors (see `audio::is_transient_error`) are retried a few times with
+//! backoff before they count as failures; permanent ones fail right away.
+//!
+//! The controller itself lives on a second thread, the executor, and every device call
+//! runs there under a deadline (`set_timeout_ms`). A driver can block a call for many
+//! seconds; when one overruns, the device is marked unhealthy and skipped for the rest of
+//! the batch, `on_timeout` is called and the executor is left behind for a fresh one with
+//! its own COM apartment and endpoint caches. The stuck thread exits once the call returns.
+//! Calls to an unhealthy device aren't dispatched until `UNHEALTHY_SKIP` has passed, and
+//! no new executor is started while `MAX_ABANDONED_EXECUTORS` are still stuck.
+
+use crate::audio::{is_transient_error, AudioController, AudioDevice, PlatformAudioController};
+use crate::hard_mute::{self, MuteStrategy};
+use crate::mute_state::MuteState;
+use crate::{HotkeyProfile, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Delays before each retry of a transiently failing device.
+const RETRY_BACKOFF_MS: [u64; 3] = [100, 300, 800];
+
+/// Default of `AppSettings::audio_timeout_ms`.
+pub const DEFAULT_TIMEOUT_MS: u64 = 2000;
+const MIN_TIMEOUT_MS: u64 = 250;
+/// `AudioWorker::call` waits this many deadlines for its result.
+const CALL_WAIT_FACTOR: u64 = 3;
+
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_MS);
+
+/// How long calls to a device are refused after one timed out. The first call after that
+/// is dispatched again and clears the mark if it finishes in time.
+const UNHEALTHY_SKIP: Duration = Duration::from_secs(30);
+
+/// Executors left behind in a call that may still be stuck. Each one holds a thread and a
+/// controller, so a device that keeps hanging can't pile them up.
+const MAX_ABANDONED_EXECUTORS: usize = 3;
+
+/// Executors given up on whose thread hasn't exited yet.
+static ABANDONED: AtomicUsize = AtomicUsize::new(0);
+
+/// Devices whose last call timed out, with when it did.
+static UNHEALTHY: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Change the deadline of device calls. Takes effect with the next call.
+pub fn set_timeout_ms(ms: u64) {
+    TIMEOUT_MS.store(ms.max(MIN_TIMEOUT_MS), Ordering::SeqCst);
+}
+
+/// Whether the last call to `device_id` finished in time.
+pub fn is_healthy(device_id: &str) -> bool {
+    !UNHEALTHY.lock_safe().contains_key(device_id)
+}
+
+/// `Err` if `device_id` timed out less than `UNHEALTHY_SKIP` ago.
+fn check_health(device_id: &str) -> Result<(), String> {
+    match UNHEALTHY.lock_safe().get(device_id) {
+        Some(at) if at.elapsed() < UNHEALTHY_SKIP => Err(format!(
+            "Skipped: the device timed out {} s ago",
+            at.elapsed().as_secs()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// One mute change for the worker.
+pub struct MuteRequest {
+    /// Profile whose devices to change. Resolved on the worker so an "all-mics" profile
+    /// never enumerates devices over COM on the caller's thread.
+    pub profile: HotkeyProfile,
+    pub muted: bool,
+    /// Cached state before the caller's optimistic update, restored if every device fails.
+    pub previous: MuteState,
+    pub generation: u64,
+    /// A fresh enough device list to resolve `profile` with instead of enumerating.
+    pub devices: Option<Arc<[AudioDevice]>>,
+    /// Receives this request's result once it has been applied.
+    pub reply: Sender<ApplyResult>,
+}
+
+/// A queued request: its generation and where its result arrives.
+pub struct PendingApply {
+    pub generation: u64,
+    pub reply: Receiver<ApplyResult>,
+}
+
+/// Per-device outcome of one request. Devices taken over by a newer request in the same
+/// batch are reported by that request instead.
+#[derive(Debug, Clone)]
+pub struct ApplyResult {
+    pub muted: bool,
+    pub previous: MuteState,
+    pub generation: u64,
+    pub results: Vec<DeviceOutcome>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceOutcome {
+    pub device_id: String,
+    pub result: Result<(), String>,
+    /// Retries needed (or spent, if `result` is still an error).
+    pub retries: u32,
+    /// The device doesn't exist and wasn't tried.
+    pub missing: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceFailure {
+    pub device_id: String,
+    pub error: String,
+    pub retries: u32,
+    pub missing: bool,
+}
+
+/// Frontend-facing summary of an `ApplyResult`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteApplyReport {
+    pub muted: bool,
+    /// The request's generation, as returned with `toggle_mute`.
+    pub generation: u64,
+    pub applied: Vec<String>,
+    pub failed: Vec<DeviceFailure>,
+    /// Retries spent across all devices.
+    pub retries: u32,
+}
+
+impl ApplyResult {
+    pub fn report(&self) -> MuteApplyReport {
+        let mut applied = Vec::new();
+        let mut failed = Vec::new();
+        for outcome in &self.results {
+            match &outcome.result {
+                Ok(()) => applied.push(outcome.device_id.clone()),
+                Err(e) => failed.push(DeviceFailure {
+                    device_id: outcome.device_id.clone(),
+                    error: e.clone(),
+                    retries: outcome.retries,
+                    missing: outcome.missing,
+                }),
+            }
+        }
+        MuteApplyReport {
+            muted: self.muted,
+            generation: self.generation,
+            applied,
+            failed,
+            retries: self.results.iter().map(|o| o.retries).sum(),
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce(&PlatformAudioController) + Send>;
+
+/// Thread owning the controller; see the module docs.
+struct Executor {
+    jobs: Sender<Job>,
+    /// Set when the worker gives up on this executor, so its thread counts itself out of
+    /// `ABANDONED` when it exits.
+    abandoned: Arc<AtomicBool>,
+}
+
+impl Executor {
+    fn spawn() -> Result<Self, String> {
+        let stuck = ABANDONED.load(Ordering::SeqCst);
+        if stuck >= MAX_ABANDONED_EXECUTORS {
+            return Err(format!(
+                "{} audio threads are still stuck in the audio driver",
+                stuck
+            ));
+        }
+        let (jobs, rx) = mpsc::channel::<Job>();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let thread_abandoned = abandoned.clone();
+        std::thread::Builder::new()
+            .name("audio-executor".to_string())
+            .spawn(move || {
+                let _exit = AbandonGuard(thread_abandoned);
+                // Initialize audio subsystem for this thread (e.g., COM on Windows)
+                let _ = PlatformAudioController::init_thread();
+                let controller = match PlatformAudioController::new() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+                // Ends when the worker drops the sender, including after giving up on
+                // this thread
+                for job in rx {
+                    // A panicking job drops its reply, which its caller handles like a timeout;
+                    // the thread and its controller carry on
+                    let ran =
+                        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| job(&controller)));
+                    if ran.is_err() {
+                        log::error!("[audio worker] a job panicked");
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start the audio thread: {}", e))?;
+        ready_rx
+            .recv()
+            .map_err(|_| "The audio thread exited during startup".to_string())??;
+        Ok(Self { jobs, abandoned })
+    }
+
+    /// Give up on the executor: its thread exits once the stuck call returns.
+    fn abandon(self) {
+        ABANDONED.fetch_add(1, Ordering::SeqCst);
+        self.abandoned.store(true, Ordering::SeqCst);
+    }
+
+    /// Run `f` on the executor. `None` if it didn't finish within `timeout` or the thread
+    /// is gone.
+    fn run<R: Send + 'static>(
+        &self,
+        timeout: Duration,
+        f: impl FnOnce(&PlatformAudioController) -> R + Send + 'static,
+    ) -> Option<R> {
+        let (tx, rx) = mpsc::channel();
+        let job: Job = Box::new(move |controller| {
+            let _ = tx.send(f(controller));
+        });
+        self.jobs.send(job).ok()?;
+        rx.recv_timeout(timeout).ok()
+    }
+}
+
+/// Takes an abandoned executor's thread out of `ABANDONED` when it exits.
+struct AbandonGuard(Arc<AtomicBool>);
+
+impl Drop for AbandonGuard {
+    fn drop(&mut self) {
+        if self.0.load(Ordering::SeqCst) {
+            ABANDONED.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// `on_timeout(device_id, operation, timeout_ms)`, see `AudioWorker::spawn`.
+type TimeoutHandler = Box<dyn Fn(Option<&str>, &str, u64) + Send>;
+
+/// The worker's handle on the executor, replacing it after a timeout.
+struct Runner {
+    executor: Option<Executor>,
+    on_timeout: TimeoutHandler,
+}
+
+impl Runner {
+    /// Run `f` on the executor under the deadline. `device_id` is the device the call is
+    /// about, for the health status; calls to a device that recently timed out aren't
+    /// dispatched.
+    fn run<R: Send + 'static>(
+        &mut self,
+        operation: &str,
+        device_id: Option<&str>,
+        f: impl FnOnce(&PlatformAudioController) -> R + Send + 'static,
+    ) -> Result<R, String> {
+        if let Some(device_id) = device_id {
+            check_health(device_id)?;
+        }
+        let executor = match self.executor.take() {
+            Some(executor) => executor,
+            None => Executor::spawn()?,
+        };
+        let timeout_ms = TIMEOUT_MS.load(Ordering::SeqCst);
+        match executor.run(Duration::from_millis(timeout_ms), f) {
+            Some(result) => {
+                self.executor = Some(executor);
+                if let Some(device_id) = device_id {
+                    UNHEALTHY.lock_safe().remove(device_id);
+                }
+                Ok(result)
+            }
+            None => {
+                // The stuck thread is left behind; the next call starts a fresh one
+                executor.abandon();
+                log::warn!(
+                    "[audio worker] {} ({}) timed out after {} ms, restarting the audio thread",
+                    operation,
+                    device_id.unwrap_or("-"),
+                    timeout_ms
+                );
+                if let Some(device_id) = device_id {
+                    UNHEALTHY
+                        .lock_safe()
+                        .insert(device_id.to_string(), Instant::now());
+                }
+                (self.on_timeout)(device_id, operation, timeout_ms);
+                Err(format!("{} timed out after {} ms", operation, timeout_ms))
+            }
+        }
+    }
+}
+
+/// Work run on the worker with the runner, see `AudioWorker::query`.
+type Task = Box<dyn FnOnce(&mut Runner) + Send>;
+
+enum Message {
+    Apply(Box<MuteRequest>),
+    Run(Task),
+    Shutdown,
+}
+
+pub struct AudioWorker {
+    tx: SyncSender<Message>,
+    thread: Option<JoinHandle<()>>,
+    generation: Arc<AtomicU64>,
+    /// Requests queued or being applied.
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl AudioWorker {
+    /// Spawn the worker thread. `on_result` runs on a separate completion thread so slow
+    /// result handling (events, tray, sounds) never delays the next device operation.
+    /// `on_retry(device_id, attempt, error)` runs on the worker before each retry, and
+    /// `on_timeout(device_id, operation, timeout_ms)` after a call overran its deadline.
+    pub fn spawn(
+        on_result: impl Fn(ApplyResult) + Send + 'static,
+        on_retry: impl Fn(&str, u32, &str) + Send + 'static,
+        on_timeout: impl Fn(Option<&str>, &str, u64) + Send + 'static,
+        on_init_error: impl FnOnce(String) + Send + 'static,
+    ) -> Self {
+        let (tx, rx) = mpsc::sync_channel::<Message>(16);
+        let (done_tx, done_rx) = mpsc::channel::<ApplyResult>();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let worker_in_flight = in_flight.clone();
+
+        std::thread::spawn(move || {
+            for result in done_rx {
+                on_result(result);
+            }
+        });
+
+        let thread = std::thread::spawn(move || {
+            let mut runner = match Executor::spawn() {
+                Ok(executor) => Runner {
+                    executor: Some(executor),
+                    on_timeout: Box::new(on_timeout),
+                },
+                Err(e) => {
+                    on_init_error(e);
+                    return;
+                }
+            };
+
+            while let Ok(first) = rx.recv() {
+                // Take everything that queued up behind this message
+                let mut requests = Vec::new();
+                let mut tasks = Vec::new();
+                for message in std::iter::once(first).chain(rx.try_iter()) {
+                    match message {
+                        Message::Apply(request) => requests.push(*request),
+                        Message::Run(task) => tasks.push(task),
+                        Message::Shutdown => return,
+                    }
+                }
+
+                for (request, result) in apply_coalesced(&mut runner, &requests, &on_retry) {
+                    let _ = request.reply.send(result.clone());
+                    if !result.results.is_empty() {
+                        let _ = done_tx.send(result);
+                    }
+                }
+                worker_in_flight.fetch_sub(requests.len(), Ordering::SeqCst);
+                for task in tasks {
+                    task(&mut runner);
+                }
+            }
+        });
+
+        Self {
+            tx,
+            thread: Some(thread),
+            generation: Arc::new(AtomicU64::new(0)),
+            in_flight,
+        }
+    }
+
+    /// Queue a mute change. Non-blocking; if the queue is full the request is dropped, as
+    /// a newer one is already waiting to supersede it (the returned receiver then reports
+    /// a disconnect). Callers that don't care about the outcome can drop the receiver.
+    pub fn apply(
+        &self,
+        profile: HotkeyProfile,
+        muted: bool,
+        previous: MuteState,
+        devices: Option<Arc<[AudioDevice]>>,
+    ) -> PendingApply {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let (reply, receiver) = mpsc::channel();
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        if self
+            .tx
+            .try_send(Message::Apply(Box::new(MuteRequest {
+                profile,
+                muted,
+                previous,
+                generation,
+                devices,
+                reply,
+            })))
+            .is_err()
+        {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            log::debug!(
+                "[audio worker] queue full, dropped request {} (muted: {})",
+                generation,
+                muted
+            );
+        }
+        PendingApply {
+            generation,
+            reply: receiver,
+        }
+    }
+
+    /// Run `query` on the audio thread under the deadline, then `then` on the worker with
+    /// its result (`None` if it timed out). Non-blocking, for callers on the UI, tray and
+    /// hotkey threads; dropped like `apply` if the queue is full.
+    pub fn query<R: Send + 'static>(
+        &self,
+        operation: &'static str,
+        query: impl FnOnce(&PlatformAudioController) -> R + Send + 'static,
+        then: impl FnOnce(Option<R>) + Send + 'static,
+    ) {
+        let task: Task = Box::new(move |runner| then(runner.run(operation, None, query).ok()));
+        if self.tx.try_send(Message::Run(task)).is_err() {
+            log::debug!("[audio worker] queue full, dropped {}", operation);
+        }
+    }
+
+    /// A handle for `AudioCaller::call` that can be used without keeping the worker
+    /// locked.
+    pub fn caller(&self) -> AudioCaller {
+        AudioCaller {
+            tx: self.tx.clone(),
+        }
+    }
+
+    /// Generation of the most recently queued request. Results for older generations are
+    /// outdated.
+    pub fn current_generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Whether requests are still queued or being applied.
+    pub fn is_busy(&self) -> bool {
+        self.in_flight.load(Ordering::SeqCst) > 0
+    }
+
+    /// Stop the worker once it has finished the batch it is working on.
+    pub fn shutdown(mut self) {
+        // Blocking send: the shutdown must not be dropped because the queue is full
+        let _ = self.tx.send(Message::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Blocking queries on the worker's audio thread, see `AudioWorker::caller`.
+#[derive(Clone)]
+pub struct AudioCaller {
+    tx: SyncSender<Message>,
+}
+
+impl AudioCaller {
+    /// Run `query` on the audio thread under the deadline and wait for its result, for
+    /// callers that need the answer. `device_id` is the device the call is about, see
+    /// `Runner::run`. Must not be called from the worker itself.
+    pub fn call<R: Send + 'static>(
+        &self,
+        operation: &'static str,
+        device_id: Option<String>,
+        query: impl FnOnce(&PlatformAudioController) -> R + Send + 'static,
+    ) -> Result<R, String> {
+        let (tx, rx) = mpsc::channel();
+        let task: Task = Box::new(move |runner| {
+            let _ = tx.send(runner.run(operation, device_id.as_deref(), query));
+        });
+        self.tx
+            .try_send(Message::Run(task))
+            .map_err(|_| format!("The audio thread is busy, {} was dropped", operation))?;
+        // The batch queued ahead of this call may run into deadlines of its own
+        let wait = Duration::from_millis(TIMEOUT_MS.load(Ordering::SeqCst) * CALL_WAIT_FACTOR);
+        rx.recv_timeout(wait)
+            .map_err(|_| format!("Timed out waiting for {}", operation))?
+    }
+}
+
+/// Apply a batch of requests, newest generation per device winning, and report the
+/// per-device outcome of every request.
+fn apply_coalesced<'a>(
+    runner: &mut Runner,
+    requests: &'a [MuteRequest],
+    on_retry: &impl Fn(&str, u32, &str),
+) -> Vec<(&'a MuteRequest, ApplyResult)> {
+    // device id → index of the newest request targeting it
+    let mut targets: HashMap<String, usize> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    let mut missing: HashSet<String> = HashSet::new();
+    for (index, request) in requests.iter().enumerate() {
+        let profile = request.profile.clone();
+        let devices = request.devices.clone();
+        let resolved = runner
+            .run("enumerate", None, move |controller| {
+                crate::resolve_device_ids(controller, &profile, devices.as_deref())
+            })
+            .and_then(|resolved| resolved)
+            .unwrap_or_else(|_| crate::ResolvedDevices {
+                present: request.profile.listed_device_ids(),
+                missing: Vec::new(),
+            });
+        for device_id in resolved.missing {
+            missing.insert(device_id.clone());
+            if targets.insert(device_id.clone(), index).is_none() {
+                order.push(device_id);
+            }
+        }
+        for device_id in resolved.present {
+            missing.remove(&device_id);
+            if targets.insert(device_id.clone(), index).is_none() {
+                order.push(device_id);
+            }
+        }
+    }
+
+    let mut results: Vec<(&MuteRequest, ApplyResult)> = requests
+        .iter()
+        .map(|request| {
+            let result = ApplyResult {
+                muted: request.muted,
+                previous: request.previous,
+                generation: request.generation,
+                results: Vec::new(),
+            };
+            (request, result)
+        })
+        .collect();
+
+    // Devices a solo profile doesn't cover stay muted whatever the request; they aren't
+    // the profile's, so failures are only logged
+    let mut others: Vec<(String, MuteStrategy)> = Vec::new();
+    if let Some(request) = requests.iter().rev().find(|r| r.profile.solo) {
+        let devices = match &request.devices {
+            Some(devices) => Ok(devices.to_vec()),
+            None => runner
+                .run("enumerate", None, |controller| {
+                    controller.enumerate_input_devices()
+                })
+                .and_then(|devices| devices),
+        };
+        match devices {
+            Ok(devices) => {
+                for device_id in crate::solo::other_devices(&request.profile, &devices) {
+                    if !targets.contains_key(&device_id) {
+                        others.push((device_id, request.profile.mute_strategy));
+                    }
+                }
+            }
+            Err(e) => log::warn!("[solo] failed to enumerate devices: {}", e),
+        }
+    }
+
+    // Devices that timed out are skipped for the rest of the batch
+    let mut timed_out: HashSet<String> = HashSet::new();
+    for device_id in order {
+        let index = targets[&device_id];
+        let request = &requests[index];
+        if missing.contains(&device_id) {
+            results[index].1.results.push(DeviceOutcome {
+                device_id,
+                result: Err(crate::missing_devices::DEVICE_MISSING.to_string()),
+                retries: 0,
+                missing: true,
+            });
+            continue;
+        }
+        // An unmuted scene takes each device to its own state
+        let muted = crate::scene::target(&request.profile, &device_id, request.muted);
+        let outcome = set_mute_with_retry(
+            runner,
+            device_id,
+            muted,
+            request.profile.mute_strategy,
+            on_retry,
+            &mut timed_out,
+        );
+        results[index].1.results.push(outcome);
+    }
+    for (device_id, strategy) in others {
+        if timed_out.contains(&device_id) {
+            continue;
+        }
+        let outcome =
+            set_mute_with_retry(runner, device_id, true, strategy, on_retry, &mut timed_out);
+        if let Err(e) = &outcome.result {
+            log::warn!("[solo] failed to mute {}: {}", outcome.device_id, e);
+        }
+    }
+
+    results
+}
+
+/// Set one device's mute state, retrying transient errors with backoff. The backend drops
+/// the device's cached endpoint on failure, so every retry starts from a fresh lookup. A
+/// call that times out isn't retried; the device is added to `timed_out`.
+fn set_mute_with_retry(
+    runner: &mut Runner,
+    device_id: String,
+    muted: bool,
+    strategy: MuteStrategy,
+    on_retry: &impl Fn(&str, u32, &str),
+    timed_out: &mut HashSet<String>,
+) -> DeviceOutcome {
+    let mut retries = 0;
+    loop {
+        let id = device_id.clone();
+        let result = match runner.run("set_mute", Some(&device_id), move |controller| {
+            hard_mute::set_mute(controller, &id, muted, strategy)
+        }) {
+            Ok(result) => result,
+            Err(e) => {
+                timed_out.insert(device_id.clone());
+                Err(e)
+            }
+        };
+        let delay = match &result {
+            Err(e) if is_transient_error(e) => RETRY_BACKOFF_MS.get(retries as usize),
+            _ => None,
+        };
+        let Some(delay) = delay else {
+            return DeviceOutcome {
+                device_id,
+                result,
+                retries,
+                missing: false,
+            };
+        };
+
+        retries += 1;
+        if let Err(e) = &result {
+            on_retry(&device_id, retries, e);
+        }
+        std::thread::sleep(Duration::from_millis(*delay));
+    }
+}
+
+#[cfg(all(test, feature = "mock-audio"))]
+mod tests {
+    use super::*;
+    use crate::audio::mock::{self, MockOp};
+    use crate::test_support::{mock_device, mock_devices, profile};
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    fn runner() -> Runner {
+        Runner {
+            executor: Some(Executor::spawn().unwrap()),
+            on_timeout: Box::new(|_, _, _| {}),
+        }
+    }
+
+    fn request(profile: HotkeyProfile, muted: bool, generation: u64) -> MuteRequest {
+        MuteRequest {
+            profile,
+            muted,
+            previous: MuteState::from(!muted),
+            generation,
+            devices: None,
+            reply: mpsc::channel().0,
+        }
+    }
+
+    fn on_device(id: &str, muted: bool) -> MuteRequest {
+        request(profile(json!({ "deviceIds": [id] })), muted, 1)
+    }
+
+    fn is_muted(id: &str) -> bool {
+        mock::devices().iter().any(|d| d.device.id == id && d.muted)
+    }
+
+    fn apply(requests: &[MuteRequest]) -> Vec<ApplyResult> {
+        apply_coalesced(&mut runner(), requests, &|_, _, _| {})
+            .into_iter()
+            .map(|(_, result)| result)
+            .collect()
+    }
+
+    fn outcome(results: &[ApplyResult]) -> &DeviceOutcome {
+        &results[0].results[0]
+    }
+
+    #[test]
+    fn sets_the_devices_of_the_request() {
+        let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+        let results = apply(&[on_device("a", true)]);
+        assert_eq!(results[0].report().applied, ["a"]);
+        assert!(is_muted("a"));
+        assert!(!is_muted("b"));
+    }
+
+    #[test]
+    fn the_newest_request_wins_per_device() {
+        let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+        let all = request(
+            profile(json!({ "deviceIds": [crate::ALL_DEVICES_ID] })),
+            true,
+            1,
+        );
+        let only_b = request(profile(json!({ "deviceIds": ["b"] })), false, 2);
+        let results = apply(&[all, only_b]);
+        assert_eq!(results[0].report().applied, ["a"]);
+        assert_eq!(results[1].report().applied, ["b"]);
+        assert!(is_muted("a"));
+        assert!(!is_muted("b"));
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let _devices = mock_devices(vec![mock_device("a", true)]);
+        mock::fail("a", MockOp::SetMute, "transient: busy", 2);
+        let attempts = RefCell::new(Vec::new());
+        let requests = [on_device("a", true)];
+        let results = apply_coalesced(&mut runner(), &requests, &|id, attempt, _| {
+            attempts.borrow_mut().push((id.to_string(), attempt))
+        });
+        let outcome = &results[0].1.results[0];
+        assert_eq!(outcome.result, Ok(()));
+        assert_eq!(outcome.retries, 2);
+        assert_eq!(
+            attempts.into_inner(),
+            [("a".to_string(), 1), ("a".to_string(), 2)]
+        );
+        assert!(is_muted("a"));
+    }
+
+    #[test]
+    fn gives_up_after_the_last_retry() {
+        let _devices = mock_devices(vec![mock_device("a", true)]);
+        mock::fail("a", MockOp::SetMute, "transient: busy", 0);
+        let results = apply(&[on_device("a", true)]);
+        assert_eq!(outcome(&results).result, Err("transient: busy".to_string()));
+        assert_eq!(outcome(&results).retries, RETRY_BACKOFF_MS.len() as u32);
+        assert!(!is_muted("a"));
+    }
+
+    #[test]
+    fn permanent_errors_fail_right_away() {
+        let _devices = mock_devices(vec![mock_device("a", true)]);
+        mock::fail("a", MockOp::SetMute, "access denied", 1);
+        let results = apply(&[on_device("a", true)]);
+        assert_eq!(outcome(&results).result, Err("access denied".to_string()));
+        assert_eq!(outcome(&results).retries, 0);
+    }
+
+    #[test]
+    fn missing_devices_are_reported_without_a_call() {
+        let _devices = mock_devices(vec![mock_device("a", true)]);
+        let results = apply(&[on_device("worker-gone", true)]);
+        assert!(outcome(&results).missing);
+        assert_eq!(
+            outcome(&results).result,
+            Err(crate::missing_devices::DEVICE_MISSING.to_string())
+        );
+    }
+
+    #[test]
+    fn an_unmuted_scene_sets_each_device_to_its_state() {
+        let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+        let scene = profile(json!({
+            "deviceStates": [
+                { "deviceId": "a", "mutedWhenActive": false },
+                { "deviceId": "b", "mutedWhenActive": true }
+            ]
+        }));
+        mock::set_external_mute("a", true).unwrap();
+        apply(&[request(scene, false, 1)]);
+        assert!(!is_muted("a"));
+        assert!(is_muted("b"));
+    }
+
+    #[test]
+    fn a_solo_profile_mutes_the_other_devices() {
+        let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+        let solo = profile(json!({ "deviceIds": ["a"], "solo": true }));
+        let results = apply(&[request(solo, false, 1)]);
+        assert_eq!(results[0].report().applied, ["a"]);
+        assert!(!is_muted("a"));
+        assert!(is_muted("b"));
+    }
+
+    #[test]
+    fn the_worker_reports_each_request() {
+        let _devices = mock_devices(vec![mock_device("a", true)]);
+        let (done_tx, done_rx) = mpsc::channel();
+        let worker = AudioWorker::spawn(
+            move |result| {
+                let _ = done_tx.send(result.generation);
+            },
+            |_, _, _| {},
+            |_, _, _| {},
+            |e| panic!("{}", e),
+        );
+        let pending = worker.apply(
+            profile(json!({ "deviceIds": ["a"] })),
+            true,
+            MuteState::Unmuted,
+            None,
+        );
+        let result = pending.reply.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(result.generation, pending.generation);
+        assert_eq!(result.report().applied, ["a"]);
+        assert_eq!(
+            done_rx.recv_timeout(Duration::from_secs(5)),
+            Ok(pending.generation)
+        );
+        assert!(is_muted("a"));
+
+        let caller = worker.caller();
+        assert_eq!(
+            caller.call("get_mute", Some("a".to_string()), |controller| {
+                controller.get_mute_state("a")
+            }),
+            Ok(Ok(true))
+        );
+        worker.shutdown();
+    }
+}
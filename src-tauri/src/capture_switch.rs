@@ -0,0 +1,154 @@
+//! Switching profiles when an application starts capturing from a microphone.
+//!
+//! The mic-usage watcher (so only with `track_mic_usage` on) hands every list of consumers
+//! to `observe`. A process counts as started once it has been capturing for
+//! `START_DELAY`, and as stopped only once it has been gone for `STOP_GRACE`, so an
+//! application that keeps opening and closing its stream triggers once. On a start, the
+//! first profile listing the process in `activate_on_capture_by` is activated the same way
+//! an external trigger switches profiles and unmuted, emitting
+//! `profile-auto-switched { reason: "capture", process }`. For a process no profile lists,
+//! `OnUnknownCapture::Notify` emits `capture-started` for the frontend to offer unmuting.
+
+use crate::foreground::{self, ProfileAutoSwitchedEvent};
+use crate::mic_usage::MicConsumer;
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+const START_DELAY: Duration = Duration::from_secs(3);
+const STOP_GRACE: Duration = Duration::from_secs(15);
+
+pub const CAPTURE_STARTED: &str = "capture-started";
+
+/// `source` of the unmute made here.
+pub const SOURCE_CAPTURE: &str = "capture";
+
+/// What to do when an application no profile lists starts capturing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OnUnknownCapture {
+    #[default]
+    Ignore,
+    /// Emit `capture-started` so the frontend can ask.
+    Notify,
+}
+
+static NOTIFY_UNKNOWN: AtomicBool = AtomicBool::new(false);
+/// Capturing processes by name, including those within `STOP_GRACE` of stopping.
+static SEEN: Lazy<Mutex<HashMap<String, Capture>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Capture {
+    since: Instant,
+    last_seen: Instant,
+    handled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptureStartedEvent<'a> {
+    process: &'a str,
+    pid: u32,
+    device_id: &'a str,
+}
+
+pub fn configure(on_unknown: OnUnknownCapture) {
+    NOTIFY_UNKNOWN.store(on_unknown == OnUnknownCapture::Notify, Ordering::SeqCst);
+}
+
+/// Act on the processes of `consumers` that just started capturing.
+pub fn observe(app: &AppHandle, consumers: &[MicConsumer]) {
+    let now = Instant::now();
+    let started: Vec<String> = {
+        let mut seen = SEEN.lock_safe();
+        for consumer in consumers {
+            seen.entry(consumer.process_name.clone())
+                .or_insert(Capture {
+                    since: now,
+                    last_seen: now,
+                    handled: false,
+                })
+                .last_seen = now;
+        }
+        seen.retain(|_, capture| now.duration_since(capture.last_seen) < STOP_GRACE);
+        seen.iter_mut()
+            .filter(|(_, capture)| {
+                !capture.handled
+                    && capture.last_seen == now
+                    && now.duration_since(capture.since) >= START_DELAY
+            })
+            .map(|(process, capture)| {
+                capture.handled = true;
+                process.clone()
+            })
+            .collect()
+    };
+
+    for process in started {
+        let Some(consumer) = consumers.iter().find(|c| c.process_name == process) else {
+            continue;
+        };
+        on_started(app, consumer);
+    }
+}
+
+fn on_started(app: &AppHandle, consumer: &MicConsumer) {
+    let process = consumer.process_name.as_str();
+    let profiles = crate::load_profiles(app);
+    let Some(target) = profiles
+        .iter()
+        .find(|p| foreground::matches_app(&p.activate_on_capture_by, process))
+    else {
+        if NOTIFY_UNKNOWN.load(Ordering::SeqCst) {
+            log::info!("[capture] {} started capturing", process);
+            let _ = app.emit(
+                CAPTURE_STARTED,
+                CaptureStartedEvent {
+                    process,
+                    pid: consumer.pid,
+                    device_id: &consumer.device_id,
+                },
+            );
+        }
+        return;
+    };
+
+    let state = app.state::<AppState>();
+    let active = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .is_some_and(|p| p.id == target.id);
+    if !active {
+        match crate::switch_profile(app, &target.id) {
+            Ok(profile) => {
+                log::info!(
+                    "[capture] switched to profile '{}' ({})",
+                    profile.name,
+                    process
+                );
+                let _ = app.emit(
+                    "profile-auto-switched",
+                    ProfileAutoSwitchedEvent {
+                        profile,
+                        process: process.to_string(),
+                        reason: "capture",
+                        reverted: false,
+                    },
+                );
+            }
+            Err(e) => {
+                state.record_error("capture_switch", e);
+                return;
+            }
+        }
+    }
+    if let Err(e) = crate::set_mute_internal(&state, app, false, false, SOURCE_CAPTURE) {
+        state.record_error("capture_switch", e);
+    }
+}
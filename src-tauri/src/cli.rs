@@ -0,0 +1,131 @@
+use crate::commands::external::{ExternalAction, URL_SCHEME};
+
+/// Parse command line flags into external actions.
+///
+/// Supported flags:
+///   --toggle              toggle the active profile's mute state
+///   --mute / --unmute     set the mute state (silent unless --sound is also passed)
+///   --sound               play feedback sounds for --mute/--unmute
+///   --profile <id|name>   switch the active profile
+///   --show                raise the main window; at startup, start with it shown
+///   --minimized           start with the main window hidden, see `start_hidden`
+///   --config <path>       use another config store; only read at startup, see
+///                         `config_location`
+///   --no-exit-unmute      don't unmute devices when this instance exits, see
+///                         `exit_restore`; only read at startup
+///
+/// Unknown flags are logged and ignored so a typo never prevents the app from launching.
+pub fn parse_args(args: &[String]) -> Vec<ExternalAction> {
+    let sound = args.iter().any(|a| a == "--sound");
+    let mut actions = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--toggle" => actions.push(ExternalAction::Toggle),
+            "--mute" => actions.push(ExternalAction::SetMute { muted: true, sound }),
+            "--unmute" => actions.push(ExternalAction::SetMute {
+                muted: false,
+                sound,
+            }),
+            "--show" => actions.push(ExternalAction::Show),
+            "--profile" => match iter.next() {
+                Some(id_or_name) => actions.push(ExternalAction::Profile {
+                    id: id_or_name.clone(),
+                }),
+                None => log::warn!("[cli] --profile requires a profile id or name"),
+            },
+            // Handled elsewhere: --sound modifies --mute/--unmute, --minimized is passed by
+            // the autostart entry and keeps the window hidden at startup or, for a second
+            // launch, keeps it from being raised.
+            "--sound" | "--minimized" => {}
+            // Picks the config at startup; a secondary launch only reaches this instance if
+            // it named the same config
+            crate::config_location::CONFIG_FLAG => {
+                iter.next();
+            }
+            flag if flag.starts_with("--config=") => {}
+            crate::exit_restore::NO_EXIT_UNMUTE_FLAG => {}
+            // togmic:// URLs also arrive in argv; the deep-link plugin dispatches them
+            url if url.starts_with(URL_SCHEME) => {}
+            other => log::warn!("[cli] Ignoring unknown argument: {}", other),
+        }
+    }
+
+    // Switch profile before acting on mute state so `--profile X --mute` mutes X's devices
+    actions.sort_by_key(|action| !matches!(action, ExternalAction::Profile { .. }));
+    actions
+}
+
+/// Whether this launch starts with the main window hidden. Precedence: `--minimized`
+/// (passed by the autostart entry) always hides it, otherwise `--show` shows it, otherwise
+/// the `start_minimized` setting decides.
+pub fn start_hidden(args: &[String], start_minimized: bool) -> bool {
+    if args.iter().any(|a| a == "--minimized") {
+        true
+    } else if args.iter().any(|a| a == "--show") {
+        false
+    } else {
+        start_minimized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn minimized_wins_over_show_and_the_setting() {
+        assert!(start_hidden(&args(&["--minimized"]), false));
+        assert!(start_hidden(&args(&["--show", "--minimized"]), false));
+        assert!(!start_hidden(&args(&["--show"]), true));
+        assert!(start_hidden(&args(&[]), true));
+        assert!(!start_hidden(&args(&["--toggle"]), false));
+    }
+
+    #[test]
+    fn switches_profile_before_changing_mute() {
+        assert_eq!(
+            parse_args(&args(&["--mute", "--sound", "--profile", "Meetings"])),
+            [
+                ExternalAction::Profile {
+                    id: "Meetings".to_string()
+                },
+                ExternalAction::SetMute {
+                    muted: true,
+                    sound: true
+                },
+            ]
+        );
+        assert_eq!(
+            parse_args(&args(&["--unmute"])),
+            [ExternalAction::SetMute {
+                muted: false,
+                sound: false
+            }]
+        );
+    }
+
+    #[test]
+    fn skips_startup_flags_and_unknown_arguments() {
+        assert_eq!(
+            parse_args(&args(&[
+                "--minimized",
+                "--config",
+                "/tmp/other.json",
+                "--config=/tmp/other.json",
+                "--no-exit-unmute",
+                "togmic://toggle",
+                "--bogus",
+                "--show",
+            ])),
+            [ExternalAction::Show]
+        );
+        // A --profile without a value is dropped
+        assert!(parse_args(&args(&["--profile"])).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{AppHandle, AppState, MutexExt};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::Manager;
+
+/// Prefix of every deep link TogMic handles.
+pub const URL_SCHEME: &str = "togmic://";
+
+/// An action requested from outside the app (command line, deep link, ...).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExternalAction {
+    Toggle,
+    SetMute { muted: bool, sound: bool },
+    Profile { id: String },
+    Show,
+}
+
+/// Parse a `togmic://` URL into an action.
+///
+///   togmic://toggle
+///   togmic://mute
+///   togmic://unmute
+///   togmic://profile/<id>
+///   togmic://show
+///
+/// `mute` and `unmute` take `?sound=true` to play the feedback sound, like `--sound` on
+/// the command line. A trailing slash, a fragment and other query parameters are
+/// tolerated; anything else is rejected.
+pub fn parse_url(url: &str) -> Result<ExternalAction, String> {
+    let rest = url
+        .get(..URL_SCHEME.len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case(URL_SCHEME))
+        .map(|_| &url[URL_SCHEME.len()..])
+        .ok_or_else(|| format!("Not a {} URL", URL_SCHEME))?;
+
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    match segments.as_slice() {
+        [command] => match command.to_ascii_lowercase().as_str() {
+            "toggle" => Ok(ExternalAction::Toggle),
+            "mute" => Ok(ExternalAction::SetMute {
+                muted: true,
+                sound: sound_param(query)?,
+            }),
+            "unmute" => Ok(ExternalAction::SetMute {
+                muted: false,
+                sound: sound_param(query)?,
+            }),
+            "show" => Ok(ExternalAction::Show),
+            "" => Err("Missing command".to_string()),
+            other => Err(format!("Unknown command: '{}'", other)),
+        },
+        [command, id] if command.eq_ignore_ascii_case("profile") => {
+            let id = percent_decode_str(id)
+                .decode_utf8()
+                .map_err(|_| "Profile id is not valid UTF-8".to_string())?;
+            if id.trim().is_empty() {
+                return Err("Missing profile id".to_string());
+            }
+            Ok(ExternalAction::Profile {
+                id: id.into_owned(),
+            })
+        }
+        _ => Err(format!("Malformed URL: '{}'", url)),
+    }
+}
+
+/// Value of the `sound` query parameter; `false` if it isn't given.
+fn sound_param(query: &str) -> Result<bool, String> {
+    let mut sound = false;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, "true"));
+        if key != "sound" {
+            continue;
+        }
+        sound = match value.to_ascii_lowercase().as_str() {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => return Err(format!("Invalid value for sound: '{}'", value)),
+        };
+    }
+    Ok(sound)
+}
+
+/// A request/response command from a scripting surface (control API, local IPC, ...).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "camelCase")]
+pub enum ControlCommand {
+    Toggle,
+    Set { muted: bool },
+    GetState,
+    ListProfiles,
+    SetProfile { id: String },
+}
+
+/// Run a control command and return its JSON result. Shared by every external control
+/// surface so they all behave identically; `source` is reported in the
+/// `external-command` event.
+pub fn dispatch(app: &AppHandle, source: &str, command: ControlCommand) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+    match command {
+        ControlCommand::Toggle => {
+            crate::perform_external_action(app, source, &ExternalAction::Toggle)?;
+            Ok(json!({ "muted": state.mute_state.is_muted() }))
+        }
+        ControlCommand::Set { muted } => {
+            crate::perform_external_action(
+                app,
+                source,
+                &ExternalAction::SetMute {
+                    muted,
+                    sound: false,
+                },
+            )?;
+            Ok(json!({ "muted": muted }))
+        }
+        ControlCommand::GetState => Ok(json!({
+            "muted": state.mute_state.is_muted(),
+            "state": state.mute_state.get(),
+            "profile": state.current_profile.lock_safe().clone(),
+        })),
+        ControlCommand::ListProfiles => Ok(json!({
+            "profiles": crate::load_profiles(app),
+            "activeProfileId": state.current_profile.lock_safe().as_ref().map(|p| p.id.clone()),
+        })),
+        ControlCommand::SetProfile { id } => {
+            crate::perform_external_action(app, source, &ExternalAction::Profile { id })?;
+            Ok(json!({ "profile": state.current_profile.lock_safe().clone() }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_mute(muted: bool, sound: bool) -> ExternalAction {
+        ExternalAction::SetMute { muted, sound }
+    }
+
+    #[test]
+    fn parses_every_command() {
+        assert_eq!(parse_url("togmic://toggle"), Ok(ExternalAction::Toggle));
+        assert_eq!(parse_url("togmic://mute"), Ok(set_mute(true, false)));
+        assert_eq!(parse_url("togmic://unmute"), Ok(set_mute(false, false)));
+        assert_eq!(parse_url("togmic://show"), Ok(ExternalAction::Show));
+        assert_eq!(
+            parse_url("togmic://profile/work"),
+            Ok(ExternalAction::Profile {
+                id: "work".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn tolerates_case_slashes_and_fragments() {
+        assert_eq!(parse_url("TOGMIC://Toggle/"), Ok(ExternalAction::Toggle));
+        assert_eq!(parse_url("togmic://mute#top"), Ok(set_mute(true, false)));
+        assert_eq!(
+            parse_url("togmic://profile/My%20Profile/"),
+            Ok(ExternalAction::Profile {
+                id: "My Profile".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_other_schemes_and_unknown_commands() {
+        assert!(parse_url("https://toggle").is_err());
+        assert!(parse_url("togmic:toggle").is_err());
+        assert!(parse_url("togmic://").is_err());
+        assert!(parse_url("togmic://explode").is_err());
+        assert!(parse_url("togmic://toggle/now").is_err());
+        assert!(parse_url("togmic://profile").is_err());
+        assert!(parse_url("togmic://profile/%20").is_err());
+        assert!(parse_url("togmic://profile/%FF").is_err());
+        assert!(parse_url("togmic://profile/a/b").is_err());
+    }
+
+    #[test]
+    fn reads_the_sound_parameter() {
+        assert_eq!(
+            parse_url("togmic://mute?sound=true"),
+            Ok(set_mute(true, true))
+        );
+        assert_eq!(
+            parse_url("togmic://unmute?sound=1"),
+            Ok(set_mute(false, true))
+        );
+        assert_eq!(parse_url("togmic://mute?sound"), Ok(set_mute(true, true)));
+        assert_eq!(
+            parse_url("togmic://mute?sound=0&source=deck"),
+            Ok(set_mute(true, false))
+        );
+        // Other parameters are tolerated
+        assert_eq!(
+            parse_url("togmic://toggle?from=deck"),
+            Ok(ExternalAction::Toggle)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_sound_values() {
+        assert!(parse_url("togmic://mute?sound=maybe").is_err());
+        assert!(parse_url("togmic://unmute?sound=").is_err());
+    }
+
+    #[test]
+    fn decodes_control_commands() {
+        let decode = |json: &str| serde_json::from_str::<ControlCommand>(json);
+        assert!(matches!(
+            decode(r#"{"cmd":"toggle"}"#),
+            Ok(ControlCommand::Toggle)
+        ));
+        assert!(matches!(
+            decode(r#"{"cmd":"set","muted":true}"#),
+            Ok(ControlCommand::Set { muted: true })
+        ));
+        assert!(matches!(
+            decode(r#"{"cmd":"setProfile","id":"work"}"#),
+            Ok(ControlCommand::SetProfile { id }) if id == "work"
+        ));
+        assert!(decode(r#"{"cmd":"set"}"#).is_err());
+        assert!(decode(r#"{"cmd":"set","muted":"yes"}"#).is_err());
+        assert!(decode(r#"{"cmd":"reboot"}"#).is_err());
+        assert!(decode(r#"{"muted":true}"#).is_err());
+    }
+}
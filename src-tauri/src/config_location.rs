@@ -0,0 +1,236 @@
+//! Where the config store lives.
+//!
+//! In priority order: `--config <path>` on the command line, the `TOGMIC_CONFIG`
+//! environment variable, then `config.json` in the app data dir. The choice is made once
+//! at startup and kept in `AppState::config_location`; everything that opens the store
+//! goes through `crate::config_store`.
+//!
+//! An override also gives the app its own identity (`instance_suffix`), so the
+//! single-instance check only finds instances using the same config and two instances
+//! with different configs can run side by side. Their data and log directories are
+//! separate too. The local IPC endpoint and the control API port are not: the instance
+//! that claims them first keeps them, as the next one finds them in use (see
+//! `ipc::create_listener`) and runs without.
+//!
+//! The location is resolved before the log file is open, so it is logged from setup with
+//! `log_override`.
+//!
+//! An override that can't be used stops the app at startup rather than silently falling
+//! back to the default config.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_ENV: &str = "TOGMIC_CONFIG";
+pub const CONFIG_FLAG: &str = "--config";
+
+/// Store file used without an override, relative to the app data dir.
+pub const DEFAULT_STORE: &str = "config.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigSource {
+    Default,
+    Cli,
+    Env,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigLocation {
+    /// Path for the store plugin: `DEFAULT_STORE`, or the absolute override.
+    pub store: PathBuf,
+    pub source: ConfigSource,
+}
+
+impl Default for ConfigLocation {
+    fn default() -> Self {
+        Self {
+            store: PathBuf::from(DEFAULT_STORE),
+            source: ConfigSource::Default,
+        }
+    }
+}
+
+impl ConfigLocation {
+    /// Resolve the location from the command line (without the executable) and the
+    /// environment.
+    pub fn resolve(args: &[String]) -> Result<Self, String> {
+        let (path, source) = match flag_value(args)? {
+            Some(path) => (path, ConfigSource::Cli),
+            None => match std::env::var(CONFIG_ENV) {
+                Ok(path) if !path.trim().is_empty() => (path, ConfigSource::Env),
+                _ => return Ok(Self::default()),
+            },
+        };
+        let store = validate(Path::new(path.trim())).map_err(|e| {
+            let origin = match source {
+                ConfigSource::Cli => CONFIG_FLAG,
+                _ => CONFIG_ENV,
+            };
+            format!("Invalid config path '{}' from {}: {}", path, origin, e)
+        })?;
+        Ok(Self { store, source })
+    }
+
+    /// Log an override and the identifier it gives the app, once the log file is open.
+    pub fn log_override(&self, identifier: &str) {
+        if self.source != ConfigSource::Default {
+            log::info!(
+                "[config] using {} ({:?}), running as {}",
+                self.store.display(),
+                self.source,
+                identifier
+            );
+        }
+    }
+
+    /// Appended to the app identifier while an override is active: a short hash of the
+    /// config path.
+    pub fn instance_suffix(&self) -> Option<String> {
+        if self.source == ConfigSource::Default {
+            return None;
+        }
+        let digest = Sha256::digest(self.store.to_string_lossy().as_bytes());
+        Some(format!(
+            "config-{:02x}{:02x}{:02x}{:02x}",
+            digest[0], digest[1], digest[2], digest[3]
+        ))
+    }
+}
+
+/// Value of `--config <path>` or `--config=<path>`.
+fn flag_value(args: &[String]) -> Result<Option<String>, String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == CONFIG_FLAG {
+            return iter
+                .next()
+                .map(|path| Some(path.clone()))
+                .ok_or_else(|| format!("{} requires a path", CONFIG_FLAG));
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Ok(Some(path.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Make `path` absolute and check it can hold the store: not a directory, and in a
+/// directory that exists or can be created. A missing file is fine; it is created on the
+/// first save.
+fn validate(path: &Path) -> Result<PathBuf, String> {
+    if path.as_os_str().is_empty() {
+        return Err("the path is empty".to_string());
+    }
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|e| format!("can't resolve the working directory: {}", e))?
+            .join(path)
+    };
+    if path.is_dir() {
+        return Err("it is a directory".to_string());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("can't create {}: {}", parent.display(), e))?;
+    }
+    if path.exists() {
+        std::fs::File::open(&path).map_err(|e| format!("can't read it: {}", e))?;
+    }
+    Ok(path)
+}
+
+/// Tell the user why the app won't start. The log file isn't open yet, so this goes to
+/// stderr and, on Windows, a message box.
+pub fn report_fatal(message: &str) {
+    eprintln!("[config] {}", message);
+    #[cfg(target_os = "windows")]
+    {
+        use windows::core::PCWSTR;
+        use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+        let text: Vec<u16> = message.encode_utf16().chain(std::iter::once(0)).collect();
+        let caption: Vec<u16> = "TogMic\0".encode_utf16().collect();
+        unsafe {
+            MessageBoxW(
+                None,
+                PCWSTR(text.as_ptr()),
+                PCWSTR(caption.as_ptr()),
+                MB_OK | MB_ICONERROR,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_the_config_flag() {
+        assert_eq!(
+            flag_value(&args(&["--hidden", "--config", "a.json"])),
+            Ok(Some("a.json".to_string()))
+        );
+        assert_eq!(
+            flag_value(&args(&["--config=b.json", "--config", "a.json"])),
+            Ok(Some("b.json".to_string()))
+        );
+        assert_eq!(flag_value(&args(&["--hidden"])), Ok(None));
+        assert_eq!(
+            flag_value(&args(&["--config"])),
+            Err("--config requires a path".to_string())
+        );
+    }
+
+    #[test]
+    fn validates_the_path() {
+        let dir = temp_dir("config-location");
+        let file = dir.join("nested").join("config.json");
+        assert_eq!(validate(&file), Ok(file.clone()));
+        assert!(file.parent().unwrap().is_dir());
+
+        assert_eq!(
+            validate(Path::new("")),
+            Err("the path is empty".to_string())
+        );
+        assert_eq!(validate(&dir), Err("it is a directory".to_string()));
+
+        let relative = validate(Path::new("togmic-relative.json")).unwrap();
+        assert!(relative.is_absolute());
+        assert!(relative.ends_with("togmic-relative.json"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn only_overrides_get_an_instance_suffix() {
+        assert_eq!(ConfigLocation::default().instance_suffix(), None);
+
+        let location = |path: &str, source| ConfigLocation {
+            store: PathBuf::from(path),
+            source,
+        };
+        let suffix = location("/configs/a.json", ConfigSource::Cli)
+            .instance_suffix()
+            .unwrap();
+        assert!(suffix.starts_with("config-"));
+        assert_eq!(suffix.len(), "config-".len() + 8);
+        // The same file gives the same identity whichever way it was passed
+        assert_eq!(
+            location("/configs/a.json", ConfigSource::Env).instance_suffix(),
+            Some(suffix.clone())
+        );
+        assert_ne!(
+            location("/configs/b.json", ConfigSource::Cli).instance_suffix(),
+            Some(suffix)
+        );
+    }
+}
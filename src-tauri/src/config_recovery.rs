@@ -0,0 +1,307 @@
+//! Recovering from a config store that can't be parsed.
+//!
+//! The store plugin treats an unreadable file like a missing one, so a broken config used
+//! to start the app as a fresh install, and the first save overwrote what was left of the
+//! user's profiles. `check` runs before anything opens the store:
+//!
+//! - A config that parses is copied to `<name>.backup.json`, the last known good state.
+//! - One that doesn't is renamed to `<name>.invalid-<timestamp>.json` and kept for
+//!   inspection. The backup takes its place if it parses; otherwise the app starts with
+//!   defaults.
+//!
+//! Every case but the clean one is kept as a `ConfigLoadProblem` (see
+//! `get_config_load_problem`) and emitted as `config-load-problem`. If the broken file
+//! can't be moved aside, it stays where the store writes, and `guard_save` refuses to
+//! overwrite it until the user acknowledges with `force`.
+
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::Emitter;
+use tauri_plugin_store::StoreBuilder;
+
+pub const CONFIG_LOAD_PROBLEM: &str = "config-load-problem";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Recovery {
+    /// The backup replaced the broken config.
+    RestoredBackup,
+    /// No usable backup; the app starts with defaults.
+    Defaults,
+    /// The broken config couldn't be moved aside and is still in place. Saving it needs
+    /// `force`.
+    Unmoved,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigLoadProblem {
+    pub recovery: Recovery,
+    /// Why the config couldn't be parsed.
+    pub error: String,
+    pub config_file: PathBuf,
+    /// Where the broken file was kept; the config file itself if it couldn't be moved.
+    pub invalid_file: PathBuf,
+    /// The backup that was restored.
+    pub backup_file: Option<PathBuf>,
+    /// Unix time in milliseconds.
+    pub detected_at: u64,
+}
+
+static PROBLEM: Lazy<Mutex<Option<ConfigLoadProblem>>> = Lazy::new(|| Mutex::new(None));
+
+/// Set while an unparsed config sits where the store writes.
+static PROTECTED: AtomicBool = AtomicBool::new(false);
+
+/// Check the config at `config_file` and recover from a broken one; see the module docs.
+/// Call before the store is first opened.
+pub fn check(app: &AppHandle, config_file: &Path) {
+    let Some(problem) = recover(config_file) else {
+        return;
+    };
+    if problem.recovery == Recovery::Unmoved {
+        // Opened once here; the plugin would otherwise save the defaults over it shortly
+        // after the first change
+        if let Err(e) = StoreBuilder::new(app, crate::config_store(app))
+            .disable_auto_save()
+            .build()
+        {
+            log::warn!("[config] failed to open the store: {}", e);
+        }
+    }
+    *PROBLEM.lock_safe() = Some(problem.clone());
+    let _ = app.emit(CONFIG_LOAD_PROBLEM, problem);
+}
+
+/// The file side of `check`: back up a good config, move a broken one aside and restore
+/// the backup. `None` if there was nothing to recover from.
+fn recover(config_file: &Path) -> Option<ConfigLoadProblem> {
+    let error = match parse(config_file) {
+        Ok(true) => {
+            keep_backup(config_file);
+            return None;
+        }
+        // Nothing saved yet
+        Ok(false) => return None,
+        Err(e) => e,
+    };
+    log::error!(
+        "[config] {} can't be parsed: {}",
+        config_file.display(),
+        error
+    );
+
+    let detected_at = crate::events::now_millis();
+    let invalid_file = invalid_path(config_file, detected_at);
+    match std::fs::rename(config_file, &invalid_file) {
+        Ok(()) => {
+            log::warn!(
+                "[config] kept the broken config as {}",
+                invalid_file.display()
+            );
+            let backup_file = restore_backup(config_file);
+            Some(ConfigLoadProblem {
+                recovery: if backup_file.is_some() {
+                    Recovery::RestoredBackup
+                } else {
+                    Recovery::Defaults
+                },
+                error,
+                config_file: config_file.to_path_buf(),
+                invalid_file,
+                backup_file,
+                detected_at,
+            })
+        }
+        Err(e) => {
+            log::error!(
+                "[config] failed to move the broken config aside, not saving over it: {}",
+                e
+            );
+            PROTECTED.store(true, Ordering::SeqCst);
+            Some(ConfigLoadProblem {
+                recovery: Recovery::Unmoved,
+                error,
+                config_file: config_file.to_path_buf(),
+                invalid_file: config_file.to_path_buf(),
+                backup_file: None,
+                detected_at,
+            })
+        }
+    }
+}
+
+/// The problem found at startup, if any.
+pub fn problem() -> Option<ConfigLoadProblem> {
+    PROBLEM.lock_safe().clone()
+}
+
+/// Refuse to save while the unparsed config is still in place, unless `force`. Saving with
+/// `force` acknowledges the problem and lifts the guard.
+pub fn guard_save(force: bool) -> Result<(), String> {
+    if !PROTECTED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    if !force {
+        return Err(
+            "The config file couldn't be read and hasn't been backed up; saving would overwrite it"
+                .to_string(),
+        );
+    }
+    log::warn!("[config] overwriting the unparsed config as acknowledged");
+    PROTECTED.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Whether `path` holds a JSON object; `Ok(false)` if it doesn't exist.
+fn parse(path: &Path) -> Result<bool, String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.to_string()),
+    };
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(serde_json::Value::Object(_)) => Ok(true),
+        Ok(_) => Err("expected a JSON object".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `<dir>/<stem><suffix>`, e.g. `config.backup.json` next to `config.json`.
+fn sibling(config_file: &Path, suffix: &str) -> PathBuf {
+    let stem = config_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "config".to_string());
+    config_file.with_file_name(format!("{}{}", stem, suffix))
+}
+
+fn backup_path(config_file: &Path) -> PathBuf {
+    sibling(config_file, ".backup.json")
+}
+
+fn invalid_path(config_file: &Path, detected_at: u64) -> PathBuf {
+    sibling(config_file, &format!(".invalid-{}.json", detected_at))
+}
+
+fn keep_backup(config_file: &Path) {
+    if let Err(e) = std::fs::copy(config_file, backup_path(config_file)) {
+        log::warn!("[config] failed to back up the config: {}", e);
+    }
+}
+
+/// Put the backup in place of the config if it parses. Returns its path.
+fn restore_backup(config_file: &Path) -> Option<PathBuf> {
+    let backup = backup_path(config_file);
+    match parse(&backup) {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(e) => {
+            log::warn!("[config] backup {} is unusable: {}", backup.display(), e);
+            return None;
+        }
+    }
+    match std::fs::copy(&backup, config_file) {
+        Ok(_) => {
+            log::info!("[config] restored {}", backup.display());
+            Some(backup)
+        }
+        Err(e) => {
+            log::warn!("[config] failed to restore {}: {}", backup.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    /// A config dir with `config.json` (if given) and its backup (if given).
+    fn setup(name: &str, config: Option<&str>, backup: Option<&str>) -> PathBuf {
+        let config_file = temp_dir(name).join("config.json");
+        if let Some(config) = config {
+            std::fs::write(&config_file, config).unwrap();
+        }
+        if let Some(backup) = backup {
+            std::fs::write(backup_path(&config_file), backup).unwrap();
+        }
+        config_file
+    }
+
+    fn cleanup(config_file: &Path) {
+        let _ = std::fs::remove_dir_all(config_file.parent().unwrap());
+    }
+
+    #[test]
+    fn a_valid_config_is_backed_up() {
+        let config_file = setup("recovery-valid", Some(r#"{"profiles":[]}"#), None);
+        assert!(recover(&config_file).is_none());
+        assert_eq!(read(&backup_path(&config_file)), r#"{"profiles":[]}"#);
+        cleanup(&config_file);
+    }
+
+    #[test]
+    fn nothing_to_do_without_a_config() {
+        let config_file = setup("recovery-missing", None, None);
+        assert!(recover(&config_file).is_none());
+        assert!(!backup_path(&config_file).exists());
+        cleanup(&config_file);
+    }
+
+    #[test]
+    fn a_broken_config_is_replaced_by_the_backup() {
+        let config_file = setup(
+            "recovery-restore",
+            Some(r#"{"profiles":"#),
+            Some(r#"{"profiles":[]}"#),
+        );
+        let problem = recover(&config_file).unwrap();
+        assert_eq!(problem.recovery, Recovery::RestoredBackup);
+        assert_eq!(problem.backup_file, Some(backup_path(&config_file)));
+        assert_eq!(read(&config_file), r#"{"profiles":[]}"#);
+
+        let invalid = problem.invalid_file;
+        let name = invalid.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("config.invalid-") && name.ends_with(".json"));
+        assert_eq!(read(&invalid), r#"{"profiles":"#);
+        cleanup(&config_file);
+    }
+
+    #[test]
+    fn defaults_are_used_without_a_usable_backup() {
+        let config_file = setup("recovery-defaults", Some("[]"), None);
+        let problem = recover(&config_file).unwrap();
+        assert_eq!(problem.recovery, Recovery::Defaults);
+        assert_eq!(problem.error, "expected a JSON object");
+        assert!(!config_file.exists());
+        assert_eq!(read(&problem.invalid_file), "[]");
+        cleanup(&config_file);
+
+        let config_file = setup("recovery-bad-backup", Some("{"), Some("{"));
+        let problem = recover(&config_file).unwrap();
+        assert_eq!(problem.recovery, Recovery::Defaults);
+        assert_eq!(problem.backup_file, None);
+        assert!(!config_file.exists());
+        cleanup(&config_file);
+    }
+
+    #[test]
+    fn saving_over_an_unmoved_config_needs_force() {
+        assert_eq!(guard_save(false), Ok(()));
+        PROTECTED.store(true, Ordering::SeqCst);
+        assert!(guard_save(false).is_err());
+        assert!(PROTECTED.load(Ordering::SeqCst));
+        assert_eq!(guard_save(true), Ok(()));
+        assert_eq!(guard_save(false), Ok(()));
+    }
+}
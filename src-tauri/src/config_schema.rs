@@ -0,0 +1,150 @@
+//! JSON Schema of the config store, for editors validating and completing it by hand.
+//!
+//! `Config` mirrors the store's top-level keys and is only used to derive the schema; the
+//! app keeps reading and writing the keys one by one. `write_if_stale` puts the schema
+//! next to the config as `config.schema.json` at startup, when it's missing or was written
+//! by another version, and freshly created configs point at it with `$schema`.
+
+use crate::device_aliases::DeviceAlias;
+use crate::window_state::WindowState;
+use crate::{AppSettings, HotkeyProfile};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const SCHEMA_FILE: &str = "config.schema.json";
+/// Value of the `$schema` key in the config store.
+pub const SCHEMA_REF: &str = "./config.schema.json";
+/// Root key holding the app version the schema was generated by.
+const VERSION_KEY: &str = "x-togmic-version";
+
+#[allow(dead_code)]
+#[derive(Deserialize, JsonSchema)]
+#[cfg_attr(test, derive(serde::Serialize, Default))]
+#[serde(rename_all = "camelCase")]
+#[schemars(title = "TogMic config")]
+struct Config {
+    #[serde(rename = "$schema")]
+    schema: Option<String>,
+    /// Layout version, bumped by config migrations.
+    config_version: Option<u64>,
+    #[serde(default)]
+    profiles: Vec<HotkeyProfile>,
+    active_profile_id: Option<String>,
+    app_settings: Option<AppSettings>,
+    /// Last selected UI language.
+    locale: Option<String>,
+    /// Token for the local HTTP API.
+    api_token: Option<String>,
+    /// Keeps this install's MQTT client id apart from others on the same base topic.
+    mqtt_client_suffix: Option<String>,
+    /// Custom names and icons, by device ID.
+    #[serde(default)]
+    device_aliases: HashMap<String, DeviceAlias>,
+    #[serde(default)]
+    hidden_device_ids: Vec<String>,
+    /// Input volume to restore on unmute, by device ID.
+    #[serde(default)]
+    remembered_volumes: HashMap<String, f32>,
+    window_state: Option<WindowState>,
+    /// Seconds since the Unix epoch.
+    last_update_check: Option<u64>,
+}
+
+pub fn schema() -> serde_json::Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+    if let Some(root) = schema.as_object_mut() {
+        root.insert(VERSION_KEY.to_string(), env!("CARGO_PKG_VERSION").into());
+    }
+    schema
+}
+
+/// Write `config.schema.json` next to `config_file` unless it's already there for this
+/// version.
+pub fn write_if_stale(config_file: &Path) {
+    let Some(dir) = config_file.parent() else {
+        return;
+    };
+    let path = dir.join(SCHEMA_FILE);
+    let current = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .is_some_and(|existing| existing[VERSION_KEY] == env!("CARGO_PKG_VERSION"));
+    if current {
+        return;
+    }
+    let written = serde_json::to_string_pretty(&schema())
+        .map_err(|e| e.to_string())
+        .and_then(|text| {
+            std::fs::create_dir_all(dir)
+                .and_then(|()| std::fs::write(&path, text))
+                .map_err(|e| e.to_string())
+        });
+    match written {
+        Ok(()) => log::info!("[config] wrote {}", path.display()),
+        Err(e) => log::warn!("[config] failed to write {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::profile;
+    use crate::window_state::WindowState;
+    use serde_json::json;
+
+    fn validate(config: &serde_json::Value) -> Result<(), Vec<String>> {
+        let schema = jsonschema::JSONSchema::compile(&schema()).expect("valid schema");
+        let result = schema.validate(config).map_err(|errors| {
+            errors
+                .map(|e| format!("{} at {}", e, e.instance_path))
+                .collect()
+        });
+        result
+    }
+
+    #[test]
+    fn the_default_config_matches_the_schema() {
+        let config = serde_json::to_value(Config::default()).unwrap();
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn a_filled_in_config_matches_the_schema() {
+        let config = Config {
+            schema: Some(SCHEMA_REF.to_string()),
+            config_version: Some(2),
+            profiles: vec![profile(json!({ "deviceIds": ["all-mics"] }))],
+            active_profile_id: Some("p1".to_string()),
+            app_settings: Some(AppSettings::default()),
+            locale: Some("de".to_string()),
+            device_aliases: HashMap::from([(
+                "mic-1".to_string(),
+                DeviceAlias {
+                    alias: "Desk".to_string(),
+                    system_name: "USB Microphone".to_string(),
+                    last_seen: 0,
+                },
+            )]),
+            remembered_volumes: HashMap::from([("mic-1".to_string(), 0.8)]),
+            window_state: Some(WindowState {
+                x: -10,
+                y: 20,
+                width: 800,
+                height: 600,
+                maximized: false,
+                monitor: None,
+            }),
+            ..Default::default()
+        };
+        let config = serde_json::to_value(config).unwrap();
+        assert_eq!(validate(&config), Ok(()));
+    }
+
+    #[test]
+    fn the_schema_rejects_a_mistyped_config() {
+        assert!(validate(&json!({ "profiles": "none" })).is_err());
+        assert!(validate(&json!({ "appSettings": { "autostart": "yes" } })).is_err());
+    }
+}
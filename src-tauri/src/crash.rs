@@ -0,0 +1,193 @@
+//! Crash reports and failing safe on panics.
+//!
+//! `install_hook` runs right after the logger is up and puts a hook in front of the
+//! default one. Every panic is appended with its backtrace to `crash.log` in the log
+//! directory (once `init` has resolved it), then logged; the report comes first in case
+//! the panic came from inside the logger. A panic on the main thread ends the
+//! process, so the hook first runs `exit_restore::fail_safe` to leave the active profile's
+//! devices unmuted or as they were at startup. Background threads are restarted by
+//! `sync_watchdog` instead.
+//!
+//! At startup `init` moves a `crash.log` left by an earlier run to `crash.previous.log`,
+//! keeps its path for `get_previous_crash` and emits it as `previous-crash`.
+
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::{Lazy, OnceCell};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::io::Write;
+use std::panic::{self, Location, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::path::BaseDirectory;
+use tauri::{Emitter, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+pub const PREVIOUS_CRASH: &str = "previous-crash";
+const CRASH_FILE: &str = "crash.log";
+const PREVIOUS_CRASH_FILE: &str = "crash.previous.log";
+
+/// For the fail-safe; set by `init`.
+static APP: OnceCell<AppHandle> = OnceCell::new();
+static CRASH_PATH: OnceCell<PathBuf> = OnceCell::new();
+/// Report left by the last run, found by `init`.
+static PREVIOUS: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Report panics and fail safe on the main thread. Call right after `logging::init`.
+pub fn install_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let thread_name = thread.name().unwrap_or("unnamed");
+        let message = panic_message(info);
+        write_report(thread_name, &message, &Backtrace::force_capture());
+        log::error!("[crash] panic on thread '{}': {}", thread_name, message);
+
+        if thread_name == "main" {
+            if let Some(app) = APP.get() {
+                crate::exit_restore::fail_safe(app);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// Resolve the crash file and pick up a report from the last run.
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    let Ok(path) = app.path().resolve(CRASH_FILE, BaseDirectory::AppLog) else {
+        return;
+    };
+    if let Some(previous) = take_previous(&path) {
+        log::warn!("[crash] the last run panicked, see {}", previous.display());
+        *PREVIOUS.lock_safe() = Some(previous.clone());
+        let _ = app.emit(PREVIOUS_CRASH, previous);
+    }
+    let _ = CRASH_PATH.set(path);
+}
+
+/// Move a report at `path` left by the last run aside, so this run starts a new one.
+/// Returns where it went.
+fn take_previous(path: &Path) -> Option<PathBuf> {
+    if !path.exists() {
+        return None;
+    }
+    let previous = path.with_file_name(PREVIOUS_CRASH_FILE);
+    match std::fs::rename(path, &previous) {
+        Ok(()) => Some(previous),
+        Err(e) => {
+            log::warn!("[crash] failed to move the last crash report: {}", e);
+            None
+        }
+    }
+}
+
+/// The report left by the last run, if it crashed.
+pub fn previous() -> Option<PathBuf> {
+    PREVIOUS.lock_safe().clone()
+}
+
+/// Open the last run's crash report in the default text editor.
+pub fn open_previous(app: &AppHandle) -> Result<(), String> {
+    let path = previous().ok_or_else(|| "No crash report".to_string())?;
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open crash report: {}", e))
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    payload_message(info.payload(), info.location())
+}
+
+fn payload_message(payload: &(dyn Any + Send), location: Option<&Location>) -> String {
+    let payload = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string());
+    match location {
+        Some(location) => format!("{} at {}", payload, location),
+        None => payload,
+    }
+}
+
+/// Append a report to the crash file. Synchronous, since the process may be about to end
+/// before the log writer gets to it.
+fn write_report(thread_name: &str, message: &str, backtrace: &Backtrace) {
+    if let Some(path) = CRASH_PATH.get() {
+        append_report(path, thread_name, message, backtrace);
+    }
+}
+
+fn append_report(path: &Path, thread_name: &str, message: &str, backtrace: &Backtrace) {
+    let report = format!(
+        "{} TogMic {} on {}\nthread '{}' panicked: {}\n\n{}\n\n",
+        crate::logging::format_timestamp(SystemTime::now()),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        thread_name,
+        message,
+        backtrace
+    );
+    let written = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(report.as_bytes()));
+    if let Err(e) = written {
+        eprintln!("[crash] failed to write {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn describes_the_payload_and_location() {
+        let location = Location::caller();
+        assert_eq!(
+            payload_message(&"boom", Some(location)),
+            format!("boom at {}", location)
+        );
+        assert_eq!(payload_message(&"boom".to_string(), None), "boom");
+        assert_eq!(payload_message(&42, None), "unknown panic payload");
+    }
+
+    #[test]
+    fn reports_are_appended() {
+        let dir = temp_dir("crash-append");
+        let path = dir.join(CRASH_FILE);
+        append_report(&path, "main", "first", &Backtrace::disabled());
+        append_report(&path, "worker", "second", &Backtrace::disabled());
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(log.contains("thread 'main' panicked: first"));
+        assert!(log.contains("thread 'worker' panicked: second"));
+        assert!(log.find("first") < log.find("second"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn the_last_report_is_moved_aside() {
+        let dir = temp_dir("crash-previous");
+        let path = dir.join(CRASH_FILE);
+        assert_eq!(take_previous(&path), None);
+
+        append_report(&path, "main", "last run", &Backtrace::disabled());
+        let previous = take_previous(&path).unwrap();
+        assert_eq!(previous, dir.join(PREVIOUS_CRASH_FILE));
+        assert!(!path.exists());
+        assert!(std::fs::read_to_string(&previous)
+            .unwrap()
+            .contains("last run"));
+
+        // A newer crash replaces the one kept from before
+        append_report(&path, "main", "this run", &Backtrace::disabled());
+        take_previous(&path).unwrap();
+        let kept = std::fs::read_to_string(&previous).unwrap();
+        assert!(kept.contains("this run") && !kept.contains("last run"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,193 @@
+//! User-defined names for audio devices.
+//!
+//! Aliases are persisted under `deviceAliases` in the config store, keyed by device id.
+//! Each one remembers the system name of its device, so when Windows hands a device a
+//! new id (another USB port, a driver update) the alias follows it by name, like
+//! `session_change::remap_by_name` does for profiles, and moves to the new id.
+//!
+//! `apply` runs on every enumeration that goes through `AppState::cache_devices`: an
+//! aliased device gets the alias as `name` and keeps the original in `system_name`, so the
+//! device list, the `devices-changed` event and notifications all show the alias. Aliases
+//! also record when their device was last seen, for `prune`.
+
+use crate::audio::AudioDevice;
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri_plugin_store::StoreExt;
+
+const STORE_KEY: &str = "deviceAliases";
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// `prune` without an age removes aliases of devices not seen for this long.
+pub const DEFAULT_PRUNE_DAYS: u32 = 90;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAlias {
+    pub alias: String,
+    /// The device's name as the system reports it.
+    pub system_name: String,
+    /// Unix time in seconds; persisted at most once a day.
+    #[serde(default)]
+    pub last_seen: u64,
+}
+
+/// Device id → alias.
+static ALIASES: Lazy<Mutex<HashMap<String, DeviceAlias>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// For persisting from enumerations, which have no handle of their own.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+
+/// Load the persisted aliases.
+pub fn init(app: &AppHandle) {
+    let saved = app
+        .store(crate::config_store(app))
+        .ok()
+        .and_then(|store| store.get(STORE_KEY))
+        .and_then(|v| serde_json::from_value::<HashMap<String, DeviceAlias>>(v).ok())
+        .unwrap_or_default();
+    if !saved.is_empty() {
+        log::info!("[aliases] {} device alias(es)", saved.len());
+    }
+    *ALIASES.lock_safe() = saved;
+    *APP.lock_safe() = Some(app.clone());
+}
+
+/// Show the aliases in an enumerated device list; see the module docs. Devices that were
+/// aliased before keep their system name, so applying twice changes nothing.
+pub fn apply(mut devices: Vec<AudioDevice>) -> Vec<AudioDevice> {
+    let now = now_secs();
+    let mut changed = false;
+    {
+        let mut aliases = ALIASES.lock_safe();
+        let ids: Vec<String> = devices.iter().map(|d| d.id.clone()).collect();
+        for device in devices.iter_mut() {
+            let system_name = device.system_name().to_string();
+            if !aliases.contains_key(&device.id) {
+                // A device that got a new id: same name, and its old id is gone
+                let moved = aliases
+                    .iter()
+                    .find(|(id, alias)| alias.system_name == system_name && !ids.contains(id))
+                    .map(|(id, _)| id.clone());
+                let Some(old_id) = moved else {
+                    device.system_name = None;
+                    device.name = system_name;
+                    continue;
+                };
+                log::info!(
+                    "[aliases] '{}' moved from {} to {}",
+                    system_name,
+                    old_id,
+                    device.id
+                );
+                if let Some(alias) = aliases.remove(&old_id) {
+                    aliases.insert(device.id.clone(), alias);
+                    changed = true;
+                }
+            }
+            let Some(alias) = aliases.get_mut(&device.id) else {
+                continue;
+            };
+            if alias.system_name != system_name {
+                alias.system_name = system_name.clone();
+                changed = true;
+            }
+            if now / DAY_SECS != alias.last_seen / DAY_SECS {
+                changed = true;
+            }
+            alias.last_seen = now;
+            device.name = alias.alias.clone();
+            device.system_name = Some(system_name);
+        }
+    }
+    if changed {
+        persist();
+    }
+    devices
+}
+
+/// Name `device_id` `alias`, or remove its alias if `alias` is blank. `system_name` is the
+/// device's current name as the system reports it.
+pub fn set(device_id: &str, system_name: &str, alias: &str) -> Result<(), String> {
+    let alias = alias.trim();
+    {
+        let mut aliases = ALIASES.lock_safe();
+        if alias.is_empty() {
+            if aliases.remove(device_id).is_none() {
+                return Ok(());
+            }
+            log::info!("[aliases] removed the alias of {}", device_id);
+        } else {
+            log::info!("[aliases] {} is now '{}'", device_id, alias);
+            aliases.insert(
+                device_id.to_string(),
+                DeviceAlias {
+                    alias: alias.to_string(),
+                    system_name: system_name.to_string(),
+                    last_seen: now_secs(),
+                },
+            );
+        }
+    }
+    persist_checked()
+}
+
+/// Remove the aliases of devices not seen for `max_age_days` that aren't in `present`.
+/// Returns the removed device ids.
+pub fn prune(present: &[AudioDevice], max_age_days: u32) -> Result<Vec<String>, String> {
+    let cutoff = now_secs().saturating_sub(u64::from(max_age_days) * DAY_SECS);
+    let removed: Vec<String> = {
+        let mut aliases = ALIASES.lock_safe();
+        let stale: Vec<String> = aliases
+            .iter()
+            .filter(|(id, alias)| alias.last_seen < cutoff && !present.iter().any(|d| d.id == **id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &stale {
+            aliases.remove(id);
+        }
+        stale
+    };
+    if !removed.is_empty() {
+        log::info!("[aliases] pruned {} alias(es)", removed.len());
+        persist_checked()?;
+    }
+    Ok(removed)
+}
+
+/// Every alias, keyed by device id.
+pub fn all() -> HashMap<String, DeviceAlias> {
+    ALIASES.lock_safe().clone()
+}
+
+fn persist() {
+    if let Err(e) = persist_checked() {
+        log::warn!("[aliases] failed to persist aliases: {}", e);
+    }
+}
+
+fn persist_checked() -> Result<(), String> {
+    let aliases = ALIASES.lock_safe().clone();
+    let Some(app) = APP.lock_safe().clone() else {
+        return Ok(());
+    };
+    let store = app
+        .store(crate::config_store(&app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    store.set(STORE_KEY, serde_json::json!(aliases));
+    crate::config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save device aliases: {}", e))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
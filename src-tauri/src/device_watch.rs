@@ -0,0 +1,333 @@
+//! Device list changes, and devices of the active profile disappearing and coming back.
+//!
+//! Every enumeration (polling loop, or the device notification callback on Windows) is
+//! diffed against the previous one. Any addition or removal emits `devices-changed` with
+//! the diff and the full list, both without hidden devices (see `hidden_devices`).
+//! Restricted to the devices the active profile resolves to, a lost device emits
+//! `profile-device-lost` and runs the configured `OnDeviceLost` action; a returning device
+//! gets the profile's current mute state applied.
+
+use crate::audio::{AudioController, AudioDevice};
+use crate::events::{self, EventKind};
+use crate::hard_mute::{self, MuteStrategy};
+use crate::hidden_devices;
+use crate::{AppHandle, AppState, HotkeyProfile, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnDeviceLost {
+    /// Only emit the event.
+    #[default]
+    None,
+    /// Mute every remaining input device so no other mic is live unnoticed.
+    MuteRemaining,
+    /// Show a system notification.
+    NotifyOnly,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileDeviceLostEvent {
+    device_id: String,
+    name: String,
+    action: OnDeviceLost,
+}
+
+/// Payload of `devices-changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DevicesChangedEvent {
+    added: Vec<AudioDevice>,
+    removed: Vec<AudioDevice>,
+    all: Arc<[AudioDevice]>,
+    /// A removed device is one the active profile uses.
+    profile_device_removed: bool,
+}
+
+/// Device list seen by the previous check; `None` until the first one.
+static LAST_DEVICES: Lazy<Mutex<Option<Arc<[AudioDevice]>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Devices of `devices` that `profile` covers, picked the same way `resolve_device_ids`
+/// does.
+fn profile_devices<'a>(
+    profile: &HotkeyProfile,
+    devices: &'a [AudioDevice],
+) -> Vec<&'a AudioDevice> {
+    if crate::profile_uses_all_devices(profile) {
+        devices
+            .iter()
+            .filter(|d| hidden_devices::expands_to(profile, &d.id))
+            .collect()
+    } else {
+        devices
+            .iter()
+            .filter(|d| profile.lists_device(&d.id))
+            .collect()
+    }
+}
+
+/// Profile devices that are in `previous` but not in `current` (lost), and the other way
+/// round (returned).
+fn diff_profile_devices(
+    profile: &HotkeyProfile,
+    previous: &[AudioDevice],
+    current: &[AudioDevice],
+) -> (Vec<AudioDevice>, Vec<AudioDevice>) {
+    let before = profile_devices(profile, previous);
+    let after = profile_devices(profile, current);
+    let lost = before
+        .iter()
+        .filter(|d| !after.iter().any(|a| a.id == d.id))
+        .map(|d| (*d).clone())
+        .collect();
+    let returned = after
+        .iter()
+        .filter(|d| !before.iter().any(|b| b.id == d.id))
+        .map(|d| (*d).clone())
+        .collect();
+    (lost, returned)
+}
+
+/// Enumerate devices with the shared controller, update the device cache and check them.
+/// For callers that only know that something changed.
+pub fn refresh(app: &AppHandle) {
+    let devices = {
+        let state = app.state::<AppState>();
+        let controller_lock = state.audio_controller.lock_safe();
+        match controller_lock
+            .as_ref()
+            .map(|c| c.enumerate_input_devices())
+        {
+            Some(Ok(devices)) => state.cache_devices(devices),
+            _ => return,
+        }
+    };
+    check(app, devices);
+}
+
+/// Devices of `a` that are not in `b`.
+fn missing_from(a: &[AudioDevice], b: &[AudioDevice]) -> Vec<AudioDevice> {
+    a.iter()
+        .filter(|d| !b.iter().any(|other| other.id == d.id))
+        .cloned()
+        .collect()
+}
+
+/// Compare `devices` with the previous list, emit `devices-changed` and act on lost or
+/// returned profile devices. Returns whether the list (or the default device) changed; the
+/// first call only records the baseline.
+pub fn check(app: &AppHandle, devices: Arc<[AudioDevice]>) -> bool {
+    let Some(previous) = LAST_DEVICES.lock_safe().replace(devices.clone()) else {
+        return false;
+    };
+    let visible = hidden_devices::visible(&devices);
+    let visible_before = hidden_devices::visible(&previous);
+    let added = missing_from(&visible, &visible_before);
+    let removed = missing_from(&visible_before, &visible);
+    // A new default device changes `isDefault` in the list, so it is reported too
+    let default_id =
+        |list: &[AudioDevice]| list.iter().find(|d| d.is_default).map(|d| d.id.clone());
+    let list_changed = !added.is_empty()
+        || !removed.is_empty()
+        || default_id(&visible_before) != default_id(&visible);
+
+    let state = app.state::<AppState>();
+    let profile = state.current_profile.lock_safe().clone();
+    // Hidden devices a profile lists still count as its devices
+    let (lost, returned) = profile
+        .as_ref()
+        .map(|profile| diff_profile_devices(profile, &previous, &devices))
+        .unwrap_or_default();
+    if !list_changed && lost.is_empty() && returned.is_empty() {
+        return false;
+    }
+    if !added.is_empty() {
+        crate::solo::enforce(&state, Some(&added));
+    }
+    if list_changed {
+        events::emit(
+            app,
+            EventKind::Device,
+            events::DEVICES_CHANGED,
+            DevicesChangedEvent {
+                added,
+                removed,
+                all: visible.into(),
+                profile_device_removed: !lost.is_empty(),
+            },
+        );
+    }
+
+    if !lost.is_empty() {
+        let action = crate::load_app_settings(app).on_device_lost;
+        for device in &lost {
+            log::warn!(
+                "[devices] profile device lost: {} ({})",
+                device.name,
+                device.id
+            );
+            let _ = app.emit(
+                "profile-device-lost",
+                ProfileDeviceLostEvent {
+                    device_id: device.id.clone(),
+                    name: device.name.clone(),
+                    action,
+                },
+            );
+        }
+        handle_lost(app, &state, action, &lost, &devices);
+    }
+
+    if !returned.is_empty() {
+        reapply_state(&state, &returned);
+    }
+    true
+}
+
+fn handle_lost(
+    app: &AppHandle,
+    state: &AppState,
+    action: OnDeviceLost,
+    lost: &[AudioDevice],
+    remaining: &[AudioDevice],
+) {
+    match action {
+        OnDeviceLost::None => {}
+        OnDeviceLost::MuteRemaining => {
+            let strategy = mute_strategy(state);
+            let controller_lock = state.audio_controller.lock_safe();
+            if let Some(controller) = controller_lock.as_ref() {
+                for device in remaining {
+                    if let Err(e) = hard_mute::set_mute(controller, &device.id, true, strategy) {
+                        log::warn!("[devices] failed to mute {}: {}", device.id, e);
+                    }
+                }
+            }
+        }
+        OnDeviceLost::NotifyOnly if !crate::focus_assist::suppressed() => {
+            let names = lost
+                .iter()
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = app
+                .notification()
+                .builder()
+                .title("TogMic")
+                .body(crate::i18n::translate_with(
+                    "notifyDeviceDisconnected",
+                    &[("names", &names)],
+                ))
+                .show();
+        }
+        OnDeviceLost::NotifyOnly => {}
+    }
+}
+
+/// Give devices that came back the mute state the profile has now. In a mixed state a
+/// device keeps its last known state, if there is one.
+fn reapply_state(state: &AppState, returned: &[AudioDevice]) {
+    let known: Vec<_> = state.device_mute_states.lock_safe().clone();
+    let fallback = state.mute_state.is_muted();
+    let strategy = mute_strategy(state);
+
+    let controller_lock = state.audio_controller.lock_safe();
+    let Some(controller) = controller_lock.as_ref() else {
+        return;
+    };
+    for device in returned {
+        let muted = match state.mute_state.get() {
+            crate::mute_state::MuteState::Mixed => known
+                .iter()
+                .find(|k| k.device_id == device.id)
+                .map_or(fallback, |k| k.muted),
+            _ => fallback,
+        };
+        if let Err(e) = hard_mute::set_mute(controller, &device.id, muted, strategy) {
+            log::warn!("[devices] failed to re-apply state to {}: {}", device.id, e);
+        }
+    }
+}
+
+fn mute_strategy(state: &AppState) -> MuteStrategy {
+    state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map_or_else(MuteStrategy::default, |p| p.mute_strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{device, profile};
+    use serde_json::json;
+
+    fn ids(devices: &[AudioDevice]) -> Vec<&str> {
+        devices.iter().map(|d| d.id.as_str()).collect()
+    }
+
+    #[test]
+    fn reports_lost_and_returned_profile_devices() {
+        let profile = profile(json!({ "deviceIds": ["a"] }));
+        let both = [device("a"), device("b")];
+        let only_b = [device("b")];
+
+        let (lost, returned) = diff_profile_devices(&profile, &both, &only_b);
+        assert_eq!(ids(&lost), ["a"]);
+        assert!(returned.is_empty());
+
+        let (lost, returned) = diff_profile_devices(&profile, &only_b, &both);
+        assert!(lost.is_empty());
+        assert_eq!(ids(&returned), ["a"]);
+    }
+
+    #[test]
+    fn ignores_devices_outside_the_profile() {
+        let profile = profile(json!({ "deviceIds": ["a"] }));
+        let (lost, returned) = diff_profile_devices(
+            &profile,
+            &[device("a"), device("b")],
+            &[device("a"), device("c")],
+        );
+        assert!(lost.is_empty());
+        assert!(returned.is_empty());
+    }
+
+    #[test]
+    fn all_devices_profiles_cover_every_device() {
+        let profile = profile(json!({ "deviceIds": [crate::ALL_DEVICES_ID] }));
+        let (lost, returned) = diff_profile_devices(
+            &profile,
+            &[device("a"), device("b")],
+            &[device("a"), device("c")],
+        );
+        assert_eq!(ids(&lost), ["b"]);
+        assert_eq!(ids(&returned), ["c"]);
+    }
+
+    #[test]
+    fn missing_from_compares_ids() {
+        let before = [device("a"), device("b")];
+        let mut renamed = device("a");
+        renamed.name = "Renamed".to_string();
+        assert_eq!(ids(&missing_from(&before, &[renamed])), ["b"]);
+        assert!(missing_from(&[], &before).is_empty());
+    }
+
+    #[test]
+    fn on_device_lost_uses_kebab_case() {
+        assert_eq!(json!(OnDeviceLost::MuteRemaining), json!("mute-remaining"));
+        assert_eq!(
+            serde_json::from_value::<OnDeviceLost>(json!("notify-only")).unwrap(),
+            OnDeviceLost::NotifyOnly
+        );
+        assert_eq!(OnDeviceLost::default(), OnDeviceLost::None);
+    }
+}
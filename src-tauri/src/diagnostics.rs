@@ -0,0 +1,286 @@
+use crate::audio::{AudioController, AudioDevice};
+use crate::{
+    AppHandle, AppSettings, AppState, ErrorEntry, HotkeyProfile, MutexExt, ALL_DEVICES_ID,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use tauri::Manager;
+use tauri_plugin_opener::OpenerExt;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiagnosticsReport {
+    /// Seconds since the Unix epoch.
+    generated_at: u64,
+    app_version: String,
+    os: String,
+    arch: &'static str,
+    anonymized: bool,
+    audio_controller_initialized: bool,
+    audio_init_status: crate::audio_init::AudioInitStatus,
+    sync_thread_alive: bool,
+    /// Restarts of the sync thread; see `sync_watchdog`.
+    sync_thread: crate::sync_watchdog::SyncThreadStatus,
+    /// COM apartment of each audio thread; empty outside Windows.
+    com_apartments: Vec<crate::audio::ThreadApartment>,
+    /// Current interval of the polling loop; `None` where change notifications are used.
+    effective_poll_interval_ms: Option<u64>,
+    is_muted: bool,
+    mute_state: crate::mute_state::MuteState,
+    devices: Vec<AudioDevice>,
+    device_error: Option<String>,
+    active_profile: Option<HotkeyProfile>,
+    app_settings: AppSettings,
+    registered_shortcuts: Vec<crate::shortcuts::ShortcutStatus>,
+    recent_errors: Vec<ErrorEntry>,
+    config_path: Option<PathBuf>,
+    /// The in-memory log buffer, oldest first; see `logging::recent`.
+    recent_logs: Vec<String>,
+}
+
+/// Folder diagnostics reports are written to. Lives under the system temp dir so reports
+/// never accumulate in the user's config folder.
+pub fn diagnostics_dir() -> PathBuf {
+    std::env::temp_dir().join("TogMic")
+}
+
+/// Replace a device ID with a short hash that is the same in every report. The
+/// "default-mic"/"all-mics" sentinels carry no personal information and are kept so the
+/// report stays readable.
+fn anonymize_id(id: &str) -> String {
+    if id == "default-mic" || id == ALL_DEVICES_ID {
+        return id.to_string();
+    }
+    Sha256::digest(id.as_bytes())[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Stand-in for the name of the device with `id`.
+fn anonymize_name(id: &str) -> String {
+    format!("Microphone {}", &anonymize_id(id)[..8])
+}
+
+/// Replaces device IDs and names in free text: error messages and log lines quote them as
+/// the system reported them.
+struct Scrubber {
+    /// Longest first, so a name isn't cut apart by a shorter one it contains.
+    replacements: Vec<(String, String)>,
+}
+
+impl Scrubber {
+    fn new(devices: &[AudioDevice]) -> Self {
+        let mut replacements = Vec::new();
+        for device in devices {
+            replacements.push((device.id.clone(), anonymize_id(&device.id)));
+            for name in [device.name.as_str(), device.system_name()] {
+                if !name.is_empty() {
+                    replacements.push((name.to_string(), anonymize_name(&device.id)));
+                }
+            }
+        }
+        replacements.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        replacements.dedup_by(|a, b| a.0 == b.0);
+        Self { replacements }
+    }
+
+    /// One pass from left to right, so replacements are never matched again.
+    fn scrub(&self, text: &str) -> String {
+        let mut scrubbed = String::with_capacity(text.len());
+        let mut rest = text;
+        'scan: while let Some(c) = rest.chars().next() {
+            for (from, to) in &self.replacements {
+                if let Some(after) = rest.strip_prefix(from.as_str()) {
+                    scrubbed.push_str(to);
+                    rest = after;
+                    continue 'scan;
+                }
+            }
+            scrubbed.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        scrubbed
+    }
+}
+
+/// Assemble a diagnostics report, write it to `diagnostics_dir()` and return its path.
+pub fn generate(app: &AppHandle, anonymize: bool) -> Result<PathBuf, String> {
+    let state = app.state::<AppState>();
+
+    let generated_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let (audio_controller_initialized, devices, device_error) = {
+        let controller_lock = state.audio_controller.lock_safe();
+        match controller_lock.as_ref() {
+            Some(controller) => match controller.enumerate_input_devices() {
+                Ok(devices) => (true, devices, None),
+                Err(e) => (true, Vec::new(), Some(e)),
+            },
+            None => (false, Vec::new(), None),
+        }
+    };
+
+    let mut report = DiagnosticsReport {
+        generated_at,
+        app_version: app.package_info().version.to_string(),
+        os: os_info::get().to_string(),
+        arch: std::env::consts::ARCH,
+        anonymized: anonymize,
+        audio_controller_initialized,
+        audio_init_status: state.audio_init_status.lock_safe().clone(),
+        sync_thread_alive: state.sync_thread_alive.load(Ordering::SeqCst),
+        sync_thread: crate::sync_watchdog::status(),
+        com_apartments: crate::audio::com_apartments(),
+        effective_poll_interval_ms: crate::poll::effective_interval_ms(),
+        is_muted: state.mute_state.is_muted(),
+        mute_state: state.mute_state.get(),
+        devices,
+        device_error,
+        active_profile: state.current_profile.lock_safe().clone(),
+        app_settings: crate::load_app_settings(app),
+        registered_shortcuts: crate::shortcuts::list(app),
+        recent_errors: state.recent_errors.lock_safe().iter().cloned().collect(),
+        config_path: crate::config_file_path(app),
+        recent_logs: crate::logging::recent(usize::MAX, log::Level::Trace),
+    };
+
+    // Never put credentials into a report that is meant to be attached to public issues
+    if let Some(mqtt) = report.app_settings.mqtt.as_mut() {
+        if mqtt.password.is_some() {
+            mqtt.password = Some("<redacted>".to_string());
+        }
+    }
+    if let Some(obs) = report.app_settings.obs.as_mut() {
+        if obs.password.is_some() {
+            obs.password = Some("<redacted>".to_string());
+        }
+    }
+
+    if anonymize {
+        // Log lines and errors name devices as well
+        let scrubber = Scrubber::new(&report.devices);
+        for line in &mut report.recent_logs {
+            *line = scrubber.scrub(line);
+        }
+        for error in &mut report.recent_errors {
+            error.message = scrubber.scrub(&error.message);
+        }
+        if let Some(error) = report.device_error.as_mut() {
+            *error = scrubber.scrub(error);
+        }
+        match &mut report.audio_init_status {
+            crate::audio_init::AudioInitStatus::Initializing {
+                last_error: error, ..
+            }
+            | crate::audio_init::AudioInitStatus::Unavailable { error } => {
+                *error = scrubber.scrub(error);
+            }
+            crate::audio_init::AudioInitStatus::Ready => {}
+        }
+        for device in &mut report.devices {
+            device.name = anonymize_name(&device.id);
+            device.system_name = None;
+            device.id = anonymize_id(&device.id);
+        }
+        if let Some(profile) = report.active_profile.as_mut() {
+            profile.device_ids = profile
+                .device_ids
+                .iter()
+                .map(|id| anonymize_id(id))
+                .collect();
+            for device in &mut profile.device_states {
+                device.device_id = anonymize_id(&device.device_id);
+            }
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?;
+
+    let dir = diagnostics_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create diagnostics folder: {}", e))?;
+    let path = dir.join(format!("togmic-diagnostics-{}.json", generated_at));
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write diagnostics: {}", e))?;
+
+    Ok(path)
+}
+
+/// Open the diagnostics folder in the system file manager. Safe to call from the tray.
+pub fn open_folder(app: &AppHandle) -> Result<(), String> {
+    let dir = diagnostics_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create diagnostics folder: {}", e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open diagnostics folder: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{DeviceKind, DeviceState, FormFactor};
+
+    fn device(id: &str, name: &str, system_name: Option<&str>) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_default: false,
+            state: DeviceState::Active,
+            kind: DeviceKind::default(),
+            form_factor: FormFactor::default(),
+            system_name: system_name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn anonymized_ids_are_stable_sha256_prefixes() {
+        // First eight bytes of sha256("{0.0.1.00000000}.{mic}")
+        assert_eq!(anonymize_id("{0.0.1.00000000}.{mic}"), "5c4dee90472e8e97");
+        assert_eq!(anonymize_id("default-mic"), "default-mic");
+        assert_eq!(anonymize_id(ALL_DEVICES_ID), ALL_DEVICES_ID);
+    }
+
+    #[test]
+    fn scrubber_replaces_ids_and_names() {
+        let devices = [
+            device("dev-1", "Headset", Some("Jabra Evolve2 65 Headset")),
+            device("dev-2", "Desk Mic", None),
+        ];
+        let scrubber = Scrubber::new(&devices);
+        let scrubbed = scrubber.scrub(
+            "set_mute on dev-1 failed: Jabra Evolve2 65 Headset is gone; Desk Mic (dev-2) ok",
+        );
+        assert_eq!(
+            scrubbed,
+            format!(
+                "set_mute on {} failed: {} is gone; {} ({}) ok",
+                anonymize_id("dev-1"),
+                anonymize_name("dev-1"),
+                anonymize_name("dev-2"),
+                anonymize_id("dev-2"),
+            )
+        );
+    }
+
+    #[test]
+    fn scrubber_prefers_the_longest_match() {
+        // "Mic" is part of "USB Mic" and of the stand-in names; each name is replaced
+        // whole, and only once
+        let devices = [
+            device("dev-a", "Mic", None),
+            device("dev-b", "USB Mic", None),
+        ];
+        let scrubber = Scrubber::new(&devices);
+        assert_eq!(
+            scrubber.scrub("USB Mic, Mic"),
+            format!("{}, {}", anonymize_name("dev-b"), anonymize_name("dev-a"))
+        );
+    }
+}
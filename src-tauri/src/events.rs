@@ -0,0 +1,192 @@
+//! Payloads of the events the backend emits to the frontend.
+//!
+//! `mute-state-changed` carries a `MuteStateChangedEvent`. Its `source` is a coarse
+//! category of the internal source names passed to `publish_mute_state`:
+//!
+//! | `source`   | internal sources                                                    |
+//! |------------|---------------------------------------------------------------------|
+//! | `hotkey`   | `hotkey`, `panic`, `hidButton`                                      |
+//! | `tray`     | `tray`                                                              |
+//! | `ui`       | `frontend`                                                          |
+//! | `startup`  | `startup`, `audioReady`, `resume`, `session`                        |
+//! | `auto`     | `rollback`, `partial`, `profileSwitch`, `idle`, `idleReturn`, `lock`, `unlock`, `temporaryUnmute`, `maxUnmuted`, `capture` |
+//! | `external` | everything else: `system` (a change picked up from the OS), the CLI, deep links, the control API, MQTT, OBS, Stream Deck |
+//!
+//! `profile-mute-changed` carries the same payload for a profile other than the active one,
+//! toggled by its own hotkey with `AppSettings::multi_profile_hotkeys`. The tray and the
+//! integrations only follow `mute-state-changed`.
+//!
+//! `profile-changed` carries the new active `HotkeyProfile`, or null once no profile is
+//! active, whatever switched it (frontend, tray, CLI, focus-based auto switch, startup).
+//!
+//! `mute-changed` still carries the bare `muted` boolean for frontends written against the
+//! old payload. It is deprecated and will be removed in the next release.
+//!
+//! Events sent through `emit` are also kept in `AppState::recent_events`, together with
+//! hotkey registrations and recorded errors, for `get_recent_events`.
+
+use crate::mute_state::{DeviceMuteState, MuteState};
+use crate::{AppHandle, AppState, HotkeyProfile, MutexExt};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+pub const MUTE_STATE_CHANGED: &str = "mute-state-changed";
+pub const PROFILE_MUTE_CHANGED: &str = "profile-mute-changed";
+pub const PROFILE_CHANGED: &str = "profile-changed";
+pub const DEVICES_CHANGED: &str = "devices-changed";
+/// Deprecated boolean form of `MUTE_STATE_CHANGED`.
+pub const LEGACY_MUTE_CHANGED: &str = "mute-changed";
+
+/// How many entries `AppState::recent_events` keeps.
+pub const MAX_RECENT_EVENTS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventKind {
+    Mute,
+    Profile,
+    Device,
+    Hotkey,
+    Error,
+}
+
+/// Entry of the recent-events history.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub kind: EventKind,
+    /// Event name, or the source of an error.
+    pub name: String,
+    /// Event payload, or the error message.
+    pub data: serde_json::Value,
+}
+
+/// What caused a mute state change, as reported to the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MuteSource {
+    Hotkey,
+    Tray,
+    Ui,
+    External,
+    Startup,
+    /// A correction TogMic made on its own (rollback, idle, lock, profile switch).
+    Auto,
+}
+
+impl MuteSource {
+    pub fn from_internal(source: &str) -> Self {
+        match source {
+            "hotkey" | "panic" => MuteSource::Hotkey,
+            crate::hid_buttons::SOURCE_HID_BUTTON => MuteSource::Hotkey,
+            "tray" => MuteSource::Tray,
+            "frontend" => MuteSource::Ui,
+            "startup" | "audioReady" | "resume" | "session" => MuteSource::Startup,
+            "rollback" | "partial" | "profileSwitch" | "lock" | "unlock" => MuteSource::Auto,
+            crate::idle::SOURCE_IDLE | crate::idle::SOURCE_RETURN => MuteSource::Auto,
+            crate::temporary_unmute::SOURCE_END => MuteSource::Auto,
+            crate::hot_mic::SOURCE_MAX_UNMUTED => MuteSource::Auto,
+            crate::capture_switch::SOURCE_CAPTURE => MuteSource::Auto,
+            _ => MuteSource::External,
+        }
+    }
+}
+
+/// Payload of `mute-state-changed`. `muted` is only true when every device is muted.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteStateChangedEvent {
+    pub muted: bool,
+    pub state: MuteState,
+    pub source: MuteSource,
+    pub profile_id: Option<String>,
+    pub device_count: usize,
+    pub devices: Vec<DeviceMuteState>,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// Emit `mute-state-changed` and its deprecated boolean twin.
+pub fn emit_mute_state(
+    app: &AppHandle,
+    mute_state: MuteState,
+    source: &str,
+    profile_id: Option<String>,
+    devices: Vec<DeviceMuteState>,
+) {
+    let muted = mute_state.is_muted();
+    emit(
+        app,
+        EventKind::Mute,
+        MUTE_STATE_CHANGED,
+        MuteStateChangedEvent {
+            muted,
+            state: mute_state,
+            source: MuteSource::from_internal(source),
+            profile_id,
+            device_count: devices.len(),
+            devices,
+            timestamp: now_millis(),
+        },
+    );
+    let _ = app.emit(LEGACY_MUTE_CHANGED, muted);
+}
+
+/// Emit `profile-mute-changed` for `profile_id`, which isn't the active profile.
+pub fn emit_profile_mute_state(
+    app: &AppHandle,
+    profile_id: String,
+    mute_state: MuteState,
+    source: &str,
+    devices: Vec<DeviceMuteState>,
+) {
+    emit(
+        app,
+        EventKind::Mute,
+        PROFILE_MUTE_CHANGED,
+        MuteStateChangedEvent {
+            muted: mute_state.is_muted(),
+            state: mute_state,
+            source: MuteSource::from_internal(source),
+            profile_id: Some(profile_id),
+            device_count: devices.len(),
+            devices,
+            timestamp: now_millis(),
+        },
+    );
+}
+
+/// Emit `profile-changed`.
+pub fn emit_profile_changed(app: &AppHandle, profile: Option<&HotkeyProfile>) {
+    emit(app, EventKind::Profile, PROFILE_CHANGED, profile);
+}
+
+/// Emit `event` to the frontend and record it in the recent-events history.
+pub fn emit<T: Serialize + Clone>(app: &AppHandle, kind: EventKind, event: &str, payload: T) {
+    let data = serde_json::to_value(&payload).unwrap_or_default();
+    app.state::<AppState>().record_event(kind, event, data);
+    let _ = app.emit(event, payload);
+}
+
+/// Up to `limit` recorded events of the given kinds (all kinds if empty), newest first.
+pub fn recent(state: &AppState, limit: usize, kinds: &[EventKind]) -> Vec<RecordedEvent> {
+    state
+        .recent_events
+        .lock_safe()
+        .iter()
+        .rev()
+        .filter(|e| kinds.is_empty() || kinds.contains(&e.kind))
+        .take(limit)
+        .cloned()
+        .collect()
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,348 @@
+//! Put microphones back the way they were when TogMic exits.
+//!
+//! The mute state of every input device is captured once the audio controller is up,
+//! before `start_muted` touches anything. On exit the configured `RestoreOnExit` mode is
+//! applied synchronously on a fresh thread, bounded by `RESTORE_TIMEOUT_MS` so a hanging
+//! device can't keep the process (or a Windows shutdown) waiting.
+//!
+//! `unmute_all_on_exit` forces `ForceUnmute` whatever the mode, unless TogMic was started
+//! with `--no-exit-unmute`, which turns forced unmuting off for scripted restarts. Installing
+//! an update counts as an exit: the Windows installer ends the process without `Exit`.
+//!
+//! `fail_safe` is the version for a panic about to end the process (see `crash`): it only
+//! touches the active profile's devices, unmuting them unless the mode is
+//! `RestoreOriginal`, and never waits for a lock the panicking thread might hold.
+
+use crate::audio::{AudioController, PlatformAudioController};
+use crate::mute_state::DeviceMuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+const RESTORE_TIMEOUT_MS: u64 = 1500;
+pub const NO_EXIT_UNMUTE_FLAG: &str = "--no-exit-unmute";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestoreOnExit {
+    /// Leave every device as the last toggle put it.
+    #[default]
+    None,
+    /// Set devices present at startup back to their startup state. Devices added later
+    /// are left alone.
+    RestoreOriginal,
+    /// Unmute every input device.
+    ForceUnmute,
+}
+
+/// Set once the restore has run; the quit handler, `ExitRequested` and `Exit` all call in.
+static RESTORED: AtomicBool = AtomicBool::new(false);
+/// Set by `--no-exit-unmute`.
+static SKIP_UNMUTE: AtomicBool = AtomicBool::new(false);
+/// `effective_mode` of the current settings, for `fail_safe`, which can't load them.
+static CONFIGURED: Lazy<Mutex<RestoreOnExit>> = Lazy::new(|| Mutex::new(RestoreOnExit::None));
+
+/// Note `--no-exit-unmute` among this launch's arguments.
+pub fn init(args: &[String]) {
+    if args.iter().any(|arg| arg == NO_EXIT_UNMUTE_FLAG) {
+        log::info!(
+            "[exit] devices won't be unmuted on exit ({})",
+            NO_EXIT_UNMUTE_FLAG
+        );
+        SKIP_UNMUTE.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Apply settings changes.
+pub fn configure(settings: &crate::AppSettings) {
+    *CONFIGURED.lock_safe() = effective_mode(settings);
+}
+
+/// The restore mode after `unmute_all_on_exit` and `--no-exit-unmute`.
+fn effective_mode(settings: &crate::AppSettings) -> RestoreOnExit {
+    let skip_unmute = SKIP_UNMUTE.load(Ordering::SeqCst);
+    let mode = if settings.unmute_all_on_exit && !skip_unmute {
+        RestoreOnExit::ForceUnmute
+    } else {
+        settings.restore_on_exit
+    };
+    if skip_unmute && mode == RestoreOnExit::ForceUnmute {
+        RestoreOnExit::None
+    } else {
+        mode
+    }
+}
+
+/// Remember the mute state of every input device. Only the first successful snapshot is
+/// kept, so a late audio initialization doesn't overwrite it.
+pub fn snapshot_initial_states(state: &AppState) {
+    if !state.initial_states.lock_safe().is_empty() {
+        return;
+    }
+
+    let snapshot: Vec<DeviceMuteState> = {
+        let controller_lock = state.audio_controller.lock_safe();
+        let Some(controller) = controller_lock.as_ref() else {
+            return;
+        };
+        let Ok(devices) = controller.enumerate_input_devices() else {
+            return;
+        };
+        devices
+            .into_iter()
+            .filter_map(|device| {
+                controller
+                    .get_mute_state(&device.id)
+                    .ok()
+                    .map(|muted| DeviceMuteState {
+                        device_id: device.id,
+                        muted,
+                    })
+            })
+            .collect()
+    };
+
+    *state.initial_states.lock_safe() = snapshot;
+}
+
+/// Apply the configured restore mode. Runs at most once and returns once the devices are
+/// set or the timeout has passed.
+pub fn restore(app: &AppHandle) {
+    if RESTORED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    crate::led_indicator::restore();
+
+    let mode = effective_mode(&crate::load_app_settings(app));
+    if mode == RestoreOnExit::None {
+        return;
+    }
+
+    let state = app.state::<AppState>();
+    // A mute change still queued on the worker must not land after the restore
+    let worker = state.audio_worker.lock_safe().take();
+    if let Some(worker) = worker {
+        worker.shutdown();
+    }
+
+    let initial_states = state.initial_states.lock_safe().clone();
+    run_bounded(&format!("{:?}", mode), move |controller| match mode {
+        RestoreOnExit::RestoreOriginal => {
+            for device in &initial_states {
+                if let Err(e) = crate::hard_mute::set_mute(
+                    controller,
+                    &device.device_id,
+                    device.muted,
+                    Default::default(),
+                ) {
+                    log::warn!("[exit] failed to restore {}: {}", device.device_id, e);
+                }
+            }
+        }
+        RestoreOnExit::ForceUnmute => {
+            let devices = controller.enumerate_input_devices().unwrap_or_else(|e| {
+                log::warn!("[exit] failed to list devices to unmute: {}", e);
+                Vec::new()
+            });
+            for device in devices {
+                if let Err(e) =
+                    crate::hard_mute::set_mute(controller, &device.id, false, Default::default())
+                {
+                    log::warn!("[exit] failed to unmute {}: {}", device.id, e);
+                }
+            }
+        }
+        RestoreOnExit::None => {}
+    });
+}
+
+/// Put the active profile's devices back after a panic, unmuted or, with
+/// `RestoreOriginal`, as they were at startup. Runs at most once, like `restore`.
+pub fn fail_safe(app: &AppHandle) {
+    if RESTORED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mode = fail_safe_mode(
+        CONFIGURED.try_lock().ok().map(|mode| *mode),
+        SKIP_UNMUTE.load(Ordering::SeqCst),
+    );
+    if mode == RestoreOnExit::None {
+        return;
+    }
+
+    let state = app.state::<AppState>();
+    let Some(profile) = state
+        .current_profile
+        .try_lock()
+        .ok()
+        .and_then(|p| p.clone())
+    else {
+        log::warn!("[exit] no active profile to fail safe with");
+        return;
+    };
+    let initial_states = if mode == RestoreOnExit::RestoreOriginal {
+        state
+            .initial_states
+            .try_lock()
+            .map(|states| states.clone())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    run_bounded("fail-safe", move |controller| {
+        fail_safe_devices(controller, &profile, mode, &initial_states)
+    });
+}
+
+/// What `fail_safe` does with the profile's devices for the `configured` mode (`None` if
+/// it couldn't be read): `RestoreOriginal` keeps its meaning, anything else unmutes
+/// (`ForceUnmute`) unless `--no-exit-unmute` was given.
+fn fail_safe_mode(configured: Option<RestoreOnExit>, skip_unmute: bool) -> RestoreOnExit {
+    match configured {
+        Some(RestoreOnExit::RestoreOriginal) => RestoreOnExit::RestoreOriginal,
+        _ if skip_unmute => RestoreOnExit::None,
+        _ => RestoreOnExit::ForceUnmute,
+    }
+}
+
+/// Apply the `fail_safe_mode` to the devices of `profile`. With `RestoreOriginal`,
+/// devices without a startup state are left alone.
+fn fail_safe_devices(
+    controller: &PlatformAudioController,
+    profile: &crate::HotkeyProfile,
+    mode: RestoreOnExit,
+    initial_states: &[DeviceMuteState],
+) {
+    let devices = controller.enumerate_input_devices().unwrap_or_else(|e| {
+        log::warn!("[exit] failed to list devices: {}", e);
+        Vec::new()
+    });
+    for id in crate::partition_device_ids(profile, &devices).present {
+        let muted = match mode {
+            RestoreOnExit::RestoreOriginal => {
+                match initial_states.iter().find(|device| device.device_id == id) {
+                    Some(device) => device.muted,
+                    None => continue,
+                }
+            }
+            RestoreOnExit::ForceUnmute => false,
+            RestoreOnExit::None => return,
+        };
+        if let Err(e) = crate::hard_mute::set_mute(controller, &id, muted, Default::default()) {
+            log::warn!("[exit] failed to restore {}: {}", id, e);
+        }
+    }
+}
+
+/// Run `f` with a fresh controller on its own thread, waiting at most
+/// `RESTORE_TIMEOUT_MS`. `what` is for the log.
+fn run_bounded(what: &str, f: impl FnOnce(&PlatformAudioController) + Send + 'static) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = PlatformAudioController::init_thread();
+        let result = PlatformAudioController::new().map(|controller| f(&controller));
+        let _ = tx.send(result);
+    });
+
+    match rx.recv_timeout(Duration::from_millis(RESTORE_TIMEOUT_MS)) {
+        Ok(Ok(())) => log::info!("[exit] restored device mute states ({})", what),
+        Ok(Err(e)) => log::warn!("[exit] failed to restore device mute states: {}", e),
+        Err(_) => log::warn!("[exit] restoring device mute states timed out"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fail_safe_unmutes_unless_restoring_the_original_state() {
+        use RestoreOnExit::*;
+        assert_eq!(fail_safe_mode(Some(None), false), ForceUnmute);
+        assert_eq!(fail_safe_mode(Some(ForceUnmute), false), ForceUnmute);
+        assert_eq!(
+            fail_safe_mode(Some(RestoreOriginal), false),
+            RestoreOriginal
+        );
+        // The lock was held by the panicking thread
+        assert_eq!(fail_safe_mode(Option::None, false), ForceUnmute);
+
+        assert_eq!(fail_safe_mode(Some(None), true), None);
+        assert_eq!(fail_safe_mode(Some(ForceUnmute), true), None);
+        assert_eq!(fail_safe_mode(Option::None, true), None);
+        assert_eq!(fail_safe_mode(Some(RestoreOriginal), true), RestoreOriginal);
+    }
+
+    #[cfg(feature = "mock-audio")]
+    mod mock_audio {
+        use super::*;
+        use crate::audio::mock;
+        use crate::test_support::{mock_device, mock_devices, profile};
+        use serde_json::json;
+
+        fn muted(id: &str) -> bool {
+            mock::devices().iter().any(|d| d.device.id == id && d.muted)
+        }
+
+        fn initial(id: &str, muted: bool) -> DeviceMuteState {
+            DeviceMuteState {
+                device_id: id.to_string(),
+                muted,
+            }
+        }
+
+        fn fail_safe_with(mode: RestoreOnExit, initial_states: &[DeviceMuteState]) {
+            let profile = profile(json!({ "id": "fail-safe", "deviceIds": ["a"] }));
+            fail_safe_devices(
+                &PlatformAudioController::new().unwrap(),
+                &profile,
+                mode,
+                initial_states,
+            );
+        }
+
+        fn muted_devices() -> std::sync::MutexGuard<'static, ()> {
+            let guard = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            mock::set_external_mute("a", true).unwrap();
+            mock::set_external_mute("b", true).unwrap();
+            guard
+        }
+
+        #[test]
+        fn unmutes_only_the_profile_devices() {
+            let _devices = muted_devices();
+            fail_safe_with(RestoreOnExit::ForceUnmute, &[]);
+            assert!(!muted("a"));
+            assert!(muted("b"));
+        }
+
+        #[test]
+        fn restores_the_startup_state_of_the_profile_devices() {
+            let _devices = muted_devices();
+            fail_safe_with(
+                RestoreOnExit::RestoreOriginal,
+                &[initial("a", false), initial("b", false)],
+            );
+            assert!(!muted("a"));
+            assert!(muted("b"));
+
+            // Devices that weren't there at startup are left alone
+            mock::set_external_mute("a", true).unwrap();
+            fail_safe_with(RestoreOnExit::RestoreOriginal, &[initial("b", false)]);
+            assert!(muted("a"));
+        }
+
+        #[test]
+        fn does_nothing_when_told_not_to_unmute() {
+            let _devices = muted_devices();
+            fail_safe_with(RestoreOnExit::None, &[]);
+            assert!(muted("a"));
+        }
+    }
+}
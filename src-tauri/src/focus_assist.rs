@@ -0,0 +1,134 @@
+//! Staying quiet while Windows Focus Assist (Do Not Disturb) is on.
+//!
+//! With `AppSettings::respect_focus_assist`, feedback sounds and notifications are
+//! skipped while Focus Assist is on, a presentation is running or a full-screen
+//! application has the screen. Events and the tray are unaffected, so the visible state
+//! stays correct. The status is cached for `CACHE_FOR`, so toggling doesn't query
+//! Windows every time.
+//!
+//! Focus Assist itself is read from its WNF state (the same one the Action Center
+//! uses); presentation mode, quiet time and full-screen applications come from
+//! `SHQueryUserNotificationState`.
+
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CACHE_FOR: Duration = Duration::from_secs(5);
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusAssistStatus {
+    Off,
+    PriorityOnly,
+    AlarmsOnly,
+    QuietTime,
+    Presentation,
+    FullScreen,
+    /// Not Windows, or the status can't be read.
+    Unknown,
+}
+
+impl FocusAssistStatus {
+    pub fn is_active(self) -> bool {
+        !matches!(self, FocusAssistStatus::Off | FocusAssistStatus::Unknown)
+    }
+}
+
+static RESPECT: AtomicBool = AtomicBool::new(false);
+static CACHED: Lazy<Mutex<Option<(Instant, FocusAssistStatus)>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn configure(respect: bool) {
+    RESPECT.store(respect, Ordering::SeqCst);
+}
+
+/// The current status, at most `CACHE_FOR` old.
+pub fn status() -> FocusAssistStatus {
+    let mut cached = CACHED.lock_safe();
+    if let Some((at, status)) = *cached {
+        if at.elapsed() < CACHE_FOR {
+            return status;
+        }
+    }
+    let status = platform::query();
+    *cached = Some((Instant::now(), status));
+    status
+}
+
+/// Whether sounds and notifications should be skipped right now.
+pub fn suppressed() -> bool {
+    RESPECT.load(Ordering::SeqCst) && status().is_active()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::FocusAssistStatus;
+    use std::ffi::c_void;
+    use windows::Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME,
+        QUNS_RUNNING_D3D_FULL_SCREEN,
+    };
+
+    /// `WNF_SHEL_QUIETHOURS_ACTIVE_PROFILE_CHANGED`: 0 off, 1 priority only, 2 alarms only.
+    const WNF_QUIET_HOURS_PROFILE: u64 = 0x0d83_063e_a3bf_1c75;
+
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn NtQueryWnfStateData(
+            state_name: *const u64,
+            type_id: *const c_void,
+            explicit_scope: *const c_void,
+            change_stamp: *mut u32,
+            buffer: *mut c_void,
+            buffer_size: *mut u32,
+        ) -> i32;
+    }
+
+    fn focus_assist_profile() -> Option<u32> {
+        let mut change_stamp = 0u32;
+        let mut profile = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            NtQueryWnfStateData(
+                &WNF_QUIET_HOURS_PROFILE,
+                std::ptr::null(),
+                std::ptr::null(),
+                &mut change_stamp,
+                &mut profile as *mut u32 as *mut c_void,
+                &mut size,
+            )
+        };
+        (status >= 0).then_some(profile)
+    }
+
+    pub fn query() -> FocusAssistStatus {
+        match focus_assist_profile() {
+            Some(1) => return FocusAssistStatus::PriorityOnly,
+            Some(2) => return FocusAssistStatus::AlarmsOnly,
+            _ => {}
+        }
+        match unsafe { SHQueryUserNotificationState() } {
+            Ok(QUNS_QUIET_TIME) => FocusAssistStatus::QuietTime,
+            Ok(QUNS_PRESENTATION_MODE) => FocusAssistStatus::Presentation,
+            Ok(QUNS_RUNNING_D3D_FULL_SCREEN) | Ok(QUNS_BUSY) => FocusAssistStatus::FullScreen,
+            Ok(_) => FocusAssistStatus::Off,
+            Err(e) => {
+                log::debug!("[focus] failed to query notification state: {}", e);
+                FocusAssistStatus::Unknown
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::FocusAssistStatus;
+
+    pub fn query() -> FocusAssistStatus {
+        FocusAssistStatus::Unknown
+    }
+}
@@ -0,0 +1,208 @@
+//! Automatic profile switching based on the foreground application.
+//!
+//! A watcher thread checks the foreground process every `POLL_INTERVAL_MS`. When a process
+//! listed in a profile's `auto_activate_apps` has had focus for `FOCUS_DELAY_MS` (so
+//! alt-tabbing through it doesn't switch), that profile is activated the same way an
+//! external trigger switches profiles. Once focus moves elsewhere, a profile with
+//! `revert_on_blur` hands back to the profile that was active before.
+
+use crate::{AppHandle, AppState, HotkeyProfile, MutexExt};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+const POLL_INTERVAL_MS: u64 = 1000;
+const FOCUS_DELAY_MS: u64 = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileAutoSwitchedEvent {
+    pub profile: HotkeyProfile,
+    /// Process whose focus (or loss of focus), or capture, triggered the switch.
+    pub process: String,
+    /// "focus" or "capture" (see `capture_switch`).
+    pub reason: &'static str,
+    /// True when switching back to the profile active before the auto switch.
+    pub reverted: bool,
+}
+
+/// An auto switch that is still in effect.
+struct AutoSwitch {
+    profile_id: String,
+    previous_profile_id: Option<String>,
+}
+
+/// Whether `process` (an executable file name) is one of `apps`. Entries match with or
+/// without the `.exe` suffix, ignoring case.
+pub fn matches_app(apps: &[String], process: &str) -> bool {
+    let process = process.to_lowercase();
+    let stem = process.strip_suffix(".exe").unwrap_or(&process);
+    apps.iter().any(|app| {
+        let app = app.trim().to_lowercase();
+        let app = app.strip_suffix(".exe").unwrap_or(&app);
+        !app.is_empty() && app == stem
+    })
+}
+
+/// Start the watcher thread. Without any `auto_activate_apps` configured it never
+/// switches anything.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        // Process in the foreground and since when
+        let mut focused: Option<(String, Instant)> = None;
+        let mut handled: Option<String> = None;
+        let mut auto_switch: Option<AutoSwitch> = None;
+
+        loop {
+            std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+
+            let Some(process) = foreground_process_name() else {
+                continue;
+            };
+            match &focused {
+                Some((name, _)) if *name == process => {}
+                _ => focused = Some((process.clone(), Instant::now())),
+            }
+            let stable = focused
+                .as_ref()
+                .is_some_and(|(_, since)| since.elapsed() >= Duration::from_millis(FOCUS_DELAY_MS));
+            if !stable || handled.as_deref() == Some(process.as_str()) {
+                continue;
+            }
+            handled = Some(process.clone());
+
+            let state = app.state::<AppState>();
+            let active_id = state
+                .current_profile
+                .lock_safe()
+                .as_ref()
+                .map(|p| p.id.clone());
+
+            // A manual switch since the auto switch takes precedence
+            if auto_switch
+                .as_ref()
+                .is_some_and(|s| Some(&s.profile_id) != active_id.as_ref())
+            {
+                auto_switch = None;
+            }
+
+            let profiles = crate::load_profiles(&app);
+            if let Some(target) = profiles
+                .iter()
+                .find(|p| matches_app(&p.auto_activate_apps, &process))
+            {
+                if Some(&target.id) == active_id.as_ref() {
+                    continue;
+                }
+                // Keep the profile from before the first switch when hopping between apps
+                let previous_profile_id = match auto_switch.take() {
+                    Some(switch) => switch.previous_profile_id,
+                    None => active_id,
+                };
+                if switch_to(&app, &target.id, &process, false) {
+                    auto_switch = Some(AutoSwitch {
+                        profile_id: target.id.clone(),
+                        previous_profile_id,
+                    });
+                }
+                continue;
+            }
+
+            // Focus left the app that triggered the auto switch
+            let Some(switch) = auto_switch.take() else {
+                continue;
+            };
+            let revert = profiles
+                .iter()
+                .find(|p| p.id == switch.profile_id)
+                .is_some_and(|p| p.revert_on_blur);
+            if let (true, Some(previous_id)) = (revert, switch.previous_profile_id) {
+                switch_to(&app, &previous_id, &process, true);
+            }
+        }
+    });
+}
+
+fn switch_to(app: &AppHandle, profile_id: &str, process: &str, reverted: bool) -> bool {
+    match crate::switch_profile(app, profile_id) {
+        Ok(profile) => {
+            log::info!(
+                "[foreground] switched to profile '{}' ({})",
+                profile.name,
+                process
+            );
+            let _ = app.emit(
+                "profile-auto-switched",
+                ProfileAutoSwitchedEvent {
+                    profile,
+                    process: process.to_string(),
+                    reason: "focus",
+                    reverted,
+                },
+            );
+            true
+        }
+        Err(e) => {
+            app.state::<AppState>().record_error("auto_switch", e);
+            false
+        }
+    }
+}
+
+/// File name of the process owning the foreground window.
+#[cfg(target_os = "windows")]
+fn foreground_process_name() -> Option<String> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0u32;
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+    }
+    if pid == 0 {
+        return None;
+    }
+    process_name(pid)
+}
+
+/// File name of the executable of process `pid`.
+#[cfg(target_os = "windows")]
+pub fn process_name(pid: u32) -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        path.rsplit('\\').next().map(str::to_string)
+    }
+}
+
+/// Foreground detection is Windows-only for now.
+#[cfg(not(target_os = "windows"))]
+fn foreground_process_name() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn process_name(_pid: u32) -> Option<String> {
+    None
+}
@@ -0,0 +1,145 @@
+//! Muting by volume, for applications that ignore the endpoint mute flag.
+//!
+//! With `MuteStrategy::Volume` or `Both` a mute remembers the device's level and sets its
+//! volume to zero (`Both` sets the flag as well). Levels are persisted under
+//! `rememberedVolumes` in the config store, keyed by device id, so a device muted this way
+//! gets its level back after a restart. Every unmute clears the flag and restores a
+//! remembered level whatever the strategy, so switching strategies while muted loses
+//! nothing. For the volume strategies a device at a near-zero level counts as muted.
+
+use crate::audio::{AudioController, PlatformAudioController};
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri_plugin_store::StoreExt;
+
+const STORE_KEY: &str = "rememberedVolumes";
+/// Levels at or below this count as silent.
+const SILENT_LEVEL: f32 = 0.001;
+
+/// How a profile mutes its devices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum MuteStrategy {
+    /// The endpoint mute flag only.
+    #[default]
+    Flag,
+    /// Zero volume only.
+    Volume,
+    /// The mute flag and zero volume.
+    Both,
+}
+
+impl MuteStrategy {
+    fn uses_volume(self) -> bool {
+        self != MuteStrategy::Flag
+    }
+
+    fn uses_flag(self) -> bool {
+        self != MuteStrategy::Volume
+    }
+}
+
+/// Device id → level before it was muted by volume.
+static REMEMBERED: Lazy<Mutex<HashMap<String, f32>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// For persisting from the audio worker, which has no handle of its own.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+
+/// Load the levels remembered by the previous run.
+pub fn init(app: &AppHandle) {
+    let saved = app
+        .store(crate::config_store(app))
+        .ok()
+        .and_then(|store| store.get(STORE_KEY))
+        .and_then(|v| serde_json::from_value::<HashMap<String, f32>>(v).ok())
+        .unwrap_or_default();
+    if !saved.is_empty() {
+        log::info!("[hard-mute] {} remembered level(s)", saved.len());
+    }
+    *REMEMBERED.lock_safe() = saved;
+    *APP.lock_safe() = Some(app.clone());
+}
+
+/// Mute or unmute one device with `strategy`.
+pub fn set_mute(
+    controller: &PlatformAudioController,
+    device_id: &str,
+    muted: bool,
+    strategy: MuteStrategy,
+) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    if let Some(result) = crate::simulate::intercept_set(device_id, muted) {
+        return result;
+    }
+    if !muted {
+        return unmute(controller, device_id);
+    }
+
+    if strategy.uses_volume() {
+        let level = controller.get_volume(device_id)?;
+        // Already silent: keep the level remembered by an earlier mute, if any
+        if level > SILENT_LEVEL {
+            update(|levels| {
+                levels.insert(device_id.to_string(), level);
+            });
+        }
+        controller.set_volume(device_id, 0.0)?;
+    }
+    if strategy.uses_flag() {
+        controller.set_mute_state(device_id, true)?;
+    }
+    Ok(())
+}
+
+fn unmute(controller: &PlatformAudioController, device_id: &str) -> Result<(), String> {
+    let remembered = REMEMBERED.lock_safe().get(device_id).copied();
+    if let Some(level) = remembered {
+        controller.set_volume(device_id, level)?;
+        update(|levels| {
+            levels.remove(device_id);
+        });
+    }
+    controller.set_mute_state(device_id, false)
+}
+
+/// Whether one device is muted as far as `strategy` is concerned.
+pub fn is_muted(
+    controller: &PlatformAudioController,
+    device_id: &str,
+    strategy: MuteStrategy,
+) -> Result<bool, String> {
+    #[cfg(debug_assertions)]
+    if let Some(result) = crate::simulate::intercept_get(device_id) {
+        return result;
+    }
+    let flag = controller.get_mute_state(device_id)?;
+    if flag || !strategy.uses_volume() {
+        return Ok(flag);
+    }
+    Ok(controller.get_volume(device_id)? <= SILENT_LEVEL)
+}
+
+fn update(change: impl FnOnce(&mut HashMap<String, f32>)) {
+    let levels = {
+        let mut levels = REMEMBERED.lock_safe();
+        change(&mut levels);
+        levels.clone()
+    };
+    let Some(app) = APP.lock_safe().clone() else {
+        return;
+    };
+    let result = app
+        .store(crate::config_store(&app))
+        .map_err(|e| e.to_string())
+        .and_then(|store| {
+            store.set(STORE_KEY, serde_json::json!(levels));
+            crate::config_recovery::guard_save(false)?;
+            store.save().map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        log::warn!("[hard-mute] failed to persist levels: {}", e);
+    }
+}
@@ -0,0 +1,360 @@
+//! Hardware mute buttons on the HID telephony usage page.
+//!
+//! Mute buttons such as MuteSync or headset pucks aren't keyboards, so hotkeys never see
+//! them. They report a Phone Mute usage (telephony page `0x0B`, usage `0x2F`) instead,
+//! and often have a Mute LED (LED page `0x08`, usage `0x09`). The report descriptor tells
+//! where both live; each press (the usage going from 0 to 1) toggles like the hotkey, and
+//! `show` writes the mute state back to the LED.
+//!
+//! A profile binds one device with `HotkeyProfile::hid_device` (`vvvv:pppp`, see
+//! `list_hid_buttons`). The listener thread only runs while the active profile has a
+//! binding; when the device is unplugged it looks for it again every `REOPEN_INTERVAL`.
+
+use crate::mute_state::MuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use hidapi::{DeviceInfo, HidApi, HidDevice};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+const TELEPHONY_PAGE: u16 = 0x0b;
+const PHONE_MUTE: u16 = 0x2f;
+const LED_PAGE: u16 = 0x08;
+const MUTE_LED: u16 = 0x09;
+
+const READ_TIMEOUT_MS: i32 = 100;
+const REOPEN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `source` of the toggles made here.
+pub const SOURCE_HID_BUTTON: &str = "hidButton";
+
+/// A connected device with a Phone Mute button; see `list_hid_buttons`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HidButton {
+    /// `vvvv:pppp`, the value for `HotkeyProfile::hid_device`.
+    pub id: String,
+    pub name: String,
+    pub has_led: bool,
+}
+
+/// The bound device of the active profile.
+static BINDING: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+static RUNNING: AtomicBool = AtomicBool::new(false);
+/// LED state still to be written.
+static PENDING_LED: Lazy<Mutex<Option<bool>>> = Lazy::new(|| Mutex::new(None));
+
+/// Listen to `hid_device`, the binding of the profile just activated, or stop listening.
+pub fn configure(app: &AppHandle, hid_device: Option<String>) {
+    let hid_device = hid_device.filter(|id| !id.is_empty());
+    let start = hid_device.is_some();
+    *BINDING.lock_safe() = hid_device;
+    if start && !RUNNING.swap(true, Ordering::SeqCst) {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            run(&app);
+            RUNNING.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Light the bound device's Mute LED while muted.
+pub fn show(mute_state: MuteState) {
+    if RUNNING.load(Ordering::SeqCst) {
+        *PENDING_LED.lock_safe() = Some(mute_state == MuteState::Muted);
+    }
+}
+
+/// Devices with a Phone Mute button connected right now.
+pub fn list_hid_buttons() -> Result<Vec<HidButton>, String> {
+    let api = HidApi::new().map_err(|e| format!("Failed to access HID devices: {}", e))?;
+    let mut buttons: Vec<HidButton> = Vec::new();
+    for info in telephony_interfaces(&api) {
+        let id = device_id(info);
+        if buttons.iter().any(|b| b.id == id) {
+            continue;
+        }
+        let Ok(mute) = open(&api, info) else {
+            continue;
+        };
+        buttons.push(HidButton {
+            name: info.product_string().unwrap_or(id.as_str()).to_string(),
+            has_led: mute.led.is_some(),
+            id,
+        });
+    }
+    Ok(buttons)
+}
+
+fn device_id(info: &DeviceInfo) -> String {
+    format!("{:04x}:{:04x}", info.vendor_id(), info.product_id())
+}
+
+fn telephony_interfaces(api: &HidApi) -> impl Iterator<Item = &DeviceInfo> {
+    api.device_list()
+        .filter(|info| info.usage_page() == TELEPHONY_PAGE)
+}
+
+/// Where a usage sits in the reports.
+#[derive(Debug, Clone, Copy)]
+struct Field {
+    report_id: u8,
+    /// Bit offset within the report, after the report ID byte.
+    bit: usize,
+    /// Length of the whole report in bytes, without the report ID.
+    report_len: usize,
+}
+
+struct MuteDevice {
+    device: HidDevice,
+    button: Field,
+    led: Option<Field>,
+}
+
+fn open(api: &HidApi, info: &DeviceInfo) -> Result<MuteDevice, String> {
+    let device = info.open_device(api).map_err(|e| e.to_string())?;
+    let mut descriptor = [0u8; hidapi::MAX_REPORT_DESCRIPTOR_SIZE];
+    let len = device
+        .get_report_descriptor(&mut descriptor)
+        .map_err(|e| e.to_string())?;
+    let fields = parse_descriptor(&descriptor[..len]);
+    let button = fields
+        .button
+        .ok_or_else(|| format!("{} has no Phone Mute usage", device_id(info)))?;
+    Ok(MuteDevice {
+        device,
+        button,
+        led: fields.led,
+    })
+}
+
+#[derive(Default)]
+struct Fields {
+    button: Option<Field>,
+    led: Option<Field>,
+}
+
+/// Find the Phone Mute input and Mute LED output in a report descriptor. Only the parts
+/// needed for that are understood: usages, report IDs, sizes and counts.
+fn parse_descriptor(descriptor: &[u8]) -> Fields {
+    #[derive(Clone, Copy, Default)]
+    struct Globals {
+        usage_page: u16,
+        report_size: usize,
+        report_count: usize,
+        report_id: u8,
+    }
+
+    let mut globals = Globals::default();
+    let mut stack: Vec<Globals> = Vec::new();
+    let mut usages: Vec<(u16, u16)> = Vec::new();
+    let mut usage_min: Option<u32> = None;
+    // Bits seen so far per (output, report ID)
+    let mut offsets: Vec<(bool, u8, usize)> = Vec::new();
+    let mut found_button: Option<(u8, usize)> = None;
+    let mut found_led: Option<(u8, usize)> = None;
+
+    let mut i = 0;
+    while i < descriptor.len() {
+        let prefix = descriptor[i];
+        if prefix == 0xfe {
+            // Long item: data size in the next byte
+            let size = descriptor.get(i + 1).copied().unwrap_or(0) as usize;
+            i += 3 + size;
+            continue;
+        }
+        let size = match prefix & 0x03 {
+            3 => 4,
+            n => n as usize,
+        };
+        let Some(data) = descriptor.get(i + 1..i + 1 + size) else {
+            break;
+        };
+        let value = data
+            .iter()
+            .rev()
+            .fold(0u32, |acc, &b| (acc << 8) | u32::from(b));
+        i += 1 + size;
+
+        match (prefix >> 2) & 0x03 {
+            // Main
+            0 => {
+                let tag = prefix >> 4;
+                if tag == 0x08 || tag == 0x09 {
+                    let output = tag == 0x09;
+                    let constant = value & 0x01 != 0;
+                    let index = match offsets
+                        .iter()
+                        .position(|(o, id, _)| *o == output && *id == globals.report_id)
+                    {
+                        Some(index) => index,
+                        None => {
+                            offsets.push((output, globals.report_id, 0));
+                            offsets.len() - 1
+                        }
+                    };
+                    let offset = &mut offsets[index].2;
+                    if !constant {
+                        for n in 0..globals.report_count {
+                            let usage = match usage_min {
+                                Some(min) => (globals.usage_page, (min as usize + n) as u16),
+                                None => match usages.get(n).or(usages.last()) {
+                                    Some(&usage) => usage,
+                                    None => break,
+                                },
+                            };
+                            let bit = *offset + n * globals.report_size;
+                            if !output && usage == (TELEPHONY_PAGE, PHONE_MUTE) {
+                                found_button.get_or_insert((globals.report_id, bit));
+                            }
+                            if output && usage == (LED_PAGE, MUTE_LED) {
+                                found_led.get_or_insert((globals.report_id, bit));
+                            }
+                        }
+                    }
+                    *offset += globals.report_size * globals.report_count;
+                }
+                usages.clear();
+                usage_min = None;
+            }
+            // Global
+            1 => match prefix >> 4 {
+                0x00 => globals.usage_page = value as u16,
+                0x07 => globals.report_size = value as usize,
+                0x08 => globals.report_id = value as u8,
+                0x09 => globals.report_count = value as usize,
+                0x0a => stack.push(globals),
+                0x0b => globals = stack.pop().unwrap_or_default(),
+                _ => {}
+            },
+            // Local
+            2 => match prefix >> 4 {
+                0x00 if size == 4 => usages.push(((value >> 16) as u16, value as u16)),
+                0x00 => usages.push((globals.usage_page, value as u16)),
+                0x01 => usage_min = Some(value & 0xffff),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    let field = |output: bool, (report_id, bit): (u8, usize)| {
+        let bits = offsets
+            .iter()
+            .find(|(o, id, _)| *o == output && *id == report_id)
+            .map_or(0, |(_, _, bits)| *bits);
+        Field {
+            report_id,
+            bit,
+            report_len: bits.div_ceil(8),
+        }
+    };
+    Fields {
+        button: found_button.map(|f| field(false, f)),
+        led: found_led.map(|f| field(true, f)),
+    }
+}
+
+fn run(app: &AppHandle) {
+    let mut api = match HidApi::new() {
+        Ok(api) => api,
+        Err(e) => {
+            log::warn!("[hid] failed to access HID devices: {}", e);
+            return;
+        }
+    };
+    let mut opened: Option<(String, MuteDevice)> = None;
+    let mut pressed = false;
+
+    loop {
+        let Some(binding) = BINDING.lock_safe().clone() else {
+            log::info!("[hid] listener stopped");
+            return;
+        };
+        if opened.as_ref().is_some_and(|(id, _)| *id != binding) {
+            opened = None;
+        }
+        let Some((_, mute)) = opened.as_ref() else {
+            match find(&mut api, &binding) {
+                Ok(mute) => {
+                    log::info!("[hid] listening to {}", binding);
+                    let muted = app.state::<AppState>().mute_state.get() == MuteState::Muted;
+                    *PENDING_LED.lock_safe() = Some(muted);
+                    pressed = false;
+                    opened = Some((binding, mute));
+                }
+                Err(e) => {
+                    log::debug!("[hid] {}", e);
+                    std::thread::sleep(REOPEN_INTERVAL);
+                }
+            }
+            continue;
+        };
+
+        if let Some(lit) = PENDING_LED.lock_safe().take() {
+            if let Some(led) = mute.led {
+                if let Err(e) = write_led(&mute.device, led, lit) {
+                    log::warn!("[hid] failed to set the mute LED: {}", e);
+                }
+            }
+        }
+
+        let mut buf = [0u8; 64];
+        match mute.device.read_timeout(&mut buf, READ_TIMEOUT_MS) {
+            Ok(0) => {}
+            Ok(len) => {
+                if let Some(now) = button_state(&buf[..len], mute.button) {
+                    if now && !pressed {
+                        let state = app.state::<AppState>();
+                        if let Err(e) = crate::toggle_mute_internal(&state, app, SOURCE_HID_BUTTON)
+                        {
+                            state.record_error("hid_button", e);
+                        }
+                    }
+                    pressed = now;
+                }
+            }
+            Err(e) => {
+                log::info!("[hid] {} disconnected: {}", binding, e);
+                opened = None;
+            }
+        }
+    }
+}
+
+fn find(api: &mut HidApi, binding: &str) -> Result<MuteDevice, String> {
+    api.refresh_devices().map_err(|e| e.to_string())?;
+    let mut last_error = format!("{} is not connected", binding);
+    for info in telephony_interfaces(api).filter(|info| device_id(info) == binding) {
+        match open(api, info) {
+            Ok(mute) => return Ok(mute),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// The button bit of an input report, if the report carries it.
+fn button_state(report: &[u8], field: Field) -> Option<bool> {
+    let data = if field.report_id == 0 {
+        report
+    } else if report.first() == Some(&field.report_id) {
+        &report[1..]
+    } else {
+        return None;
+    };
+    data.get(field.bit / 8)
+        .map(|byte| byte & (1 << (field.bit % 8)) != 0)
+}
+
+fn write_led(device: &HidDevice, field: Field, lit: bool) -> Result<(), String> {
+    let mut report = vec![0u8; 1 + field.report_len];
+    report[0] = field.report_id;
+    if lit {
+        report[1 + field.bit / 8] |= 1 << (field.bit % 8);
+    }
+    device.write(&report).map(|_| ()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,87 @@
+//! Devices the user hid, such as virtual microphones other software depends on.
+//!
+//! The ids are persisted under `hiddenDeviceIds` in the config store. A hidden device is
+//! left out of `get_audio_devices` (unless asked for), out of the all-devices expansion of
+//! profiles (`expands_to`), out of the devices a solo profile mutes and out of the
+//! `devices-changed` diff. A profile that lists a hidden device explicitly still controls
+//! it.
+
+use crate::audio::AudioDevice;
+use crate::{AppHandle, HotkeyProfile, MutexExt};
+use once_cell::sync::Lazy;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use tauri_plugin_store::StoreExt;
+
+const STORE_KEY: &str = "hiddenDeviceIds";
+
+static HIDDEN: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+
+/// Load the persisted ids.
+pub fn init(app: &AppHandle) {
+    let saved = app
+        .store(crate::config_store(app))
+        .ok()
+        .and_then(|store| store.get(STORE_KEY))
+        .and_then(|v| serde_json::from_value::<BTreeSet<String>>(v).ok())
+        .unwrap_or_default();
+    if !saved.is_empty() {
+        log::info!("[devices] {} hidden device(s)", saved.len());
+    }
+    *HIDDEN.lock_safe() = saved;
+}
+
+pub fn ids() -> Vec<String> {
+    HIDDEN.lock_safe().iter().cloned().collect()
+}
+
+pub fn is_hidden(device_id: &str) -> bool {
+    HIDDEN.lock_safe().contains(device_id)
+}
+
+/// Whether an all-devices `profile` covers `device_id`: hidden devices only if the profile
+/// lists them.
+pub fn expands_to(profile: &HotkeyProfile, device_id: &str) -> bool {
+    !is_hidden(device_id) || profile.lists_device(device_id)
+}
+
+/// `devices` without the hidden ones.
+pub fn visible(devices: &[AudioDevice]) -> Vec<AudioDevice> {
+    let hidden = HIDDEN.lock_safe();
+    devices
+        .iter()
+        .filter(|d| !hidden.contains(&d.id))
+        .cloned()
+        .collect()
+}
+
+/// Hide or show `device_id` and persist the change. Returns the hidden ids.
+pub fn set_hidden(app: &AppHandle, device_id: &str, hidden: bool) -> Result<Vec<String>, String> {
+    let ids = {
+        let mut ids = HIDDEN.lock_safe();
+        let changed = if hidden {
+            ids.insert(device_id.to_string())
+        } else {
+            ids.remove(device_id)
+        };
+        if !changed {
+            return Ok(ids.iter().cloned().collect());
+        }
+        ids.clone()
+    };
+    log::info!(
+        "[devices] {} {}",
+        if hidden { "hid" } else { "unhid" },
+        device_id
+    );
+
+    let store = app
+        .store(crate::config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    store.set(STORE_KEY, serde_json::json!(ids));
+    crate::config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save hidden devices: {}", e))?;
+    Ok(ids.into_iter().collect())
+}
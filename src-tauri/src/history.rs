@@ -0,0 +1,245 @@
+//! Mute history: every published change of the mute state, with its source, as a timeline.
+//!
+//! Transitions are collected in memory by `record` and appended to `history.jsonl` in the
+//! app data directory by `flush`, which runs on the stats save thread and on exit, so the
+//! toggle path never touches the disk. Once the file passes `MAX_FILE_BYTES` it is rotated
+//! to `history.1.jsonl` and so on, keeping `KEEP_GENERATIONS` older files. Entries older
+//! than `AppSettings::history_retention_days` are left out of queries, and rotated files
+//! past it are deleted; a retention of 0 turns the history off.
+//!
+//! `query` walks the pending entries and then the files from the newest line backwards,
+//! stopping at the limit or at the first entry before the range.
+
+use crate::mute_state::MuteState;
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+const HISTORY_FILE: &str = "history.jsonl";
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const KEEP_GENERATIONS: usize = 5;
+const MS_PER_DAY: u64 = 86_400_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+    pub state: MuteState,
+    /// The `publish_mute_state` source ("hotkey", "tray", "system", ...).
+    pub source: String,
+    pub profile_id: Option<String>,
+    /// Devices of the active profile the state was published for.
+    pub device_count: usize,
+}
+
+#[derive(Default)]
+struct History {
+    path: Option<PathBuf>,
+    retention_days: u32,
+    /// Recorded but not yet written, oldest first.
+    pending: Vec<HistoryEntry>,
+    /// Last recorded state, so republishing the same state isn't a transition.
+    last_state: Option<MuteState>,
+}
+
+static HISTORY: Lazy<Mutex<History>> = Lazy::new(|| {
+    Mutex::new(History {
+        retention_days: DEFAULT_RETENTION_DAYS,
+        ..Default::default()
+    })
+});
+
+/// Resolve the history file. Recording starts right away.
+pub fn init(app: &AppHandle) {
+    HISTORY.lock_safe().path = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join(HISTORY_FILE));
+}
+
+/// Apply settings changes.
+pub fn configure(retention_days: u32) {
+    HISTORY.lock_safe().retention_days = retention_days;
+}
+
+/// Note a published mute state; see `publish_mute_state`.
+pub fn record(state: MuteState, source: &str, profile_id: Option<String>, device_count: usize) {
+    let mut history = HISTORY.lock_safe();
+    if history.retention_days == 0 || history.last_state == Some(state) {
+        return;
+    }
+    history.last_state = Some(state);
+    history.pending.push(HistoryEntry {
+        at: now_ms(),
+        state,
+        source: source.to_string(),
+        profile_id,
+        device_count,
+    });
+}
+
+/// Append the pending entries to the file, rotating and pruning as needed.
+pub fn flush() {
+    let (path, pending, retention_days) = {
+        let mut history = HISTORY.lock_safe();
+        let Some(path) = history.path.clone() else {
+            return;
+        };
+        (
+            path,
+            std::mem::take(&mut history.pending),
+            history.retention_days,
+        )
+    };
+    if let Err(e) = append(&path, &pending) {
+        log::warn!("[history] {}", e);
+    }
+    prune(&path, retention_days);
+}
+
+/// Entries from `from` to `to` (milliseconds since the epoch, both inclusive), newest
+/// first, at most `limit`.
+pub fn query(from: Option<u64>, to: Option<u64>, limit: usize) -> Vec<HistoryEntry> {
+    let (path, pending, retention_days) = {
+        let history = HISTORY.lock_safe();
+        (
+            history.path.clone(),
+            history.pending.clone(),
+            history.retention_days,
+        )
+    };
+    let cutoff = now_ms().saturating_sub(u64::from(retention_days) * MS_PER_DAY);
+    let from = from.unwrap_or(0).max(cutoff);
+    let to = to.unwrap_or(u64::MAX);
+
+    let mut entries = Vec::new();
+    if limit == 0 {
+        return entries;
+    }
+    // Newest first: false once the range has been passed
+    let mut take = |entry: HistoryEntry| -> bool {
+        if entry.at < from {
+            return false;
+        }
+        if entry.at <= to {
+            entries.push(entry);
+        }
+        entries.len() < limit
+    };
+
+    if !pending.into_iter().rev().all(&mut take) {
+        return entries;
+    }
+    let Some(path) = path else {
+        return entries;
+    };
+    for file in generations(&path) {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let more = text
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+            .all(&mut take);
+        if !more {
+            break;
+        }
+    }
+    entries
+}
+
+/// Forget everything recorded so far, on disk as well.
+pub fn clear() -> Result<(), String> {
+    let path = {
+        let mut history = HISTORY.lock_safe();
+        history.pending.clear();
+        history.path.clone()
+    };
+    let Some(path) = path else {
+        return Ok(());
+    };
+    for file in generations(&path) {
+        match std::fs::remove_file(&file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("Failed to delete {}: {}", file.display(), e));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn append(path: &Path, entries: &[HistoryEntry]) -> Result<(), String> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size >= MAX_FILE_BYTES {
+        rotate(path);
+    }
+    let mut text = String::new();
+    for entry in entries {
+        if let Ok(line) = serde_json::to_string(entry) {
+            text.push_str(&line);
+            text.push('\n');
+        }
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// `history.jsonl` and the rotated files, newest first.
+fn generations(path: &Path) -> Vec<PathBuf> {
+    std::iter::once(path.to_path_buf())
+        .chain((1..=KEEP_GENERATIONS).map(|n| generation(path, n)))
+        .collect()
+}
+
+fn generation(path: &Path, n: usize) -> PathBuf {
+    path.with_extension(format!("{}.jsonl", n))
+}
+
+/// Shift `history.N.jsonl` to `history.N+1.jsonl`, dropping the oldest, and move the
+/// current file to `history.1.jsonl`.
+fn rotate(path: &Path) {
+    let _ = std::fs::remove_file(generation(path, KEEP_GENERATIONS));
+    for n in (1..KEEP_GENERATIONS).rev() {
+        let _ = std::fs::rename(generation(path, n), generation(path, n + 1));
+    }
+    let _ = std::fs::rename(path, generation(path, 1));
+}
+
+/// Delete rotated files last written before the retention period.
+fn prune(path: &Path, retention_days: u32) {
+    let retention = Duration::from_millis(u64::from(retention_days) * MS_PER_DAY);
+    for file in generations(path).into_iter().skip(1) {
+        let expired = std::fs::metadata(&file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > retention);
+        if expired {
+            let _ = std::fs::remove_file(&file);
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,175 @@
+//! Local commands run on mute state transitions.
+//!
+//! A hook is a command line executed through the platform shell (`cmd /C` on Windows,
+//! `sh -c` elsewhere) with `TOGMIC_MUTED` set to `1` or `0`. Hooks run on their own
+//! thread so the toggle path never waits on them; a hook still running after the
+//! configured timeout is killed. Output is captured into the log.
+
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HookSettings {
+    #[serde(default)]
+    pub on_mute: Option<String>,
+    #[serde(default)]
+    pub on_unmute: Option<String>,
+    /// Seconds a hook may run before it is killed.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HookKind {
+    Mute,
+    Unmute,
+}
+
+impl HookSettings {
+    fn command_for(&self, kind: HookKind) -> Option<&str> {
+        let command = match kind {
+            HookKind::Mute => self.on_mute.as_deref(),
+            HookKind::Unmute => self.on_unmute.as_deref(),
+        };
+        command.filter(|c| !c.trim().is_empty())
+    }
+}
+
+/// Currently configured hooks; `None` disables them.
+static HOOKS: Lazy<Mutex<Option<HookSettings>>> = Lazy::new(|| Mutex::new(None));
+
+/// Replace the configured hooks.
+pub fn configure(settings: Option<HookSettings>) {
+    *HOOKS.lock_safe() = settings;
+}
+
+/// Run the hook for this transition in the background, if one is configured.
+pub fn notify(app: &AppHandle, muted: bool) {
+    let kind = if muted {
+        HookKind::Mute
+    } else {
+        HookKind::Unmute
+    };
+    let Some((command, timeout)) = lookup(kind) else {
+        return;
+    };
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = run(&command, muted, timeout) {
+            app.state::<AppState>().record_error("hook", e);
+        }
+    });
+}
+
+/// Run the hook of the given kind once and wait for it. Used by the settings UI to verify
+/// a script without toggling.
+pub fn test(kind: HookKind) -> Result<(), String> {
+    let (command, timeout) = lookup(kind).ok_or("No command configured for this hook")?;
+    run(&command, matches!(kind, HookKind::Mute), timeout)
+}
+
+fn lookup(kind: HookKind) -> Option<(String, Duration)> {
+    let hooks = HOOKS.lock_safe();
+    let settings = hooks.as_ref()?;
+    let command = settings.command_for(kind)?.to_string();
+    Some((command, Duration::from_secs(settings.timeout_secs)))
+}
+
+fn shell_command(command_line: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window on every toggle
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(command_line);
+        command.creation_flags(CREATE_NO_WINDOW);
+        command
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(command_line);
+        command
+    }
+}
+
+fn run(command_line: &str, muted: bool, timeout: Duration) -> Result<(), String> {
+    let mut child = shell_command(command_line)
+        .env("TOGMIC_MUTED", if muted { "1" } else { "0" })
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run hook '{}': {}", command_line, e))?;
+
+    // Drain both pipes on their own threads so a chatty hook can't block on a full pipe
+    let stdout = capture(child.stdout.take());
+    let stderr = capture(child.stderr.take());
+
+    // On timeout the reader threads are left to finish on their own: a grandchild
+    // started by the shell may still hold the pipes open after the kill
+    let Some(status) = wait_with_timeout(&mut child, timeout) else {
+        return Err(format!(
+            "Hook '{}' timed out after {}s and was killed",
+            command_line,
+            timeout.as_secs()
+        ));
+    };
+
+    for line in stdout.join().unwrap_or_default().lines() {
+        log::debug!("[hooks] stdout: {}", line);
+    }
+    for line in stderr.join().unwrap_or_default().lines() {
+        log::debug!("[hooks] stderr: {}", line);
+    }
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Hook '{}' exited with {}", command_line, status))
+    }
+}
+
+fn capture<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    })
+}
+
+/// Wait for the child to exit, killing it once `timeout` has passed. Returns `None` on
+/// timeout.
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Option<std::process::ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) if Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Mute after a microphone has been live for too long.
+//!
+//! `publish_mute_state` reports every state to `note_state`: the clock starts when the
+//! state becomes unmuted (or mixed, where some microphone is live) and any mute stops
+//! it, so unmuting again starts over. A monitor thread checks the clock every
+//! `CHECK_INTERVAL_SECS`. `warning_minutes` before the limit it emits `hot-mic-warning`,
+//! optionally with a notification and the error sound. At the limit it mutes through the
+//! regular path and emits `auto-muted { reason: "max-unmuted" }`, or, with
+//! `HotMicAction::Warn`, only warns once more.
+
+use crate::mute_state::MuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const CHECK_INTERVAL_SECS: u64 = 5;
+
+pub const HOT_MIC_WARNING: &str = "hot-mic-warning";
+
+/// `source` of the mute made here.
+pub const SOURCE_MAX_UNMUTED: &str = "maxUnmuted";
+
+/// What happens once the limit is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum HotMicAction {
+    #[default]
+    Mute,
+    /// Only warn; the microphone stays live.
+    Warn,
+}
+
+/// Unmuted time after which to act; 0 disables the watchdog.
+static LIMIT_SECS: AtomicU64 = AtomicU64::new(0);
+static WARNING_SECS: AtomicU64 = AtomicU64::new(0);
+static MUTE_AT_LIMIT: AtomicBool = AtomicBool::new(true);
+static NOTIFY: AtomicBool = AtomicBool::new(true);
+static PERIOD: Lazy<Mutex<Option<LivePeriod>>> = Lazy::new(|| Mutex::new(None));
+
+/// The current stretch of unmuted time.
+struct LivePeriod {
+    since: Instant,
+    warned: bool,
+    handled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HotMicWarningEvent {
+    /// How long the microphone has been live.
+    unmuted_secs: u64,
+    /// Until the limit; 0 once it is reached.
+    seconds_remaining: u64,
+    /// Whether the limit mutes (`HotMicAction::Mute`).
+    will_mute: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AutoMutedEvent {
+    reason: &'static str,
+}
+
+pub fn configure(
+    max_unmuted_minutes: u32,
+    warning_minutes: u32,
+    action: HotMicAction,
+    notify: bool,
+) {
+    LIMIT_SECS.store(u64::from(max_unmuted_minutes) * 60, Ordering::SeqCst);
+    WARNING_SECS.store(u64::from(warning_minutes) * 60, Ordering::SeqCst);
+    MUTE_AT_LIMIT.store(action == HotMicAction::Mute, Ordering::SeqCst);
+    NOTIFY.store(notify, Ordering::SeqCst);
+}
+
+/// Start or stop the clock for a newly published state.
+pub fn note_state(state: MuteState) {
+    let mut period = PERIOD.lock_safe();
+    match state {
+        MuteState::Muted => *period = None,
+        _ => {
+            period.get_or_insert_with(|| LivePeriod {
+                since: Instant::now(),
+                warned: false,
+                handled: false,
+            });
+        }
+    }
+}
+
+enum Due {
+    Warn { unmuted: u64, remaining: u64 },
+    Limit { unmuted: u64 },
+}
+
+/// Start the monitor thread. It idles along cheaply while the watchdog is disabled.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECS));
+
+        let limit = LIMIT_SECS.load(Ordering::SeqCst);
+        if limit == 0 {
+            continue;
+        }
+        let due = {
+            let mut period = PERIOD.lock_safe();
+            let Some(period) = period.as_mut().filter(|p| !p.handled) else {
+                continue;
+            };
+            let unmuted = period.since.elapsed().as_secs();
+            let warning = WARNING_SECS.load(Ordering::SeqCst);
+            if unmuted >= limit {
+                period.handled = true;
+                Due::Limit { unmuted }
+            } else if !period.warned && warning > 0 && unmuted + warning >= limit {
+                period.warned = true;
+                Due::Warn {
+                    unmuted,
+                    remaining: limit - unmuted,
+                }
+            } else {
+                continue;
+            }
+        };
+
+        let mute = MUTE_AT_LIMIT.load(Ordering::SeqCst);
+        match due {
+            Due::Warn { unmuted, remaining } => warn(&app, unmuted, remaining, mute),
+            Due::Limit { unmuted } if mute => {
+                log::info!("[hot-mic] live for {} s, muting", unmuted);
+                let state = app.state::<AppState>();
+                match crate::set_mute_internal(&state, &app, true, false, SOURCE_MAX_UNMUTED) {
+                    Ok(_) => {
+                        let _ = app.emit(
+                            "auto-muted",
+                            AutoMutedEvent {
+                                reason: "max-unmuted",
+                            },
+                        );
+                    }
+                    Err(e) => {
+                        state.record_error("max_unmuted", e);
+                    }
+                }
+            }
+            Due::Limit { unmuted } => warn(&app, unmuted, 0, false),
+        }
+    });
+}
+
+fn warn(app: &AppHandle, unmuted_secs: u64, seconds_remaining: u64, will_mute: bool) {
+    log::info!(
+        "[hot-mic] live for {} s, {} s left",
+        unmuted_secs,
+        seconds_remaining
+    );
+    let _ = app.emit(
+        HOT_MIC_WARNING,
+        HotMicWarningEvent {
+            unmuted_secs,
+            seconds_remaining,
+            will_mute,
+        },
+    );
+    if !NOTIFY.load(Ordering::SeqCst) || crate::focus_assist::suppressed() {
+        return;
+    }
+
+    crate::sound::play_error_sound();
+    let minutes = (unmuted_secs / 60).to_string();
+    let remaining = seconds_remaining.div_ceil(60).to_string();
+    let body = if will_mute {
+        crate::i18n::translate_with(
+            "notifyHotMicWarning",
+            &[("minutes", &minutes), ("remaining", &remaining)],
+        )
+    } else {
+        crate::i18n::translate_with("notifyHotMicLive", &[("minutes", &minutes)])
+    };
+    let _ = app
+        .notification()
+        .builder()
+        .title("TogMic")
+        .body(body)
+        .show();
+}
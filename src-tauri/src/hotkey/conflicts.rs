@@ -0,0 +1,186 @@
+//! Well-known shortcuts a hotkey would shadow.
+//!
+//! A registered global shortcut is swallowed before the focused application sees it, so
+//! binding Teams' own mute key (`CommandOrControl+Shift+M`) breaks it there, and a bare
+//! letter stops it from being typed anywhere. `check` compares a binding, and with
+//! `ignore_modifiers` every combination it expands to, against a curated table for the
+//! current platform. The result is advisory: saving such a profile is still allowed.
+
+use super::{HotkeyBinding, KeyRef, Modifier};
+use crate::scancode;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyConflict {
+    /// The colliding combination, which differs from the binding with `ignore_modifiers`.
+    pub shortcut: String,
+    /// What the combination normally does.
+    pub description: String,
+}
+
+/// Collisions on every platform.
+const COMMON: &[(&str, &str)] = &[
+    (
+        "CommandOrControl+Shift+M",
+        "Toggles mute in Microsoft Teams and Discord",
+    ),
+    (
+        "CommandOrControl+D",
+        "Toggles the microphone in Google Meet",
+    ),
+    ("CommandOrControl+E", "Toggles the camera in Google Meet"),
+    ("CommandOrControl+C", "Copy"),
+    ("CommandOrControl+V", "Paste"),
+    ("CommandOrControl+X", "Cut"),
+    ("CommandOrControl+Z", "Undo"),
+    ("CommandOrControl+A", "Select all"),
+    ("CommandOrControl+S", "Save"),
+    ("CommandOrControl+F", "Find"),
+    ("CommandOrControl+W", "Closes the current tab or window"),
+    ("CommandOrControl+T", "Opens a new tab"),
+    ("CommandOrControl+Tab", "Switches tabs"),
+];
+
+#[cfg(target_os = "windows")]
+const PLATFORM: &[(&str, &str)] = &[
+    ("Alt+A", "Toggles mute in Zoom"),
+    (
+        "Super+Alt+K",
+        "Toggles mute in the current call (Windows 11)",
+    ),
+    ("PrintScreen", "Takes a screenshot"),
+    ("Super+Shift+S", "Opens the Snipping Tool"),
+    ("Alt+PrintScreen", "Takes a screenshot of the active window"),
+    ("Alt+F4", "Closes the active window"),
+    ("Alt+Tab", "Switches windows"),
+    ("CommandOrControl+Shift+Escape", "Opens Task Manager"),
+    ("Super+L", "Locks the computer"),
+    ("Super+D", "Shows the desktop"),
+    ("Super+E", "Opens File Explorer"),
+    ("Super+R", "Opens the Run dialog"),
+    ("Super+G", "Opens the Xbox Game Bar"),
+    ("Super+Alt+R", "Starts recording with the Xbox Game Bar"),
+    ("Super+V", "Opens the clipboard history"),
+    ("Super+Space", "Switches the keyboard layout"),
+];
+
+#[cfg(target_os = "macos")]
+const PLATFORM: &[(&str, &str)] = &[
+    ("CommandOrControl+Shift+A", "Toggles mute in Zoom"),
+    ("CommandOrControl+Space", "Opens Spotlight"),
+    ("CommandOrControl+Q", "Quits the active application"),
+    ("CommandOrControl+H", "Hides the active application"),
+    ("CommandOrControl+M", "Minimizes the active window"),
+    ("CommandOrControl+Shift+3", "Takes a screenshot"),
+    (
+        "CommandOrControl+Shift+4",
+        "Takes a screenshot of a selection",
+    ),
+    ("CommandOrControl+Shift+5", "Opens the screenshot toolbar"),
+    (
+        "CommandOrControl+Alt+Escape",
+        "Opens Force Quit Applications",
+    ),
+    ("CommandOrControl+Shift+Q", "Logs out"),
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const PLATFORM: &[(&str, &str)] = &[
+    ("Alt+A", "Toggles mute in Zoom"),
+    ("PrintScreen", "Takes a screenshot"),
+    ("Shift+PrintScreen", "Takes a screenshot of a selection"),
+    ("Alt+F4", "Closes the active window"),
+    ("Alt+Tab", "Switches windows"),
+    ("CommandOrControl+Alt+T", "Opens a terminal"),
+    ("CommandOrControl+Alt+Delete", "Logs out"),
+    ("CommandOrControl+Alt+L", "Locks the screen"),
+    ("Super+L", "Locks the screen"),
+    ("Super+Space", "Switches the keyboard layout"),
+];
+
+/// Keys that type something or edit text when pressed without Control, Alt or Super.
+const TEXT_KEYS: &[&str] = &[
+    "Space",
+    "Enter",
+    "Tab",
+    "Backspace",
+    "Delete",
+    "Minus",
+    "Equal",
+    "BracketLeft",
+    "BracketRight",
+    "Backslash",
+    "Semicolon",
+    "Quote",
+    "Backquote",
+    "Comma",
+    "Period",
+    "Slash",
+];
+
+/// The well-known shortcuts `binding` would shadow; with `ignore_modifiers`, those of
+/// every combination it is registered as.
+pub fn check(binding: &HotkeyBinding, ignore_modifiers: bool) -> Vec<HotkeyConflict> {
+    // A scancode collides with whatever key it is in the current layout
+    let resolved;
+    let binding = match binding.key {
+        KeyRef::Scancode(_) => match scancode::resolve(binding) {
+            Ok(key) => {
+                resolved = key;
+                &resolved
+            }
+            Err(_) => return Vec::new(),
+        },
+        _ => binding,
+    };
+
+    let candidates = if ignore_modifiers {
+        binding.with_all_modifier_combinations()
+    } else {
+        vec![binding.clone()]
+    };
+    candidates
+        .iter()
+        .filter_map(|candidate| {
+            let shortcut = candidate.to_string();
+            let description = if types_text(candidate) {
+                Some("Types text, so it stops working in text fields")
+            } else {
+                COMMON
+                    .iter()
+                    .chain(PLATFORM)
+                    .find(|(known, _)| is_entry(known, candidate))
+                    .map(|(_, description)| *description)
+            }?;
+            Some(HotkeyConflict {
+                shortcut,
+                description: description.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Whether the table entry `known` is `binding`, whatever the order and case of its parts.
+fn is_entry(known: &str, binding: &HotkeyBinding) -> bool {
+    known.parse::<HotkeyBinding>().is_ok_and(|known| {
+        known.modifiers == binding.modifiers
+            && known
+                .key
+                .to_string()
+                .eq_ignore_ascii_case(&binding.key.to_string())
+    })
+}
+
+/// Whether `binding` is a key that produces text on its own (Shift only selects case).
+fn types_text(binding: &HotkeyBinding) -> bool {
+    let KeyRef::Named(key) = &binding.key else {
+        return false;
+    };
+    let unmodified = binding.modifiers.iter().all(|m| *m == Modifier::Shift);
+    let printable = key.chars().count() == 1
+        || key.strip_prefix("Key").is_some_and(|c| c.len() == 1)
+        || key.strip_prefix("Digit").is_some_and(|c| c.len() == 1)
+        || TEXT_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key));
+    unmodified && printable
+}
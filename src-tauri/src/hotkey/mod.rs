@@ -0,0 +1,416 @@
+//! Typed hotkey bindings.
+//!
+//! A profile's toggle key is a `HotkeyBinding`: modifiers plus a key, which is either a
+//! key name the shortcut plugin understands, a virtual-key code for the keyboard hook
+//! (`keyboard_hook`) or a scancode resolved against the current layout (`scancode`).
+//! `Display` and `FromStr` convert to and from the string form used by the plugin,
+//! `register_hotkey` and configs written before version 2 (`CommandOrControl+Shift+M`,
+//! `vk:0x13`, `sc:0x1A`). A binding still stored as a string deserializes as well, so
+//! profiles sent by an older frontend keep working; `migrate_config` rewrites the stored
+//! ones once.
+
+pub mod conflicts;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use tauri_plugin_global_shortcut::Shortcut;
+
+const VK_PREFIX: &str = "vk:";
+const SCANCODE_PREFIX: &str = "sc:";
+
+/// Modifiers in the order they are written.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Modifier {
+    /// Control on Windows and Linux, Command on macOS.
+    CommandOrControl,
+    Shift,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    fn as_str(self) -> &'static str {
+        match self {
+            Modifier::CommandOrControl => "CommandOrControl",
+            Modifier::Shift => "Shift",
+            Modifier::Alt => "Alt",
+            Modifier::Super => "Super",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "commandorcontrol" | "cmdorctrl" | "commandorctrl" | "cmdorcontrol" | "control"
+            | "ctrl" | "command" | "cmd" => Some(Modifier::CommandOrControl),
+            "shift" => Some(Modifier::Shift),
+            "alt" | "option" => Some(Modifier::Alt),
+            "super" | "meta" | "win" => Some(Modifier::Super),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum KeyRef {
+    /// A key name the shortcut plugin accepts (`M`, `F13`, `Space`, `NumpadAdd`).
+    Named(String),
+    /// A virtual-key code, bound through the keyboard hook (Windows).
+    Vk(u16),
+    /// A scancode; extended keys carry the `0xE0` prefix (`0xE04D`) (Windows).
+    Scancode(u16),
+}
+
+impl KeyRef {
+    /// The key for a scancode as the keyboard hook reports it.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn from_scan_code(scan_code: u32, extended: bool) -> Self {
+        let code = if extended {
+            0xE000 | scan_code
+        } else {
+            scan_code
+        };
+        KeyRef::Scancode(code as u16)
+    }
+
+    fn parse(key: &str) -> Result<Self, String> {
+        let number = |code: &str| {
+            code.strip_prefix("0x")
+                .or_else(|| code.strip_prefix("0X"))
+                .map_or_else(
+                    || code.parse().ok(),
+                    |hex| u16::from_str_radix(hex, 16).ok(),
+                )
+        };
+        if let Some(code) = key.strip_prefix(VK_PREFIX) {
+            return number(code)
+                .filter(|vk| (0x01..=0xFE).contains(vk))
+                .map(KeyRef::Vk)
+                .ok_or_else(|| format!("Invalid virtual-key code: '{}'", key));
+        }
+        if let Some(code) = key.strip_prefix(SCANCODE_PREFIX) {
+            return number(code)
+                .filter(|sc| matches!(sc, 0x01..=0x7F | 0xE001..=0xE07F))
+                .map(KeyRef::Scancode)
+                .ok_or_else(|| format!("Invalid scancode: '{}'", key));
+        }
+        if key.is_empty() {
+            return Err("Hotkey has no key".to_string());
+        }
+        Ok(KeyRef::Named(key.to_string()))
+    }
+}
+
+impl fmt::Display for KeyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyRef::Named(name) => f.write_str(name),
+            KeyRef::Vk(vk) => write!(f, "{}0x{:02X}", VK_PREFIX, vk),
+            KeyRef::Scancode(sc) => write!(f, "{}0x{:02X}", SCANCODE_PREFIX, sc),
+        }
+    }
+}
+
+/// Serialized form of `HotkeyBinding`.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct TypedBinding {
+    #[serde(default)]
+    modifiers: Vec<Modifier>,
+    key: KeyRef,
+}
+
+/// What a stored binding can look like: typed, or the string form of older configs.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum StoredBinding {
+    Typed(TypedBinding),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", try_from = "StoredBinding")]
+pub struct HotkeyBinding {
+    /// Sorted and without duplicates.
+    pub modifiers: Vec<Modifier>,
+    pub key: KeyRef,
+}
+
+impl HotkeyBinding {
+    pub fn new(modifiers: impl IntoIterator<Item = Modifier>, key: KeyRef) -> Self {
+        let modifiers: BTreeSet<Modifier> = modifiers.into_iter().collect();
+        Self {
+            modifiers: modifiers.into_iter().collect(),
+            key,
+        }
+    }
+
+    /// Check that the binding can be registered on this platform.
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.key {
+            KeyRef::Named(_) => Shortcut::from_str(&self.to_string())
+                .map(|_| ())
+                .map_err(|e| format!("Invalid hotkey '{}': {}", self, e)),
+            KeyRef::Vk(_) | KeyRef::Scancode(_) if cfg!(target_os = "windows") => Ok(()),
+            KeyRef::Vk(_) | KeyRef::Scancode(_) => Err(format!(
+                "Hotkey '{}' needs a keyboard hook, which is only available on Windows",
+                self
+            )),
+        }
+    }
+
+    /// The binding plus every combination of Control, Alt and Shift, for profiles with
+    /// `ignore_modifiers`.
+    pub fn with_all_modifier_combinations(&self) -> Vec<HotkeyBinding> {
+        let extra = [Modifier::CommandOrControl, Modifier::Alt, Modifier::Shift];
+        (0..1 << extra.len())
+            .map(|mask| {
+                let added = extra
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, m)| *m);
+                HotkeyBinding::new(
+                    self.modifiers.iter().copied().chain(added),
+                    self.key.clone(),
+                )
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+impl PartialOrd for HotkeyBinding {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HotkeyBinding {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.modifiers.len(), &self.modifiers, self.to_string()).cmp(&(
+            other.modifiers.len(),
+            &other.modifiers,
+            other.to_string(),
+        ))
+    }
+}
+
+impl fmt::Display for HotkeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier.as_str())?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for HotkeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // The last part is the key, which may be `+` itself (`Shift++`)
+        let (modifiers, key) = if s == "+" || s.ends_with("++") {
+            (&s[..s.len().saturating_sub(2)], "+")
+        } else {
+            match s.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key.trim()),
+                None => ("", s),
+            }
+        };
+        let modifiers = modifiers
+            .split('+')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(|m| Modifier::parse(m).ok_or_else(|| format!("Unknown modifier '{}'", m)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(HotkeyBinding::new(modifiers, KeyRef::parse(key)?))
+    }
+}
+
+/// Rewrite a binding stored in the string form as a typed one. Typed bindings and strings
+/// that don't parse are left alone.
+pub fn upgrade_stored(stored: &mut serde_json::Value) -> Result<(), String> {
+    let Some(text) = stored.as_str() else {
+        return Ok(());
+    };
+    match text.parse::<HotkeyBinding>() {
+        Ok(binding) => *stored = serde_json::to_value(binding).map_err(|e| e.to_string())?,
+        Err(e) => log::warn!("[config] keeping hotkey '{}': {}", text, e),
+    }
+    Ok(())
+}
+
+impl TryFrom<StoredBinding> for HotkeyBinding {
+    type Error = String;
+
+    fn try_from(stored: StoredBinding) -> Result<Self, Self::Error> {
+        match stored {
+            StoredBinding::Typed(typed) => Ok(HotkeyBinding::new(typed.modifiers, typed.key)),
+            StoredBinding::Text(text) => text.parse(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(text: &str) -> HotkeyBinding {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays_the_plugin_form() {
+        let binding = parse("Shift+CommandOrControl+M");
+        assert_eq!(
+            binding,
+            HotkeyBinding::new(
+                [Modifier::CommandOrControl, Modifier::Shift],
+                KeyRef::Named("M".to_string())
+            )
+        );
+        assert_eq!(binding.to_string(), "CommandOrControl+Shift+M");
+        assert_eq!(parse("F13").to_string(), "F13");
+    }
+
+    #[test]
+    fn accepts_modifier_aliases_and_duplicates() {
+        assert_eq!(parse("ctrl+alt+M"), parse("CommandOrControl+Alt+M"));
+        assert_eq!(parse("Cmd+Option+M"), parse("CommandOrControl+Alt+M"));
+        assert_eq!(parse("Win+Shift+Shift+M").to_string(), "Shift+Super+M");
+    }
+
+    #[test]
+    fn plus_can_be_the_key() {
+        assert_eq!(parse("Shift++").key, KeyRef::Named("+".to_string()));
+        assert_eq!(parse("Shift++").modifiers, [Modifier::Shift]);
+        assert_eq!(parse("+").key, KeyRef::Named("+".to_string()));
+    }
+
+    #[test]
+    fn parses_virtual_keys_and_scancodes() {
+        assert_eq!(parse("vk:0x13").key, KeyRef::Vk(0x13));
+        assert_eq!(parse("vk:19").key, KeyRef::Vk(19));
+        assert_eq!(parse("Alt+sc:0xE04D").key, KeyRef::Scancode(0xE04D));
+        assert_eq!(parse("vk:0x13").to_string(), "vk:0x13");
+        assert_eq!(parse("sc:0x1A").to_string(), "sc:0x1A");
+    }
+
+    #[test]
+    fn rejects_invalid_bindings() {
+        assert!("vk:0x00".parse::<HotkeyBinding>().is_err());
+        assert!("vk:0xFF".parse::<HotkeyBinding>().is_err());
+        assert!("sc:0x80".parse::<HotkeyBinding>().is_err());
+        assert!("sc:zz".parse::<HotkeyBinding>().is_err());
+        assert!("Hyper+M".parse::<HotkeyBinding>().is_err());
+        assert!("Shift+".parse::<HotkeyBinding>().is_err());
+    }
+
+    #[test]
+    fn deserializes_typed_and_string_forms() {
+        let typed = json!({ "modifiers": ["shift"], "key": { "type": "named", "value": "M" } });
+        let expected = parse("Shift+M");
+        assert_eq!(
+            serde_json::from_value::<HotkeyBinding>(typed.clone()).unwrap(),
+            expected
+        );
+        assert_eq!(serde_json::to_value(&expected).unwrap(), typed);
+        assert_eq!(
+            serde_json::from_value::<HotkeyBinding>(json!("Shift+M")).unwrap(),
+            expected
+        );
+        assert!(serde_json::from_value::<HotkeyBinding>(json!("vk:0")).is_err());
+    }
+
+    #[test]
+    fn upgrades_stored_strings_only() {
+        let mut stored = json!("CommandOrControl+Shift+M");
+        upgrade_stored(&mut stored).unwrap();
+        assert_eq!(
+            stored,
+            serde_json::to_value(parse("CommandOrControl+Shift+M")).unwrap()
+        );
+
+        let typed = stored.clone();
+        upgrade_stored(&mut stored).unwrap();
+        assert_eq!(stored, typed);
+
+        let mut broken = json!("Hyper+M");
+        upgrade_stored(&mut broken).unwrap();
+        assert_eq!(broken, json!("Hyper+M"));
+    }
+
+    #[test]
+    fn expands_every_modifier_combination() {
+        let combinations = parse("M").with_all_modifier_combinations();
+        assert_eq!(combinations.len(), 8);
+        assert_eq!(combinations[0], parse("M"));
+        assert!(combinations.contains(&parse("CommandOrControl+Alt+Shift+M")));
+
+        // Modifiers the binding already has are not added twice
+        assert_eq!(parse("Shift+M").with_all_modifier_combinations().len(), 4);
+    }
+
+    #[test]
+    fn every_modifier_combination_round_trips() {
+        let modifiers = [
+            Modifier::CommandOrControl,
+            Modifier::Shift,
+            Modifier::Alt,
+            Modifier::Super,
+        ];
+        let keys = ["+", "Space", "NumpadAdd", "M"]
+            .map(|name| KeyRef::Named(name.to_string()))
+            .into_iter()
+            .chain([KeyRef::Vk(0x13), KeyRef::Scancode(0xE04D)]);
+        for key in keys {
+            for mask in 0..1 << modifiers.len() {
+                let binding = HotkeyBinding::new(
+                    modifiers
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| mask & (1 << i) != 0)
+                        .map(|(_, m)| *m),
+                    key.clone(),
+                );
+                let text = binding.to_string();
+                assert_eq!(parse(&text), binding, "{}", text);
+                let typed = serde_json::to_value(&binding).unwrap();
+                assert_eq!(
+                    serde_json::from_value::<HotkeyBinding>(typed).unwrap(),
+                    binding,
+                    "{}",
+                    text
+                );
+                assert_eq!(
+                    serde_json::from_value::<HotkeyBinding>(json!(text)).unwrap(),
+                    binding,
+                    "{}",
+                    text
+                );
+                for expanded in binding.with_all_modifier_combinations() {
+                    assert_eq!(parse(&expanded.to_string()), expanded);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hook_keys_need_windows() {
+        assert!(parse("CommandOrControl+Shift+M").validate().is_ok());
+        assert_eq!(
+            parse("vk:0x13").validate().is_ok(),
+            cfg!(target_os = "windows")
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! One-shot capture of the next key press for the hotkey picker.
+//!
+//! The webview only sees keys while it is focused, and never some of them (PrintScreen).
+//! A capture suspends the active hotkeys and listens system-wide instead: on Windows
+//! through a low-level keyboard hook that also swallows the key, elsewhere by registering
+//! a fixed set of unmodified keys with the global shortcut plugin. Escape cancels. However
+//! the capture ends, the hotkeys are resumed (unless they were suspended already), and
+//! only one capture runs at a time.
+
+use crate::{suspend, AppHandle, AppState};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::Manager;
+
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum CaptureResult {
+    #[serde(rename_all = "camelCase")]
+    Captured {
+        /// Binding for `register_hotkey`: an accelerator, or a `vk:` binding for keys the
+        /// shortcut plugin can't express.
+        shortcut: String,
+        /// The same key as a layout-independent `sc:` binding, where scancodes are known.
+        scancode_shortcut: Option<String>,
+        /// The key as the keyboard layout names it, for display.
+        key_name: String,
+    },
+    Cancelled,
+    TimedOut,
+}
+
+/// Wait up to `timeout_ms` for the next key press.
+pub async fn capture(app: AppHandle, timeout_ms: u64) -> Result<CaptureResult, String> {
+    if CAPTURING.swap(true, Ordering::SeqCst) {
+        return Err("A hotkey capture is already running".to_string());
+    }
+
+    let state = app.state::<AppState>();
+    let was_suspended = suspend::is_suspended(&state);
+    if !was_suspended {
+        if let Err(e) = suspend::suspend(&app, 0) {
+            log::warn!("[capture] {}", e);
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let result = match platform::start(&app, tx) {
+        Ok(listener) => {
+            let timeout = Duration::from_millis(timeout_ms.min(MAX_TIMEOUT_MS));
+            let received = tauri::async_runtime::spawn_blocking(move || {
+                rx.recv_timeout(timeout).unwrap_or(CaptureResult::TimedOut)
+            })
+            .await
+            .map_err(|e| format!("Hotkey capture failed: {}", e));
+            platform::stop(&app, listener);
+            received
+        }
+        Err(e) => Err(e),
+    };
+
+    if !was_suspended {
+        if let Err(e) = suspend::resume(&app) {
+            state.record_error("capture_next_hotkey", e);
+        }
+    }
+    CAPTURING.store(false, Ordering::SeqCst);
+    result
+}
+
+#[cfg(target_os = "windows")]
+pub use platform::shortcut_key;
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::CaptureResult;
+    use crate::hotkey::{HotkeyBinding, KeyRef, Modifier};
+    use crate::keyboard_hook::HookThread;
+    use crate::MutexExt;
+    use once_cell::sync::Lazy;
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+    use windows::Win32::UI::Input::KeyboardAndMouse::*;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, HC_ACTION, KBDLLHOOKSTRUCT, LLKHF_EXTENDED, WM_KEYDOWN, WM_SYSKEYDOWN,
+    };
+
+    /// Where the hook procedure sends what it captured.
+    static SENDER: Lazy<Mutex<Option<mpsc::Sender<CaptureResult>>>> =
+        Lazy::new(|| Mutex::new(None));
+
+    pub type Listener = HookThread;
+
+    pub fn start(
+        _app: &crate::AppHandle,
+        tx: mpsc::Sender<CaptureResult>,
+    ) -> Result<Listener, String> {
+        *SENDER.lock_safe() = Some(tx);
+        HookThread::spawn(Some(hook_proc))
+    }
+
+    pub fn stop(_app: &crate::AppHandle, listener: Listener) {
+        listener.stop();
+        SENDER.lock_safe().take();
+    }
+
+    unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        let key_down = matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN);
+        if code == HC_ACTION as i32 && key_down {
+            let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            let vk = VIRTUAL_KEY(info.vkCode as u16);
+            // Modifiers are read when the main key arrives
+            if !is_modifier(vk) {
+                let result = if vk == VK_ESCAPE {
+                    CaptureResult::Cancelled
+                } else {
+                    let extended = (info.flags & LLKHF_EXTENDED).0 != 0;
+                    CaptureResult::Captured {
+                        // Keys the plugin can't bind go through the keyboard hook
+                        shortcut: match shortcut_key(vk) {
+                            Some(key) => with_modifiers(KeyRef::Named(key)),
+                            None => HotkeyBinding::new([], KeyRef::Vk(vk.0)).to_string(),
+                        },
+                        scancode_shortcut: Some(with_modifiers(KeyRef::from_scan_code(
+                            info.scanCode,
+                            extended,
+                        ))),
+                        key_name: key_name(info.scanCode, extended),
+                    }
+                };
+                if let Some(tx) = SENDER.lock_safe().as_ref() {
+                    let _ = tx.send(result);
+                }
+                // Swallow the key so it doesn't reach the focused application
+                return LRESULT(1);
+            }
+        }
+        CallNextHookEx(None, code, wparam, lparam)
+    }
+
+    fn is_modifier(vk: VIRTUAL_KEY) -> bool {
+        matches!(
+            vk,
+            VK_SHIFT
+                | VK_LSHIFT
+                | VK_RSHIFT
+                | VK_CONTROL
+                | VK_LCONTROL
+                | VK_RCONTROL
+                | VK_MENU
+                | VK_LMENU
+                | VK_RMENU
+                | VK_LWIN
+                | VK_RWIN
+        )
+    }
+
+    /// `key` with the modifiers held right now.
+    fn with_modifiers(key: KeyRef) -> String {
+        let held = |vk: VIRTUAL_KEY| unsafe { GetAsyncKeyState(vk.0 as i32) } < 0;
+        let modifiers = [
+            (held(VK_CONTROL), Modifier::CommandOrControl),
+            (held(VK_SHIFT), Modifier::Shift),
+            (held(VK_MENU), Modifier::Alt),
+            (held(VK_LWIN) || held(VK_RWIN), Modifier::Super),
+        ];
+        let held_modifiers = modifiers.into_iter().filter(|(h, _)| *h).map(|(_, m)| m);
+        HotkeyBinding::new(held_modifiers, key).to_string()
+    }
+
+    /// Name of the key at `scan_code` in the current layout.
+    fn key_name(scan_code: u32, extended: bool) -> String {
+        let lparam = (scan_code << 16) | (u32::from(extended) << 24);
+        let mut buffer = [0u16; 64];
+        let len = unsafe { GetKeyNameTextW(lparam as i32, &mut buffer) };
+        String::from_utf16_lossy(&buffer[..len.max(0) as usize])
+    }
+
+    /// Key name `Shortcut::from_str` accepts for `vk`, if any.
+    pub fn shortcut_key(vk: VIRTUAL_KEY) -> Option<String> {
+        let code = vk.0;
+        let name = match code {
+            0x30..=0x39 | 0x41..=0x5A => return Some(char::from(code as u8).to_string()),
+            0x60..=0x69 => return Some(format!("Numpad{}", code - 0x60)),
+            0x70..=0x87 => return Some(format!("F{}", code - 0x6F)),
+            _ => match vk {
+                VK_SPACE => "Space",
+                VK_RETURN => "Enter",
+                VK_TAB => "Tab",
+                VK_BACK => "Backspace",
+                VK_DELETE => "Delete",
+                VK_INSERT => "Insert",
+                VK_HOME => "Home",
+                VK_END => "End",
+                VK_PRIOR => "PageUp",
+                VK_NEXT => "PageDown",
+                VK_UP => "ArrowUp",
+                VK_DOWN => "ArrowDown",
+                VK_LEFT => "ArrowLeft",
+                VK_RIGHT => "ArrowRight",
+                VK_SNAPSHOT => "PrintScreen",
+                VK_SCROLL => "ScrollLock",
+                VK_PAUSE => "Pause",
+                VK_NUMLOCK => "NumLock",
+                VK_CAPITAL => "CapsLock",
+                VK_ADD => "NumpadAdd",
+                VK_SUBTRACT => "NumpadSubtract",
+                VK_MULTIPLY => "NumpadMultiply",
+                VK_DIVIDE => "NumpadDivide",
+                VK_DECIMAL => "NumpadDecimal",
+                VK_OEM_MINUS => "Minus",
+                VK_OEM_PLUS => "Equal",
+                VK_OEM_4 => "BracketLeft",
+                VK_OEM_6 => "BracketRight",
+                VK_OEM_5 => "Backslash",
+                VK_OEM_1 => "Semicolon",
+                VK_OEM_7 => "Quote",
+                VK_OEM_3 => "Backquote",
+                VK_OEM_COMMA => "Comma",
+                VK_OEM_PERIOD => "Period",
+                VK_OEM_2 => "Slash",
+                VK_VOLUME_MUTE => "AudioVolumeMute",
+                VK_VOLUME_DOWN => "AudioVolumeDown",
+                VK_VOLUME_UP => "AudioVolumeUp",
+                VK_MEDIA_PLAY_PAUSE => "MediaPlayPause",
+                VK_MEDIA_STOP => "MediaStop",
+                VK_MEDIA_NEXT_TRACK => "MediaTrackNext",
+                VK_MEDIA_PREV_TRACK => "MediaTrackPrevious",
+                _ => return None,
+            },
+        };
+        Some(name.to_string())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::CaptureResult;
+    use crate::AppHandle;
+    use std::str::FromStr;
+    use std::sync::mpsc;
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+    /// Keys listened for without a keyboard hook: the ones the webview can't capture
+    /// itself, plus Escape to cancel.
+    const KEYS: &[&str] = &[
+        "F1",
+        "F2",
+        "F3",
+        "F4",
+        "F5",
+        "F6",
+        "F7",
+        "F8",
+        "F9",
+        "F10",
+        "F11",
+        "F12",
+        "F13",
+        "F14",
+        "F15",
+        "F16",
+        "F17",
+        "F18",
+        "F19",
+        "F20",
+        "F21",
+        "F22",
+        "F23",
+        "F24",
+        "PrintScreen",
+        "ScrollLock",
+        "Pause",
+        "Insert",
+        "MediaPlayPause",
+        "MediaStop",
+        "MediaTrackNext",
+        "MediaTrackPrevious",
+        "AudioVolumeMute",
+        "Escape",
+    ];
+
+    pub struct Listener(Vec<Shortcut>);
+
+    pub fn start(app: &AppHandle, tx: mpsc::Sender<CaptureResult>) -> Result<Listener, String> {
+        let mut registered = Vec::new();
+        for key in KEYS {
+            let Ok(shortcut) = Shortcut::from_str(key) else {
+                continue;
+            };
+            let tx = tx.clone();
+            let result =
+                app.global_shortcut()
+                    .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                        if event.state != ShortcutState::Pressed {
+                            return;
+                        }
+                        let _ = tx.send(match *key {
+                            "Escape" => CaptureResult::Cancelled,
+                            _ => CaptureResult::Captured {
+                                shortcut: key.to_string(),
+                                scancode_shortcut: None,
+                                key_name: key.to_string(),
+                            },
+                        });
+                    });
+            match result {
+                Ok(()) => registered.push(shortcut),
+                Err(e) => log::debug!("[capture] could not listen for '{}': {}", key, e),
+            }
+        }
+        if registered.is_empty() {
+            return Err("Failed to register any key for hotkey capture".to_string());
+        }
+        Ok(Listener(registered))
+    }
+
+    pub fn stop(app: &AppHandle, listener: Listener) {
+        for shortcut in listener.0 {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    }
+}
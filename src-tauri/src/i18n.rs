@@ -0,0 +1,269 @@
+//! Locale bundles for the strings the backend shows itself (tray menu, tooltips,
+//! notifications).
+//!
+//! A bundle is a flat JSON object of keys to strings, named after its language code
+//! (`en.json`). English and German are embedded; any `*.json` dropped into the bundled
+//! `resources/locales` folder is loaded at startup as well, adding a language or replacing
+//! keys of an embedded one. Lookups fall back from the selected locale to its base
+//! language (`de` for `de-at`), then to English and finally to the key itself. The selected locale is persisted under `locale` in the
+//! config store so the tray is localized before the frontend has loaded.
+
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::path::BaseDirectory;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+const FALLBACK_LOCALE: &str = "en";
+const STORE_KEY: &str = "locale";
+const EMBEDDED: &[(&str, &str)] = &[
+    ("en", include_str!("../resources/locales/en.json")),
+    ("de", include_str!("../resources/locales/de.json")),
+];
+
+type Bundle = HashMap<String, String>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    pub code: String,
+    /// The language's own name (`languageName` in its bundle).
+    pub name: String,
+}
+
+#[derive(Default)]
+struct Locales {
+    bundles: BTreeMap<String, Bundle>,
+    current: String,
+    /// Strings pushed by the deprecated `update_tray_labels`; cleared on `set_locale`.
+    overrides: Bundle,
+}
+
+static LOCALES: Lazy<Mutex<Locales>> = Lazy::new(|| {
+    let bundles = EMBEDDED
+        .iter()
+        .filter_map(|(code, json)| Some((code.to_string(), serde_json::from_str(json).ok()?)))
+        .collect();
+    Mutex::new(Locales {
+        bundles,
+        current: FALLBACK_LOCALE.to_string(),
+        overrides: Bundle::new(),
+    })
+});
+
+/// Load the bundles from the resources folder and select the persisted locale.
+pub fn init(app: &AppHandle) {
+    if let Ok(dir) = app
+        .path()
+        .resolve("resources/locales", BaseDirectory::Resource)
+    {
+        load_dir(&dir);
+    }
+
+    let saved = app
+        .store(crate::config_store(app))
+        .ok()
+        .and_then(|store| store.get(STORE_KEY))
+        .and_then(|v| v.as_str().map(str::to_string));
+    if let Some(lang) = saved {
+        select(&lang);
+    }
+}
+
+fn load_dir(dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut locales = LOCALES.lock_safe();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(code) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let bundle: Bundle = match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+        {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                log::warn!("[i18n] skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        locales
+            .bundles
+            .entry(code.to_lowercase())
+            .or_default()
+            .extend(bundle);
+    }
+}
+
+/// Select the bundle for `lang` (e.g. `de` or `de-AT`), persist the choice and drop any
+/// `update_tray_labels` overrides. Returns the locale actually selected.
+pub fn set_locale(app: &AppHandle, lang: &str) -> Result<String, String> {
+    let selected = select(lang);
+    let store = app
+        .store(crate::config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    store.set(STORE_KEY, serde_json::Value::String(selected.clone()));
+    crate::save_config_store(app, false)?;
+    Ok(selected)
+}
+
+fn select(lang: &str) -> String {
+    LOCALES.lock_safe().select(lang)
+}
+
+/// The language part of a locale code (`de` for `de-at`).
+fn base_language(code: &str) -> &str {
+    code.split('-').next().unwrap_or_default()
+}
+
+impl Locales {
+    /// Pick the best bundle for `lang`: an exact match, then the base language, then
+    /// English.
+    fn select(&mut self, lang: &str) -> String {
+        let lang = lang.trim().to_lowercase().replace('_', "-");
+        let selected = [lang.as_str(), base_language(&lang)]
+            .into_iter()
+            .find(|code| self.bundles.contains_key(*code))
+            .unwrap_or(FALLBACK_LOCALE)
+            .to_string();
+        self.current = selected.clone();
+        self.overrides.clear();
+        selected
+    }
+
+    fn translate(&self, key: &str) -> String {
+        let lookup = |code: &str| self.bundles.get(code)?.get(key);
+        self.overrides
+            .get(key)
+            .or_else(|| lookup(&self.current))
+            .or_else(|| lookup(base_language(&self.current)))
+            .or_else(|| lookup(FALLBACK_LOCALE))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/// Put `overrides` on top of the selected bundle.
+pub fn set_overrides(overrides: impl IntoIterator<Item = (&'static str, String)>) {
+    let mut locales = LOCALES.lock_safe();
+    for (key, value) in overrides {
+        locales.overrides.insert(key.to_string(), value);
+    }
+}
+
+/// Available locales, sorted by code.
+pub fn list_locales() -> Vec<LocaleInfo> {
+    LOCALES
+        .lock_safe()
+        .bundles
+        .iter()
+        .map(|(code, bundle)| LocaleInfo {
+            code: code.clone(),
+            name: bundle
+                .get("languageName")
+                .cloned()
+                .unwrap_or_else(|| code.clone()),
+        })
+        .collect()
+}
+
+/// The string for `key` in the selected locale.
+pub fn translate(key: &str) -> String {
+    LOCALES.lock_safe().translate(key)
+}
+
+/// `translate` with `{name}` placeholders replaced by `args`.
+pub fn translate_with(key: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(translate(key), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(entries: &[(&str, &str)]) -> Bundle {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn locales() -> Locales {
+        let mut bundles = BTreeMap::new();
+        bundles.insert(
+            "en".to_string(),
+            bundle(&[("mute", "Mute"), ("quit", "Quit"), ("about", "About")]),
+        );
+        bundles.insert(
+            "de".to_string(),
+            bundle(&[("mute", "Stumm"), ("quit", "Beenden")]),
+        );
+        bundles.insert("de-at".to_string(), bundle(&[("mute", "Stummschalten")]));
+        Locales {
+            bundles,
+            current: FALLBACK_LOCALE.to_string(),
+            overrides: Bundle::new(),
+        }
+    }
+
+    #[test]
+    fn selects_exact_then_base_language_then_english() {
+        let mut locales = locales();
+        assert_eq!(locales.select("de_AT"), "de-at");
+        assert_eq!(locales.select(" DE-ch "), "de");
+        assert_eq!(locales.select("de"), "de");
+        assert_eq!(locales.select("fr-FR"), "en");
+        assert_eq!(locales.select(""), "en");
+    }
+
+    #[test]
+    fn translates_with_fallbacks() {
+        let mut locales = locales();
+        locales.select("de-AT");
+        assert_eq!(locales.translate("mute"), "Stummschalten");
+        assert_eq!(locales.translate("quit"), "Beenden");
+        assert_eq!(locales.translate("about"), "About");
+        assert_eq!(locales.translate("missing"), "missing");
+    }
+
+    #[test]
+    fn overrides_win_until_the_next_selection() {
+        let mut locales = locales();
+        locales.select("de");
+        locales
+            .overrides
+            .insert("mute".to_string(), "Custom".to_string());
+        assert_eq!(locales.translate("mute"), "Custom");
+        locales.select("de");
+        assert_eq!(locales.translate("mute"), "Stumm");
+    }
+
+    #[test]
+    fn embedded_bundles_cover_english() {
+        let embedded: BTreeMap<&str, Bundle> = EMBEDDED
+            .iter()
+            .map(|(code, json)| (*code, serde_json::from_str(json).unwrap()))
+            .collect();
+        let english = &embedded["en"];
+        for (code, bundle) in &embedded {
+            for key in bundle.keys() {
+                assert!(
+                    english.contains_key(key),
+                    "{} has '{}', en doesn't",
+                    code,
+                    key
+                );
+            }
+        }
+    }
+}
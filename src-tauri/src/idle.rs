@@ -0,0 +1,130 @@
+//! Mute after a period without keyboard/mouse input.
+//!
+//! A monitor thread checks the system idle time every `CHECK_INTERVAL_SECS`. Once it
+//! crosses the configured threshold an unmuted profile is muted through the regular path
+//! and `auto-muted { reason: "idle" }` is emitted. Coming back only unmutes again with
+//! `unmute_on_return`, and only if nobody changed the state in between. A manual mute
+//! change counts as activity, so it restarts the idle clock.
+
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+const CHECK_INTERVAL_SECS: u64 = 15;
+
+/// `source` of the mute changes made here; they don't count as manual changes.
+pub const SOURCE_IDLE: &str = "idle";
+pub const SOURCE_RETURN: &str = "idleReturn";
+
+/// Idle time after which to mute; 0 disables idle muting.
+static THRESHOLD_SECS: AtomicU64 = AtomicU64::new(0);
+static UNMUTE_ON_RETURN: AtomicBool = AtomicBool::new(false);
+/// Set while the current mute was made by this module.
+static AUTO_MUTED: AtomicBool = AtomicBool::new(false);
+static LAST_MANUAL_CHANGE: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+struct AutoMutedEvent {
+    reason: &'static str,
+}
+
+pub fn configure(minutes: u32, unmute_on_return: bool) {
+    THRESHOLD_SECS.store(u64::from(minutes) * 60, Ordering::SeqCst);
+    UNMUTE_ON_RETURN.store(unmute_on_return, Ordering::SeqCst);
+}
+
+/// Record a mute change made by the user (hotkey, tray, frontend, remote control).
+pub fn note_manual_change() {
+    *LAST_MANUAL_CHANGE.lock_safe() = Some(Instant::now());
+    AUTO_MUTED.store(false, Ordering::SeqCst);
+}
+
+/// Start the idle monitor thread. It idles along cheaply while idle muting is disabled.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut idle_handled = false;
+        let mut warned_unsupported = false;
+        loop {
+            std::thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECS));
+
+            let threshold = THRESHOLD_SECS.load(Ordering::SeqCst);
+            if threshold == 0 {
+                idle_handled = false;
+                continue;
+            }
+            let Some(system_idle) = system_idle_secs() else {
+                if !warned_unsupported {
+                    warned_unsupported = true;
+                    log::info!("[idle] idle detection is not supported on this platform");
+                }
+                continue;
+            };
+            let since_manual = LAST_MANUAL_CHANGE
+                .lock_safe()
+                .map_or(u64::MAX, |t| t.elapsed().as_secs());
+            let idle = system_idle.min(since_manual);
+
+            let state = app.state::<AppState>();
+            if idle >= threshold {
+                // Only once per idle period
+                if idle_handled {
+                    continue;
+                }
+                idle_handled = true;
+                if state.mute_state.is_muted() {
+                    continue;
+                }
+                match crate::set_mute_internal(&state, &app, true, false, SOURCE_IDLE) {
+                    Ok(_) => {
+                        AUTO_MUTED.store(true, Ordering::SeqCst);
+                        let _ = app.emit("auto-muted", AutoMutedEvent { reason: "idle" });
+                    }
+                    Err(e) => {
+                        state.record_error("idle_mute", e);
+                    }
+                }
+            } else if idle_handled {
+                idle_handled = false;
+                let auto_muted = AUTO_MUTED.swap(false, Ordering::SeqCst);
+                if auto_muted
+                    && UNMUTE_ON_RETURN.load(Ordering::SeqCst)
+                    && state.mute_state.is_muted()
+                {
+                    if let Err(e) =
+                        crate::set_mute_internal(&state, &app, false, false, SOURCE_RETURN)
+                    {
+                        state.record_error("idle_mute", e);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Seconds since the last keyboard/mouse input, or `None` where that isn't available.
+#[cfg(target_os = "windows")]
+fn system_idle_secs() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    unsafe {
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return None;
+        }
+        // Both are 32-bit tick counts; wrapping_sub stays correct across the 49-day wrap
+        Some(u64::from(GetTickCount().wrapping_sub(info.dwTime)) / 1000)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_idle_secs() -> Option<u64> {
+    None
+}
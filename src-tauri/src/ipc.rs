@@ -0,0 +1,324 @@
+//! Local IPC for scripting: a named pipe (`\\.\pipe\togmic`) on Windows and a Unix domain
+//! socket (`$XDG_RUNTIME_DIR/togmic.sock`, falling back to a private `togmic-$USER` directory
+//! in the temp dir) elsewhere. Off unless `localIpc` is enabled in the settings.
+//!
+//! Each request is one line of JSON and gets exactly one line of JSON back:
+//!   → {"cmd":"getState"}
+//!   ← {"ok":true,"data":{"muted":true,"profile":{...}}}
+//!   → {"cmd":"nope"}
+//!   ← {"ok":false,"error":"unknown variant `nope`, ..."}
+//!
+//! Commands are the ones understood by `commands::external::dispatch`. See
+//! `scripts/togmic-ipc.ps1` and `scripts/togmic-ipc.sh` for usage examples.
+
+use crate::commands::external::{self, ControlCommand};
+use crate::{AppHandle, AppState};
+use interprocess::local_socket::{prelude::*, ListenerOptions, Stream};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::Duration;
+use tauri::Manager;
+
+/// How long a client may stay silent before it is dropped. Clients are served one at a
+/// time, so an idle connection would otherwise block everyone else.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The socket path (Unix) or pipe name (Windows) clients connect to.
+pub fn endpoint() -> String {
+    #[cfg(target_os = "windows")]
+    {
+        r"\\.\pipe\togmic".to_string()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        socket_dir()
+            .join("togmic.sock")
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// `$XDG_RUNTIME_DIR` is private to the user already. The temp dir is shared on most
+/// systems, so the fallback is a subdirectory only the user can enter.
+#[cfg(not(target_os = "windows"))]
+fn socket_dir() -> std::path::PathBuf {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return dir.into();
+    }
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!("togmic-{}", user))
+}
+
+/// Create `dir` with mode 0700 unless it exists, and refuse directories other users could
+/// reach into.
+#[cfg(not(target_os = "windows"))]
+fn ensure_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    let meta = std::fs::symlink_metadata(dir)?;
+    if !meta.is_dir() || meta.permissions().mode() & 0o077 != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Spawn the IPC server thread. Clients are served one after another; a client that
+/// misbehaves or stays idle for `CLIENT_TIMEOUT` only ends its own connection.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let listener = match create_listener() {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!("[ipc] Failed to listen on {}: {}", endpoint(), e);
+                return;
+            }
+        };
+
+        for connection in listener.incoming() {
+            match connection {
+                Ok(stream) => {
+                    if let Err(e) = stream.set_recv_timeout(Some(CLIENT_TIMEOUT)) {
+                        log::warn!("[ipc] Failed to set client timeout: {}", e);
+                    }
+                    serve_client(stream, |request| handle_request(&app, request));
+                }
+                Err(e) => log::warn!("[ipc] Failed to accept client: {}", e),
+            }
+        }
+    });
+}
+
+fn create_listener() -> std::io::Result<interprocess::local_socket::Listener> {
+    #[cfg(target_os = "windows")]
+    {
+        use interprocess::local_socket::GenericNamespaced;
+        let name = "togmic".to_ns_name::<GenericNamespaced>()?;
+        ListenerOptions::new().name(name).create_sync()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let dir = socket_dir();
+        ensure_private_dir(&dir)?;
+        create_socket_listener(&dir.join("togmic.sock"))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_socket_listener(
+    path: &std::path::Path,
+) -> std::io::Result<interprocess::local_socket::Listener> {
+    use interprocess::local_socket::GenericFilePath;
+    use std::os::unix::fs::PermissionsExt;
+
+    if path.exists() {
+        // Only a socket file left behind by a crash may be replaced; one that still accepts
+        // connections belongs to another running instance.
+        if Stream::connect(path.to_fs_name::<GenericFilePath>()?).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                "another instance is already listening",
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = ListenerOptions::new()
+        .name(path.to_fs_name::<GenericFilePath>()?)
+        .create_sync()?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Answer each line read from `stream` with the line `handle` returns, until the client
+/// disconnects, times out or sends something that is not text.
+fn serve_client<S: Read + Write>(stream: S, mut handle: impl FnMut(&str) -> Value) {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = handle(line.trim());
+        let stream = reader.get_mut();
+        if writeln!(stream, "{}", reply)
+            .and_then(|_| stream.flush())
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn handle_request(app: &AppHandle, request: &str) -> Value {
+    let result = parse_request(request).and_then(|command| external::dispatch(app, "ipc", command));
+    if let Err(e) = &result {
+        app.state::<AppState>().record_error("ipc", e.clone());
+    }
+    response(result)
+}
+
+fn parse_request(request: &str) -> Result<ControlCommand, String> {
+    serde_json::from_str(request).map_err(|e| e.to_string())
+}
+
+fn response(result: Result<Value, String>) -> Value {
+    match result {
+        Ok(data) => json!({ "ok": true, "data": data }),
+        Err(e) => json!({ "ok": false, "error": e }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A client that sends `input` and collects whatever the server writes back.
+    struct FakeClient {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for FakeClient {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for FakeClient {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn exchange(input: &str) -> Vec<Value> {
+        let mut client = FakeClient {
+            input: Cursor::new(input.as_bytes().to_vec()),
+            output: Vec::new(),
+        };
+        serve_client(&mut client, |request| {
+            response(parse_request(request).map(|command| json!(format!("{:?}", command))))
+        });
+        String::from_utf8(client.output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn answers_every_request_with_one_line() {
+        let replies = exchange("{\"cmd\":\"getState\"}\n\n  \n{\"cmd\":\"toggle\"}\n");
+        assert_eq!(replies.len(), 2);
+        assert!(replies.iter().all(|r| r["ok"] == json!(true)));
+    }
+
+    #[test]
+    fn serves_a_last_request_without_newline() {
+        let replies = exchange("{\"cmd\":\"set\",\"muted\":true}");
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0]["ok"], json!(true));
+    }
+
+    #[test]
+    fn bad_requests_get_an_error_and_keep_the_connection() {
+        let replies =
+            exchange("{\"cmd\":\"nope\"}\nnot json\n{\"cmd\":\"set\"}\n{\"cmd\":\"getState\"}\n");
+        assert_eq!(replies.len(), 4);
+        for reply in &replies[..3] {
+            assert_eq!(reply["ok"], json!(false));
+            assert!(reply["error"].as_str().is_some_and(|e| !e.is_empty()));
+        }
+        assert_eq!(replies[3]["ok"], json!(true));
+    }
+
+    #[test]
+    fn response_shapes() {
+        assert_eq!(
+            response(Ok(json!({ "muted": true }))),
+            json!({ "ok": true, "data": { "muted": true } })
+        );
+        assert_eq!(
+            response(Err("boom".into())),
+            json!({ "ok": false, "error": "boom" })
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    mod socket {
+        use super::super::*;
+        use std::os::unix::fs::PermissionsExt;
+        use std::path::PathBuf;
+
+        fn temp_dir(name: &str) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!(
+                "togmic-ipc-test-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            ensure_private_dir(&dir).unwrap();
+            dir
+        }
+
+        #[test]
+        fn socket_is_private() {
+            let dir = temp_dir("private");
+            let path = dir.join("togmic.sock");
+            let _listener = create_socket_listener(&path).unwrap();
+            let dir_mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+            let socket_mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(dir_mode & 0o777, 0o700);
+            assert_eq!(socket_mode & 0o777, 0o600);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn refuses_shared_directories() {
+            let dir = temp_dir("shared");
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+            assert!(ensure_private_dir(&dir).is_err());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn keeps_a_live_socket_and_replaces_a_stale_one() {
+            let dir = temp_dir("probe");
+            let path = dir.join("togmic.sock");
+
+            let listener = create_socket_listener(&path).unwrap();
+            let err = create_socket_listener(&path).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+            assert!(path.exists());
+
+            // Dropping the listener may or may not remove the file; a leftover must not
+            // keep the next instance from listening.
+            drop(listener);
+            std::fs::write(&path, b"").ok();
+            assert!(create_socket_listener(&path).is_ok());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+}
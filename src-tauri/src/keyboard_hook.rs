@@ -0,0 +1,153 @@
+//! Low-level keyboard hook for bindings the shortcut plugin can't express (Windows).
+//!
+//! Such bindings have a `KeyRef::Vk` key (written `vk:<code>`, a virtual-key code in
+//! decimal or `0x` hex). While at least one is bound, a `WH_KEYBOARD_LL` hook runs
+//! on its own thread; a press of a bound key runs the action it was bound to (toggling,
+//! for the profile hotkey) and is swallowed. Held keys repeat their key-down message, so only the first one of a press
+//! counts. Modifiers are ignored. The hook is removed once nothing is bound anymore.
+
+#[cfg(target_os = "windows")]
+pub use windows_hook::{bind, is_bound, unbind_all, HookThread};
+
+#[cfg(target_os = "windows")]
+mod windows_hook {
+    use crate::AppHandle;
+    use crate::MutexExt;
+    use once_cell::sync::Lazy;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use windows::Win32::Foundation::{HINSTANCE, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx,
+        HC_ACTION, HOOKPROC, KBDLLHOOKSTRUCT, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_QUIT,
+        WM_SYSKEYDOWN, WM_SYSKEYUP,
+    };
+
+    /// Bound virtual-key codes and what a press runs.
+    static BOUND: Lazy<Mutex<HashMap<u16, fn(&AppHandle)>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+    /// Bound keys currently held down, for auto-repeat suppression.
+    static PRESSED: Lazy<Mutex<HashSet<u16>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+    static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+    static HOOK: Lazy<Mutex<Option<HookThread>>> = Lazy::new(|| Mutex::new(None));
+
+    /// A `WH_KEYBOARD_LL` hook and the thread pumping messages for it.
+    pub struct HookThread {
+        thread_id: u32,
+        thread: std::thread::JoinHandle<()>,
+    }
+
+    impl HookThread {
+        /// Install `proc` on a new thread, which pumps messages until `stop`.
+        pub fn spawn(proc: HOOKPROC) -> Result<Self, String> {
+            let (ready_tx, ready_rx) = mpsc::channel::<Result<u32, String>>();
+            let thread = std::thread::spawn(move || unsafe {
+                let module: HINSTANCE = GetModuleHandleW(None).unwrap_or_default().into();
+                let hook = match SetWindowsHookExW(WH_KEYBOARD_LL, proc, module, 0) {
+                    Ok(hook) => hook,
+                    Err(e) => {
+                        let _ =
+                            ready_tx.send(Err(format!("Failed to install keyboard hook: {}", e)));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(GetCurrentThreadId()));
+
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, None, 0, 0).as_bool() {}
+                let _ = UnhookWindowsHookEx(hook);
+            });
+
+            match ready_rx.recv() {
+                Ok(Ok(thread_id)) => Ok(Self { thread_id, thread }),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err("Keyboard hook thread exited".to_string()),
+            }
+        }
+
+        /// Remove the hook and wait for its thread to end.
+        pub fn stop(self) {
+            unsafe {
+                let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+            }
+            let _ = self.thread.join();
+        }
+    }
+
+    /// Run `action` on presses of `vk`, installing the hook if it isn't running yet.
+    pub fn bind(app: &AppHandle, vk: u16, action: fn(&AppHandle)) -> Result<(), String> {
+        *APP.lock_safe() = Some(app.clone());
+        let mut hook = HOOK.lock_safe();
+        if hook.is_none() {
+            *hook = Some(HookThread::spawn(Some(hook_proc))?);
+            log::info!("[hotkey] keyboard hook installed");
+        }
+        BOUND.lock_safe().insert(vk, action);
+        Ok(())
+    }
+
+    pub fn is_bound(vk: u16) -> bool {
+        BOUND.lock_safe().contains_key(&vk)
+    }
+
+    /// Drop every binding and remove the hook.
+    pub fn unbind_all() {
+        BOUND.lock_safe().clear();
+        PRESSED.lock_safe().clear();
+        if let Some(hook) = HOOK.lock_safe().take() {
+            hook.stop();
+            log::info!("[hotkey] keyboard hook removed");
+        }
+    }
+
+    unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 {
+            let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            let vk = info.vkCode as u16;
+            // Presses driving the LED indicator aren't the user's
+            let action = if info.dwExtraInfo == crate::led_indicator::INJECTED_MARKER {
+                None
+            } else {
+                BOUND.lock_safe().get(&vk).copied()
+            };
+            if let Some(action) = action {
+                match wparam.0 as u32 {
+                    WM_KEYDOWN | WM_SYSKEYDOWN => {
+                        if PRESSED.lock_safe().insert(vk) {
+                            // The hook must return quickly, so act elsewhere
+                            if let Some(app) = APP.lock_safe().clone() {
+                                std::thread::spawn(move || action(&app));
+                            }
+                        }
+                    }
+                    WM_KEYUP | WM_SYSKEYUP => {
+                        PRESSED.lock_safe().remove(&vk);
+                    }
+                    _ => {}
+                }
+                return LRESULT(1);
+            }
+        }
+        CallNextHookEx(None, code, wparam, lparam)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn bind(
+    _app: &crate::AppHandle,
+    _vk: u16,
+    _action: fn(&crate::AppHandle),
+) -> Result<(), String> {
+    Err("Virtual-key bindings are only supported on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_bound(_vk: u16) -> bool {
+    false
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn unbind_all() {}
@@ -0,0 +1,175 @@
+//! A keyboard lock-key LED as a physical mute indicator (Windows).
+//!
+//! With `AppSettings::led_indicator` on, the LED of the chosen lock key (Scroll Lock
+//! unless configured otherwise) is lit while the microphones are muted and dark otherwise.
+//! The LED follows the key's toggle state, so `show` presses the key with `SendInput`
+//! whenever `GetKeyState` disagrees with the mute state. Those presses carry
+//! `INJECTED_MARKER` so our own keyboard hook lets them pass.
+//!
+//! The key stays the user's: `show` only runs on mute transitions, so a press of the key
+//! (or another application toggling it) in between is left alone until the next
+//! transition. The state the key had when the indicator was turned on is put back when
+//! it is turned off or TogMic exits, unless someone else toggled the key since our last
+//! press, in which case the key is left as they set it.
+
+use crate::mute_state::MuteState;
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// `dwExtraInfo` of the key presses sent here.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub const INJECTED_MARKER: usize = 0x546f_674d;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+#[allow(clippy::enum_variant_names)]
+pub enum LedKey {
+    #[default]
+    ScrollLock,
+    NumLock,
+    CapsLock,
+}
+
+struct Indicator {
+    key: LedKey,
+    /// Toggle state of the key when the indicator was turned on.
+    original: bool,
+    /// Toggle state of our last press, if any.
+    last_set: Option<bool>,
+}
+
+/// The running indicator; `None` while it is off.
+static INDICATOR: Lazy<Mutex<Option<Indicator>>> = Lazy::new(|| Mutex::new(None));
+
+/// Turn the indicator on with `key`, or off. Turning it off or switching keys restores
+/// the previous key first.
+pub fn configure(enabled: bool, key: LedKey, mute_state: MuteState) {
+    let mut indicator = INDICATOR.lock_safe();
+    if indicator.as_ref().is_some_and(|i| enabled && i.key == key) {
+        return;
+    }
+    if let Some(previous) = indicator.take() {
+        restore_key(&previous);
+    }
+    if !enabled {
+        return;
+    }
+    let Some(original) = platform::is_toggled(key) else {
+        log::warn!("[led] lock key LEDs are not supported on this platform");
+        return;
+    };
+    log::info!("[led] indicating mute state on {:?}", key);
+    let mut new = Indicator {
+        key,
+        original,
+        last_set: None,
+    };
+    apply(&mut new, mute_state);
+    *indicator = Some(new);
+}
+
+/// Light the LED if `mute_state` is muted, otherwise turn it off.
+pub fn show(mute_state: MuteState) {
+    if let Some(indicator) = INDICATOR.lock_safe().as_mut() {
+        apply(indicator, mute_state);
+    }
+}
+
+/// Put the key back the way it was before the indicator; for exit.
+pub fn restore() {
+    if let Some(indicator) = INDICATOR.lock_safe().take() {
+        restore_key(&indicator);
+    }
+}
+
+fn apply(indicator: &mut Indicator, mute_state: MuteState) {
+    let lit = mute_state == MuteState::Muted;
+    let Some(current) = platform::is_toggled(indicator.key) else {
+        return;
+    };
+    if indicator.last_set.is_some_and(|set| set != current) {
+        log::debug!("[led] {:?} was toggled outside TogMic", indicator.key);
+    }
+    if current != lit {
+        if let Err(e) = platform::toggle(indicator.key) {
+            log::warn!("[led] {}", e);
+            return;
+        }
+    }
+    indicator.last_set = Some(lit);
+}
+
+fn restore_key(indicator: &Indicator) {
+    let Some(current) = platform::is_toggled(indicator.key) else {
+        return;
+    };
+    // Someone else toggled it since; leave it as they set it
+    if indicator.last_set.is_some_and(|set| set != current) {
+        return;
+    }
+    if current != indicator.original {
+        if let Err(e) = platform::toggle(indicator.key) {
+            log::warn!("[led] failed to restore {:?}: {}", indicator.key, e);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{LedKey, INJECTED_MARKER};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+        KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_CAPITAL, VK_NUMLOCK, VK_SCROLL,
+    };
+
+    fn vk(key: LedKey) -> VIRTUAL_KEY {
+        match key {
+            LedKey::ScrollLock => VK_SCROLL,
+            LedKey::NumLock => VK_NUMLOCK,
+            LedKey::CapsLock => VK_CAPITAL,
+        }
+    }
+
+    pub fn is_toggled(key: LedKey) -> Option<bool> {
+        // The low bit is the toggle state
+        Some(unsafe { GetKeyState(i32::from(vk(key).0)) } & 1 != 0)
+    }
+
+    /// Press and release `key`.
+    pub fn toggle(key: LedKey) -> Result<(), String> {
+        let input = |flags: KEYBD_EVENT_FLAGS| INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk(key),
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: INJECTED_MARKER,
+                },
+            },
+        };
+        let inputs = [input(KEYBD_EVENT_FLAGS(0)), input(KEYEVENTF_KEYUP)];
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            return Err(format!("failed to toggle {:?}", key));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::LedKey;
+
+    pub fn is_toggled(_key: LedKey) -> Option<bool> {
+        None
+    }
+
+    pub fn toggle(_key: LedKey) -> Result<(), String> {
+        Err("Lock key LEDs are only supported on Windows".to_string())
+    }
+}
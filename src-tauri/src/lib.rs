@@ -1,21 +1,94 @@
+mod api;
 mod audio;
+mod audio_error;
+mod audio_init;
+mod audio_worker;
+mod capture_switch;
+mod cli;
+mod commands;
+mod config_location;
+mod config_recovery;
+mod config_schema;
+mod crash;
+mod device_aliases;
+mod device_watch;
+mod diagnostics;
+mod events;
+mod exit_restore;
+mod focus_assist;
+mod foreground;
+mod hard_mute;
+mod hid_buttons;
+mod hidden_devices;
+mod history;
+mod hooks;
+mod hot_mic;
+mod hotkey;
+mod hotkey_capture;
+mod i18n;
+mod idle;
+mod ipc;
+mod keyboard_hook;
+mod led_indicator;
+mod lights;
+mod logging;
+mod mic_usage;
+mod missing_devices;
+mod mqtt;
+mod mute_lock;
+mod mute_notify;
+mod mute_state;
+mod obs;
+mod peer_sync;
+mod poll;
+mod portal_shortcuts;
+mod reconcile;
+mod scancode;
+mod scene;
+mod session_change;
+mod shortcuts;
+#[cfg(debug_assertions)]
+mod simulate;
+mod solo;
 mod sound;
+mod speaking;
+mod stats;
+mod streamdeck;
+mod suspend;
+mod sync_watchdog;
+mod system_events;
+mod temporary_unmute;
+#[cfg(test)]
+mod test_support;
+mod tray_icons;
+mod undo;
+mod updates;
+mod voice_activity;
+mod webhooks;
+mod window_state;
 
 use audio::{AudioController, AudioDevice, PlatformAudioController};
+use audio_error::{report_error, ErrorCategory};
+use commands::external::{self, ExternalAction};
+use mute_state::{AtomicMuteState, DeviceMuteState, MuteState, ProfileMuteState, ToggleScope};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-// note: mpsc/debounce not used yet
-use once_cell::sync::Lazy;
-use std::str::FromStr;
+use std::time::{Duration, Instant};
 use tauri::image::Image as TauriImage;
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
-    AppHandle, Emitter, Manager, State,
+    menu::{CheckMenuItem, CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder},
+    Emitter, Manager, State,
 };
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
 use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_opener::OpenerExt;
 use tauri_plugin_store::StoreExt;
 
 /// Locking helper that recovers from poisoning instead of panicking. A panic while a
@@ -23,6 +96,14 @@ use tauri_plugin_store::StoreExt;
 /// mutex, turning every subsequent `.lock_safe()` into a cascading panic that bricks
 /// the app. The protected state here is plain config/flags, so reusing it after a panic
 /// is safe.
+/// The Tauri runtime the app runs on. Tests run on the mock runtime of `tauri::test`, so
+/// they can drive commands and check the events they emit.
+#[cfg(not(test))]
+type AppRuntime = tauri::Wry;
+#[cfg(test)]
+type AppRuntime = tauri::test::MockRuntime;
+type AppHandle = tauri::AppHandle<AppRuntime>;
+
 trait MutexExt<T> {
     fn lock_safe(&self) -> std::sync::MutexGuard<'_, T>;
 }
@@ -33,113 +114,930 @@ impl<T> MutexExt<T> for Mutex<T> {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct HotkeyProfile {
     pub id: String,
     pub name: String,
-    pub toggle_key: String,
+    pub toggle_key: hotkey::HotkeyBinding,
+    /// Devices muted and unmuted together; scenes use `device_states` instead.
+    #[serde(default)]
     pub device_ids: Vec<String>,
+    /// Target state per device, which makes the profile a scene; see `scene`.
+    #[serde(default)]
+    pub device_states: Vec<scene::SceneDevice>,
     #[serde(default)]
     pub ignore_modifiers: bool,
+    /// Process names (e.g. `obs64.exe`) that activate this profile when focused.
+    #[serde(default)]
+    pub auto_activate_apps: Vec<String>,
+    /// Switch back to the previous profile once the auto-activating app loses focus.
+    #[serde(default)]
+    pub revert_on_blur: bool,
+    /// Process names that activate and unmute this profile when they start capturing from
+    /// a microphone. Needs `AppSettings::track_mic_usage`.
+    #[serde(default)]
+    pub activate_on_capture_by: Vec<String>,
+    /// HID mute button toggling this profile, as `vvvv:pppp` (see `list_hid_buttons`).
+    #[serde(default)]
+    pub hid_device: Option<String>,
+    /// Second hotkey that unmutes for `temporary_unmute_seconds`, then mutes again.
+    #[serde(default)]
+    pub temporary_unmute_key: Option<hotkey::HotkeyBinding>,
+    #[serde(default = "default_temporary_unmute_seconds")]
+    pub temporary_unmute_seconds: u32,
+    /// While muted, mute devices again that other applications unmute.
+    #[serde(default)]
+    pub mute_lock: bool,
+    #[serde(default)]
+    pub mute_strategy: hard_mute::MuteStrategy,
+    /// Keep every input device the profile doesn't cover muted while it is active.
+    #[serde(default)]
+    pub solo: bool,
+    /// Make the profile's first device the system default for this role on activation.
+    #[serde(default)]
+    pub make_default: Option<audio::DefaultRole>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl HotkeyProfile {
+    pub fn is_scene(&self) -> bool {
+        !self.device_states.is_empty()
+    }
+
+    /// The devices the profile lists: the scene's, or `device_ids`.
+    pub fn listed_device_ids(&self) -> Vec<String> {
+        if self.is_scene() {
+            self.device_states
+                .iter()
+                .map(|d| d.device_id.clone())
+                .collect()
+        } else {
+            self.device_ids.clone()
+        }
+    }
+
+    pub fn lists_device(&self, device_id: &str) -> bool {
+        if self.is_scene() {
+            self.device_states.iter().any(|d| d.device_id == device_id)
+        } else {
+            self.device_ids.iter().any(|id| id == device_id)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
     #[serde(default)]
     pub start_muted: bool,
     #[serde(default)]
     pub autostart: bool,
+    /// Check for updates in the background (at most every few hours).
     #[serde(default = "default_check_updates")]
     pub check_updates: bool,
+    /// Release channel the updater follows.
+    #[serde(default)]
+    pub update_channel: updates::UpdateChannel,
     #[serde(default)]
     pub close_to_tray: bool,
+    /// Hide the window to the tray when it is minimized, leaving no taskbar button.
+    #[serde(default)]
+    pub minimize_to_tray: bool,
+    /// Offer "Open config folder" in the tray menu.
+    #[serde(default)]
+    pub tray_config_folder: bool,
+    /// Left-clicking the tray icon toggles mute instead of opening the menu. Off by
+    /// default on macOS, where a menu bar icon is expected to open its menu.
+    #[serde(default = "default_tray_left_click_toggles")]
+    pub tray_left_click_toggles: bool,
+    /// Show the embedded tray icons instead of the generated ones with a state badge.
+    #[serde(default)]
+    pub classic_tray_icons: bool,
+    #[serde(default)]
+    pub tray_icon_style: tray_icons::TrayIconStyle,
+    /// Tray menu entries that open the window at a frontend route, below "Show window".
+    #[serde(default = "default_tray_extra_items")]
+    pub tray_extra_items: Vec<TrayMenuItem>,
     #[serde(default)]
     pub start_minimized: bool,
+    /// Port of the local WebSocket control API; `None` keeps the server off.
+    #[serde(default)]
+    pub api_port: Option<u16>,
+    /// MQTT broker connection; `None` keeps the integration dormant.
+    #[serde(default)]
+    pub mqtt: Option<mqtt::MqttSettings>,
+    /// Serve the named pipe / Unix socket scripting interface.
+    #[serde(default = "default_local_ipc")]
+    pub local_ipc: bool,
+    /// OBS websocket connection; `None` keeps the integration dormant.
+    #[serde(default)]
+    pub obs: Option<obs::ObsSettings>,
+    /// HTTP endpoints notified on mute state transitions.
+    #[serde(default)]
+    pub webhooks: Vec<webhooks::Webhook>,
+    /// Local commands run on mute state transitions; `None` disables them.
+    #[serde(default)]
+    pub hooks: Option<hooks::HookSettings>,
+    /// Base interval of the mute state polling loop (non-Windows). Stretched automatically
+    /// while nothing changes.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// How long a single device call may take before the device counts as unhealthy and
+    /// the audio thread is restarted.
+    #[serde(default = "default_audio_timeout_ms")]
+    pub audio_timeout_ms: u64,
+    /// Where a toggle goes when the profile's devices disagree.
+    #[serde(default)]
+    pub mixed_toggle_target: MixedToggleTarget,
+    /// What happens to the microphones' mute states when TogMic exits.
+    #[serde(default)]
+    pub restore_on_exit: exit_restore::RestoreOnExit,
+    /// Unmute every input device on exit, overriding `restore_on_exit`.
+    #[serde(default)]
+    pub unmute_all_on_exit: bool,
+    /// Global hotkey that unmutes every input device, regardless of the active profile.
+    #[serde(default)]
+    pub panic_unmute_key: Option<String>,
+    /// Global hotkey that undoes the last mute change.
+    #[serde(default)]
+    pub undo_key: Option<String>,
+    /// How long a mute change can be undone, in seconds.
+    #[serde(default = "default_undo_window_seconds")]
+    pub undo_window_seconds: u32,
+    /// Mute the active profile when the workstation is locked (Windows).
+    #[serde(default)]
+    pub mute_on_lock: bool,
+    /// With `mute_on_lock`: go back to the pre-lock state on unlock.
+    #[serde(default)]
+    pub restore_on_unlock: bool,
+    /// Minutes without keyboard/mouse input before muting; 0 disables it.
+    #[serde(default)]
+    pub idle_mute_minutes: u32,
+    /// Unmute again when input resumes after an idle mute.
+    #[serde(default)]
+    pub unmute_on_return: bool,
+    /// What to do when a device of the active profile disappears.
+    #[serde(default)]
+    pub on_device_lost: device_watch::OnDeviceLost,
+    /// Record usage statistics (time muted, toggle counts) to `stats.json`.
+    #[serde(default = "default_collect_stats")]
+    pub collect_stats: bool,
+    /// Days the mute history in `history.jsonl` is kept; 0 records none.
+    #[serde(default = "default_history_retention_days")]
+    pub history_retention_days: u32,
+    /// How long suspending the hotkeys from the tray lasts; 0 means until resumed.
+    #[serde(default = "default_hotkey_suspend_minutes")]
+    pub hotkey_suspend_minutes: u32,
+    /// Register every profile's toggle key, each toggling its own profile's devices. The
+    /// active profile stays the one the tray and integrations show.
+    #[serde(default)]
+    pub multi_profile_hotkeys: bool,
+    /// Minutes a microphone may stay live before `hot_mic_action`; 0 disables it.
+    #[serde(default)]
+    pub max_unmuted_minutes: u32,
+    /// How long before the limit to warn; 0 skips the early warning.
+    #[serde(default = "default_hot_mic_warning_minutes")]
+    pub hot_mic_warning_minutes: u32,
+    #[serde(default)]
+    pub hot_mic_action: hot_mic::HotMicAction,
+    /// Show a notification and play a sound with each hot mic warning.
+    #[serde(default = "default_hot_mic_notify")]
+    pub hot_mic_notify: bool,
+    /// What to do when a device of the active profile is muted or unmuted outside TogMic.
+    #[serde(default)]
+    pub external_change_policy: reconcile::ExternalChangePolicy,
+    /// Remind the user when they speak while muted.
+    #[serde(default)]
+    pub speaking_reminder: bool,
+    /// Peak level that counts as speech; see `calibrate_voice_threshold`.
+    #[serde(default = "default_speaking_threshold")]
+    pub speaking_threshold: f32,
+    #[serde(default = "default_speaking_reminder_notify")]
+    pub speaking_reminder_notify: bool,
+    #[serde(default = "default_speaking_reminder_sound")]
+    pub speaking_reminder_sound: bool,
+    /// Watch which applications capture from the microphones.
+    #[serde(default)]
+    pub track_mic_usage: bool,
+    /// What to do when an application no profile lists starts capturing.
+    #[serde(default)]
+    pub on_unknown_capture: capture_switch::OnUnknownCapture,
+    /// Light a keyboard lock-key LED while muted (Windows).
+    #[serde(default)]
+    pub led_indicator: bool,
+    /// Which lock key's LED `led_indicator` uses.
+    #[serde(default)]
+    pub led_indicator_key: led_indicator::LedKey,
+    /// USB busylight showing the mute state.
+    #[serde(default)]
+    pub busylight: lights::BusylightSettings,
+    /// Mute state sync with other machines; `None` keeps it off.
+    #[serde(default)]
+    pub peers: Option<peer_sync::PeerSettings>,
+    /// Skip sounds and notifications while Focus Assist is on (Windows).
+    #[serde(default)]
+    pub respect_focus_assist: bool,
+    /// Show a desktop notification when the mute state changes.
+    #[serde(default)]
+    pub notify_mute_changes: bool,
+    #[serde(default)]
+    pub sound_theme: sound::SoundTheme,
+    /// Stream category of the feedback sounds (Windows).
+    #[serde(default)]
+    pub sound_category: sound::SoundCategory,
+    /// Larger `mute.wav` etc. next to the executable are ignored.
+    #[serde(default = "default_max_sound_file_mb")]
+    pub max_sound_file_mb: u32,
+    /// Thresholds and timing of the `voice-activity` events.
+    #[serde(default)]
+    pub vad: audio::vad::VadSettings,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum MixedToggleTarget {
+    /// Mute everything: the safe direction when some mic is live.
+    #[default]
+    Mute,
+    Unmute,
 }
 
 fn default_check_updates() -> bool {
     true
 }
 
+fn default_collect_stats() -> bool {
+    true
+}
+
+fn default_history_retention_days() -> u32 {
+    history::DEFAULT_RETENTION_DAYS
+}
+
+fn default_hotkey_suspend_minutes() -> u32 {
+    15
+}
+
+fn default_hot_mic_warning_minutes() -> u32 {
+    1
+}
+
+fn default_max_sound_file_mb() -> u32 {
+    sound::DEFAULT_MAX_SOUND_FILE_MB
+}
+
+fn default_hot_mic_notify() -> bool {
+    true
+}
+
+fn default_speaking_threshold() -> f32 {
+    speaking::DEFAULT_THRESHOLD
+}
+
+fn default_speaking_reminder_notify() -> bool {
+    true
+}
+
+fn default_speaking_reminder_sound() -> bool {
+    true
+}
+
+fn default_temporary_unmute_seconds() -> u32 {
+    30
+}
+
+fn default_local_ipc() -> bool {
+    false
+}
+
+fn default_undo_window_seconds() -> u32 {
+    undo::DEFAULT_WINDOW_SECS
+}
+
+fn default_poll_interval_ms() -> u64 {
+    poll::DEFAULT_INTERVAL_MS
+}
+
+fn default_audio_timeout_ms() -> u64 {
+    audio_worker::DEFAULT_TIMEOUT_MS
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             start_muted: true,
             autostart: true,
             check_updates: true,
+            update_channel: updates::UpdateChannel::Stable,
             close_to_tray: true,
+            minimize_to_tray: false,
+            tray_config_folder: false,
+            tray_left_click_toggles: default_tray_left_click_toggles(),
+            classic_tray_icons: false,
+            tray_icon_style: tray_icons::TrayIconStyle::Default,
+            tray_extra_items: default_tray_extra_items(),
             start_minimized: true,
+            api_port: None,
+            mqtt: None,
+            local_ipc: false,
+            obs: None,
+            webhooks: Vec::new(),
+            hooks: None,
+            poll_interval_ms: poll::DEFAULT_INTERVAL_MS,
+            audio_timeout_ms: audio_worker::DEFAULT_TIMEOUT_MS,
+            mixed_toggle_target: MixedToggleTarget::Mute,
+            restore_on_exit: exit_restore::RestoreOnExit::None,
+            unmute_all_on_exit: false,
+            panic_unmute_key: None,
+            undo_key: None,
+            undo_window_seconds: undo::DEFAULT_WINDOW_SECS,
+            mute_on_lock: false,
+            restore_on_unlock: false,
+            idle_mute_minutes: 0,
+            unmute_on_return: false,
+            on_device_lost: device_watch::OnDeviceLost::None,
+            collect_stats: true,
+            history_retention_days: default_history_retention_days(),
+            hotkey_suspend_minutes: default_hotkey_suspend_minutes(),
+            multi_profile_hotkeys: false,
+            max_unmuted_minutes: 0,
+            hot_mic_warning_minutes: default_hot_mic_warning_minutes(),
+            hot_mic_action: hot_mic::HotMicAction::Mute,
+            hot_mic_notify: true,
+            external_change_policy: reconcile::ExternalChangePolicy::Adopt,
+            speaking_reminder: false,
+            speaking_threshold: speaking::DEFAULT_THRESHOLD,
+            speaking_reminder_notify: true,
+            speaking_reminder_sound: true,
+            track_mic_usage: false,
+            on_unknown_capture: capture_switch::OnUnknownCapture::Ignore,
+            led_indicator: false,
+            led_indicator_key: led_indicator::LedKey::ScrollLock,
+            busylight: lights::BusylightSettings::default(),
+            peers: None,
+            respect_focus_assist: false,
+            notify_mute_changes: false,
+            sound_theme: sound::SoundTheme::Bundled,
+            sound_category: sound::SoundCategory::Standard,
+            max_sound_file_mb: default_max_sound_file_mb(),
+            vad: audio::vad::VadSettings::default(),
         }
     }
 }
 
+/// How many command errors are kept for the diagnostics report.
+const MAX_RECENT_ERRORS: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub source: String,
+    pub message: String,
+}
+
 pub struct AppState {
     pub current_profile: Arc<Mutex<Option<HotkeyProfile>>>,
-    pub is_muted: Arc<AtomicBool>,
-    pub devices: Arc<Mutex<Vec<AudioDevice>>>,
+    pub mute_state: Arc<AtomicMuteState>,
+    // Per-device breakdown behind mute_state, sent with mute-state-changed
+    pub device_mute_states: Arc<Mutex<Vec<DeviceMuteState>>>,
+    // Toggling a mixed state mutes (true) or unmutes (false)
+    pub mixed_toggle_mutes: Arc<AtomicBool>,
+    // Last enumerated device list, shared as a snapshot instead of cloned per reader
+    pub devices: Arc<Mutex<DeviceCache>>,
     pub audio_controller: Arc<Mutex<Option<PlatformAudioController>>>,
     pub close_to_tray: Arc<Mutex<bool>>,
+    // minimize_to_tray setting, read on every resize of the main window
+    pub minimize_to_tray: Arc<AtomicBool>,
+    // tray_config_folder setting, read when the tray menu is rebuilt
+    pub tray_config_folder: Arc<AtomicBool>,
+    // tray_left_click_toggles setting, read on every tray click
+    pub tray_left_click_toggles: Arc<AtomicBool>,
+    // tray_extra_items setting, read when the tray menu is rebuilt
+    pub tray_extra_items: Arc<Mutex<Vec<TrayMenuItem>>>,
     // Cache last visible tray state to avoid redundant tray API calls
-    pub last_tray_muted: Arc<Mutex<Option<bool>>>,
+    pub last_tray_state: Arc<Mutex<Option<MuteState>>>,
     pub last_tray_dark_mode: Arc<Mutex<Option<bool>>>,
-    // Localized tray tooltip strings
-    pub tray_tooltip_muted: Arc<Mutex<String>>,
-    pub tray_tooltip_unmuted: Arc<Mutex<String>>,
-    // Localized tray menu label strings
-    pub tray_label_mute: Arc<Mutex<String>>,
-    pub tray_label_unmute: Arc<Mutex<String>>,
-    pub tray_label_show: Arc<Mutex<String>>,
-    pub tray_label_quit: Arc<Mutex<String>>,
+    pub last_tray_icon: Arc<Mutex<Option<tray_icons::IconKey>>>,
+    // The tray's mute entry, checked while muted; updated in place on mute changes
+    pub tray_toggle_item: Arc<Mutex<Option<CheckMenuItem<AppRuntime>>>>,
+    pub last_tray_checked: Arc<Mutex<Option<bool>>>,
+    // Devices the last mute change could not reach, noted in the tray tooltip
+    pub tray_failed_devices: Arc<Mutex<usize>>,
+    // Accelerators currently registered with the global shortcut plugin, and what for
+    pub registered_shortcuts: Arc<Mutex<Vec<shortcuts::RegisteredShortcut>>>,
+    // Configured panic unmute hotkey
+    pub panic_unmute_key: Arc<Mutex<Option<String>>>,
+    // Configured undo hotkey
+    pub undo_key: Arc<Mutex<Option<String>>>,
+    // Last mute change, for undo
+    pub mute_history: Arc<Mutex<undo::MuteHistory>>,
+    // Set while suspend_hotkeys has the global shortcuts unregistered
+    pub hotkeys_suspended: Arc<AtomicBool>,
+    // Set while the background mute/device sync thread is running
+    pub sync_thread_alive: Arc<AtomicBool>,
+    // Ring buffer of the most recent command errors, surfaced in diagnostics reports
+    pub recent_errors: Arc<Mutex<VecDeque<ErrorEntry>>>,
+    // Ring buffer of mute changes, profile switches, device changes, hotkey registrations
+    // and errors, for get_recent_events
+    pub recent_events: Arc<Mutex<VecDeque<events::RecordedEvent>>>,
+    // Persistent thread applying mute changes; started in setup(), stopped on exit
+    pub audio_worker: Arc<Mutex<Option<audio_worker::AudioWorker>>>,
+    // mute_on_lock / restore_on_unlock settings, and the state to go back to on unlock
+    pub mute_on_lock: Arc<AtomicBool>,
+    pub restore_on_unlock: Arc<AtomicBool>,
+    pub pre_lock_muted: Arc<Mutex<Option<bool>>>,
+    // Where the config store lives, resolved once at startup
+    pub config_location: config_location::ConfigLocation,
+    // Mute state of every input device before TogMic changed anything, for restore_on_exit
+    pub initial_states: Arc<Mutex<Vec<DeviceMuteState>>>,
+    // Why audio_controller is None, if it is
+    pub audio_init_status: Arc<Mutex<audio_init::AudioInitStatus>>,
+    // multi_profile_hotkeys setting, and the last known state of each profile other than
+    // the active one, by profile id
+    pub multi_profile_hotkeys: Arc<AtomicBool>,
+    pub profile_mute_states: Arc<Mutex<HashMap<String, MuteState>>>,
+}
+
+impl AppState {
+    /// Record an error in the ring buffer and hand the message back, so call sites can
+    /// write `.map_err(|e| state.record_error("command", e))`.
+    pub fn record_error(&self, source: &str, message: impl Into<String>) -> String {
+        let message = message.into();
+        log::warn!("[{}] {}", source, message);
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut errors = self.recent_errors.lock_safe();
+        if errors.len() >= MAX_RECENT_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(ErrorEntry {
+            timestamp,
+            source: source.to_string(),
+            message: message.clone(),
+        });
+        drop(errors);
+
+        self.record_event(
+            events::EventKind::Error,
+            source,
+            serde_json::Value::String(message.clone()),
+        );
+        message
+    }
+
+    /// Append an entry to the recent-events history, dropping the oldest when full.
+    pub fn record_event(&self, kind: events::EventKind, name: &str, data: serde_json::Value) {
+        let mut recent = self.recent_events.lock_safe();
+        if recent.len() >= events::MAX_RECENT_EVENTS {
+            recent.pop_front();
+        }
+        recent.push_back(events::RecordedEvent {
+            timestamp: events::now_millis(),
+            kind,
+            name: name.to_string(),
+            data,
+        });
+    }
+
+    /// Replace the cached device list and return the new snapshot.
+    pub fn cache_devices(&self, devices: Vec<AudioDevice>) -> Arc<[AudioDevice]> {
+        #[cfg(debug_assertions)]
+        let devices = simulate::overlay(devices);
+        let devices = device_aliases::apply(devices);
+        let snapshot: Arc<[AudioDevice]> = devices.into();
+        *self.devices.lock_safe() = DeviceCache {
+            devices: snapshot.clone(),
+            refreshed: Some(Instant::now()),
+            refreshed_at: events::now_millis(),
+        };
+        snapshot
+    }
+
+    /// The last enumerated device list. Cloning the snapshot doesn't copy the devices.
+    pub fn cached_devices(&self) -> Arc<[AudioDevice]> {
+        self.devices.lock_safe().devices.clone()
+    }
+
+    /// The cached device list if it is at most `DEVICE_CACHE_MAX_AGE` old.
+    fn fresh_devices(&self) -> Option<Arc<[AudioDevice]>> {
+        let cache = self.devices.lock_safe();
+        cache
+            .refreshed
+            .is_some_and(|refreshed| refreshed.elapsed() <= DEVICE_CACHE_MAX_AGE)
+            .then(|| cache.devices.clone())
+    }
+
+    /// Cache a freshly queried profile state and return the previous one.
+    fn store_mute_state(&self, queried: ProfileMuteState) -> MuteState {
+        *self.device_mute_states.lock_safe() = queried.devices;
+        self.mute_state.swap(queried.state)
+    }
+
+    /// Optimistically mark every device of the active profile as `muted` and return the
+    /// previous state.
+    fn set_muted(&self, muted: bool) -> MuteState {
+        self.mark_devices(muted);
+        self.mute_state.swap(MuteState::from(muted))
+    }
+
+    /// Set the cached state of every device of the active profile to where `muted` takes
+    /// it, which for an unmuted scene is the scene's.
+    fn mark_devices(&self, muted: bool) {
+        let profile = self.current_profile.lock_safe().clone();
+        for device in self.device_mute_states.lock_safe().iter_mut() {
+            device.muted = match &profile {
+                Some(profile) => scene::target(profile, &device.device_id, muted),
+                None => muted,
+            };
+        }
+    }
+
+    /// The mute value a toggle switches to. A mixed state goes to the configured target.
+    fn toggle_target(&self) -> bool {
+        match self.mute_state.get() {
+            MuteState::Muted => false,
+            MuteState::Unmuted => true,
+            MuteState::Mixed => self.mixed_toggle_mutes.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Clone the active profile for a mute change, failing if there is none or audio isn't
+    /// available.
+    fn active_profile_for_mute(&self) -> Result<HotkeyProfile, String> {
+        if self.audio_controller.lock_safe().is_none() {
+            return Err(self.audio_init_status.lock_safe().error_message());
+        }
+        self.current_profile
+            .lock_safe()
+            .clone()
+            .ok_or_else(|| "No active profile".to_string())
+    }
+
+    /// Run `f` with a copy of the active profile on the audio worker's thread, under its
+    /// deadline; see `AudioCaller::call`. No lock is held while it runs. Returns `None` if
+    /// there is no profile or worker, or the call failed.
+    fn with_profile_on_worker<R: Send + 'static>(
+        &self,
+        operation: &'static str,
+        f: impl FnOnce(&HotkeyProfile, &PlatformAudioController) -> R + Send + 'static,
+    ) -> Option<R> {
+        let profile = self.current_profile.lock_safe().clone()?;
+        let caller = self.audio_worker.lock_safe().as_ref()?.caller();
+        caller
+            .call(operation, None, move |controller| f(&profile, controller))
+            .map_err(|e| log::warn!("[audio worker] {}", e))
+            .ok()
+    }
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             current_profile: Arc::new(Mutex::new(None)),
-            is_muted: Arc::new(AtomicBool::new(false)),
-            devices: Arc::new(Mutex::new(Vec::new())),
+            mute_state: Arc::new(AtomicMuteState::new(MuteState::Unmuted)),
+            device_mute_states: Arc::new(Mutex::new(Vec::new())),
+            mixed_toggle_mutes: Arc::new(AtomicBool::new(true)),
+            devices: Arc::new(Mutex::new(DeviceCache::default())),
             audio_controller: Arc::new(Mutex::new(None)),
             close_to_tray: Arc::new(Mutex::new(true)),
-            last_tray_muted: Arc::new(Mutex::new(None)),
+            minimize_to_tray: Arc::new(AtomicBool::new(false)),
+            tray_config_folder: Arc::new(AtomicBool::new(false)),
+            tray_left_click_toggles: Arc::new(AtomicBool::new(default_tray_left_click_toggles())),
+            tray_extra_items: Arc::new(Mutex::new(default_tray_extra_items())),
+            last_tray_state: Arc::new(Mutex::new(None)),
             last_tray_dark_mode: Arc::new(Mutex::new(None)),
-            tray_tooltip_muted: Arc::new(Mutex::new("TogMic - Muted".to_string())),
-            tray_tooltip_unmuted: Arc::new(Mutex::new("TogMic - Unmuted".to_string())),
-            tray_label_mute: Arc::new(Mutex::new("Mute".to_string())),
-            tray_label_unmute: Arc::new(Mutex::new("Unmute".to_string())),
-            tray_label_show: Arc::new(Mutex::new("Show Window".to_string())),
-            tray_label_quit: Arc::new(Mutex::new("Quit".to_string())),
+            last_tray_icon: Arc::new(Mutex::new(None)),
+            tray_toggle_item: Arc::new(Mutex::new(None)),
+            last_tray_checked: Arc::new(Mutex::new(None)),
+            tray_failed_devices: Arc::new(Mutex::new(0)),
+            registered_shortcuts: Arc::new(Mutex::new(Vec::new())),
+            panic_unmute_key: Arc::new(Mutex::new(None)),
+            undo_key: Arc::new(Mutex::new(None)),
+            mute_history: Arc::new(Mutex::new(undo::MuteHistory::default())),
+            hotkeys_suspended: Arc::new(AtomicBool::new(false)),
+            sync_thread_alive: Arc::new(AtomicBool::new(false)),
+            recent_errors: Arc::new(Mutex::new(VecDeque::new())),
+            recent_events: Arc::new(Mutex::new(VecDeque::new())),
+            audio_worker: Arc::new(Mutex::new(None)),
+            mute_on_lock: Arc::new(AtomicBool::new(false)),
+            restore_on_unlock: Arc::new(AtomicBool::new(false)),
+            pre_lock_muted: Arc::new(Mutex::new(None)),
+            initial_states: Arc::new(Mutex::new(Vec::new())),
+            config_location: config_location::ConfigLocation::default(),
+            audio_init_status: Arc::new(Mutex::new(audio_init::AudioInitStatus::Ready)),
+            multi_profile_hotkeys: Arc::new(AtomicBool::new(false)),
+            profile_mute_states: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
 
 const ALL_DEVICES_ID: &str = "all-mics";
 
+/// Path of the store holding profiles, the active profile id and app settings, for
+/// `app.store(..)`; see `config_location`.
+fn config_store<R: tauri::Runtime>(app: &impl Manager<R>) -> std::path::PathBuf {
+    app.state::<AppState>().config_location.store.clone()
+}
+
+/// Resolve the on-disk location of the config store (the store plugin resolves relative
+/// paths against the app data dir).
+fn config_file_path(app: &AppHandle) -> Option<std::path::PathBuf> {
+    let store = config_store(app);
+    if store.is_absolute() {
+        return Some(store);
+    }
+    app.path().app_data_dir().ok().map(|dir| dir.join(store))
+}
+
+/// Where TogMic keeps its files, for users editing them by hand.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigPaths {
+    /// The config store, which may not exist yet.
+    config_file: Option<std::path::PathBuf>,
+    /// Whether `--config` or `TOGMIC_CONFIG` chose the config store.
+    config_source: config_location::ConfigSource,
+    config_dir: Option<std::path::PathBuf>,
+    logs_dir: Option<std::path::PathBuf>,
+    diagnostics_dir: std::path::PathBuf,
+    /// Where `mute.wav`, `unmute.wav`, `error.wav` and `reminder.wav` replace the
+    /// built-in sounds.
+    sounds_dir: Option<std::path::PathBuf>,
+}
+
+fn config_paths(app: &AppHandle) -> ConfigPaths {
+    ConfigPaths {
+        config_file: config_file_path(app),
+        config_source: app.state::<AppState>().config_location.source,
+        config_dir: config_file_path(app)
+            .and_then(|file| file.parent().map(|dir| dir.to_path_buf())),
+        logs_dir: app.path().app_log_dir().ok(),
+        diagnostics_dir: diagnostics::diagnostics_dir(),
+        sounds_dir: std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf())),
+    }
+}
+
+/// Open the directory of the config store in the system file manager, creating it if
+/// nothing has been saved yet.
+fn open_config_dir(app: &AppHandle) -> Result<(), String> {
+    let dir = config_file_path(app)
+        .and_then(|file| file.parent().map(|dir| dir.to_path_buf()))
+        .ok_or_else(|| "Failed to resolve config folder".to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config folder: {}", e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open config folder: {}", e))
+}
+
+/// Layout of the config store; bumped by `migrate_config` steps.
+/// 2: profile hotkeys are `HotkeyBinding` objects instead of strings.
+const CONFIG_VERSION: u64 = 2;
+
+/// Bring a config written by an older version up to `CONFIG_VERSION`, before anything
+/// reads it.
+fn migrate_config(app: &AppHandle) -> Result<(), String> {
+    let store = app
+        .store(config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    // A new config points editors at the schema; it's saved with the version below
+    if store.is_empty() {
+        store.set("$schema", config_schema::SCHEMA_REF);
+    }
+    // Configs from before versioning have no version
+    let version = store
+        .get("configVersion")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1);
+    if version >= CONFIG_VERSION {
+        return Ok(());
+    }
+
+    if version < 2 {
+        if let Some(serde_json::Value::Array(mut profiles)) = store.get("profiles") {
+            for profile in &mut profiles {
+                if let Some(toggle_key) = profile.get_mut("toggleKey") {
+                    hotkey::upgrade_stored(toggle_key)?;
+                }
+            }
+            store.set("profiles", profiles);
+        }
+    }
+
+    store.set("configVersion", CONFIG_VERSION);
+    config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save config: {}", e))?;
+    log::info!(
+        "[config] migrated config from version {} to {}",
+        version,
+        CONFIG_VERSION
+    );
+    Ok(())
+}
+
+/// Read the persisted profile list (empty if missing or unreadable).
+fn load_profiles(app: &AppHandle) -> Vec<HotkeyProfile> {
+    app.store(config_store(app))
+        .ok()
+        .and_then(|store| store.get("profiles"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Persist `profile`, replacing the stored profile with its id or appending it. Fields
+/// the backend doesn't know are kept.
+fn upsert_profile(app: &AppHandle, profile: &HotkeyProfile) -> Result<(), String> {
+    let store = app
+        .store(config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    let mut profiles = match store.get("profiles") {
+        Some(serde_json::Value::Array(profiles)) => profiles,
+        _ => Vec::new(),
+    };
+    let value = serde_json::to_value(profile).map_err(|e| e.to_string())?;
+    match profiles
+        .iter_mut()
+        .find(|p| p.get("id").and_then(|id| id.as_str()) == Some(profile.id.as_str()))
+    {
+        Some(serde_json::Value::Object(stored)) => {
+            if let serde_json::Value::Object(fields) = value {
+                stored.extend(fields);
+            }
+        }
+        Some(stored) => *stored = value,
+        None => profiles.push(value),
+    }
+    store.set("profiles", profiles);
+    config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// `profiles` (the persisted JSON entries) in the order of `ids`, which must name each of
+/// them exactly once.
+fn order_profiles(
+    profiles: Vec<serde_json::Value>,
+    ids: &[String],
+) -> Result<Vec<serde_json::Value>, String> {
+    let id_of = |p: &serde_json::Value| p.get("id").and_then(|id| id.as_str()).map(str::to_string);
+    let mut remaining: Vec<(Option<String>, serde_json::Value)> =
+        profiles.into_iter().map(|p| (id_of(&p), p)).collect();
+    let mut ordered = Vec::with_capacity(remaining.len());
+    for id in ids {
+        let index = remaining
+            .iter()
+            .position(|(stored, _)| stored.as_deref() == Some(id.as_str()))
+            .ok_or_else(|| {
+                if ordered
+                    .iter()
+                    .any(|p| id_of(p).as_deref() == Some(id.as_str()))
+                {
+                    format!("Profile '{}' is listed twice", id)
+                } else {
+                    format!("Unknown profile: '{}'", id)
+                }
+            })?;
+        ordered.push(remaining.remove(index).1);
+    }
+    if !remaining.is_empty() {
+        let missing: Vec<String> = remaining
+            .iter()
+            .map(|(id, _)| id.clone().unwrap_or_default())
+            .collect();
+        return Err(format!("Missing profiles: {}", missing.join(", ")));
+    }
+    Ok(ordered)
+}
+
+/// Write the config store to a temporary file and rename it over the config, so a crash
+/// mid-write leaves the old or the new config rather than half of one.
+fn save_config_atomic(
+    app: &AppHandle,
+    store: &tauri_plugin_store::Store<AppRuntime>,
+) -> Result<(), String> {
+    config_recovery::guard_save(false)?;
+    let path = config_file_path(app).ok_or("No app data directory for the config")?;
+    let entries: serde_json::Map<String, serde_json::Value> = store.entries().into_iter().collect();
+    let json = serde_json::to_vec_pretty(&entries).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, json)
+        .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    std::fs::rename(&temp, &path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        format!("Failed to replace {}: {}", path.display(), e)
+    })
+}
+
+/// Persist `appSettings`.
+fn save_app_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let store = app
+        .store(config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    let value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    store.set("appSettings", value);
+    config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// Read the persisted `appSettings`, falling back to defaults if missing or unreadable.
+fn load_app_settings(app: &AppHandle) -> AppSettings {
+    app.store(config_store(app))
+        .ok()
+        .and_then(|store| store.get("appSettings"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+// macOS menu bar template images: black with alpha, recolored by the system for the menu
+// bar's appearance, so the state shows only in the glyph (the slash over the mic)
+#[cfg(target_os = "macos")]
+const TRAY_TEMPLATE_MUTED_BYTES: &[u8] = include_bytes!("../icons/tray-template-muted.png");
+#[cfg(target_os = "macos")]
+const TRAY_TEMPLATE_UNMUTED_BYTES: &[u8] = include_bytes!("../icons/tray-template-unmuted.png");
+
+#[cfg(target_os = "macos")]
+static LAZY_TRAY_TEMPLATE_MUTED: Lazy<TauriImage<'static>> = Lazy::new(|| {
+    TauriImage::from_bytes(TRAY_TEMPLATE_MUTED_BYTES)
+        .expect("failed to create muted template tray image")
+});
+
+#[cfg(target_os = "macos")]
+static LAZY_TRAY_TEMPLATE_UNMUTED: Lazy<TauriImage<'static>> = Lazy::new(|| {
+    TauriImage::from_bytes(TRAY_TEMPLATE_UNMUTED_BYTES)
+        .expect("failed to create unmuted template tray image")
+});
+
+#[cfg(target_os = "macos")]
+static LAZY_TRAY_TEMPLATE_MIXED: Lazy<TauriImage<'static>> =
+    Lazy::new(|| compose_mixed_icon(&LAZY_TRAY_TEMPLATE_MUTED, &LAZY_TRAY_TEMPLATE_UNMUTED));
+
 const TRAY_MUTED_DARK_BYTES: &[u8] = include_bytes!("../icons/tray-muted-dark.png");
 const TRAY_MUTED_LIGHT_BYTES: &[u8] = include_bytes!("../icons/tray-muted-light.png");
 const TRAY_UNMUTED_DARK_BYTES: &[u8] = include_bytes!("../icons/tray-unmuted-dark.png");
 const TRAY_UNMUTED_LIGHT_BYTES: &[u8] = include_bytes!("../icons/tray-unmuted-light.png");
 
 // Lazy cached TauriImage instances created from embedded bytes to avoid repeated IO/decoding
+#[cfg_attr(target_os = "macos", allow(dead_code))]
 static LAZY_TRAY_MUTED_DARK: Lazy<TauriImage<'static>> = Lazy::new(|| {
     TauriImage::from_bytes(TRAY_MUTED_DARK_BYTES).expect("failed to create muted-dark tray image")
 });
 
+#[cfg_attr(target_os = "macos", allow(dead_code))]
 static LAZY_TRAY_MUTED_LIGHT: Lazy<TauriImage<'static>> = Lazy::new(|| {
     TauriImage::from_bytes(TRAY_MUTED_LIGHT_BYTES).expect("failed to create muted-light tray image")
 });
 
+#[cfg_attr(target_os = "macos", allow(dead_code))]
 static LAZY_TRAY_UNMUTED_DARK: Lazy<TauriImage<'static>> = Lazy::new(|| {
     TauriImage::from_bytes(TRAY_UNMUTED_DARK_BYTES)
         .expect("failed to create unmuted-dark tray image")
 });
 
+#[cfg_attr(target_os = "macos", allow(dead_code))]
 static LAZY_TRAY_UNMUTED_LIGHT: Lazy<TauriImage<'static>> = Lazy::new(|| {
     TauriImage::from_bytes(TRAY_UNMUTED_LIGHT_BYTES)
         .expect("failed to create unmuted-light tray image")
 });
 
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+static LAZY_TRAY_MIXED_DARK: Lazy<TauriImage<'static>> =
+    Lazy::new(|| compose_mixed_icon(&LAZY_TRAY_MUTED_DARK, &LAZY_TRAY_UNMUTED_DARK));
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+static LAZY_TRAY_MIXED_LIGHT: Lazy<TauriImage<'static>> =
+    Lazy::new(|| compose_mixed_icon(&LAZY_TRAY_MUTED_LIGHT, &LAZY_TRAY_UNMUTED_LIGHT));
+
+/// Build the mixed-state icon from the two existing ones: the muted icon above the
+/// anti-diagonal, the unmuted icon below it.
+fn compose_mixed_icon(muted: &TauriImage<'_>, unmuted: &TauriImage<'_>) -> TauriImage<'static> {
+    let (width, height) = (muted.width(), muted.height());
+    let mut rgba = muted.rgba().to_vec();
+    if unmuted.width() == width && unmuted.height() == height {
+        let other = unmuted.rgba();
+        for y in 0..height {
+            for x in 0..width {
+                // x/width + y/height >= 1, without floats
+                if x * height + y * width >= width * height {
+                    let i = ((y * width + x) * 4) as usize;
+                    rgba[i..i + 4].copy_from_slice(&other[i..i + 4]);
+                }
+            }
+        }
+    }
+    TauriImage::new_owned(rgba, width, height)
+}
+
 /// Returns true if Windows is currently in dark mode (SystemUsesLightTheme == 0).
 /// Defaults to dark mode on non-Windows or if the registry key cannot be read.
 fn is_system_dark_mode() -> bool {
@@ -181,16 +1079,67 @@ fn is_system_dark_mode() -> bool {
     }
 }
 
+/// PNG bytes of the tray icon currently shown for `is_muted`, for integrations that
+/// mirror the tray (e.g. Stream Deck key images).
+fn tray_icon_png(is_muted: bool) -> &'static [u8] {
+    match (is_muted, is_system_dark_mode()) {
+        (true, true) => TRAY_MUTED_DARK_BYTES,
+        (true, false) => TRAY_MUTED_LIGHT_BYTES,
+        (false, true) => TRAY_UNMUTED_DARK_BYTES,
+        (false, false) => TRAY_UNMUTED_LIGHT_BYTES,
+    }
+}
+
 fn get_tray_icon(is_muted: bool) -> TauriImage<'static> {
-    let dark = is_system_dark_mode();
-    match (is_muted, dark) {
-        (true, true) => LAZY_TRAY_MUTED_DARK.clone(),
-        (true, false) => LAZY_TRAY_MUTED_LIGHT.clone(),
-        (false, true) => LAZY_TRAY_UNMUTED_DARK.clone(),
-        (false, false) => LAZY_TRAY_UNMUTED_LIGHT.clone(),
+    tray_icon_image(MuteState::from(is_muted), is_system_dark_mode())
+}
+
+/// The tray icon for `mute_state`: a template image on macOS, otherwise the colored icon
+/// for the taskbar's theme.
+fn tray_icon_image(mute_state: MuteState, dark_mode: bool) -> TauriImage<'static> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = dark_mode;
+        match mute_state {
+            MuteState::Muted => LAZY_TRAY_TEMPLATE_MUTED.clone(),
+            MuteState::Unmuted => LAZY_TRAY_TEMPLATE_UNMUTED.clone(),
+            MuteState::Mixed => LAZY_TRAY_TEMPLATE_MIXED.clone(),
+        }
     }
+    #[cfg(not(target_os = "macos"))]
+    match (mute_state, dark_mode) {
+        (MuteState::Muted, true) => LAZY_TRAY_MUTED_DARK.clone(),
+        (MuteState::Muted, false) => LAZY_TRAY_MUTED_LIGHT.clone(),
+        (MuteState::Unmuted, true) => LAZY_TRAY_UNMUTED_DARK.clone(),
+        (MuteState::Unmuted, false) => LAZY_TRAY_UNMUTED_LIGHT.clone(),
+        (MuteState::Mixed, true) => LAZY_TRAY_MIXED_DARK.clone(),
+        (MuteState::Mixed, false) => LAZY_TRAY_MIXED_LIGHT.clone(),
+    }
+}
+
+fn default_tray_left_click_toggles() -> bool {
+    !cfg!(target_os = "macos")
+}
+
+/// A tray menu entry that shows the window and emits `navigate` with its route.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayMenuItem {
+    /// Locale key of the label; a label no bundle knows is shown as written.
+    pub label: String,
+    /// Frontend route, a page optionally followed by a settings tab (`settings/behavior`).
+    pub route: String,
+}
+
+fn default_tray_extra_items() -> Vec<TrayMenuItem> {
+    vec![TrayMenuItem {
+        label: "trayOpenSettings".to_string(),
+        route: "settings".to_string(),
+    }]
 }
 
+const NAVIGATE_MENU_PREFIX: &str = "navigate:";
+
 /// Spawns a background thread that blocks on `RegNotifyChangeKeyValue` for the
 /// Windows personalization registry key. When the key changes (dark/light mode
 /// toggle), the thread wakes up immediately and updates the tray icon, then
@@ -233,8 +1182,7 @@ fn start_theme_change_listener(app: AppHandle) {
             // Registry key changed: invalidate cached theme and refresh tray icon
             let state = app.state::<AppState>();
             *state.last_tray_dark_mode.lock_safe() = None;
-            let is_muted = state.is_muted.load(Ordering::SeqCst);
-            update_tray_icon(&app, is_muted);
+            update_tray_icon(&app, state.mute_state.get());
         }
 
         let _ = RegCloseKey(hkey);
@@ -242,268 +1190,970 @@ fn start_theme_change_listener(app: AppHandle) {
 }
 
 fn profile_uses_all_devices(profile: &HotkeyProfile) -> bool {
+    if profile.is_scene() {
+        return false;
+    }
     profile.device_ids.len() > 1 || profile.device_ids.iter().any(|id| id == ALL_DEVICES_ID)
 }
 
-fn resolve_device_ids(
-    controller: &PlatformAudioController,
-    profile: &HotkeyProfile,
-) -> Result<Vec<String>, String> {
-    if profile_uses_all_devices(profile) {
-        let devices = controller.enumerate_input_devices()?;
-        Ok(devices.into_iter().map(|device| device.id).collect())
-    } else {
-        Ok(profile.device_ids.clone())
+/// How old the device cache may be to resolve a profile's devices without enumerating
+/// them. The polling loop rescans at least this often while it isn't backing off, and
+/// device notifications refresh the cache right away.
+const DEVICE_CACHE_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// The last enumerated device list and when it was taken.
+#[derive(Debug, Clone)]
+pub struct DeviceCache {
+    pub devices: Arc<[AudioDevice]>,
+    /// `None` until the first enumeration.
+    pub refreshed: Option<Instant>,
+    /// Unix time in milliseconds of the enumeration, 0 before the first.
+    pub refreshed_at: u64,
+}
+
+impl Default for DeviceCache {
+    fn default() -> Self {
+        Self {
+            devices: Arc::from([]),
+            refreshed: None,
+            refreshed_at: 0,
+        }
     }
 }
 
-fn get_profile_mute_state(
+/// A profile's device ids, split by whether the system has them.
+#[derive(Debug, Clone)]
+struct ResolvedDevices {
+    present: Vec<String>,
+    /// Ids the profile lists that aren't among the input devices.
+    missing: Vec<String>,
+}
+
+/// The devices `profile` covers; see `missing_devices`. `cached` is a fresh enough device
+/// list (see `AppState::fresh_devices`); without one the devices are enumerated. If they
+/// can't be, a profile with explicit devices counts them all as present.
+fn resolve_device_ids(
     controller: &PlatformAudioController,
     profile: &HotkeyProfile,
-    fallback: bool,
-) -> Result<bool, String> {
-    if profile_uses_all_devices(profile) {
-        let devices = controller.enumerate_input_devices()?;
-        if devices.is_empty() {
-            return Ok(fallback);
-        }
-
-        for device in devices {
-            let muted = controller.get_mute_state(&device.id).unwrap_or(fallback);
-            if !muted {
-                return Ok(false);
-            }
+    cached: Option<&[AudioDevice]>,
+) -> Result<ResolvedDevices, String> {
+    if let Some(devices) = cached {
+        return Ok(partition_device_ids(profile, devices));
+    }
+    let devices = match controller.enumerate_input_devices() {
+        Ok(devices) => devices,
+        Err(e) if profile_uses_all_devices(profile) => return Err(e),
+        Err(_) => {
+            return Ok(ResolvedDevices {
+                present: profile.listed_device_ids(),
+                missing: Vec::new(),
+            })
         }
+    };
+    Ok(partition_device_ids(profile, &devices))
+}
 
-        Ok(true)
-    } else if let Some(first_device) = profile.device_ids.first() {
-        Ok(controller.get_mute_state(first_device).unwrap_or(fallback))
+/// Split the devices of `profile` into the ones in `devices` and the missing ones, and
+/// report newly missing ones.
+fn partition_device_ids(profile: &HotkeyProfile, devices: &[AudioDevice]) -> ResolvedDevices {
+    let listed = profile.listed_device_ids();
+    let missing: Vec<String> = listed
+        .iter()
+        .filter(|id| *id != ALL_DEVICES_ID && !session_change::covers(devices, id))
+        .cloned()
+        .collect();
+    let present: Vec<String> = if profile_uses_all_devices(profile) {
+        devices
+            .iter()
+            .map(|device| device.id.clone())
+            .filter(|id| hidden_devices::expands_to(profile, id))
+            .collect()
     } else {
-        Ok(fallback)
-    }
+        listed
+            .into_iter()
+            .filter(|id| !missing.contains(id))
+            .collect()
+    };
+    missing_devices::note(profile, &present, &missing);
+    ResolvedDevices { present, missing }
 }
-/// A command for the persistent audio worker thread.
-enum AudioCommand {
-    /// Resolve the profile's devices and apply `muted` to each — off the UI/command path.
-    Apply { profile: HotkeyProfile, muted: bool },
+
+/// Query every device the profile covers. Missing devices and devices that can't be
+/// queried are left out of the breakdown; `fallback` is used if none could be.
+fn get_profile_mute_state_detailed(
+    controller: &PlatformAudioController,
+    profile: &HotkeyProfile,
+    cached: Option<&[AudioDevice]>,
+    fallback: MuteState,
+) -> Result<ProfileMuteState, String> {
+    let resolved = resolve_device_ids(controller, profile, cached)?;
+    Ok(query_device_mute_states(
+        profile,
+        &resolved.present,
+        |id| hard_mute::is_muted(controller, id, profile.mute_strategy),
+        fallback,
+    ))
 }
 
-/// Sender to the persistent audio worker. Initialized once during setup().
-static AUDIO_WORKER: Lazy<Mutex<Option<std::sync::mpsc::SyncSender<AudioCommand>>>> =
-    Lazy::new(|| Mutex::new(None));
+fn query_device_mute_states(
+    profile: &HotkeyProfile,
+    device_ids: &[String],
+    query: impl Fn(&str) -> Result<bool, String>,
+    fallback: MuteState,
+) -> ProfileMuteState {
+    let devices: Vec<DeviceMuteState> = device_ids
+        .iter()
+        .filter_map(|id| {
+            query(id).ok().map(|muted| DeviceMuteState {
+                device_id: id.clone(),
+                muted,
+            })
+        })
+        .collect();
+    let state = scene::state(profile, &devices).unwrap_or(fallback);
+    ProfileMuteState { state, devices }
+}
 
-/// Spawn the persistent audio worker thread. It owns a single AudioController and keeps
-/// the per-thread COM state + endpoint cache warm across toggles, so each hotkey press no
-/// longer pays the full CoInitializeEx + CoCreateInstance + endpoint-activation cost that
-/// a freshly spawned thread did.
-fn start_audio_worker(app: AppHandle) {
-    let (tx, rx) = std::sync::mpsc::sync_channel::<AudioCommand>(16);
-    *AUDIO_WORKER.lock_safe() = Some(tx);
+/// Payload of `mute-apply-failed` (nothing changed, state rolled back) and
+/// `mute-apply-partial` (some devices didn't change).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MuteApplyFailedEvent {
+    muted: bool,
+    generation: u64,
+    failures: Vec<audio_worker::DeviceFailure>,
+}
 
-    std::thread::spawn(move || {
-        // Initialize audio subsystem for this thread (e.g., COM on Windows)
-        let _ = PlatformAudioController::init_thread();
+/// Payload of `mute-retrying`, emitted before each retry of a transiently failing device.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MuteRetryingEvent<'a> {
+    device_id: &'a str,
+    attempt: u32,
+    error: &'a str,
+}
 
-        let controller = match PlatformAudioController::new() {
-            Ok(c) => c,
-            Err(e) => {
-                eprintln!("[audio worker] failed to initialize: {}", e);
-                return;
-            }
-        };
+/// Payload of `audio-timeout`, emitted when a device call overran its deadline.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioTimeoutEvent<'a> {
+    /// `None` for calls not about one device, such as enumerating.
+    device_id: Option<&'a str>,
+    operation: &'a str,
+    timeout_ms: u64,
+}
 
-        for cmd in rx {
-            match cmd {
-                AudioCommand::Apply { profile, muted } => {
-                    // Resolve devices here (off the command lock) so an "all-mics" profile
-                    // never blocks the UI thread enumerating devices over COM.
-                    let device_ids = resolve_device_ids(&controller, &profile)
-                        .unwrap_or_else(|_| profile.device_ids.clone());
-
-                    let mut any_failed = false;
-                    for device_id in &device_ids {
-                        if controller.set_mute_state(device_id, muted).is_err() {
-                            any_failed = true;
-                        }
-                    }
+/// How long `set_mute` waits for the worker to report back. Covers the retry backoff.
+const APPLY_TIMEOUT_SECS: u64 = 5;
 
-                    if any_failed {
-                        let _ = app
-                            .notification()
-                            .builder()
-                            .title("TogMic")
-                            .body("A microphone is no longer connected and could not be muted/unmuted.")
-                            .show();
-                        // The optimistic cached state may now disagree with the hardware.
-                        // Reconcile is_muted / tray / frontend with what the devices report.
-                        reconcile_mute_state(&app, &controller, &profile);
-                    }
-                }
-            }
+/// Start the persistent audio worker that applies mute changes off the UI thread.
+fn start_audio_worker(app: AppHandle) {
+    let result_app = app.clone();
+    let retry_app = app.clone();
+    let timeout_app = app.clone();
+    let error_app = app.clone();
+    let worker = audio_worker::AudioWorker::spawn(
+        move |result| handle_apply_result(&result_app, result),
+        move |device_id, attempt, error| {
+            log::warn!(
+                "[audio worker] retrying {} (attempt {}): {}",
+                device_id,
+                attempt,
+                error
+            );
+            let _ = retry_app.emit(
+                "mute-retrying",
+                MuteRetryingEvent {
+                    device_id,
+                    attempt,
+                    error,
+                },
+            );
+        },
+        move |device_id, operation, timeout_ms| {
+            report_error(
+                &timeout_app,
+                ErrorCategory::Device,
+                format!(
+                    "{} on {} timed out after {} ms",
+                    operation,
+                    device_id.unwrap_or("the audio system"),
+                    timeout_ms
+                ),
+                Some(serde_json::json!({ "deviceId": device_id, "operation": operation })),
+            );
+            let _ = timeout_app.emit(
+                "audio-timeout",
+                AudioTimeoutEvent {
+                    device_id,
+                    operation,
+                    timeout_ms,
+                },
+            );
+        },
+        move |e| {
+            report_error(
+                &error_app,
+                ErrorCategory::Controller,
+                format!("Audio worker failed to initialize: {}", e),
+                None,
+            );
+        },
+    );
+    *app.state::<AppState>().audio_worker.lock_safe() = Some(worker);
+}
+
+/// React to a finished mute application: report it, roll back the optimistic state if
+/// nothing changed, and mark the profile mixed if only some devices did.
+fn handle_apply_result(app: &AppHandle, result: audio_worker::ApplyResult) {
+    let state = app.state::<AppState>();
+
+    // A newer toggle is already in flight (or done); its result is the one that counts
+    let current_generation = state
+        .audio_worker
+        .lock_safe()
+        .as_ref()
+        .map(|w| w.current_generation());
+    if current_generation != Some(result.generation) {
+        return;
+    }
+
+    let report = result.report();
+    let _ = app.emit("mute-applied", &report);
+
+    let failed_count = report.failed.len();
+    let previous_failed =
+        std::mem::replace(&mut *state.tray_failed_devices.lock_safe(), failed_count);
+    if previous_failed != failed_count {
+        refresh_tray_tooltip(app);
+    }
+    if report.failed.is_empty() {
+        return;
+    }
+    // Missing devices were already reported with `profile-devices-missing`; if the others
+    // changed, the profile isn't mixed
+    let missing_only = report.failed.iter().all(|f| f.missing);
+
+    let summary = report
+        .failed
+        .iter()
+        .map(|f| format!("{}: {}", f.device_id, f.error))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let event = MuteApplyFailedEvent {
+        muted: result.muted,
+        generation: result.generation,
+        failures: report.failed.clone(),
+    };
+
+    let context = serde_json::json!({ "muted": result.muted, "failures": &report.failed });
+    if !report.applied.is_empty() && missing_only {
+        log::warn!("[audio] skipped missing devices: {}", summary);
+        let _ = app.emit("mute-apply-partial", event);
+        return;
+    }
+    if !report.applied.is_empty() {
+        report_error(
+            app,
+            ErrorCategory::Device,
+            format!("Some devices did not change: {}", summary),
+            Some(context),
+        );
+        let _ = app.emit("mute-apply-partial", event);
+
+        // The devices that failed kept their old state, so the profile is mixed now
+        let failed = report.failed.iter().map(|f| f.device_id.clone()).collect();
+        requery_device_states(app, &state, result.generation, failed);
+        if state.mute_state.swap(MuteState::Mixed) != MuteState::Mixed {
+            publish_mute_state(app, MuteState::Mixed, "partial");
         }
-    });
+        return;
+    }
+
+    // Every device failed: the hardware still has the previous state, so the cached
+    // state, tray and frontend must go back to it.
+    report_error(
+        app,
+        ErrorCategory::Device,
+        format!("Failed to apply mute state: {}", summary),
+        Some(context),
+    );
+    if let Some(muted) = match result.previous {
+        MuteState::Muted => Some(true),
+        MuteState::Unmuted => Some(false),
+        // The per-device values before a mixed state aren't kept; the next system change
+        // notification or poll fills them in again
+        MuteState::Mixed => None,
+    } {
+        state.mark_devices(muted);
+    }
+    state.mute_state.set(result.previous);
+    publish_mute_state(app, result.previous, "rollback");
+    let _ = app.emit("mute-apply-failed", event);
+    sound::play_error_sound();
+
+    if focus_assist::suppressed() {
+        return;
+    }
+    let _ = app
+        .notification()
+        .builder()
+        .title("TogMic")
+        .body(i18n::translate("notifyDeviceUnreachable"))
+        .show();
 }
 
-/// Re-read the real mute state for `profile` and, if it differs from the cached value,
-/// update the atomic, the tray, and notify the frontend. Used to recover from a failed
-/// optimistic toggle (e.g. a device disappeared mid-toggle).
-fn reconcile_mute_state(
+/// Read back the state of `device_ids` after the request `generation` failed on them. Their
+/// old state can't be told from the request: a scene's devices each had their own. Devices
+/// that can't be read are left out, as in `get_profile_mute_state_detailed`.
+fn requery_device_states(
     app: &AppHandle,
-    controller: &PlatformAudioController,
-    profile: &HotkeyProfile,
+    state: &AppState,
+    generation: u64,
+    device_ids: Vec<String>,
 ) {
+    let strategy = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.mute_strategy)
+        .unwrap_or_default();
+    let worker = state.audio_worker.lock_safe();
+    let Some(worker) = worker.as_ref() else {
+        return;
+    };
+    let app = app.clone();
+    worker.query(
+        "failed device states",
+        move |controller| {
+            device_ids
+                .into_iter()
+                .map(|id| {
+                    let muted = hard_mute::is_muted(controller, &id, strategy).ok();
+                    (id, muted)
+                })
+                .collect::<Vec<_>>()
+        },
+        move |queried| {
+            let Some(queried) = queried else {
+                return;
+            };
+            let state = app.state::<AppState>();
+            // A newer request marked the devices again; its result is the one that counts
+            let current_generation = state
+                .audio_worker
+                .lock_safe()
+                .as_ref()
+                .map(|w| w.current_generation());
+            if current_generation != Some(generation) {
+                return;
+            }
+            state.device_mute_states.lock_safe().retain_mut(|device| {
+                match queried.iter().find(|(id, _)| *id == device.device_id) {
+                    Some((_, Some(muted))) => {
+                        device.muted = *muted;
+                        true
+                    }
+                    Some((_, None)) => false,
+                    None => true,
+                }
+            });
+            // The badge counts the muted devices
+            update_tray_icon(&app, state.mute_state.get());
+        },
+    );
+}
+
+/// Read the active profile's mute state from the system and publish it. With `mute_first`
+/// the profile's devices are muted before reading (start_muted).
+fn sync_profile_state(app: &AppHandle, mute_first: bool, source: &str) {
+    let state = app.state::<AppState>();
+    let cached = state.mute_state.get();
+    let devices = state.fresh_devices();
+    let synced = state.with_profile_on_worker("sync_profile_state", move |profile, controller| {
+        if mute_first {
+            if let Ok(resolved) = resolve_device_ids(controller, profile, devices.as_deref()) {
+                for device_id in &resolved.present {
+                    if let Err(e) =
+                        hard_mute::set_mute(controller, device_id, true, profile.mute_strategy)
+                    {
+                        log::warn!("[startup] failed to mute {}: {}", device_id, e);
+                    }
+                }
+            }
+        }
+        get_profile_mute_state_detailed(controller, profile, devices.as_deref(), cached).ok()
+    });
+    if let Some(Some(queried)) = synced {
+        let system_state = queried.state;
+        state.store_mute_state(queried);
+        publish_mute_state(app, system_state, source);
+    }
+}
+
+/// Called once a late or manual audio initialization succeeded: bring everything that
+/// depends on audio up to date and tell the frontend.
+pub(crate) fn on_audio_controller_ready(app: &AppHandle, apply_start_muted: bool) {
+    // The worker creates its own controller and may have failed the same way; start a
+    // fresh one (taken out first so the lock isn't held while joining)
+    let worker = app.state::<AppState>().audio_worker.lock_safe().take();
+    if let Some(worker) = worker {
+        worker.shutdown();
+    }
+    start_audio_worker(app.clone());
+
+    exit_restore::snapshot_initial_states(&app.state::<AppState>());
+    let start_muted = apply_start_muted && load_app_settings(app).start_muted;
+    sync_profile_state(app, start_muted, "audioReady");
+    refresh_tray_tooltip(app);
+    let _ = app.emit("audio-controller-ready", ());
+}
+
+/// Mute on lock and optionally restore on unlock, through the regular mute path so tray,
+/// events and integrations are up to date when the user comes back.
+fn handle_session_event(app: &AppHandle, event: system_events::SessionEvent) {
     let state = app.state::<AppState>();
-    let cached = state.is_muted.load(Ordering::SeqCst);
-    if let Ok(real) = get_profile_mute_state(controller, profile, cached) {
-        if state.is_muted.swap(real, Ordering::SeqCst) != real {
-            let _ = app.emit("mute-state-changed", real);
-            update_tray_icon(app, real);
+    match event {
+        system_events::SessionEvent::Locked => {
+            if !state.mute_on_lock.load(Ordering::SeqCst) {
+                return;
+            }
+            let was_muted = state.mute_state.is_muted();
+            *state.pre_lock_muted.lock_safe() = Some(was_muted);
+            if !was_muted {
+                if let Err(e) = set_mute_internal(&state, app, true, true, "lock") {
+                    state.record_error("mute_on_lock", e);
+                }
+            }
+        }
+        system_events::SessionEvent::Unlocked => {
+            let pre_lock_muted = state.pre_lock_muted.lock_safe().take();
+            if pre_lock_muted == Some(false)
+                && state.restore_on_unlock.load(Ordering::SeqCst)
+                && state.mute_state.is_muted()
+            {
+                if let Err(e) = set_mute_internal(&state, app, false, true, "unlock") {
+                    state.record_error("restore_on_unlock", e);
+                }
+            }
+        }
+        system_events::SessionEvent::Resumed => {
+            // Off the window thread, which must keep pumping messages
+            let app = app.clone();
+            std::thread::spawn(move || on_system_resumed(&app));
         }
+        system_events::SessionEvent::Connection(change) => {
+            let app = app.clone();
+            std::thread::spawn(move || session_change::handle(&app, change));
+        }
+    }
+}
+
+/// Enumerate the input devices after dropping every thread's cached endpoints.
+fn enumerate_fresh(state: &AppState) -> Option<Vec<AudioDevice>> {
+    #[cfg(target_os = "windows")]
+    audio::clear_endpoint_cache();
+    let result = state
+        .audio_controller
+        .lock_safe()
+        .as_ref()
+        .map(|controller| controller.enumerate_input_devices())?;
+    result
+        .map_err(|e| log::warn!("[audio] failed to enumerate devices: {}", e))
+        .ok()
+}
+
+/// Enumerate the devices again after a change outside TogMic and report the diff.
+#[cfg_attr(not(any(debug_assertions, feature = "mock-audio")), allow(dead_code))]
+fn on_system_devices_changed(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Some(devices) = enumerate_fresh(&state) {
+        device_watch::check(app, state.cache_devices(devices));
+    }
+}
+
+/// Query the active profile's mute state after a device changed outside TogMic. The
+/// notification's own value isn't used: it may come from a device the profile doesn't
+/// cover, which would corrupt `mute_state`.
+#[cfg_attr(
+    not(any(target_os = "windows", debug_assertions, feature = "mock-audio")),
+    allow(dead_code)
+)]
+fn on_system_mute_changed(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let cached = state.mute_state.get();
+    let devices = state.fresh_devices();
+    let queried = state.with_profile_on_worker("get_mute_state", move |profile, controller| {
+        get_profile_mute_state_detailed(controller, profile, devices.as_deref(), cached).ok()
+    });
+    let Some(Some(queried)) = queried else {
+        return;
+    };
+    if mute_lock::enforce(app, &state, &queried) {
+        return;
     }
+    reconcile::observe(app, &state, queried);
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemResumedEvent {
+    state: MuteState,
+}
+
+/// Endpoints cached before sleep are usually invalid afterwards. Drop them on every
+/// thread, rescan the devices and read the profile's state again, muting first when
+/// `start_muted` or the mute lock asks for it.
+fn on_system_resumed(app: &AppHandle) {
+    log::info!("[session] resumed from sleep");
+    let _ = PlatformAudioController::init_thread();
+    let state = app.state::<AppState>();
+    if let Some(devices) = enumerate_fresh(&state) {
+        device_watch::check(app, state.cache_devices(devices));
+    }
+
+    let mute_locked = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .is_some_and(|p| p.mute_lock)
+        && state.mute_state.get() == MuteState::Muted;
+    let mute_first = load_app_settings(app).start_muted || mute_locked;
+    sync_profile_state(app, mute_first, "resume");
+    refresh_tray_tooltip(app);
+    poll::wake();
+    let _ = app.emit(
+        "system-resumed",
+        SystemResumedEvent {
+            state: state.mute_state.get(),
+        },
+    );
 }
 
 /// Queue a mute application on the persistent worker thread. Non-blocking; never touches
-/// COM on the caller's thread.
-fn apply_mute(profile: HotkeyProfile, muted: bool) {
-    if let Some(tx) = AUDIO_WORKER.lock_safe().as_ref() {
-        // try_send: if the worker is briefly backed up, drop this request — a newer toggle
-        // will supersede it and the worker reconciles on failure anyway.
-        let _ = tx.try_send(AudioCommand::Apply { profile, muted });
+/// COM on the caller's thread. `previous` is the cached state before the caller's
+/// optimistic update.
+fn apply_mute(
+    app: &AppHandle,
+    state: &AppState,
+    profile: HotkeyProfile,
+    muted: bool,
+    previous: MuteState,
+) -> Option<audio_worker::PendingApply> {
+    if let Some(worker) = state.audio_worker.lock_safe().as_ref() {
+        return Some(worker.apply(profile, muted, previous, state.fresh_devices()));
     }
+    report_error(
+        app,
+        ErrorCategory::Controller,
+        format!(
+            "Audio worker not running, mute change for profile '{}' not applied",
+            profile.name
+        ),
+        Some(serde_json::json!({ "profileId": profile.id, "muted": muted })),
+    );
+    None
 }
 
 // Tauri Commands
 
+/// Payload of `default-input-changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DefaultInputChangedEvent<'a> {
+    device_id: &'a str,
+    role: audio::DefaultRole,
+    /// Whether the system reports the device as the default afterwards; null for the
+    /// communications role, which the device list doesn't show.
+    confirmed: Option<bool>,
+}
+
+/// Make `device_id` the system default input for `role`, then enumerate again so the new
+/// default is confirmed and reported with `devices-changed`, and emit
+/// `default-input-changed`.
+fn set_default_input(
+    app: &AppHandle,
+    device_id: &str,
+    role: audio::DefaultRole,
+) -> Result<Option<bool>, String> {
+    let state = app.state::<AppState>();
+    {
+        let controller_lock = state.audio_controller.lock_safe();
+        let controller = controller_lock
+            .as_ref()
+            .ok_or_else(|| state.audio_init_status.lock_safe().error_message())?;
+        controller.set_default_input_device(device_id, role)?;
+    }
+    log::info!("[audio] made {} the default input ({:?})", device_id, role);
+
+    let devices = enumerate_fresh(&state).map(|devices| state.cache_devices(devices));
+    let confirmed = match role {
+        audio::DefaultRole::Communications => None,
+        _ => Some(
+            devices
+                .as_ref()
+                .is_some_and(|devices| devices.iter().any(|d| d.id == device_id && d.is_default)),
+        ),
+    };
+    if confirmed == Some(false) {
+        log::warn!("[audio] {} isn't reported as the default input", device_id);
+    }
+    if let Some(devices) = devices {
+        device_watch::check(app, devices);
+    }
+    let _ = app.emit(
+        "default-input-changed",
+        DefaultInputChangedEvent {
+            device_id,
+            role,
+            confirmed,
+        },
+    );
+    Ok(confirmed)
+}
+
+/// Make `device_id` the system default input device for `role`. Returns whether the
+/// change could be confirmed (null if it can't be checked); errors starting with
+/// `NotSupported` where the platform can't change it.
 #[tauri::command]
-fn get_audio_devices(state: State<AppState>) -> Result<Vec<AudioDevice>, String> {
-    let controller_lock = state.audio_controller.lock_safe();
+fn set_default_input_device(
+    app: AppHandle,
+    device_id: String,
+    role: audio::DefaultRole,
+) -> Result<Option<bool>, String> {
+    set_default_input(&app, &device_id, role).map_err(|e| {
+        app.state::<AppState>()
+            .record_error("set_default_input_device", e)
+    })
+}
 
-    if let Some(controller) = controller_lock.as_ref() {
-        let devices = controller.enumerate_input_devices()?;
+/// Show `device_id` as `alias` everywhere in TogMic; a blank alias removes it. Returns the
+/// device list with the change applied.
+#[tauri::command]
+fn set_device_alias(
+    device_id: String,
+    alias: String,
+    state: State<AppState>,
+) -> Result<Arc<[AudioDevice]>, String> {
+    let devices = state.cached_devices();
+    let device = devices.iter().find(|d| d.id == device_id).ok_or_else(|| {
+        state.record_error("set_device_alias", format!("Unknown device: {}", device_id))
+    })?;
+    device_aliases::set(&device_id, device.system_name(), &alias)
+        .map_err(|e| state.record_error("set_device_alias", e))?;
+    Ok(state.cache_devices(devices.to_vec()))
+}
 
-        // Update cached devices
-        let mut devices_lock = state.devices.lock_safe();
-        *devices_lock = devices.clone();
+/// Hide `device_id` from the device list, all-devices profiles and solo muting. Returns
+/// the hidden device ids.
+#[tauri::command]
+fn hide_device(app: AppHandle, device_id: String) -> Result<Vec<String>, String> {
+    let result = hidden_devices::set_hidden(&app, &device_id, true);
+    poll::wake();
+    result.map_err(|e| app.state::<AppState>().record_error("hide_device", e))
+}
 
-        Ok(devices)
-    } else {
-        Err("Audio controller not initialized".to_string())
-    }
+#[tauri::command]
+fn get_hidden_devices() -> Vec<String> {
+    hidden_devices::ids()
 }
 
 #[tauri::command]
-fn toggle_mute(state: State<AppState>, app: AppHandle) -> Result<bool, String> {
-    // Clone the active profile under the lock, then release locks before doing any work —
-    // device resolution and the COM call happen on the worker thread.
-    let profile = {
-        let controller_lock = state.audio_controller.lock_safe();
-        let profile_lock = state.current_profile.lock_safe();
-        match (controller_lock.as_ref(), profile_lock.as_ref()) {
-            (Some(_), Some(profile)) => profile.clone(),
-            _ => return Err("No active profile or audio controller not initialized".to_string()),
-        }
-    };
+fn unhide_device(app: AppHandle, device_id: String) -> Result<Vec<String>, String> {
+    let result = hidden_devices::set_hidden(&app, &device_id, false);
+    poll::wake();
+    result.map_err(|e| app.state::<AppState>().record_error("unhide_device", e))
+}
 
-    // Fast path: toggle based on cached state so UI/tray update is immediate
-    let new_state = !state.is_muted.load(Ordering::SeqCst);
-    state.is_muted.store(new_state, Ordering::SeqCst);
+#[tauri::command]
+fn get_device_aliases() -> HashMap<String, device_aliases::DeviceAlias> {
+    device_aliases::all()
+}
 
-    // Play sound feedback immediately
-    if new_state {
-        sound::play_mute_sound();
+/// Remove the aliases of devices that are gone and weren't seen for `max_age_days`
+/// (default 90). Returns the device ids whose alias was removed.
+#[tauri::command]
+fn prune_aliases(max_age_days: Option<u32>, state: State<AppState>) -> Result<Vec<String>, String> {
+    device_aliases::prune(
+        &state.cached_devices(),
+        max_age_days.unwrap_or(device_aliases::DEFAULT_PRUNE_DAYS),
+    )
+    .map_err(|e| state.record_error("prune_aliases", e))
+}
+
+/// Result of `get_audio_devices`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceList {
+    devices: Arc<[AudioDevice]>,
+    /// Unix time in milliseconds of the enumeration the list comes from.
+    refreshed_at: u64,
+    age_ms: u64,
+}
+
+/// The input devices, from the cache the device notifications and the polling loop keep
+/// up to date. `force_refresh`, or a cache that was never filled, enumerates them again
+/// on the audio worker and waits for the result.
+#[tauri::command]
+async fn get_audio_devices(
+    force_refresh: Option<bool>,
+    include_hidden: Option<bool>,
+    app: AppHandle,
+) -> Result<DeviceList, String> {
+    let state = app.state::<AppState>();
+    let cache = state.devices.lock_safe().clone();
+    let cache = if force_refresh.unwrap_or(false) || cache.refreshed.is_none() {
+        enumerate_on_worker(&state)
+            .await
+            .map_err(|e| state.record_error("get_audio_devices", e))?;
+        state.devices.lock_safe().clone()
     } else {
-        sound::play_unmute_sound();
+        cache
+    };
+
+    // Hidden devices only for managing them
+    let devices = if include_hidden.unwrap_or(false) {
+        cache.devices
+    } else {
+        hidden_devices::visible(&cache.devices).into()
+    };
+    let age_ms = cache
+        .refreshed
+        .map(|refreshed| u64::try_from(refreshed.elapsed().as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default();
+    Ok(DeviceList {
+        devices,
+        refreshed_at: cache.refreshed_at,
+        age_ms,
+    })
+}
+
+/// Enumerate the input devices on the audio worker and cache them.
+async fn enumerate_on_worker(state: &AppState) -> Result<(), String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    {
+        let worker = state.audio_worker.lock_safe();
+        let Some(worker) = worker.as_ref() else {
+            return Err(state.audio_init_status.lock_safe().error_message());
+        };
+        worker.query(
+            "enumerate",
+            |controller| controller.enumerate_input_devices(),
+            move |devices| {
+                let _ = tx.send(
+                    devices.unwrap_or_else(|| Err("Enumerating the devices timed out".to_string())),
+                );
+            },
+        );
     }
 
-    // Emit event to frontend and update tray icon immediately
-    let _ = app.emit("mute-state-changed", new_state);
-    update_tray_icon(&app, new_state);
+    let devices = tauri::async_runtime::spawn_blocking(move || {
+        rx.recv_timeout(Duration::from_secs(APPLY_TIMEOUT_SECS))
+            .map_err(|_| "Timed out waiting for the device list".to_string())
+            .and_then(|devices| devices)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|devices| devices)?;
+    state.cache_devices(devices);
+    Ok(())
+}
 
-    // Apply system mute changes on the worker; it notifies + reconciles if a device is gone
-    apply_mute(profile, new_state);
+/// Outcome of `toggle_mute`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToggleResult {
+    muted: bool,
+    /// The worker is still applying the change; `mute-applied` or `mute-apply-failed`
+    /// reports it with `generation`.
+    pending: bool,
+    /// Devices the change targets.
+    device_count: usize,
+    profile_id: Option<String>,
+    /// `None` for a single device, which is applied before returning.
+    generation: Option<u64>,
+}
 
-    Ok(new_state)
+/// Toggle the active profile (the default), every device or one device; see
+/// `toggle_scope`.
+#[tauri::command]
+async fn toggle_mute(scope: Option<ToggleScope>, app: AppHandle) -> Result<ToggleResult, String> {
+    let state = app.state::<AppState>();
+    let reply = toggle_scope(&state, &app, scope.unwrap_or_default(), "frontend")
+        .map_err(|e| state.record_error("toggle_mute", e))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        reply
+            .recv_timeout(std::time::Duration::from_secs(APPLY_TIMEOUT_SECS))
+            .map_err(|_| "Timed out waiting for the mute change to be applied".to_string())
+            .and_then(|result| result)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result)
+    .map_err(|e| app.state::<AppState>().record_error("toggle_mute", e))
 }
 
+/// Set the mute state and wait for the audio worker to apply it. Every device is attempted;
+/// the result lists which ones changed and which failed.
 #[tauri::command]
-fn set_mute(
+async fn set_mute(
     muted: bool,
     silent: Option<bool>,
-    state: State<AppState>,
     app: AppHandle,
-) -> Result<(), String> {
-    // Clone the active profile under the lock, then release before doing any COM work.
-    let profile = {
-        let controller_lock = state.audio_controller.lock_safe();
-        let profile_lock = state.current_profile.lock_safe();
-        match (controller_lock.as_ref(), profile_lock.as_ref()) {
-            (Some(_), Some(profile)) => profile.clone(),
-            _ => return Err("No active profile or audio controller not initialized".to_string()),
-        }
-    };
-
-    state.is_muted.store(muted, Ordering::SeqCst);
-
-    // Play sound feedback only if not silent
-    if !silent.unwrap_or(false) {
-        if muted {
-            sound::play_mute_sound();
-        } else {
-            sound::play_unmute_sound();
-        }
-    }
+) -> Result<audio_worker::MuteApplyReport, String> {
+    let state = app.state::<AppState>();
+    let pending = set_mute_internal(&state, &app, muted, silent.unwrap_or(false), "frontend")
+        .map_err(|e| state.record_error("set_mute", e))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        pending
+            .reply
+            .recv_timeout(std::time::Duration::from_secs(APPLY_TIMEOUT_SECS))
+            .map(|result| result.report())
+            .map_err(|_| "Timed out waiting for the mute change to be applied".to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result)
+    .map_err(|e| app.state::<AppState>().record_error("set_mute", e))
+}
 
-    // Emit event to frontend and update tray icon
-    let _ = app.emit("mute-state-changed", muted);
-    update_tray_icon(&app, muted);
+/// Per-device entry of `get_device_mute_states`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceMuteStatus {
+    device_id: String,
+    muted: bool,
+    /// The last call to the device finished within the deadline; see `audio_worker`.
+    healthy: bool,
+}
 
-    // Apply the change on the worker thread so a disconnected device can't freeze the UI
-    apply_mute(profile, muted);
+/// The last known mute state of every device of the active profile, with its health.
+#[tauri::command]
+fn get_device_mute_states(state: State<AppState>) -> Vec<DeviceMuteStatus> {
+    state
+        .device_mute_states
+        .lock_safe()
+        .iter()
+        .map(|device| DeviceMuteStatus {
+            device_id: device.device_id.clone(),
+            muted: device.muted,
+            healthy: audio_worker::is_healthy(&device.device_id),
+        })
+        .collect()
+}
 
-    Ok(())
+/// Go back to the mute state before the last change, if it was at most
+/// `undoWindowSeconds` ago. Returns the muted value now applied.
+#[tauri::command]
+fn undo_last_change(app: AppHandle) -> Result<bool, String> {
+    undo::undo(&app).map_err(|e| app.state::<AppState>().record_error("undo_last_change", e))
 }
 
+/// Whether the active profile is muted, or with `device_id` whether that device is. The
+/// devices are queried on the audio worker, off the command thread.
 #[tauri::command]
-fn get_mute_state(state: State<AppState>) -> Result<bool, String> {
+async fn get_mute_state(device_id: Option<String>, app: AppHandle) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || query_mute_state(&app, device_id))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result)
+}
+
+fn query_mute_state(app: &AppHandle, device_id: Option<String>) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    if let Some(device_id) = device_id {
+        let strategy = state
+            .current_profile
+            .lock_safe()
+            .as_ref()
+            .map(|p| p.mute_strategy)
+            .unwrap_or_default();
+        let caller = state
+            .audio_worker
+            .lock_safe()
+            .as_ref()
+            .map(|worker| worker.caller())
+            .ok_or_else(|| state.audio_init_status.lock_safe().error_message())?;
+        let id = device_id.clone();
+        return caller
+            .call("get_mute_state", Some(device_id), move |controller| {
+                hard_mute::is_muted(controller, &id, strategy)
+            })
+            .and_then(|result| result)
+            .map_err(|e| state.record_error("get_mute_state", e));
+    }
+
     // Read actual mute state from the system instead of using cached value
-    let controller_lock = state.audio_controller.lock_safe();
-    let profile_lock = state.current_profile.lock_safe();
+    let cached = state.mute_state.get();
+    let devices = state.fresh_devices();
+    let queried = state.with_profile_on_worker("get_mute_state", move |profile, controller| {
+        get_profile_mute_state_detailed(controller, profile, devices.as_deref(), cached).ok()
+    });
 
-    if let (Some(controller), Some(profile)) = (controller_lock.as_ref(), profile_lock.as_ref()) {
-        let cached = state.is_muted.load(Ordering::SeqCst);
-        if let Ok(system_muted) = get_profile_mute_state(controller, profile, cached) {
-            state.is_muted.store(system_muted, Ordering::SeqCst);
-            return Ok(system_muted);
-        }
+    if let Some(Some(queried)) = queried {
+        state.store_mute_state(queried);
     }
 
-    Ok(state.is_muted.load(Ordering::SeqCst))
+    Ok(state.mute_state.is_muted())
 }
 
+/// Validate `profile`; returns the well-known shortcuts its hotkey would shadow, which
+/// don't prevent saving.
 #[tauri::command]
-fn save_profile(profile: HotkeyProfile) -> Result<(), String> {
+fn save_profile(
+    profile: HotkeyProfile,
+    state: State<AppState>,
+) -> Result<Vec<hotkey::conflicts::HotkeyConflict>, String> {
     // Validate the profile
     if profile.name.is_empty() {
-        return Err("Profile name cannot be empty".to_string());
-    }
-
-    if profile.toggle_key.is_empty() {
-        return Err("Hotkey cannot be empty".to_string());
+        return Err(state.record_error("save_profile", "Profile name cannot be empty"));
     }
 
     // Reject hotkeys the shortcut parser can't understand. Otherwise the profile saves
     // fine but do_register_hotkey silently skips the unparsable accelerator, leaving a
     // profile whose hotkey never fires with no feedback to the user.
-    if Shortcut::from_str(&profile.toggle_key).is_err() {
-        return Err(format!("Invalid hotkey: '{}'", profile.toggle_key));
+    profile
+        .toggle_key
+        .validate()
+        .map_err(|e| state.record_error("save_profile", e))?;
+    if let Some(key) = &profile.temporary_unmute_key {
+        key.validate()
+            .map_err(|e| state.record_error("save_profile", e))?;
+        if *key == profile.toggle_key {
+            return Err(state.record_error(
+                "save_profile",
+                "The temporary unmute hotkey must differ from the toggle hotkey",
+            ));
+        }
+    }
+    if profile.temporary_unmute_seconds == 0 {
+        return Err(
+            state.record_error("save_profile", "Temporary unmute needs at least one second")
+        );
+    }
+
+    if profile.listed_device_ids().is_empty() {
+        return Err(state.record_error("save_profile", "At least one device must be selected"));
     }
+    scene::validate(&profile).map_err(|e| state.record_error("save_profile", e))?;
 
-    if profile.device_ids.is_empty() {
-        return Err("At least one device must be selected".to_string());
+    let mut conflicts = hotkey::conflicts::check(&profile.toggle_key, profile.ignore_modifiers);
+    if let Some(key) = &profile.temporary_unmute_key {
+        conflicts.extend(hotkey::conflicts::check(key, false));
+    }
+    for conflict in &conflicts {
+        log::info!(
+            "[hotkey] '{}' shadows '{}': {}",
+            profile.toggle_key,
+            conflict.shortcut,
+            conflict.description
+        );
     }
 
     // Note: This command just validates the profile; saving is done by the frontend via the store
-    Ok(())
+    Ok(conflicts)
 }
 
 #[tauri::command]
@@ -512,24 +2162,171 @@ fn set_active_profile(
     state: State<AppState>,
     app: AppHandle,
 ) -> Result<(), String> {
-    // Set the new active profile
-    {
-        let mut profile_lock = state.current_profile.lock_safe();
-        *profile_lock = Some(profile.clone());
+    // Persist the passed copy first, so the active profile is always the stored one
+    upsert_profile(&app, &profile).map_err(|e| state.record_error("set_active_profile", e))?;
+    set_active_profile_by_id(profile.id, state, app).map(|_| ())
+}
+
+/// Activate the persisted profile `id` and register its hotkey. Returns the profile as
+/// stored, which is what becomes active.
+#[tauri::command]
+fn set_active_profile_by_id(
+    id: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<HotkeyProfile, String> {
+    let profile = load_profiles(&app)
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| {
+            state.record_error(
+                "set_active_profile_by_id",
+                format!("Unknown profile: '{}'", id),
+            )
+        })?;
+    activate_profile(&app, profile).map_err(|e| state.record_error("set_active_profile_by_id", e))
+}
+
+/// Make `profile` active and register its hotkey. An unregistrable hotkey fails the call
+/// but leaves the profile active.
+fn activate_profile(app: &AppHandle, profile: HotkeyProfile) -> Result<HotkeyProfile, String> {
+    let state = app.state::<AppState>();
+    set_active_profile_internal(&state, app, profile.clone());
+    if let Some(role) = profile.make_default {
+        let device_ids = profile.listed_device_ids();
+        let device_id = device_ids
+            .iter()
+            .find(|id| !id.is_empty() && *id != "default-mic" && *id != ALL_DEVICES_ID);
+        if let Some(device_id) = device_id {
+            if let Err(e) = set_default_input(app, device_id, role) {
+                state.record_error("make_default", e);
+            }
+        }
     }
+    do_register_hotkey(&profile.toggle_key, profile.ignore_modifiers, app, &state)?;
+    Ok(profile)
+}
 
-    // Immediately sync mute state and tray icon for the newly selected profile
-    let controller_lock = state.audio_controller.lock_safe();
-    if let Some(controller) = controller_lock.as_ref() {
-        let cached = state.is_muted.load(Ordering::SeqCst);
-        if let Ok(system_muted) = get_profile_mute_state(controller, &profile, cached) {
-            state.is_muted.store(system_muted, Ordering::SeqCst);
-            let _ = app.emit("mute-state-changed", system_muted);
-            update_tray_icon(&app, system_muted);
+/// Make `profile` the active profile and immediately sync mute state and tray icon for it.
+fn set_active_profile_internal(state: &AppState, app: &AppHandle, profile: HotkeyProfile) {
+    set_current_profile(app, state, Some(profile.clone()));
+    poll::wake();
+
+    // Activating a scene applies it. The worker applies before running queries, so the
+    // query below reads the applied scene
+    if profile.is_scene() {
+        if let Err(e) = set_mute_internal(state, app, false, true, "profileSwitch") {
+            log::warn!("[scene] failed to apply '{}': {}", profile.name, e);
         }
     }
 
-    Ok(())
+    // Sync mute state and tray icon for the newly selected profile. Queried on the audio
+    // worker: this runs on the tray and hotkey threads, which a hanging driver mustn't block
+    let cached = state.mute_state.get();
+    let worker = state.audio_worker.lock_safe();
+    let Some(worker) = worker.as_ref() else {
+        return;
+    };
+    let app = app.clone();
+    let profile_id = profile.id.clone();
+    let devices = state.fresh_devices();
+    worker.query(
+        "profile mute state",
+        move |controller| {
+            get_profile_mute_state_detailed(controller, &profile, devices.as_deref(), cached).ok()
+        },
+        move |queried| {
+            let Some(Some(queried)) = queried else {
+                return;
+            };
+            let state = app.state::<AppState>();
+            // Another profile may have been activated since
+            let current = state
+                .current_profile
+                .lock_safe()
+                .as_ref()
+                .map(|p| p.id.clone());
+            if current.as_deref() != Some(profile_id.as_str()) {
+                return;
+            }
+            let system_state = queried.state;
+            state.store_mute_state(queried);
+            publish_mute_state(&app, system_state, "profileSwitch");
+        },
+    );
+}
+
+/// Write `AppState::current_profile`, persist its id as `activeProfileId` so a crash keeps
+/// the selection, and tell the frontend with `profile-changed`. Every change of the active
+/// profile goes through here.
+fn set_current_profile(app: &AppHandle, state: &AppState, profile: Option<HotkeyProfile>) {
+    let id = profile.as_ref().map(|p| p.id.clone());
+    *state.current_profile.lock_safe() = profile.clone();
+
+    match app.store(config_store(app)) {
+        Ok(store) => {
+            store.set("activeProfileId", serde_json::json!(id));
+            if let Err(e) = config_recovery::guard_save(false)
+                .and_then(|()| store.save().map_err(|e| e.to_string()))
+            {
+                log::warn!("[profile] failed to save active profile: {}", e);
+            }
+        }
+        Err(e) => log::warn!("[profile] failed to open config: {}", e),
+    }
+
+    hid_buttons::configure(app, profile.as_ref().and_then(|p| p.hid_device.clone()));
+    events::emit_profile_changed(app, profile.as_ref());
+    refresh_tray_tooltip(app);
+    solo::enforce(state, None);
+}
+
+/// Switch to the persisted profile matching `id_or_name` (id first, then case-insensitive
+/// name) and register its hotkey. Used by external triggers that don't go through the
+/// frontend.
+fn switch_profile(app: &AppHandle, id_or_name: &str) -> Result<HotkeyProfile, String> {
+    let profiles = load_profiles(app);
+    let profile = profiles
+        .iter()
+        .find(|p| p.id == id_or_name)
+        .or_else(|| {
+            profiles
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(id_or_name))
+        })
+        .cloned()
+        .ok_or_else(|| format!("Unknown profile: '{}'", id_or_name))?;
+    activate_profile(app, profile)
+}
+
+/// The persisted profiles, in their stored order.
+#[tauri::command]
+fn list_profiles(app: AppHandle) -> Vec<HotkeyProfile> {
+    load_profiles(&app)
+}
+
+/// Store the profiles in the order of `ids`, which must contain every profile ID exactly
+/// once; nothing changes otherwise. Returns the reordered list.
+#[tauri::command]
+fn reorder_profiles(app: AppHandle, ids: Vec<String>) -> Result<Vec<HotkeyProfile>, String> {
+    let state = app.state::<AppState>();
+    let store = app.store(config_store(&app)).map_err(|e| {
+        state.record_error("reorder_profiles", format!("Failed to open config: {}", e))
+    })?;
+    let profiles = match store.get("profiles") {
+        Some(serde_json::Value::Array(profiles)) => profiles,
+        _ => Vec::new(),
+    };
+    let ordered = order_profiles(profiles.clone(), &ids)
+        .map_err(|e| state.record_error("reorder_profiles", e))?;
+
+    store.set("profiles", ordered);
+    if let Err(e) = save_config_atomic(&app, &store) {
+        store.set("profiles", profiles);
+        return Err(state.record_error("reorder_profiles", e));
+    }
+    log::info!("[profile] reordered profiles: {}", ids.join(", "));
+    Ok(load_profiles(&app))
 }
 
 #[tauri::command]
@@ -539,130 +2336,827 @@ fn get_active_profile(state: State<AppState>) -> Result<Option<HotkeyProfile>, S
 }
 
 fn do_register_hotkey(
-    hotkey: &str,
+    hotkey: &hotkey::HotkeyBinding,
+    ignore_modifiers: bool,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<(), String> {
+    // Resuming registers the active profile's hotkeys
+    if suspend::is_suspended(state) {
+        log::debug!(
+            "[hotkey] hotkeys are suspended, not registering '{}'",
+            hotkey
+        );
+        return Ok(());
+    }
+    if let Err(e) = unregister_all_shortcuts(app, state) {
+        log::warn!("[hotkey] {}", e);
+    }
+
+    // The temporary unmute and global hotkeys are registered even if the profile hotkey
+    // fails, and their own failures never fail the profile registration
+    let result = register_profile_hotkeys(hotkey, ignore_modifiers, app, state);
+    register_temporary_unmute_hotkey(app, state);
+    state.record_event(
+        events::EventKind::Hotkey,
+        "hotkey-registration",
+        serde_json::json!({
+            "hotkey": hotkey.to_string(),
+            "registered": shortcuts::registered(state, shortcuts::ShortcutPurpose::Toggle),
+            "error": result.as_ref().err(),
+        }),
+    );
+    register_global_hotkeys(app, state);
+    register_other_profile_hotkeys(app, state);
+    // Everything above binds through one portal session on Wayland
+    let committed = portal_shortcuts::commit();
+    result.and(committed)
+}
+
+fn register_profile_hotkeys(
+    hotkey: &hotkey::HotkeyBinding,
     ignore_modifiers: bool,
     app: &AppHandle,
     state: &AppState,
 ) -> Result<(), String> {
-    let _ = app.global_shortcut().unregister_all();
+    // A scancode binding becomes whatever key sits at that position in the current layout
+    let resolved;
+    let hotkey = if let hotkey::KeyRef::Scancode(_) = hotkey.key {
+        resolved = scancode::resolve(hotkey)?;
+        log::debug!("[hotkey] '{}' resolves to '{}'", hotkey, resolved);
+        &resolved
+    } else {
+        hotkey
+    };
 
     let hotkeys_to_register: Vec<String> = if ignore_modifiers {
         // Register all modifier combinations so the hotkey fires regardless of held modifiers
-        let prefixes = [
-            "",
-            "CommandOrControl+",
-            "Alt+",
-            "Shift+",
-            "CommandOrControl+Alt+",
-            "CommandOrControl+Shift+",
-            "Alt+Shift+",
-            "CommandOrControl+Alt+Shift+",
-        ];
-        prefixes
+        hotkey
+            .with_all_modifier_combinations()
             .iter()
-            .map(|p| format!("{}{}", p, hotkey))
+            .map(ToString::to_string)
             .collect()
     } else {
         vec![hotkey.to_string()]
     };
 
-    let current_profile = state.current_profile.clone();
-    let is_muted = state.is_muted.clone();
+    let profile_id = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.id.clone());
+    let register = |hotkey_str: String, engine: shortcuts::ShortcutEngine| {
+        shortcuts::add(
+            state,
+            hotkey_str,
+            profile_id.clone(),
+            shortcuts::ShortcutPurpose::Toggle,
+            engine,
+        )
+    };
+
+    // Keys the shortcut plugin can't express go through the keyboard hook, which ignores
+    // modifiers anyway
+    if let hotkey::KeyRef::Vk(vk) = hotkey.key {
+        keyboard_hook::bind(app, vk, on_toggle_hotkey)
+            .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey, e))?;
+        log::debug!("[hotkey] registered '{}' through the keyboard hook", hotkey);
+        register(hotkey.to_string(), shortcuts::ShortcutEngine::Hook);
+        return Ok(());
+    }
+
+    // Wayland doesn't let the plugin grab keys; the desktop portal binds the hotkey
+    // itself, without the modifier variants
+    if portal_shortcuts::is_active() {
+        portal_shortcuts::bind(
+            app,
+            shortcuts::ShortcutPurpose::Toggle,
+            hotkey,
+            on_toggle_hotkey,
+        )
+        .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey, e))?;
+        log::debug!(
+            "[hotkey] registered '{}' through the desktop portal",
+            hotkey
+        );
+        register(hotkey.to_string(), shortcuts::ShortcutEngine::Portal);
+        return Ok(());
+    }
 
     for hotkey_str in hotkeys_to_register {
         let shortcut = match Shortcut::from_str(&hotkey_str) {
             Ok(s) => s,
             // Skip combinations that the OS doesn't allow (e.g. reserved system shortcuts)
-            Err(_) => continue,
+            Err(e) => {
+                log::debug!("[hotkey] skipping '{}': {}", hotkey_str, e);
+                continue;
+            }
         };
 
         // If unregister_all() silently failed and the shortcut is still registered,
         // keep the existing handler instead of returning a duplicate-registration error.
         if app.global_shortcut().is_registered(shortcut) {
+            log::debug!("[hotkey] '{}' is still registered, keeping it", hotkey_str);
+            register(hotkey_str, shortcuts::ShortcutEngine::Plugin);
             continue;
         }
 
-        let current_profile = current_profile.clone();
-        let is_muted = is_muted.clone();
-
         app.global_shortcut()
             .on_shortcut(shortcut, move |app, _shortcut, event| {
                 // Only toggle on key press, not on key release
                 use tauri_plugin_global_shortcut::ShortcutState;
-                if event.state != ShortcutState::Pressed {
-                    return;
+                if event.state == ShortcutState::Pressed {
+                    on_toggle_hotkey(app);
                 }
+            })
+            .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey_str, e))?;
 
-                // Clone the active profile under the lock, then release it before any work.
-                let profile = {
-                    let profile_lock = current_profile.lock_safe();
-                    match profile_lock.as_ref() {
-                        Some(profile) => profile.clone(),
-                        None => return,
-                    }
-                };
+        log::debug!("[hotkey] registered '{}'", hotkey_str);
+        register(hotkey_str, shortcuts::ShortcutEngine::Plugin);
+    }
 
-                // Fast path: toggle based on cached state and apply changes on the worker
-                let new_state = !is_muted.load(Ordering::SeqCst);
-                is_muted.store(new_state, Ordering::SeqCst);
+    Ok(())
+}
 
-                // Play sound feedback immediately
-                if new_state {
-                    sound::play_mute_sound();
-                } else {
-                    sound::play_unmute_sound();
+/// A press of the profile's toggle hotkey, from the shortcut plugin or the keyboard hook.
+fn on_toggle_hotkey(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    // Clone the active profile under the lock, then release it before any work.
+    let Some(profile) = state.current_profile.lock_safe().clone() else {
+        return;
+    };
+
+    toggle_profile_devices(&state, app, profile, "hotkey");
+}
+
+/// Payload of `profile-hotkey-conflict`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileHotkeyConflictEvent {
+    profile_id: String,
+    shortcut: String,
+    /// The registration already holding the shortcut.
+    taken_by: shortcuts::RegisteredShortcut,
+}
+
+/// With `multi_profile_hotkeys`, register the toggle key of every profile but the active
+/// one, bound to that profile. Keys already taken by the active profile, a global hotkey
+/// or an earlier profile are skipped and reported with `profile-hotkey-conflict`.
+fn register_other_profile_hotkeys(app: &AppHandle, state: &AppState) {
+    for previous in shortcuts::remove(state, shortcuts::ShortcutPurpose::ProfileToggle) {
+        if let Ok(shortcut) = Shortcut::from_str(&previous) {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    }
+    if !state.multi_profile_hotkeys.load(Ordering::SeqCst) || suspend::is_suspended(state) {
+        return;
+    }
+    // The desktop portal binds one action per purpose, which belongs to the active profile
+    if portal_shortcuts::is_active() {
+        state.record_error(
+            "multi_profile_hotkeys",
+            "Profiles can't have their own hotkeys under Wayland",
+        );
+        return;
+    }
+
+    let active_id = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.id.clone());
+    for profile in load_profiles(app) {
+        if active_id.as_deref() == Some(profile.id.as_str()) {
+            continue;
+        }
+        if let Err(e) = register_other_profile_hotkey(app, state, &profile) {
+            state.record_error("multi_profile_hotkeys", e);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProfileHotkeyPlan {
+    free: Vec<String>,
+    /// Shortcuts with the registration already holding them.
+    taken: Vec<(String, shortcuts::RegisteredShortcut)>,
+}
+
+/// The shortcuts to register for a profile other than the active one, split by whether
+/// the active profile, a global hotkey or an earlier profile already holds them.
+fn plan_profile_hotkeys(
+    state: &AppState,
+    profile: &HotkeyProfile,
+) -> Result<ProfileHotkeyPlan, String> {
+    // The keyboard hook binds one action per key, which belongs to the active profile
+    if let hotkey::KeyRef::Vk(_) = profile.toggle_key.key {
+        return Err(format!(
+            "The hotkey '{}' of profile '{}' only works while the profile is active",
+            profile.toggle_key, profile.name
+        ));
+    }
+    let resolved;
+    let hotkey = if let hotkey::KeyRef::Scancode(_) = profile.toggle_key.key {
+        resolved = scancode::resolve(&profile.toggle_key)?;
+        &resolved
+    } else {
+        &profile.toggle_key
+    };
+    let hotkeys_to_register: Vec<String> = if profile.ignore_modifiers {
+        hotkey
+            .with_all_modifier_combinations()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        vec![hotkey.to_string()]
+    };
+
+    let mut plan = ProfileHotkeyPlan::default();
+    for hotkey_str in hotkeys_to_register {
+        match shortcuts::owner(state, &hotkey_str) {
+            Some(taken_by) => plan.taken.push((hotkey_str, taken_by)),
+            None => plan.free.push(hotkey_str),
+        }
+    }
+    Ok(plan)
+}
+
+/// Register the shortcuts of `plan_profile_hotkeys` for `profile`. One that fails is
+/// recorded and the others are still registered.
+fn register_other_profile_hotkey(
+    app: &AppHandle,
+    state: &AppState,
+    profile: &HotkeyProfile,
+) -> Result<(), String> {
+    let plan = plan_profile_hotkeys(state, profile)?;
+    for (hotkey_str, taken_by) in plan.taken {
+        log::warn!(
+            "[hotkey] '{}' of profile '{}' is already taken by {:?}",
+            hotkey_str,
+            profile.name,
+            taken_by.purpose
+        );
+        events::emit(
+            app,
+            events::EventKind::Hotkey,
+            "profile-hotkey-conflict",
+            ProfileHotkeyConflictEvent {
+                profile_id: profile.id.clone(),
+                shortcut: hotkey_str,
+                taken_by,
+            },
+        );
+    }
+    for hotkey_str in plan.free {
+        let shortcut = match Shortcut::from_str(&hotkey_str) {
+            Ok(s) => s,
+            Err(e) => {
+                log::debug!("[hotkey] skipping '{}': {}", hotkey_str, e);
+                continue;
+            }
+        };
+        let profile_id = profile.id.clone();
+        let handler = move |app: &AppHandle, _: &Shortcut, event: ShortcutEvent| {
+            if event.state == ShortcutState::Pressed {
+                on_profile_hotkey(app, &profile_id);
+            }
+        };
+        if let Err(e) = app.global_shortcut().on_shortcut(shortcut, handler) {
+            state.record_error(
+                "multi_profile_hotkeys",
+                format!("Failed to register hotkey '{}': {}", hotkey_str, e),
+            );
+            continue;
+        }
+        log::debug!(
+            "[hotkey] registered '{}' for profile '{}'",
+            hotkey_str,
+            profile.name
+        );
+        shortcuts::add(
+            state,
+            hotkey_str,
+            Some(profile.id.clone()),
+            shortcuts::ShortcutPurpose::ProfileToggle,
+            shortcuts::ShortcutEngine::Plugin,
+        );
+    }
+    Ok(())
+}
+
+/// A press of a profile's own hotkey with `multi_profile_hotkeys`.
+fn on_profile_hotkey(app: &AppHandle, profile_id: &str) {
+    let state = app.state::<AppState>();
+    let active = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .is_some_and(|p| p.id == profile_id);
+    // Activated since the hotkeys were registered
+    if active {
+        on_toggle_hotkey(app);
+        return;
+    }
+    let Some(profile) = load_profiles(app).into_iter().find(|p| p.id == profile_id) else {
+        return;
+    };
+    if let Err(e) = toggle_other_profile(&state, app, profile, "hotkey") {
+        state.record_error("profile_hotkey", e);
+    }
+}
+
+/// Toggle the devices of `profile`, which isn't the active profile, on the audio worker.
+/// The devices are read first, since the cached state in `AppState::profile_mute_states`
+/// misses changes made through other profiles. The result is cached and emitted as
+/// `profile-mute-changed`; the active profile's state, tray and integrations stay as they
+/// are.
+fn toggle_other_profile(
+    state: &AppState,
+    app: &AppHandle,
+    profile: HotkeyProfile,
+    source: &str,
+) -> Result<(), String> {
+    let cached = state
+        .profile_mute_states
+        .lock_safe()
+        .get(&profile.id)
+        .copied()
+        .unwrap_or(MuteState::Unmuted);
+    let mixed_toggle_mutes = state.mixed_toggle_mutes.load(Ordering::SeqCst);
+    let devices = state.fresh_devices();
+    let worker = state.audio_worker.lock_safe();
+    let worker = worker
+        .as_ref()
+        .ok_or_else(|| "Audio worker not running".to_string())?;
+
+    let app = app.clone();
+    let source = source.to_string();
+    let profile_id = profile.id.clone();
+    worker.query(
+        "toggle profile",
+        move |controller| -> Result<(bool, ProfileMuteState), String> {
+            let resolved = resolve_device_ids(controller, &profile, devices.as_deref())?;
+            let is_muted = |id: &str| hard_mute::is_muted(controller, id, profile.mute_strategy);
+            let current = query_device_mute_states(&profile, &resolved.present, is_muted, cached);
+            let muted = match current.state {
+                MuteState::Muted => false,
+                MuteState::Unmuted => true,
+                MuteState::Mixed => mixed_toggle_mutes,
+            };
+            for device_id in &resolved.present {
+                let target = scene::target(&profile, device_id, muted);
+                if let Err(e) =
+                    hard_mute::set_mute(controller, device_id, target, profile.mute_strategy)
+                {
+                    log::warn!(
+                        "[hotkey] failed to change {} for profile '{}': {}",
+                        device_id,
+                        profile.name,
+                        e
+                    );
+                }
+            }
+            let queried = query_device_mute_states(
+                &profile,
+                &resolved.present,
+                is_muted,
+                MuteState::from(muted),
+            );
+            Ok((muted, queried))
+        },
+        move |result| {
+            let state = app.state::<AppState>();
+            let (muted, queried) = match result {
+                Some(Ok(result)) => result,
+                Some(Err(e)) => {
+                    state.record_error("profile_hotkey", e);
+                    return;
+                }
+                None => {
+                    state.record_error("profile_hotkey", "Toggling the profile timed out");
+                    return;
                 }
+            };
+            if muted {
+                sound::play_mute_sound();
+            } else {
+                sound::play_unmute_sound();
+            }
+            state
+                .profile_mute_states
+                .lock_safe()
+                .insert(profile_id.clone(), queried.state);
+            events::emit_profile_mute_state(
+                &app,
+                profile_id,
+                queried.state,
+                &source,
+                queried.devices,
+            );
+            poll::wake();
+        },
+    );
+    Ok(())
+}
+
+/// Unregister every shortcut, from the plugin, the keyboard hook and the desktop portal
+/// alike.
+fn unregister_all_shortcuts(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    keyboard_hook::unbind_all();
+    portal_shortcuts::unbind_all();
+    shortcuts::clear(state);
+    app.global_shortcut()
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister hotkeys: {}", e))
+}
+
+/// Register the active profile's temporary unmute hotkey, if it has one. Failures are
+/// recorded but not returned, like the panic hotkey's.
+fn register_temporary_unmute_hotkey(app: &AppHandle, state: &AppState) {
+    let profile = state.current_profile.lock_safe().clone();
+    let Some((profile_id, key)) = profile.and_then(|p| Some(p.id).zip(p.temporary_unmute_key))
+    else {
+        return;
+    };
+
+    match bind_temporary_unmute_hotkey(app, key) {
+        Ok((key, engine)) => {
+            log::info!("[hotkey] registered temporary unmute hotkey '{}'", key);
+            shortcuts::add(
+                state,
+                key.to_string(),
+                Some(profile_id),
+                shortcuts::ShortcutPurpose::TemporaryUnmute,
+                engine,
+            );
+        }
+        Err(e) => {
+            state.record_error("temporary_unmute_hotkey", e);
+        }
+    }
+}
+
+/// Register `key` for `on_temporary_unmute_hotkey` and return what it was registered as.
+fn bind_temporary_unmute_hotkey(
+    app: &AppHandle,
+    key: hotkey::HotkeyBinding,
+) -> Result<(hotkey::HotkeyBinding, shortcuts::ShortcutEngine), String> {
+    let key = match key.key {
+        hotkey::KeyRef::Scancode(_) => scancode::resolve(&key)?,
+        _ => key,
+    };
+    if let hotkey::KeyRef::Vk(vk) = key.key {
+        keyboard_hook::bind(app, vk, on_temporary_unmute_hotkey)?;
+        return Ok((key, shortcuts::ShortcutEngine::Hook));
+    }
+    if portal_shortcuts::is_active() {
+        portal_shortcuts::bind(
+            app,
+            shortcuts::ShortcutPurpose::TemporaryUnmute,
+            &key,
+            on_temporary_unmute_hotkey,
+        )?;
+        return Ok((key, shortcuts::ShortcutEngine::Portal));
+    }
+    let shortcut = Shortcut::from_str(&key.to_string())
+        .map_err(|e| format!("Invalid temporary unmute hotkey '{}': {}", key, e))?;
+    if app.global_shortcut().is_registered(shortcut) {
+        return Err(format!(
+            "Temporary unmute hotkey '{}' is already used by the profile hotkey",
+            key
+        ));
+    }
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            use tauri_plugin_global_shortcut::ShortcutState;
+            if event.state == ShortcutState::Pressed {
+                on_temporary_unmute_hotkey(app);
+            }
+        })
+        .map_err(|e| {
+            format!(
+                "Failed to register temporary unmute hotkey '{}': {}",
+                key, e
+            )
+        })?;
+    Ok((key, shortcuts::ShortcutEngine::Plugin))
+}
+
+/// A press of the profile's temporary unmute hotkey.
+fn on_temporary_unmute_hotkey(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(seconds) = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.temporary_unmute_seconds)
+    else {
+        return;
+    };
+    if let Err(e) = temporary_unmute::start(app, seconds, "hotkey") {
+        state.record_error("temporary_unmute", e);
+    }
+}
+
+/// (Re-)register the panic unmute and undo hotkeys from `AppState::panic_unmute_key` and
+/// `AppState::undo_key`.
+fn register_global_hotkeys(app: &AppHandle, state: &AppState) {
+    let panic_key = state.panic_unmute_key.lock_safe().clone();
+    register_global_hotkey(
+        app,
+        state,
+        shortcuts::ShortcutPurpose::Panic,
+        panic_key,
+        "panic",
+        panic_unmute,
+    );
+    let undo_key = state.undo_key.lock_safe().clone();
+    register_global_hotkey(
+        app,
+        state,
+        shortcuts::ShortcutPurpose::Undo,
+        undo_key,
+        "undo",
+        undo_hotkey_pressed,
+    );
+}
+
+/// Register `hotkey` to run `action` on press, replacing the previous hotkey for
+/// `purpose`. Failures are logged and recorded but not returned, so they never get in the
+/// way of profile hotkeys.
+fn register_global_hotkey(
+    app: &AppHandle,
+    state: &AppState,
+    purpose: shortcuts::ShortcutPurpose,
+    hotkey: Option<String>,
+    name: &str,
+    action: fn(&AppHandle),
+) {
+    for previous in shortcuts::remove(state, purpose) {
+        if let Ok(shortcut) = Shortcut::from_str(&previous) {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    }
+    portal_shortcuts::unbind(purpose);
+    if suspend::is_suspended(state) {
+        return;
+    }
 
-                // Emit event to frontend and update tray icon
-                let _ = app.emit("mute-state-changed", new_state);
-                update_tray_icon(app, new_state);
+    let Some(hotkey) = hotkey.filter(|k| !k.trim().is_empty()) else {
+        return;
+    };
 
-                // Apply system mute on the worker; it notifies + reconciles if a device is gone
-                apply_mute(profile, new_state);
+    let engine = if portal_shortcuts::is_active() {
+        shortcuts::ShortcutEngine::Portal
+    } else {
+        shortcuts::ShortcutEngine::Plugin
+    };
+    let result = if engine == shortcuts::ShortcutEngine::Portal {
+        hotkey
+            .parse::<hotkey::HotkeyBinding>()
+            .map_err(|e| format!("Invalid {} hotkey '{}': {}", name, hotkey, e))
+            .and_then(|binding| {
+                portal_shortcuts::bind(app, purpose, &binding, action)
+                    .map_err(|e| format!("Failed to register {} hotkey '{}': {}", name, hotkey, e))
             })
-            .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey_str, e))?;
+    } else {
+        Shortcut::from_str(&hotkey)
+            .map_err(|e| format!("Invalid {} hotkey '{}': {}", name, hotkey, e))
+            .and_then(|shortcut| {
+                if app.global_shortcut().is_registered(shortcut) {
+                    return Err(format!(
+                        "The {} hotkey '{}' is already used by another hotkey",
+                        name, hotkey
+                    ));
+                }
+                app.global_shortcut()
+                    .on_shortcut(shortcut, move |app, _shortcut, event| {
+                        use tauri_plugin_global_shortcut::ShortcutState;
+                        if event.state == ShortcutState::Pressed {
+                            action(app);
+                        }
+                    })
+                    .map_err(|e| format!("Failed to register {} hotkey '{}': {}", name, hotkey, e))
+            })
+    };
+
+    match result {
+        Ok(()) => {
+            log::info!("[hotkey] registered {} hotkey '{}'", name, hotkey);
+            state.record_event(
+                events::EventKind::Hotkey,
+                &format!("{}-hotkey-registration", name),
+                serde_json::json!({ "hotkey": hotkey }),
+            );
+            shortcuts::add(state, hotkey, None, purpose, engine);
+        }
+        Err(e) => {
+            state.record_error(&format!("{}_hotkey", name), e);
+        }
     }
+}
 
-    Ok(())
+fn undo_hotkey_pressed(app: &AppHandle) {
+    if let Err(e) = undo::undo(app) {
+        app.state::<AppState>().record_error("undo", e);
+    }
+}
+
+/// Unmute every input device on the system, whatever the active profile is (or whether
+/// there is one). Runs on the audio worker, under its deadline, and publishes the unmuted
+/// state once the devices have been changed.
+fn panic_unmute(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let worker = state.audio_worker.lock_safe();
+    let Some(worker) = worker.as_ref() else {
+        state.record_error(
+            "panic_unmute",
+            state.audio_init_status.lock_safe().error_message(),
+        );
+        return;
+    };
+    let app = app.clone();
+    worker.query(
+        "panic unmute",
+        |controller| {
+            controller.enumerate_input_devices().map(|devices| {
+                devices
+                    .iter()
+                    .filter_map(|device| {
+                        hard_mute::set_mute(controller, &device.id, false, Default::default()).err()
+                    })
+                    .collect::<Vec<_>>()
+            })
+        },
+        move |result| {
+            let state = app.state::<AppState>();
+            match result {
+                Some(Ok(errors)) if !errors.is_empty() => {
+                    state.record_error(
+                        "panic_unmute",
+                        format!("Some devices could not be unmuted: {}", errors.join("; ")),
+                    );
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    state.record_error("panic_unmute", e);
+                    return;
+                }
+                None => {
+                    state.record_error("panic_unmute", "Timed out unmuting the devices");
+                    return;
+                }
+            }
+
+            temporary_unmute::cancel(&app);
+            state.set_muted(false);
+            sound::play_unmute_sound();
+            publish_mute_state(&app, MuteState::Unmuted, "panic");
+        },
+    );
 }
 
 #[tauri::command]
 fn register_hotkey(
-    hotkey: String,
+    hotkey: hotkey::HotkeyBinding,
     ignore_modifiers: Option<bool>,
     app: AppHandle,
     state: State<AppState>,
-) -> Result<(), String> {
+) -> Result<Vec<shortcuts::RegisteredShortcut>, String> {
     do_register_hotkey(&hotkey, ignore_modifiers.unwrap_or(false), &app, &state)
+        .map_err(|e| state.record_error("register_hotkey", e))?;
+    // Which engine each binding landed in, for the UI
+    Ok(shortcuts::registered(
+        &state,
+        shortcuts::ShortcutPurpose::Toggle,
+    ))
+}
+
+/// Unregister all hotkeys while keeping the active profile, for `minutes` (default:
+/// `hotkey_suspend_minutes`; 0 means until `resume_hotkeys`).
+#[tauri::command]
+fn suspend_hotkeys(
+    minutes: Option<u32>,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let minutes = minutes.unwrap_or_else(|| load_app_settings(&app).hotkey_suspend_minutes);
+    suspend::suspend(&app, minutes).map_err(|e| state.record_error("suspend_hotkeys", e))
+}
+
+#[tauri::command]
+fn resume_hotkeys(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    suspend::resume(&app).map_err(|e| state.record_error("resume_hotkeys", e))
+}
+
+/// Capture the next key press system-wide for the hotkey picker; the active hotkeys are
+/// suspended meanwhile.
+#[tauri::command]
+async fn capture_next_hotkey(
+    timeout_ms: Option<u64>,
+    app: AppHandle,
+) -> Result<hotkey_capture::CaptureResult, String> {
+    hotkey_capture::capture(
+        app.clone(),
+        timeout_ms.unwrap_or(hotkey_capture::DEFAULT_TIMEOUT_MS),
+    )
+    .await
+    .map_err(|e| {
+        app.state::<AppState>()
+            .record_error("capture_next_hotkey", e)
+    })
+}
+
+/// Every shortcut TogMic has registered, flagged `stale` where the OS registration is gone.
+#[tauri::command]
+fn list_registered_shortcuts(app: AppHandle) -> Vec<shortcuts::ShortcutStatus> {
+    shortcuts::list(&app)
+}
+
+/// Whether `hotkey` can be bound, and which well-known shortcuts it would shadow.
+#[tauri::command]
+fn check_hotkey_available(
+    hotkey: hotkey::HotkeyBinding,
+    ignore_modifiers: Option<bool>,
+) -> Result<Vec<hotkey::conflicts::HotkeyConflict>, String> {
+    hotkey.validate()?;
+    Ok(hotkey::conflicts::check(
+        &hotkey,
+        ignore_modifiers.unwrap_or(false),
+    ))
+}
+
+/// How to show `binding` in the UI: scancode keys are named after the current layout.
+#[tauri::command]
+fn get_hotkey_display_name(binding: hotkey::HotkeyBinding) -> String {
+    scancode::display_name(&binding)
+}
+
+#[tauri::command]
+fn get_hotkeys_suspended(state: State<AppState>) -> bool {
+    suspend::is_suspended(&state)
+}
+
+/// Unmute for `seconds` (default: the active profile's `temporary_unmute_seconds`), then
+/// mute again unless the state is changed meanwhile. Extends a running temporary unmute.
+#[tauri::command]
+fn temporary_unmute(
+    seconds: Option<u32>,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let seconds = seconds
+        .or_else(|| {
+            state
+                .current_profile
+                .lock_safe()
+                .as_ref()
+                .map(|p| p.temporary_unmute_seconds)
+        })
+        .unwrap_or_else(default_temporary_unmute_seconds);
+    temporary_unmute::start(&app, seconds, "frontend")
+        .map_err(|e| state.record_error("temporary_unmute", e))
+}
+
+/// Seconds until a running temporary unmute mutes again.
+#[tauri::command]
+fn get_temporary_unmute_remaining() -> Option<u64> {
+    temporary_unmute::remaining_secs()
 }
 
 #[tauri::command]
 fn unregister_hotkey(app: AppHandle, state: State<AppState>) -> Result<(), String> {
-    let mut profile_lock = state.current_profile.lock_safe();
-    *profile_lock = None;
-    drop(profile_lock);
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| format!("Failed to unregister hotkeys: {}", e))
+    set_current_profile(&app, &state, None);
+    let result = unregister_all_shortcuts(&app, &state)
+        .map_err(|e| state.record_error("unregister_hotkey", e));
+
+    // The panic and undo hotkeys stay available without a profile
+    register_global_hotkeys(&app, &state);
+    result
 }
 
 #[tauri::command]
 async fn set_autostart(enabled: bool, app: AppHandle) -> Result<(), String> {
+    sync_autostart(&app, enabled)
+        .map_err(|e| app.state::<AppState>().record_error("set_autostart", e))
+}
+
+/// Make the OS autostart entry match `enabled`.
+fn sync_autostart(app: &AppHandle, enabled: bool) -> Result<(), String> {
     let autostart_manager = app.state::<tauri_plugin_autostart::AutoLaunchManager>();
 
     if enabled {
         autostart_manager
             .enable()
             .map_err(|e| format!("Failed to enable autostart: {}", e))?;
-    } else {
-        if let Err(e) = autostart_manager.disable() {
-            // If disable fails, check if the entry already doesn't exist (desired state)
-            let still_enabled = autostart_manager.is_enabled().unwrap_or(false);
-            if still_enabled {
-                return Err(format!("Failed to disable autostart: {}", e));
-            }
-            // Already disabled - desired state achieved, no error
+    } else if let Err(e) = autostart_manager.disable() {
+        // If disable fails, check if the entry already doesn't exist (desired state)
+        let still_enabled = autostart_manager.is_enabled().unwrap_or(false);
+        if still_enabled {
+            return Err(format!("Failed to disable autostart: {}", e));
         }
+        // Already disabled - desired state achieved, no error
     }
 
     Ok(())
@@ -671,9 +3165,157 @@ async fn set_autostart(enabled: bool, app: AppHandle) -> Result<(), String> {
 #[tauri::command]
 async fn get_autostart_status(app: AppHandle) -> Result<bool, String> {
     let autostart_manager = app.state::<tauri_plugin_autostart::AutoLaunchManager>();
-    autostart_manager
-        .is_enabled()
-        .map_err(|e| format!("Failed to get autostart status: {}", e))
+    autostart_manager.is_enabled().map_err(|e| {
+        app.state::<AppState>().record_error(
+            "get_autostart_status",
+            format!("Failed to get autostart status: {}", e),
+        )
+    })
+}
+
+/// Copy the settings that only live in memory into `AppState` and the runtime modules.
+/// Integrations with connections (API, MQTT, OBS) and autostart are handled by the callers.
+fn apply_settings_to_state(app: &AppHandle, settings: &AppSettings) {
+    let state = app.state::<AppState>();
+    *state.close_to_tray.lock_safe() = settings.close_to_tray;
+    state
+        .minimize_to_tray
+        .store(settings.minimize_to_tray, Ordering::SeqCst);
+    state
+        .tray_config_folder
+        .store(settings.tray_config_folder, Ordering::SeqCst);
+    state
+        .tray_left_click_toggles
+        .store(settings.tray_left_click_toggles, Ordering::SeqCst);
+    if let Some(tray) = app.tray_by_id("main-tray") {
+        let _ = tray.set_show_menu_on_left_click(!settings.tray_left_click_toggles);
+    }
+    *state.tray_extra_items.lock_safe() = settings.tray_extra_items.clone();
+    exit_restore::configure(settings);
+    tray_icons::configure(settings.classic_tray_icons, settings.tray_icon_style);
+    update_tray_icon(app, state.mute_state.get());
+    state.mixed_toggle_mutes.store(
+        settings.mixed_toggle_target == MixedToggleTarget::Mute,
+        Ordering::SeqCst,
+    );
+    poll::set_base_interval(settings.poll_interval_ms);
+    audio_worker::set_timeout_ms(settings.audio_timeout_ms);
+    hooks::configure(settings.hooks.clone());
+    webhooks::configure(settings.webhooks.clone());
+    state
+        .mute_on_lock
+        .store(settings.mute_on_lock, Ordering::SeqCst);
+    state
+        .restore_on_unlock
+        .store(settings.restore_on_unlock, Ordering::SeqCst);
+    idle::configure(settings.idle_mute_minutes, settings.unmute_on_return);
+    hot_mic::configure(
+        settings.max_unmuted_minutes,
+        settings.hot_mic_warning_minutes,
+        settings.hot_mic_action,
+        settings.hot_mic_notify,
+    );
+    reconcile::configure(settings.external_change_policy);
+    speaking::configure(
+        settings.speaking_reminder,
+        settings.speaking_threshold,
+        settings.speaking_reminder_notify,
+        settings.speaking_reminder_sound,
+    );
+    voice_activity::configure(settings.vad.clone());
+    mic_usage::configure(settings.track_mic_usage);
+    capture_switch::configure(settings.on_unknown_capture);
+    led_indicator::configure(
+        settings.led_indicator,
+        settings.led_indicator_key,
+        state.mute_state.get(),
+    );
+    lights::configure(&settings.busylight, state.mute_state.get());
+    focus_assist::configure(settings.respect_focus_assist);
+    mute_notify::configure(settings.notify_mute_changes);
+    sound::configure(
+        settings.sound_theme,
+        settings.sound_category,
+        settings.max_sound_file_mb,
+    );
+    stats::configure(app, settings.collect_stats);
+    history::configure(settings.history_retention_days);
+    rebuild_tray_menu(app);
+
+    undo::configure(settings.undo_window_seconds);
+    let panic_key_changed = {
+        let mut panic_key = state.panic_unmute_key.lock_safe();
+        let changed = *panic_key != settings.panic_unmute_key;
+        *panic_key = settings.panic_unmute_key.clone();
+        changed
+    };
+    let undo_key_changed = {
+        let mut undo_key = state.undo_key.lock_safe();
+        let changed = *undo_key != settings.undo_key;
+        *undo_key = settings.undo_key.clone();
+        changed
+    };
+    if panic_key_changed || undo_key_changed {
+        register_global_hotkeys(app, &state);
+    }
+    let multi_changed = state
+        .multi_profile_hotkeys
+        .swap(settings.multi_profile_hotkeys, Ordering::SeqCst)
+        != settings.multi_profile_hotkeys;
+    if multi_changed || panic_key_changed || undo_key_changed {
+        register_other_profile_hotkeys(app, &state);
+    }
+}
+
+/// Serializes `apply_app_settings` calls so the saved config and the running state always
+/// come from the same call.
+static APPLY_SETTINGS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Persist `settings` and apply all of them to the running app in one call. Integrations
+/// are only restarted when their own settings changed. Emits `app-settings-changed` so
+/// other windows pick up the new settings.
+#[tauri::command]
+async fn apply_app_settings(settings: AppSettings, app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let _guard = APPLY_SETTINGS_LOCK.lock_safe();
+
+    let previous = load_app_settings(&app);
+    save_app_settings(&app, &settings).map_err(|e| state.record_error("apply_app_settings", e))?;
+    apply_settings_to_state(&app, &settings);
+
+    // Everything else is attempted even if one part fails
+    let mut errors = Vec::new();
+    if let Err(e) = sync_autostart(&app, settings.autostart) {
+        errors.push(e);
+    }
+    if settings.api_port != previous.api_port {
+        if let Err(e) = set_api_server(settings.api_port, app.clone()) {
+            errors.push(e);
+        }
+    }
+    if settings.mqtt != previous.mqtt {
+        if let Err(e) = set_mqtt_settings(settings.mqtt.clone(), app.clone()) {
+            errors.push(e);
+        }
+    }
+    if settings.obs != previous.obs {
+        if let Err(e) = set_obs_settings(settings.obs.clone(), app.clone()) {
+            errors.push(e);
+        }
+    }
+    if settings.peers != previous.peers {
+        if let Err(e) = set_peer_settings(settings.peers.clone(), app.clone()) {
+            errors.push(e);
+        }
+    }
+
+    let _ = app.emit("app-settings-changed", &settings);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(state.record_error("apply_app_settings", errors.join("; ")))
+    }
 }
 
 #[tauri::command]
@@ -683,7 +3325,26 @@ fn set_close_to_tray(enabled: bool, state: State<AppState>) -> Result<(), String
     Ok(())
 }
 
+/// Select the locale of the tray menu, tooltips and notifications. Returns the locale
+/// actually used, which falls back to the base language or English.
+#[tauri::command]
+fn set_locale(lang: String, state: State<AppState>, app: AppHandle) -> Result<String, String> {
+    let selected =
+        i18n::set_locale(&app, &lang).map_err(|e| state.record_error("set_locale", e))?;
+    rebuild_tray_menu(&app);
+    refresh_tray_tooltip(&app);
+    Ok(selected)
+}
+
 #[tauri::command]
+fn list_locales() -> Vec<i18n::LocaleInfo> {
+    i18n::list_locales()
+}
+
+/// Deprecated: use `set_locale`. The strings are kept as overrides on top of the selected
+/// locale bundle until the next `set_locale`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn update_tray_labels(
     mute: String,
     unmute: String,
@@ -691,55 +3352,147 @@ fn update_tray_labels(
     quit: String,
     muted_tooltip: String,
     unmuted_tooltip: String,
-    state: State<AppState>,
+    mixed_tooltip: Option<String>,
+    failed_tooltip: Option<String>,
     app: AppHandle,
 ) -> Result<(), String> {
-    *state.tray_label_mute.lock_safe() = mute;
-    *state.tray_label_unmute.lock_safe() = unmute;
-    *state.tray_label_show.lock_safe() = show;
-    *state.tray_label_quit.lock_safe() = quit;
-    *state.tray_tooltip_muted.lock_safe() = muted_tooltip.clone();
-    *state.tray_tooltip_unmuted.lock_safe() = unmuted_tooltip.clone();
-
-    let is_muted = state.is_muted.load(Ordering::SeqCst);
-    rebuild_tray_menu(&app, is_muted);
-
-    if let Some(tray) = app.tray_by_id("main-tray") {
-        let tooltip = if is_muted {
-            &muted_tooltip
-        } else {
-            &unmuted_tooltip
-        };
-        let _ = tray.set_tooltip(Some(tooltip.as_str()));
+    let mut overrides = vec![
+        ("trayMute", mute),
+        ("trayUnmute", unmute),
+        ("trayShowWindow", show),
+        ("trayQuit", quit),
+        ("trayMutedTooltip", muted_tooltip),
+        ("trayUnmutedTooltip", unmuted_tooltip),
+    ];
+    if let Some(mixed_tooltip) = mixed_tooltip {
+        overrides.push(("trayMixedTooltip", mixed_tooltip));
+    }
+    if let Some(failed_tooltip) = failed_tooltip {
+        overrides.push(("trayFailedTooltip", failed_tooltip));
     }
+    i18n::set_overrides(overrides);
+
+    rebuild_tray_menu(&app);
+    refresh_tray_tooltip(&app);
 
     Ok(())
 }
 
-fn rebuild_tray_menu(app: &AppHandle, is_muted: bool) {
+/// Check the tray's mute entry while muted, or while a click would unmute a mixed state.
+/// Skipped if the mark is already right, unless `force`: clicking the entry flips the mark
+/// by itself, also when the toggle then fails.
+fn update_tray_toggle_item(app: &AppHandle, force: bool) {
     let state = app.state::<AppState>();
-    let toggle_label = if is_muted {
-        state.tray_label_unmute.lock_safe().clone()
-    } else {
-        state.tray_label_mute.lock_safe().clone()
-    };
-    let show_label = state.tray_label_show.lock_safe().clone();
-    let quit_label = state.tray_label_quit.lock_safe().clone();
+    let checked = !state.toggle_target();
+    set_tray_checked(
+        &state.tray_toggle_item,
+        &state.last_tray_checked,
+        checked,
+        force,
+        |item| match item.set_checked(checked) {
+            Ok(()) => true,
+            Err(e) => {
+                report_error(
+                    app,
+                    ErrorCategory::Tray,
+                    format!("Failed to update tray menu: {}", e),
+                    None,
+                );
+                false
+            }
+        },
+    );
+}
 
-    if let Some(tray) = app.tray_by_id("main-tray") {
-        if let (Ok(toggle_item), Ok(show_item), Ok(quit_item)) = (
-            MenuItemBuilder::with_id("toggle", &toggle_label).build(app),
-            MenuItemBuilder::with_id("show", &show_label).build(app),
-            MenuItemBuilder::with_id("quit", &quit_label).build(app),
+/// Show `checked` on the tray toggle item unless it already does. `set` waits on the main
+/// thread, which takes both locks while rebuilding the menu, so it runs without them.
+fn set_tray_checked<T: Clone>(
+    item: &Mutex<Option<T>>,
+    last_checked: &Mutex<Option<bool>>,
+    checked: bool,
+    force: bool,
+    set: impl FnOnce(&T) -> bool,
+) {
+    if !force && *last_checked.lock_safe() == Some(checked) {
+        return;
+    }
+    let item = item.lock_safe().clone();
+    if let Some(item) = item {
+        if !set(&item) {
+            return;
+        }
+    }
+    *last_checked.lock_safe() = Some(checked);
+}
+
+/// Build the tray menu anew. Only needed when labels or entries change; the mute entry's
+/// check mark is kept up to date by `update_tray_toggle_item`.
+fn rebuild_tray_menu(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let toggle_label = i18n::translate("trayMute");
+    let checked = !state.toggle_target();
+    let hotkeys_label = if suspend::is_suspended(&state) {
+        i18n::translate("trayResumeHotkeys")
+    } else {
+        i18n::translate("traySuspendHotkeys")
+    };
+    let show_label = i18n::translate("trayShowWindow");
+    let config_label = i18n::translate("trayOpenConfigFolder");
+    let quit_label = i18n::translate("trayQuit");
+
+    if let Some(tray) = app.tray_by_id("main-tray") {
+        if let (Ok(toggle_item), Ok(hotkeys_item), Ok(show_item), Ok(quit_item)) = (
+            CheckMenuItemBuilder::with_id("toggle", &toggle_label)
+                .checked(checked)
+                .build(app),
+            MenuItemBuilder::with_id("hotkeys", &hotkeys_label).build(app),
+            MenuItemBuilder::with_id("show", &show_label).build(app),
+            MenuItemBuilder::with_id("quit", &quit_label).build(app),
         ) {
-            if let Ok(menu) = MenuBuilder::new(app)
+            let config_item = state
+                .tray_config_folder
+                .load(Ordering::SeqCst)
+                .then(|| {
+                    MenuItemBuilder::with_id("config", &config_label)
+                        .build(app)
+                        .ok()
+                })
+                .flatten();
+            // Labels are looked up on every rebuild, so they follow locale changes
+            let extra_items: Vec<_> = state
+                .tray_extra_items
+                .lock_safe()
+                .iter()
+                .filter_map(|item| {
+                    let id = format!("{}{}", NAVIGATE_MENU_PREFIX, item.route);
+                    MenuItemBuilder::with_id(id, i18n::translate(&item.label))
+                        .build(app)
+                        .ok()
+                })
+                .collect();
+            let mut builder = MenuBuilder::new(app)
                 .item(&toggle_item)
+                .item(&hotkeys_item)
                 .separator()
-                .item(&show_item)
-                .item(&quit_item)
-                .build()
-            {
-                let _ = tray.set_menu(Some(menu));
+                .item(&show_item);
+            for item in &extra_items {
+                builder = builder.item(item);
+            }
+            if let Some(config_item) = &config_item {
+                builder = builder.item(config_item);
+            }
+            if let Ok(menu) = builder.item(&quit_item).build() {
+                if let Err(e) = tray.set_menu(Some(menu)) {
+                    report_error(
+                        app,
+                        ErrorCategory::Tray,
+                        format!("Failed to set tray menu: {}", e),
+                        None,
+                    );
+                    return;
+                }
+                *state.tray_toggle_item.lock_safe() = Some(toggle_item);
+                *state.last_tray_checked.lock_safe() = Some(checked);
             }
         }
     }
@@ -820,72 +3573,364 @@ fn trim_process_memory() {
     }
 }
 
+/// Tray tooltip for `mute_state`, noting devices the last mute change couldn't reach.
+fn tray_tooltip(state: &AppState, mute_state: MuteState) -> String {
+    let base = match mute_state {
+        MuteState::Muted => i18n::translate("trayMutedTooltip"),
+        MuteState::Unmuted => i18n::translate("trayUnmutedTooltip"),
+        MuteState::Mixed => i18n::translate("trayMixedTooltip"),
+    };
+    let base = if suspend::is_suspended(state) {
+        format!("{} ({})", base, i18n::translate("traySuspendedTooltip"))
+    } else {
+        base
+    };
+    let solo = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .is_some_and(|p| p.solo);
+    let base = if solo {
+        format!("{} ({})", base, i18n::translate("traySoloTooltip"))
+    } else {
+        base
+    };
+    let consumers = mic_usage::process_names();
+    let base = if consumers.is_empty() {
+        base
+    } else {
+        format!(
+            "{} ({})",
+            base,
+            i18n::translate_with("trayMicUsersTooltip", &[("names", &consumers.join(", "))])
+        )
+    };
+    match *state.tray_failed_devices.lock_safe() {
+        0 => base,
+        failed => format!(
+            "{} ({} {})",
+            base,
+            failed,
+            i18n::translate("trayFailedTooltip")
+        ),
+    }
+}
+
+fn refresh_tray_tooltip(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Some(tray) = app.tray_by_id("main-tray") {
+        let mute_state = state.mute_state.get();
+        if let Err(e) = tray.set_tooltip(Some(tray_tooltip(&state, mute_state).as_str())) {
+            report_error(
+                app,
+                ErrorCategory::Tray,
+                format!("Failed to set tray tooltip: {}", e),
+                None,
+            );
+        }
+    }
+}
+
 // Helper function to update the tray icon based on mute state
-fn update_tray_icon(app: &AppHandle, is_muted: bool) {
-    // Avoid redundant tray updates by comparing with cached visible state
+fn update_tray_icon(app: &AppHandle, mute_state: MuteState) {
     let state = app.state::<AppState>();
-    let mut last_muted_lock = state.last_tray_muted.lock_safe();
+    let Some(update) = claim_tray_update(
+        &state,
+        mute_state,
+        is_system_dark_mode,
+        // The badge also changes with the number of muted devices and the tray's scaling
+        |devices, dark_mode| tray_icons::key(app, mute_state, devices, dark_mode),
+    ) else {
+        return; // nothing to update
+    };
+
+    sync_tray(&state, update, |update| {
+        apply_tray_update(app, &state, update)
+    });
+    update_tray_toggle_item(app, false);
+}
+
+/// What the tray shows, as cached in `AppState::last_tray_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrayUpdate {
+    mute_state: MuteState,
+    dark_mode: bool,
+    icon_key: Option<tray_icons::IconKey>,
+}
+
+/// Work out what the tray should show for `mute_state` and record it as shown. `None` if
+/// it already shows that. The cache locks are only held in here: the tray calls may wait
+/// for the main thread, which can itself be waiting for these locks.
+fn claim_tray_update(
+    state: &AppState,
+    mute_state: MuteState,
+    read_dark_mode: impl FnOnce() -> bool,
+    icon_key: impl FnOnce(&[DeviceMuteState], bool) -> Option<tray_icons::IconKey>,
+) -> Option<TrayUpdate> {
+    let devices = state.device_mute_states.lock_safe().clone();
+    let mut last_state_lock = state.last_tray_state.lock_safe();
     let mut last_dark_lock = state.last_tray_dark_mode.lock_safe();
+    let mut last_icon_lock = state.last_tray_icon.lock_safe();
 
-    let mute_unchanged = last_muted_lock.map_or(false, |prev| prev == is_muted);
+    let mute_unchanged = *last_state_lock == Some(mute_state);
 
     // Defer the registry read: when mute changed, use the cached dark-mode value
     // (avoids a registry syscall on every toggle). Only call is_system_dark_mode()
     // when: (a) no cache yet, or (b) mute is unchanged (theme-change-listener path,
     // where the theme may have just changed and the cache is stale).
-    let dark_mode = if mute_unchanged || last_dark_lock.is_none() {
-        is_system_dark_mode()
-    } else {
-        last_dark_lock.unwrap()
+    let dark_mode = match *last_dark_lock {
+        Some(cached) if !mute_unchanged => cached,
+        _ => read_dark_mode(),
     };
 
-    let theme_unchanged = last_dark_lock.map_or(false, |prev| prev == dark_mode);
-    if mute_unchanged && theme_unchanged {
-        return; // nothing to update
+    let theme_unchanged = *last_dark_lock == Some(dark_mode);
+    let icon_key = icon_key(&devices, dark_mode);
+    let icon_unchanged = *last_icon_lock == icon_key;
+    if mute_unchanged && theme_unchanged && icon_unchanged {
+        return None;
     }
 
-    if let Some(tray) = app.tray_by_id("main-tray") {
-        // Use pre-computed dark_mode to select the icon, avoiding a second registry
-        // read that would otherwise happen inside get_tray_icon.
-        let icon = match (is_muted, dark_mode) {
-            (true, true) => LAZY_TRAY_MUTED_DARK.clone(),
-            (true, false) => LAZY_TRAY_MUTED_LIGHT.clone(),
-            (false, true) => LAZY_TRAY_UNMUTED_DARK.clone(),
-            (false, false) => LAZY_TRAY_UNMUTED_LIGHT.clone(),
-        };
-        let _ = tray.set_icon(Some(icon));
-        let tooltip = if is_muted {
-            state.tray_tooltip_muted.lock_safe().clone()
-        } else {
-            state.tray_tooltip_unmuted.lock_safe().clone()
-        };
-        let _ = tray.set_tooltip(Some(tooltip.as_str()));
+    *last_state_lock = Some(mute_state);
+    *last_dark_lock = Some(dark_mode);
+    *last_icon_lock = icon_key;
+    Some(TrayUpdate {
+        mute_state,
+        dark_mode,
+        icon_key,
+    })
+}
+
+/// The tray state last claimed, unless the theme listener invalidated it meanwhile (and
+/// will claim a new one itself).
+fn latest_tray_update(state: &AppState) -> Option<TrayUpdate> {
+    let mute_state = (*state.last_tray_state.lock_safe())?;
+    let dark_mode = (*state.last_tray_dark_mode.lock_safe())?;
+    let icon_key = *state.last_tray_icon.lock_safe();
+    Some(TrayUpdate {
+        mute_state,
+        dark_mode,
+        icon_key,
+    })
+}
+
+/// Apply `update`, then whatever was claimed while it was applied. Concurrent updates
+/// may apply out of order, so the one that finishes last re-applies the latest claim
+/// instead of leaving an older icon behind.
+fn sync_tray(state: &AppState, mut update: TrayUpdate, apply: impl Fn(&TrayUpdate)) {
+    loop {
+        apply(&update);
+        match latest_tray_update(state) {
+            Some(latest) if latest != update => update = latest,
+            _ => return,
+        }
     }
+}
 
-    *last_muted_lock = Some(is_muted);
-    *last_dark_lock = Some(dark_mode);
-    drop(last_muted_lock);
-    drop(last_dark_lock);
+fn apply_tray_update(app: &AppHandle, state: &AppState, update: &TrayUpdate) {
+    let Some(tray) = app.tray_by_id("main-tray") else {
+        log::debug!("[tray] no tray icon to update");
+        return;
+    };
+    let TrayUpdate {
+        mute_state,
+        dark_mode,
+        icon_key,
+    } = *update;
+    // Use the claimed dark_mode to select the icon, avoiding a second registry read that
+    // would otherwise happen inside get_tray_icon.
+    let icon = match icon_key {
+        Some(key) => tray_icons::icon(key, |state| tray_icon_image(state, dark_mode)),
+        None => tray_icon_image(mute_state, dark_mode),
+    };
+    if let Err(e) = tray.set_icon(Some(icon)) {
+        report_error(
+            app,
+            ErrorCategory::Tray,
+            format!("Failed to set tray icon: {}", e),
+            Some(serde_json::json!({ "state": mute_state })),
+        );
+    }
+    // Setting the icon drops the template flag
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_icon_as_template(true);
+    if let Err(e) = tray.set_tooltip(Some(tray_tooltip(state, mute_state).as_str())) {
+        report_error(
+            app,
+            ErrorCategory::Tray,
+            format!("Failed to set tray tooltip: {}", e),
+            Some(serde_json::json!({ "state": mute_state })),
+        );
+    }
+}
 
-    rebuild_tray_menu(app, is_muted);
+/// Push a mute state change everywhere it is shown: the frontend event, the tray icon and
+/// the external integrations (control API, MQTT, OBS, webhooks, hooks). `source` names
+/// what caused the change ("hotkey", "tray", "frontend", "system", ...); see `events` for
+/// how it is reported to the frontend.
+fn publish_mute_state(app: &AppHandle, mute_state: MuteState, source: &str) {
+    let muted = mute_state.is_muted();
+    let state = app.state::<AppState>();
+    undo::record(&state, mute_state, source);
+    let devices = state.device_mute_states.lock_safe().clone();
+    let devices_len = devices.len();
+    let profile_id = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.id.clone());
+    events::emit_mute_state(app, mute_state, source, profile_id.clone(), devices);
+    update_tray_icon(app, mute_state);
+    led_indicator::show(mute_state);
+    lights::show(mute_state);
+    hid_buttons::show(mute_state);
+    hot_mic::note_state(mute_state);
+    mute_notify::notify(app, mute_state);
+    stats::record(app, mute_state, source);
+    history::record(mute_state, source, profile_id.clone(), devices_len);
+    api::publish_state(muted);
+    mqtt::publish_state(muted);
+    obs::publish_state(muted);
+    peer_sync::publish_state(muted, source);
+
+    webhooks::notify(muted, profile_id, source);
+    hooks::notify(app, muted);
+    poll::wake();
 }
 
 // Helper function for tray/click toggle — uses fast path to avoid blocking COM calls under lock
-fn toggle_mute_internal(state: &AppState, app: &AppHandle) -> Result<bool, String> {
+fn toggle_mute_internal(
+    state: &AppState,
+    app: &AppHandle,
+    source: &str,
+) -> Result<ToggleResult, String> {
     // Clone the active profile under the lock, then release before any work. The actual COM
     // call runs on the worker thread to avoid freezing the app if a device is disconnected.
-    let profile = {
-        let controller_lock = state.audio_controller.lock_safe();
-        let profile_lock = state.current_profile.lock_safe();
-        match (controller_lock.as_ref(), profile_lock.as_ref()) {
-            (Some(_), Some(profile)) => profile.clone(),
-            _ => return Err("No active profile or audio controller not initialized".to_string()),
+    let profile = state.active_profile_for_mute()?;
+    Ok(toggle_profile_devices(state, app, profile, source))
+}
+
+/// Toggle `scope`. The profile and all-devices scopes go through the active profile's
+/// state, so sound, tray and `mute-state-changed` behave like any toggle; a single device
+/// only plays the sound and emits `device-mute-changed`. The receiver gets the new muted
+/// value once it is known: right away for the profile scopes, after the worker changed
+/// the device for a device.
+fn toggle_scope(
+    state: &AppState,
+    app: &AppHandle,
+    scope: ToggleScope,
+    source: &str,
+) -> Result<std::sync::mpsc::Receiver<Result<ToggleResult, String>>, String> {
+    let result = match scope {
+        ToggleScope::Profile => toggle_mute_internal(state, app, source)?,
+        ToggleScope::All => {
+            let profile = all_devices_profile(state.active_profile_for_mute()?);
+            toggle_profile_devices(state, app, profile, source)
         }
+        ToggleScope::Device(device_id) => return toggle_device(state, app, device_id, source),
     };
+    let (tx, rx) = std::sync::mpsc::channel();
+    let _ = tx.send(Ok(result));
+    Ok(rx)
+}
+
+/// `profile` covering every input device, for the `all` toggle scope. Its other settings
+/// (mute strategy, sounds) still apply.
+fn all_devices_profile(profile: HotkeyProfile) -> HotkeyProfile {
+    HotkeyProfile {
+        device_ids: vec![ALL_DEVICES_ID.to_string()],
+        device_states: Vec::new(),
+        solo: false,
+        ..profile
+    }
+}
+
+/// Payload of `device-mute-changed`, for a toggle of a single device.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceMuteChangedEvent<'a> {
+    device_id: &'a str,
+    muted: bool,
+    source: &'a str,
+}
+
+fn toggle_device(
+    state: &AppState,
+    app: &AppHandle,
+    device_id: String,
+    source: &str,
+) -> Result<std::sync::mpsc::Receiver<Result<ToggleResult, String>>, String> {
+    let (strategy, profile_id) = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| (p.mute_strategy, Some(p.id.clone())))
+        .unwrap_or_default();
+    let worker = state.audio_worker.lock_safe();
+    let worker = worker
+        .as_ref()
+        .ok_or_else(|| "Audio worker not running".to_string())?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let app = app.clone();
+    let source = source.to_string();
+    let id = device_id.clone();
+    worker.query(
+        "toggle device",
+        move |controller| {
+            let muted = !hard_mute::is_muted(controller, &id, strategy)?;
+            hard_mute::set_mute(controller, &id, muted, strategy).map(|_| muted)
+        },
+        move |result| {
+            let result = result.unwrap_or_else(|| Err(format!("Toggling {} timed out", device_id)));
+            if let Ok(muted) = result {
+                if muted {
+                    sound::play_mute_sound();
+                } else {
+                    sound::play_unmute_sound();
+                }
+                // Keep the breakdown right without touching the profile's state and tray
+                let state = app.state::<AppState>();
+                for device in state.device_mute_states.lock_safe().iter_mut() {
+                    if device.device_id == device_id {
+                        device.muted = muted;
+                    }
+                }
+                let _ = app.emit(
+                    "device-mute-changed",
+                    DeviceMuteChangedEvent {
+                        device_id: &device_id,
+                        muted,
+                        source: &source,
+                    },
+                );
+            }
+            let _ = tx.send(result.map(|muted| ToggleResult {
+                muted,
+                pending: false,
+                device_count: 1,
+                profile_id,
+                generation: None,
+            }));
+        },
+    );
+    Ok(rx)
+}
+
+/// Toggle `profile`'s devices as the active profile's: flip the cached state right away,
+/// play the sound, publish, and apply on the worker. Every toggle (tray, hotkeys,
+/// frontend) ends up here, so they share the worker's generations.
+fn toggle_profile_devices(
+    state: &AppState,
+    app: &AppHandle,
+    profile: HotkeyProfile,
+    source: &str,
+) -> ToggleResult {
+    idle::note_manual_change();
+    temporary_unmute::cancel(app);
 
     // Fast path: flip cached state immediately so the UI/tray responds without delay.
-    let new_state = !state.is_muted.load(Ordering::SeqCst);
-    state.is_muted.store(new_state, Ordering::SeqCst);
+    let new_state = state.toggle_target();
+    let previous = state.set_muted(new_state);
 
     if new_state {
         sound::play_mute_sound();
@@ -893,19 +3938,182 @@ fn toggle_mute_internal(state: &AppState, app: &AppHandle) -> Result<bool, Strin
         sound::play_unmute_sound();
     }
 
-    let _ = app.emit("mute-state-changed", new_state);
-    update_tray_icon(app, new_state);
+    publish_mute_state(app, MuteState::from(new_state), source);
+
+    let device_count = if profile_uses_all_devices(&profile) {
+        state
+            .cached_devices()
+            .iter()
+            .filter(|d| hidden_devices::expands_to(&profile, &d.id))
+            .count()
+    } else {
+        profile.listed_device_ids().len()
+    };
+    let profile_id = Some(profile.id.clone());
+
+    // Apply system mute on the worker; it rolls back if no device could be changed
+    let generation =
+        apply_mute(app, state, profile, new_state, previous).map(|pending| pending.generation);
+
+    ToggleResult {
+        muted: new_state,
+        pending: generation.is_some(),
+        device_count,
+        profile_id,
+        generation,
+    }
+}
+
+// Helper function for explicit mute/unmute from commands and external triggers. Returns the
+// queued request, whose receiver gets the worker's per-device result; fire-and-forget
+// callers can drop it.
+fn set_mute_internal(
+    state: &AppState,
+    app: &AppHandle,
+    muted: bool,
+    silent: bool,
+    source: &str,
+) -> Result<audio_worker::PendingApply, String> {
+    // Clone the active profile under the lock, then release before doing any COM work.
+    let profile = state.active_profile_for_mute()?;
+    let automatic = [
+        idle::SOURCE_IDLE,
+        idle::SOURCE_RETURN,
+        hot_mic::SOURCE_MAX_UNMUTED,
+        capture_switch::SOURCE_CAPTURE,
+    ];
+    if !automatic.contains(&source) {
+        idle::note_manual_change();
+    }
+    if source != temporary_unmute::SOURCE_END {
+        temporary_unmute::cancel(app);
+    }
+
+    let previous = state.set_muted(muted);
+
+    // Play sound feedback only if not silent
+    if !silent {
+        if muted {
+            sound::play_mute_sound();
+        } else {
+            sound::play_unmute_sound();
+        }
+    }
+
+    // Emit event to frontend and update tray icon
+    publish_mute_state(app, MuteState::from(muted), source);
+
+    // Apply the change on the worker thread so a disconnected device can't freeze the UI
+    apply_mute(app, state, profile, muted, previous)
+        .ok_or_else(|| "Audio worker not running".to_string())
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        // Hidden by minimize_to_tray, the window is still minimized
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalCommandEvent<'a> {
+    /// What delivered the command: "cli", "deepLink", "api", "mqtt", "ipc", "streamDeck" or "obs".
+    source: &'a str,
+    action: &'a ExternalAction,
+}
+
+/// Run an action requested from outside the app. Routed to the same internal paths as the
+/// tray menu so tray and frontend stay in sync; an `external-command` event is emitted
+/// first so the UI can show what triggered the following state change.
+fn perform_external_action(
+    app: &AppHandle,
+    source: &str,
+    action: &ExternalAction,
+) -> Result<(), String> {
+    let _ = app.emit("external-command", ExternalCommandEvent { source, action });
 
-    // Apply system mute on the worker; it notifies + reconciles if a device is unavailable
-    apply_mute(profile, new_state);
+    let state = app.state::<AppState>();
+    match action {
+        ExternalAction::Toggle => toggle_mute_internal(&state, app, source).map(|_| ()),
+        ExternalAction::SetMute { muted, sound } => {
+            set_mute_internal(&state, app, *muted, !*sound, source).map(|_| ())
+        }
+        ExternalAction::Profile { id } => switch_profile(app, id).map(|_| ()),
+        ExternalAction::Show => {
+            show_main_window(app);
+            Ok(())
+        }
+    }
+}
 
-    Ok(new_state)
+/// Run actions parsed from command line flags, in order.
+fn run_cli_actions(app: &AppHandle, actions: Vec<ExternalAction>) {
+    for action in actions {
+        if let Err(e) = perform_external_action(app, "cli", &action) {
+            app.state::<AppState>()
+                .record_error("cli", format!("{:?} failed: {}", action, e));
+        }
+    }
+}
+
+/// A second launch while TogMic runs. Its actions run here; without any, the launch was
+/// meant to open TogMic (Start Menu, desktop shortcut) and raises the window. An autostart
+/// launch (`--minimized`) or a `togmic://` URL, which the deep-link plugin handles, leaves
+/// the window alone.
+fn on_second_instance(app: &AppHandle, args: &[String]) {
+    let actions = cli::parse_args(args);
+    if !actions.is_empty() {
+        run_cli_actions(app, actions);
+        return;
+    }
+    if args
+        .iter()
+        .any(|arg| arg == "--minimized" || arg.starts_with(external::URL_SCHEME))
+    {
+        log::info!("[cli] second launch without actions, window left as is");
+        return;
+    }
+
+    log::info!("[cli] second launch, raising the window");
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let on_screen = window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false);
+    let focused = window.is_focused().unwrap_or(false);
+    show_main_window(app);
+    // Windows may refuse to focus a background process's window; flash it instead
+    if on_screen && !focused {
+        let _ = window.request_user_attention(Some(tauri::UserAttentionType::Informational));
+    }
+}
+
+/// Route `togmic://` URLs to their actions. Malformed URLs are rejected before anything
+/// runs, so a bad link never has side effects.
+fn handle_deep_links(app: &AppHandle, urls: &[String]) {
+    for url in urls {
+        match external::parse_url(url) {
+            Ok(action) => {
+                if let Err(e) = perform_external_action(app, "deepLink", &action) {
+                    app.state::<AppState>()
+                        .record_error("deep_link", format!("{} failed: {}", url, e));
+                }
+            }
+            Err(e) => {
+                app.state::<AppState>()
+                    .record_error("deep_link", format!("Rejected {}: {}", url, e));
+            }
+        }
+    }
 }
 
 fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let toggle_item = MenuItemBuilder::with_id("toggle", "Toggle Mute").build(app)?;
-    let show_item = MenuItemBuilder::with_id("show", "Show Window").build(app)?;
-    let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
+    let show_item =
+        MenuItemBuilder::with_id("show", i18n::translate("trayShowWindow")).build(app)?;
+    let quit_item = MenuItemBuilder::with_id("quit", i18n::translate("trayQuit")).build(app)?;
 
     let menu = MenuBuilder::new(app)
         .item(&toggle_item)
@@ -916,26 +4124,51 @@ fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
 
     let initial_icon = get_tray_icon(false);
 
+    let left_click_toggles = app
+        .state::<AppState>()
+        .tray_left_click_toggles
+        .load(Ordering::SeqCst);
     let _tray = TrayIconBuilder::with_id("main-tray")
         .icon(initial_icon)
+        .icon_as_template(cfg!(target_os = "macos"))
         .menu(&menu)
-        .show_menu_on_left_click(false)
-        .tooltip("TogMic - Unmuted")
+        .show_menu_on_left_click(!left_click_toggles)
+        .tooltip(i18n::translate("trayUnmutedTooltip"))
         .on_menu_event(|app, event| match event.id().as_ref() {
             "toggle" => {
                 let state = app.state::<AppState>();
-                let _ = toggle_mute_internal(&state, app);
+                if let Err(e) = toggle_mute_internal(&state, app, "tray") {
+                    state.record_error("tray", e);
+                }
+                update_tray_toggle_item(app, true);
             }
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
+            "hotkeys" => {
+                let state = app.state::<AppState>();
+                let result = if suspend::is_suspended(&state) {
+                    suspend::resume(app)
+                } else {
+                    suspend::suspend(app, load_app_settings(app).hotkey_suspend_minutes)
+                };
+                if let Err(e) = result {
+                    state.record_error("tray_hotkeys", e);
+                }
+            }
+            "show" => show_main_window(app),
+            "config" => {
+                if let Err(e) = open_config_dir(app) {
+                    app.state::<AppState>().record_error("tray_config", e);
                 }
             }
             "quit" => {
+                exit_restore::restore(app);
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(route) = id.strip_prefix(NAVIGATE_MENU_PREFIX) {
+                    show_main_window(app);
+                    let _ = app.emit("navigate", route);
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
@@ -946,65 +4179,631 @@ fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             {
                 let app = tray.app_handle();
                 let state = app.state::<AppState>();
-                let _ = toggle_mute_internal(&state, app);
+                // Otherwise the click opens the menu
+                if !state.tray_left_click_toggles.load(Ordering::SeqCst) {
+                    return;
+                }
+                if let Err(e) = toggle_mute_internal(&state, app, "tray") {
+                    state.record_error("tray", e);
+                }
             }
         })
         .build(app)?;
 
-    // Ensure tray icon reflects the current mute state at startup
-    let state = app.state::<AppState>();
-    let current = state.is_muted.load(Ordering::SeqCst);
-    update_tray_icon(app, current);
+    // Ensure tray icon reflects the current mute state at startup
+    let state = app.state::<AppState>();
+    update_tray_icon(app, state.mute_state.get());
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_window_theme(app: AppHandle, theme: String) -> Result<(), String> {
+    use tauri::Theme;
+    let state = app.state::<AppState>();
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| state.record_error("set_window_theme", "no main window"))?;
+    let t = match theme.as_str() {
+        "light" => Some(Theme::Light),
+        "dark" => Some(Theme::Dark),
+        _ => None,
+    };
+    window
+        .set_theme(t)
+        .map_err(|e| state.record_error("set_window_theme", e.to_string()))
+}
+
+#[tauri::command]
+async fn check_for_updates(app: AppHandle) -> Result<updates::UpdateInfo, String> {
+    updates::check(&app)
+        .await
+        .map_err(|e| app.state::<AppState>().record_error("check_for_updates", e))
+}
+
+/// The update found by the last (background) check, without querying the endpoint.
+#[tauri::command]
+fn get_available_update() -> updates::UpdateInfo {
+    updates::available()
+}
+
+#[tauri::command]
+async fn install_update(app: AppHandle) -> Result<(), String> {
+    updates::install(&app)
+        .await
+        .map_err(|e| app.state::<AppState>().record_error("install_update", e))
+}
+
+#[tauri::command]
+async fn show_update_notification(
+    app: AppHandle,
+    title: String,
+    body: String,
+) -> Result<(), String> {
+    if focus_assist::suppressed() {
+        return Ok(());
+    }
+    app.notification()
+        .builder()
+        .title(&title)
+        .body(&body)
+        .show()
+        .map_err(|e| {
+            app.state::<AppState>()
+                .record_error("show_update_notification", e.to_string())
+        })?;
+    Ok(())
+}
+
+/// Start the local control API on `port`, or stop it when `port` is `None`. Returns the
+/// access token clients must present (generated and persisted on first enable).
+#[tauri::command]
+fn set_api_server(port: Option<u16>, app: AppHandle) -> Result<Option<String>, String> {
+    let Some(port) = port else {
+        api::stop();
+        return Ok(None);
+    };
+
+    let token =
+        api_token(&app).map_err(|e| app.state::<AppState>().record_error("set_api_server", e))?;
+    api::start(app.clone(), port, token.clone())
+        .map_err(|e| app.state::<AppState>().record_error("set_api_server", e))?;
+    Ok(Some(token))
+}
+
+/// Return the persisted API token, generating and saving one if none exists yet.
+fn api_token(app: &AppHandle) -> Result<String, String> {
+    let store = app
+        .store(config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    if let Some(token) = store
+        .get("apiToken")
+        .and_then(|v| v.as_str().map(str::to_string))
+    {
+        return Ok(token);
+    }
+
+    let token = api::generate_token();
+    store.set("apiToken", serde_json::json!(token));
+    config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save API token: {}", e))?;
+    Ok(token)
+}
+
+/// Connect to the given MQTT broker, or disconnect when `settings` is `None`.
+#[tauri::command]
+fn set_mqtt_settings(settings: Option<mqtt::MqttSettings>, app: AppHandle) -> Result<(), String> {
+    match settings {
+        Some(settings) => {
+            let muted = app.state::<AppState>().mute_state.is_muted();
+            mqtt::start(app.clone(), settings, muted)
+                .map_err(|e| app.state::<AppState>().record_error("set_mqtt_settings", e))
+        }
+        None => {
+            mqtt::stop();
+            Ok(())
+        }
+    }
+}
+
+/// Connect to OBS with the given settings, or disconnect when `settings` is `None`.
+#[tauri::command]
+fn set_obs_settings(settings: Option<obs::ObsSettings>, app: AppHandle) -> Result<(), String> {
+    match settings {
+        Some(settings) => {
+            let muted = app.state::<AppState>().mute_state.is_muted();
+            obs::start(app.clone(), settings, muted)
+                .map_err(|e| app.state::<AppState>().record_error("set_obs_settings", e))
+        }
+        None => {
+            obs::stop();
+            Ok(())
+        }
+    }
+}
+
+fn set_peer_settings(
+    settings: Option<peer_sync::PeerSettings>,
+    app: AppHandle,
+) -> Result<(), String> {
+    match settings {
+        Some(settings) => peer_sync::start(app.clone(), settings)
+            .map_err(|e| app.state::<AppState>().record_error("set_peer_settings", e)),
+        None => {
+            peer_sync::stop();
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+fn set_webhooks(webhooks: Vec<webhooks::Webhook>) -> Result<(), String> {
+    webhooks::configure(webhooks);
+    Ok(())
+}
+
+/// Fire the configured webhook at `index` once with the current state and report whether
+/// the endpoint accepted it.
+#[tauri::command]
+async fn test_webhook(index: usize, app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let muted = state.mute_state.is_muted();
+    let profile_id = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.id.clone());
+
+    tauri::async_runtime::spawn_blocking(move || webhooks::test(index, muted, profile_id))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result)
+        .map_err(|e| app.state::<AppState>().record_error("test_webhook", e))
+}
+
+/// The applications capturing from any input device right now. Needs `trackMicUsage`.
+#[tauri::command]
+fn get_mic_consumers(state: State<AppState>) -> Result<Vec<mic_usage::MicConsumer>, String> {
+    if !mic_usage::is_enabled() {
+        return Err(state.record_error(
+            "get_mic_consumers",
+            "Microphone usage tracking is turned off",
+        ));
+    }
+    let controller_lock = state.audio_controller.lock_safe();
+    match controller_lock.as_ref() {
+        Some(controller) => {
+            mic_usage::query(controller).map_err(|e| state.record_error("get_mic_consumers", e))
+        }
+        None => Ok(mic_usage::current()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackendInfo {
+    version: String,
+    os: &'static str,
+    focus_assist: focus_assist::FocusAssistStatus,
+    mic_permission: audio::MicPermission,
+    /// Global shortcuts through the desktop portal (Wayland).
+    shortcut_portal: portal_shortcuts::PortalStatus,
+}
+
+/// Facts about the running backend for the settings UI.
+#[tauri::command]
+fn get_backend_info(app: AppHandle) -> BackendInfo {
+    BackendInfo {
+        version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        focus_assist: focus_assist::status(),
+        mic_permission: audio::check_mic_permission(),
+        shortcut_portal: portal_shortcuts::status(),
+    }
+}
+
+/// Ask for microphone access (the macOS prompt) and wait for the answer. Returns
+/// `notRequired` right away where the platform doesn't gate the microphone.
+#[tauri::command]
+async fn request_mic_permission() -> Result<audio::MicPermission, String> {
+    tauri::async_runtime::spawn_blocking(audio::request_mic_permission)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Open the microphone page of the macOS privacy settings, where a denied permission
+/// can be granted.
+#[tauri::command]
+fn open_mic_privacy_settings(app: AppHandle) -> Result<(), String> {
+    if !cfg!(target_os = "macos") {
+        return Err(app.state::<AppState>().record_error(
+            "open_mic_privacy_settings",
+            "NotSupported: only macOS gates microphone access",
+        ));
+    }
+    app.opener()
+        .open_url(
+            "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone",
+            None::<&str>,
+        )
+        .map_err(|e| {
+            app.state::<AppState>().record_error(
+                "open_mic_privacy_settings",
+                format!("Failed to open the privacy settings: {}", e),
+            )
+        })
+}
+
+/// Simulate a change on the mock devices and pick it up like a system notification.
+#[cfg(feature = "mock-audio")]
+#[tauri::command]
+fn mock_audio(
+    action: audio::mock::MockAction,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<Vec<audio::mock::MockDevice>, String> {
+    let devices = audio::mock::apply(action).map_err(|e| state.record_error("mock_audio", e))?;
+    on_system_devices_changed(&app);
+    on_system_mute_changed(&app);
+    Ok(devices)
+}
+
+/// Pretend `device` was plugged in (debug builds only).
+#[cfg(debug_assertions)]
+#[tauri::command]
+fn simulate_device_added(device: AudioDevice, app: AppHandle) {
+    simulate::device_added(&app, device);
+}
+
+/// Pretend the device was unplugged (debug builds only).
+#[cfg(debug_assertions)]
+#[tauri::command]
+fn simulate_device_removed(device_id: String, app: AppHandle) {
+    simulate::device_removed(&app, &device_id);
+}
+
+/// Change a device's mute state as another application would (debug builds only).
+#[cfg(debug_assertions)]
+#[tauri::command]
+fn simulate_external_mute(
+    device_id: String,
+    muted: bool,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    simulate::external_mute(&app, &device_id, muted)
+        .map_err(|e| state.record_error("simulate_external_mute", e))
+}
+
+/// Devices with a HID mute button connected right now, for `hidDevice`.
+#[tauri::command]
+async fn list_hid_buttons(app: AppHandle) -> Result<Vec<hid_buttons::HidButton>, String> {
+    tauri::async_runtime::spawn_blocking(hid_buttons::list_hid_buttons)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| app.state::<AppState>().record_error("list_hid_buttons", e))
+}
+
+/// Busylights connected right now that `busylight` can drive.
+#[tauri::command]
+async fn list_hid_lights(app: AppHandle) -> Result<Vec<lights::HidLight>, String> {
+    tauri::async_runtime::spawn_blocking(lights::list_hid_lights)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| app.state::<AppState>().record_error("list_hid_lights", e))
+}
+
+/// Emit `voice-activity` when the user starts or stops speaking into the active profile's
+/// primary device, until `stop_voice_monitor`.
+#[tauri::command]
+fn start_voice_monitor(app: AppHandle) {
+    voice_activity::start(app);
+}
+
+#[tauri::command]
+fn stop_voice_monitor() {
+    voice_activity::stop();
+}
+
+/// Sample `seconds` of background noise, then `seconds` of speech, and store the suggested
+/// threshold as `speakingThreshold`.
+#[tauri::command]
+async fn calibrate_voice_threshold(
+    seconds: u32,
+    app: AppHandle,
+) -> Result<speaking::VoiceCalibration, String> {
+    let calibrate_app = app.clone();
+    let calibration =
+        tauri::async_runtime::spawn_blocking(move || speaking::calibrate(&calibrate_app, seconds))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result)
+            .map_err(|e| {
+                app.state::<AppState>()
+                    .record_error("calibrate_voice_threshold", e)
+            })?;
+
+    let _guard = APPLY_SETTINGS_LOCK.lock_safe();
+    let mut settings = load_app_settings(&app);
+    settings.speaking_threshold = calibration.threshold;
+    save_app_settings(&app, &settings).map_err(|e| {
+        app.state::<AppState>()
+            .record_error("calibrate_voice_threshold", e)
+    })?;
+    apply_settings_to_state(&app, &settings);
+    let _ = app.emit("app-settings-changed", &settings);
+    Ok(calibration)
+}
+
+#[tauri::command]
+fn set_mixed_toggle_target(target: MixedToggleTarget, app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state
+        .mixed_toggle_mutes
+        .store(target == MixedToggleTarget::Mute, Ordering::SeqCst);
+    update_tray_toggle_item(&app, false);
+    Ok(())
+}
+
+/// Recreate the audio controller now, e.g. after the audio service was restarted.
+#[tauri::command]
+fn reinitialize_audio(app: AppHandle) -> Result<(), String> {
+    audio_init::reinitialize(&app).map_err(|e| {
+        app.state::<AppState>()
+            .record_error("reinitialize_audio", e)
+    })
+}
+
+#[tauri::command]
+fn get_audio_init_status(state: State<AppState>) -> audio_init::AudioInitStatus {
+    state.audio_init_status.lock_safe().clone()
+}
+
+#[tauri::command]
+fn set_poll_interval(interval_ms: u64) -> Result<(), String> {
+    poll::set_base_interval(interval_ms);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_hooks(hooks: Option<hooks::HookSettings>) -> Result<(), String> {
+    hooks::configure(hooks);
+    Ok(())
+}
+
+/// Run the configured mute or unmute hook once and wait for it to finish.
+#[tauri::command]
+async fn test_hook(kind: hooks::HookKind, app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || hooks::test(kind))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result)
+        .map_err(|e| app.state::<AppState>().record_error("test_hook", e))
+}
+
+#[tauri::command]
+fn generate_diagnostics(anonymize: Option<bool>, app: AppHandle) -> Result<String, String> {
+    diagnostics::generate(&app, anonymize.unwrap_or(false))
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|e| {
+            app.state::<AppState>()
+                .record_error("generate_diagnostics", e)
+        })
+}
+
+#[tauri::command]
+fn get_config_path(app: AppHandle) -> ConfigPaths {
+    config_paths(&app)
+}
+
+/// The last known state of every profile: the active one's, and those toggled by their own
+/// hotkey with `multi_profile_hotkeys`.
+#[tauri::command]
+fn get_profile_mute_states(state: State<AppState>) -> HashMap<String, MuteState> {
+    let mut states = state.profile_mute_states.lock_safe().clone();
+    if let Some(profile) = state.current_profile.lock_safe().as_ref() {
+        states.insert(profile.id.clone(), state.mute_state.get());
+    }
+    states
+}
+
+/// JSON Schema of the config store; see `config_schema`.
+#[tauri::command]
+fn get_config_schema() -> serde_json::Value {
+    config_schema::schema()
+}
+
+#[tauri::command]
+fn get_config_load_problem() -> Option<config_recovery::ConfigLoadProblem> {
+    config_recovery::problem()
+}
+
+/// Write the config store to disk, unless `config_recovery` refuses to overwrite a
+/// config that failed to parse.
+fn save_config_store(app: &AppHandle, force: bool) -> Result<(), String> {
+    config_recovery::guard_save(force)?;
+    app.store(config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?
+        .save()
+        .map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// Save the config store after the frontend changed it. Refused while a config that
+/// failed to parse is still in place, unless `force`; see `config_recovery`.
+#[tauri::command]
+fn save_config(app: AppHandle, force: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    save_config_store(&app, force).map_err(|e| state.record_error("save_config", e))?;
+    // Profiles are edited through the store, so their own hotkeys follow each save
+    if state.multi_profile_hotkeys.load(Ordering::SeqCst) {
+        register_other_profile_hotkeys(&app, &state);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn open_config_folder(app: AppHandle) -> Result<(), String> {
+    open_config_dir(&app).map_err(|e| {
+        app.state::<AppState>()
+            .record_error("open_config_folder", e)
+    })
+}
+
+#[tauri::command]
+fn open_diagnostics_folder(app: AppHandle) -> Result<(), String> {
+    diagnostics::open_folder(&app).map_err(|e| {
+        app.state::<AppState>()
+            .record_error("open_diagnostics_folder", e)
+    })
+}
+
+#[tauri::command]
+fn get_stats(range: stats::StatsRange) -> stats::StatsSummary {
+    stats::get(range)
+}
+
+#[tauri::command]
+fn reset_stats(state: State<AppState>) -> Result<(), String> {
+    stats::reset().map_err(|e| state.record_error("reset_stats", e))
+}
+
+/// Mute state transitions from `from` to `to` (milliseconds since the epoch), newest
+/// first, at most `limit` (default 1000).
+#[tauri::command]
+fn get_mute_history(
+    from: Option<u64>,
+    to: Option<u64>,
+    limit: Option<usize>,
+) -> Vec<history::HistoryEntry> {
+    history::query(from, to, limit.unwrap_or(1000))
+}
+
+#[tauri::command]
+fn clear_history(state: State<AppState>) -> Result<(), String> {
+    history::clear().map_err(|e| state.record_error("clear_history", e))
+}
+
+/// Recorded events, newest first: at most `limit` (default: all kept), only of `kinds` if
+/// given.
+#[tauri::command]
+fn get_recent_events(
+    limit: Option<usize>,
+    kinds: Option<Vec<events::EventKind>>,
+    state: State<AppState>,
+) -> Vec<events::RecordedEvent> {
+    events::recent(
+        &state,
+        limit.unwrap_or(events::MAX_RECENT_EVENTS),
+        &kinds.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+fn get_log_path(app: AppHandle) -> Result<String, String> {
+    logging::log_path()
+        .map(|path| path.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            app.state::<AppState>()
+                .record_error("get_log_path", "File logging is not running")
+        })
+}
+
+#[tauri::command]
+fn open_logs_folder(app: AppHandle) -> Result<(), String> {
+    logging::open_folder(&app)
+        .map_err(|e| app.state::<AppState>().record_error("open_logs_folder", e))
+}
 
-    Ok(())
+#[tauri::command]
+fn get_sync_thread_status() -> sync_watchdog::SyncThreadStatus {
+    sync_watchdog::status()
 }
 
+/// The crash report of the last run, if it panicked; see `crash`.
 #[tauri::command]
-fn set_window_theme(app: AppHandle, theme: String) -> Result<(), String> {
-    use tauri::Theme;
-    let window = app.get_webview_window("main").ok_or("no main window")?;
-    let t = match theme.as_str() {
-        "light" => Some(Theme::Light),
-        "dark" => Some(Theme::Dark),
-        _ => None,
-    };
-    window.set_theme(t).map_err(|e| e.to_string())
+fn get_previous_crash() -> Option<String> {
+    crash::previous().map(|path| path.to_string_lossy().into_owned())
 }
 
 #[tauri::command]
-async fn show_update_notification(
-    app: AppHandle,
-    title: String,
-    body: String,
-) -> Result<(), String> {
-    app.notification()
-        .builder()
-        .title(&title)
-        .body(&body)
-        .show()
-        .map_err(|e| e.to_string())?;
-    Ok(())
+fn open_crash_report(app: AppHandle) -> Result<(), String> {
+    crash::open_previous(&app)
+        .map_err(|e| app.state::<AppState>().record_error("open_crash_report", e))
+}
+
+#[tauri::command]
+fn set_log_level(level: String, state: State<AppState>) -> Result<(), String> {
+    logging::set_level(&level).map_err(|e| state.record_error("set_log_level", e))
+}
+
+/// The last `limit` (default all kept) log lines at `min_level` (default `trace`) or more
+/// severe, oldest first.
+#[tauri::command]
+fn get_recent_logs(
+    limit: Option<usize>,
+    min_level: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<String>, String> {
+    let min_level = match min_level {
+        Some(level) => log::Level::from_str(level.trim()).map_err(|_| {
+            state.record_error("get_recent_logs", format!("Unknown log level '{}'", level))
+        })?,
+        None => log::Level::Trace,
+    };
+    Ok(logging::recent(limit.unwrap_or(usize::MAX), min_level))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize audio controller
-    let audio_controller = match PlatformAudioController::new() {
-        Ok(controller) => Some(controller),
+    // Capture everything from here on; the file writer starts in setup()
+    logging::init();
+    crash::install_hook();
+
+    // Initialize audio controller; a failure is retried in the background from setup()
+    let (audio_controller, audio_init_error) = match audio_init::create_controller() {
+        Ok(controller) => (Some(controller), None),
+        Err(e) => {
+            log::warn!(
+                "[audio init] failed to initialize audio controller, retrying: {}",
+                e
+            );
+            (None, Some(e))
+        }
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    exit_restore::init(&args);
+    let config_location = match config_location::ConfigLocation::resolve(&args) {
+        Ok(location) => location,
         Err(e) => {
-            eprintln!("Warning: Failed to initialize audio controller: {}", e);
-            None
+            config_location::report_fatal(&e);
+            std::process::exit(2);
         }
     };
+    // Scope the single-instance check (which goes by the identifier) to the config
+    let mut context = tauri::generate_context!();
+    if let Some(suffix) = config_location.instance_suffix() {
+        let identifier = format!("{}.{}", context.config().identifier, suffix);
+        context.config_mut().identifier = identifier;
+    }
 
     let app_state = AppState {
         audio_controller: Arc::new(Mutex::new(audio_controller)),
+        config_location,
         ..Default::default()
     };
 
-    tauri::Builder::default()
+    tauri::Builder::<AppRuntime>::new()
         .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_single_instance::init(|_app, _argv, _cwd| {}))
+        // Must be registered before the deep-link plugin so forwarded URLs reach us
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            // argv[0] is the executable path of the secondary launch
+            let args: Vec<String> = argv.into_iter().skip(1).collect();
+            on_second_instance(app, &args);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::init(
@@ -1016,23 +4815,129 @@ pub fn run() {
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             get_audio_devices,
+            set_device_alias,
+            get_device_aliases,
+            prune_aliases,
+            hide_device,
+            set_default_input_device,
+            unhide_device,
+            get_hidden_devices,
             toggle_mute,
             set_mute,
             get_mute_state,
+            undo_last_change,
+            get_device_mute_states,
             save_profile,
             set_active_profile,
+            set_active_profile_by_id,
             get_active_profile,
+            list_profiles,
+            reorder_profiles,
             register_hotkey,
             unregister_hotkey,
             set_autostart,
             get_autostart_status,
             set_close_to_tray,
             update_tray_labels,
+            set_locale,
+            list_locales,
             show_update_notification,
             set_window_theme,
+            generate_diagnostics,
+            open_diagnostics_folder,
+            get_config_path,
+            get_config_load_problem,
+            get_config_schema,
+            get_profile_mute_states,
+            save_config,
+            open_config_folder,
+            get_log_path,
+            open_logs_folder,
+            get_previous_crash,
+            get_sync_thread_status,
+            open_crash_report,
+            set_log_level,
+            get_recent_logs,
+            get_stats,
+            get_recent_events,
+            suspend_hotkeys,
+            resume_hotkeys,
+            get_hotkeys_suspended,
+            capture_next_hotkey,
+            list_registered_shortcuts,
+            get_hotkey_display_name,
+            check_hotkey_available,
+            temporary_unmute,
+            get_temporary_unmute_remaining,
+            calibrate_voice_threshold,
+            start_voice_monitor,
+            stop_voice_monitor,
+            get_mic_consumers,
+            list_hid_lights,
+            list_hid_buttons,
+            get_backend_info,
+            request_mic_permission,
+            open_mic_privacy_settings,
+            #[cfg(feature = "mock-audio")]
+            mock_audio,
+            #[cfg(debug_assertions)]
+            simulate_device_added,
+            #[cfg(debug_assertions)]
+            simulate_device_removed,
+            #[cfg(debug_assertions)]
+            simulate_external_mute,
+            check_for_updates,
+            get_available_update,
+            install_update,
+            reset_stats,
+            get_mute_history,
+            clear_history,
+            set_api_server,
+            set_mqtt_settings,
+            set_obs_settings,
+            set_webhooks,
+            test_webhook,
+            set_hooks,
+            test_hook,
+            set_poll_interval,
+            set_mixed_toggle_target,
+            reinitialize_audio,
+            get_audio_init_status,
+            apply_app_settings,
         ])
         .on_window_event(|window, event| {
+            if window.label() == "main"
+                && matches!(
+                    event,
+                    tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_)
+                )
+            {
+                window_state::schedule_save();
+            }
+
+            // Minimizing reports a resize; with minimize_to_tray the window goes to the
+            // tray instead of the taskbar
+            if window.label() == "main" && matches!(event, tauri::WindowEvent::Resized(_)) {
+                let app = window.app_handle();
+                if app
+                    .state::<AppState>()
+                    .minimize_to_tray
+                    .load(Ordering::SeqCst)
+                    && window.is_minimized().unwrap_or(false)
+                    && window.is_visible().unwrap_or(false)
+                {
+                    let _ = window.hide();
+                    #[cfg(target_os = "windows")]
+                    trim_process_memory();
+                }
+            }
+
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Save before hiding: a hidden window's geometry isn't saved
+                if window.label() == "main" {
+                    window_state::save(window);
+                }
+
                 let app = window.app_handle();
                 let state = app.state::<AppState>();
                 let close_to_tray = state.close_to_tray.lock_safe();
@@ -1045,18 +4950,54 @@ pub fn run() {
             }
         })
         .setup(|app| {
+            if let Err(e) = logging::start(app.handle()) {
+                // No logger to write to, so stderr as well
+                eprintln!("[logging] {}", e);
+                report_error(app.handle(), ErrorCategory::Logging, e, None);
+            }
+            app.state::<AppState>()
+                .config_location
+                .log_override(&app.config().identifier);
+            crash::init(app.handle());
+            // Before anything opens the store, which would treat a broken config as empty
+            if let Some(file) = config_file_path(app.handle()) {
+                config_recovery::check(app.handle(), &file);
+            }
+            // Tray strings in the last selected locale, before the frontend pushes one
+            i18n::init(app.handle());
+            // Before the audio worker can unmute anything
+            hard_mute::init(app.handle());
+            device_aliases::init(app.handle());
+            missing_devices::init(app.handle());
+            hidden_devices::init(app.handle());
             setup_tray(app.handle())?;
 
             // Initialize persistent audio playback thread
-            sound::init();
+            sound::init(app.handle().clone());
 
             // Start the persistent audio worker that applies mute changes off the UI thread,
             // keeping COM + the endpoint cache warm across toggles.
             start_audio_worker(app.handle().clone());
 
+            if let Some(e) = audio_init_error {
+                audio_init::start_retry(app.handle().clone(), e);
+            }
+
+            // Before start_muted changes anything
+            exit_restore::snapshot_initial_states(&app.state::<AppState>());
+            // Baseline for spotting profile devices that disappear
+            device_watch::refresh(app.handle());
+
+            if let Err(e) = migrate_config(app.handle()) {
+                log::warn!("[config] {}", e);
+            }
+            if let Some(file) = config_file_path(app.handle()) {
+                config_schema::write_if_stale(&file);
+            }
+
             // Load saved config from store and set active profile on startup so tray matches
             {
-                let store = app.store("config.json")?;
+                let store = app.store(config_store(app))?;
                 let profiles: Vec<HotkeyProfile> = store
                     .get("profiles")
                     .and_then(|v| serde_json::from_value(v).ok())
@@ -1073,62 +5014,40 @@ pub fn run() {
                     if let Some(profile) = profiles.iter().find(|p| p.id == active_id).cloned() {
                         // Set active profile and sync state/tray
                         let state = app.state::<AppState>();
-                        let mut profile_lock = state.current_profile.lock_safe();
-                        *profile_lock = Some(profile.clone());
-                        let controller_lock = state.audio_controller.lock_safe();
-                        if let Some(controller) = controller_lock.as_ref() {
-                            // Apply start_muted immediately before the frontend loads
-                            if app_settings.start_muted {
-                                if let Ok(device_ids) = resolve_device_ids(controller, &profile) {
-                                    for device_id in &device_ids {
-                                        let _ = controller.set_mute_state(device_id, true);
-                                    }
-                                }
-                                state.is_muted.store(true, Ordering::SeqCst);
-                                let _ = app.handle().emit("mute-state-changed", true);
-                                update_tray_icon(&app.handle(), true);
-                            } else {
-                                let cached = state.is_muted.load(Ordering::SeqCst);
-                                if let Ok(system_muted) =
-                                    get_profile_mute_state(controller, &profile, cached)
-                                {
-                                    state.is_muted.store(system_muted, Ordering::SeqCst);
-                                    let _ = app.handle().emit("mute-state-changed", system_muted);
-                                    update_tray_icon(&app.handle(), system_muted);
-                                }
-                            }
-                        }
-                        drop(controller_lock);
-                        drop(profile_lock);
+                        set_current_profile(app.handle(), &state, Some(profile.clone()));
+
+                        // Apply start_muted immediately before the frontend loads
+                        sync_profile_state(app.handle(), app_settings.start_muted, "startup");
 
                         // Register the hotkey immediately at startup — don't wait for the
                         // frontend to load (which adds a multi-second delay).
                         if let Err(e) = do_register_hotkey(
                             &profile.toggle_key,
                             profile.ignore_modifiers,
-                            &app.handle(),
+                            app.handle(),
                             &state,
                         ) {
-                            eprintln!("[startup] Failed to register hotkey: {}", e);
+                            log::warn!("[startup] Failed to register hotkey: {}", e);
                         }
                     }
                 }
 
                 // Sync OS autostart entry with config (e.g. if registry entry was missing
                 // despite config saying autostart=true, or needs to be removed)
-                {
-                    let autostart_manager =
-                        app.state::<tauri_plugin_autostart::AutoLaunchManager>();
-                    if app_settings.autostart {
-                        let _ = autostart_manager.enable();
-                    } else {
-                        let _ = autostart_manager.disable();
-                    }
+                if let Err(e) = sync_autostart(app.handle(), app_settings.autostart) {
+                    log::warn!("[startup] {}", e);
+                }
+
+                // Put the window back where it was, while it is still hidden
+                if let Some(window) = app.get_webview_window("main") {
+                    window_state::restore(&window.as_ref().window());
                 }
+                window_state::init(app.handle().clone());
 
                 // tauri.conf.json now creates the main window hidden by default to avoid a flash.
-                // Show the window only if the user did NOT enable start_minimized.
-                if !app_settings.start_minimized {
+                // Show it unless start_minimized or --minimized keeps it hidden.
+                let args: Vec<String> = std::env::args().skip(1).collect();
+                if !cli::start_hidden(&args, app_settings.start_minimized) {
                     if let Some(window) = app.get_webview_window("main") {
                         let _ = window.show();
                         let _ = window.set_focus();
@@ -1144,10 +5063,123 @@ pub fn run() {
                 }
             }
 
+            let app_settings = load_app_settings(app.handle());
+
+            // Start the local control API if the user enabled it
+            if let Some(port) = app_settings.api_port {
+                match api_token(app.handle()) {
+                    Ok(token) => {
+                        if let Err(e) = api::start(app.handle().clone(), port, token) {
+                            log::warn!("[api] {}", e);
+                        }
+                    }
+                    Err(e) => log::warn!("[api] {}", e),
+                }
+            }
+
+            // Local scripting interface (named pipe / Unix socket)
+            if app_settings.local_ipc {
+                ipc::start(app.handle().clone());
+            }
+
+            // Connect to the MQTT broker if one is configured
+            if let Some(settings) = app_settings.mqtt.clone() {
+                let muted = app.state::<AppState>().mute_state.is_muted();
+                if let Err(e) = mqtt::start(app.handle().clone(), settings, muted) {
+                    log::warn!("[mqtt] {}", e);
+                }
+            }
+
+            // Webhook delivery worker (idle unless webhooks are configured)
+            webhooks::init(app.handle().clone(), app_settings.webhooks.clone());
+
+            // Usage statistics; recording is switched on by apply_settings_to_state
+            history::init(app.handle());
+            stats::init(app.handle().clone());
+
+            // close_to_tray, hooks, poll interval etc. as persisted, not the built-in
+            // defaults, even if the frontend never gets to push them
+            apply_settings_to_state(app.handle(), &app_settings);
+
+            // Connect to OBS if configured
+            if let Some(settings) = app_settings.obs {
+                let muted = app.state::<AppState>().mute_state.is_muted();
+                if let Err(e) = obs::start(app.handle().clone(), settings, muted) {
+                    log::warn!("[obs] {}", e);
+                }
+            }
+
+            // Sync the mute state with other machines if configured
+            if let Some(settings) = app_settings.peers {
+                if let Err(e) = peer_sync::start(app.handle().clone(), settings) {
+                    log::warn!("[peers] {}", e);
+                }
+            }
+
+            // Apply this launch's own command line flags now that the active profile has
+            // been restored (e.g. `TogMic --profile Meetings --mute`). --show was already
+            // weighed against --minimized for the window above.
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            let actions = cli::parse_args(&args)
+                .into_iter()
+                .filter(|action| !matches!(action, ExternalAction::Show))
+                .collect();
+            run_cli_actions(app.handle(), actions);
+
+            // togmic:// deep links. The scheme is declared in tauri.conf.json; registering
+            // at runtime as well keeps it working for portable and dev builds on Windows
+            // and Linux. Links opened while we're already running are forwarded here by
+            // the single-instance plugin.
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
+            if let Err(e) = app.deep_link().register_all() {
+                log::warn!("[deep-link] Failed to register URL scheme: {}", e);
+            }
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                let urls: Vec<String> = urls.iter().map(|u| u.to_string()).collect();
+                handle_deep_links(app.handle(), &urls);
+            }
+            {
+                let app_handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    let urls: Vec<String> = event.urls().iter().map(|u| u.to_string()).collect();
+                    handle_deep_links(&app_handle, &urls);
+                });
+            }
+
+            // Switch profiles when an app listed in a profile's auto_activate_apps is focused
+            foreground::start(app.handle().clone());
+
+            // Background update checks, honoring check_updates and the minimum interval
+            updates::start_background_check(app.handle().clone());
+
+            // Mute after idle_mute_minutes without input (configured by apply_settings_to_state)
+            idle::start(app.handle().clone());
+            // Mute after max_unmuted_minutes of live microphone (configured likewise)
+            hot_mic::start(app.handle().clone());
+            speaking::start(app.handle().clone());
+            mic_usage::start(app.handle().clone());
+
+            // Workstation lock/unlock for mute_on_lock. The listener runs regardless of the
+            // setting, which is checked per event, so toggling it needs no restart.
+            {
+                use system_events::SessionEventSource;
+                let app_handle = app.handle().clone();
+                let handler = Box::new(move |event| handle_session_event(&app_handle, event));
+                if let Err(e) = system_events::PlatformSessionEvents::start(handler) {
+                    if app_settings.mute_on_lock {
+                        log::warn!("[session] {}", e);
+                    }
+                }
+            }
+
             // Listen for Windows dark/light mode changes via registry key notification
             #[cfg(target_os = "windows")]
             start_theme_change_listener(app.handle().clone());
 
+            // Re-resolve scancode hotkeys when the keyboard layout changes
+            #[cfg(target_os = "windows")]
+            scancode::start_layout_watch(app.handle().clone());
+
             // On Windows: use zero-CPU COM callbacks instead of a polling loop.
             // IAudioEndpointVolumeCallback fires on external mute changes;
             // IMMNotificationClient fires on device plug/unplug.
@@ -1155,40 +5187,7 @@ pub fn run() {
             {
                 let app_handle_mute = app.handle().clone();
                 let on_mute_changed = std::sync::Arc::new(move |_raw_muted: bool| {
-                    let state = app_handle_mute.state::<AppState>();
-
-                    // Read the profile and query the actual profile mute state instead of
-                    // using the raw callback value directly. Callbacks fire for ALL capture
-                    // devices, but the profile may only care about one specific device.
-                    // Using the raw value from a non-profile device would corrupt is_muted.
-                    let profile = {
-                        let lock = state.current_profile.lock_safe();
-                        match lock.as_ref().cloned() {
-                            Some(p) => p,
-                            None => return,
-                        }
-                    };
-
-                    let cached = state.is_muted.load(Ordering::SeqCst);
-                    let system_muted = {
-                        let controller_lock = state.audio_controller.lock_safe();
-                        match controller_lock.as_ref() {
-                            Some(controller) => {
-                                match get_profile_mute_state(controller, &profile, cached) {
-                                    Ok(m) => m,
-                                    Err(_) => return,
-                                }
-                            }
-                            None => return,
-                        }
-                    }; // controller_lock released here
-
-                    let prev = state.is_muted.load(Ordering::SeqCst);
-                    if prev != system_muted {
-                        state.is_muted.store(system_muted, Ordering::SeqCst);
-                        let _ = app_handle_mute.emit("mute-state-changed", system_muted);
-                        update_tray_icon(&app_handle_mute, system_muted);
-                    }
+                    on_system_mute_changed(&app_handle_mute);
                 });
 
                 let app_handle_dev = app.handle().clone();
@@ -1196,75 +5195,827 @@ pub fn run() {
                     audio::clear_endpoint_cache();
                     // Enumerate directly on the COM STA thread using the already-cached
                     // THREAD_ENUMERATOR — avoids creating a second enumerator via audio_controller.
-                    if let Ok(ids) = audio::enumerate_capture_device_ids() {
-                        let _ = app_handle_dev.emit("devices-changed", ids);
+                    let devices = PlatformAudioController::new()
+                        .and_then(|controller| controller.enumerate_input_devices());
+                    match devices {
+                        Ok(devices) => {
+                            let state = app_handle_dev.state::<AppState>();
+                            device_watch::check(&app_handle_dev, state.cache_devices(devices));
+                        }
+                        Err(e) => {
+                            report_error(
+                                &app_handle_dev,
+                                ErrorCategory::Device,
+                                format!("Failed to enumerate devices: {}", e),
+                                None,
+                            );
+                        }
                     }
                 });
 
-                audio::start_audio_listeners(on_mute_changed, on_devices_changed);
+                let app_handle_err = app.handle().clone();
+                let on_listener_error = std::sync::Arc::new(move |e: String| {
+                    report_error(&app_handle_err, ErrorCategory::Listener, e, None);
+                });
+
+                let alive = app.state::<AppState>().sync_thread_alive.clone();
+                audio::start_audio_listeners(
+                    on_mute_changed,
+                    on_devices_changed,
+                    on_listener_error,
+                    alive,
+                );
             }
 
             // On non-Windows platforms: keep the polling loop as fallback
             #[cfg(not(target_os = "windows"))]
             {
-                let app_handle = app.handle().clone();
-                std::thread::spawn(move || {
+                // A panic restarts the thread with a new controller instead of ending
+                // syncing for good; see `sync_watchdog`
+                let alive = app.state::<AppState>().sync_thread_alive.clone();
+                sync_watchdog::spawn(app.handle().clone(), move |app_handle| {
+                    let _alive = audio::AliveGuard::new(alive.clone());
                     let _ = PlatformAudioController::init_thread();
 
-                    let poll_controller = match PlatformAudioController::new() {
-                        Ok(c) => c,
-                        Err(e) => {
-                            eprintln!("Failed to create polling audio controller: {}", e);
-                            return;
+                    // Keep trying instead of ending the thread, which would stop syncing
+                    // for good
+                    let poll_controller = loop {
+                        match PlatformAudioController::new() {
+                            Ok(c) => break c,
+                            Err(e) => {
+                                report_error(
+                                    app_handle,
+                                    ErrorCategory::Controller,
+                                    format!("Failed to create the polling audio controller: {}", e),
+                                    None,
+                                );
+                                std::thread::sleep(std::time::Duration::from_secs(5));
+                            }
                         }
                     };
 
-                    let mut prev_device_ids: Option<Vec<String>> = None;
+                    let mut schedule = poll::Schedule::start();
+                    let mut polls_since_scan = 0u32;
                     loop {
-                        std::thread::sleep(std::time::Duration::from_millis(500));
+                        schedule.wait();
 
                         let state = app_handle.state::<AppState>();
                         let profile = {
                             let profile_lock = state.current_profile.lock_safe();
                             profile_lock.as_ref().cloned()
                         };
-
-                        if let Some(profile) = profile {
-                            if let Ok(devs) = poll_controller.enumerate_input_devices() {
-                                let ids: Vec<String> = devs.iter().map(|d| d.id.clone()).collect();
-                                if prev_device_ids.as_ref() != Some(&ids) {
-                                    prev_device_ids = Some(ids.clone());
-                                    let _ = app_handle.emit("devices-changed", ids);
+                        let Some(profile) = profile else {
+                            continue;
+                        };
+                        let uses_all_devices = profile_uses_all_devices(&profile);
+
+                        // Enumerating is the expensive part. An all-devices profile needs the
+                        // list on every poll; otherwise a periodic scan is enough to notice
+                        // plugged/unplugged devices.
+                        let mut changed = false;
+                        let mut devs = None;
+                        polls_since_scan += 1;
+                        if uses_all_devices || polls_since_scan >= poll::DEVICE_SCAN_EVERY_N_POLLS {
+                            polls_since_scan = 0;
+                            match poll_controller.enumerate_input_devices() {
+                                Ok(list) => {
+                                    // Diffs against the previous enumeration and emits
+                                    // devices-changed if anything was added or removed
+                                    let list = state.cache_devices(list);
+                                    changed = device_watch::check(app_handle, list.clone());
+                                    devs = Some(list);
                                 }
-
-                                let cached = state.is_muted.load(Ordering::SeqCst);
-                                let system_muted = if profile_uses_all_devices(&profile) {
-                                    if devs.is_empty() {
-                                        cached
-                                    } else {
-                                        devs.iter().all(|d| {
-                                            poll_controller.get_mute_state(&d.id).unwrap_or(cached)
-                                        })
-                                    }
-                                } else if let Some(first) = profile.device_ids.first() {
-                                    poll_controller.get_mute_state(first).unwrap_or(cached)
-                                } else {
-                                    cached
-                                };
-
-                                if cached != system_muted {
-                                    state.is_muted.store(system_muted, Ordering::SeqCst);
-                                    let _ = app_handle.emit("mute-state-changed", system_muted);
-                                    update_tray_icon(&app_handle, system_muted);
+                                Err(e) => {
+                                    report_error(
+                                        app_handle,
+                                        ErrorCategory::Device,
+                                        format!("Failed to enumerate devices: {}", e),
+                                        None,
+                                    );
+                                    schedule.record(false);
+                                    continue;
                                 }
                             }
                         }
+
+                        // Between scans the cached list tells which devices are missing
+                        let devs = devs.unwrap_or_else(|| state.cached_devices());
+                        let resolved = partition_device_ids(&profile, &devs);
+                        let queried = query_device_mute_states(
+                            &profile,
+                            &resolved.present,
+                            |id| hard_mute::is_muted(&poll_controller, id, profile.mute_strategy),
+                            state.mute_state.get(),
+                        );
+                        if mute_lock::enforce(app_handle, &state, &queried) {
+                            schedule.record(true);
+                            continue;
+                        }
+
+                        if reconcile::observe(app_handle, &state, queried) {
+                            changed = true;
+                        }
+                        schedule.record(changed);
                     }
                 });
             }
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Also reached when Windows shuts down or the last window closes
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                exit_restore::restore(app);
+            }
+
+            if let tauri::RunEvent::Exit = event {
+                exit_restore::restore(app);
+
+                // Take the worker out first so the lock isn't held while joining its thread
+                let worker = app.state::<AppState>().audio_worker.lock_safe().take();
+                if let Some(worker) = worker {
+                    worker.shutdown();
+                }
+                api::stop();
+                mqtt::stop();
+                obs::stop();
+                peer_sync::stop();
+                stats::save();
+                history::flush();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{device, profile};
+    use serde_json::json;
+
+    fn default_device(id: &str) -> AudioDevice {
+        AudioDevice {
+            is_default: true,
+            ..device(id)
+        }
+    }
+
+    fn profile_with(ids: &[&str]) -> HotkeyProfile {
+        profile(json!({ "deviceIds": ids }))
+    }
+
+    #[test]
+    fn partitions_present_and_missing_devices() {
+        let profile = profile_with(&["partition-gone"]);
+        let resolved = partition_device_ids(&profile, &[device("partition-a")]);
+        assert!(resolved.present.is_empty());
+        assert_eq!(resolved.missing, ["partition-gone"]);
+
+        let profile = profile_with(&["partition-a"]);
+        let resolved = partition_device_ids(&profile, &[device("partition-a")]);
+        assert_eq!(resolved.present, ["partition-a"]);
+        assert!(resolved.missing.is_empty());
+    }
+
+    #[test]
+    fn default_device_aliases_need_a_default_device() {
+        let profile = profile_with(&["default-mic"]);
+        let resolved = partition_device_ids(&profile, &[default_device("partition-d")]);
+        assert_eq!(resolved.present, ["default-mic"]);
+
+        let resolved = partition_device_ids(&profile, &[device("partition-d")]);
+        assert_eq!(resolved.missing, ["default-mic"]);
+    }
+
+    #[test]
+    fn all_devices_profiles_are_never_missing_devices() {
+        let profile = profile_with(&[ALL_DEVICES_ID]);
+        let resolved =
+            partition_device_ids(&profile, &[device("partition-x"), device("partition-y")]);
+        assert_eq!(resolved.present, ["partition-x", "partition-y"]);
+        assert!(resolved.missing.is_empty());
+
+        let resolved = partition_device_ids(&profile, &[]);
+        assert!(resolved.present.is_empty());
+        assert!(resolved.missing.is_empty());
+    }
+
+    #[test]
+    fn all_scope_covers_every_device() {
+        let scene = profile(json!({
+            "deviceIds": ["a"],
+            "deviceStates": [{ "deviceId": "a", "mutedWhenActive": true }],
+            "solo": true,
+            "muteStrategy": "both",
+        }));
+        let all = all_devices_profile(scene.clone());
+        assert_eq!(all.device_ids, [ALL_DEVICES_ID]);
+        assert!(!all.is_scene());
+        assert!(!all.solo);
+        assert!(profile_uses_all_devices(&all));
+        assert_eq!(all.mute_strategy, scene.mute_strategy);
+        assert_eq!(all.id, scene.id);
+    }
+
+    fn claim(state: &AppState, mute_state: MuteState) -> Option<TrayUpdate> {
+        claim_tray_update(state, mute_state, || false, |_, _| None)
+    }
+
+    #[test]
+    fn tray_updates_are_claimed_once() {
+        let state = AppState::default();
+        assert!(claim(&state, MuteState::Muted).is_some());
+        assert!(claim(&state, MuteState::Muted).is_none());
+        assert!(claim(&state, MuteState::Mixed).is_some());
+
+        // A theme change shows up once the listener invalidated the cached theme
+        *state.last_tray_dark_mode.lock_safe() = None;
+        let update = claim_tray_update(&state, MuteState::Mixed, || true, |_, _| None);
+        assert!(update.is_some_and(|u| u.dark_mode));
+    }
+
+    #[test]
+    fn tray_calls_run_without_the_cache_locks() {
+        // Stands in for the main thread, which the tray calls wait for and which may be
+        // waiting for the cache locks itself
+        let state = Arc::new(AppState::default());
+        let (request_tx, request_rx) = std::sync::mpsc::channel::<std::sync::mpsc::Sender<()>>();
+        let main_state = state.clone();
+        std::thread::spawn(move || {
+            for reply in request_rx {
+                drop(main_state.last_tray_state.lock_safe());
+                drop(main_state.last_tray_dark_mode.lock_safe());
+                drop(main_state.last_tray_icon.lock_safe());
+                let _ = reply.send(());
+            }
+        });
+
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let updaters: Vec<_> = (0..8)
+            .map(|i| {
+                let state = state.clone();
+                let applied = applied.clone();
+                let request_tx = request_tx.clone();
+                std::thread::spawn(move || {
+                    for round in 0..25 {
+                        let mute_state = match (i + round) % 3 {
+                            0 => MuteState::Muted,
+                            1 => MuteState::Unmuted,
+                            _ => MuteState::Mixed,
+                        };
+                        let Some(update) = claim(&state, mute_state) else {
+                            continue;
+                        };
+                        sync_tray(&state, update, |update| {
+                            let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+                            request_tx.send(reply_tx).unwrap();
+                            reply_rx
+                                .recv_timeout(Duration::from_secs(5))
+                                .expect("main thread blocked on the tray cache");
+                            applied.lock_safe().push(update.mute_state);
+                        });
+                    }
+                })
+            })
+            .collect();
+        for updater in updaters {
+            updater.join().unwrap();
+        }
+
+        // Whatever was applied last is what the cache says the tray shows
+        let last = *applied.lock_safe().last().unwrap();
+        assert_eq!(*state.last_tray_state.lock_safe(), Some(last));
+    }
+
+    #[test]
+    fn tray_check_mark_is_set_without_the_locks() {
+        // Stands in for the main thread rebuilding the menu under both locks
+        let state = Arc::new(AppState::default());
+        let item = Arc::new(Mutex::new(Some(0u32)));
+        let (request_tx, request_rx) = std::sync::mpsc::channel::<std::sync::mpsc::Sender<()>>();
+        let main_state = state.clone();
+        let main_item = item.clone();
+        std::thread::spawn(move || {
+            for reply in request_rx {
+                let _item = main_item.lock_safe();
+                let _checked = main_state.last_tray_checked.lock_safe();
+                let _ = reply.send(());
+            }
+        });
+
+        let updaters: Vec<_> = (0..8)
+            .map(|i| {
+                let state = state.clone();
+                let item = item.clone();
+                let request_tx = request_tx.clone();
+                std::thread::spawn(move || {
+                    for round in 0..25 {
+                        let checked = (i + round) % 2 == 0;
+                        set_tray_checked(&item, &state.last_tray_checked, checked, false, |_| {
+                            let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+                            request_tx.send(reply_tx).unwrap();
+                            reply_rx
+                                .recv_timeout(Duration::from_secs(5))
+                                .expect("main thread blocked on the tray check mark");
+                            true
+                        });
+                    }
+                })
+            })
+            .collect();
+        for updater in updaters {
+            updater.join().unwrap();
+        }
+        assert!(state.last_tray_checked.lock_safe().is_some());
+    }
+
+    #[test]
+    fn tray_check_mark_is_only_recorded_once_set() {
+        let item = Mutex::new(Some(()));
+        let last_checked = Mutex::new(None);
+        set_tray_checked(&item, &last_checked, true, false, |_| false);
+        assert_eq!(*last_checked.lock_safe(), None);
+        set_tray_checked(&item, &last_checked, true, false, |_| true);
+        assert_eq!(*last_checked.lock_safe(), Some(true));
+        // Unchanged marks are skipped unless forced
+        set_tray_checked(&item, &last_checked, true, false, |_| panic!("set again"));
+        let mut forced = false;
+        set_tray_checked(&item, &last_checked, true, true, |_| {
+            forced = true;
+            true
+        });
+        assert!(forced);
+    }
+
+    #[test]
+    fn queries_skip_devices_that_fail() {
+        let profile = profile_with(&["a", "b"]);
+        let ids = ["a".to_string(), "b".to_string()];
+        let queried = query_device_mute_states(
+            &profile,
+            &ids,
+            |id| match id {
+                "a" => Ok(true),
+                _ => Err(missing_devices::DEVICE_MISSING.to_string()),
+            },
+            MuteState::Unmuted,
+        );
+        assert_eq!(queried.state, MuteState::Muted);
+        assert_eq!(queried.devices.len(), 1);
+        assert_eq!(queried.devices[0].device_id, "a");
+
+        let queried = query_device_mute_states(
+            &profile,
+            &ids,
+            |_| Err("gone".to_string()),
+            MuteState::Mixed,
+        );
+        assert_eq!(queried.state, MuteState::Mixed);
+        assert!(queried.devices.is_empty());
+    }
+
+    fn hotkey_plan(
+        state: &AppState,
+        toggle_key: &str,
+        ignore_modifiers: bool,
+    ) -> ProfileHotkeyPlan {
+        let other = profile(json!({
+            "id": "other",
+            "toggleKey": toggle_key,
+            "ignoreModifiers": ignore_modifiers
+        }));
+        plan_profile_hotkeys(state, &other).unwrap()
+    }
+
+    #[test]
+    fn other_profiles_skip_hotkeys_that_are_taken() {
+        use shortcuts::{ShortcutEngine::Plugin, ShortcutPurpose};
+
+        let state = AppState::default();
+        let active = Some("active".to_string());
+        shortcuts::add(
+            &state,
+            "CommandOrControl+F13",
+            active,
+            ShortcutPurpose::Toggle,
+            Plugin,
+        );
+        shortcuts::add(&state, "F14", None, ShortcutPurpose::Panic, Plugin);
+        let earlier = Some("earlier".to_string());
+        shortcuts::add(
+            &state,
+            "Alt+F15",
+            earlier,
+            ShortcutPurpose::ProfileToggle,
+            Plugin,
+        );
+
+        let taken_by = |toggle_key: &str| {
+            let plan = hotkey_plan(&state, toggle_key, false);
+            assert!(plan.free.is_empty());
+            let (_, owner) = plan.taken.into_iter().next().unwrap();
+            (owner.purpose, owner.profile_id)
+        };
+        // Spelled differently from the registration
+        assert_eq!(
+            taken_by("ctrl+F13"),
+            (ShortcutPurpose::Toggle, Some("active".to_string()))
+        );
+        assert_eq!(taken_by("F14"), (ShortcutPurpose::Panic, None));
+        assert_eq!(
+            taken_by("alt+F15"),
+            (ShortcutPurpose::ProfileToggle, Some("earlier".to_string()))
+        );
+
+        let plan = hotkey_plan(&state, "F16", false);
+        assert_eq!(plan.free, ["F16"]);
+        assert!(plan.taken.is_empty());
+    }
+
+    #[test]
+    fn other_profiles_plan_every_modifier_combination() {
+        let state = AppState::default();
+        shortcuts::add(
+            &state,
+            "Shift+F13",
+            None,
+            shortcuts::ShortcutPurpose::Undo,
+            shortcuts::ShortcutEngine::Plugin,
+        );
+        let plan = hotkey_plan(&state, "F13", true);
+        assert_eq!(plan.free.len(), 7);
+        assert!(plan.free.contains(&"F13".to_string()));
+        let taken: Vec<&str> = plan.taken.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(taken, ["Shift+F13"]);
+    }
+
+    #[test]
+    fn virtual_key_hotkeys_stay_with_the_active_profile() {
+        let other = profile(json!({ "toggleKey": "vk:0x13" }));
+        assert!(plan_profile_hotkeys(&AppState::default(), &other).is_err());
+    }
+
+    /// The profile and mute logic against the in-memory devices of `mock-audio`.
+    #[cfg(feature = "mock-audio")]
+    mod mock_audio {
+        use super::*;
+        use crate::audio::mock::{self, MockOp};
+        use crate::test_support::{mock_app, mock_device, mock_devices, recorded_events};
+
+        fn controller() -> PlatformAudioController {
+            PlatformAudioController::new().unwrap()
+        }
+
+        fn state_of(profile: &HotkeyProfile) -> ProfileMuteState {
+            get_profile_mute_state_detailed(&controller(), profile, None, MuteState::Unmuted)
+                .unwrap()
+        }
+
+        fn breakdown(state: &ProfileMuteState) -> Vec<(&str, bool)> {
+            state
+                .devices
+                .iter()
+                .map(|d| (d.device_id.as_str(), d.muted))
+                .collect()
+        }
+
+        #[test]
+        fn resolves_devices_by_enumerating() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let resolved = resolve_device_ids(&controller(), &profile_with(&["b"]), None).unwrap();
+            assert_eq!(resolved.present, ["b"]);
+            assert!(resolved.missing.is_empty());
+
+            let resolved =
+                resolve_device_ids(&controller(), &profile_with(&["gone"]), None).unwrap();
+            assert!(resolved.present.is_empty());
+            assert_eq!(resolved.missing, ["gone"]);
+
+            // More than one device stands for all of them, like the all-devices entry
+            let resolved =
+                resolve_device_ids(&controller(), &profile_with(&["b", "gone"]), None).unwrap();
+            assert_eq!(resolved.present, ["a", "b"]);
+            assert_eq!(resolved.missing, ["gone"]);
+
+            let resolved =
+                resolve_device_ids(&controller(), &profile_with(&[ALL_DEVICES_ID]), None).unwrap();
+            assert_eq!(resolved.present, ["a", "b"]);
+            assert!(resolved.missing.is_empty());
+
+            let resolved =
+                resolve_device_ids(&controller(), &profile_with(&["default-mic"]), None).unwrap();
+            assert_eq!(resolved.present, ["default-mic"]);
+        }
+
+        #[test]
+        fn a_cached_device_list_is_used_as_is() {
+            let _devices = mock_devices(vec![mock_device("a", true)]);
+            let cached = [default_device("cached")];
+            let resolved =
+                resolve_device_ids(&controller(), &profile_with(&["a"]), Some(&cached)).unwrap();
+            assert!(resolved.present.is_empty());
+            assert_eq!(resolved.missing, ["a"]);
+        }
+
+        #[test]
+        fn explicit_devices_survive_a_failed_enumeration() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            mock::fail("a", MockOp::Enumerate, "enumeration failed", 0);
+            let resolved =
+                resolve_device_ids(&controller(), &profile_with(&["gone"]), None).unwrap();
+            assert_eq!(resolved.present, ["gone"]);
+            assert!(resolved.missing.is_empty());
+
+            assert_eq!(
+                resolve_device_ids(&controller(), &profile_with(&[ALL_DEVICES_ID]), None)
+                    .unwrap_err(),
+                "enumeration failed"
+            );
+        }
+
+        #[test]
+        fn combines_the_device_states() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let profile = profile_with(&["a", "b"]);
+            assert_eq!(state_of(&profile).state, MuteState::Unmuted);
+
+            mock::set_external_mute("a", true).unwrap();
+            let mixed = state_of(&profile);
+            assert_eq!(mixed.state, MuteState::Mixed);
+            assert_eq!(breakdown(&mixed), [("a", true), ("b", false)]);
+
+            mock::set_external_mute("b", true).unwrap();
+            assert_eq!(state_of(&profile).state, MuteState::Muted);
+        }
+
+        #[test]
+        fn devices_that_cant_be_queried_are_left_out() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            mock::set_external_mute("b", true).unwrap();
+            mock::fail("a", MockOp::GetMute, "unplugged", 1);
+            let state = state_of(&profile_with(&["a", "b"]));
+            assert_eq!(state.state, MuteState::Muted);
+            assert_eq!(breakdown(&state), [("b", true)]);
+
+            mock::fail("a", MockOp::GetMute, "unplugged", 1);
+            let state = get_profile_mute_state_detailed(
+                &controller(),
+                &profile_with(&["a"]),
+                None,
+                MuteState::Mixed,
+            )
+            .unwrap();
+            assert_eq!(state.state, MuteState::Mixed);
+            assert!(state.devices.is_empty());
+        }
+
+        #[test]
+        fn toggles_and_profile_switches_dont_deadlock() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = mock_app(AppState::default());
+            let first = profile(json!({ "id": "p1", "deviceIds": ["a"] }));
+            let second = profile(json!({ "id": "p2", "deviceIds": ["b"] }));
+            let handle = app.handle().clone();
+            set_active_profile_internal(&handle.state(), &handle, first.clone());
+
+            let (done_tx, done_rx) = std::sync::mpsc::channel();
+            let toggler = {
+                let app = handle.clone();
+                let done_tx = done_tx.clone();
+                std::thread::spawn(move || {
+                    let state = app.state::<AppState>();
+                    for _ in 0..25 {
+                        toggle_mute_internal(&state, &app, "test").unwrap();
+                        let profile = state.active_profile_for_mute().unwrap();
+                        toggle_profile_devices(&state, &app, all_devices_profile(profile), "test");
+                    }
+                    let _ = done_tx.send("toggle");
+                })
+            };
+            let switcher = {
+                let app = handle.clone();
+                std::thread::spawn(move || {
+                    let state = app.state::<AppState>();
+                    for i in 0..50 {
+                        let profile = if i % 2 == 0 { &second } else { &first };
+                        set_active_profile_internal(&state, &app, profile.clone());
+                    }
+                    let _ = done_tx.send("profile switch");
+                })
+            };
+            for _ in 0..2 {
+                done_rx
+                    .recv_timeout(Duration::from_secs(10))
+                    .expect("toggling deadlocked with switching profiles");
+            }
+            toggler.join().unwrap();
+            switcher.join().unwrap();
+
+            // The worker still answers, and the last switch left the first profile active
+            let state = handle.state::<AppState>();
+            let active = state.with_profile_on_worker("test", |profile, _| profile.id.clone());
+            assert_eq!(active.as_deref(), Some("p1"));
+        }
+
+        /// A mock app with "a" and "b", whose active profile is "a".
+        fn toggle_app() -> tauri::App<AppRuntime> {
+            let app = mock_app(AppState::default());
+            let state = app.state::<AppState>();
+            *state.current_profile.lock_safe() = Some(profile_with(&["a"]));
+            *state.device_mute_states.lock_safe() = vec![DeviceMuteState {
+                device_id: "a".to_string(),
+                muted: false,
+            }];
+            app
+        }
+
+        /// Run the `toggle_mute` command and wait for the worker to apply it. Calls queue
+        /// behind pending changes, so one returning means the change reached the devices.
+        fn run_toggle(app: &AppHandle, scope: Option<ToggleScope>) -> ToggleResult {
+            let result = tauri::async_runtime::block_on(toggle_mute(scope, app.clone())).unwrap();
+            app.state::<AppState>()
+                .with_profile_on_worker("test", |_, _| ())
+                .unwrap();
+            result
+        }
+
+        fn mock_muted(id: &str) -> bool {
+            mock::devices()
+                .iter()
+                .find(|d| d.device.id == id)
+                .is_some_and(|d| d.muted)
+        }
+
+        #[test]
+        fn toggling_a_device_leaves_the_profile_alone() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = toggle_app();
+            let handle = app.handle();
+            let state = app.state::<AppState>();
+            let changed = recorded_events(handle, "device-mute-changed");
+            let published = recorded_events(handle, events::MUTE_STATE_CHANGED);
+
+            let result = run_toggle(handle, Some(ToggleScope::Device("a".to_string())));
+            assert!(result.muted);
+            assert_eq!(result.device_count, 1);
+            assert!(mock_muted("a"));
+            assert!(!mock_muted("b"));
+            assert_eq!(changed.lock_safe()[0]["deviceId"], "a");
+            assert_eq!(changed.lock_safe()[0]["muted"], true);
+            // The breakdown follows; the profile's state, tray and listeners don't
+            assert!(state.device_mute_states.lock_safe()[0].muted);
+            assert_eq!(state.mute_state.get(), MuteState::Unmuted);
+            assert_eq!(*state.last_tray_state.lock_safe(), None);
+            assert!(published.lock_safe().is_empty());
+
+            // Devices outside the profile can be toggled too
+            let result = run_toggle(handle, Some(ToggleScope::Device("b".to_string())));
+            assert!(result.muted);
+            assert!(mock_muted("b"));
+            let result = run_toggle(handle, Some(ToggleScope::Device("b".to_string())));
+            assert!(!result.muted);
+            assert!(!mock_muted("b"));
+        }
+
+        #[test]
+        fn toggling_all_reaches_devices_outside_the_profile() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = toggle_app();
+            let state = app.state::<AppState>();
+
+            let result = run_toggle(app.handle(), Some(ToggleScope::All));
+            assert!(result.muted);
+            assert_eq!(result.profile_id.as_deref(), Some("p1"));
+            assert!(mock_muted("a"));
+            assert!(mock_muted("b"));
+            assert_eq!(state.mute_state.get(), MuteState::Muted);
+            assert_eq!(*state.last_tray_state.lock_safe(), Some(MuteState::Muted));
+
+            run_toggle(app.handle(), Some(ToggleScope::All));
+            assert!(!mock_muted("a"));
+            assert!(!mock_muted("b"));
+            assert_eq!(state.mute_state.get(), MuteState::Unmuted);
+        }
+
+        #[test]
+        fn toggling_without_a_scope_toggles_the_profile() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = toggle_app();
+            let handle = app.handle();
+            let state = app.state::<AppState>();
+            let published = recorded_events(handle, events::MUTE_STATE_CHANGED);
+
+            let result = run_toggle(handle, None);
+            assert!(result.muted);
+            assert_eq!(result.device_count, 1);
+            assert_eq!(result.profile_id.as_deref(), Some("p1"));
+            assert!(mock_muted("a"));
+            assert!(!mock_muted("b"));
+            assert_eq!(state.mute_state.get(), MuteState::Muted);
+            assert_eq!(*state.last_tray_state.lock_safe(), Some(MuteState::Muted));
+            assert_eq!(published.lock_safe()[0]["muted"], true);
+
+            // The same as the profile's toggle, which was all there was before scopes
+            let profile = toggle_mute_internal(&state, handle, "frontend").unwrap();
+            state.with_profile_on_worker("test", |_, _| ()).unwrap();
+            assert!(!profile.muted);
+            assert_eq!(profile.device_count, result.device_count);
+            assert_eq!(profile.profile_id, result.profile_id);
+            assert!(!mock_muted("a"));
+            let published = published.lock_safe();
+            assert_eq!(published[1]["source"], published[0]["source"]);
+        }
+
+        #[test]
+        fn the_state_of_one_device_is_its_own() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = toggle_app();
+            mock::set_external_mute("b", true).unwrap();
+            let query = |device_id: Option<&str>| {
+                let device_id = device_id.map(str::to_string);
+                tauri::async_runtime::block_on(get_mute_state(device_id, app.handle().clone()))
+                    .unwrap()
+            };
+            assert!(query(Some("b")));
+            assert!(!query(Some("a")));
+            assert!(!query(None));
+        }
+
+        #[test]
+        fn devices_that_fail_keep_the_state_they_have() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            mock::set_external_mute("a", true).unwrap();
+            let app = mock_app(AppState::default());
+            let handle = app.handle();
+            let state = app.state::<AppState>();
+            let scene = profile(json!({
+                "deviceStates": [
+                    { "deviceId": "a", "mutedWhenActive": false },
+                    { "deviceId": "b", "mutedWhenActive": true }
+                ]
+            }));
+            state.store_mute_state(state_of(&scene));
+            *state.current_profile.lock_safe() = Some(scene);
+            let partial = recorded_events(handle, "mute-apply-partial");
+
+            // Applying the scene unmutes "a" but can't mute "b", which stays unmuted
+            mock::fail("b", MockOp::SetMute, "unplugged", 0);
+            set_mute_internal(&state, handle, false, true, "frontend").unwrap();
+            // Once for the request, once for the read-back it queued
+            state.with_profile_on_worker("test", |_, _| ()).unwrap();
+            state.with_profile_on_worker("test", |_, _| ()).unwrap();
+
+            assert_eq!(partial.lock_safe()[0]["failures"][0]["deviceId"], "b");
+            assert_eq!(state.mute_state.get(), MuteState::Mixed);
+            assert_eq!(
+                breakdown(&ProfileMuteState {
+                    state: MuteState::Mixed,
+                    devices: state.device_mute_states.lock_safe().clone(),
+                }),
+                [("a", false), ("b", false)]
+            );
+        }
+
+        #[test]
+        fn devices_that_cant_be_read_back_are_left_out() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = mock_app(AppState::default());
+            let handle = app.handle();
+            let state = app.state::<AppState>();
+            *state.current_profile.lock_safe() = Some(profile_with(&["a", "b"]));
+            state.store_mute_state(state_of(&profile_with(&["a", "b"])));
+
+            mock::fail("b", MockOp::SetMute, "unplugged", 0);
+            mock::fail("b", MockOp::GetMute, "unplugged", 0);
+            set_mute_internal(&state, handle, true, true, "frontend").unwrap();
+            state.with_profile_on_worker("test", |_, _| ()).unwrap();
+            state.with_profile_on_worker("test", |_, _| ()).unwrap();
+
+            assert_eq!(state.mute_state.get(), MuteState::Mixed);
+            assert_eq!(
+                breakdown(&ProfileMuteState {
+                    state: MuteState::Mixed,
+                    devices: state.device_mute_states.lock_safe().clone(),
+                }),
+                [("a", true)]
+            );
+        }
+
+        #[test]
+        fn scenes_are_unmuted_while_applied() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let scene = profile(json!({
+                "deviceStates": [
+                    { "deviceId": "a", "mutedWhenActive": false },
+                    { "deviceId": "b", "mutedWhenActive": true }
+                ]
+            }));
+            assert_eq!(state_of(&scene).state, MuteState::Mixed);
+            mock::set_external_mute("b", true).unwrap();
+            assert_eq!(state_of(&scene).state, MuteState::Unmuted);
+            mock::set_external_mute("a", true).unwrap();
+            assert_eq!(state_of(&scene).state, MuteState::Muted);
+        }
+    }
 }
@@ -0,0 +1,310 @@
+//! USB busylights showing the mute state.
+//!
+//! With `AppSettings::busylight` enabled, the first compatible light found turns
+//! `muted_color` while muted and `unmuted_color` otherwise (a mixed state counts as live).
+//! Writes run on a dedicated thread so a slow USB write never delays a toggle. A failed
+//! write closes the light; it is looked for again with backoff, which also picks up a
+//! light plugged in later. Nothing is opened, and no thread runs, until the light is
+//! enabled.
+//!
+//! Supported are Luxafor lights (one colour report) and Kuando Busylights (a 64-byte step
+//! program, which has to be resent within `KUANDO_KEEPALIVE` or the light goes dark).
+
+use crate::mute_state::MuteState;
+use crate::MutexExt;
+use hidapi::{HidApi, HidDevice};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(30);
+const KUANDO_KEEPALIVE: Duration = Duration::from_secs(15);
+
+const DEFAULT_MUTED_COLOR: &str = "#00ff00";
+const DEFAULT_UNMUTED_COLOR: &str = "#ff0000";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BusylightSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `#rrggbb`.
+    #[serde(default = "default_muted_color")]
+    pub muted_color: String,
+    #[serde(default = "default_unmuted_color")]
+    pub unmuted_color: String,
+}
+
+fn default_muted_color() -> String {
+    DEFAULT_MUTED_COLOR.to_string()
+}
+
+fn default_unmuted_color() -> String {
+    DEFAULT_UNMUTED_COLOR.to_string()
+}
+
+impl Default for BusylightSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            muted_color: default_muted_color(),
+            unmuted_color: default_unmuted_color(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Protocol {
+    Luxafor,
+    Kuando,
+}
+
+/// A compatible light; see `list_hid_lights`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HidLight {
+    pub name: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub protocol: Protocol,
+}
+
+/// Known lights by vendor and product ID.
+const KNOWN: &[(u16, u16, Protocol)] = &[
+    (0x04d8, 0xf372, Protocol::Luxafor),
+    (0x04d8, 0xf848, Protocol::Kuando),
+    (0x27bb, 0x3bca, Protocol::Kuando),
+    (0x27bb, 0x3bcb, Protocol::Kuando),
+    (0x27bb, 0x3bcc, Protocol::Kuando),
+    (0x27bb, 0x3bcd, Protocol::Kuando),
+    (0x27bb, 0x3bce, Protocol::Kuando),
+    (0x27bb, 0x3bcf, Protocol::Kuando),
+];
+
+type Rgb = [u8; 3];
+
+/// Colour to show; `None` turns the light off.
+type Command = Option<Rgb>;
+
+struct Colors {
+    muted: Rgb,
+    unmuted: Rgb,
+}
+
+/// Colours while enabled; `None` while the light is disabled.
+static COLORS: Lazy<Mutex<Option<Colors>>> = Lazy::new(|| Mutex::new(None));
+/// Sender to the light thread, spawned on first enable.
+static WORKER: Lazy<Mutex<Option<mpsc::Sender<Command>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Apply `settings`, showing `mute_state` right away when enabled.
+pub fn configure(settings: &BusylightSettings, mute_state: MuteState) {
+    if !settings.enabled {
+        if COLORS.lock_safe().take().is_some() {
+            send(None);
+        }
+        return;
+    }
+    *COLORS.lock_safe() = Some(Colors {
+        muted: parse_color(&settings.muted_color, DEFAULT_MUTED_COLOR),
+        unmuted: parse_color(&settings.unmuted_color, DEFAULT_UNMUTED_COLOR),
+    });
+    show(mute_state);
+}
+
+/// Show `mute_state` on the light, if enabled. Never blocks.
+pub fn show(mute_state: MuteState) {
+    let color = match COLORS.lock_safe().as_ref() {
+        Some(colors) if mute_state == MuteState::Muted => colors.muted,
+        Some(colors) => colors.unmuted,
+        None => return,
+    };
+    send(Some(color));
+}
+
+fn send(command: Command) {
+    let mut worker = WORKER.lock_safe();
+    let tx = worker.get_or_insert_with(|| {
+        let (tx, rx) = mpsc::channel::<Command>();
+        std::thread::spawn(move || run(rx));
+        tx
+    });
+    let _ = tx.send(command);
+}
+
+fn parse_color(value: &str, fallback: &str) -> Rgb {
+    let parse = |value: &str| -> Option<Rgb> {
+        let hex = value.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some([channel(0)?, channel(2)?, channel(4)?])
+    };
+    parse(value).unwrap_or_else(|| {
+        log::warn!("[lights] invalid colour '{}', using {}", value, fallback);
+        parse(fallback).unwrap_or_default()
+    })
+}
+
+/// Compatible lights currently connected.
+pub fn list_hid_lights() -> Result<Vec<HidLight>, String> {
+    let api = HidApi::new().map_err(|e| format!("Failed to access HID devices: {}", e))?;
+    Ok(compatible(&api)
+        .map(|(info, protocol)| HidLight {
+            name: info
+                .product_string()
+                .unwrap_or(match protocol {
+                    Protocol::Luxafor => "Luxafor",
+                    Protocol::Kuando => "Kuando Busylight",
+                })
+                .to_string(),
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+            protocol,
+        })
+        .collect())
+}
+
+fn compatible(api: &HidApi) -> impl Iterator<Item = (&hidapi::DeviceInfo, Protocol)> {
+    api.device_list().filter_map(|info| {
+        KNOWN
+            .iter()
+            .find(|(vendor, product, _)| {
+                *vendor == info.vendor_id() && *product == info.product_id()
+            })
+            .map(|(_, _, protocol)| (info, *protocol))
+    })
+}
+
+struct Light {
+    device: HidDevice,
+    protocol: Protocol,
+}
+
+impl Light {
+    fn open(api: &mut HidApi) -> Result<Self, String> {
+        api.refresh_devices().map_err(|e| e.to_string())?;
+        let (info, protocol) = compatible(api)
+            .next()
+            .ok_or_else(|| "no compatible light connected".to_string())?;
+        let device = info.open_device(api).map_err(|e| e.to_string())?;
+        log::info!(
+            "[lights] opened {:04x}:{:04x} ({:?})",
+            info.vendor_id(),
+            info.product_id(),
+            protocol
+        );
+        Ok(Self { device, protocol })
+    }
+
+    fn write(&self, color: Rgb) -> Result<(), String> {
+        let report = match self.protocol {
+            Protocol::Luxafor => luxafor_report(color),
+            Protocol::Kuando => kuando_report(color),
+        };
+        self.device
+            .write(&report)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Static colour on all LEDs, prefixed with report ID 0.
+fn luxafor_report([r, g, b]: Rgb) -> Vec<u8> {
+    vec![0x00, 0x01, 0xff, r, g, b, 0x00, 0x00, 0x00]
+}
+
+/// A one-step program looping on itself, prefixed with report ID 0. Kuando colours run
+/// from 0 to 100.
+fn kuando_report([r, g, b]: Rgb) -> Vec<u8> {
+    let scale = |c: u8| (u16::from(c) * 100 / 255) as u8;
+    let mut program = [0u8; 64];
+    // Jump to step 0, i.e. keep showing this step
+    program[0] = 0x10;
+    program[2] = scale(r);
+    program[3] = scale(g);
+    program[4] = scale(b);
+    program[59..62].fill(0xff);
+    let checksum: u16 = program[..62].iter().map(|&b| u16::from(b)).sum();
+    program[62..].copy_from_slice(&checksum.to_be_bytes());
+
+    let mut report = Vec::with_capacity(65);
+    report.push(0x00);
+    report.extend_from_slice(&program);
+    report
+}
+
+fn run(rx: mpsc::Receiver<Command>) {
+    let mut api = match HidApi::new() {
+        Ok(api) => api,
+        Err(e) => {
+            log::warn!("[lights] failed to access HID devices: {}", e);
+            return;
+        }
+    };
+    let mut light: Option<Light> = None;
+    // What the light should show, and whether it does
+    let mut wanted: Command = None;
+    let mut shown = true;
+    let mut retry = RETRY_MIN;
+
+    loop {
+        let timeout = if !shown {
+            retry
+        } else if wanted.is_some()
+            && light
+                .as_ref()
+                .is_some_and(|l| l.protocol == Protocol::Kuando)
+        {
+            KUANDO_KEEPALIVE
+        } else {
+            Duration::MAX
+        };
+        match rx.recv_timeout(timeout) {
+            Ok(command) => {
+                wanted = command;
+                shown = false;
+                retry = RETRY_MIN;
+            }
+            // Retry, or resend as the Kuando keepalive
+            Err(RecvTimeoutError::Timeout) => shown = false,
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        if shown {
+            continue;
+        }
+
+        let Some(color) = wanted else {
+            // Turn off and let go of the light while disabled
+            if let Some(light) = light.take() {
+                let _ = light.write([0, 0, 0]);
+            }
+            shown = true;
+            continue;
+        };
+        if light.is_none() {
+            match Light::open(&mut api) {
+                Ok(opened) => light = Some(opened),
+                Err(e) => {
+                    log::debug!("[lights] {}, retrying in {:?}", e, retry);
+                    retry = (retry * 2).min(RETRY_MAX);
+                    continue;
+                }
+            }
+        }
+        if let Some(open) = light.as_ref() {
+            match open.write(color) {
+                Ok(()) => shown = true,
+                Err(e) => {
+                    log::warn!("[lights] write failed, reopening: {}", e);
+                    light = None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Leveled logging to a size-rotated file in the app log directory.
+//!
+//! Records go through the `log` facade into a channel; a writer thread appends them to
+//! `togmic.log` and rotates it once it grows past `MAX_FILE_BYTES`, keeping
+//! `KEEP_GENERATIONS` older files (`togmic.1.log` is the newest of those). Logging never
+//! touches the disk on the calling thread, so it is safe on the toggle path. Records
+//! logged before `start` are buffered in the channel until the writer is up.
+//!
+//! The last `RECENT_CAPACITY` lines are also kept in memory for `recent`, which support
+//! reads while a user reproduces a problem after raising the level with `set_level`. A
+//! line is formatted once and shared between the writer and that buffer.
+
+use crate::{AppHandle, MutexExt};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::path::BaseDirectory;
+use tauri::Manager;
+use tauri_plugin_opener::OpenerExt;
+
+const LOG_FILE: &str = "togmic.log";
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const KEEP_GENERATIONS: usize = 3;
+/// How long the writer waits for more records before flushing what it has.
+const FLUSH_DELAY_MS: u64 = 500;
+/// Lines kept for `recent`.
+const RECENT_CAPACITY: usize = 2000;
+
+struct FileLogger {
+    tx: Mutex<mpsc::Sender<Arc<str>>>,
+}
+
+/// A formatted log line with its level.
+type RecentLine = (Level, Arc<str>);
+
+/// The last `RECENT_CAPACITY` lines, oldest first.
+static RECENT: Lazy<Mutex<VecDeque<RecentLine>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)));
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line: Arc<str> = format!(
+            "{} {:<5} {}",
+            format_timestamp(SystemTime::now()),
+            record.level(),
+            record.args()
+        )
+        .into();
+        #[cfg(debug_assertions)]
+        eprintln!("{}", line);
+        {
+            let mut recent = RECENT.lock_safe();
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back((record.level(), line.clone()));
+        }
+        let _ = self.tx.lock_safe().send(line);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Receiving end of the logger's channel, handed to the writer thread by `start`.
+static PENDING: Lazy<Mutex<Option<mpsc::Receiver<Arc<str>>>>> = Lazy::new(|| Mutex::new(None));
+static LOG_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Install the logger. Call first thing in `run()` so startup failures are captured.
+pub fn init() {
+    let (tx, rx) = mpsc::channel();
+    let logger = FileLogger { tx: Mutex::new(tx) };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+        *PENDING.lock_safe() = Some(rx);
+    }
+}
+
+/// Resolve the log file and start the writer thread.
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let path = app
+        .path()
+        .resolve(LOG_FILE, BaseDirectory::AppLog)
+        .map_err(|e| format!("Failed to resolve log directory: {}", e))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create log directory: {}", e))?;
+    }
+    let rx = PENDING
+        .lock_safe()
+        .take()
+        .ok_or_else(|| "Logger is not initialized".to_string())?;
+    *LOG_PATH.lock_safe() = Some(path.clone());
+
+    std::thread::spawn(move || run_writer(&path, rx));
+    Ok(())
+}
+
+/// Path of the current log file, once `start` succeeded.
+pub fn log_path() -> Option<PathBuf> {
+    LOG_PATH.lock_safe().clone()
+}
+
+/// Open the log directory in the system file manager.
+pub fn open_folder(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .resolve("", BaseDirectory::AppLog)
+        .map_err(|e| format!("Failed to resolve log directory: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open log directory: {}", e))
+}
+
+/// Change the level at runtime. Accepts `off`, `error`, `warn`, `info`, `debug`, `trace`.
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = LevelFilter::from_str(level.trim())
+        .map_err(|_| format!("Unknown log level '{}'", level))?;
+    log::set_max_level(filter);
+    log::info!("[logging] log level set to {}", filter);
+    Ok(())
+}
+
+/// The last `limit` lines at `min_level` or more severe, oldest first. Lines below the
+/// level that was set when they were logged were never recorded.
+pub fn recent(limit: usize, min_level: Level) -> Vec<String> {
+    let recent = RECENT.lock_safe();
+    let mut lines: Vec<String> = recent
+        .iter()
+        .rev()
+        .filter(|(level, _)| *level <= min_level)
+        .take(limit)
+        .map(|(_, line)| line.to_string())
+        .collect();
+    lines.reverse();
+    lines
+}
+
+fn run_writer(path: &Path, rx: mpsc::Receiver<Arc<str>>) {
+    let mut writer = match open(path) {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("[logging] {}", e);
+            return;
+        }
+    };
+    let mut size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+    while let Ok(line) = rx.recv() {
+        let mut next = Some(line);
+        // Write everything that queued up, then flush once things are quiet
+        while let Some(line) = next.take() {
+            if size >= MAX_FILE_BYTES {
+                let _ = writer.flush();
+                rotate(path);
+                writer = match open(path) {
+                    Ok(writer) => writer,
+                    Err(e) => {
+                        eprintln!("[logging] {}", e);
+                        return;
+                    }
+                };
+                size = 0;
+            }
+            if writeln!(writer, "{}", line).is_ok() {
+                size += line.len() as u64 + 1;
+            }
+            next = rx.recv_timeout(Duration::from_millis(FLUSH_DELAY_MS)).ok();
+        }
+        let _ = writer.flush();
+    }
+}
+
+fn open(path: &Path) -> Result<BufWriter<File>, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("Failed to open log file {}: {}", path.display(), e))
+}
+
+/// Shift `togmic.N.log` to `togmic.N+1.log`, dropping the oldest, and move the current
+/// file to `togmic.1.log`.
+fn rotate(path: &Path) {
+    let generation = |n: usize| path.with_extension(format!("{}.log", n));
+    let _ = std::fs::remove_file(generation(KEEP_GENERATIONS));
+    for n in (1..KEEP_GENERATIONS).rev() {
+        let _ = std::fs::rename(generation(n), generation(n + 1));
+    }
+    let _ = std::fs::rename(path, generation(1));
+}
+
+/// `YYYY-MM-DD HH:MM:SS.mmm` in UTC.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, day_secs) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        day_secs / 3600,
+        day_secs / 60 % 60,
+        day_secs % 60,
+        since_epoch.subsec_millis()
+    )
+}
@@ -0,0 +1,145 @@
+//! Which applications are capturing from the microphones right now.
+//!
+//! Off unless `AppSettings::track_mic_usage` is on, since it looks at other processes.
+//! While on, a watcher thread lists the active capture sessions of every input device
+//! every `POLL_INTERVAL`, resolves their process names and emits `mic-consumers-changed`
+//! with the full list whenever it differs from the previous one; the tray tooltip names
+//! the applications too. Each list also goes to `capture_switch`. Where the platform can't
+//! list sessions the watcher stops until the setting is applied again.
+
+use crate::audio::{AudioController, PlatformAudioController, SESSIONS_UNSUPPORTED};
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Emitter;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub const MIC_CONSUMERS_CHANGED: &str = "mic-consumers-changed";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Set by the watcher once session tracking turned out to be unsupported.
+static UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+/// What the watcher saw last; empty while tracking is off.
+static CONSUMERS: Lazy<Mutex<Vec<MicConsumer>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MicConsumer {
+    pub device_id: String,
+    /// Executable file name, or `pid <n>` if it can't be resolved.
+    pub process_name: String,
+    pub pid: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MicConsumersChangedEvent<'a> {
+    consumers: &'a [MicConsumer],
+}
+
+pub fn configure(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    UNSUPPORTED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// The applications seen by the last check.
+pub fn current() -> Vec<MicConsumer> {
+    CONSUMERS.lock_safe().clone()
+}
+
+/// Distinct process names of the current consumers, for the tray tooltip.
+pub fn process_names() -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for consumer in CONSUMERS.lock_safe().iter() {
+        if !names.contains(&consumer.process_name) {
+            names.push(consumer.process_name.clone());
+        }
+    }
+    names
+}
+
+/// List the applications capturing from any input device.
+pub fn query(controller: &PlatformAudioController) -> Result<Vec<MicConsumer>, String> {
+    let mut consumers = Vec::new();
+    for device in controller.enumerate_input_devices()? {
+        let pids = match controller.capture_session_pids(&device.id) {
+            Ok(pids) => pids,
+            Err(e) if e == SESSIONS_UNSUPPORTED => return Err(e),
+            // A device that just disappeared has no consumers
+            Err(_) => continue,
+        };
+        for pid in pids {
+            consumers.push(MicConsumer {
+                device_id: device.id.clone(),
+                process_name: crate::foreground::process_name(pid)
+                    .unwrap_or_else(|| format!("pid {}", pid)),
+                pid,
+            });
+        }
+    }
+    Ok(consumers)
+}
+
+/// Start the watcher thread. It idles along cheaply while tracking is off.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let _ = PlatformAudioController::init_thread();
+        let controller = match PlatformAudioController::new() {
+            Ok(controller) => controller,
+            Err(e) => {
+                log::warn!("[mic-usage] failed to create audio controller: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+
+            let consumers = if !is_enabled() || UNSUPPORTED.load(Ordering::SeqCst) {
+                Vec::new()
+            } else {
+                match query(&controller) {
+                    Ok(consumers) => {
+                        crate::capture_switch::observe(&app, &consumers);
+                        consumers
+                    }
+                    Err(e) if e == SESSIONS_UNSUPPORTED => {
+                        log::info!("[mic-usage] {}, watcher stopped", e);
+                        UNSUPPORTED.store(true, Ordering::SeqCst);
+                        Vec::new()
+                    }
+                    Err(e) => {
+                        log::warn!("[mic-usage] {}", e);
+                        continue;
+                    }
+                }
+            };
+            update(&app, consumers);
+        }
+    });
+}
+
+fn update(app: &AppHandle, consumers: Vec<MicConsumer>) {
+    {
+        let mut current = CONSUMERS.lock_safe();
+        if *current == consumers {
+            return;
+        }
+        *current = consumers.clone();
+    }
+    log::debug!("[mic-usage] {} capture session(s)", consumers.len());
+    let _ = app.emit(
+        MIC_CONSUMERS_CHANGED,
+        MicConsumersChangedEvent {
+            consumers: &consumers,
+        },
+    );
+    crate::refresh_tray_tooltip(app);
+}
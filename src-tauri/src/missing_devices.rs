@@ -0,0 +1,98 @@
+//! Profile devices the system no longer has.
+//!
+//! `resolve_device_ids` splits a profile's devices into the ones that exist and the
+//! missing ones. Mute states are read only from the existing devices, and applying a mute
+//! change skips the missing ones and reports them as failures. `note` emits
+//! `profile-devices-missing` the first time a device is noticed missing; once it is back,
+//! it can be noticed again.
+
+use crate::{AppHandle, HotkeyProfile, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::Emitter;
+
+pub const PROFILE_DEVICES_MISSING: &str = "profile-devices-missing";
+
+/// Error a missing device fails a mute change with.
+pub const DEVICE_MISSING: &str = "The device is not connected";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileDevicesMissingEvent<'a> {
+    profile_id: &'a str,
+    /// Devices noticed missing with this check.
+    device_ids: Vec<String>,
+    /// Every device of the profile that is missing.
+    missing: &'a [String],
+    /// None of the profile's devices exist.
+    all_missing: bool,
+}
+
+/// Device ids already reported missing.
+static NOTICED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+/// For emitting from the worker and polling threads, which have no handle of their own.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn init(app: &AppHandle) {
+    *APP.lock_safe() = Some(app.clone());
+}
+
+/// Record which of `profile`'s devices are `missing` out of `present` ones, emitting
+/// `profile-devices-missing` for the ones not reported before.
+pub fn note(profile: &HotkeyProfile, present: &[String], missing: &[String]) {
+    let new = newly_missing(present, missing);
+    if new.is_empty() {
+        return;
+    }
+    log::warn!(
+        "[devices] profile '{}' uses missing device(s): {}",
+        profile.name,
+        new.join(", ")
+    );
+    let Some(app) = APP.lock_safe().clone() else {
+        return;
+    };
+    let _ = app.emit(
+        PROFILE_DEVICES_MISSING,
+        ProfileDevicesMissingEvent {
+            profile_id: &profile.id,
+            device_ids: new,
+            missing,
+            all_missing: present.is_empty(),
+        },
+    );
+}
+
+/// The `missing` ids not noticed before. Ids in `present` can be noticed again.
+fn newly_missing(present: &[String], missing: &[String]) -> Vec<String> {
+    let mut noticed = NOTICED.lock_safe();
+    noticed.retain(|id| !present.contains(id));
+    missing
+        .iter()
+        .filter(|id| noticed.insert((*id).clone()))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn notices_a_device_once_until_it_is_back() {
+        // NOTICED is global, so the ids are unique to this test
+        let missing = ids(&["noticed-a", "noticed-b"]);
+        assert_eq!(newly_missing(&[], &missing), missing);
+        assert!(newly_missing(&[], &missing).is_empty());
+
+        // noticed-a came back and went missing again
+        assert!(newly_missing(&ids(&["noticed-a"]), &ids(&["noticed-b"])).is_empty());
+        assert_eq!(newly_missing(&[], &missing), ids(&["noticed-a"]));
+    }
+}
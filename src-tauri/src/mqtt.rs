@@ -0,0 +1,337 @@
+//! Optional MQTT integration for home automation (e.g. Home Assistant).
+//!
+//! Topics, relative to the configured base topic:
+//!   <base>/state         "muted" | "unmuted" (retained, published on every transition)
+//!   <base>/set           "mute" | "unmute" | "toggle" (subscribed)
+//!   <base>/availability  "online" | "offline" (retained, "offline" is the last will)
+//!
+//! Home Assistant discovery configs for a binary sensor and a switch are published on
+//! every (re)connect so the entities appear automatically.
+
+use crate::commands::external::ExternalAction;
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// Store key of this install's client id suffix, see `client_suffix`.
+const CLIENT_SUFFIX_KEY: &str = "mqttClientSuffix";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttSettings {
+    /// e.g. `mqtt://broker.local:1883` or `mqtts://broker.example.com:8883`
+    pub broker_url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_base_topic")]
+    pub base_topic: String,
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+fn default_base_topic() -> String {
+    "togmic".to_string()
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+struct Connection {
+    client: Client,
+    base_topic: String,
+    stop: Arc<AtomicBool>,
+}
+
+static CONNECTION: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+/// Last published state, re-sent after every reconnect.
+static LAST_STATE: AtomicBool = AtomicBool::new(false);
+
+fn state_payload(muted: bool) -> &'static str {
+    if muted {
+        "muted"
+    } else {
+        "unmuted"
+    }
+}
+
+/// `base_topic` as used in discovery ids.
+fn node_id(base_topic: &str) -> String {
+    base_topic
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// The broker disconnects a client when another one connects with the same id, so the id
+/// carries a per-install suffix besides the base topic every install shares by default.
+fn client_id(node_id: &str, suffix: &str) -> String {
+    format!("togmic-{}-{}", node_id, suffix)
+}
+
+fn generate_suffix() -> String {
+    let bytes: [u8; 4] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// This install's client id suffix, created and saved to the config on first use.
+fn client_suffix(app: &AppHandle) -> String {
+    let store = match app.store(crate::config_store(app)) {
+        Ok(store) => store,
+        Err(e) => {
+            log::warn!("[mqtt] Failed to open config: {}", e);
+            return generate_suffix();
+        }
+    };
+    if let Some(suffix) = store
+        .get(CLIENT_SUFFIX_KEY)
+        .and_then(|v| v.as_str().map(str::to_string))
+    {
+        return suffix;
+    }
+
+    let suffix = generate_suffix();
+    store.set(CLIENT_SUFFIX_KEY, json!(suffix));
+    if let Err(e) = crate::config_recovery::guard_save(false)
+        .and_then(|()| store.save().map_err(|e| e.to_string()))
+    {
+        log::warn!("[mqtt] Failed to save the client id suffix: {}", e);
+    }
+    suffix
+}
+
+/// Connect to the broker described by `settings`, replacing any existing connection.
+/// Connecting and reconnecting happen on a background thread; this only fails if the
+/// settings themselves are invalid.
+pub fn start(app: AppHandle, settings: MqttSettings, muted: bool) -> Result<(), String> {
+    stop();
+    LAST_STATE.store(muted, Ordering::SeqCst);
+
+    let base_topic = settings.base_topic.trim_end_matches('/').to_string();
+    if base_topic.is_empty() {
+        return Err("MQTT base topic cannot be empty".to_string());
+    }
+    let node_id = node_id(&base_topic);
+
+    // rumqttc requires the client id as part of the URL
+    let separator = if settings.broker_url.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    let url = format!(
+        "{}{}client_id={}",
+        settings.broker_url,
+        separator,
+        client_id(&node_id, &client_suffix(&app))
+    );
+    let mut options =
+        MqttOptions::parse_url(url).map_err(|e| format!("Invalid MQTT broker URL: {}", e))?;
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        format!("{}/availability", base_topic),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = settings.username.clone() {
+        options.set_credentials(username, settings.password.clone().unwrap_or_default());
+    }
+
+    let (client, mut connection) = Client::new(options, 16);
+    let stop = Arc::new(AtomicBool::new(false));
+
+    *CONNECTION.lock_safe() = Some(Connection {
+        client: client.clone(),
+        base_topic: base_topic.clone(),
+        stop: stop.clone(),
+    });
+
+    let version = app.package_info().version.to_string();
+    std::thread::spawn(move || {
+        let command_topic = format!("{}/set", base_topic);
+        let mut backoff = 1;
+
+        for notification in connection.iter() {
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    backoff = 1;
+                    let _ = client.try_subscribe(command_topic.as_str(), QoS::AtLeastOnce);
+                    publish_discovery(&client, &settings, &base_topic, &node_id, &version);
+                    let _ = client.try_publish(
+                        format!("{}/availability", base_topic),
+                        QoS::AtLeastOnce,
+                        true,
+                        "online",
+                    );
+                    let _ = client.try_publish(
+                        format!("{}/state", base_topic),
+                        QoS::AtLeastOnce,
+                        true,
+                        state_payload(LAST_STATE.load(Ordering::SeqCst)),
+                    );
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == command_topic => {
+                    handle_command(&app, &publish.payload);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    // The next iteration reconnects; wait first so a dead broker doesn't spin
+                    log::warn!("[mqtt] Connection error, retrying in {}s: {}", backoff, e);
+                    for _ in 0..backoff {
+                        if stop.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        std::thread::sleep(Duration::from_secs(1));
+                    }
+                    backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Announce ourselves offline and disconnect. A no-op if not connected.
+pub fn stop() {
+    if let Some(connection) = CONNECTION.lock_safe().take() {
+        connection.stop.store(true, Ordering::SeqCst);
+        let _ = connection.client.try_publish(
+            format!("{}/availability", connection.base_topic),
+            QoS::AtLeastOnce,
+            true,
+            "offline",
+        );
+        let _ = connection.client.try_disconnect();
+    }
+}
+
+/// Publish a retained state message. Never blocks: if the client's queue is full or the
+/// broker is unreachable the message is dropped and the state is re-sent on reconnect.
+pub fn publish_state(muted: bool) {
+    LAST_STATE.store(muted, Ordering::SeqCst);
+    if let Some(connection) = CONNECTION.lock_safe().as_ref() {
+        let _ = connection.client.try_publish(
+            format!("{}/state", connection.base_topic),
+            QoS::AtLeastOnce,
+            true,
+            state_payload(muted),
+        );
+    }
+}
+
+fn handle_command(app: &AppHandle, payload: &[u8]) {
+    let action = match std::str::from_utf8(payload).map(|p| p.trim().to_ascii_lowercase()) {
+        Ok(p) if p == "toggle" => ExternalAction::Toggle,
+        Ok(p) if p == "mute" => ExternalAction::SetMute {
+            muted: true,
+            sound: false,
+        },
+        Ok(p) if p == "unmute" => ExternalAction::SetMute {
+            muted: false,
+            sound: false,
+        },
+        _ => {
+            log::warn!("[mqtt] Ignoring unknown command payload");
+            return;
+        }
+    };
+
+    if let Err(e) = crate::perform_external_action(app, "mqtt", &action) {
+        app.state::<AppState>().record_error("mqtt", e);
+    }
+}
+
+fn publish_discovery(
+    client: &Client,
+    settings: &MqttSettings,
+    base_topic: &str,
+    node_id: &str,
+    version: &str,
+) {
+    let device = json!({
+        "identifiers": [format!("togmic_{}", node_id)],
+        "name": "TogMic",
+        "manufacturer": "DaBorsten",
+        "sw_version": version,
+    });
+    let availability_topic = format!("{}/availability", base_topic);
+    let state_topic = format!("{}/state", base_topic);
+
+    let sensor = json!({
+        "name": "Microphone muted",
+        "unique_id": format!("togmic_{}_muted", node_id),
+        "state_topic": state_topic,
+        "payload_on": "muted",
+        "payload_off": "unmuted",
+        "availability_topic": availability_topic,
+        "icon": "mdi:microphone-off",
+        "device": device,
+    });
+    let switch = json!({
+        "name": "Microphone mute",
+        "unique_id": format!("togmic_{}_mute", node_id),
+        "state_topic": state_topic,
+        "command_topic": format!("{}/set", base_topic),
+        "payload_on": "mute",
+        "payload_off": "unmute",
+        "state_on": "muted",
+        "state_off": "unmuted",
+        "availability_topic": availability_topic,
+        "icon": "mdi:microphone",
+        "device": device,
+    });
+
+    let prefix = settings.discovery_prefix.trim_end_matches('/');
+    let _ = client.try_publish(
+        format!("{}/binary_sensor/togmic_{}/muted/config", prefix, node_id),
+        QoS::AtLeastOnce,
+        true,
+        sensor.to_string(),
+    );
+    let _ = client.try_publish(
+        format!("{}/switch/togmic_{}/mute/config", prefix, node_id),
+        QoS::AtLeastOnce,
+        true,
+        switch.to_string(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_id_replaces_topic_separators() {
+        assert_eq!(node_id("togmic"), "togmic");
+        assert_eq!(node_id("home/office-pc"), "home_office_pc");
+    }
+
+    #[test]
+    fn client_ids_differ_between_installs() {
+        let a = generate_suffix();
+        let b = generate_suffix();
+        assert_eq!(a.len(), 8);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(client_id("togmic", &a), client_id("togmic", &b));
+        assert_eq!(client_id("togmic", "0a1b2c3d"), "togmic-togmic-0a1b2c3d");
+    }
+}
@@ -0,0 +1,130 @@
+//! Keeping a profile muted when other applications unmute its devices.
+//!
+//! With `HotkeyProfile::mute_lock` on and TogMic's state muted, an unmute observed by the
+//! device notifications or the polling loop is a violation instead of a change to adopt:
+//! `enforce` mutes the offending devices again through the audio worker and emits
+//! `mute-lock-enforced { deviceId }` for each. An application that unmutes right back
+//! would keep both sides busy, so after `MAX_ENFORCEMENTS` within `WINDOW` the lock backs
+//! off for `BACKOFF`, adopting what it observes meanwhile, and tells the user. The flag and
+//! the mute state are read on every observation, so turning the lock off or unmuting
+//! through TogMic ends enforcement at once.
+
+use crate::events::{self, EventKind};
+use crate::mute_state::{MuteState, ProfileMuteState};
+use crate::{AppHandle, AppState, HotkeyProfile, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri_plugin_notification::NotificationExt;
+
+pub const MUTE_LOCK_ENFORCED: &str = "mute-lock-enforced";
+
+const MAX_ENFORCEMENTS: usize = 5;
+const WINDOW: Duration = Duration::from_secs(10);
+const BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Limiter {
+    recent: VecDeque<Instant>,
+    backoff_until: Option<Instant>,
+}
+
+static LIMITER: Lazy<Mutex<Limiter>> = Lazy::new(|| Mutex::new(Limiter::default()));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MuteLockEnforcedEvent<'a> {
+    device_id: &'a str,
+}
+
+/// Mute the devices `observed` unmuted if the active profile's mute lock applies. Returns
+/// whether it did, in which case the observation must not be adopted.
+pub fn enforce(app: &AppHandle, state: &AppState, observed: &ProfileMuteState) -> bool {
+    let profile = state.current_profile.lock_safe().clone();
+    let Some(profile) = profile.filter(|p| p.mute_lock) else {
+        return false;
+    };
+    if state.mute_state.get() != MuteState::Muted {
+        return false;
+    }
+    let offending: Vec<String> = observed
+        .devices
+        .iter()
+        .filter(|d| !d.muted)
+        .map(|d| d.device_id.clone())
+        .collect();
+    if offending.is_empty() || !allow_enforcement(app) {
+        return false;
+    }
+
+    log::info!("[mute-lock] muting {} again", offending.join(", "));
+    for device_id in &offending {
+        events::emit(
+            app,
+            EventKind::Mute,
+            MUTE_LOCK_ENFORCED,
+            MuteLockEnforcedEvent { device_id },
+        );
+    }
+    let _ = crate::apply_mute(
+        app,
+        state,
+        HotkeyProfile {
+            device_ids: offending,
+            device_states: Vec::new(),
+            ..profile
+        },
+        true,
+        MuteState::Muted,
+    );
+    true
+}
+
+/// Count an enforcement against the rate limit, or refuse it while backing off.
+fn allow_enforcement(app: &AppHandle) -> bool {
+    let now = Instant::now();
+    let mut limiter = LIMITER.lock_safe();
+    if let Some(until) = limiter.backoff_until {
+        if now < until {
+            return false;
+        }
+        limiter.backoff_until = None;
+        limiter.recent.clear();
+    }
+
+    while limiter
+        .recent
+        .front()
+        .is_some_and(|t| now.duration_since(*t) >= WINDOW)
+    {
+        limiter.recent.pop_front();
+    }
+    if limiter.recent.len() >= MAX_ENFORCEMENTS {
+        limiter.backoff_until = Some(now + BACKOFF);
+        drop(limiter);
+        back_off(app);
+        return false;
+    }
+    limiter.recent.push_back(now);
+    true
+}
+
+fn back_off(app: &AppHandle) {
+    log::warn!(
+        "[mute-lock] {} enforcements within {} s, pausing for {} s",
+        MAX_ENFORCEMENTS,
+        WINDOW.as_secs(),
+        BACKOFF.as_secs()
+    );
+    if crate::focus_assist::suppressed() {
+        return;
+    }
+    let _ = app
+        .notification()
+        .builder()
+        .title("TogMic")
+        .body(crate::i18n::translate("notifyMuteLockBackoff"))
+        .show();
+}
@@ -0,0 +1,73 @@
+//! Desktop notifications for mute changes (`notifyMuteChanges`).
+//!
+//! Changes in quick succession are coalesced: a notification waits `COOLDOWN` and is only
+//! shown if no newer change came in meanwhile and the state differs from the one shown
+//! last. Notifications go through the notification plugin, i.e. the freedesktop
+//! notification spec on Linux; without a session bus (headless, CI) they are skipped.
+
+use crate::mute_state::MuteState;
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri_plugin_notification::NotificationExt;
+
+const COOLDOWN: Duration = Duration::from_millis(1500);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Bumped by every change; a waiting notification is dropped once it no longer matches.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static SHOWN: Lazy<Mutex<Option<MuteState>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn configure(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// Note a published mute state.
+pub fn notify(app: &AppHandle, mute_state: MuteState) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if !ENABLED.load(Ordering::SeqCst) {
+        // Keep the baseline so enabling doesn't notify a stale change
+        *SHOWN.lock_safe() = Some(mute_state);
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(COOLDOWN);
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        // The first state (at startup) is only the baseline
+        match SHOWN.lock_safe().replace(mute_state) {
+            Some(shown) if shown != mute_state => {}
+            _ => return,
+        }
+        show(&app, mute_state);
+    });
+}
+
+fn show(app: &AppHandle, mute_state: MuteState) {
+    if crate::focus_assist::suppressed() {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_none() {
+        log::debug!("[notify] no session bus, skipping the notification");
+        return;
+    }
+    let key = match mute_state {
+        MuteState::Muted => "notifyMuted",
+        MuteState::Unmuted => "notifyUnmuted",
+        MuteState::Mixed => "notifyMixed",
+    };
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("TogMic")
+        .body(crate::i18n::translate(key))
+        .show()
+    {
+        log::debug!("[notify] failed to show the notification: {}", e);
+    }
+}
@@ -0,0 +1,186 @@
+//! Tri-state mute state of the active profile.
+//!
+//! A profile can cover several microphones, and they don't always agree (headset muted,
+//! webcam mic live). `Mixed` reports that instead of collapsing it to muted or unmuted.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MuteState {
+    Muted,
+    Unmuted,
+    /// Some of the profile's devices are muted, others aren't.
+    Mixed,
+}
+
+impl MuteState {
+    /// Combine per-device mute states. `None` if there are no devices to go by.
+    pub fn from_devices(devices: impl IntoIterator<Item = bool>) -> Option<Self> {
+        let (mut any_muted, mut any_unmuted) = (false, false);
+        for muted in devices {
+            if muted {
+                any_muted = true;
+            } else {
+                any_unmuted = true;
+            }
+        }
+        match (any_muted, any_unmuted) {
+            (true, false) => Some(MuteState::Muted),
+            (false, true) => Some(MuteState::Unmuted),
+            (true, true) => Some(MuteState::Mixed),
+            (false, false) => None,
+        }
+    }
+
+    /// Whether the profile counts as muted. `Mixed` does not: at least one mic is live.
+    pub fn is_muted(self) -> bool {
+        self == MuteState::Muted
+    }
+}
+
+impl From<bool> for MuteState {
+    fn from(muted: bool) -> Self {
+        if muted {
+            MuteState::Muted
+        } else {
+            MuteState::Unmuted
+        }
+    }
+}
+
+/// Per-device entry of the breakdown sent with `mute-state-changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMuteState {
+    pub device_id: String,
+    pub muted: bool,
+}
+
+/// What `toggle_mute` toggles. Written `profile`, `all` or `device:<id>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ToggleScope {
+    /// The active profile's devices.
+    #[default]
+    Profile,
+    /// Every input device, going where the profile's toggle would.
+    All,
+    Device(String),
+}
+
+impl TryFrom<String> for ToggleScope {
+    type Error = String;
+
+    fn try_from(scope: String) -> Result<Self, Self::Error> {
+        match scope.as_str() {
+            "profile" => Ok(ToggleScope::Profile),
+            "all" => Ok(ToggleScope::All),
+            _ => scope
+                .strip_prefix("device:")
+                .filter(|id| !id.is_empty())
+                .map(|id| ToggleScope::Device(id.to_string()))
+                .ok_or_else(|| format!("Unknown toggle scope '{}'", scope)),
+        }
+    }
+}
+
+impl From<ToggleScope> for String {
+    fn from(scope: ToggleScope) -> Self {
+        match scope {
+            ToggleScope::Profile => "profile".to_string(),
+            ToggleScope::All => "all".to_string(),
+            ToggleScope::Device(id) => format!("device:{}", id),
+        }
+    }
+}
+
+/// Result of querying every device of a profile.
+#[derive(Debug, Clone)]
+pub struct ProfileMuteState {
+    pub state: MuteState,
+    pub devices: Vec<DeviceMuteState>,
+}
+
+/// Lock-free cell holding a `MuteState`, shared between commands, hotkeys and the sync
+/// threads.
+#[derive(Debug)]
+pub struct AtomicMuteState(AtomicU8);
+
+impl AtomicMuteState {
+    pub fn new(state: MuteState) -> Self {
+        Self(AtomicU8::new(Self::encode(state)))
+    }
+
+    pub fn get(&self) -> MuteState {
+        Self::decode(self.0.load(Ordering::SeqCst))
+    }
+
+    pub fn set(&self, state: MuteState) {
+        self.0.store(Self::encode(state), Ordering::SeqCst);
+    }
+
+    /// Store `state` and return the previous one.
+    pub fn swap(&self, state: MuteState) -> MuteState {
+        Self::decode(self.0.swap(Self::encode(state), Ordering::SeqCst))
+    }
+
+    /// Shorthand for `get().is_muted()`.
+    pub fn is_muted(&self) -> bool {
+        self.get().is_muted()
+    }
+
+    fn encode(state: MuteState) -> u8 {
+        match state {
+            MuteState::Unmuted => 0,
+            MuteState::Muted => 1,
+            MuteState::Mixed => 2,
+        }
+    }
+
+    fn decode(value: u8) -> MuteState {
+        match value {
+            1 => MuteState::Muted,
+            2 => MuteState::Mixed,
+            _ => MuteState::Unmuted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn toggle_scopes_round_trip() {
+        for (text, scope) in [
+            ("profile", ToggleScope::Profile),
+            ("all", ToggleScope::All),
+            (
+                "device:{0.0.1}.{abc}",
+                ToggleScope::Device("{0.0.1}.{abc}".to_string()),
+            ),
+        ] {
+            assert_eq!(
+                serde_json::from_value::<ToggleScope>(json!(text)).unwrap(),
+                scope
+            );
+            assert_eq!(serde_json::to_value(&scope).unwrap(), json!(text));
+        }
+        assert_eq!(ToggleScope::default(), ToggleScope::Profile);
+    }
+
+    #[test]
+    fn rejects_unknown_toggle_scopes() {
+        for text in ["", "everything", "device:", "Device:abc"] {
+            assert!(serde_json::from_value::<ToggleScope>(json!(text)).is_err());
+        }
+        // Calls without a scope still deserialize
+        assert_eq!(
+            serde_json::from_value::<Option<ToggleScope>>(json!(null)).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Optional OBS integration over the obs-websocket v5 protocol.
+//!
+//! Every TogMic state change is pushed to OBS as `SetInputMute` for the configured input.
+//! With `mirror_from_obs` enabled, `InputMuteStateChanged` events for that input are fed
+//! back through the normal set-mute path. OBS emits that event for our own requests too,
+//! before answering them, so each `SetInputMute` stays pending until its response arrives
+//! and one event with its state is dropped as the echo (`PendingRequests`). Anything else
+//! is a change made in OBS, even if TogMic has moved on since it sent a request; matching
+//! events by state alone would replay a stale echo and ping-pong the two sides.
+
+use crate::commands::external::ExternalAction;
+use crate::mute_state::MuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::sync::{mpsc, watch};
+use tokio_tungstenite::tungstenite::Message;
+
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// obs-websocket event subscription bit for input events (`EventSubscription::Inputs`).
+const EVENT_SUBSCRIPTION_INPUTS: u64 = 1 << 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ObsSettings {
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Name of the OBS audio input to keep in sync, e.g. "Mic/Aux".
+    pub input_name: String,
+    /// Also apply mute changes made in OBS to TogMic.
+    #[serde(default)]
+    pub mirror_from_obs: bool,
+}
+
+fn default_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_port() -> u16 {
+    4455
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionChangedEvent {
+    connected: bool,
+    error: Option<String>,
+}
+
+struct Connection {
+    updates: mpsc::UnboundedSender<bool>,
+    shutdown: watch::Sender<bool>,
+}
+
+static CONNECTION: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+/// Connect to OBS, replacing any existing connection. Runs in the background and keeps
+/// reconnecting with backoff until `stop()` is called.
+pub fn start(app: AppHandle, settings: ObsSettings, muted: bool) -> Result<(), String> {
+    if settings.input_name.trim().is_empty() {
+        return Err("OBS input name cannot be empty".to_string());
+    }
+    stop();
+
+    let (updates_tx, updates_rx) = mpsc::unbounded_channel();
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    *CONNECTION.lock_safe() = Some(Connection {
+        updates: updates_tx,
+        shutdown: shutdown_tx,
+    });
+
+    tauri::async_runtime::spawn(run(app, settings, muted, updates_rx, shutdown_rx));
+    Ok(())
+}
+
+/// Disconnect from OBS. A no-op if not connected.
+pub fn stop() {
+    if let Some(connection) = CONNECTION.lock_safe().take() {
+        let _ = connection.shutdown.send(true);
+    }
+}
+
+/// Queue a mute state for OBS. Never blocks; the latest state is also re-sent after every
+/// reconnect.
+pub fn publish_state(muted: bool) {
+    if let Some(connection) = CONNECTION.lock_safe().as_ref() {
+        let _ = connection.updates.send(muted);
+    }
+}
+
+fn emit_connection(app: &AppHandle, connected: bool, error: Option<String>) {
+    let _ = app.emit(
+        "obs-connection-changed",
+        ConnectionChangedEvent { connected, error },
+    );
+}
+
+async fn run(
+    app: AppHandle,
+    settings: ObsSettings,
+    mut last_state: bool,
+    mut updates: mpsc::UnboundedReceiver<bool>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut backoff = 1;
+
+    loop {
+        let result = session(
+            &app,
+            &settings,
+            &mut last_state,
+            &mut updates,
+            &mut shutdown,
+        )
+        .await;
+
+        if *shutdown.borrow() {
+            emit_connection(&app, false, None);
+            return;
+        }
+
+        let error = match result {
+            // A session that got as far as identifying resets the backoff
+            Ok(()) => {
+                backoff = 1;
+                None
+            }
+            Err(e) => Some(e),
+        };
+        if let Some(e) = &error {
+            log::warn!("[obs] {}, retrying in {}s", e, backoff);
+        }
+        emit_connection(&app, false, error);
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(backoff)) => {}
+            _ = shutdown.changed() => return,
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+    }
+}
+
+/// obs-websocket v5 authentication string:
+/// base64(sha256(base64(sha256(password + salt)) + challenge))
+fn auth_string(password: &str, salt: &str, challenge: &str) -> String {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let secret = engine.encode(Sha256::digest(format!("{}{}", password, salt)));
+    engine.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+/// `SetInputMute` requests of one connection that OBS hasn't answered yet.
+#[derive(Default)]
+struct PendingRequests {
+    next_id: u64,
+    /// Request id and the state it sets, oldest first.
+    pending: VecDeque<(String, bool)>,
+}
+
+impl PendingRequests {
+    fn set_input_mute(&mut self, input_name: &str, muted: bool) -> Message {
+        self.next_id += 1;
+        let request_id = format!("togmic-{}", self.next_id);
+        let request = json!({
+            "op": 6,
+            "d": {
+                "requestType": "SetInputMute",
+                "requestId": request_id,
+                "requestData": { "inputName": input_name, "inputMuted": muted },
+            },
+        });
+        self.pending.push_back((request_id, muted));
+        Message::Text(request.to_string())
+    }
+
+    /// Forget a request once OBS answered it (op 7). OBS sends no event for a request
+    /// that didn't change anything, so this is the only way some requests leave.
+    fn on_response(&mut self, message: &Value) {
+        if message["op"] != 7 {
+            return;
+        }
+        if let Some(id) = message["d"]["requestId"].as_str() {
+            self.pending.retain(|(pending, _)| pending != id);
+        }
+    }
+
+    /// Whether a mute event for `muted` is the echo of a pending request, which it then
+    /// settles.
+    fn take_echo(&mut self, muted: bool) -> bool {
+        match self.pending.iter().position(|(_, state)| *state == muted) {
+            Some(index) => {
+                self.pending.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// One connection: handshake, then forward state changes until the socket drops or we
+/// are told to shut down.
+async fn session(
+    app: &AppHandle,
+    settings: &ObsSettings,
+    last_state: &mut bool,
+    updates: &mut mpsc::UnboundedReceiver<bool>,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<(), String> {
+    let url = format!("ws://{}:{}", settings.host, settings.port);
+    let (ws, _) = tokio_tungstenite::connect_async(url.as_str())
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+    let (mut sink, mut source) = ws.split();
+
+    // Hello (op 0) → Identify (op 1) → Identified (op 2)
+    let hello = next_json(&mut source).await?;
+    let event_subscriptions = if settings.mirror_from_obs {
+        EVENT_SUBSCRIPTION_INPUTS
+    } else {
+        0
+    };
+    let mut identify = json!({
+        "rpcVersion": 1,
+        "eventSubscriptions": event_subscriptions,
+    });
+    if let Some(auth) = hello["d"].get("authentication") {
+        let password = settings
+            .password
+            .as_deref()
+            .ok_or("OBS requires a password")?;
+        let salt = auth["salt"].as_str().unwrap_or_default();
+        let challenge = auth["challenge"].as_str().unwrap_or_default();
+        identify["authentication"] = json!(auth_string(password, salt, challenge));
+    }
+    sink.send(Message::Text(json!({ "op": 1, "d": identify }).to_string()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let identified = next_json(&mut source).await?;
+    if identified["op"] != 2 {
+        return Err("OBS rejected the identification (wrong password?)".to_string());
+    }
+    emit_connection(app, true, None);
+
+    let mut requests = PendingRequests::default();
+    // Bring OBS in line with TogMic right away
+    while let Ok(muted) = updates.try_recv() {
+        *last_state = muted;
+    }
+    sink.send(requests.set_input_mute(&settings.input_name, *last_state))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let Some(muted) = update else { return Ok(()) };
+                *last_state = muted;
+                sink.send(requests.set_input_mute(&settings.input_name, muted))
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            incoming = source.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Err(e)) => return Err(e.to_string()),
+                    Some(Ok(_)) => continue,
+                };
+                handle_message(app, settings, &mut requests, &text);
+            }
+            _ = shutdown.changed() => {
+                let _ = sink.send(Message::Close(None)).await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn next_json<S>(source: &mut S) -> Result<Value, String>
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        match source.next().await {
+            Some(Ok(Message::Text(text))) => {
+                return serde_json::from_str(&text).map_err(|e| e.to_string())
+            }
+            Some(Ok(Message::Close(_))) | None => {
+                return Err("OBS closed the connection".to_string())
+            }
+            Some(Err(e)) => return Err(e.to_string()),
+            Some(Ok(_)) => continue,
+        }
+    }
+}
+
+/// Settle answered requests and mirror changes made in OBS into TogMic.
+fn handle_message(
+    app: &AppHandle,
+    settings: &ObsSettings,
+    requests: &mut PendingRequests,
+    text: &str,
+) {
+    let Ok(message) = serde_json::from_str::<Value>(text) else {
+        return;
+    };
+    requests.on_response(&message);
+    let Some(muted) = input_mute_event(&message, &settings.input_name) else {
+        return;
+    };
+    if requests.take_echo(muted) || !settings.mirror_from_obs {
+        return;
+    }
+    // Nothing to do if TogMic is already there
+    if app.state::<AppState>().mute_state.get() == MuteState::from(muted) {
+        return;
+    }
+
+    let action = ExternalAction::SetMute {
+        muted,
+        sound: false,
+    };
+    if let Err(e) = crate::perform_external_action(app, "obs", &action) {
+        app.state::<AppState>().record_error("obs", e);
+    }
+}
+
+/// The new state from an `InputMuteStateChanged` event for `input_name`.
+fn input_mute_event(message: &Value, input_name: &str) -> Option<bool> {
+    let data = &message["d"];
+    if message["op"] != 5 || data["eventType"] != "InputMuteStateChanged" {
+        return None;
+    }
+    if data["eventData"]["inputName"].as_str() != Some(input_name) {
+        return None;
+    }
+    data["eventData"]["inputMuted"].as_bool()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_id(message: &Message) -> String {
+        let Message::Text(text) = message else {
+            panic!("not a text message");
+        };
+        let request: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(request["d"]["requestType"], "SetInputMute");
+        request["d"]["requestId"].as_str().unwrap().to_string()
+    }
+
+    fn response(request_id: &str) -> Value {
+        json!({ "op": 7, "d": { "requestId": request_id, "requestStatus": { "result": true } } })
+    }
+
+    fn mute_event(input_name: &str, muted: bool) -> Value {
+        json!({
+            "op": 5,
+            "d": {
+                "eventType": "InputMuteStateChanged",
+                "eventData": { "inputName": input_name, "inputMuted": muted },
+            },
+        })
+    }
+
+    #[test]
+    fn request_ids_are_unique() {
+        let mut requests = PendingRequests::default();
+        let first = request_id(&requests.set_input_mute("Mic", true));
+        let second = request_id(&requests.set_input_mute("Mic", true));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn drops_one_echo_per_request() {
+        let mut requests = PendingRequests::default();
+        requests.set_input_mute("Mic", true);
+        assert!(requests.take_echo(true));
+        // A second event with the same state was made in OBS
+        assert!(!requests.take_echo(true));
+    }
+
+    #[test]
+    fn stale_echoes_are_not_mistaken_for_changes() {
+        // TogMic muted and unmuted quickly; OBS reports both
+        let mut requests = PendingRequests::default();
+        requests.set_input_mute("Mic", true);
+        requests.set_input_mute("Mic", false);
+        assert!(requests.take_echo(true));
+        assert!(requests.take_echo(false));
+        assert!(!requests.take_echo(false));
+    }
+
+    #[test]
+    fn answered_requests_have_no_echo() {
+        // OBS answers without an event when the input already had the state
+        let mut requests = PendingRequests::default();
+        let id = request_id(&requests.set_input_mute("Mic", true));
+        requests.on_response(&mute_event("Mic", false));
+        requests.on_response(&response("someone-else"));
+        assert_eq!(requests.pending.len(), 1);
+        requests.on_response(&response(&id));
+        assert!(!requests.take_echo(true));
+    }
+
+    #[test]
+    fn reads_mute_events_for_our_input_only() {
+        assert_eq!(
+            input_mute_event(&mute_event("Mic", true), "Mic"),
+            Some(true)
+        );
+        assert_eq!(input_mute_event(&mute_event("Desktop", true), "Mic"), None);
+        assert_eq!(input_mute_event(&response("togmic-1"), "Mic"), None);
+        let mut other = mute_event("Mic", true);
+        other["d"]["eventType"] = json!("InputVolumeChanged");
+        assert_eq!(input_mute_event(&other, "Mic"), None);
+    }
+}
@@ -0,0 +1,525 @@
+//! Keeping the mute state in sync with TogMic on other machines in the LAN.
+//!
+//! Off unless `AppSettings::peers` is set. Peers are the configured `host:port` entries
+//! plus, with `discover`, every TogMic announcing `_togmic-sync._tcp` over mDNS. Each
+//! local mute change is sent to all of them as one line over a short TCP connection:
+//!
+//!   <hex HMAC-SHA256 of the body with the shared secret> {"origin":..,"seq":..,"muted":..,"at":..}
+//!
+//! A peer applies a message through the normal external set-mute path with source
+//! `peer`, and changes from that source are never sent on, so a change doesn't echo
+//! back. Messages from an origin are only accepted with a higher `seq` than the last one,
+//! and within `MAX_CLOCK_SKEW_MS` of the local clock, which rejects replays.
+//!
+//! When two machines change at the same time, muting wins: an unmute arriving within
+//! `CONFLICT_WINDOW` of a local mute is dropped, while a mute is always applied.
+
+use crate::commands::external::ExternalAction;
+use crate::{AppHandle, AppState, MutexExt};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const SERVICE_TYPE: &str = "_togmic-sync._tcp.local.";
+const CONFLICT_WINDOW: Duration = Duration::from_secs(1);
+const MAX_CLOCK_SKEW_MS: u64 = 30_000;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// `source` of changes received from a peer.
+pub const SOURCE_PEER: &str = "peer";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerSettings {
+    /// TCP port to listen on; peers without a port are reached on the same one.
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Shared by all machines; messages signed with another secret are dropped.
+    pub secret: String,
+    /// `host` or `host:port` of peers to sync with in addition to discovered ones.
+    #[serde(default)]
+    pub peers: Vec<String>,
+    /// Find peers over mDNS.
+    #[serde(default = "default_discover")]
+    pub discover: bool,
+}
+
+fn default_port() -> u16 {
+    47821
+}
+
+fn default_discover() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Message {
+    /// Random ID of the sending instance, new on every start.
+    origin: String,
+    seq: u64,
+    muted: bool,
+    /// Milliseconds since the Unix epoch.
+    at: u64,
+}
+
+/// Signing, sequencing and conflict resolution, without any networking.
+pub struct Engine {
+    origin: String,
+    secret: Vec<u8>,
+    seq: u64,
+    /// Highest `seq` seen per origin.
+    last_seen: HashMap<String, u64>,
+    /// The last local change and when it was made.
+    last_local: Option<(bool, Instant)>,
+}
+
+impl Engine {
+    pub fn new(origin: String, secret: &str) -> Self {
+        Self {
+            origin,
+            secret: secret.as_bytes().to_vec(),
+            seq: 0,
+            last_seen: HashMap::new(),
+            last_local: None,
+        }
+    }
+
+    /// The line announcing a local change to `muted`.
+    pub fn outgoing(&mut self, muted: bool) -> String {
+        self.seq += 1;
+        self.last_local = Some((muted, Instant::now()));
+        let body = serde_json::to_string(&Message {
+            origin: self.origin.clone(),
+            seq: self.seq,
+            muted,
+            at: now_ms(),
+        })
+        .unwrap_or_default();
+        format!("{} {}\n", hex(&hmac(&self.secret, body.as_bytes())), body)
+    }
+
+    /// Check a received line. `Ok(Some(muted))` is to be applied, `Ok(None)` is a valid
+    /// message that loses a conflict.
+    pub fn incoming(&mut self, line: &str) -> Result<Option<bool>, String> {
+        let (mac, body) = line
+            .trim_end()
+            .split_once(' ')
+            .ok_or_else(|| "malformed message".to_string())?;
+        if !constant_time_eq(
+            mac.as_bytes(),
+            hex(&hmac(&self.secret, body.as_bytes())).as_bytes(),
+        ) {
+            return Err("bad signature".to_string());
+        }
+        let message: Message = serde_json::from_str(body).map_err(|e| e.to_string())?;
+        if message.origin == self.origin {
+            return Err("own message".to_string());
+        }
+        if now_ms().abs_diff(message.at) > MAX_CLOCK_SKEW_MS {
+            return Err("stale message".to_string());
+        }
+        let last = self.last_seen.entry(message.origin).or_insert(0);
+        if message.seq <= *last {
+            return Err("replayed message".to_string());
+        }
+        *last = message.seq;
+
+        let conflicting = self
+            .last_local
+            .is_some_and(|(muted, at)| muted && at.elapsed() < CONFLICT_WINDOW);
+        if !message.muted && conflicting {
+            return Ok(None);
+        }
+        Ok(Some(message.muted))
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+struct Running {
+    settings: PeerSettings,
+    engine: Arc<Mutex<Engine>>,
+    outgoing: mpsc::Sender<String>,
+    stop: Arc<AtomicBool>,
+    mdns: Option<ServiceDaemon>,
+}
+
+static RUNNING: Lazy<Mutex<Option<Running>>> = Lazy::new(|| Mutex::new(None));
+/// Peers found over mDNS, by service name.
+static DISCOVERED: Lazy<Mutex<HashMap<String, SocketAddr>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Start syncing, replacing a previous configuration.
+pub fn start(app: AppHandle, settings: PeerSettings) -> Result<(), String> {
+    if settings.secret.trim().is_empty() {
+        return Err("Peer sync needs a shared secret".to_string());
+    }
+    stop();
+
+    // A previous listener on the same port lets go within one accept interval
+    let mut attempts = 0;
+    let listener = loop {
+        match TcpListener::bind(("0.0.0.0", settings.port)) {
+            Ok(listener) => break listener,
+            Err(_) if attempts < 3 => {
+                attempts += 1;
+                std::thread::sleep(ACCEPT_INTERVAL);
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Failed to bind peer sync to port {}: {}",
+                    settings.port, e
+                ))
+            }
+        }
+    };
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure peer sync socket: {}", e))?;
+
+    let origin = crate::api::generate_token();
+    let engine = Arc::new(Mutex::new(Engine::new(origin.clone(), &settings.secret)));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let mdns = if settings.discover {
+        match announce(&origin, settings.port) {
+            Ok(daemon) => Some(daemon),
+            Err(e) => {
+                log::warn!("[peers] mDNS discovery unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    {
+        let engine = engine.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || listen(app, listener, engine, stop));
+    }
+    let (tx, rx) = mpsc::channel::<String>();
+    {
+        let static_peers = settings.peers.clone();
+        let port = settings.port;
+        std::thread::spawn(move || {
+            for line in rx {
+                send_to_all(&static_peers, port, &line);
+            }
+        });
+    }
+
+    log::info!("[peers] syncing on port {}", settings.port);
+    *RUNNING.lock_safe() = Some(Running {
+        settings,
+        engine,
+        outgoing: tx,
+        stop,
+        mdns,
+    });
+    Ok(())
+}
+
+/// Stop syncing. A no-op if not running.
+pub fn stop() {
+    let Some(running) = RUNNING.lock_safe().take() else {
+        return;
+    };
+    running.stop.store(true, Ordering::SeqCst);
+    if let Some(mdns) = running.mdns {
+        let _ = mdns.shutdown();
+    }
+    DISCOVERED.lock_safe().clear();
+    log::info!("[peers] stopped (port {})", running.settings.port);
+}
+
+/// Send a local change to the peers. Never blocks; changes received from peers aren't
+/// sent on.
+pub fn publish_state(muted: bool, source: &str) {
+    if source == SOURCE_PEER {
+        return;
+    }
+    if let Some(running) = RUNNING.lock_safe().as_ref() {
+        let line = running.engine.lock_safe().outgoing(muted);
+        let _ = running.outgoing.send(line);
+    }
+}
+
+/// Announce this instance and collect the others.
+fn announce(origin: &str, port: u16) -> Result<ServiceDaemon, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let host = format!("togmic-{}.local.", origin);
+    let info = ServiceInfo::new(SERVICE_TYPE, origin, &host, "", port, None)
+        .map_err(|e| e.to_string())?
+        .enable_addr_auto();
+    let own = info.get_fullname().to_string();
+    daemon.register(info).map_err(|e| e.to_string())?;
+
+    let events = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    std::thread::spawn(move || {
+        while let Ok(event) = events.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) if info.get_fullname() != own => {
+                    if let Some(ip) = info.get_addresses().iter().next() {
+                        let addr = SocketAddr::new(*ip, info.get_port());
+                        log::info!("[peers] discovered {}", addr);
+                        DISCOVERED
+                            .lock_safe()
+                            .insert(info.get_fullname().to_string(), addr);
+                    }
+                }
+                ServiceEvent::ServiceRemoved(_, name) => {
+                    DISCOVERED.lock_safe().remove(&name);
+                }
+                _ => {}
+            }
+        }
+    });
+    Ok(daemon)
+}
+
+/// `peer` with the default `port` added unless it names one.
+fn with_port(peer: &str, port: u16) -> String {
+    if peer.contains(':') {
+        peer.to_string()
+    } else {
+        format!("{}:{}", peer, port)
+    }
+}
+
+fn send_to_all(static_peers: &[String], port: u16, line: &str) {
+    let mut targets: Vec<SocketAddr> = DISCOVERED.lock_safe().values().copied().collect();
+    for peer in static_peers {
+        match with_port(peer, port).to_socket_addrs() {
+            Ok(mut addrs) => targets.extend(addrs.next()),
+            Err(e) => log::debug!("[peers] can't resolve {}: {}", peer, e),
+        }
+    }
+    targets.sort();
+    targets.dedup();
+
+    for addr in targets {
+        let result = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .and_then(|mut stream| stream.write_all(line.as_bytes()));
+        if let Err(e) = result {
+            log::debug!("[peers] failed to reach {}: {}", addr, e);
+        }
+    }
+}
+
+fn listen(
+    app: AppHandle,
+    listener: TcpListener,
+    engine: Arc<Mutex<Engine>>,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::SeqCst) {
+        let (stream, addr) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_INTERVAL);
+                continue;
+            }
+            Err(e) => {
+                log::warn!("[peers] accept failed: {}", e);
+                std::thread::sleep(ACCEPT_INTERVAL);
+                continue;
+            }
+        };
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+        let mut line = String::new();
+        if BufReader::new(stream).read_line(&mut line).is_err() {
+            continue;
+        }
+
+        let decision = engine.lock_safe().incoming(&line);
+        match decision {
+            Ok(Some(muted)) => {
+                let action = ExternalAction::SetMute {
+                    muted,
+                    sound: false,
+                };
+                if let Err(e) = crate::perform_external_action(&app, SOURCE_PEER, &action) {
+                    app.state::<AppState>().record_error("peer_sync", e);
+                }
+            }
+            Ok(None) => log::info!(
+                "[peers] kept muted over a simultaneous unmute from {}",
+                addr
+            ),
+            Err(e) => log::debug!("[peers] dropped message from {}: {}", addr, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "shared secret";
+
+    fn engines() -> (Engine, Engine) {
+        (
+            Engine::new("desktop".to_string(), SECRET),
+            Engine::new("laptop".to_string(), SECRET),
+        )
+    }
+
+    /// A line as `origin` would send it, with any `seq` and timestamp.
+    fn signed(origin: &str, seq: u64, muted: bool, at: u64) -> String {
+        let body = serde_json::to_string(&Message {
+            origin: origin.to_string(),
+            seq,
+            muted,
+            at,
+        })
+        .unwrap();
+        format!(
+            "{} {}\n",
+            hex(&hmac(SECRET.as_bytes(), body.as_bytes())),
+            body
+        )
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        // Test case 2
+        assert_eq!(
+            hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn applies_changes_from_a_peer() {
+        let (mut desktop, mut laptop) = engines();
+        assert_eq!(laptop.incoming(&desktop.outgoing(true)), Ok(Some(true)));
+        assert_eq!(laptop.incoming(&desktop.outgoing(false)), Ok(Some(false)));
+    }
+
+    #[test]
+    fn rejects_other_secrets_and_garbage() {
+        let (mut desktop, _) = engines();
+        let mut stranger = Engine::new("stranger".to_string(), "other secret");
+        assert_eq!(
+            desktop.incoming(&stranger.outgoing(true)),
+            Err("bad signature".to_string())
+        );
+        assert_eq!(
+            desktop.incoming("no-separator"),
+            Err("malformed message".to_string())
+        );
+
+        let line = signed("laptop", 1, true, now_ms());
+        let tampered = line.replace("\"muted\":true", "\"muted\":false");
+        assert_eq!(
+            desktop.incoming(&tampered),
+            Err("bad signature".to_string())
+        );
+    }
+
+    #[test]
+    fn ignores_its_own_messages() {
+        let (mut desktop, _) = engines();
+        let line = desktop.outgoing(true);
+        assert_eq!(desktop.incoming(&line), Err("own message".to_string()));
+    }
+
+    #[test]
+    fn rejects_replays_and_stale_messages() {
+        let (mut desktop, mut laptop) = engines();
+        let line = desktop.outgoing(true);
+        assert_eq!(laptop.incoming(&line), Ok(Some(true)));
+        assert_eq!(laptop.incoming(&line), Err("replayed message".to_string()));
+
+        // Sequence numbers are tracked per origin
+        assert_eq!(
+            laptop.incoming(&signed("tablet", 1, true, now_ms())),
+            Ok(Some(true))
+        );
+
+        let old = now_ms() - MAX_CLOCK_SKEW_MS - 1_000;
+        assert_eq!(
+            laptop.incoming(&signed("desktop", 10, true, old)),
+            Err("stale message".to_string())
+        );
+        let ahead = now_ms() + MAX_CLOCK_SKEW_MS + 1_000;
+        assert_eq!(
+            laptop.incoming(&signed("desktop", 11, true, ahead)),
+            Err("stale message".to_string())
+        );
+    }
+
+    #[test]
+    fn a_local_mute_wins_over_a_simultaneous_unmute() {
+        let (mut desktop, mut laptop) = engines();
+        laptop.outgoing(true);
+        assert_eq!(laptop.incoming(&desktop.outgoing(false)), Ok(None));
+        assert_eq!(laptop.incoming(&desktop.outgoing(true)), Ok(Some(true)));
+
+        // A local unmute doesn't block anything
+        laptop.outgoing(false);
+        assert_eq!(laptop.incoming(&desktop.outgoing(false)), Ok(Some(false)));
+    }
+
+    #[test]
+    fn an_unmute_after_the_conflict_window_is_applied() {
+        let (mut desktop, mut laptop) = engines();
+        laptop.outgoing(true);
+        laptop.last_local = laptop
+            .last_local
+            .map(|(muted, at)| (muted, at - CONFLICT_WINDOW));
+        assert_eq!(laptop.incoming(&desktop.outgoing(false)), Ok(Some(false)));
+    }
+
+    #[test]
+    fn peers_without_a_port_use_the_default() {
+        assert_eq!(with_port("laptop.local", 47821), "laptop.local:47821");
+        assert_eq!(with_port("10.0.0.2:5000", 47821), "10.0.0.2:5000");
+    }
+}
@@ -0,0 +1,118 @@
+//! Interval schedule for the mute state polling loop (non-Windows platforms).
+//!
+//! The loop polls at the configured base interval. After `IDLE_POLLS_BEFORE_BACKOFF`
+//! consecutive polls without a change the interval doubles, up to `MAX_INTERVAL_MS`, so an
+//! idle machine's audio stack isn't kept awake. Any toggle, profile switch or device change
+//! calls `wake()`, which ends the current wait and snaps back to the base interval.
+
+// Windows gets change notifications instead of a polling loop, so only the settings side
+// of this module is used there.
+#![cfg_attr(target_os = "windows", allow(dead_code))]
+
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+pub const DEFAULT_INTERVAL_MS: u64 = 500;
+const MIN_INTERVAL_MS: u64 = 100;
+const MAX_INTERVAL_MS: u64 = 3000;
+const IDLE_POLLS_BEFORE_BACKOFF: u32 = 10;
+
+/// How often a profile that targets specific devices rescans the device list. Profiles
+/// targeting all devices enumerate on every poll anyway.
+pub const DEVICE_SCAN_EVERY_N_POLLS: u32 = 10;
+
+/// Configured base interval.
+static BASE_INTERVAL_MS: AtomicU64 = AtomicU64::new(DEFAULT_INTERVAL_MS);
+
+/// Interval the loop is currently waiting with; 0 while no polling loop is running.
+static EFFECTIVE_INTERVAL_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Set by `wake()`, cleared by the loop once it has been woken.
+static WAKE: Lazy<(Mutex<bool>, Condvar)> = Lazy::new(|| (Mutex::new(false), Condvar::new()));
+
+/// Change the base interval. Takes effect with the next poll.
+pub fn set_base_interval(ms: u64) {
+    BASE_INTERVAL_MS.store(ms.max(MIN_INTERVAL_MS), Ordering::SeqCst);
+    wake();
+}
+
+/// The interval the polling loop is currently using, or `None` if there is no polling
+/// loop (Windows uses change notifications instead).
+pub fn effective_interval_ms() -> Option<u64> {
+    match EFFECTIVE_INTERVAL_MS.load(Ordering::SeqCst) {
+        0 => None,
+        ms => Some(ms),
+    }
+}
+
+/// Poll again right away and drop back to the base interval.
+pub fn wake() {
+    let (flag, condvar) = &*WAKE;
+    *flag.lock_safe() = true;
+    condvar.notify_all();
+}
+
+/// Per-loop backoff state. Owned by the polling thread.
+pub struct Schedule {
+    idle_polls: u32,
+    interval_ms: u64,
+}
+
+impl Schedule {
+    pub fn start() -> Self {
+        let interval_ms = BASE_INTERVAL_MS.load(Ordering::SeqCst);
+        EFFECTIVE_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+        Self {
+            idle_polls: 0,
+            interval_ms,
+        }
+    }
+
+    /// Sleep until the next poll is due or `wake()` is called.
+    pub fn wait(&mut self) {
+        let (flag, condvar) = &*WAKE;
+        let guard = flag.lock_safe();
+        let (mut woken, _) = condvar
+            .wait_timeout_while(guard, Duration::from_millis(self.interval_ms), |w| !*w)
+            .unwrap_or_else(|e| e.into_inner());
+
+        if *woken {
+            *woken = false;
+            self.reset();
+        }
+    }
+
+    /// Record the outcome of a poll: a change resets the backoff, otherwise the interval
+    /// stretches once the loop has been idle long enough.
+    pub fn record(&mut self, changed: bool) {
+        if changed {
+            self.reset();
+            return;
+        }
+
+        self.idle_polls = self.idle_polls.saturating_add(1);
+        if self.idle_polls >= IDLE_POLLS_BEFORE_BACKOFF {
+            self.interval_ms = (self.interval_ms * 2).min(MAX_INTERVAL_MS.max(self.base()));
+            EFFECTIVE_INTERVAL_MS.store(self.interval_ms, Ordering::SeqCst);
+        }
+    }
+
+    fn base(&self) -> u64 {
+        BASE_INTERVAL_MS.load(Ordering::SeqCst)
+    }
+
+    fn reset(&mut self) {
+        self.idle_polls = 0;
+        self.interval_ms = self.base();
+        EFFECTIVE_INTERVAL_MS.store(self.interval_ms, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Schedule {
+    fn drop(&mut self) {
+        EFFECTIVE_INTERVAL_MS.store(0, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,461 @@
+//! Global shortcuts through the `org.freedesktop.portal.GlobalShortcuts` desktop portal
+//! (Linux, Wayland).
+//!
+//! Wayland compositors don't let applications grab keys, so under a Wayland session
+//! (`is_active`) the shortcuts are bound through the portal instead of the shortcut
+//! plugin. The portal binds a session's shortcuts in one go and may show the compositor's
+//! consent dialog for them, so `bind` only collects the bindings and `commit` binds them
+//! all in a new session, replacing the previous one. Bindings changed without a `commit`
+//! are committed shortly after on their own. `Activated` signals of the current session
+//! run the bound action. `status` reports the outcome for `get_backend_info`.
+
+use crate::shortcuts::ShortcutPurpose;
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+
+#[cfg(target_os = "linux")]
+pub use portal::{bind, commit, is_bound, unbind, unbind_all};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PortalState {
+    /// Not a Wayland session, or nothing to bind.
+    Unused,
+    /// Waiting for the user to answer the compositor's dialog.
+    Pending,
+    Bound,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortalStatus {
+    /// Shortcuts go through the portal instead of the shortcut plugin.
+    pub active: bool,
+    pub state: PortalState,
+    pub error: Option<String>,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+static STATUS: Lazy<Mutex<(PortalState, Option<String>)>> =
+    Lazy::new(|| Mutex::new((PortalState::Unused, None)));
+
+/// Whether this is a Wayland session, where the shortcut plugin can't grab keys.
+pub fn is_active() -> bool {
+    cfg!(target_os = "linux")
+        && std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+}
+
+pub fn status() -> PortalStatus {
+    let (state, error) = STATUS.lock_safe().clone();
+    PortalStatus {
+        active: is_active(),
+        state,
+        error,
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn set_status(state: PortalState, error: Option<String>) {
+    *STATUS.lock_safe() = (state, error);
+}
+
+/// Shortcut id and the description the compositor shows for it.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn describe(purpose: ShortcutPurpose) -> (&'static str, &'static str) {
+    match purpose {
+        ShortcutPurpose::Toggle => ("toggle", "Toggle microphone mute"),
+        ShortcutPurpose::TemporaryUnmute => ("temporary-unmute", "Unmute temporarily"),
+        ShortcutPurpose::Panic => ("panic", "Unmute every microphone"),
+        ShortcutPurpose::Undo => ("undo", "Undo the last mute change"),
+        ShortcutPurpose::ProfileToggle => ("profile-toggle", "Toggle another profile"),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use super::{describe, set_status, PortalState};
+    use crate::hotkey::{HotkeyBinding, KeyRef, Modifier};
+    use crate::shortcuts::ShortcutPurpose;
+    use crate::AppHandle;
+    use crate::{AppState, MutexExt};
+    use once_cell::sync::{Lazy, OnceCell};
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{mpsc, Mutex};
+    use std::time::Duration;
+    use tauri::Manager;
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+    const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+    const PATH: &str = "/org/freedesktop/portal/desktop";
+    const INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+    /// How long `commit` waits for the portal's answer before leaving the dialog to the
+    /// user and reporting `Pending`.
+    const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+    /// Quiet time before bindings changed without a `commit` are committed.
+    const COMMIT_DELAY: Duration = Duration::from_millis(250);
+
+    /// `Response` codes of a portal request.
+    const RESPONSE_SUCCESS: u32 = 0;
+    const RESPONSE_CANCELLED: u32 = 1;
+
+    type Results = HashMap<String, OwnedValue>;
+    /// The body of a request's `Response` signal.
+    type Response = Result<(u32, Results), String>;
+
+    struct Binding {
+        trigger: String,
+        description: &'static str,
+        action: fn(&AppHandle),
+    }
+
+    /// Shortcut id → binding, as the next `commit` binds them.
+    static BOUND: Lazy<Mutex<BTreeMap<&'static str, Binding>>> =
+        Lazy::new(|| Mutex::new(BTreeMap::new()));
+    /// Shortcut ids and triggers the current session was bound with.
+    static COMMITTED: Lazy<Mutex<Vec<(&'static str, String)>>> =
+        Lazy::new(|| Mutex::new(Vec::new()));
+    static SESSION: Lazy<Mutex<Option<OwnedObjectPath>>> = Lazy::new(|| Mutex::new(None));
+    static CONNECTION: OnceCell<Connection> = OnceCell::new();
+    static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+    /// Serializes commits, which replace the session.
+    static COMMIT_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+    /// Bumped by every change; a scheduled commit only runs if it still matches.
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+    static TOKEN: AtomicU64 = AtomicU64::new(0);
+
+    /// Bind `binding` for `purpose` with the next commit.
+    pub fn bind(
+        app: &AppHandle,
+        purpose: ShortcutPurpose,
+        binding: &HotkeyBinding,
+        action: fn(&AppHandle),
+    ) -> Result<(), String> {
+        *APP.lock_safe() = Some(app.clone());
+        let trigger = preferred_trigger(binding)?;
+        let (id, description) = describe(purpose);
+        BOUND.lock_safe().insert(
+            id,
+            Binding {
+                trigger,
+                description,
+                action,
+            },
+        );
+        schedule_commit();
+        Ok(())
+    }
+
+    pub fn unbind(purpose: ShortcutPurpose) {
+        if BOUND.lock_safe().remove(describe(purpose).0).is_some() {
+            schedule_commit();
+        }
+    }
+
+    pub fn unbind_all() {
+        let mut bound = BOUND.lock_safe();
+        if !bound.is_empty() {
+            bound.clear();
+            schedule_commit();
+        }
+    }
+
+    pub fn is_bound(purpose: ShortcutPurpose) -> bool {
+        let id = describe(purpose).0;
+        BOUND.lock_safe().contains_key(id) && COMMITTED.lock_safe().iter().any(|(c, _)| *c == id)
+    }
+
+    /// Bind everything collected so far in a new session, unless the current one already
+    /// has exactly these shortcuts. Errors if the portal is missing or refuses; while the
+    /// user still has to answer the dialog this succeeds with the status `Pending`.
+    pub fn commit() -> Result<(), String> {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        let _guard = COMMIT_LOCK.lock_safe();
+        let wanted: Vec<(&'static str, String)> = BOUND
+            .lock_safe()
+            .iter()
+            .map(|(id, binding)| (*id, binding.trigger.clone()))
+            .collect();
+        if *COMMITTED.lock_safe() == wanted {
+            return Ok(());
+        }
+
+        close_session();
+        COMMITTED.lock_safe().clear();
+        if wanted.is_empty() {
+            set_status(PortalState::Unused, None);
+            return Ok(());
+        }
+        match bind_session() {
+            Ok(()) => {
+                *COMMITTED.lock_safe() = wanted;
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("[portal] {}", e);
+                set_status(PortalState::Failed, Some(e.clone()));
+                Err(e)
+            }
+        }
+    }
+
+    fn schedule_commit() {
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        std::thread::spawn(move || {
+            std::thread::sleep(COMMIT_DELAY);
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Err(e) = commit() {
+                if let Some(app) = APP.lock_safe().clone() {
+                    app.state::<AppState>().record_error("portal_shortcuts", e);
+                }
+            }
+        });
+    }
+
+    fn bind_session() -> Result<(), String> {
+        let connection = connection()?;
+        let portal = portal(connection)?;
+
+        let token = next_token();
+        let session_token = next_token();
+        let options: HashMap<&str, Value> = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("session_handle_token", Value::from(session_token.as_str())),
+        ]);
+        let responses = request(connection, &token, || {
+            portal.call_method("CreateSession", &(options,)).map(|_| ())
+        })?;
+        let results = match responses.recv_timeout(RESPONSE_TIMEOUT) {
+            Ok(response) => check_response(response?, "create a shortcut session")?,
+            Err(_) => return Err("The desktop portal didn't create a shortcut session".to_string()),
+        };
+        let session = results
+            .get("session_handle")
+            .and_then(|handle| match &**handle {
+                Value::Str(path) => OwnedObjectPath::try_from(path.to_string()).ok(),
+                Value::ObjectPath(path) => Some(path.clone().into()),
+                _ => None,
+            })
+            .ok_or_else(|| "The desktop portal returned no session".to_string())?;
+        *SESSION.lock_safe() = Some(session.clone());
+
+        let shortcuts: Vec<(String, HashMap<&str, Value>)> = BOUND
+            .lock_safe()
+            .iter()
+            .map(|(id, binding)| {
+                let properties = HashMap::from([
+                    ("description", Value::from(binding.description)),
+                    ("preferred_trigger", Value::from(binding.trigger.clone())),
+                ]);
+                (id.to_string(), properties)
+            })
+            .collect();
+        let token = next_token();
+        let options: HashMap<&str, Value> =
+            HashMap::from([("handle_token", Value::from(token.as_str()))]);
+        let responses = request(connection, &token, || {
+            portal
+                .call_method("BindShortcuts", &(session, shortcuts, "", options))
+                .map(|_| ())
+        })?;
+        match responses.recv_timeout(RESPONSE_TIMEOUT) {
+            Ok(response) => {
+                check_response(response?, "bind the shortcuts")?;
+                log::info!("[portal] shortcuts bound");
+                set_status(PortalState::Bound, None);
+            }
+            Err(_) => {
+                // The compositor is asking the user; the answer arrives later
+                log::info!("[portal] waiting for the user to confirm the shortcuts");
+                set_status(PortalState::Pending, None);
+                std::thread::spawn(move || {
+                    let outcome = responses
+                        .recv()
+                        .map_err(|_| "The desktop portal didn't answer".to_string())
+                        .and_then(|response| response)
+                        .and_then(|response| check_response(response, "bind the shortcuts"));
+                    match outcome {
+                        Ok(_) => set_status(PortalState::Bound, None),
+                        Err(e) => {
+                            log::warn!("[portal] {}", e);
+                            set_status(PortalState::Failed, Some(e));
+                        }
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Subscribe to the `Response` of the request `token` names, then send it with
+    /// `call`. The response arrives on the returned receiver.
+    fn request(
+        connection: &Connection,
+        token: &str,
+        call: impl FnOnce() -> zbus::Result<()>,
+    ) -> Result<mpsc::Receiver<Response>, String> {
+        let sender = connection
+            .unique_name()
+            .ok_or_else(|| "Not connected to the session bus".to_string())?
+            .as_str()
+            .trim_start_matches(':')
+            .replace('.', "_");
+        let path = format!("{}/request/{}/{}", PATH, sender, token);
+        let mut responses = Proxy::new(
+            connection,
+            DESTINATION,
+            path,
+            "org.freedesktop.portal.Request",
+        )
+        .and_then(|request| request.receive_signal("Response"))
+        .map_err(|e| format!("Failed to watch the portal request: {}", e))?;
+        call().map_err(|e| format!("Desktop portal call failed: {}", e))?;
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Some(message) = responses.next() {
+                let _ = tx.send(
+                    message
+                        .body()
+                        .deserialize::<(u32, Results)>()
+                        .map_err(|e| format!("Unexpected portal response: {}", e)),
+                );
+            }
+        });
+        Ok(rx)
+    }
+
+    fn check_response((code, results): (u32, Results), what: &str) -> Result<Results, String> {
+        match code {
+            RESPONSE_SUCCESS => Ok(results),
+            RESPONSE_CANCELLED => Err(format!("The request to {} was declined", what)),
+            _ => Err(format!("The desktop portal failed to {}", what)),
+        }
+    }
+
+    fn close_session() {
+        let Some(session) = SESSION.lock_safe().take() else {
+            return;
+        };
+        let Some(connection) = CONNECTION.get() else {
+            return;
+        };
+        let closed = Proxy::new(
+            connection,
+            DESTINATION,
+            session,
+            "org.freedesktop.portal.Session",
+        )
+        .and_then(|proxy| proxy.call_method("Close", &()).map(|_| ()));
+        if let Err(e) = closed {
+            log::debug!("[portal] failed to close the session: {}", e);
+        }
+    }
+
+    /// The session bus connection, with a thread listening for `Activated`.
+    fn connection() -> Result<&'static Connection, String> {
+        CONNECTION.get_or_try_init(|| {
+            let connection = Connection::session().map_err(|e| format!("No session bus: {}", e))?;
+            let activations = portal(&connection)?
+                .receive_signal("Activated")
+                .map_err(|e| format!("Failed to listen for shortcuts: {}", e))?;
+            std::thread::spawn(move || {
+                for message in activations {
+                    let Ok((session, id, _timestamp, _options)) =
+                        message
+                            .body()
+                            .deserialize::<(OwnedObjectPath, String, u64, Results)>()
+                    else {
+                        continue;
+                    };
+                    if SESSION.lock_safe().as_ref() != Some(&session) {
+                        continue;
+                    }
+                    let action = BOUND.lock_safe().get(id.as_str()).map(|b| b.action);
+                    let app = APP.lock_safe().clone();
+                    if let (Some(action), Some(app)) = (action, app) {
+                        action(&app);
+                    }
+                }
+            });
+            Ok(connection)
+        })
+    }
+
+    fn portal(connection: &Connection) -> Result<Proxy<'static>, String> {
+        let portal = Proxy::new(connection, DESTINATION, PATH, INTERFACE)
+            .map_err(|e| format!("Failed to reach the desktop portal: {}", e))?;
+        // Fails if the portal has no global shortcuts backend
+        portal
+            .get_property::<u32>("version")
+            .map_err(|e| format!("The desktop portal doesn't support global shortcuts: {}", e))?;
+        Ok(portal)
+    }
+
+    fn next_token() -> String {
+        format!("togmic_{}", TOKEN.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// `binding` in the XDG shortcut format (`CTRL+SHIFT+m`).
+    fn preferred_trigger(binding: &HotkeyBinding) -> Result<String, String> {
+        let KeyRef::Named(key) = &binding.key else {
+            return Err(format!(
+                "'{}' can't be bound through the desktop portal",
+                binding
+            ));
+        };
+        let mut parts: Vec<String> = binding
+            .modifiers
+            .iter()
+            .map(|modifier| {
+                match modifier {
+                    Modifier::CommandOrControl => "CTRL",
+                    Modifier::Shift => "SHIFT",
+                    Modifier::Alt => "ALT",
+                    Modifier::Super => "LOGO",
+                }
+                .to_string()
+            })
+            .collect();
+        // Keysym names: letters in lower case, the named keys as xkb spells them
+        let key = match key.as_str() {
+            "Space" => "space".to_string(),
+            key if key.len() == 1 => key.to_ascii_lowercase(),
+            key => key.to_string(),
+        };
+        parts.push(key);
+        Ok(parts.join("+"))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn bind(
+    _app: &crate::AppHandle,
+    _purpose: ShortcutPurpose,
+    _binding: &crate::hotkey::HotkeyBinding,
+    _action: fn(&crate::AppHandle),
+) -> Result<(), String> {
+    Err("The desktop portal is only used on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn unbind(_purpose: ShortcutPurpose) {}
+
+#[cfg(not(target_os = "linux"))]
+pub fn unbind_all() {}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_bound(_purpose: ShortcutPurpose) -> bool {
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn commit() -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,665 @@
+//! Reconciling mute changes made outside TogMic with the profile's state.
+//!
+//! The device notifications (Windows) and the polling loop hand every observed profile
+//! state to `observe`. A device whose observed state differs from the last known one
+//! changed externally, and `ExternalChangePolicy` decides what happens:
+//!
+//! - `adopt` takes the observed aggregate as the profile state, as before.
+//! - `sync-to-change` applies the changed devices' new state to the profile's other
+//!   devices. If the changed devices disagree it falls back to adopting.
+//! - `ignore` keeps TogMic's state and only records the devices, so a partial external
+//!   change shows up as `observed: "mixed"` in the event.
+//!
+//! Each external change emits `state-reconciled` describing what was done. While the audio
+//! worker is still applying TogMic's own change, the devices are expected to disagree with
+//! the optimistic state, so observations are adopted without an event, or skipped with the
+//! other policies.
+
+use crate::events::{self, EventKind};
+use crate::mute_state::{DeviceMuteState, MuteState, ProfileMuteState};
+use crate::{AppHandle, AppState, HotkeyProfile, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub const STATE_RECONCILED: &str = "state-reconciled";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExternalChangePolicy {
+    /// Take the observed state as the profile state.
+    #[default]
+    Adopt,
+    /// Apply an external change of one device to the profile's other devices.
+    SyncToChange,
+    /// Keep TogMic's state.
+    Ignore,
+}
+
+static POLICY: Lazy<Mutex<ExternalChangePolicy>> =
+    Lazy::new(|| Mutex::new(ExternalChangePolicy::default()));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateReconciledEvent {
+    policy: ExternalChangePolicy,
+    /// `adopted`, `propagated` or `ignored`.
+    action: &'static str,
+    /// Devices whose state changed outside TogMic.
+    changed: Vec<String>,
+    /// Devices TogMic changed in response; empty unless propagated.
+    devices: Vec<String>,
+    /// The state across the profile's devices as observed.
+    observed: MuteState,
+    /// The profile state afterwards, which differs from `observed` when ignored.
+    state: MuteState,
+}
+
+/// What to do about an external change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Adopt,
+    /// Set `targets` to `muted`.
+    Propagate {
+        muted: bool,
+        targets: Vec<String>,
+    },
+    Ignore,
+}
+
+pub fn configure(policy: ExternalChangePolicy) {
+    *POLICY.lock_safe() = policy;
+}
+
+/// Devices of `observed` whose state differs from their `known` entry. Devices without one
+/// (just added) don't count as changed.
+pub fn changed_devices<'a>(
+    known: &[DeviceMuteState],
+    observed: &'a [DeviceMuteState],
+) -> Vec<&'a DeviceMuteState> {
+    observed
+        .iter()
+        .filter(|device| {
+            known
+                .iter()
+                .any(|k| k.device_id == device.device_id && k.muted != device.muted)
+        })
+        .collect()
+}
+
+/// Decide how `policy` handles the `changed` devices of `observed`.
+pub fn decide(
+    policy: ExternalChangePolicy,
+    changed: &[&DeviceMuteState],
+    observed: &ProfileMuteState,
+) -> Decision {
+    match policy {
+        ExternalChangePolicy::Adopt => Decision::Adopt,
+        ExternalChangePolicy::Ignore => Decision::Ignore,
+        ExternalChangePolicy::SyncToChange => {
+            let Some(first) = changed.first() else {
+                return Decision::Adopt;
+            };
+            let muted = first.muted;
+            if changed.iter().any(|d| d.muted != muted) {
+                return Decision::Adopt;
+            }
+            let targets: Vec<String> = observed
+                .devices
+                .iter()
+                .filter(|d| d.muted != muted)
+                .map(|d| d.device_id.clone())
+                .collect();
+            if targets.is_empty() {
+                Decision::Adopt
+            } else {
+                Decision::Propagate { muted, targets }
+            }
+        }
+    }
+}
+
+/// Act on a profile state observed by the device notifications or the polling loop.
+/// Returns whether the published state changed.
+pub fn observe(app: &AppHandle, state: &AppState, observed: ProfileMuteState) -> bool {
+    let mut policy = *POLICY.lock_safe();
+    // A scene's devices are meant to differ, so one device's change says nothing about the
+    // others
+    let scene = state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .is_some_and(|p| p.is_scene());
+    if scene && policy == ExternalChangePolicy::SyncToChange {
+        policy = ExternalChangePolicy::Adopt;
+    }
+    let busy = state
+        .audio_worker
+        .lock_safe()
+        .as_ref()
+        .is_some_and(|worker| worker.is_busy());
+    if busy {
+        return policy == ExternalChangePolicy::Adopt && adopt(app, state, observed);
+    }
+
+    let changed: Vec<DeviceMuteState> = {
+        let known = state.device_mute_states.lock_safe();
+        changed_devices(&known, &observed.devices)
+            .into_iter()
+            .cloned()
+            .collect()
+    };
+    let decision = if changed.is_empty() {
+        match policy {
+            ExternalChangePolicy::Ignore => Decision::Ignore,
+            _ => Decision::Adopt,
+        }
+    } else {
+        decide(policy, &changed.iter().collect::<Vec<_>>(), &observed)
+    };
+    let changed: Vec<String> = changed.into_iter().map(|d| d.device_id).collect();
+    let observed_state = observed.state;
+
+    match decision {
+        Decision::Adopt => {
+            let published = adopt(app, state, observed);
+            if !changed.is_empty() {
+                report(
+                    app,
+                    policy,
+                    "adopted",
+                    changed,
+                    Vec::new(),
+                    observed_state,
+                    observed_state,
+                );
+            }
+            published
+        }
+        Decision::Propagate { muted, targets } => {
+            let published = propagate(app, state, observed, muted, &targets);
+            report(
+                app,
+                policy,
+                "propagated",
+                changed,
+                targets,
+                observed_state,
+                MuteState::from(muted),
+            );
+            published
+        }
+        Decision::Ignore => {
+            *state.device_mute_states.lock_safe() = observed.devices;
+            if !changed.is_empty() {
+                report(
+                    app,
+                    policy,
+                    "ignored",
+                    changed,
+                    Vec::new(),
+                    observed_state,
+                    state.mute_state.get(),
+                );
+            }
+            false
+        }
+    }
+}
+
+fn adopt(app: &AppHandle, state: &AppState, observed: ProfileMuteState) -> bool {
+    let system_state = observed.state;
+    if state.store_mute_state(observed) == system_state {
+        return false;
+    }
+    crate::publish_mute_state(app, system_state, "system");
+    true
+}
+
+fn propagate(
+    app: &AppHandle,
+    state: &AppState,
+    observed: ProfileMuteState,
+    muted: bool,
+    targets: &[String],
+) -> bool {
+    let Some(profile) = state.current_profile.lock_safe().clone() else {
+        return false;
+    };
+    log::info!(
+        "[reconcile] external change to muted: {}, applying to {}",
+        muted,
+        targets.join(", ")
+    );
+
+    let devices = observed
+        .devices
+        .into_iter()
+        .map(|d| DeviceMuteState {
+            device_id: d.device_id,
+            muted,
+        })
+        .collect();
+    let synced = MuteState::from(muted);
+    let previous = state.store_mute_state(ProfileMuteState {
+        state: synced,
+        devices,
+    });
+    let _ = crate::apply_mute(
+        app,
+        state,
+        HotkeyProfile {
+            device_ids: targets.to_vec(),
+            device_states: Vec::new(),
+            // The other devices are the profile's own here
+            solo: false,
+            ..profile
+        },
+        muted,
+        previous,
+    );
+    if previous == synced {
+        return false;
+    }
+    crate::publish_mute_state(app, synced, "system");
+    true
+}
+
+fn report(
+    app: &AppHandle,
+    policy: ExternalChangePolicy,
+    action: &'static str,
+    changed: Vec<String>,
+    devices: Vec<String>,
+    observed: MuteState,
+    state: MuteState,
+) {
+    log::debug!(
+        "[reconcile] {} external change of {}",
+        action,
+        changed.join(", ")
+    );
+    events::emit(
+        app,
+        EventKind::Mute,
+        STATE_RECONCILED,
+        StateReconciledEvent {
+            policy,
+            action,
+            changed,
+            devices,
+            observed,
+            state,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices(states: &[(&str, bool)]) -> Vec<DeviceMuteState> {
+        states
+            .iter()
+            .map(|(id, muted)| DeviceMuteState {
+                device_id: id.to_string(),
+                muted: *muted,
+            })
+            .collect()
+    }
+
+    fn profile_state(states: &[(&str, bool)]) -> ProfileMuteState {
+        let devices = devices(states);
+        ProfileMuteState {
+            state: MuteState::from_devices(devices.iter().map(|d| d.muted))
+                .unwrap_or(MuteState::Unmuted),
+            devices,
+        }
+    }
+
+    fn ids<'a>(changed: &[&'a DeviceMuteState]) -> Vec<&'a str> {
+        changed.iter().map(|d| d.device_id.as_str()).collect()
+    }
+
+    #[test]
+    fn only_known_devices_with_a_new_state_changed() {
+        let known = devices(&[("a", true), ("b", true)]);
+        let observed = devices(&[("a", false), ("b", true), ("c", false)]);
+        assert_eq!(ids(&changed_devices(&known, &observed)), ["a"]);
+        assert!(changed_devices(&[], &observed).is_empty());
+    }
+
+    #[test]
+    fn adopt_and_ignore_do_not_look_at_the_devices() {
+        let observed = profile_state(&[("a", false), ("b", true)]);
+        let changed = devices(&[("a", false)]);
+        let changed: Vec<_> = changed.iter().collect();
+        assert_eq!(
+            decide(ExternalChangePolicy::Adopt, &changed, &observed),
+            Decision::Adopt
+        );
+        assert_eq!(
+            decide(ExternalChangePolicy::Ignore, &changed, &observed),
+            Decision::Ignore
+        );
+    }
+
+    #[test]
+    fn sync_to_change_propagates_to_the_other_devices() {
+        let observed = profile_state(&[("a", false), ("b", true), ("c", true)]);
+        let changed = devices(&[("a", false)]);
+        assert_eq!(
+            decide(
+                ExternalChangePolicy::SyncToChange,
+                &changed.iter().collect::<Vec<_>>(),
+                &observed
+            ),
+            Decision::Propagate {
+                muted: false,
+                targets: vec!["b".to_string(), "c".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn sync_to_change_adopts_when_nothing_is_left_to_do() {
+        // The changed devices disagree
+        let observed = profile_state(&[("a", false), ("b", true)]);
+        let changed = devices(&[("a", false), ("b", true)]);
+        assert_eq!(
+            decide(
+                ExternalChangePolicy::SyncToChange,
+                &changed.iter().collect::<Vec<_>>(),
+                &observed
+            ),
+            Decision::Adopt
+        );
+
+        // Every device already has the new state
+        let observed = profile_state(&[("a", true), ("b", true)]);
+        let changed = devices(&[("a", true)]);
+        assert_eq!(
+            decide(
+                ExternalChangePolicy::SyncToChange,
+                &changed.iter().collect::<Vec<_>>(),
+                &observed
+            ),
+            Decision::Adopt
+        );
+
+        assert_eq!(
+            decide(ExternalChangePolicy::SyncToChange, &[], &observed),
+            Decision::Adopt
+        );
+    }
+
+    #[test]
+    fn policies_use_kebab_case() {
+        assert_eq!(
+            serde_json::to_value(ExternalChangePolicy::SyncToChange).unwrap(),
+            "sync-to-change"
+        );
+        assert_eq!(ExternalChangePolicy::default(), ExternalChangePolicy::Adopt);
+    }
+
+    /// Sequences of external changes through `observe`, on a mock app.
+    #[cfg(feature = "mock-audio")]
+    mod mock_audio {
+        use super::*;
+        use crate::audio::mock;
+        use crate::audio::{AudioController, PlatformAudioController};
+        use crate::test_support::{mock_app, mock_device, mock_devices, profile, recorded_events};
+        use crate::AppRuntime;
+        use serde_json::{json, Value};
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+        use tauri::Manager;
+
+        /// A mock app whose active profile is `profile`, under `policy`, with the devices'
+        /// current state known.
+        fn app(policy: ExternalChangePolicy, profile: HotkeyProfile) -> tauri::App<AppRuntime> {
+            configure(policy);
+            let app = mock_app(AppState::default());
+            *app.state::<AppState>().current_profile.lock_safe() = Some(profile);
+            assert!(!poll(app.handle()));
+            app
+        }
+
+        fn headset_and_desk() -> HotkeyProfile {
+            profile(json!({ "deviceIds": ["headset", "desk"] }))
+        }
+
+        /// The profile's state as the poll reads it.
+        fn observed(state: &AppState) -> ProfileMuteState {
+            let profile = state.current_profile.lock_safe().clone().unwrap();
+            crate::get_profile_mute_state_detailed(
+                &PlatformAudioController::new().unwrap(),
+                &profile,
+                None,
+                state.mute_state.get(),
+            )
+            .unwrap()
+        }
+
+        /// What the next poll does, once the worker applied what it decided.
+        fn poll(app: &AppHandle) -> bool {
+            let state = app.state::<AppState>();
+            let published = observe(app, &state, observed(&state));
+            state.with_profile_on_worker("test", |_, _| ()).unwrap();
+            published
+        }
+
+        fn known(state: &AppState) -> Vec<(String, bool)> {
+            state
+                .device_mute_states
+                .lock_safe()
+                .iter()
+                .map(|d| (d.device_id.clone(), d.muted))
+                .collect()
+        }
+
+        fn states(states: &[(&str, bool)]) -> Vec<(String, bool)> {
+            states
+                .iter()
+                .map(|(id, muted)| (id.to_string(), *muted))
+                .collect()
+        }
+
+        fn mock_muted(id: &str) -> bool {
+            mock::devices()
+                .iter()
+                .find(|d| d.device.id == id)
+                .is_some_and(|d| d.muted)
+        }
+
+        fn take(events: &Arc<Mutex<Vec<Value>>>) -> Vec<Value> {
+            std::mem::take(&mut *events.lock_safe())
+        }
+
+        #[test]
+        fn adopt_follows_each_device_change() {
+            let _devices = mock_devices(vec![
+                mock_device("headset", true),
+                mock_device("desk", false),
+            ]);
+            let app = app(ExternalChangePolicy::Adopt, headset_and_desk());
+            let state = app.state::<AppState>();
+            let reconciled = recorded_events(app.handle(), STATE_RECONCILED);
+
+            mock::set_external_mute("headset", true).unwrap();
+            assert!(poll(app.handle()));
+            let events = take(&reconciled);
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0]["policy"], "adopt");
+            assert_eq!(events[0]["action"], "adopted");
+            assert_eq!(events[0]["changed"], json!(["headset"]));
+            assert_eq!(events[0]["observed"], "mixed");
+            assert_eq!(events[0]["state"], "mixed");
+            assert_eq!(state.mute_state.get(), MuteState::Mixed);
+            assert_eq!(known(&state), states(&[("headset", true), ("desk", false)]));
+
+            // The change is known now, so the next poll has nothing to report
+            assert!(!poll(app.handle()));
+            assert!(take(&reconciled).is_empty());
+
+            mock::set_external_mute("desk", true).unwrap();
+            assert!(poll(app.handle()));
+            let events = take(&reconciled);
+            assert_eq!(events[0]["changed"], json!(["desk"]));
+            assert_eq!(events[0]["state"], "muted");
+            assert_eq!(state.mute_state.get(), MuteState::Muted);
+            assert_eq!(known(&state), states(&[("headset", true), ("desk", true)]));
+        }
+
+        #[test]
+        fn sync_to_change_carries_each_change_to_the_other_devices() {
+            let _devices = mock_devices(vec![
+                mock_device("headset", true),
+                mock_device("desk", false),
+            ]);
+            let app = app(ExternalChangePolicy::SyncToChange, headset_and_desk());
+            let state = app.state::<AppState>();
+            let reconciled = recorded_events(app.handle(), STATE_RECONCILED);
+
+            mock::set_external_mute("headset", true).unwrap();
+            assert!(poll(app.handle()));
+            let events = take(&reconciled);
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0]["policy"], "sync-to-change");
+            assert_eq!(events[0]["action"], "propagated");
+            assert_eq!(events[0]["changed"], json!(["headset"]));
+            assert_eq!(events[0]["devices"], json!(["desk"]));
+            assert_eq!(events[0]["observed"], "mixed");
+            assert_eq!(events[0]["state"], "muted");
+            assert!(mock_muted("desk"));
+            assert_eq!(state.mute_state.get(), MuteState::Muted);
+            assert_eq!(known(&state), states(&[("headset", true), ("desk", true)]));
+
+            // The propagated mute isn't taken for another external change
+            assert!(!poll(app.handle()));
+            assert!(take(&reconciled).is_empty());
+
+            mock::set_external_mute("desk", false).unwrap();
+            assert!(poll(app.handle()));
+            let events = take(&reconciled);
+            assert_eq!(events[0]["changed"], json!(["desk"]));
+            assert_eq!(events[0]["devices"], json!(["headset"]));
+            assert_eq!(events[0]["state"], "unmuted");
+            assert!(!mock_muted("headset"));
+            assert_eq!(state.mute_state.get(), MuteState::Unmuted);
+            assert_eq!(
+                known(&state),
+                states(&[("headset", false), ("desk", false)])
+            );
+        }
+
+        #[test]
+        fn scenes_adopt_instead_of_syncing() {
+            let _devices = mock_devices(vec![
+                mock_device("headset", true),
+                mock_device("desk", false),
+                mock_device("room", false),
+            ]);
+            mock::set_external_mute("desk", true).unwrap();
+            let scene = profile(json!({
+                "deviceStates": [
+                    { "deviceId": "headset", "mutedWhenActive": false },
+                    { "deviceId": "desk", "mutedWhenActive": true },
+                    { "deviceId": "room", "mutedWhenActive": false }
+                ]
+            }));
+            let app = app(ExternalChangePolicy::SyncToChange, scene);
+            let state = app.state::<AppState>();
+            let reconciled = recorded_events(app.handle(), STATE_RECONCILED);
+
+            mock::set_external_mute("headset", true).unwrap();
+            poll(app.handle());
+            let events = take(&reconciled);
+            assert_eq!(events[0]["policy"], "adopt");
+            assert_eq!(events[0]["action"], "adopted");
+            assert_eq!(events[0]["changed"], json!(["headset"]));
+            assert!(!mock_muted("room"));
+            assert_eq!(
+                known(&state),
+                states(&[("headset", true), ("desk", true), ("room", false)])
+            );
+        }
+
+        #[test]
+        fn ignore_keeps_the_state_but_records_the_devices() {
+            let _devices = mock_devices(vec![
+                mock_device("headset", true),
+                mock_device("desk", false),
+            ]);
+            let app = app(ExternalChangePolicy::Ignore, headset_and_desk());
+            let state = app.state::<AppState>();
+            let reconciled = recorded_events(app.handle(), STATE_RECONCILED);
+            assert_eq!(
+                known(&state),
+                states(&[("headset", false), ("desk", false)])
+            );
+
+            mock::set_external_mute("headset", true).unwrap();
+            assert!(!poll(app.handle()));
+            let events = take(&reconciled);
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0]["policy"], "ignore");
+            assert_eq!(events[0]["action"], "ignored");
+            assert_eq!(events[0]["changed"], json!(["headset"]));
+            assert_eq!(events[0]["observed"], "mixed");
+            assert_eq!(events[0]["state"], "unmuted");
+            assert_eq!(state.mute_state.get(), MuteState::Unmuted);
+            assert!(!mock_muted("desk"));
+            assert_eq!(known(&state), states(&[("headset", true), ("desk", false)]));
+
+            // Overwriting the known state means the same change isn't reported twice
+            assert!(!poll(app.handle()));
+            assert!(take(&reconciled).is_empty());
+
+            mock::set_external_mute("desk", true).unwrap();
+            poll(app.handle());
+            let events = take(&reconciled);
+            assert_eq!(events[0]["changed"], json!(["desk"]));
+            assert_eq!(events[0]["observed"], "muted");
+            assert_eq!(events[0]["state"], "unmuted");
+            assert_eq!(known(&state), states(&[("headset", true), ("desk", true)]));
+        }
+
+        #[test]
+        fn observations_are_skipped_while_the_worker_applies() {
+            let _devices = mock_devices(vec![
+                mock_device("headset", true),
+                mock_device("desk", false),
+            ]);
+            let app = app(ExternalChangePolicy::SyncToChange, headset_and_desk());
+            let handle = app.handle();
+            let state = app.state::<AppState>();
+            let reconciled = recorded_events(handle, STATE_RECONCILED);
+
+            // Hold the worker so the mute below stays queued
+            let (release, held) = std::sync::mpsc::channel::<()>();
+            state.audio_worker.lock_safe().as_ref().unwrap().query(
+                "hold",
+                move |_| {
+                    let _ = held.recv_timeout(Duration::from_secs(1));
+                },
+                |_| (),
+            );
+            crate::set_mute_internal(&state, handle, true, true, "frontend").unwrap();
+            assert!(state.audio_worker.lock_safe().as_ref().unwrap().is_busy());
+
+            // The devices are still unmuted, which says nothing about external changes yet
+            mock::set_external_mute("headset", true).unwrap();
+            assert!(!observe(handle, &state, observed(&state)));
+            assert!(take(&reconciled).is_empty());
+            assert_eq!(state.mute_state.get(), MuteState::Muted);
+
+            let _ = release.send(());
+            assert!(!poll(handle));
+            assert!(take(&reconciled).is_empty());
+            assert!(mock_muted("desk"));
+            assert_eq!(known(&state), states(&[("headset", true), ("desk", true)]));
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Layout-independent hotkey bindings by scancode (Windows).
+//!
+//! A binding's key can be a `KeyRef::Scancode` (written `sc:<code>`, e.g.
+//! `CommandOrControl+sc:0x1A`), naming the physical key instead of the character on it;
+//! extended keys carry the `0xE0` prefix (`sc:0xE04D`). On registration the scancode is
+//! mapped with `MapVirtualKeyExW` to the virtual key it produces in the keyboard layout of
+//! the foreground window, which is the layout Windows translates key presses with, and
+//! registered like any other binding: through the shortcut plugin when it can express the
+//! key, otherwise through the keyboard hook. `WM_INPUTLANGCHANGE` is only delivered to the windows of the thread whose layout
+//! changed, so instead a watcher compares the foreground layout every
+//! `LAYOUT_CHECK_INTERVAL_MS` and re-registers the active profile's hotkey when it changes.
+
+#[cfg(target_os = "windows")]
+pub use windows_layout::{display_name, resolve, start_layout_watch};
+
+#[cfg(target_os = "windows")]
+mod windows_layout {
+    use crate::hotkey::{HotkeyBinding, KeyRef};
+    use crate::AppHandle;
+    use crate::{hotkey_capture, AppState, MutexExt};
+    use std::time::Duration;
+    use tauri::Manager;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetKeyNameTextW, GetKeyboardLayout, MapVirtualKeyExW, HKL, MAPVK_VSC_TO_VK_EX, VIRTUAL_KEY,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    const LAYOUT_CHECK_INTERVAL_MS: u64 = 1000;
+
+    /// Keyboard layout of the foreground window's thread.
+    fn foreground_layout() -> HKL {
+        unsafe {
+            let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+            GetKeyboardLayout(thread_id)
+        }
+    }
+
+    /// `binding` with its scancode replaced by the key it produces in the current layout:
+    /// a named key, or a virtual-key code (without modifiers) for the keyboard hook.
+    pub fn resolve(binding: &HotkeyBinding) -> Result<HotkeyBinding, String> {
+        let KeyRef::Scancode(code) = binding.key else {
+            return Ok(binding.clone());
+        };
+        let vk =
+            unsafe { MapVirtualKeyExW(u32::from(code), MAPVK_VSC_TO_VK_EX, foreground_layout()) }
+                as u16;
+        if vk == 0 {
+            return Err(format!(
+                "Scancode 0x{:02X} has no key in the current layout",
+                code
+            ));
+        }
+        Ok(match hotkey_capture::shortcut_key(VIRTUAL_KEY(vk)) {
+            Some(key) => HotkeyBinding::new(binding.modifiers.clone(), KeyRef::Named(key)),
+            None => HotkeyBinding::new([], KeyRef::Vk(vk)),
+        })
+    }
+
+    /// `binding` with its scancode replaced by the key's name in the current layout, for
+    /// display. Other bindings are written as they are.
+    pub fn display_name(binding: &HotkeyBinding) -> String {
+        let KeyRef::Scancode(code) = binding.key else {
+            return binding.to_string();
+        };
+        let extended = code & 0xE000 == 0xE000;
+        let lparam = (u32::from(code & 0xFF) << 16) | (u32::from(extended) << 24);
+        let mut buffer = [0u16; 64];
+        let len = unsafe { GetKeyNameTextW(lparam as i32, &mut buffer) };
+        match len {
+            1.. => HotkeyBinding::new(
+                binding.modifiers.clone(),
+                KeyRef::Named(String::from_utf16_lossy(&buffer[..len as usize])),
+            )
+            .to_string(),
+            _ => binding.to_string(),
+        }
+    }
+
+    /// Re-register the active profile's scancode hotkey whenever the foreground keyboard
+    /// layout changes.
+    pub fn start_layout_watch(app: AppHandle) {
+        std::thread::spawn(move || {
+            let mut last = foreground_layout();
+            loop {
+                std::thread::sleep(Duration::from_millis(LAYOUT_CHECK_INTERVAL_MS));
+                let layout = foreground_layout();
+                if layout == last {
+                    continue;
+                }
+                last = layout;
+
+                let state = app.state::<AppState>();
+                let profile = state.current_profile.lock_safe().clone();
+                let Some(profile) =
+                    profile.filter(|p| matches!(p.toggle_key.key, KeyRef::Scancode(_)))
+                else {
+                    continue;
+                };
+                log::info!(
+                    "[hotkey] keyboard layout changed, re-resolving '{}'",
+                    profile.toggle_key
+                );
+                if let Err(e) = crate::do_register_hotkey(
+                    &profile.toggle_key,
+                    profile.ignore_modifiers,
+                    &app,
+                    &state,
+                ) {
+                    state.record_error("layout_change", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn resolve(
+    _binding: &crate::hotkey::HotkeyBinding,
+) -> Result<crate::hotkey::HotkeyBinding, String> {
+    Err("Scancode bindings are only supported on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn display_name(binding: &crate::hotkey::HotkeyBinding) -> String {
+    binding.to_string()
+}
@@ -0,0 +1,158 @@
+//! Scene profiles: a target state per device instead of one mute state for all of them.
+//!
+//! A profile with `HotkeyProfile::device_states` is a scene, such as "XLR interface live,
+//! every other mic muted". Unmuting it applies the scene and muting it mutes every scene
+//! device, so the toggle flips between the two; activating it applies the scene. Its state
+//! is `Unmuted` while the devices are as the scene lists them, `Muted` while all of them
+//! are muted and `Mixed` otherwise. A scene lists its devices explicitly: `device_ids` and
+//! the all-devices entry don't apply to it.
+
+use crate::mute_state::{DeviceMuteState, MuteState};
+use crate::HotkeyProfile;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneDevice {
+    pub device_id: String,
+    /// Whether the device is muted while the scene is applied.
+    pub muted_when_active: bool,
+}
+
+/// Whether `device_id` ends up muted when `profile` is set to `muted`. That's `muted`
+/// itself unless a scene is unmuted.
+pub fn target(profile: &HotkeyProfile, device_id: &str, muted: bool) -> bool {
+    muted
+        || profile
+            .device_states
+            .iter()
+            .any(|d| d.device_id == device_id && d.muted_when_active)
+}
+
+/// The profile state for the per-device states `devices`. `None` if there are no devices
+/// to go by.
+pub fn state(profile: &HotkeyProfile, devices: &[DeviceMuteState]) -> Option<MuteState> {
+    if !profile.is_scene() {
+        return MuteState::from_devices(devices.iter().map(|d| d.muted));
+    }
+    if devices.is_empty() {
+        None
+    } else if devices.iter().all(|d| d.muted) {
+        Some(MuteState::Muted)
+    } else if devices
+        .iter()
+        .all(|d| d.muted == target(profile, &d.device_id, false))
+    {
+        Some(MuteState::Unmuted)
+    } else {
+        Some(MuteState::Mixed)
+    }
+}
+
+/// Check a scene before it is saved. A scene muting every device would be the same as
+/// muting it, so its toggle couldn't change anything.
+pub fn validate(profile: &HotkeyProfile) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for device in &profile.device_states {
+        if device.device_id == crate::ALL_DEVICES_ID {
+            return Err("A scene must list its devices one by one".to_string());
+        }
+        if !seen.insert(device.device_id.as_str()) {
+            return Err(format!("'{}' is in the scene twice", device.device_id));
+        }
+    }
+    if profile.is_scene() && profile.device_states.iter().all(|d| d.muted_when_active) {
+        return Err("A scene must leave at least one device unmuted".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::profile;
+    use serde_json::json;
+
+    fn scene(states: &[(&str, bool)]) -> HotkeyProfile {
+        let states: Vec<_> = states
+            .iter()
+            .map(|(id, muted)| json!({ "deviceId": id, "mutedWhenActive": muted }))
+            .collect();
+        profile(json!({ "deviceStates": states }))
+    }
+
+    fn devices(states: &[(&str, bool)]) -> Vec<DeviceMuteState> {
+        states
+            .iter()
+            .map(|(id, muted)| DeviceMuteState {
+                device_id: id.to_string(),
+                muted: *muted,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn an_unmuted_scene_targets_each_devices_state() {
+        let scene = scene(&[("xlr", false), ("webcam", true)]);
+        assert!(!target(&scene, "xlr", false));
+        assert!(target(&scene, "webcam", false));
+        assert!(target(&scene, "xlr", true));
+        assert!(target(&scene, "webcam", true));
+    }
+
+    #[test]
+    fn a_scene_is_unmuted_while_applied() {
+        let scene = scene(&[("xlr", false), ("webcam", true)]);
+        let state = |states: &[(&str, bool)]| super::state(&scene, &devices(states));
+        assert_eq!(
+            state(&[("xlr", false), ("webcam", true)]),
+            Some(MuteState::Unmuted)
+        );
+        assert_eq!(
+            state(&[("xlr", true), ("webcam", true)]),
+            Some(MuteState::Muted)
+        );
+        assert_eq!(
+            state(&[("xlr", false), ("webcam", false)]),
+            Some(MuteState::Mixed)
+        );
+        assert_eq!(state(&[]), None);
+    }
+
+    #[test]
+    fn profiles_without_a_scene_behave_as_before() {
+        let plain = profile(json!({ "deviceIds": ["a", "b"] }));
+        for muted in [false, true] {
+            assert_eq!(target(&plain, "a", muted), muted);
+        }
+        for states in [
+            vec![],
+            vec![("a", true)],
+            vec![("a", false), ("b", false)],
+            vec![("a", true), ("b", false)],
+            vec![("a", true), ("b", true)],
+        ] {
+            let devices = devices(&states);
+            assert_eq!(
+                state(&plain, &devices),
+                MuteState::from_devices(devices.iter().map(|d| d.muted))
+            );
+        }
+    }
+
+    #[test]
+    fn validates_scenes() {
+        assert_eq!(validate(&profile(json!({ "deviceIds": ["a"] }))), Ok(()));
+        assert_eq!(validate(&scene(&[("a", false), ("b", true)])), Ok(()));
+        assert_eq!(
+            validate(&scene(&[("a", true), ("b", true)])),
+            Err("A scene must leave at least one device unmuted".to_string())
+        );
+        assert_eq!(
+            validate(&scene(&[("a", false), ("a", true)])),
+            Err("'a' is in the scene twice".to_string())
+        );
+        assert!(validate(&scene(&[("all-mics", false)])).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Recovering when the session moves between the console and a remote desktop.
+//!
+//! Connecting over RDP (or switching users) remaps the audio endpoints: the session sees
+//! redirected devices with other IDs, and the old ones come back on return. On every
+//! such change the endpoint caches are dropped, the devices enumerated again (emitting
+//! `devices-changed` with the diff) and the active profile's device IDs that are gone
+//! are matched by name against the new devices. The remapped profile is only kept in
+//! memory, so the saved profile still names the console devices. `session-changed`
+//! reports what happened; if none of the profile's devices exist in the new session,
+//! `profile-devices-unavailable` warns that toggling won't do anything.
+
+use crate::audio::{AudioController, AudioDevice, PlatformAudioController};
+use crate::system_events::SessionConnection;
+use crate::{AppHandle, AppState, HotkeyProfile, MutexExt};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+pub const SESSION_CHANGED: &str = "session-changed";
+pub const PROFILE_DEVICES_UNAVAILABLE: &str = "profile-devices-unavailable";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Remapped {
+    from: String,
+    to: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionChangedEvent {
+    change: SessionConnection,
+    /// Profile device IDs replaced by a device of the same name.
+    remapped: Vec<Remapped>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileDevicesUnavailableEvent {
+    profile_id: String,
+    device_ids: Vec<String>,
+}
+
+pub fn handle(app: &AppHandle, change: SessionConnection) {
+    log::info!("[session] {:?}", change);
+    let _ = PlatformAudioController::init_thread();
+    let state = app.state::<AppState>();
+    let previous = state.cached_devices();
+    let Some(devices) = crate::enumerate_fresh(&state) else {
+        return;
+    };
+
+    let profile = state.current_profile.lock_safe().clone();
+    let mut remapped = Vec::new();
+    if let Some(profile) = profile {
+        let (updated, changes) = remap_by_name(&profile, &previous, &devices);
+        remapped = changes;
+        if !remapped.is_empty() {
+            crate::set_current_profile(app, &state, Some(updated.clone()));
+        }
+        if !crate::profile_uses_all_devices(&updated)
+            && !updated
+                .listed_device_ids()
+                .iter()
+                .any(|id| covers(&devices, id))
+        {
+            log::warn!(
+                "[session] none of the devices of profile '{}' exist in this session",
+                updated.name
+            );
+            let _ = app.emit(
+                PROFILE_DEVICES_UNAVAILABLE,
+                ProfileDevicesUnavailableEvent {
+                    profile_id: updated.id.clone(),
+                    device_ids: updated.listed_device_ids(),
+                },
+            );
+        }
+    }
+
+    crate::device_watch::check(app, state.cache_devices(devices));
+    crate::sync_profile_state(app, false, "session");
+    crate::refresh_tray_tooltip(app);
+    let _ = app.emit(SESSION_CHANGED, SessionChangedEvent { change, remapped });
+}
+
+/// Whether `id` names a device of `devices`; the default-device aliases always do while
+/// there is a default device.
+pub fn covers(devices: &[AudioDevice], id: &str) -> bool {
+    devices
+        .iter()
+        .any(|d| d.id == id || (d.is_default && (id == "default-mic" || id.is_empty())))
+}
+
+/// Replace the device IDs of `profile` missing from `current` by the ID of a device in
+/// `current` with the name the device had in `previous`.
+fn remap_by_name(
+    profile: &HotkeyProfile,
+    previous: &[AudioDevice],
+    current: &[AudioDevice],
+) -> (HotkeyProfile, Vec<Remapped>) {
+    let mut updated = profile.clone();
+    let mut remapped = Vec::new();
+    if crate::profile_uses_all_devices(profile) {
+        return (updated, remapped);
+    }
+    let ids = updated.device_ids.iter_mut().chain(
+        updated
+            .device_states
+            .iter_mut()
+            .map(|device| &mut device.device_id),
+    );
+    for id in ids {
+        if covers(current, id) {
+            continue;
+        }
+        // By system name, which an alias doesn't change
+        let Some(name) = previous
+            .iter()
+            .find(|d| d.id == *id)
+            .map(|d| d.system_name())
+        else {
+            continue;
+        };
+        let Some(replacement) = current
+            .iter()
+            .find(|d| d.system_name() == name && !profile.lists_device(&d.id))
+        else {
+            continue;
+        };
+        log::info!(
+            "[session] profile device '{}' is now {}",
+            name,
+            replacement.id
+        );
+        remapped.push(Remapped {
+            from: std::mem::replace(id, replacement.id.clone()),
+            to: replacement.id.clone(),
+            name: name.to_string(),
+        });
+    }
+    (updated, remapped)
+}
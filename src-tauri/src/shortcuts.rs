@@ -0,0 +1,144 @@
+//! Registry of the shortcuts TogMic has registered with the OS.
+//!
+//! Every successful registration is added to `AppState::registered_shortcuts` with what it
+//! is for, and every unregistration removes it again, so the registry can answer what is
+//! bound without asking the plugin for each candidate. `list` cross-checks it with the
+//! plugin (or the keyboard hook, or the desktop portal) anyway: an entry it no longer knows
+//! is flagged as stale.
+
+use crate::hotkey::{HotkeyBinding, KeyRef};
+use crate::{keyboard_hook, portal_shortcuts, AppHandle, AppState, MutexExt};
+use serde::Serialize;
+use std::str::FromStr;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutPurpose {
+    /// The active profile's toggle key (one entry per modifier variant with
+    /// `ignore_modifiers`).
+    Toggle,
+    /// The active profile's temporary unmute key.
+    TemporaryUnmute,
+    Panic,
+    Undo,
+    /// Another profile's toggle key, with `AppSettings::multi_profile_hotkeys`.
+    ProfileToggle,
+}
+
+/// What carries a registration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutEngine {
+    /// The global shortcut plugin.
+    Plugin,
+    /// The low-level keyboard hook, for `KeyRef::Vk` bindings.
+    Hook,
+    /// The desktop portal, under Wayland.
+    Portal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredShortcut {
+    pub shortcut: String,
+    /// Profile the shortcut belongs to; `None` for the global ones (panic).
+    pub profile_id: Option<String>,
+    pub purpose: ShortcutPurpose,
+    pub engine: ShortcutEngine,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    #[serde(flatten)]
+    pub entry: RegisteredShortcut,
+    /// The plugin doesn't have the shortcut registered (anymore).
+    pub stale: bool,
+}
+
+pub fn add(
+    state: &AppState,
+    shortcut: impl Into<String>,
+    profile_id: Option<String>,
+    purpose: ShortcutPurpose,
+    engine: ShortcutEngine,
+) {
+    state
+        .registered_shortcuts
+        .lock_safe()
+        .push(RegisteredShortcut {
+            shortcut: shortcut.into(),
+            profile_id,
+            purpose,
+            engine,
+        });
+}
+
+/// Remove the entries for `purpose` and return their shortcuts.
+pub fn remove(state: &AppState, purpose: ShortcutPurpose) -> Vec<String> {
+    let mut registered = state.registered_shortcuts.lock_safe();
+    let (removed, kept) = registered
+        .drain(..)
+        .partition::<Vec<_>, _>(|entry| entry.purpose == purpose);
+    *registered = kept;
+    removed.into_iter().map(|entry| entry.shortcut).collect()
+}
+
+/// Forget every entry; after `unregister_all`.
+pub fn clear(state: &AppState) {
+    state.registered_shortcuts.lock_safe().clear();
+}
+
+/// Entries registered for `purpose`.
+pub fn registered(state: &AppState, purpose: ShortcutPurpose) -> Vec<RegisteredShortcut> {
+    state
+        .registered_shortcuts
+        .lock_safe()
+        .iter()
+        .filter(|entry| entry.purpose == purpose)
+        .cloned()
+        .collect()
+}
+
+/// The entry that already binds `shortcut`, comparing parsed bindings so spelling and
+/// modifier order don't matter.
+pub fn owner(state: &AppState, shortcut: &str) -> Option<RegisteredShortcut> {
+    let binding = shortcut.parse::<HotkeyBinding>().ok();
+    state
+        .registered_shortcuts
+        .lock_safe()
+        .iter()
+        .find(
+            |entry| match (&binding, entry.shortcut.parse::<HotkeyBinding>()) {
+                (Some(binding), Ok(other)) => *binding == other,
+                _ => entry.shortcut.eq_ignore_ascii_case(shortcut),
+            },
+        )
+        .cloned()
+}
+
+/// The registry, each entry checked against the plugin.
+pub fn list(app: &AppHandle) -> Vec<ShortcutStatus> {
+    let entries = app
+        .state::<AppState>()
+        .registered_shortcuts
+        .lock_safe()
+        .clone();
+    entries
+        .into_iter()
+        .map(|entry| {
+            let stale = match entry.engine {
+                ShortcutEngine::Plugin => !Shortcut::from_str(&entry.shortcut)
+                    .is_ok_and(|shortcut| app.global_shortcut().is_registered(shortcut)),
+                ShortcutEngine::Hook => !matches!(
+                    entry.shortcut.parse::<HotkeyBinding>(),
+                    Ok(HotkeyBinding { key: KeyRef::Vk(vk), .. }) if keyboard_hook::is_bound(vk)
+                ),
+                ShortcutEngine::Portal => !portal_shortcuts::is_bound(entry.purpose),
+            };
+            ShortcutStatus { entry, stale }
+        })
+        .collect()
+}
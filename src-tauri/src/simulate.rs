@@ -0,0 +1,293 @@
+//! Simulated device and mute changes for QA, in debug builds only.
+//!
+//! The `simulate_*` commands change what TogMic sees of the system and then take the
+//! same path a real notification does (endpoint caches cleared, devices enumerated,
+//! `devices-changed`, the profile's state queried, mute lock and reconcile, tray), so
+//! e.g. a device vanishing mid-toggle can be tried without pulling a cable.
+//!
+//! Added and removed devices are an overlay over the cached device list (`overlay`, in
+//! `AppState::cache_devices`) and over the mute calls of `hard_mute` (`intercept_set`,
+//! `intercept_get`): a removed device fails like an unplugged one, an added one keeps its
+//! mute state here. Enumerations that bypass the cache (resolving a profile's devices,
+//! solo, restoring on exit) still see the real devices. External mute changes go to the
+//! device directly, as another application would make them.
+
+use crate::audio::{AudioController, AudioDevice};
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::Manager;
+
+/// How Windows reports an endpoint that is gone (`ERROR_NOT_FOUND`).
+const REMOVED_ERROR: &str = "Element not found. (0x80070490)";
+
+/// Simulated devices and their mute state.
+static ADDED: Lazy<Mutex<HashMap<String, (AudioDevice, bool)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static REMOVED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Apply the simulated changes to an enumerated device list.
+pub fn overlay(mut devices: Vec<AudioDevice>) -> Vec<AudioDevice> {
+    let removed = REMOVED.lock_safe();
+    devices.retain(|d| !removed.contains(&d.id));
+    for (device, _) in ADDED.lock_safe().values() {
+        if !devices.iter().any(|d| d.id == device.id) {
+            devices.push(device.clone());
+        }
+    }
+    devices
+}
+
+/// Result of setting the mute state of a simulated or removed device; `None` for others.
+pub fn intercept_set(device_id: &str, muted: bool) -> Option<Result<(), String>> {
+    if REMOVED.lock_safe().contains(device_id) {
+        return Some(Err(REMOVED_ERROR.to_string()));
+    }
+    let mut added = ADDED.lock_safe();
+    let (_, state) = added.get_mut(device_id)?;
+    *state = muted;
+    Some(Ok(()))
+}
+
+/// Mute state of a simulated or removed device; `None` for others.
+pub fn intercept_get(device_id: &str) -> Option<Result<bool, String>> {
+    if REMOVED.lock_safe().contains(device_id) {
+        return Some(Err(REMOVED_ERROR.to_string()));
+    }
+    ADDED
+        .lock_safe()
+        .get(device_id)
+        .map(|(_, muted)| Ok(*muted))
+}
+
+pub fn device_added(app: &AppHandle, device: AudioDevice) {
+    log::info!("[simulate] device added: {}", device.id);
+    add(device);
+    propagate(app);
+}
+
+pub fn device_removed(app: &AppHandle, device_id: &str) {
+    log::info!("[simulate] device removed: {}", device_id);
+    remove(device_id);
+    propagate(app);
+}
+
+fn add(device: AudioDevice) {
+    // Adding a removed device brings the real one back
+    if !REMOVED.lock_safe().remove(&device.id) {
+        ADDED.lock_safe().insert(device.id.clone(), (device, false));
+    }
+}
+
+fn remove(device_id: &str) {
+    if ADDED.lock_safe().remove(device_id).is_none() {
+        REMOVED.lock_safe().insert(device_id.to_string());
+    }
+}
+
+pub fn external_mute(app: &AppHandle, device_id: &str, muted: bool) -> Result<(), String> {
+    log::info!(
+        "[simulate] {} externally set to muted: {}",
+        device_id,
+        muted
+    );
+    if let Some(result) = intercept_set(device_id, muted) {
+        result?;
+    } else {
+        let state = app.state::<AppState>();
+        let controller = state.audio_controller.lock_safe();
+        controller
+            .as_ref()
+            .ok_or_else(|| "Audio controller not initialized".to_string())?
+            .set_mute_state(device_id, muted)?;
+    }
+    crate::on_system_mute_changed(app);
+    Ok(())
+}
+
+/// What the device notification and the next poll would do after a topology change.
+fn propagate(app: &AppHandle) {
+    crate::on_system_devices_changed(app);
+    crate::on_system_mute_changed(app);
+    crate::poll::wake();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::device;
+
+    // The overlay is global, so every test uses its own device ids
+
+    fn ids(devices: &[AudioDevice]) -> Vec<&str> {
+        devices.iter().map(|d| d.id.as_str()).collect()
+    }
+
+    #[test]
+    fn removed_devices_vanish_and_fail_like_unplugged_ones() {
+        remove("removed-real");
+        let devices = overlay(vec![device("removed-real"), device("removed-other")]);
+        assert!(!ids(&devices).contains(&"removed-real"));
+        assert!(ids(&devices).contains(&"removed-other"));
+        assert_eq!(
+            intercept_set("removed-real", true),
+            Some(Err(REMOVED_ERROR.to_string()))
+        );
+        assert_eq!(
+            intercept_get("removed-real"),
+            Some(Err(REMOVED_ERROR.to_string()))
+        );
+        assert_eq!(intercept_get("removed-other"), None);
+
+        // Adding it again brings the real device back
+        add(device("removed-real"));
+        assert!(ids(&overlay(vec![device("removed-real")])).contains(&"removed-real"));
+        assert_eq!(intercept_get("removed-real"), None);
+    }
+
+    #[test]
+    fn added_devices_keep_their_mute_state() {
+        add(device("added-sim"));
+        assert!(ids(&overlay(Vec::new())).contains(&"added-sim"));
+        assert_eq!(intercept_get("added-sim"), Some(Ok(false)));
+        assert_eq!(intercept_set("added-sim", true), Some(Ok(())));
+        assert_eq!(intercept_get("added-sim"), Some(Ok(true)));
+
+        // Removing a simulated device forgets it instead of hiding a real one
+        remove("added-sim");
+        assert!(!ids(&overlay(Vec::new())).contains(&"added-sim"));
+        assert_eq!(intercept_get("added-sim"), None);
+    }
+
+    #[test]
+    fn simulated_devices_are_not_listed_twice() {
+        add(device("added-twice"));
+        let devices = overlay(vec![device("added-twice")]);
+        assert_eq!(
+            ids(&devices)
+                .iter()
+                .filter(|id| **id == "added-twice")
+                .count(),
+            1
+        );
+        remove("added-twice");
+    }
+
+    /// The simulation on a mock app, down to the events the frontend gets and the tray.
+    #[cfg(feature = "mock-audio")]
+    mod mock_audio {
+        use super::*;
+        use crate::events::{DEVICES_CHANGED, MUTE_STATE_CHANGED};
+        use crate::mute_state::MuteState;
+        use crate::reconcile::{self, ExternalChangePolicy};
+        use crate::test_support::{mock_app, mock_device, mock_devices, profile, recorded_events};
+        use crate::AppRuntime;
+        use serde_json::{json, Value};
+
+        /// A mock app with "a" and "b", whose active profile is "a", after a first device
+        /// check, so the next one reports changes.
+        fn app() -> tauri::App<AppRuntime> {
+            let app = mock_app(AppState::default());
+            let state = app.state::<AppState>();
+            *state.current_profile.lock_safe() = Some(profile(json!({ "deviceIds": ["a"] })));
+            reconcile::configure(ExternalChangePolicy::Adopt);
+            crate::on_system_devices_changed(app.handle());
+            app
+        }
+
+        fn ids(payload: &Value, field: &str) -> Vec<String> {
+            payload[field]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| d["id"].as_str().unwrap().to_string())
+                .collect()
+        }
+
+        #[test]
+        fn added_devices_are_reported_and_removed_again() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = app();
+            let changed = recorded_events(app.handle(), DEVICES_CHANGED);
+
+            device_added(app.handle(), device("sim-usb"));
+            {
+                let changed = changed.lock_safe();
+                assert_eq!(changed.len(), 1);
+                assert_eq!(ids(&changed[0], "added"), ["sim-usb"]);
+                assert!(ids(&changed[0], "all").contains(&"sim-usb".to_string()));
+                assert_eq!(changed[0]["profileDeviceRemoved"], false);
+            }
+            let state = app.state::<AppState>();
+            assert!(state.cached_devices().iter().any(|d| d.id == "sim-usb"));
+
+            device_removed(app.handle(), "sim-usb");
+            let changed = changed.lock_safe();
+            assert_eq!(changed.len(), 2);
+            assert_eq!(ids(&changed[1], "removed"), ["sim-usb"]);
+            assert!(!state.cached_devices().iter().any(|d| d.id == "sim-usb"));
+        }
+
+        #[test]
+        fn removing_a_profile_device_is_flagged() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = app();
+            let changed = recorded_events(app.handle(), DEVICES_CHANGED);
+            let lost = recorded_events(app.handle(), "profile-device-lost");
+
+            device_removed(app.handle(), "a");
+            assert_eq!(ids(&changed.lock_safe()[0], "removed"), ["a"]);
+            assert_eq!(changed.lock_safe()[0]["profileDeviceRemoved"], true);
+            assert_eq!(lost.lock_safe()[0]["deviceId"], "a");
+
+            // Adding it again brings the real device back
+            device_added(app.handle(), device("a"));
+            assert_eq!(ids(&changed.lock_safe()[1], "added"), ["a"]);
+            assert_eq!(intercept_get("a"), None);
+        }
+
+        #[test]
+        fn external_mutes_reach_the_state_events_and_tray() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = app();
+            let state = app.state::<AppState>();
+            let published = recorded_events(app.handle(), MUTE_STATE_CHANGED);
+
+            external_mute(app.handle(), "a", true).unwrap();
+            assert!(crate::audio::mock::devices()
+                .iter()
+                .any(|d| d.device.id == "a" && d.muted));
+            assert_eq!(state.mute_state.get(), MuteState::Muted);
+            assert_eq!(*state.last_tray_state.lock_safe(), Some(MuteState::Muted));
+            {
+                let published = published.lock_safe();
+                assert_eq!(published.len(), 1);
+                assert_eq!(published[0]["muted"], true);
+                assert_eq!(published[0]["source"], "external");
+            }
+
+            // A device outside the profile changes nothing
+            external_mute(app.handle(), "b", true).unwrap();
+            assert_eq!(published.lock_safe().len(), 1);
+
+            external_mute(app.handle(), "a", false).unwrap();
+            assert_eq!(state.mute_state.get(), MuteState::Unmuted);
+            assert_eq!(*state.last_tray_state.lock_safe(), Some(MuteState::Unmuted));
+            assert_eq!(published.lock_safe()[1]["muted"], false);
+        }
+
+        #[test]
+        fn external_mutes_of_simulated_devices_stay_simulated() {
+            let _devices = mock_devices(vec![mock_device("a", true), mock_device("b", false)]);
+            let app = app();
+            device_added(app.handle(), device("sim-headset"));
+            external_mute(app.handle(), "sim-headset", true).unwrap();
+            assert_eq!(intercept_get("sim-headset"), Some(Ok(true)));
+            assert!(!crate::audio::mock::devices()
+                .iter()
+                .any(|d| d.device.id == "sim-headset"));
+            device_removed(app.handle(), "sim-headset");
+        }
+    }
+}
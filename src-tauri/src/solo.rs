@@ -0,0 +1,65 @@
+//! Solo profiles: only the profile's own devices may be live.
+//!
+//! With `HotkeyProfile::solo` every other input device is forced muted: when the profile
+//! is activated (`enforce`), with every mute change the audio worker applies, and when
+//! `device_watch::check` sees devices appear. Muting the profile therefore leaves
+//! everything muted. Deactivating the profile leaves the other devices as they are.
+
+use crate::audio::{AudioController, AudioDevice};
+use crate::{AppState, HotkeyProfile, MutexExt};
+
+/// Devices of `devices` the profile doesn't cover; empty unless it is a solo profile.
+pub fn other_devices(profile: &HotkeyProfile, devices: &[AudioDevice]) -> Vec<String> {
+    if !profile.solo || crate::profile_uses_all_devices(profile) {
+        return Vec::new();
+    }
+    devices
+        .iter()
+        .filter(|device| !covers(profile, device) && !crate::hidden_devices::is_hidden(&device.id))
+        .map(|device| device.id.clone())
+        .collect()
+}
+
+fn covers(profile: &HotkeyProfile, device: &AudioDevice) -> bool {
+    profile
+        .listed_device_ids()
+        .iter()
+        .any(|id| id == &device.id || (device.is_default && (id == "default-mic" || id.is_empty())))
+}
+
+/// Mute the devices the active solo profile doesn't cover, of `devices` or, without
+/// them, of a fresh enumeration.
+pub fn enforce(state: &AppState, devices: Option<&[AudioDevice]>) {
+    let Some(profile) = state.current_profile.lock_safe().clone() else {
+        return;
+    };
+    if !profile.solo {
+        return;
+    }
+
+    let controller_lock = state.audio_controller.lock_safe();
+    let Some(controller) = controller_lock.as_ref() else {
+        return;
+    };
+    let enumerated;
+    let devices = match devices {
+        Some(devices) => devices,
+        None => match controller.enumerate_input_devices() {
+            Ok(list) => {
+                enumerated = list;
+                &enumerated
+            }
+            Err(e) => {
+                log::warn!("[solo] failed to enumerate devices: {}", e);
+                return;
+            }
+        },
+    };
+    for device_id in other_devices(&profile, devices) {
+        if let Err(e) =
+            crate::hard_mute::set_mute(controller, &device_id, true, profile.mute_strategy)
+        {
+            log::warn!("[solo] failed to mute {}: {}", device_id, e);
+        }
+    }
+}
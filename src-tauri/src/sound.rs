@@ -1,9 +1,16 @@
-use std::fs;
-use std::io::Cursor;
-use std::sync::mpsc;
-use std::time::Duration;
-use once_cell::sync::OnceCell;
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::{Lazy, OnceCell};
 use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::Emitter;
 
 // Release audio stream after this many seconds of silence so Bluetooth devices
 // can go idle and other audio sources (e.g. phone music) can take over.
@@ -13,7 +20,109 @@ const STREAM_IDLE_TIMEOUT_SECS: u64 = 5;
 const MUTE_WAV: &[u8] = include_bytes!("../resources/mute.wav");
 const UNMUTE_WAV: &[u8] = include_bytes!("../resources/unmute.wav");
 
-static SOUND_TX: OnceCell<mpsc::SyncSender<Vec<u8>>> = OnceCell::new();
+static MUTE: Lazy<Arc<[u8]>> = Lazy::new(|| Arc::from(MUTE_WAV));
+static UNMUTE: Lazy<Arc<[u8]>> = Lazy::new(|| Arc::from(UNMUTE_WAV));
+static ERROR: Lazy<Arc<[u8]>> = Lazy::new(|| Arc::from(generate_error_wav()));
+static REMINDER: Lazy<Arc<[u8]>> = Lazy::new(|| Arc::from(generate_reminder_wav()));
+
+/// Emitted with `{ file, error }` when an external sound file is rejected; the built-in
+/// sound plays instead.
+pub const SOUND_LOAD_ERROR: &str = "sound-load-error";
+
+/// Default of `AppSettings::max_sound_file_mb`.
+pub const DEFAULT_MAX_SOUND_FILE_MB: u32 = 4;
+
+static MAX_SOUND_FILE_BYTES: AtomicU64 =
+    AtomicU64::new(DEFAULT_MAX_SOUND_FILE_MB as u64 * 1024 * 1024);
+
+static SOUND_TX: OnceCell<mpsc::SyncSender<Arc<[u8]>>> = OnceCell::new();
+static APP: OnceCell<AppHandle> = OnceCell::new();
+
+/// An external sound file as last loaded, kept until the file changes so toggles don't
+/// read it again.
+struct ExternalSound {
+    len: u64,
+    modified: Option<SystemTime>,
+    /// `None` if the file was rejected; that was reported when it was loaded.
+    data: Option<Arc<[u8]>>,
+}
+
+static EXTERNAL: Lazy<Mutex<HashMap<&'static str, ExternalSound>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Where the feedback sounds come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SoundTheme {
+    /// The WAVs shipped with TogMic (or dropped next to the executable).
+    #[default]
+    Bundled,
+    /// The desktop's sound theme through libcanberra (Linux); the bundled sounds
+    /// elsewhere or when it isn't available.
+    System,
+}
+
+/// How the OS treats the feedback sound stream (Windows).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SoundCategory {
+    /// An ordinary stream on the default output device.
+    #[default]
+    Standard,
+    /// The communications device, mixed like call cues (ducked with other voice audio).
+    Communications,
+    /// Tagged as a sound effect, like the system's own cues.
+    SoundEffects,
+}
+
+static SYSTEM_THEME: AtomicBool = AtomicBool::new(false);
+static CATEGORY: Mutex<SoundCategory> = Mutex::new(SoundCategory::Standard);
+
+pub fn configure(theme: SoundTheme, category: SoundCategory, max_file_mb: u32) {
+    SYSTEM_THEME.store(theme == SoundTheme::System, Ordering::SeqCst);
+    *CATEGORY.lock_safe() = category;
+    let max_bytes = u64::from(max_file_mb.max(1)) * 1024 * 1024;
+    if MAX_SOUND_FILE_BYTES.swap(max_bytes, Ordering::SeqCst) != max_bytes {
+        // Files rejected (or accepted) under the old limit are checked again
+        EXTERNAL.lock_safe().clear();
+    }
+}
+
+/// Play the freedesktop sound `event_id` from the user's sound theme. Returns false if
+/// the system theme isn't selected or `canberra-gtk-play` can't be started, so the
+/// caller falls back to the bundled sound.
+#[cfg(target_os = "linux")]
+fn play_theme_sound(event_id: &str) -> bool {
+    if !SYSTEM_THEME.load(Ordering::SeqCst) {
+        return false;
+    }
+    if crate::focus_assist::suppressed() {
+        return true;
+    }
+    match std::process::Command::new("canberra-gtk-play")
+        .arg("--id")
+        .arg(event_id)
+        .arg("--description")
+        .arg("TogMic")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            // Reap it; a missing sound server only makes it exit with an error
+            std::thread::spawn(move || {
+                let _ = child.wait();
+            });
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn play_theme_sound(_event_id: &str) -> bool {
+    false
+}
 
 fn default_device_name() -> Option<String> {
     rodio::cpal::default_host()
@@ -22,10 +131,11 @@ fn default_device_name() -> Option<String> {
 }
 
 /// Initialize the persistent audio thread (call once at startup)
-pub fn init() {
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
     // Buffer up to 8 sounds so rapid toggles don't silently drop messages;
     // the debounce loop below drains this queue and plays only the latest.
-    let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(8);
+    let (tx, rx) = mpsc::sync_channel::<Arc<[u8]>>(8);
     if SOUND_TX.set(tx).is_err() {
         return;
     }
@@ -34,6 +144,8 @@ pub fn init() {
         type StreamState = (rodio::OutputStream, rodio::OutputStreamHandle, String);
         let mut stream_state: Option<StreamState> = None;
         let mut current_sink: Option<rodio::Sink> = None;
+        // Stops the sound playing on its own render stream (`render_with_category`)
+        let mut current_render: Option<Arc<AtomicBool>> = None;
 
         loop {
             match rx.recv_timeout(Duration::from_secs(STREAM_IDLE_TIMEOUT_SECS)) {
@@ -49,6 +161,16 @@ pub fn init() {
                         }
                     }
 
+                    // Newest wins on the render streams as well
+                    if let Some(stop) = current_render.take() {
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                    if let Some(stop) = render_with_category(&data) {
+                        drop(current_sink.take());
+                        current_render = Some(stop);
+                        continue;
+                    }
+
                     let current_device = default_device_name().unwrap_or_default();
 
                     // Reinitialize stream only if device changed or not yet initialized
@@ -79,7 +201,7 @@ pub fn init() {
                     // No sound played for STREAM_IDLE_TIMEOUT_SECS seconds.
                     // Release the audio stream so Bluetooth devices can go idle
                     // and other audio sources (e.g. phone) can take over.
-                    if current_sink.as_ref().map_or(true, |s| s.empty()) {
+                    if current_sink.as_ref().is_none_or(|s| s.empty()) {
                         drop(current_sink.take());
                         drop(stream_state.take());
                     }
@@ -90,15 +212,172 @@ pub fn init() {
     });
 }
 
-/// Try to load an external sound file from the executable directory
-fn load_external_sound(filename: &str) -> Option<Vec<u8>> {
+/// Play `data` on a render stream of its own, tagged with the configured
+/// `SoundCategory` (Windows). Returns the flag that stops it, or `None` if the category
+/// is `Standard`, the platform has no categories or the sound can't be decoded, for the
+/// shared rodio stream to play it instead.
+#[cfg(target_os = "windows")]
+fn render_with_category(data: &Arc<[u8]>) -> Option<Arc<AtomicBool>> {
+    use rodio::Source;
+
+    let communications = match *CATEGORY.lock_safe() {
+        SoundCategory::Standard => return None,
+        SoundCategory::Communications => true,
+        SoundCategory::SoundEffects => false,
+    };
+    let decoder = rodio::Decoder::new(Cursor::new(data.clone())).ok()?;
+    let channels = decoder.channels();
+    let sample_rate = decoder.sample_rate();
+    let samples: Vec<f32> = decoder.map(|s| s as f32 / 32768.0).collect();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    std::thread::spawn(move || {
+        let result =
+            crate::audio::render_samples(&samples, channels, sample_rate, communications, &stopped);
+        if let Err(e) = result {
+            log::warn!("[sound] failed to play on a categorized stream: {}", e);
+        }
+    });
+    Some(stop)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn render_with_category(_data: &Arc<[u8]>) -> Option<Arc<AtomicBool>> {
+    None
+}
+
+/// Try to load an external sound file from the executable directory. A missing file is
+/// no error; a file that fails `load_sound_file` is reported once per version of the
+/// file, and the caller plays the built-in sound.
+fn load_external_sound(filename: &'static str) -> Option<Arc<[u8]>> {
     let exe_path = std::env::current_exe().ok()?;
     let exe_dir = exe_path.parent()?;
     let sound_path = exe_dir.join(filename);
-    fs::read(sound_path).ok()
+    let metadata = fs::metadata(&sound_path).ok()?;
+    let modified = metadata.modified().ok();
+
+    let mut external = EXTERNAL.lock_safe();
+    if let Some(sound) = external.get(filename) {
+        if sound.len == metadata.len() && sound.modified == modified {
+            return sound.data.clone();
+        }
+    }
+    let data = match load_sound_file(&sound_path) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            log::warn!("[sound] ignoring {}: {}", sound_path.display(), e);
+            if let Some(app) = APP.get() {
+                let _ = app.emit(
+                    SOUND_LOAD_ERROR,
+                    serde_json::json!({ "file": sound_path, "error": e }),
+                );
+            }
+            None
+        }
+    };
+    external.insert(
+        filename,
+        ExternalSound {
+            len: metadata.len(),
+            modified,
+            data: data.clone(),
+        },
+    );
+    data
+}
+
+/// Read a WAV file for playback: at most `AppSettings::max_sound_file_mb`, and only if
+/// `validate_wav` accepts it.
+fn load_sound_file(path: &Path) -> Result<Arc<[u8]>, String> {
+    let max_bytes = MAX_SOUND_FILE_BYTES.load(Ordering::SeqCst);
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open the file: {}", e))?;
+    // Read one byte past the limit rather than trusting the size from before
+    let mut data = Vec::new();
+    file.take(max_bytes + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read the file: {}", e))?;
+    if data.len() as u64 > max_bytes {
+        return Err(format!(
+            "The file is larger than {} MB",
+            max_bytes / (1024 * 1024)
+        ));
+    }
+    validate_wav(&data)?;
+    Ok(Arc::from(data))
+}
+
+/// Check that `data` is a RIFF/WAVE file in a sample format the decoder plays: integer
+/// PCM of 8 to 32 bits or 32-bit float, with audio data.
+fn validate_wav(data: &[u8]) -> Result<(), String> {
+    const FORMAT_PCM: u16 = 1;
+    const FORMAT_FLOAT: u16 = 3;
+    const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+    let u16_at = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let u32_at =
+        |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err("Not a WAV file (no RIFF/WAVE header)".to_string());
+    }
+
+    let mut format = None;
+    let mut at = 12;
+    while at + 8 <= data.len() {
+        let id = &data[at..at + 4];
+        let size = u32_at(at + 4) as usize;
+        let body = at + 8;
+        match id {
+            b"fmt " => {
+                if size < 16 || body + size > data.len() {
+                    return Err("The format chunk is truncated".to_string());
+                }
+                let mut tag = u16_at(body);
+                if tag == FORMAT_EXTENSIBLE && size >= 26 {
+                    // The sub format GUID starts with the actual format tag
+                    tag = u16_at(body + 24);
+                }
+                let channels = u16_at(body + 2);
+                let sample_rate = u32_at(body + 4);
+                let bits = u16_at(body + 14);
+                format = Some((tag, channels, sample_rate, bits));
+            }
+            b"data" => {
+                let Some((tag, channels, sample_rate, bits)) = format else {
+                    return Err("The audio data comes before the format chunk".to_string());
+                };
+                let supported = match tag {
+                    FORMAT_PCM => matches!(bits, 8 | 16 | 24 | 32),
+                    FORMAT_FLOAT => bits == 32,
+                    _ => false,
+                };
+                if !supported {
+                    return Err(format!(
+                        "Unsupported sample format (format {}, {} bits)",
+                        tag, bits
+                    ));
+                }
+                if channels == 0 || sample_rate == 0 {
+                    return Err("The format chunk has no channels or sample rate".to_string());
+                }
+                if size == 0 || body >= data.len() {
+                    return Err("The file has no audio data".to_string());
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        at = body.saturating_add(size).saturating_add(size & 1);
+    }
+    Err("The file has no audio data".to_string())
 }
 
-fn play_wav(data: Vec<u8>) {
+fn play_wav(data: Arc<[u8]>) {
+    if crate::focus_assist::suppressed() {
+        return;
+    }
     if let Some(tx) = SOUND_TX.get() {
         // Discard old queued sound if the channel is full — newest wins
         let _ = tx.try_send(data);
@@ -107,14 +386,87 @@ fn play_wav(data: Vec<u8>) {
 
 /// Play the mute sound (tries external file first, falls back to embedded)
 pub fn play_mute_sound() {
-    let data = load_external_sound("mute.wav")
-        .unwrap_or_else(|| MUTE_WAV.to_vec());
+    if play_theme_sound("audio-volume-change") {
+        return;
+    }
+    let data = load_external_sound("mute.wav").unwrap_or_else(|| MUTE.clone());
     play_wav(data);
 }
 
 /// Play the unmute sound (tries external file first, falls back to embedded)
 pub fn play_unmute_sound() {
-    let data = load_external_sound("unmute.wav")
-        .unwrap_or_else(|| UNMUTE_WAV.to_vec());
+    if play_theme_sound("audio-volume-change") {
+        return;
+    }
+    let data = load_external_sound("unmute.wav").unwrap_or_else(|| UNMUTE.clone());
+    play_wav(data);
+}
+
+/// Build the fallback error sound: two short descending beeps.
+/// Generated instead of embedded so it stays clearly distinct from the mute/unmute sounds.
+fn generate_error_wav() -> Vec<u8> {
+    generate_tones_wav(&[(660.0, 0.12), (440.0, 0.18)], 0.4)
+}
+
+/// Build the fallback reminder sound: one quiet, low beep.
+fn generate_reminder_wav() -> Vec<u8> {
+    generate_tones_wav(&[(520.0, 0.2)], 0.15)
+}
+
+/// Sine beeps of (frequency, seconds) at `amplitude` as 16-bit mono PCM WAV.
+fn generate_tones_wav(tones: &[(f32, f32)], amplitude: f32) -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 22050;
+
+    let mut samples: Vec<i16> = Vec::new();
+    for &(freq, secs) in tones {
+        let len = (SAMPLE_RATE as f32 * secs) as usize;
+        for i in 0..len {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            // Short linear fade in/out to avoid clicks
+            let fade = (i.min(len - i) as f32 / 200.0).min(1.0);
+            let value = (2.0 * std::f32::consts::PI * freq * t).sin() * fade * amplitude;
+            samples.push((value * i16::MAX as f32) as i16);
+        }
+        // 40 ms gap between the beeps
+        samples.resize(samples.len() + SAMPLE_RATE as usize / 25, 0);
+    }
+
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// Play the error sound when a mute change could not be applied (tries external file
+/// first, falls back to a generated beep)
+pub fn play_error_sound() {
+    if play_theme_sound("dialog-error") {
+        return;
+    }
+    let data = load_external_sound("error.wav").unwrap_or_else(|| ERROR.clone());
+    play_wav(data);
+}
+
+/// Play the reminder sound for speaking while muted (tries external file first, falls
+/// back to a generated beep)
+pub fn play_reminder_sound() {
+    if play_theme_sound("dialog-warning") {
+        return;
+    }
+    let data = load_external_sound("reminder.wav").unwrap_or_else(|| REMINDER.clone());
     play_wav(data);
 }
@@ -0,0 +1,237 @@
+//! Reminding the user that they are talking into a muted microphone.
+//!
+//! While the profile is muted a monitor thread samples the peak level of its device (the
+//! default device for a profile covering several) every `SAMPLE_INTERVAL`. Once the level
+//! stays above the threshold for `SPEAKING_FOR` it emits `speaking-while-muted` and, as
+//! configured, shows a notification and plays a soft reminder tone, at most once per
+//! `REMINDER_INTERVAL`. Where the platform has no metering the monitor stops until it is
+//! configured again; while the device can't be read it only retries every
+//! `RETRY_INTERVAL`.
+//!
+//! `calibrate` samples the background level, then speech, and suggests a threshold in
+//! between.
+
+use crate::audio::{AudioController, PlatformAudioController, METERING_UNSUPPORTED};
+use crate::mute_state::MuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+/// Between checks while muting is off, unmuted or unsupported.
+const IDLE_INTERVAL: Duration = Duration::from_millis(500);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const SPEAKING_FOR: Duration = Duration::from_millis(1500);
+const REMINDER_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_CALIBRATION_SECS: u32 = 30;
+
+pub const SPEAKING_WHILE_MUTED: &str = "speaking-while-muted";
+pub const VOICE_CALIBRATION_PHASE: &str = "voice-calibration-phase";
+
+/// Peak level treated as speech until calibrated.
+pub const DEFAULT_THRESHOLD: f32 = 0.1;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// `f32` bits of the threshold.
+static THRESHOLD: AtomicU32 = AtomicU32::new(0x3dcc_cccd);
+static NOTIFY: AtomicBool = AtomicBool::new(true);
+static SOUND: AtomicBool = AtomicBool::new(true);
+/// Set by the monitor once metering turned out to be unsupported.
+static UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpeakingWhileMutedEvent {
+    device_id: String,
+    peak: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CalibrationPhaseEvent {
+    /// "ambient" (stay quiet) or "speaking" (talk normally).
+    phase: &'static str,
+    seconds: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceCalibration {
+    /// Background level (95th percentile of the quiet phase).
+    pub ambient: f32,
+    /// Speech level (median of the speaking phase).
+    pub speaking: f32,
+    pub threshold: f32,
+}
+
+pub fn configure(enabled: bool, threshold: f32, notify: bool, sound: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    THRESHOLD.store(threshold.clamp(0.0, 1.0).to_bits(), Ordering::SeqCst);
+    NOTIFY.store(notify, Ordering::SeqCst);
+    SOUND.store(sound, Ordering::SeqCst);
+    UNSUPPORTED.store(false, Ordering::SeqCst);
+}
+
+fn threshold() -> f32 {
+    f32::from_bits(THRESHOLD.load(Ordering::SeqCst))
+}
+
+/// The device to meter: the profile's only device, otherwise the default device.
+fn monitored_device(state: &AppState) -> Option<String> {
+    let profile = state.current_profile.lock_safe();
+    let profile = profile.as_ref()?;
+    match profile.listed_device_ids().as_slice() {
+        [id] if id != crate::ALL_DEVICES_ID => Some(id.clone()),
+        _ => Some("default-mic".to_string()),
+    }
+}
+
+/// Start the monitor thread. It idles along cheaply while the reminder is disabled.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let _ = PlatformAudioController::init_thread();
+        let controller = match PlatformAudioController::new() {
+            Ok(controller) => controller,
+            Err(e) => {
+                log::warn!("[speaking] failed to create audio controller: {}", e);
+                return;
+            }
+        };
+
+        let mut speaking_since: Option<Instant> = None;
+        let mut last_reminder: Option<Instant> = None;
+        let mut unreadable = false;
+        loop {
+            let state = app.state::<AppState>();
+            let device_id = if ENABLED.load(Ordering::SeqCst)
+                && !UNSUPPORTED.load(Ordering::SeqCst)
+                && state.mute_state.get() == MuteState::Muted
+            {
+                monitored_device(&state)
+            } else {
+                None
+            };
+            let Some(device_id) = device_id else {
+                speaking_since = None;
+                std::thread::sleep(IDLE_INTERVAL);
+                continue;
+            };
+
+            match controller.get_peak_level(&device_id) {
+                Err(e) if e == METERING_UNSUPPORTED => {
+                    log::info!("[speaking] {}, monitor stopped", e);
+                    UNSUPPORTED.store(true, Ordering::SeqCst);
+                    continue;
+                }
+                Err(e) => {
+                    if !unreadable {
+                        log::warn!("[speaking] pausing, can't read {}: {}", device_id, e);
+                        unreadable = true;
+                    }
+                    speaking_since = None;
+                    std::thread::sleep(RETRY_INTERVAL);
+                    continue;
+                }
+                Ok(peak) => {
+                    if unreadable {
+                        log::info!("[speaking] resumed");
+                        unreadable = false;
+                    }
+                    if peak < threshold() {
+                        speaking_since = None;
+                    } else {
+                        let since = *speaking_since.get_or_insert_with(Instant::now);
+                        let rate_limited =
+                            last_reminder.is_some_and(|t| t.elapsed() < REMINDER_INTERVAL);
+                        if since.elapsed() >= SPEAKING_FOR && !rate_limited {
+                            last_reminder = Some(Instant::now());
+                            speaking_since = None;
+                            remind(&app, device_id, peak);
+                        }
+                    }
+                }
+            }
+            std::thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+}
+
+fn remind(app: &AppHandle, device_id: String, peak: f32) {
+    log::info!("[speaking] speaking while muted (peak {:.2})", peak);
+    let _ = app.emit(
+        SPEAKING_WHILE_MUTED,
+        SpeakingWhileMutedEvent { device_id, peak },
+    );
+    if SOUND.load(Ordering::SeqCst) {
+        crate::sound::play_reminder_sound();
+    }
+    if NOTIFY.load(Ordering::SeqCst) && !crate::focus_assist::suppressed() {
+        let _ = app
+            .notification()
+            .builder()
+            .title("TogMic")
+            .body(crate::i18n::translate("notifySpeakingWhileMuted"))
+            .show();
+    }
+}
+
+/// Sample `seconds` of background noise, then `seconds` of speech, on the device the
+/// monitor watches, and suggest a threshold. Blocks for twice `seconds`.
+pub fn calibrate(app: &AppHandle, seconds: u32) -> Result<VoiceCalibration, String> {
+    let seconds = seconds.clamp(1, MAX_CALIBRATION_SECS);
+    let device_id =
+        monitored_device(&app.state::<AppState>()).unwrap_or_else(|| "default-mic".to_string());
+    let _ = PlatformAudioController::init_thread();
+    let controller = PlatformAudioController::new()?;
+
+    let ambient = sample_phase(app, &controller, &device_id, "ambient", seconds)?;
+    let speaking = sample_phase(app, &controller, &device_id, "speaking", seconds)?;
+    let ambient = percentile(ambient, 0.95);
+    let speaking = percentile(speaking, 0.5);
+    if speaking <= ambient * 1.5 || speaking - ambient < 0.01 {
+        return Err(
+            "Speech was not clearly louder than the background; try again closer to the microphone"
+                .to_string(),
+        );
+    }
+
+    let calibration = VoiceCalibration {
+        ambient,
+        speaking,
+        threshold: (ambient + speaking) / 2.0,
+    };
+    log::info!("[speaking] calibrated: {:?}", calibration);
+    Ok(calibration)
+}
+
+fn sample_phase(
+    app: &AppHandle,
+    controller: &PlatformAudioController,
+    device_id: &str,
+    phase: &'static str,
+    seconds: u32,
+) -> Result<Vec<f32>, String> {
+    let _ = app.emit(
+        VOICE_CALIBRATION_PHASE,
+        CalibrationPhaseEvent { phase, seconds },
+    );
+    let until = Instant::now() + Duration::from_secs(u64::from(seconds));
+    let mut samples = Vec::new();
+    while Instant::now() < until {
+        samples.push(controller.get_peak_level(device_id)?);
+        std::thread::sleep(SAMPLE_INTERVAL);
+    }
+    Ok(samples)
+}
+
+fn percentile(mut samples: Vec<f32>, fraction: f32) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.sort_by(f32::total_cmp);
+    let index = ((samples.len() - 1) as f32 * fraction).round() as usize;
+    samples[index]
+}
@@ -0,0 +1,325 @@
+//! Usage statistics: how long the microphone was muted or live, how often it was toggled
+//! and from where, and how each profile was used.
+//!
+//! Every published mute state closes the running segment and opens a new one; segment
+//! durations are added to per-day totals (UTC days, split at midnight). The totals are
+//! kept in memory and written to `stats.json` beside the config every
+//! `SAVE_INTERVAL_SECS` and on exit, through a temporary file so a crash loses at most the
+//! last interval. Nothing is recorded while `AppSettings::collect_stats` is off.
+
+use crate::mute_state::MuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const STATS_FILE: &str = "stats.json";
+const SAVE_INTERVAL_SECS: u64 = 60;
+const SECS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileUsage {
+    #[serde(default)]
+    pub active_secs: u64,
+    #[serde(default)]
+    pub muted_secs: u64,
+    #[serde(default)]
+    pub toggles: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DayStats {
+    #[serde(default)]
+    muted_secs: u64,
+    #[serde(default)]
+    unmuted_secs: u64,
+    /// Toggle counts by source category (`hotkey`, `tray`, `ui`, `external`).
+    #[serde(default)]
+    toggles: BTreeMap<String, u64>,
+    /// Usage by profile id.
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileUsage>,
+}
+
+/// Contents of `stats.json`, keyed by day number (days since the Unix epoch, UTC).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsData {
+    #[serde(default)]
+    days: BTreeMap<u64, DayStats>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatsRange {
+    Today,
+    Week,
+    Month,
+    All,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaySummary {
+    /// Seconds since the Unix epoch at the start of the day (UTC).
+    pub date: u64,
+    pub muted_secs: u64,
+    pub unmuted_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsSummary {
+    pub muted_secs: u64,
+    pub unmuted_secs: u64,
+    pub toggles: BTreeMap<String, u64>,
+    pub profiles: BTreeMap<String, ProfileUsage>,
+    /// Per-day totals, oldest first.
+    pub days: Vec<DaySummary>,
+}
+
+/// State since the last published change.
+struct Segment {
+    muted: bool,
+    profile_id: Option<String>,
+    since: SystemTime,
+}
+
+#[derive(Default)]
+struct Tracker {
+    enabled: bool,
+    path: Option<PathBuf>,
+    data: StatsData,
+    segment: Option<Segment>,
+    dirty: bool,
+}
+
+impl Tracker {
+    /// Add the running segment's time up to `now` to the totals and restart it at `now`.
+    fn account(&mut self, now: SystemTime) {
+        let Some(segment) = self.segment.as_mut() else {
+            return;
+        };
+        let start = secs_since_epoch(segment.since);
+        let end = secs_since_epoch(now).max(start);
+        segment.since = now;
+
+        let mut t = start;
+        while t < end {
+            let day = t / SECS_PER_DAY;
+            let chunk = end.min((day + 1) * SECS_PER_DAY) - t;
+            let stats = self.data.days.entry(day).or_default();
+            if segment.muted {
+                stats.muted_secs += chunk;
+            } else {
+                stats.unmuted_secs += chunk;
+            }
+            if let Some(profile_id) = &segment.profile_id {
+                let usage = stats.profiles.entry(profile_id.clone()).or_default();
+                usage.active_secs += chunk;
+                if segment.muted {
+                    usage.muted_secs += chunk;
+                }
+            }
+            t += chunk;
+            self.dirty = true;
+        }
+    }
+}
+
+static TRACKER: Lazy<Mutex<Tracker>> = Lazy::new(|| Mutex::new(Tracker::default()));
+
+/// Load the saved statistics and start the periodic save. Recording starts with
+/// `configure`.
+pub fn init(app: AppHandle) {
+    let path = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join(STATS_FILE));
+    let data = path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    {
+        let mut tracker = TRACKER.lock_safe();
+        tracker.path = path;
+        tracker.data = data;
+    }
+
+    // Also writes the mute history, which batches its entries for this thread
+    std::thread::spawn(|| loop {
+        std::thread::sleep(Duration::from_secs(SAVE_INTERVAL_SECS));
+        save();
+        crate::history::flush();
+    });
+}
+
+/// Turn recording on or off. Turning it on starts a segment with the app's current state;
+/// turning it off accounts the running segment and records nothing more.
+pub fn configure(app: &AppHandle, enabled: bool) {
+    let mut tracker = TRACKER.lock_safe();
+    if tracker.enabled == enabled {
+        return;
+    }
+    let now = SystemTime::now();
+    tracker.account(now);
+    tracker.enabled = enabled;
+    tracker.segment = enabled.then(|| {
+        let state = app.state::<AppState>();
+        Segment {
+            muted: state.mute_state.is_muted(),
+            profile_id: current_profile_id(&state),
+            since: now,
+        }
+    });
+}
+
+/// Record a published mute state. Called from `publish_mute_state` for every source,
+/// including external changes picked up by polling.
+pub fn record(app: &AppHandle, mute_state: MuteState, source: &str) {
+    let mut tracker = TRACKER.lock_safe();
+    if !tracker.enabled {
+        return;
+    }
+    let now = SystemTime::now();
+    tracker.account(now);
+
+    // A mixed profile has a live microphone, so it counts as unmuted
+    let muted = mute_state.is_muted();
+    let profile_id = current_profile_id(&app.state::<AppState>());
+    let changed = tracker.segment.as_ref().is_some_and(|s| s.muted != muted);
+    if let (true, Some(category)) = (changed, category(source)) {
+        let stats = tracker
+            .data
+            .days
+            .entry(secs_since_epoch(now) / SECS_PER_DAY)
+            .or_default();
+        *stats.toggles.entry(category.to_string()).or_default() += 1;
+        if let Some(profile_id) = &profile_id {
+            stats
+                .profiles
+                .entry(profile_id.clone())
+                .or_default()
+                .toggles += 1;
+        }
+        tracker.dirty = true;
+    }
+    tracker.segment = Some(Segment {
+        muted,
+        profile_id,
+        since: now,
+    });
+}
+
+/// Totals over `range`, including the running segment.
+pub fn get(range: StatsRange) -> StatsSummary {
+    let mut tracker = TRACKER.lock_safe();
+    let now = SystemTime::now();
+    tracker.account(now);
+
+    let today = secs_since_epoch(now) / SECS_PER_DAY;
+    let first_day = match range {
+        StatsRange::Today => today,
+        StatsRange::Week => today.saturating_sub(6),
+        StatsRange::Month => today.saturating_sub(29),
+        StatsRange::All => 0,
+    };
+
+    let mut summary = StatsSummary::default();
+    for (day, stats) in tracker.data.days.range(first_day..) {
+        summary.muted_secs += stats.muted_secs;
+        summary.unmuted_secs += stats.unmuted_secs;
+        for (category, count) in &stats.toggles {
+            *summary.toggles.entry(category.clone()).or_default() += count;
+        }
+        for (profile_id, usage) in &stats.profiles {
+            let total = summary.profiles.entry(profile_id.clone()).or_default();
+            total.active_secs += usage.active_secs;
+            total.muted_secs += usage.muted_secs;
+            total.toggles += usage.toggles;
+        }
+        summary.days.push(DaySummary {
+            date: day * SECS_PER_DAY,
+            muted_secs: stats.muted_secs,
+            unmuted_secs: stats.unmuted_secs,
+        });
+    }
+    summary
+}
+
+/// Forget everything recorded so far, on disk as well.
+pub fn reset() -> Result<(), String> {
+    {
+        let mut tracker = TRACKER.lock_safe();
+        tracker.data = StatsData::default();
+        let now = SystemTime::now();
+        if let Some(segment) = tracker.segment.as_mut() {
+            segment.since = now;
+        }
+        tracker.dirty = true;
+    }
+    write()
+}
+
+/// Write the totals if anything changed. Called periodically and on exit.
+pub fn save() {
+    if let Err(e) = write() {
+        log::warn!("[stats] {}", e);
+    }
+}
+
+fn write() -> Result<(), String> {
+    let (path, json) = {
+        let mut tracker = TRACKER.lock_safe();
+        tracker.account(SystemTime::now());
+        if !tracker.dirty {
+            return Ok(());
+        }
+        let Some(path) = tracker.path.clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_string(&tracker.data)
+            .map_err(|e| format!("Failed to serialize stats: {}", e))?;
+        tracker.dirty = false;
+        (path, json)
+    };
+
+    // Write next to the target and rename over it, so a crash mid-write keeps the old file
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write stats: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to save stats: {}", e))
+}
+
+/// Toggle category of a `publish_mute_state` source. `None` for corrections that aren't a
+/// toggle (rollback, partial failure, startup sync, profile switch).
+fn category(source: &str) -> Option<&'static str> {
+    match source {
+        "hotkey" | "panic" => Some("hotkey"),
+        "tray" => Some("tray"),
+        "frontend" => Some("ui"),
+        "rollback" | "partial" | "startup" | "audioReady" | "profileSwitch" => None,
+        _ => Some("external"),
+    }
+}
+
+fn current_profile_id(state: &AppState) -> Option<String> {
+    state
+        .current_profile
+        .lock_safe()
+        .as_ref()
+        .map(|p| p.id.clone())
+}
+
+fn secs_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,309 @@
+//! Stream Deck plugin protocol, served by the control API at `/streamdeck`.
+//!
+//! Messages follow the Stream Deck SDK's `event` + `context` model so a plugin can pass
+//! its key contexts straight through and route replies back to the right key.
+//!
+//! Plugin → TogMic:
+//!   {"event": "subscribe", "context": "<key>"}     state now, then on every change
+//!   {"event": "unsubscribe", "context": "<key>"}
+//!   {"event": "toggle", "context": "<key>"}
+//!   {"event": "setProfile", "context": "<key>", "id": "<profile id or name>"}
+//!   {"event": "getIcons", "context": "<key>"}
+//!
+//! TogMic → plugin:
+//!   {"event": "state", "context": "<key>", "payload": {"muted": true, "profile": {...}}}
+//!   {"event": "icons", "context": "<key>",
+//!    "payload": {"muted": "<base64 png>", "unmuted": "<base64 png>", "current": "muted"}}
+//!   {"event": "error", "context": "<key>", "payload": {"message": "..."}}
+//!
+//! State pushes are driven by `api::publish_state`, so they fire for changes from any
+//! source: hotkeys, the tray, the frontend and external changes picked up by the
+//! background sync.
+
+use crate::commands::external::{self, ControlCommand};
+use crate::{AppHandle, AppState, MutexExt};
+use base64::Engine;
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tauri::Manager;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// URL path the control API serves this protocol on.
+pub const PATH: &str = "/streamdeck";
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum PluginMessage {
+    Subscribe {
+        context: String,
+    },
+    Unsubscribe {
+        context: String,
+    },
+    Toggle {
+        #[serde(default)]
+        context: Value,
+    },
+    SetProfile {
+        #[serde(default)]
+        context: Value,
+        id: String,
+    },
+    GetIcons {
+        #[serde(default)]
+        context: Value,
+    },
+}
+
+type Sink = SplitSink<WebSocketStream<TcpStream>, Message>;
+
+pub async fn serve(
+    app: AppHandle,
+    ws: WebSocketStream<TcpStream>,
+    mut events: broadcast::Receiver<bool>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let (mut sink, mut source) = ws.split();
+    let mut subscribed: HashSet<String> = HashSet::new();
+
+    loop {
+        tokio::select! {
+            incoming = source.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let replies = handle_message(&app, &text, &mut subscribed).await;
+                if send_all(&mut sink, replies).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                match event {
+                    Ok(muted) => {
+                        let pushes = state_pushes(&subscribed, muted, &current_profile(&app));
+                        if send_all(&mut sink, pushes).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Missed some pushes; the next one carries the full state anyway
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            _ = shutdown.changed() => {
+                let _ = sink.send(Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+}
+
+async fn send_all(sink: &mut Sink, messages: Vec<Value>) -> Result<(), ()> {
+    for message in messages {
+        sink.send(Message::Text(message.to_string()))
+            .await
+            .map_err(|_| ())?;
+    }
+    Ok(())
+}
+
+fn current_profile(app: &AppHandle) -> Value {
+    let state = app.state::<AppState>();
+    let profile = state.current_profile.lock_safe();
+    match profile.as_ref() {
+        Some(p) => json!({ "id": p.id, "name": p.name }),
+        None => Value::Null,
+    }
+}
+
+fn state_message(context: &Value, muted: bool, profile: &Value) -> Value {
+    json!({
+        "event": "state",
+        "context": context,
+        "payload": { "muted": muted, "profile": profile },
+    })
+}
+
+/// One state message per subscribed key.
+fn state_pushes(subscribed: &HashSet<String>, muted: bool, profile: &Value) -> Vec<Value> {
+    subscribed
+        .iter()
+        .map(|context| state_message(&json!(context), muted, profile))
+        .collect()
+}
+
+fn icons_message(context: &Value, muted: bool) -> Value {
+    let engine = base64::engine::general_purpose::STANDARD;
+    json!({
+        "event": "icons",
+        "context": context,
+        "payload": {
+            "muted": engine.encode(crate::tray_icon_png(true)),
+            "unmuted": engine.encode(crate::tray_icon_png(false)),
+            "current": if muted { "muted" } else { "unmuted" },
+        },
+    })
+}
+
+fn error_message(context: &Value, message: &str) -> Value {
+    json!({
+        "event": "error",
+        "context": context,
+        "payload": { "message": message },
+    })
+}
+
+fn current_state(app: &AppHandle, context: &Value) -> Value {
+    let muted = app.state::<AppState>().mute_state.is_muted();
+    state_message(context, muted, &current_profile(app))
+}
+
+async fn handle_message(
+    app: &AppHandle,
+    text: &str,
+    subscribed: &mut HashSet<String>,
+) -> Vec<Value> {
+    let message: PluginMessage = match serde_json::from_str(text) {
+        Ok(m) => m,
+        Err(e) => return vec![error_message(&Value::Null, &e.to_string())],
+    };
+
+    match message {
+        PluginMessage::Subscribe { context } => {
+            let reply = current_state(app, &json!(context));
+            subscribed.insert(context);
+            vec![reply]
+        }
+        PluginMessage::Unsubscribe { context } => {
+            subscribed.remove(&context);
+            Vec::new()
+        }
+        PluginMessage::Toggle { context } => run(app, &context, ControlCommand::Toggle).await,
+        PluginMessage::SetProfile { context, id } => {
+            run(app, &context, ControlCommand::SetProfile { id }).await
+        }
+        PluginMessage::GetIcons { context } => {
+            let muted = app.state::<AppState>().mute_state.is_muted();
+            vec![icons_message(&context, muted)]
+        }
+    }
+}
+
+/// Run an action through the shared dispatcher and answer with the resulting state.
+async fn run(app: &AppHandle, context: &Value, command: ControlCommand) -> Vec<Value> {
+    let worker_app = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        external::dispatch(&worker_app, "streamDeck", command)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(_)) => vec![current_state(app, context)],
+        Ok(Err(e)) => vec![error_message(context, &e)],
+        Err(e) => vec![error_message(context, &e.to_string())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<PluginMessage, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    #[test]
+    fn parses_plugin_messages() {
+        assert!(matches!(
+            parse(r#"{"event":"subscribe","context":"key1"}"#),
+            Ok(PluginMessage::Subscribe { context }) if context == "key1"
+        ));
+        assert!(matches!(
+            parse(r#"{"event":"unsubscribe","context":"key1"}"#),
+            Ok(PluginMessage::Unsubscribe { context }) if context == "key1"
+        ));
+        assert!(matches!(
+            parse(r#"{"event":"toggle"}"#),
+            Ok(PluginMessage::Toggle {
+                context: Value::Null
+            })
+        ));
+        assert!(matches!(
+            parse(r#"{"event":"setProfile","context":"key2","id":"Meetings"}"#),
+            Ok(PluginMessage::SetProfile { context, id }) if context == json!("key2") && id == "Meetings"
+        ));
+        assert!(matches!(
+            parse(r#"{"event":"getIcons","context":{"device":"abc"}}"#),
+            Ok(PluginMessage::GetIcons { context }) if context == json!({ "device": "abc" })
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        assert!(parse(r#"{"event":"explode","context":"key1"}"#).is_err());
+        assert!(parse(r#"{"event":"subscribe"}"#).is_err());
+        assert!(parse(r#"{"event":"setProfile","context":"key1"}"#).is_err());
+        assert!(parse(r#"{"context":"key1"}"#).is_err());
+        assert!(parse("toggle").is_err());
+    }
+
+    #[test]
+    fn state_and_error_messages_echo_the_context() {
+        let profile = json!({ "id": "p1", "name": "Meetings" });
+        assert_eq!(
+            state_message(&json!("key1"), true, &profile),
+            json!({
+                "event": "state",
+                "context": "key1",
+                "payload": { "muted": true, "profile": { "id": "p1", "name": "Meetings" } },
+            })
+        );
+        assert_eq!(
+            error_message(&Value::Null, "boom"),
+            json!({ "event": "error", "context": null, "payload": { "message": "boom" } })
+        );
+    }
+
+    #[test]
+    fn pushes_state_to_every_subscribed_key() {
+        let mut subscribed = HashSet::new();
+        assert!(state_pushes(&subscribed, true, &Value::Null).is_empty());
+
+        subscribed.insert("key1".to_string());
+        subscribed.insert("key2".to_string());
+        let pushes = state_pushes(&subscribed, false, &Value::Null);
+        let mut contexts: Vec<&str> = pushes
+            .iter()
+            .map(|p| p["context"].as_str().unwrap())
+            .collect();
+        contexts.sort();
+        assert_eq!(contexts, ["key1", "key2"]);
+        assert!(pushes.iter().all(|p| p["payload"]["muted"] == json!(false)));
+    }
+
+    #[test]
+    fn icons_are_base64_tray_pngs() {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let message = icons_message(&json!("key1"), true);
+        assert_eq!(message["event"], json!("icons"));
+        assert_eq!(message["payload"]["current"], json!("muted"));
+        for key in ["muted", "unmuted"] {
+            let png = engine
+                .decode(message["payload"][key].as_str().unwrap())
+                .unwrap();
+            assert!(png.starts_with(b"\x89PNG"));
+        }
+        assert_eq!(
+            icons_message(&json!("key1"), false)["payload"]["current"],
+            json!("unmuted")
+        );
+    }
+}
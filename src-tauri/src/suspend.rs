@@ -0,0 +1,98 @@
+//! Temporarily giving the global hotkeys back to other applications.
+//!
+//! `suspend` unregisters every shortcut, including the panic hotkey, but keeps the active
+//! profile; hotkey registrations requested while suspended are skipped. `resume`
+//! registers the active profile's hotkeys again. A suspension can end by itself after a
+//! number of minutes: each suspension bumps a generation counter, and the timer thread
+//! only resumes if its generation is still the current one. The tray keeps toggling the
+//! microphone throughout.
+
+use crate::events::{self, EventKind};
+use crate::{AppHandle, AppState, MutexExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::Manager;
+
+pub const HOTKEYS_SUSPENDED_CHANGED: &str = "hotkeys-suspended-changed";
+
+/// Incremented by every suspend and resume, so a stale auto-resume timer does nothing.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HotkeysSuspendedChangedEvent {
+    suspended: bool,
+    /// When the suspension ends by itself (milliseconds since the Unix epoch).
+    resume_at: Option<u64>,
+}
+
+pub fn is_suspended(state: &AppState) -> bool {
+    state.hotkeys_suspended.load(Ordering::SeqCst)
+}
+
+/// Unregister all hotkeys until `resume`, or for `minutes` (0: until resumed).
+pub fn suspend(app: &AppHandle, minutes: u32) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    state.hotkeys_suspended.store(true, Ordering::SeqCst);
+
+    let result = crate::unregister_all_shortcuts(app, &state);
+
+    let resume_at = (minutes > 0).then(|| {
+        let delay = Duration::from_secs(u64::from(minutes) * 60);
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            if GENERATION.load(Ordering::SeqCst) == generation {
+                log::info!("[hotkey] suspension timed out");
+                if let Err(e) = resume(&app) {
+                    app.state::<AppState>().record_error("resume_hotkeys", e);
+                }
+            }
+        });
+        events::now_millis() + delay.as_millis() as u64
+    });
+
+    log::info!("[hotkey] hotkeys suspended for {} min", minutes);
+    changed(app, true, resume_at);
+    result
+}
+
+/// Register the active profile's hotkeys (and the global ones) again.
+pub fn resume(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if !state.hotkeys_suspended.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let profile = state.current_profile.lock_safe().clone();
+    let result = match profile {
+        Some(profile) => {
+            crate::do_register_hotkey(&profile.toggle_key, profile.ignore_modifiers, app, &state)
+        }
+        None => {
+            crate::register_global_hotkeys(app, &state);
+            Ok(())
+        }
+    };
+
+    log::info!("[hotkey] hotkeys resumed");
+    changed(app, false, None);
+    result
+}
+
+fn changed(app: &AppHandle, suspended: bool, resume_at: Option<u64>) {
+    events::emit(
+        app,
+        EventKind::Hotkey,
+        HOTKEYS_SUSPENDED_CHANGED,
+        HotkeysSuspendedChangedEvent {
+            suspended,
+            resume_at,
+        },
+    );
+    crate::refresh_tray_tooltip(app);
+    crate::rebuild_tray_menu(app);
+}
@@ -0,0 +1,115 @@
+//! Restarts the background sync thread (the polling loop outside Windows) when it dies.
+//!
+//! Every attempt runs on a fresh thread, so it initializes the audio subsystem and creates
+//! its controller anew. When the thread panics or returns, the watchdog waits, doubling
+//! the delay from `INITIAL_BACKOFF` up to `MAX_BACKOFF`, emits `sync-thread-restarted` and
+//! starts the next attempt. An attempt that ran for `STABLE_AFTER` counts as a success
+//! again. After `MAX_FAILURES` failures in a row the watchdog gives up, emits
+//! `sync-thread-failed` and leaves the state in `status()` for the dashboard and the
+//! diagnostics report.
+
+// Windows gets change notifications instead of a polling loop, so only `status` is used
+// there.
+#![cfg_attr(target_os = "windows", allow(dead_code))]
+
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+pub const SYNC_THREAD_RESTARTED: &str = "sync-thread-restarted";
+pub const SYNC_THREAD_FAILED: &str = "sync-thread-failed";
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const STABLE_AFTER: Duration = Duration::from_secs(300);
+const MAX_FAILURES: u32 = 6;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncThreadStatus {
+    /// Whether a watchdog was started; false on Windows, which has no polling loop.
+    pub supervised: bool,
+    /// Restarts over the whole session.
+    pub restarts: u32,
+    pub consecutive_failures: u32,
+    /// Panic message of the last failure.
+    pub last_failure: Option<String>,
+    /// Set once the watchdog stopped retrying; syncing is off until TogMic restarts.
+    pub gave_up: bool,
+}
+
+static STATUS: Lazy<Mutex<SyncThreadStatus>> =
+    Lazy::new(|| Mutex::new(SyncThreadStatus::default()));
+
+pub fn status() -> SyncThreadStatus {
+    STATUS.lock_safe().clone()
+}
+
+/// Run `body` on a sync thread under the watchdog. `body` is expected to loop forever.
+pub fn spawn(app: AppHandle, body: impl Fn(&AppHandle) + Send + Sync + 'static) {
+    let body = Arc::new(body);
+    STATUS.lock_safe().supervised = true;
+    std::thread::spawn(move || {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let started = Instant::now();
+            let attempt_app = app.clone();
+            let attempt_body = body.clone();
+            let joined = std::thread::Builder::new()
+                .name("sync".to_string())
+                .spawn(move || attempt_body(&attempt_app))
+                .map_err(|e| format!("Failed to start the sync thread: {}", e))
+                .and_then(|thread| thread.join().map_err(|payload| panic_text(&*payload)));
+            let failure = match joined {
+                Ok(()) => "The sync thread exited".to_string(),
+                Err(e) => e,
+            };
+
+            if started.elapsed() >= STABLE_AFTER {
+                backoff = INITIAL_BACKOFF;
+                STATUS.lock_safe().consecutive_failures = 0;
+            }
+            let status = {
+                let mut status = STATUS.lock_safe();
+                status.consecutive_failures += 1;
+                status.last_failure = Some(failure.clone());
+                status.gave_up = status.consecutive_failures >= MAX_FAILURES;
+                status.clone()
+            };
+            if status.gave_up {
+                log::error!(
+                    "[sync] thread failed {} times in a row, giving up: {}",
+                    status.consecutive_failures,
+                    failure
+                );
+                let _ = app.emit(SYNC_THREAD_FAILED, &status);
+                return;
+            }
+
+            log::warn!(
+                "[sync] thread died ({}), restarting in {} s",
+                failure,
+                backoff.as_secs()
+            );
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            let status = {
+                let mut status = STATUS.lock_safe();
+                status.restarts += 1;
+                status.clone()
+            };
+            let _ = app.emit(SYNC_THREAD_RESTARTED, &status);
+        }
+    });
+}
+
+fn panic_text(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "The sync thread panicked".to_string())
+}
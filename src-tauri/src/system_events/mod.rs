@@ -0,0 +1,52 @@
+//! Operating system session events (workstation lock/unlock, resume from sleep, remote
+//! desktop and fast user switching), for features like muting when the user walks away.
+//!
+//! Each platform provides a `SessionEventSource`; platforms without an implementation get
+//! `UnsupportedSessionEvents`, whose `start` fails so callers can log it.
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+pub use windows::WindowsSessionEvents as PlatformSessionEvents;
+
+#[cfg(not(target_os = "windows"))]
+pub use UnsupportedSessionEvents as PlatformSessionEvents;
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    Locked,
+    Unlocked,
+    /// The system woke up from sleep or hibernation.
+    Resumed,
+    /// The session moved between the console and a remote desktop connection.
+    Connection(SessionConnection),
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionConnection {
+    ConsoleConnected,
+    ConsoleDisconnected,
+    RemoteConnected,
+    RemoteDisconnected,
+}
+
+pub type SessionEventHandler = Box<dyn Fn(SessionEvent) + Send + 'static>;
+
+pub trait SessionEventSource {
+    /// Start delivering session events to `handler` on a background thread. Returns once
+    /// the platform listener is registered.
+    fn start(handler: SessionEventHandler) -> Result<(), String>;
+}
+
+#[cfg(not(target_os = "windows"))]
+pub struct UnsupportedSessionEvents;
+
+#[cfg(not(target_os = "windows"))]
+impl SessionEventSource for UnsupportedSessionEvents {
+    fn start(_handler: SessionEventHandler) -> Result<(), String> {
+        Err("Session lock events are not supported on this platform".to_string())
+    }
+}
@@ -0,0 +1,135 @@
+use super::{SessionConnection, SessionEvent, SessionEventHandler, SessionEventSource};
+use crate::MutexExt;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use windows::core::w;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::RemoteDesktop::{
+    WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW,
+    TranslateMessage, MSG, PBT_APMRESUMEAUTOMATIC, WINDOW_EX_STYLE, WM_POWERBROADCAST,
+    WM_WTSSESSION_CHANGE, WNDCLASSW, WS_OVERLAPPED, WTS_CONSOLE_CONNECT, WTS_CONSOLE_DISCONNECT,
+    WTS_REMOTE_CONNECT, WTS_REMOTE_DISCONNECT, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
+};
+
+/// Handler called from the window procedure.
+static HANDLER: Lazy<Mutex<Option<SessionEventHandler>>> = Lazy::new(|| Mutex::new(None));
+
+/// Receives `WM_WTSSESSION_CHANGE` through a hidden window registered with
+/// `WTSRegisterSessionNotification`, and `WM_POWERBROADCAST`, which every top-level window
+/// gets.
+pub struct WindowsSessionEvents;
+
+impl SessionEventSource for WindowsSessionEvents {
+    fn start(handler: SessionEventHandler) -> Result<(), String> {
+        *HANDLER.lock_safe() = Some(handler);
+
+        // The window has to be created on the thread that pumps its messages
+        let (tx, rx) = std::sync::mpsc::channel::<Result<(), String>>();
+        std::thread::spawn(move || unsafe {
+            let hwnd = match create_window() {
+                Ok(hwnd) => hwnd,
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    return;
+                }
+            };
+            if let Err(e) = WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) {
+                let _ = tx.send(Err(format!(
+                    "Failed to register for session notifications: {}",
+                    e
+                )));
+                return;
+            }
+            let _ = tx.send(Ok(()));
+
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        });
+
+        rx.recv()
+            .unwrap_or_else(|_| Err("Session event thread exited".to_string()))
+    }
+}
+
+unsafe fn create_window() -> Result<HWND, String> {
+    let instance =
+        GetModuleHandleW(None).map_err(|e| format!("Failed to get module handle: {}", e))?;
+    let class_name = w!("TogMicSessionEvents");
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance.into(),
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        return Err("Failed to register session event window class".to_string());
+    }
+
+    // Never shown; a regular (not message-only) window so it receives session broadcasts
+    let hwnd = CreateWindowExW(
+        WINDOW_EX_STYLE::default(),
+        class_name,
+        w!("TogMic session events"),
+        WS_OVERLAPPED,
+        0,
+        0,
+        0,
+        0,
+        None,
+        None,
+        instance,
+        None,
+    );
+    if hwnd.0 == 0 {
+        return Err("Failed to create session event window".to_string());
+    }
+    Ok(hwnd)
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == WM_WTSSESSION_CHANGE {
+        let event = match wparam.0 as u32 {
+            WTS_SESSION_LOCK => Some(SessionEvent::Locked),
+            WTS_SESSION_UNLOCK => Some(SessionEvent::Unlocked),
+            WTS_CONSOLE_CONNECT => Some(SessionEvent::Connection(
+                SessionConnection::ConsoleConnected,
+            )),
+            WTS_CONSOLE_DISCONNECT => Some(SessionEvent::Connection(
+                SessionConnection::ConsoleDisconnected,
+            )),
+            WTS_REMOTE_CONNECT => {
+                Some(SessionEvent::Connection(SessionConnection::RemoteConnected))
+            }
+            WTS_REMOTE_DISCONNECT => Some(SessionEvent::Connection(
+                SessionConnection::RemoteDisconnected,
+            )),
+            _ => None,
+        };
+        if let Some(event) = event {
+            if let Some(handler) = HANDLER.lock_safe().as_ref() {
+                handler(event);
+            }
+        }
+        return LRESULT(0);
+    }
+    // Sent on every resume, whether or not a user is present yet
+    if msg == WM_POWERBROADCAST && wparam.0 as u32 == PBT_APMRESUMEAUTOMATIC {
+        if let Some(handler) = HANDLER.lock_safe().as_ref() {
+            handler(SessionEvent::Resumed);
+        }
+        return LRESULT(1);
+    }
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
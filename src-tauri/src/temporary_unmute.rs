@@ -0,0 +1,142 @@
+//! Unmuting for a fixed time ("unmute for 30 seconds").
+//!
+//! `start` unmutes through the regular path and re-mutes once the deadline has passed.
+//! Meanwhile a timer thread emits `temporary-unmute-started { secondsRemaining }` every
+//! second for the countdown in the UI. Starting again while a timer runs extends its
+//! deadline instead of starting a second one. Any other mute change cancels the timer
+//! (`cancel`), so it never re-mutes over a manual toggle; as in `suspend`, a generation
+//! counter tells the thread it has been cancelled.
+
+use crate::events::{self, EventKind};
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+pub const TEMPORARY_UNMUTE_STARTED: &str = "temporary-unmute-started";
+pub const TEMPORARY_UNMUTE_ENDED: &str = "temporary-unmute-ended";
+
+/// `source` of the re-mute at the end.
+pub const SOURCE_END: &str = "temporaryUnmute";
+
+const MAX_SECONDS: u32 = 60 * 60;
+
+/// When the running timer re-mutes; `None` while none runs.
+static DEADLINE: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+/// Incremented by every start and cancellation, so a stale timer thread does nothing.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TickEvent {
+    seconds_remaining: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EndedEvent {
+    /// "expired" (re-muted) or "cancelled" (another mute change came first).
+    reason: &'static str,
+}
+
+/// Unmute for `seconds`, or add `seconds` to the running timer.
+pub fn start(app: &AppHandle, seconds: u32, source: &str) -> Result<(), String> {
+    if seconds == 0 {
+        return Err("Temporary unmute needs at least one second".to_string());
+    }
+    let duration = Duration::from_secs(u64::from(seconds.min(MAX_SECONDS)));
+
+    if let Some(deadline) = DEADLINE.lock_safe().as_mut() {
+        *deadline += duration;
+        log::info!("[temporary] extended by {} s", duration.as_secs());
+        return Ok(());
+    }
+
+    // Cancels nothing yet, since no deadline is set
+    let state = app.state::<AppState>();
+    crate::set_mute_internal(&state, app, false, false, source)?;
+
+    let generation = {
+        let mut deadline = DEADLINE.lock_safe();
+        *deadline = Some(Instant::now() + duration);
+        GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+    };
+    log::info!("[temporary] unmuted for {} s", duration.as_secs());
+
+    let app = app.clone();
+    std::thread::spawn(move || run(app, generation));
+    Ok(())
+}
+
+/// Stop the running timer without re-muting; called for every other mute change.
+pub fn cancel(app: &AppHandle) {
+    {
+        let mut deadline = DEADLINE.lock_safe();
+        if deadline.take().is_none() {
+            return;
+        }
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+    log::info!("[temporary] cancelled by a mute change");
+    ended(app, "cancelled");
+}
+
+/// Seconds until the running timer re-mutes.
+pub fn remaining_secs() -> Option<u64> {
+    DEADLINE
+        .lock_safe()
+        .map(|deadline| ceil_secs(deadline.saturating_duration_since(Instant::now())))
+}
+
+fn run(app: AppHandle, generation: u64) {
+    loop {
+        let remaining = {
+            let deadline = DEADLINE.lock_safe();
+            match *deadline {
+                Some(deadline) if GENERATION.load(Ordering::SeqCst) == generation => {
+                    deadline.saturating_duration_since(Instant::now())
+                }
+                _ => return,
+            }
+        };
+        if remaining.is_zero() {
+            break;
+        }
+        let _ = app.emit(
+            TEMPORARY_UNMUTE_STARTED,
+            TickEvent {
+                seconds_remaining: ceil_secs(remaining),
+            },
+        );
+        std::thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+
+    {
+        let mut deadline = DEADLINE.lock_safe();
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        deadline.take();
+    }
+    log::info!("[temporary] time is up, muting");
+    ended(&app, "expired");
+    let state = app.state::<AppState>();
+    if let Err(e) = crate::set_mute_internal(&state, &app, true, false, SOURCE_END) {
+        state.record_error("temporary_unmute", e);
+    }
+}
+
+fn ended(app: &AppHandle, reason: &'static str) {
+    events::emit(
+        app,
+        EventKind::Mute,
+        TEMPORARY_UNMUTE_ENDED,
+        EndedEvent { reason },
+    );
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_millis().div_ceil(1000) as u64
+}
@@ -0,0 +1,88 @@
+//! Builders for unit tests.
+
+#[cfg(feature = "mock-audio")]
+use crate::audio::mock::{self, MockDevice};
+use crate::audio::AudioDevice;
+#[cfg(feature = "mock-audio")]
+use crate::audio::{AudioController, PlatformAudioController};
+use crate::HotkeyProfile;
+#[cfg(feature = "mock-audio")]
+use crate::{AppHandle, AppRuntime, AppState, MutexExt};
+use serde_json::{json, Value};
+#[cfg(feature = "mock-audio")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "mock-audio")]
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "mock-audio")]
+use tauri::{Listener, Manager};
+
+/// Held by tests using the mock devices, which all tests share.
+#[cfg(feature = "mock-audio")]
+static MOCK_DEVICES: Mutex<()> = Mutex::new(());
+
+/// A profile from its JSON form, with `id`, `name` and `toggleKey` filled in unless given.
+pub fn profile(fields: Value) -> HotkeyProfile {
+    let mut value = json!({ "id": "p1", "name": "Profile", "toggleKey": "F13" });
+    if let (Some(base), Some(fields)) = (value.as_object_mut(), fields.as_object()) {
+        base.extend(fields.clone());
+    }
+    serde_json::from_value(value).expect("valid profile")
+}
+
+/// An active capture device named after its id.
+pub fn device(id: &str) -> AudioDevice {
+    serde_json::from_value(json!({ "id": id, "name": format!("Mic {}", id), "isDefault": false }))
+        .expect("valid device")
+}
+
+/// Replace the mock devices by `devices`, for as long as the returned guard is held.
+#[cfg(feature = "mock-audio")]
+pub fn mock_devices(devices: Vec<MockDevice>) -> MutexGuard<'static, ()> {
+    let guard = MOCK_DEVICES.lock().unwrap_or_else(|e| e.into_inner());
+    mock::set_devices(devices);
+    guard
+}
+
+/// An unmuted mock device named after its id.
+#[cfg(feature = "mock-audio")]
+pub fn mock_device(id: &str, is_default: bool) -> MockDevice {
+    MockDevice::new(id, &format!("Mic {}", id), is_default)
+}
+
+/// An app on the mock runtime managing `state`, whose audio controller and worker run on
+/// the mock devices.
+#[cfg(feature = "mock-audio")]
+pub fn mock_app(mut state: AppState) -> tauri::App<AppRuntime> {
+    static APPS: AtomicUsize = AtomicUsize::new(0);
+    let app = tauri::test::mock_builder()
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .build(tauri::test::mock_context(tauri::test::noop_assets()))
+        .expect("mock app");
+    let n = APPS.fetch_add(1, Ordering::SeqCst);
+    state.config_location.store = temp_dir(&format!("app-{}", n)).join("config.json");
+    *state.audio_controller.lock_safe() = PlatformAudioController::new().ok();
+    app.manage(state);
+    crate::start_audio_worker(app.handle().clone());
+    app
+}
+
+/// The payloads of every `event` emitted on `app` from now on.
+#[cfg(feature = "mock-audio")]
+pub fn recorded_events(app: &AppHandle, event: &str) -> Arc<Mutex<Vec<Value>>> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    app.listen_any(event, move |event| {
+        if let Ok(payload) = serde_json::from_str(event.payload()) {
+            sink.lock_safe().push(payload);
+        }
+    });
+    events
+}
+
+/// An empty directory for `name`, unique to the test run.
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("togmic-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("temp dir");
+    dir
+}
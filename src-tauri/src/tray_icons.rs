@@ -0,0 +1,362 @@
+//! Tray icons generated at runtime.
+//!
+//! The embedded icons are 64 px, and the OS scales them down to the tray size itself,
+//! which blurs at fractional scaling. Instead the state's icon is resized here to the
+//! tray's size in physical pixels on the monitor the tray is on, and a colored state dot
+//! is drawn over its corner: red for muted, green for unmuted, amber for mixed. For
+//! profiles with more than one device the dot carries the number of muted devices.
+//! Variants are cached per `IconKey`. With `AppSettings::classic_tray_icons`, and on
+//! macOS where the tray takes monochrome template images, the embedded icons stay.
+//!
+//! The `TrayIconStyle`s other than `Default` don't rely on color at all: they draw the
+//! glyph in the taskbar's foreground color and tell the states apart by shape, so they
+//! also work as macOS template images.
+
+use crate::mute_state::{DeviceMuteState, MuteState};
+use crate::{AppHandle, MutexExt};
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::image::Image as TauriImage;
+
+/// Tray icon size in logical pixels.
+const TRAY_SIZE: f64 = if cfg!(target_os = "windows") {
+    16.0
+} else {
+    22.0
+};
+
+const MUTED_COLOR: Rgba<u8> = Rgba([0xE5, 0x39, 0x35, 0xFF]);
+const UNMUTED_COLOR: Rgba<u8> = Rgba([0x43, 0xA0, 0x47, 0xFF]);
+const MIXED_COLOR: Rgba<u8> = Rgba([0xFB, 0x8C, 0x00, 0xFF]);
+const WHITE: Rgba<u8> = Rgba([0xFF, 0xFF, 0xFF, 0xFF]);
+const BLACK: Rgba<u8> = Rgba([0x00, 0x00, 0x00, 0xFF]);
+
+/// Digits 0-9 as 3×5 bitmaps, one row per entry, most significant bit on the left.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// How the tray icons show the mute state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrayIconStyle {
+    /// The red and green icons.
+    #[default]
+    Default,
+    /// Monochrome: a filled disc with the glyph cut out when muted, the glyph in a ring
+    /// when unmuted.
+    HighContrast,
+    /// Monochrome: the microphone with an X when muted, a checkmark when unmuted.
+    Symbols,
+}
+
+/// Everything a generated icon depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IconKey {
+    pub state: MuteState,
+    pub style: TrayIconStyle,
+    /// Whether to draw the state dot and device count.
+    pub badge: bool,
+    /// Muted devices (if any), for profiles with more than one device.
+    pub muted_count: Option<usize>,
+    pub dark_mode: bool,
+    /// Edge length in physical pixels.
+    pub size: u32,
+}
+
+static CLASSIC: AtomicBool = AtomicBool::new(false);
+static STYLE: Mutex<TrayIconStyle> = Mutex::new(TrayIconStyle::Default);
+static CACHE: Lazy<Mutex<HashMap<IconKey, TauriImage<'static>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn configure(classic: bool, style: TrayIconStyle) {
+    CLASSIC.store(classic, Ordering::SeqCst);
+    *STYLE.lock_safe() = style;
+}
+
+/// The icon to show for `state`, or `None` for the embedded icons.
+pub fn key(
+    app: &AppHandle,
+    state: MuteState,
+    devices: &[DeviceMuteState],
+    dark_mode: bool,
+) -> Option<IconKey> {
+    let style = *STYLE.lock_safe();
+    let badge = !cfg!(target_os = "macos") && !CLASSIC.load(Ordering::SeqCst);
+    if !badge && style == TrayIconStyle::Default {
+        return None;
+    }
+    let muted_count = (badge && devices.len() > 1)
+        .then(|| devices.iter().filter(|device| device.muted).count())
+        .filter(|&count| count > 0);
+    Some(IconKey {
+        state,
+        style,
+        badge,
+        muted_count,
+        dark_mode,
+        size: (TRAY_SIZE * tray_scale_factor(app)).round() as u32,
+    })
+}
+
+/// Scale factor of the monitor the tray icon is on, else of the primary monitor. Without a
+/// tray icon there is nothing to scale.
+fn tray_scale_factor(app: &AppHandle) -> f64 {
+    let Some(tray) = app.tray_by_id("main-tray") else {
+        return 1.0;
+    };
+    let tray_monitor = tray.rect().ok().flatten().and_then(|rect| {
+        let position = rect.position.to_physical::<f64>(1.0);
+        app.monitor_from_point(position.x, position.y)
+            .ok()
+            .flatten()
+    });
+    tray_monitor
+        .or_else(|| app.primary_monitor().ok().flatten())
+        .map_or(1.0, |monitor| monitor.scale_factor())
+}
+
+/// The icon for `key` unless cached, generated from `base`, which gives the embedded
+/// icon of a state for the key's theme.
+pub fn icon(key: IconKey, base: impl Fn(MuteState) -> TauriImage<'static>) -> TauriImage<'static> {
+    if let Some(icon) = CACHE.lock_safe().get(&key) {
+        return icon.clone();
+    }
+    let icon = generate(key, &base);
+    CACHE.lock_safe().insert(key, icon.clone());
+    icon
+}
+
+fn generate(key: IconKey, base: &dyn Fn(MuteState) -> TauriImage<'static>) -> TauriImage<'static> {
+    let pixels = key.size.clamp(8, 256);
+    let glyph = |state: MuteState| {
+        let image = base(state);
+        RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+            .map(|image| imageops::resize(&image, pixels, pixels, FilterType::Lanczos3))
+            .unwrap_or_else(|| RgbaImage::new(pixels, pixels))
+    };
+    // Template images are recolored by macOS; elsewhere contrast with the taskbar
+    let (foreground, background) = if cfg!(target_os = "macos") {
+        (BLACK, WHITE)
+    } else if key.dark_mode {
+        (WHITE, BLACK)
+    } else {
+        (BLACK, WHITE)
+    };
+
+    let mut icon = match key.style {
+        TrayIconStyle::Default => glyph(key.state),
+        TrayIconStyle::HighContrast => {
+            let filled = || filled_disc(&glyph(MuteState::Muted), foreground);
+            let ringed = || ringed_glyph(&glyph(MuteState::Unmuted), foreground);
+            match key.state {
+                MuteState::Muted => filled(),
+                MuteState::Unmuted => ringed(),
+                MuteState::Mixed => split_diagonally(filled(), &ringed()),
+            }
+        }
+        TrayIconStyle::Symbols => symbol(&glyph(MuteState::Unmuted), key.state, foreground),
+    };
+
+    // The monochrome styles show the state by shape and only need the dot for a count
+    if key.badge && (key.style == TrayIconStyle::Default || key.muted_count.is_some()) {
+        let (color, digit_color) = match (key.style, key.state) {
+            (TrayIconStyle::Default, MuteState::Muted) => (MUTED_COLOR, WHITE),
+            (TrayIconStyle::Default, MuteState::Unmuted) => (UNMUTED_COLOR, WHITE),
+            (TrayIconStyle::Default, MuteState::Mixed) => (MIXED_COLOR, WHITE),
+            _ => (foreground, background),
+        };
+        let size = pixels as f32;
+        // Room for a digit when there is a count
+        let radius = size * if key.muted_count.is_some() { 0.3 } else { 0.2 };
+        let center = size - radius - 0.5;
+        // A transparent ring around the dot separates it from the glyph
+        let gap = (size / 16.0).max(1.0);
+        draw_circle(&mut icon, center, center, radius + gap, None);
+        draw_circle(&mut icon, center, center, radius, Some(color));
+        if let Some(count) = key.muted_count {
+            draw_digit(&mut icon, center, radius, count.min(9), digit_color);
+        }
+    }
+    TauriImage::new_owned(icon.into_raw(), pixels, pixels)
+}
+
+/// A disc in `color` with `glyph` (shrunk) cut out of it.
+fn filled_disc(glyph: &RgbaImage, color: Rgba<u8>) -> RgbaImage {
+    let size = glyph.width();
+    let mut icon = RgbaImage::new(size, size);
+    let half = size as f32 / 2.0;
+    draw_circle(&mut icon, half, half, half - 0.5, Some(color));
+    let inner = shrink(glyph, 0.7);
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let cut = inner.get_pixel(x, y)[3] as f32 / 255.0;
+        pixel[3] = (pixel[3] as f32 * (1.0 - cut)) as u8;
+    }
+    icon
+}
+
+/// `glyph` (shrunk) in `color` inside a ring.
+fn ringed_glyph(glyph: &RgbaImage, color: Rgba<u8>) -> RgbaImage {
+    let size = glyph.width() as f32;
+    let mut icon = recolor(&shrink(glyph, 0.7), color);
+    let half = size / 2.0;
+    let width = (size / 12.0).max(1.0);
+    let ring_radius = half - width / 2.0 - 0.5;
+    paint(&mut icon, Some(color), |x, y| {
+        let distance = ((x - half).powi(2) + (y - half).powi(2)).sqrt();
+        (width / 2.0 - (distance - ring_radius).abs() + 0.5).clamp(0.0, 1.0)
+    });
+    icon
+}
+
+/// The microphone `glyph` in `color` with an X (muted), a checkmark (unmuted) or a dash
+/// (mixed) over the bottom right corner.
+fn symbol(glyph: &RgbaImage, state: MuteState, color: Rgba<u8>) -> RgbaImage {
+    let mut icon = recolor(glyph, color);
+    let size = glyph.width() as f32;
+    let width = (size / 8.0).max(1.5);
+    let at = |x: f32, y: f32| (size * x, size * y);
+    let strokes = match state {
+        MuteState::Muted => vec![
+            (at(0.58, 0.58), at(0.92, 0.92)),
+            (at(0.92, 0.58), at(0.58, 0.92)),
+        ],
+        MuteState::Unmuted => vec![
+            (at(0.52, 0.76), at(0.66, 0.9)),
+            (at(0.66, 0.9), at(0.94, 0.56)),
+        ],
+        MuteState::Mixed => vec![(at(0.56, 0.75), at(0.94, 0.75))],
+    };
+    // Clear around the strokes first so they stay readable over the glyph
+    for &(from, to) in &strokes {
+        draw_line(&mut icon, from, to, width * 2.2, None);
+    }
+    for &(from, to) in &strokes {
+        draw_line(&mut icon, from, to, width, Some(color));
+    }
+    icon
+}
+
+/// `muted` above the anti-diagonal, `unmuted` below it, like the embedded mixed icon.
+fn split_diagonally(mut muted: RgbaImage, unmuted: &RgbaImage) -> RgbaImage {
+    let size = muted.width();
+    for (x, y, pixel) in muted.enumerate_pixels_mut() {
+        if x + y >= size {
+            *pixel = *unmuted.get_pixel(x, y);
+        }
+    }
+    muted
+}
+
+/// `image` scaled by `factor` around its center, on a canvas of the same size.
+fn shrink(image: &RgbaImage, factor: f32) -> RgbaImage {
+    let size = image.width();
+    let inner = ((size as f32 * factor).round() as u32).clamp(1, size);
+    let scaled = imageops::resize(image, inner, inner, FilterType::Lanczos3);
+    let mut canvas = RgbaImage::new(size, size);
+    let offset = ((size - inner) / 2) as i64;
+    imageops::overlay(&mut canvas, &scaled, offset, offset);
+    canvas
+}
+
+/// The shape of `image` (its alpha) in `color`.
+fn recolor(image: &RgbaImage, color: Rgba<u8>) -> RgbaImage {
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        Rgba([color[0], color[1], color[2], image.get_pixel(x, y)[3]])
+    })
+}
+
+/// Fill an anti-aliased circle with `color`, or erase it to transparent with `None`.
+fn draw_circle(icon: &mut RgbaImage, cx: f32, cy: f32, radius: f32, color: Option<Rgba<u8>>) {
+    paint(icon, color, |x, y| {
+        let distance = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+        (radius - distance + 0.5).clamp(0.0, 1.0)
+    });
+}
+
+/// Draw an anti-aliased line with round caps, or erase it with `None`.
+fn draw_line(
+    icon: &mut RgbaImage,
+    (x1, y1): (f32, f32),
+    (x2, y2): (f32, f32),
+    width: f32,
+    color: Option<Rgba<u8>>,
+) {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length = (dx * dx + dy * dy).max(f32::EPSILON);
+    paint(icon, color, |x, y| {
+        // Distance to the segment
+        let t = (((x - x1) * dx + (y - y1) * dy) / length).clamp(0.0, 1.0);
+        let distance = ((x - x1 - t * dx).powi(2) + (y - y1 - t * dy).powi(2)).sqrt();
+        (width / 2.0 - distance + 0.5).clamp(0.0, 1.0)
+    });
+}
+
+/// Blend `color` over every pixel by the coverage `shape` gives for the pixel's center,
+/// or erase by it with `None`.
+fn paint(icon: &mut RgbaImage, color: Option<Rgba<u8>>, shape: impl Fn(f32, f32) -> f32) {
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let coverage = shape(x as f32 + 0.5, y as f32 + 0.5);
+        if coverage == 0.0 {
+            continue;
+        }
+        match color {
+            None => pixel[3] = (pixel[3] as f32 * (1.0 - coverage)) as u8,
+            Some(color) => {
+                // Source over
+                let under = pixel[3] as f32 / 255.0 * (1.0 - coverage);
+                let alpha = coverage + under;
+                for channel in 0..3 {
+                    pixel[channel] = ((color[channel] as f32 * coverage
+                        + pixel[channel] as f32 * under)
+                        / alpha) as u8;
+                }
+                pixel[3] = (alpha * 255.0).round() as u8;
+            }
+        }
+    }
+}
+
+/// Draw `digit` in `color`, centered in the dot, if the dot is big enough for a legible
+/// one.
+fn draw_digit(icon: &mut RgbaImage, center: f32, radius: f32, digit: usize, color: Rgba<u8>) {
+    // Whole pixels per font pixel, so the digit stays sharp
+    let scale = ((radius * 1.2) / 5.0).floor() as u32;
+    if scale == 0 {
+        return;
+    }
+    let (width, height) = (3 * scale, 5 * scale);
+    let left = (center - width as f32 / 2.0).round() as u32;
+    let top = (center - height as f32 / 2.0).round() as u32;
+    for (row, bits) in DIGITS[digit].iter().enumerate() {
+        for column in 0..3 {
+            if bits & (0b100 >> column) == 0 {
+                continue;
+            }
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let x = left + column * scale + dx;
+                    let y = top + row as u32 * scale + dy;
+                    if x < icon.width() && y < icon.height() {
+                        icon.put_pixel(x, y, color);
+                    }
+                }
+            }
+        }
+    }
+}
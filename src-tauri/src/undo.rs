@@ -0,0 +1,98 @@
+//! Undoing the last mute change.
+//!
+//! `record` runs with every published mute state and keeps the last transition with its
+//! source and time in `AppState::mute_history`. `undo` goes back to the state before it
+//! through `set_mute_internal`, as source `undo`, unless the change is older than the
+//! configured window. The undo is itself a transition, so undoing again goes forward
+//! again.
+
+use crate::mute_state::MuteState;
+use crate::{AppHandle, AppState, MutexExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+pub const SOURCE_UNDO: &str = "undo";
+
+/// Default of `AppSettings::undo_window_seconds`.
+pub const DEFAULT_WINDOW_SECS: u32 = 30;
+
+static WINDOW_SECS: AtomicU32 = AtomicU32::new(DEFAULT_WINDOW_SECS);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteChange {
+    pub from: MuteState,
+    pub to: MuteState,
+    pub source: String,
+    /// Unix time in milliseconds.
+    pub at: u64,
+    #[serde(skip)]
+    pub instant: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct MuteHistory {
+    /// The state published last.
+    published: Option<MuteState>,
+    last_change: Option<MuteChange>,
+}
+
+pub fn configure(window_secs: u32) {
+    WINDOW_SECS.store(window_secs, Ordering::SeqCst);
+}
+
+/// Note a published mute state; a different one than before is a change.
+pub fn record(state: &AppState, mute_state: MuteState, source: &str) {
+    let mut history = state.mute_history.lock_safe();
+    let previous = history.published.replace(mute_state);
+    let Some(from) = previous.filter(|from| *from != mute_state) else {
+        return;
+    };
+    history.last_change = Some(MuteChange {
+        from,
+        to: mute_state,
+        source: source.to_string(),
+        at: crate::events::now_millis(),
+        instant: Instant::now(),
+    });
+}
+
+/// Revert the last change. Returns the muted value it went back to.
+pub fn undo(app: &AppHandle) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    let change = state
+        .mute_history
+        .lock_safe()
+        .last_change
+        .clone()
+        .ok_or_else(|| "There is no mute change to undo".to_string())?;
+
+    let window_secs = WINDOW_SECS.load(Ordering::SeqCst);
+    if change.instant.elapsed() > Duration::from_secs(u64::from(window_secs)) {
+        return Err(format!(
+            "The last mute change was more than {} seconds ago",
+            window_secs
+        ));
+    }
+    let muted = match change.from {
+        MuteState::Muted => true,
+        MuteState::Unmuted => false,
+        MuteState::Mixed => {
+            return Err(
+                "The microphones disagreed before the last change, so it can't be undone"
+                    .to_string(),
+            )
+        }
+    };
+
+    log::info!(
+        "[undo] {:?} -> {:?}, undoing the change from {}",
+        change.to,
+        change.from,
+        change.source
+    );
+    crate::set_mute_internal(&state, app, muted, false, SOURCE_UNDO)?;
+    Ok(muted)
+}
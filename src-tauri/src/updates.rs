@@ -0,0 +1,203 @@
+//! Update checks and installation through the updater plugin.
+//!
+//! The endpoint follows `AppSettings::update_channel`. Background checks run only while
+//! `AppSettings::check_updates` is on, and at most every `MIN_CHECK_INTERVAL_SECS`: the
+//! time of the last check is kept under `lastUpdateCheck` in the config store, so
+//! restarting TogMic doesn't query the endpoint again. A found update is remembered for
+//! `install_update` and announced with `update-available`.
+
+use crate::{AppHandle, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Url};
+use tauri_plugin_store::StoreExt;
+use tauri_plugin_updater::{Update, UpdaterExt};
+use time::format_description::well_known::Rfc3339;
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/Daborsten-org/TogMic/releases/latest/download/latest.json";
+/// Pre-releases publish their manifest on the rolling `beta` release.
+const BETA_ENDPOINT: &str =
+    "https://github.com/Daborsten-org/TogMic/releases/download/beta/latest.json";
+const LAST_CHECK_KEY: &str = "lastUpdateCheck";
+const MIN_CHECK_INTERVAL_SECS: u64 = 12 * 60 * 60;
+/// How often the background loop wakes to see whether a check is due.
+const BACKGROUND_TICK_SECS: u64 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub version: Option<String>,
+    pub notes: Option<String>,
+    /// Release date, RFC 3339.
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateDownloadProgressEvent {
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+/// The update found by the last check, kept for `install`.
+static PENDING: Lazy<Mutex<Option<Update>>> = Lazy::new(|| Mutex::new(None));
+
+/// Query the configured channel's endpoint now.
+pub async fn check(app: &AppHandle) -> Result<UpdateInfo, String> {
+    let channel = crate::load_app_settings(app).update_channel;
+    let url = Url::parse(channel.endpoint()).map_err(|e| e.to_string())?;
+    let update = app
+        .updater_builder()
+        .endpoints(vec![url])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    save_last_check(app);
+
+    let info = update_info(update.as_ref());
+    *PENDING.lock_safe() = update;
+    Ok(info)
+}
+
+/// The update found by the last check, if any, without querying the endpoint.
+pub fn available() -> UpdateInfo {
+    update_info(PENDING.lock_safe().as_ref())
+}
+
+fn update_info(update: Option<&Update>) -> UpdateInfo {
+    match update {
+        Some(update) => UpdateInfo {
+            available: true,
+            version: Some(update.version.clone()),
+            notes: update.body.clone(),
+            date: update.date.and_then(|d| d.format(&Rfc3339).ok()),
+        },
+        None => UpdateInfo::default(),
+    }
+}
+
+/// Download and install the pending update (checking first if there is none), emitting
+/// `update-download-progress` along the way. The caller relaunches afterwards; the exit
+/// restore runs between download and installation, since the Windows installer ends the
+/// process right away.
+pub async fn install(app: &AppHandle) -> Result<(), String> {
+    if PENDING.lock_safe().is_none() {
+        check(app).await?;
+    }
+    let update = PENDING
+        .lock_safe()
+        .take()
+        .ok_or_else(|| "No update available".to_string())?;
+    log::info!("[updates] installing {}", update.version);
+
+    let progress_app = app.clone();
+    let mut downloaded = 0u64;
+    let mut last_percent = None;
+    let bytes = update
+        .download(
+            move |chunk, total| {
+                downloaded += chunk as u64;
+                // One event per percent; without a known size, one per chunk
+                let percent = total.map(|t| downloaded * 100 / t.max(1));
+                if percent.is_some() && percent == last_percent {
+                    return;
+                }
+                last_percent = percent;
+                let _ = progress_app.emit(
+                    "update-download-progress",
+                    UpdateDownloadProgressEvent { downloaded, total },
+                );
+            },
+            || log::info!("[updates] download finished"),
+        )
+        .await
+        .map_err(|e| format!("Failed to download update: {}", e))?;
+    crate::exit_restore::restore(app);
+    update
+        .install(bytes)
+        .map_err(|e| format!("Failed to install update: {}", e))
+}
+
+/// Check in the background whenever a check is due and `check_updates` is on.
+pub fn start_background_check(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let due_in = seconds_until_due(&app);
+            if due_in == 0 && crate::load_app_settings(&app).check_updates {
+                match check(&app).await {
+                    Ok(info) if info.available => {
+                        log::info!("[updates] {:?} is available", info.version);
+                        let _ = app.emit("update-available", &info);
+                    }
+                    Ok(_) => {}
+                    // Counts as a check too, so a broken endpoint isn't retried every tick
+                    Err(e) => {
+                        log::warn!("[updates] {}", e);
+                        save_last_check(&app);
+                    }
+                }
+                continue;
+            }
+            let wait = match due_in {
+                0 => BACKGROUND_TICK_SECS,
+                secs => secs.min(BACKGROUND_TICK_SECS),
+            };
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+        }
+    });
+}
+
+/// Seconds until the next background check is due (0 if it is due now).
+fn seconds_until_due(app: &AppHandle) -> u64 {
+    let last = app
+        .store(crate::config_store(app))
+        .ok()
+        .and_then(|store| store.get(LAST_CHECK_KEY))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let due = last.saturating_add(MIN_CHECK_INTERVAL_SECS);
+    due.saturating_sub(now_secs())
+}
+
+fn save_last_check(app: &AppHandle) {
+    let Ok(store) = app.store(crate::config_store(app)) else {
+        return;
+    };
+    store.set(LAST_CHECK_KEY, serde_json::json!(now_secs()));
+    if let Err(e) = crate::config_recovery::guard_save(false)
+        .and_then(|()| store.save().map_err(|e| e.to_string()))
+    {
+        log::warn!("[updates] failed to save last check time: {}", e);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,149 @@
+//! `voice-activity` events: whether the user is speaking into the active profile's
+//! primary microphone.
+//!
+//! `start` runs a monitor thread that reads the device's peak level at
+//! `VadSettings::sample_rate_hz` and feeds it to `audio::vad::VoiceDetector`; only the
+//! transitions are emitted. The device is looked up again before every reading, so the
+//! monitor follows profile switches. While the device can't be read (unplugged, disabled)
+//! ongoing speech counts as ended and the monitor only checks again every
+//! `RETRY_INTERVAL`. Where the platform has no metering the monitor stops on its own.
+
+use crate::audio::vad::{VadSettings, VoiceDetector};
+use crate::audio::{AudioController, PlatformAudioController, METERING_UNSUPPORTED};
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+pub const VOICE_ACTIVITY: &str = "voice-activity";
+
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+static SETTINGS: Lazy<Mutex<VadSettings>> = Lazy::new(|| Mutex::new(VadSettings::default()));
+/// Bumped by `start` and `stop`; a monitor thread exits once it no longer matches.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VoiceActivityEvent<'a> {
+    speaking: bool,
+    device_id: &'a str,
+    /// Smoothed level at the transition.
+    level: f32,
+}
+
+pub fn configure(settings: VadSettings) {
+    *SETTINGS.lock_safe() = settings;
+}
+
+/// The profile's first device, or the default device for an all-devices profile.
+fn primary_device(state: &AppState) -> Option<String> {
+    let profile = state.current_profile.lock_safe();
+    let id = profile
+        .as_ref()?
+        .listed_device_ids()
+        .into_iter()
+        .find(|id| id != crate::ALL_DEVICES_ID);
+    Some(id.unwrap_or_else(|| "default-mic".to_string()))
+}
+
+/// Start a monitor thread, replacing one that is running.
+pub fn start(app: AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    log::info!("[voice] monitor started");
+    std::thread::spawn(move || run(app, generation));
+}
+
+/// Stop the monitor. It emits the end of ongoing speech before it exits.
+pub fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    log::info!("[voice] monitor stopped");
+}
+
+fn run(app: AppHandle, generation: u64) {
+    let _ = PlatformAudioController::init_thread();
+    let controller = match PlatformAudioController::new() {
+        Ok(controller) => controller,
+        Err(e) => {
+            log::warn!("[voice] failed to create audio controller: {}", e);
+            return;
+        }
+    };
+
+    let mut detector = VoiceDetector::new();
+    let mut device: Option<String> = None;
+    let mut last_sample = Instant::now();
+    let mut unreadable = false;
+    while GENERATION.load(Ordering::SeqCst) == generation {
+        let settings = SETTINGS.lock_safe().clone();
+        let Some(device_id) = primary_device(&app.state::<AppState>()) else {
+            if let Some(previous) = device.take() {
+                end_speech(&app, &mut detector, &previous);
+            }
+            std::thread::sleep(RETRY_INTERVAL);
+            continue;
+        };
+        if device.as_deref() != Some(device_id.as_str()) {
+            if let Some(previous) = device.replace(device_id.clone()) {
+                end_speech(&app, &mut detector, &previous);
+            }
+            last_sample = Instant::now();
+        }
+
+        match controller.get_peak_level(&device_id) {
+            Err(e) if e == METERING_UNSUPPORTED => {
+                log::info!("[voice] {}, monitor stopped", e);
+                break;
+            }
+            Err(e) => {
+                if !unreadable {
+                    log::warn!("[voice] pausing, can't read {}: {}", device_id, e);
+                    unreadable = true;
+                }
+                end_speech(&app, &mut detector, &device_id);
+                std::thread::sleep(RETRY_INTERVAL);
+                last_sample = Instant::now();
+                continue;
+            }
+            Ok(peak) => {
+                if unreadable {
+                    log::info!("[voice] resumed");
+                    unreadable = false;
+                }
+                let elapsed_ms =
+                    u32::try_from(last_sample.elapsed().as_millis()).unwrap_or(u32::MAX);
+                last_sample = Instant::now();
+                if let Some(speaking) = detector.push(&settings, peak, elapsed_ms) {
+                    emit(&app, speaking, &device_id, detector.level());
+                }
+            }
+        }
+        std::thread::sleep(settings.sample_interval());
+    }
+
+    if let Some(device_id) = device {
+        end_speech(&app, &mut detector, &device_id);
+    }
+}
+
+fn end_speech(app: &AppHandle, detector: &mut VoiceDetector, device_id: &str) {
+    let level = detector.level();
+    if detector.reset() {
+        emit(app, false, device_id, level);
+    }
+}
+
+fn emit(app: &AppHandle, speaking: bool, device_id: &str, level: f32) {
+    log::debug!("[voice] speaking: {} ({})", speaking, device_id);
+    let _ = app.emit(
+        VOICE_ACTIVITY,
+        VoiceActivityEvent {
+            speaking,
+            device_id,
+            level,
+        },
+    );
+}
@@ -0,0 +1,293 @@
+//! HTTP webhooks fired on mute state transitions.
+//!
+//! Deliveries run on a dedicated worker thread so a slow or unreachable endpoint never
+//! delays a toggle. Each delivery is retried with backoff before it is reported through
+//! the `webhook-failed` event.
+
+use crate::{AppHandle, AppState, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+const MAX_ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookTrigger {
+    Mute,
+    Unmute,
+    Both,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default = "default_trigger")]
+    pub on: WebhookTrigger,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+fn default_trigger() -> WebhookTrigger {
+    WebhookTrigger::Both
+}
+
+impl Webhook {
+    fn fires_on(&self, muted: bool) -> bool {
+        match self.on {
+            WebhookTrigger::Both => true,
+            WebhookTrigger::Mute => muted,
+            WebhookTrigger::Unmute => !muted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookFailedEvent {
+    url: String,
+    error: String,
+}
+
+struct Delivery {
+    webhook: Webhook,
+    body: Value,
+}
+
+/// Currently configured webhooks.
+static WEBHOOKS: Lazy<Mutex<Vec<Webhook>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Sender to the delivery worker. Initialized once during setup().
+static WORKER: Lazy<Mutex<Option<mpsc::Sender<Delivery>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Spawn the delivery worker.
+pub fn init(app: AppHandle, webhooks: Vec<Webhook>) {
+    *WEBHOOKS.lock_safe() = webhooks;
+
+    let (tx, rx) = mpsc::channel::<Delivery>();
+    *WORKER.lock_safe() = Some(tx);
+
+    std::thread::spawn(move || {
+        for delivery in rx {
+            if let Err(e) = deliver_with_retry(&delivery.webhook, &delivery.body) {
+                app.state::<AppState>()
+                    .record_error("webhook", format!("{} failed: {}", delivery.webhook.url, e));
+                let _ = app.emit(
+                    "webhook-failed",
+                    WebhookFailedEvent {
+                        url: delivery.webhook.url.clone(),
+                        error: e,
+                    },
+                );
+            }
+        }
+    });
+}
+
+/// Replace the configured webhooks.
+pub fn configure(webhooks: Vec<Webhook>) {
+    *WEBHOOKS.lock_safe() = webhooks;
+}
+
+fn body(muted: bool, profile_id: Option<String>, source: &str) -> Value {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    json!({
+        "muted": muted,
+        "profileId": profile_id,
+        "source": source,
+        "timestamp": timestamp,
+    })
+}
+
+/// Queue deliveries for every webhook interested in this transition. Never blocks.
+pub fn notify(muted: bool, profile_id: Option<String>, source: &str) {
+    let webhooks = WEBHOOKS.lock_safe();
+    if webhooks.is_empty() {
+        return;
+    }
+    let body = body(muted, profile_id, source);
+
+    if let Some(tx) = WORKER.lock_safe().as_ref() {
+        for webhook in webhooks.iter().filter(|w| w.fires_on(muted)) {
+            let _ = tx.send(Delivery {
+                webhook: webhook.clone(),
+                body: body.clone(),
+            });
+        }
+    }
+}
+
+/// Send the webhook at `index` once, synchronously, with the current state. Used by the
+/// settings UI to verify a configuration.
+pub fn test(index: usize, muted: bool, profile_id: Option<String>) -> Result<(), String> {
+    let webhook = WEBHOOKS
+        .lock_safe()
+        .get(index)
+        .cloned()
+        .ok_or_else(|| format!("No webhook at index {}", index))?;
+    deliver(&webhook, &body(muted, profile_id, "test"))
+}
+
+fn deliver_with_retry(webhook: &Webhook, body: &Value) -> Result<(), String> {
+    let mut attempt = 1;
+    loop {
+        match deliver(webhook, body) {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt >= MAX_ATTEMPTS => return Err(e),
+            Err(_) => {
+                // 1 s, then 2 s
+                std::thread::sleep(Duration::from_secs(1 << (attempt - 1)));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn deliver(webhook: &Webhook, body: &Value) -> Result<(), String> {
+    let mut request = ureq::request(&webhook.method.to_uppercase(), &webhook.url)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS));
+    for (name, value) in &webhook.headers {
+        request = request.set(name, value);
+    }
+
+    let result = if webhook.method.eq_ignore_ascii_case("GET") {
+        request.call()
+    } else {
+        request.send_json(body)
+    };
+
+    result.map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    fn webhook(fields: Value) -> Webhook {
+        serde_json::from_value(fields).unwrap()
+    }
+
+    /// Serve one request with `status` and return it as received.
+    fn serve_once(status: u16) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+            request
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn defaults_to_post_on_both() {
+        let webhook = webhook(json!({ "url": "http://localhost/hook" }));
+        assert_eq!(webhook.method, "POST");
+        assert_eq!(webhook.on, WebhookTrigger::Both);
+        assert!(webhook.headers.is_empty());
+    }
+
+    #[test]
+    fn fires_on_the_configured_transitions() {
+        let on = |trigger: &str| webhook(json!({ "url": "http://x", "on": trigger }));
+        assert!(on("mute").fires_on(true));
+        assert!(!on("mute").fires_on(false));
+        assert!(on("unmute").fires_on(false));
+        assert!(!on("unmute").fires_on(true));
+        assert!(on("both").fires_on(true) && on("both").fires_on(false));
+    }
+
+    #[test]
+    fn payload_describes_the_transition() {
+        let payload = body(true, Some("p1".to_string()), "hotkey");
+        assert_eq!(payload["muted"], json!(true));
+        assert_eq!(payload["profileId"], json!("p1"));
+        assert_eq!(payload["source"], json!("hotkey"));
+        assert!(payload["timestamp"].as_u64().is_some_and(|t| t > 0));
+
+        assert_eq!(body(false, None, "tray")["profileId"], Value::Null);
+    }
+
+    #[test]
+    fn posts_the_payload_with_custom_headers() {
+        let (url, server) = serve_once(200);
+        let webhook = webhook(json!({
+            "url": url,
+            "method": "put",
+            "headers": { "X-Token": "secret" },
+        }));
+        deliver(&webhook, &body(true, None, "test")).unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("PUT /hook HTTP/1.1\r\n"));
+        assert!(request.to_ascii_lowercase().contains("x-token: secret\r\n"));
+        let payload: Value =
+            serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(payload["muted"], json!(true));
+        assert_eq!(payload["source"], json!("test"));
+    }
+
+    #[test]
+    fn get_requests_have_no_body() {
+        let (url, server) = serve_once(204);
+        deliver(
+            &webhook(json!({ "url": url, "method": "GET" })),
+            &body(false, None, "test"),
+        )
+        .unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /hook HTTP/1.1\r\n"));
+        assert!(request.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn error_statuses_fail_the_delivery() {
+        let (url, server) = serve_once(500);
+        let result = deliver(&webhook(json!({ "url": url })), &body(true, None, "test"));
+        server.join().unwrap();
+        assert!(result.unwrap_err().contains("500"));
+    }
+}
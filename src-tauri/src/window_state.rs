@@ -0,0 +1,194 @@
+//! Main window size and position, persisted across launches.
+//!
+//! Moves and resizes are debounced: the geometry is written `SAVE_DELAY_MS` after the last
+//! one, and right away when the window is closed or hidden to the tray. On startup the
+//! saved geometry is fitted into the monitors that are actually connected, so the window
+//! never comes back off-screen after a monitor was unplugged.
+
+use crate::{AppHandle, AppRuntime, MutexExt};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Manager, Monitor, PhysicalPosition, PhysicalSize, Window};
+use tauri_plugin_store::StoreExt;
+
+const STORE_KEY: &str = "windowState";
+const SAVE_DELAY_MS: u64 = 500;
+
+/// Geometry in physical pixels. `x`/`y`/`width`/`height` are the last un-maximized bounds,
+/// so un-maximizing after a restore goes back to them.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+    /// Name of the monitor the window was on.
+    #[serde(default)]
+    pub monitor: Option<String>,
+}
+
+/// Sender to the debounce thread. Initialized once during setup().
+static SAVER: Lazy<Mutex<Option<mpsc::Sender<()>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Start the debounce thread that saves the geometry once moves/resizes settle.
+pub fn init(app: AppHandle) {
+    let (tx, rx) = mpsc::channel::<()>();
+    *SAVER.lock_safe() = Some(tx);
+
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            // Keep waiting while events keep coming in
+            loop {
+                match rx.recv_timeout(Duration::from_millis(SAVE_DELAY_MS)) {
+                    Ok(()) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if let Some(window) = app.get_webview_window("main") {
+                save(&window.as_ref().window());
+            }
+        }
+    });
+}
+
+/// Note a move or resize; the geometry is saved once they stop.
+pub fn schedule_save() {
+    if let Some(tx) = SAVER.lock_safe().as_ref() {
+        let _ = tx.send(());
+    }
+}
+
+/// Save the window's current geometry now.
+pub fn save(window: &Window<AppRuntime>) {
+    // A minimized window reports a meaningless position; keep what was saved before
+    if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(true) {
+        return;
+    }
+
+    let app = window.app_handle();
+    let maximized = window.is_maximized().unwrap_or(false);
+    let mut state = load(app).unwrap_or(WindowState {
+        x: 0,
+        y: 0,
+        width: 0,
+        height: 0,
+        maximized,
+        monitor: None,
+    });
+    state.maximized = maximized;
+    state.monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|m| m.name().cloned());
+    if !maximized {
+        if let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) {
+            state.x = position.x;
+            state.y = position.y;
+            state.width = size.width;
+            state.height = size.height;
+        }
+    }
+    if state.width == 0 || state.height == 0 {
+        return;
+    }
+
+    if let Err(e) = write(app, &state) {
+        log::warn!("[window state] {}", e);
+    }
+}
+
+/// Apply the saved geometry to `window`. Call before the window is shown.
+pub fn restore(window: &Window<AppRuntime>) {
+    let Some(saved) = load(window.app_handle()) else {
+        return;
+    };
+    let monitors = window.available_monitors().unwrap_or_default();
+    let primary = window.primary_monitor().ok().flatten();
+    let Some(state) = fit_to_monitors(&saved, &monitors, primary.as_ref()) else {
+        return;
+    };
+
+    let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+    let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Fit the saved geometry into the current monitor layout. The saved monitor is used if
+/// it is still connected (matched by name, or by overlap if names aren't available);
+/// otherwise the window is centered on the primary display.
+fn fit_to_monitors(
+    saved: &WindowState,
+    monitors: &[Monitor],
+    primary: Option<&Monitor>,
+) -> Option<WindowState> {
+    let same_monitor = monitors.iter().find(|m| match (&saved.monitor, m.name()) {
+        (Some(saved_name), Some(name)) => saved_name == name,
+        _ => overlaps(saved, m),
+    });
+    let (monitor, keep_position) = match same_monitor {
+        Some(m) => (m, true),
+        None => (primary.or(monitors.first())?, false),
+    };
+
+    let area = monitor.work_area();
+    let width = saved.width.min(area.size.width);
+    let height = saved.height.min(area.size.height);
+    let (min_x, min_y) = (area.position.x, area.position.y);
+    let max_x = min_x + (area.size.width - width) as i32;
+    let max_y = min_y + (area.size.height - height) as i32;
+    let (x, y) = if keep_position {
+        (saved.x.clamp(min_x, max_x), saved.y.clamp(min_y, max_y))
+    } else {
+        (
+            min_x + (area.size.width - width) as i32 / 2,
+            min_y + (area.size.height - height) as i32 / 2,
+        )
+    };
+
+    Some(WindowState {
+        x,
+        y,
+        width,
+        height,
+        maximized: saved.maximized,
+        monitor: monitor.name().cloned(),
+    })
+}
+
+fn overlaps(state: &WindowState, monitor: &Monitor) -> bool {
+    let (position, size) = (monitor.position(), monitor.size());
+    state.x < position.x + size.width as i32
+        && position.x < state.x + state.width as i32
+        && state.y < position.y + size.height as i32
+        && position.y < state.y + state.height as i32
+}
+
+fn load(app: &AppHandle) -> Option<WindowState> {
+    app.store(crate::config_store(app))
+        .ok()
+        .and_then(|store| store.get(STORE_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+}
+
+fn write(app: &AppHandle, state: &WindowState) -> Result<(), String> {
+    let store = app
+        .store(crate::config_store(app))
+        .map_err(|e| format!("Failed to open config: {}", e))?;
+    let value = serde_json::to_value(state).map_err(|e| e.to_string())?;
+    store.set(STORE_KEY, value);
+    crate::config_recovery::guard_save(false)?;
+    store
+        .save()
+        .map_err(|e| format!("Failed to save window state: {}", e))
+}
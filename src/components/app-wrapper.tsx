@@ -13,6 +13,7 @@ import { AppProvider } from "@/contexts/AppContent";
 
 import { MuteContext } from "@/contexts/MuteContext";
 import { AppContext } from "@/contexts/AppContext";
+import { formatHotkey } from "@/lib/hotkey";
 import { cn } from "@/lib/utils";
 
 export type Page = "dashboard" | "profiles" | "settings";
@@ -60,7 +61,7 @@ function HeaderMuteChip() {
         className="inline-flex items-center rounded-md border bg-card px-2 py-0.5 font-mono text-[10px] text-foreground/70 select-none"
         style={{ boxShadow: "var(--shadow-kbd)" }}
       >
-        {activeProfile.toggleKey}
+        {formatHotkey(activeProfile.toggleKey)}
       </kbd>
     </div>
   );
@@ -86,6 +87,18 @@ export function AppWrapper() {
     [],
   );
 
+  // Routes come from the tray menu: a page, optionally followed by a settings tab
+  const handleNavigate = useCallback((route: string) => {
+    const [page, tab] = route.split("/");
+    if (page !== "dashboard" && page !== "profiles" && page !== "settings") {
+      return;
+    }
+    setCurrentPage(page);
+    if (page === "settings" && tab) {
+      setSettingsInitialTab(tab);
+    }
+  }, []);
+
   const renderPage = () => {
     switch (currentPage) {
       case "dashboard":
@@ -123,6 +136,7 @@ export function AppWrapper() {
   return (
     <AppProvider
       onNavigateToUpdates={handleNavigateToUpdates}
+      onNavigate={handleNavigate}
       onRequestInstall={() => {
         setCurrentPage("settings");
         setSettingsInitialTab("updates");
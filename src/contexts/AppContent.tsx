@@ -4,7 +4,6 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { load, type Store } from "@tauri-apps/plugin-store";
-import { check, type Update } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
 import { toast } from "sonner";
 import { useTranslation } from "react-i18next";
@@ -15,11 +14,24 @@ import {
   type AppSettings,
   type Config,
   type AppContextType,
+  type MuteStateChangedEvent,
+  type UpdateInfo,
+  type DevicesChangedEvent,
+  type HotkeyConflict,
+  type CaptureStartedEvent,
+  type ConfigPaths,
+  type SessionChangedEvent,
+  type ProfileDevicesMissingEvent,
+  type ProfileDevicesUnavailableEvent,
+  type ConfigLoadProblem,
+  type ToggleResult,
+  type DeviceList,
 } from "@/contexts/AppContext";
 import { MuteContext, type MuteContextType } from "@/contexts/MuteContext";
 import { SettingsContext, type SettingsContextType } from "@/contexts/SettingsContext";
+import { normalizeHotkey } from "@/lib/hotkey";
 
-export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }: { children: ReactNode; onNavigateToUpdates?: (version: string, body?: string, date?: string) => void; onRequestInstall?: () => void }) {
+export function AppProvider({ children, onNavigateToUpdates, onRequestInstall, onNavigate }: { children: ReactNode; onNavigateToUpdates?: (version: string, body?: string, date?: string) => void; onRequestInstall?: () => void; onNavigate?: (route: string) => void }) {
   const [devices, setDevices] = useState<AudioDevice[]>([]);
   const [profiles, setProfiles] = useState<HotkeyProfile[]>([]);
   const [activeProfile, setActiveProfileState] = useState<HotkeyProfile | null>(
@@ -36,9 +48,11 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
   const [configLoaded, setConfigLoaded] = useState(false);
   const startupApplied = useRef(false);
   const storeRef = useRef<Store | null>(null);
-  const pendingUpdateRef = useRef<Update | null>(null);
+  const pendingUpdateRef = useRef<UpdateInfo | null>(null);
+  const announcedUpdateRef = useRef<string | null>(null);
   const onNavigateToUpdatesRef = useRef(onNavigateToUpdates);
   const onRequestInstallRef = useRef(onRequestInstall);
+  const onNavigateRef = useRef(onNavigate);
   const { t, i18n } = useTranslation();
 
   // Keep refs in sync so stable useCallbacks always read latest state
@@ -49,34 +63,37 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
   useLayoutEffect(() => {
     onNavigateToUpdatesRef.current = onNavigateToUpdates;
     onRequestInstallRef.current = onRequestInstall;
+    onNavigateRef.current = onNavigate;
     profilesRef.current = profiles;
     activeProfileRef.current = activeProfile;
     settingsRef.current = settings;
   });
 
   const getStore = async () => {
-    storeRef.current ??= await load("config.json", { autoSave: false, defaults: {} });
+    // The backend may use another config via --config or TOGMIC_CONFIG
+    if (!storeRef.current) {
+      const paths = await invoke<ConfigPaths>("get_config_path");
+      storeRef.current = await load(paths.configFile ?? "config.json", {
+        autoSave: false,
+        defaults: {},
+      });
+    }
     return storeRef.current;
   };
 
-  // Sync tray menu labels whenever language changes
+  // Switch the backend's tray and notification strings whenever the language changes
   useEffect(() => {
-    invoke("update_tray_labels", {
-      mute: t("trayMute"),
-      unmute: t("trayUnmute"),
-      show: t("trayShowWindow"),
-      quit: t("trayQuit"),
-      mutedTooltip: t("trayMutedTooltip"),
-      unmutedTooltip: t("trayUnmutedTooltip"),
-    }).catch(console.error);
-  }, [i18n.language, t]);
+    invoke("set_locale", { lang: i18n.language }).catch(console.error);
+  }, [i18n.language]);
 
   // Load config from store
   const loadConfig = useCallback(async () => {
     try {
       const store = await getStore();
-      const loadedProfiles =
-        (await store.get<HotkeyProfile[]>("profiles")) ?? [];
+      // The backend migrates string hotkeys on startup; normalize in case it couldn't
+      const loadedProfiles = ((await store.get<HotkeyProfile[]>("profiles")) ?? []).map(
+        (p) => ({ ...p, toggleKey: normalizeHotkey(p.toggleKey) ?? p.toggleKey }),
+      );
       const loadedActiveProfileId =
         (await store.get<string | null>("activeProfileId")) ?? null;
       const loadedSettings = (await store.get<AppSettings>("appSettings")) ?? {
@@ -99,12 +116,11 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
           (p) => p.id === loadedActiveProfileId,
         );
         if (active) {
-          await invoke("set_active_profile", { profile: active });
-          await invoke("register_hotkey", {
-            hotkey: active.toggleKey,
-            ignoreModifiers: active.ignoreModifiers ?? false,
+          // Activates the stored profile and registers its hotkey
+          const canonical = await invoke<HotkeyProfile>("set_active_profile_by_id", {
+            id: active.id,
           });
-          setActiveProfileState(active);
+          setActiveProfileState(canonical);
           const muteState = await invoke<boolean>("get_mute_state");
           setIsMuted(muteState);
         }
@@ -118,7 +134,8 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
   }, []);
 
   // Save config to store — stable callback that reads from refs
-  const saveConfig = useCallback(async (updatedConfig: Partial<Config>) => {
+  // `force` overwrites a config that failed to parse and couldn't be moved aside
+  const saveConfig = useCallback(async (updatedConfig: Partial<Config>, force = false) => {
     try {
       const store = await getStore();
       await store.set("profiles", updatedConfig.profiles ?? profilesRef.current);
@@ -129,7 +146,7 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
           : (activeProfileRef.current?.id ?? null),
       );
       await store.set("appSettings", updatedConfig.appSettings ?? settingsRef.current);
-      await store.save();
+      await invoke("save_config", { force });
     } catch (error) {
       console.error("Failed to save config:", error);
       throw error;
@@ -139,8 +156,8 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
   // Refresh devices list
   const refreshDevices = useCallback(async () => {
     try {
-      const devicesList = await invoke<AudioDevice[]>("get_audio_devices");
-      setDevices(devicesList);
+      const list = await invoke<DeviceList>("get_audio_devices");
+      setDevices(list.devices);
     } catch (error) {
       console.error("Failed to get devices:", error);
     }
@@ -149,8 +166,8 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
   // Toggle mute state
   const toggleMute = useCallback(async () => {
     try {
-      const newState = await invoke<boolean>("toggle_mute");
-      setIsMuted(newState);
+      const result = await invoke<ToggleResult>("toggle_mute");
+      setIsMuted(result.muted);
     } catch (error) {
       console.error("Failed to toggle mute:", error);
       throw error;
@@ -171,17 +188,14 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
   // Set active profile
   const setActiveProfile = useCallback(async (profile: HotkeyProfile) => {
     try {
-      // Set in backend
-      await invoke("set_active_profile", { profile });
-
-      // Register hotkey
-      await invoke("register_hotkey", {
-        hotkey: profile.toggleKey,
-        ignoreModifiers: profile.ignoreModifiers ?? false,
+      // The backend activates its stored copy and registers the hotkey; profiles are
+      // saved before they are activated
+      const canonical = await invoke<HotkeyProfile>("set_active_profile_by_id", {
+        id: profile.id,
       });
 
       // Update local state
-      setActiveProfileState(profile);
+      setActiveProfileState(canonical);
       await saveConfig({ activeProfileId: profile.id });
 
       // Get current mute state
@@ -195,8 +209,13 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
 
   const saveProfile = useCallback(async (profile: HotkeyProfile) => {
     try {
-      // Validate with backend
-      await invoke("save_profile", { profile });
+      // Validate with backend; conflicts with well-known shortcuts don't block saving
+      const conflicts = await invoke<HotkeyConflict[]>("save_profile", { profile });
+      for (const conflict of conflicts) {
+        toast.warning(t("hotkeyConflict", { shortcut: conflict.shortcut }), {
+          description: conflict.description,
+        });
+      }
 
       // Update local state
       const currentProfiles = profilesRef.current;
@@ -223,7 +242,7 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
       console.error("Failed to save profile:", error);
       throw error;
     }
-  }, [saveConfig, setActiveProfile]);
+  }, [saveConfig, setActiveProfile, t]);
 
   // Delete profile
   const deleteProfile = useCallback(async (id: string) => {
@@ -252,6 +271,17 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
     }
   }, [saveConfig]);
 
+  // Reorder profiles; the backend validates and persists the order
+  const reorderProfiles = useCallback(async (ids: string[]) => {
+    try {
+      const reordered = await invoke<HotkeyProfile[]>("reorder_profiles", { ids });
+      setProfiles(reordered);
+    } catch (error) {
+      console.error("Failed to reorder profiles:", error);
+      throw error;
+    }
+  }, []);
+
   // Deactivate current profile
   const deactivateProfile = useCallback(async () => {
     try {
@@ -282,53 +312,155 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
     try {
       const updatedSettings = { ...settingsRef.current, ...newSettings };
       setSettings(updatedSettings);
-      await saveConfig({ appSettings: updatedSettings });
-
-      // Apply autostart setting
-      if (newSettings.autostart !== undefined) {
-        await invoke("set_autostart", { enabled: newSettings.autostart });
-      }
 
-      // Apply close to tray setting
-      if (newSettings.closeToTray !== undefined) {
-        await invoke("set_close_to_tray", { enabled: newSettings.closeToTray });
-      }
+      // Persists the settings and applies them (autostart, close to tray, integrations,
+      // hooks, ...) in one go
+      await invoke("apply_app_settings", { settings: updatedSettings });
 
       // Note: startMuted is only applied on app startup, not when toggling the setting
     } catch (error) {
       console.error("Failed to update settings:", error);
       throw error;
     }
-  }, [saveConfig]);
+  }, []);
 
   // Initialize on mount
   useEffect(() => {
     let mounted = true;
 
+    const showConfigLoadProblem = (problem: ConfigLoadProblem) => {
+      if (problem.recovery === "unmoved") {
+        toast.error(t("configLoadProblemUnmoved"), {
+          description: problem.configFile,
+          duration: Infinity,
+          action: {
+            label: t("configLoadProblemOverwrite"),
+            onClick: () => {
+              saveConfig({}, true).catch(console.error);
+            },
+          },
+        });
+        return;
+      }
+      toast.warning(
+        t(
+          problem.recovery === "restoredBackup"
+            ? "configLoadProblemRestored"
+            : "configLoadProblemDefaults",
+        ),
+        { description: problem.invalidFile, duration: Infinity },
+      );
+    };
+
     const init = async () => {
       await refreshDevices();
       await loadConfig();
 
       setConfigLoaded(true);
+
+      // Found at startup, before this window could listen for `config-load-problem`
+      const problem = await invoke<ConfigLoadProblem | null>("get_config_load_problem");
+      if (problem && mounted) {
+        showConfigLoadProblem(problem);
+      }
+
+      // Likewise emitted as `previous-crash` before this window could listen
+      const crashReport = await invoke<string | null>("get_previous_crash");
+      if (crashReport && mounted) {
+        toast.error(t("previousCrash"), {
+          description: crashReport,
+          duration: Infinity,
+          action: {
+            label: t("previousCrashOpen"),
+            onClick: () => {
+              invoke("open_crash_report").catch(console.error);
+            },
+          },
+        });
+      }
     };
 
     void init();
 
     // Listen for mute state changes
-    const unlistenMute = listen<boolean>("mute-state-changed", (event) => {
+    const unlistenMute = listen<MuteStateChangedEvent>("mute-state-changed", (event) => {
       if (mounted) {
-        setIsMuted(event.payload);
+        setIsMuted(event.payload.muted);
       }
     });
 
     // Listen for device plug/unplug so the device list stays in sync with the hardware.
     // The backend emits the changed IDs, but we re-fetch the full list to get fresh names.
-    const unlistenDevices = listen<string[]>("devices-changed", () => {
+    const unlistenDevices = listen<DevicesChangedEvent>("devices-changed", (event) => {
+      if (mounted) {
+        setDevices(event.payload.all);
+      }
+    });
+
+    // Settings applied from another window
+    const unlistenSettings = listen<AppSettings>("app-settings-changed", (event) => {
+      if (mounted) {
+        setSettings(event.payload);
+      }
+    });
+
+    // The active profile changed, from here or from the tray, CLI or a focus-based switch
+    const unlistenProfile = listen<HotkeyProfile | null>("profile-changed", (event) => {
+      if (mounted) {
+        setActiveProfileState(event.payload);
+      }
+    });
+
+    // A tray menu entry asked for a page ("settings/behavior")
+    const unlistenNavigate = listen<string>("navigate", (event) => {
+      if (mounted) {
+        onNavigateRef.current?.(event.payload);
+      }
+    });
+
+    // Audio came up after a failed startup initialization (or a manual reinitialize)
+    const unlistenAudioReady = listen("audio-controller-ready", () => {
       if (mounted) {
         void refreshDevices();
       }
     });
 
+    // Devices may have changed while the system was asleep
+    const unlistenResumed = listen("system-resumed", () => {
+      if (mounted) {
+        void refreshDevices();
+      }
+    });
+
+    // The endpoints change when connecting over remote desktop or switching users
+    const unlistenSession = listen<SessionChangedEvent>("session-changed", () => {
+      if (mounted) {
+        void refreshDevices();
+      }
+    });
+
+    // The active profile has no device left in this session, so toggling does nothing
+    const unlistenUnavailable = listen<ProfileDevicesUnavailableEvent>(
+      "profile-devices-unavailable",
+      () => {
+        if (mounted) {
+          toast.warning(t("profileDevicesUnavailable"));
+        }
+      },
+    );
+
+    // Mute changes skip devices that are gone; say so once per device
+    const unlistenMissing = listen<ProfileDevicesMissingEvent>(
+      "profile-devices-missing",
+      (event) => {
+        if (mounted) {
+          toast.warning(
+            t(event.payload.allMissing ? "profileDevicesAllMissing" : "profileDevicesMissing"),
+          );
+        }
+      },
+    );
+
     // When window gains focus after a background notification, navigate to updates and show toast
     const win = getCurrentWindow();
     const unlistenFocus = win.listen("tauri://focus", () => {
@@ -336,7 +468,11 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
       const update = pendingUpdateRef.current;
       if (!update) return;
       pendingUpdateRef.current = null;
-      onNavigateToUpdatesRef.current?.(update.version, update.body, update.date ?? undefined);
+      onNavigateToUpdatesRef.current?.(
+        update.version ?? "",
+        update.notes ?? undefined,
+        update.date ?? undefined,
+      );
       toast(t("updateAvailable", { version: update.version }), {
         duration: Infinity,
         action: {
@@ -352,9 +488,17 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
       mounted = false;
       void unlistenMute.then((fn) => fn());
       void unlistenDevices.then((fn) => fn());
+      void unlistenAudioReady.then((fn) => fn());
+      void unlistenNavigate.then((fn) => fn());
+      void unlistenResumed.then((fn) => fn());
+      void unlistenSession.then((fn) => fn());
+      void unlistenUnavailable.then((fn) => fn());
+      void unlistenMissing.then((fn) => fn());
+      void unlistenSettings.then((fn) => fn());
+      void unlistenProfile.then((fn) => fn());
       void unlistenFocus.then((fn) => fn());
     };
-  }, [loadConfig, refreshDevices, t]);
+  }, [loadConfig, refreshDevices, saveConfig, t]);
 
   // Apply startup-only settings once after config is loaded.
   // This ensures toggling `startMuted` in settings doesn't immediately mute the app;
@@ -374,33 +518,61 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
       console.error,
     );
 
-    // Auto-check for updates on startup if enabled
-    if (settings.checkUpdates) {
-      check()
-        .then(async (update) => {
-          if (update) {
-            const win = getCurrentWindow();
-            const isVisible = await win.isVisible();
-            if (!isVisible) {
-              pendingUpdateRef.current = update;
-              invoke("show_update_notification", {
-                title: t("updateAvailable", { version: update.version }),
-                body: t("updateNotificationBody"),
-              }).catch(console.error);
-            } else {
-              toast(t("updateAvailable", { version: update.version }), {
-                duration: Infinity,
-                action: {
-                  label: t("update"),
-                  onClick: () => { void update.downloadAndInstall().then(() => relaunch()); },
-                },
-              });
-            }
-          }
-        })
-        .catch(console.error);
-    }
-  }, [configLoaded, settings.startMuted, settings.closeToTray, settings.checkUpdates, t]);
+  }, [configLoaded, settings.startMuted, settings.closeToTray]);
+
+  // The backend checks for updates in the background (when checkUpdates is on); pick up
+  // one found before we were listening, then every new one
+  useEffect(() => {
+    const announce = async (update: UpdateInfo) => {
+      if (!update.available || announcedUpdateRef.current === update.version) return;
+      announcedUpdateRef.current = update.version;
+      const isVisible = await getCurrentWindow().isVisible();
+      if (!isVisible) {
+        pendingUpdateRef.current = update;
+        invoke("show_update_notification", {
+          title: t("updateAvailable", { version: update.version }),
+          body: t("updateNotificationBody"),
+        }).catch(console.error);
+      } else {
+        toast(t("updateAvailable", { version: update.version }), {
+          duration: Infinity,
+          action: {
+            label: t("update"),
+            onClick: () => {
+              void invoke("install_update").then(() => relaunch());
+            },
+          },
+        });
+      }
+    };
+
+    invoke<UpdateInfo>("get_available_update")
+      .then((update) => announce(update))
+      .catch(console.error);
+    const unlisten = listen<UpdateInfo>("update-available", (event) => {
+      void announce(event.payload);
+    });
+    return () => {
+      void unlisten.then((fn) => fn());
+    };
+  }, [t]);
+
+  // An application no profile lists started recording; offer to unmute
+  useEffect(() => {
+    const unlisten = listen<CaptureStartedEvent>("capture-started", (event) => {
+      toast(t("captureStarted", { process: event.payload.process }), {
+        action: {
+          label: t("unmute"),
+          onClick: () => {
+            void setMute(false);
+          },
+        },
+      });
+    });
+    return () => {
+      void unlisten.then((fn) => fn());
+    };
+  }, [setMute, t]);
 
   const muteValue: MuteContextType = useMemo(() => ({
     isMuted,
@@ -415,10 +587,11 @@ export function AppProvider({ children, onNavigateToUpdates, onRequestInstall }:
     refreshDevices,
     saveProfile,
     deleteProfile,
+    reorderProfiles,
     setActiveProfile,
     deactivateProfile,
     registerHotkey,
-  }), [devices, profiles, activeProfile, refreshDevices, saveProfile, deleteProfile, setActiveProfile, deactivateProfile, registerHotkey]);
+  }), [devices, profiles, activeProfile, refreshDevices, saveProfile, deleteProfile, reorderProfiles, setActiveProfile, deactivateProfile, registerHotkey]);
 
   const settingsValue: SettingsContextType = useMemo(() => ({
     settings,
@@ -4,14 +4,289 @@ export interface AudioDevice {
   id: string;
   name: string;
   isDefault: boolean;
+  state: "active" | "disabled" | "notPresent" | "unplugged";
+  kind: "builtin" | "usb" | "bluetooth" | "virtual" | "unknown";
+  formFactor:
+    | "microphone"
+    | "headset"
+    | "handset"
+    | "lineLevel"
+    | "digital"
+    | "remote"
+    | "unknown";
+  /** The system's name when `name` is an alias set with `set_device_alias`. */
+  systemName?: string;
 }
 
+/** Persisted alias of a device, from `get_device_aliases`. */
+export interface DeviceAlias {
+  alias: string;
+  systemName: string;
+  /** Unix time in seconds. */
+  lastSeen: number;
+}
+
+export interface DevicesChangedEvent {
+  added: AudioDevice[];
+  removed: AudioDevice[];
+  all: AudioDevice[];
+  /** A removed device is one the active profile uses. */
+  profileDeviceRemoved: boolean;
+}
+
+export type HotkeyModifier = "commandOrControl" | "shift" | "alt" | "super";
+
+/** A key name the shortcut plugin accepts, a virtual-key code, or a scancode. */
+export type HotkeyKey =
+  | { type: "named"; value: string }
+  | { type: "vk"; value: number }
+  | { type: "scancode"; value: number };
+
+export interface HotkeyBinding {
+  modifiers: HotkeyModifier[];
+  key: HotkeyKey;
+}
+
+/** A well-known shortcut a hotkey would shadow (advisory). */
+export interface HotkeyConflict {
+  shortcut: string;
+  description: string;
+}
+
+/** Which system default input device `set_default_input_device` changes. */
+export type DefaultRole = "console" | "communications" | "both";
+
 export interface HotkeyProfile {
   id: string;
   name: string;
-  toggleKey: string;
+  toggleKey: HotkeyBinding;
   deviceIds: string[];
+  /** Target state per device, which makes the profile a scene; replaces `deviceIds`. */
+  deviceStates?: SceneDevice[];
   ignoreModifiers?: boolean;
+  autoActivateApps?: string[];
+  revertOnBlur?: boolean;
+  /** Unmutes for `temporaryUnmuteSeconds`, then mutes again. */
+  temporaryUnmuteKey?: HotkeyBinding | null;
+  temporaryUnmuteSeconds?: number;
+  /** While muted, mute devices again that other applications unmute. */
+  muteLock?: boolean;
+  muteStrategy?: MuteStrategy;
+  /** Keep every input device the profile doesn't cover muted while it is active. */
+  solo?: boolean;
+  /** Make the first device the system default for this role on activation. */
+  makeDefault?: DefaultRole | null;
+  /** Processes that activate and unmute this profile when they start recording. */
+  activateOnCaptureBy?: string[];
+  /** HID mute button (`vvvv:pppp`) toggling this profile; see `list_hid_buttons`. */
+  hidDevice?: string | null;
+}
+
+/** A scene device: unmuting the profile applies `mutedWhenActive`, muting it mutes. */
+export interface SceneDevice {
+  deviceId: string;
+  mutedWhenActive: boolean;
+}
+
+/** How a profile mutes: the mute flag, zero volume (restored on unmute), or both. */
+export type MuteStrategy = "flag" | "volume" | "both";
+
+/** USB busylight colours (`#rrggbb`); a mixed state shows `unmutedColor`. */
+export interface BusylightSettings {
+  enabled: boolean;
+  mutedColor: string;
+  unmutedColor: string;
+}
+
+/** A connected light `busylight` can drive; see `list_hid_lights`. */
+export interface HidLight {
+  name: string;
+  vendorId: number;
+  productId: number;
+  protocol: "luxafor" | "kuando";
+}
+
+/** A connected device with a HID mute button. */
+export interface HidButton {
+  id: string;
+  name: string;
+  hasLed: boolean;
+}
+
+/** Lock key whose LED shows the mute state. */
+export type LedKey = "scroll-lock" | "num-lock" | "caps-lock";
+
+/** What happens when a profile device is muted or unmuted outside TogMic. */
+export type ExternalChangePolicy = "adopt" | "sync-to-change" | "ignore";
+
+/** Payload of `state-reconciled`, emitted for each external change. */
+export interface StateReconciledEvent {
+  policy: ExternalChangePolicy;
+  action: "adopted" | "propagated" | "ignored";
+  /** Devices whose state changed outside TogMic. */
+  changed: string[];
+  /** Devices TogMic changed in response; empty unless propagated. */
+  devices: string[];
+  observed: MuteState;
+  /** Profile state afterwards; differs from `observed` when ignored. */
+  state: MuteState;
+}
+
+/** Payload of `speaking-while-muted`. */
+export interface SpeakingWhileMutedEvent {
+  deviceId: string;
+  peak: number;
+}
+
+/** Payload of `voice-calibration-phase`, emitted as each calibration phase starts. */
+export interface VoiceCalibrationPhaseEvent {
+  phase: "ambient" | "speaking";
+  seconds: number;
+}
+
+/** Result of `calibrate_voice_threshold`; `threshold` is stored in the settings. */
+export interface VoiceCalibration {
+  ambient: number;
+  speaking: number;
+  threshold: number;
+}
+
+/** An application capturing from a microphone; see `get_mic_consumers`. */
+export interface MicConsumer {
+  deviceId: string;
+  processName: string;
+  pid: number;
+}
+
+export interface MicConsumersChangedEvent {
+  consumers: MicConsumer[];
+}
+
+/** Payload of `capture-started`, for processes no profile lists (`onUnknownCapture: "notify"`). */
+export interface CaptureStartedEvent {
+  process: string;
+  pid: number;
+  deviceId: string;
+}
+
+/** Payload of `system-resumed`, once the state has been re-read after sleep. */
+export interface SystemResumedEvent {
+  state: MuteState;
+}
+
+/** Payload of `session-changed`, after a remote desktop connection or user switch. */
+export interface SessionChangedEvent {
+  change: "consoleConnected" | "consoleDisconnected" | "remoteConnected" | "remoteDisconnected";
+  /** Profile device IDs replaced by a device of the same name in the new session. */
+  remapped: { from: string; to: string; name: string }[];
+}
+
+/** None of the active profile's devices exist in the current session. */
+export interface ProfileDevicesUnavailableEvent {
+  profileId: string;
+  deviceIds: string[];
+}
+
+/** Devices of a profile noticed missing for the first time; `profile-devices-missing`. */
+export interface ProfileDevicesMissingEvent {
+  profileId: string;
+  /** Newly noticed missing devices. */
+  deviceIds: string[];
+  /** Every missing device of the profile. */
+  missing: string[];
+  allMissing: boolean;
+}
+
+/** The config couldn't be parsed at startup; `config-load-problem` or `get_config_load_problem`. */
+export interface ConfigLoadProblem {
+  /** `unmoved`: the broken file is still in place and saving needs `force`. */
+  recovery: "restoredBackup" | "defaults" | "unmoved";
+  error: string;
+  configFile: string;
+  /** Where the broken file was kept. */
+  invalidFile: string;
+  backupFile: string | null;
+  detectedAt: number;
+}
+
+/** Payload of `audio-error`, a failure in the background (worker, polling, listeners, tray). */
+export interface AudioErrorEvent {
+  category: "device" | "controller" | "listener" | "tray" | "logging";
+  message: string;
+  /** Details such as the affected devices, or null. */
+  context: unknown;
+  /** How often the same error was left out since it was last reported. */
+  repeated: number;
+}
+
+/** Payload of `sound-load-error`: a custom sound file was rejected; the built-in sound plays instead. */
+export type TrayIconStyle = "default" | "high-contrast" | "symbols";
+
+/** A tray menu entry that opens the window at `route` (`"settings/behavior"`). */
+export interface TrayMenuItem {
+  /** Backend locale key of the label, or the label itself. */
+  label: string;
+  route: string;
+}
+
+export type SoundCategory = "standard" | "communications" | "soundEffects";
+
+export interface SoundLoadErrorEvent {
+  file: string;
+  error: string;
+}
+
+/** A mute state transition returned by `get_mute_history`. */
+export interface MuteHistoryEntry {
+  /** Milliseconds since the Unix epoch. */
+  at: number;
+  state: MuteState;
+  source: string;
+  profileId: string | null;
+  deviceCount: number;
+}
+
+/** Payload of `sync-thread-restarted` / `sync-thread-failed` and `get_sync_thread_status`. */
+export interface SyncThreadStatus {
+  /** False on Windows, which has no polling thread. */
+  supervised: boolean;
+  restarts: number;
+  consecutiveFailures: number;
+  lastFailure: string | null;
+  /** Retrying stopped; external mute changes go unnoticed until TogMic restarts. */
+  gaveUp: boolean;
+}
+
+export interface MuteLockEnforcedEvent {
+  deviceId: string;
+}
+
+/** A microphone has been live for long; see `maxUnmutedMinutes`. */
+export interface HotMicWarningEvent {
+  unmutedSecs: number;
+  /** 0 once the limit is reached. */
+  secondsRemaining: number;
+  willMute: boolean;
+}
+
+/** Ticks every second while a temporary unmute runs. */
+export interface TemporaryUnmuteStartedEvent {
+  secondsRemaining: number;
+}
+
+export interface TemporaryUnmuteEndedEvent {
+  reason: "expired" | "cancelled";
+}
+
+/** Result of `get_config_path`; the files may not exist yet. */
+export interface ConfigPaths {
+  configFile: string | null;
+  /** Whether `--config` or `TOGMIC_CONFIG` chose the config file. */
+  configSource: "default" | "cli" | "env";
+  configDir: string | null;
+  logsDir: string | null;
+  diagnosticsDir: string;
+  soundsDir: string | null;
 }
 
 export interface AppSettings {
@@ -19,7 +294,251 @@ export interface AppSettings {
   autostart: boolean;
   checkUpdates: boolean;
   closeToTray: boolean;
+  /** Hide the window to the tray when it is minimized. */
+  minimizeToTray?: boolean;
+  /** Offer "Open config folder" in the tray menu. */
+  trayConfigFolder?: boolean;
+  /** Left-clicking the tray icon toggles mute; unset uses the platform default (off on macOS). */
+  trayLeftClickToggles?: boolean;
+  /** The embedded tray icons instead of the generated ones with a state badge. */
+  classicTrayIcons?: boolean;
+  /** "high-contrast" and "symbols" show the state by shape instead of color. */
+  trayIconStyle?: TrayIconStyle;
+  /** Entries below "Show Window"; defaults to "Open Settings". */
+  trayExtraItems?: TrayMenuItem[];
   startMinimized: boolean;
+  apiPort?: number | null;
+  mqtt?: MqttSettings | null;
+  obs?: ObsSettings | null;
+  webhooks?: Webhook[];
+  hooks?: HookSettings | null;
+  pollIntervalMs?: number;
+  /** Deadline of a single device call before the audio thread is restarted. */
+  audioTimeoutMs?: number;
+  mixedToggleTarget?: "mute" | "unmute";
+  restoreOnExit?: "none" | "restore-original" | "force-unmute";
+  /** Unmute every input device on exit, overriding restoreOnExit. */
+  unmuteAllOnExit?: boolean;
+  panicUnmuteKey?: string | null;
+  undoKey?: string | null;
+  /** How long a mute change can be undone. */
+  undoWindowSeconds?: number;
+  muteOnLock?: boolean;
+  restoreOnUnlock?: boolean;
+  idleMuteMinutes?: number;
+  unmuteOnReturn?: boolean;
+  onDeviceLost?: "none" | "mute-remaining" | "notify-only";
+  collectStats?: boolean;
+  /** Days the mute history is kept; 0 records none. */
+  historyRetentionDays?: number;
+  hotkeySuspendMinutes?: number;
+  /** Every profile's hotkey toggles that profile's devices; see `profile-mute-changed`. */
+  multiProfileHotkeys?: boolean;
+  maxUnmutedMinutes?: number;
+  hotMicWarningMinutes?: number;
+  hotMicAction?: "mute" | "warn";
+  hotMicNotify?: boolean;
+  externalChangePolicy?: ExternalChangePolicy;
+  /** Remind when speaking while muted; see `calibrate_voice_threshold`. */
+  speakingReminder?: boolean;
+  speakingThreshold?: number;
+  speakingReminderNotify?: boolean;
+  speakingReminderSound?: boolean;
+  /** Watch which applications capture from the microphones; off for privacy. */
+  trackMicUsage?: boolean;
+  /** What to do when an application no profile lists starts recording. */
+  onUnknownCapture?: "ignore" | "notify";
+  /** Light a keyboard lock-key LED while muted (Windows). */
+  ledIndicator?: boolean;
+  ledIndicatorKey?: LedKey;
+  busylight?: BusylightSettings;
+  /** Mute state sync with TogMic on other machines; null keeps it off. */
+  peers?: PeerSettings | null;
+  /** Skip sounds and notifications while Focus Assist is on (Windows). */
+  respectFocusAssist?: boolean;
+  /** Show a desktop notification when the mute state changes. */
+  notifyMuteChanges?: boolean;
+  /** "system" plays the desktop's sound theme (Linux). */
+  soundTheme?: "bundled" | "system";
+  /** Stream category of the feedback sounds (Windows). */
+  soundCategory?: SoundCategory;
+  /** Larger custom sound files (mute.wav etc.) are ignored. */
+  maxSoundFileMb?: number;
+  updateChannel?: "stable" | "beta";
+  vad?: VadSettings;
+}
+
+/** Voice activity detection; levels are peak meter values from 0 to 1. */
+export interface VadSettings {
+  threshold: number;
+  /** Level speech has to fall below to end; at most `threshold`. */
+  releaseThreshold: number;
+  attackMs: number;
+  releaseMs: number;
+  /** How long the level has to stay low before speech ends. */
+  holdMs: number;
+  /** Meter readings per second, 10 to 20. */
+  sampleRateHz: number;
+}
+
+/** Speech started or ended; `voice-activity`, while `start_voice_monitor` runs. */
+export interface VoiceActivityEvent {
+  speaking: boolean;
+  deviceId: string;
+  level: number;
+}
+
+export interface UpdateInfo {
+  available: boolean;
+  version: string | null;
+  notes: string | null;
+  date: string | null;
+}
+
+export interface UpdateDownloadProgress {
+  downloaded: number;
+  total: number | null;
+}
+
+export type MuteState = "muted" | "unmuted" | "mixed";
+
+export interface DeviceMuteState {
+  deviceId: string;
+  muted: boolean;
+}
+
+/** What `toggle_mute` toggles; the active profile if omitted. */
+export type ToggleScope = "profile" | "all" | `device:${string}`;
+
+/** Result of `get_audio_devices`. */
+export interface DeviceList {
+  devices: AudioDevice[];
+  /** Unix time in milliseconds of the enumeration the list comes from. */
+  refreshedAt: number;
+  ageMs: number;
+}
+
+/** Result of `toggle_mute`. */
+export interface ToggleResult {
+  muted: boolean;
+  /** Still being applied; `mute-applied` or `mute-apply-failed` follows with `generation`. */
+  pending: boolean;
+  deviceCount: number;
+  profileId: string | null;
+  /** `null` for a single device, which is applied before returning. */
+  generation: number | null;
+}
+
+/** A single device was toggled with the `device:<id>` scope; `device-mute-changed`. */
+export interface DeviceMuteChangedEvent {
+  deviceId: string;
+  muted: boolean;
+  source: string;
+}
+
+/** Entry of `get_device_mute_states`. */
+export interface DeviceMuteStatus extends DeviceMuteState {
+  /** The last call to the device finished within `audioTimeoutMs`. */
+  healthy: boolean;
+}
+
+/** A device call overran its deadline; `audio-timeout`. */
+export interface AudioTimeoutEvent {
+  deviceId: string | null;
+  operation: string;
+  timeoutMs: number;
+}
+
+export type MuteSource =
+  | "hotkey"
+  | "tray"
+  | "ui"
+  | "external"
+  | "startup"
+  | "auto"
+  | "undo";
+
+export interface MuteStateChangedEvent {
+  muted: boolean;
+  state: MuteState;
+  source: MuteSource;
+  profileId: string | null;
+  deviceCount: number;
+  devices: DeviceMuteState[];
+  /** Milliseconds since the Unix epoch */
+  timestamp: number;
+}
+
+export interface HookSettings {
+  onMute?: string | null;
+  onUnmute?: string | null;
+  timeoutSecs?: number;
+}
+
+export interface Webhook {
+  url: string;
+  method?: string;
+  on?: "mute" | "unmute" | "both";
+  headers?: Record<string, string>;
+}
+
+export interface ObsSettings {
+  host?: string;
+  port?: number;
+  password?: string | null;
+  inputName: string;
+  mirrorFromObs?: boolean;
+}
+
+export type FocusAssistStatus =
+  | "off"
+  | "priorityOnly"
+  | "alarmsOnly"
+  | "quietTime"
+  | "presentation"
+  | "fullScreen"
+  | "unknown";
+
+/** Whether the OS lets TogMic use the microphone; only macOS gates it. */
+export type MicPermission =
+  | "granted"
+  | "denied"
+  | "restricted"
+  | "notDetermined"
+  | "notRequired";
+
+/** Result of `get_backend_info`. */
+export interface BackendInfo {
+  version: string;
+  os: string;
+  focusAssist: FocusAssistStatus;
+  micPermission: MicPermission;
+  /** Global shortcuts through the desktop portal (Wayland). */
+  shortcutPortal: ShortcutPortalStatus;
+}
+
+export interface ShortcutPortalStatus {
+  /** Shortcuts go through the portal instead of the shortcut plugin. */
+  active: boolean;
+  state: "unused" | "pending" | "bound" | "failed";
+  error: string | null;
+}
+
+export interface PeerSettings {
+  port?: number;
+  /** Shared by all machines. */
+  secret: string;
+  /** `host` or `host:port`, in addition to peers found over mDNS. */
+  peers?: string[];
+  discover?: boolean;
+}
+
+export interface MqttSettings {
+  brokerUrl: string;
+  username?: string | null;
+  password?: string | null;
+  baseTopic?: string;
+  discoveryPrefix?: string;
 }
 
 export interface Config {
@@ -35,6 +554,8 @@ export interface AppContextType {
   refreshDevices: () => Promise<void>;
   saveProfile: (profile: HotkeyProfile) => Promise<void>;
   deleteProfile: (id: string) => Promise<void>;
+  /** Persist a new order; `ids` must contain every profile exactly once. */
+  reorderProfiles: (ids: string[]) => Promise<void>;
   setActiveProfile: (profile: HotkeyProfile) => Promise<void>;
   deactivateProfile: () => Promise<void>;
   registerHotkey: (hotkey: string) => Promise<void>;
@@ -0,0 +1,123 @@
+import type {
+  HotkeyBinding,
+  HotkeyKey,
+  HotkeyModifier,
+} from "@/contexts/AppContext";
+
+// Canonical order, matching the backend's `Modifier`
+const MODIFIERS: HotkeyModifier[] = [
+  "commandOrControl",
+  "shift",
+  "alt",
+  "super",
+];
+
+const MODIFIER_NAMES: Record<HotkeyModifier, string> = {
+  commandOrControl: "CommandOrControl",
+  shift: "Shift",
+  alt: "Alt",
+  super: "Super",
+};
+
+const MODIFIER_ALIASES: Record<string, HotkeyModifier> = {
+  commandorcontrol: "commandOrControl",
+  cmdorctrl: "commandOrControl",
+  commandorctrl: "commandOrControl",
+  cmdorcontrol: "commandOrControl",
+  control: "commandOrControl",
+  ctrl: "commandOrControl",
+  command: "commandOrControl",
+  cmd: "commandOrControl",
+  shift: "shift",
+  alt: "alt",
+  option: "alt",
+  super: "super",
+  meta: "super",
+  win: "super",
+};
+
+function formatKey(key: HotkeyKey): string {
+  const hex = (code: number) =>
+    `0x${code.toString(16).toUpperCase().padStart(2, "0")}`;
+  switch (key.type) {
+    case "named":
+      return key.value;
+    case "vk":
+      return `vk:${hex(key.value)}`;
+    case "scancode":
+      return `sc:${hex(key.value)}`;
+  }
+}
+
+function parseKey(key: string): HotkeyKey | null {
+  const code = (text: string) => {
+    const value = /^0x/i.test(text) ? parseInt(text.slice(2), 16) : Number(text);
+    return Number.isInteger(value) ? value : null;
+  };
+  if (key.startsWith("vk:")) {
+    const value = code(key.slice(3));
+    return value !== null && value >= 0x01 && value <= 0xfe
+      ? { type: "vk", value }
+      : null;
+  }
+  if (key.startsWith("sc:")) {
+    const value = code(key.slice(3));
+    const valid =
+      value !== null &&
+      ((value >= 0x01 && value <= 0x7f) || (value >= 0xe001 && value <= 0xe07f));
+    return valid ? { type: "scancode", value } : null;
+  }
+  return key ? { type: "named", value: key } : null;
+}
+
+/** The binding in the shortcut plugin's string form, e.g. `CommandOrControl+Shift+M`. */
+export function formatHotkey(binding: HotkeyBinding): string {
+  return hotkeyParts(binding).join("+");
+}
+
+/** Parse the string form; `null` if it has no key or an unknown modifier. */
+export function parseHotkey(text: string): HotkeyBinding | null {
+  const trimmed = text.trim();
+  // The last part is the key, which may be `+` itself (`Shift++`)
+  let modifierPart: string;
+  let keyPart: string;
+  if (trimmed === "+" || trimmed.endsWith("++")) {
+    modifierPart = trimmed.slice(0, Math.max(trimmed.length - 2, 0));
+    keyPart = "+";
+  } else {
+    const split = trimmed.lastIndexOf("+");
+    modifierPart = split < 0 ? "" : trimmed.slice(0, split);
+    keyPart = trimmed.slice(split + 1).trim();
+  }
+
+  const modifiers = new Set<HotkeyModifier>();
+  for (const part of modifierPart.split("+")) {
+    const name = part.trim();
+    if (!name) continue;
+    const modifier = MODIFIER_ALIASES[name.toLowerCase()];
+    if (!modifier) return null;
+    modifiers.add(modifier);
+  }
+
+  const key = parseKey(keyPart);
+  if (!key) return null;
+  return { modifiers: MODIFIERS.filter((m) => modifiers.has(m)), key };
+}
+
+/** A stored hotkey as a binding, whether it is typed or the string of older configs. */
+export function normalizeHotkey(
+  hotkey: HotkeyBinding | string | null | undefined,
+): HotkeyBinding | null {
+  if (!hotkey) return null;
+  return typeof hotkey === "string" ? parseHotkey(hotkey) : hotkey;
+}
+
+/** The binding's keys for display, modifiers first. */
+export function hotkeyParts(binding: HotkeyBinding): string[] {
+  return [
+    ...MODIFIERS.filter((m) => binding.modifiers.includes(m)).map(
+      (m) => MODIFIER_NAMES[m],
+    ),
+    formatKey(binding.key),
+  ];
+}
@@ -1,12 +1,23 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useApp } from "@/contexts/useApp";
 import { useMuteState } from "@/contexts/useMuteState";
+import { useSettings } from "@/contexts/useSettings";
+import type {
+  AudioErrorEvent,
+  MicConsumer,
+  MicConsumersChangedEvent,
+  SoundLoadErrorEvent,
+  SyncThreadStatus,
+} from "@/contexts/AppContext";
 import { MuteIndicator } from "@/components/MuteIndicator";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
 import { Kbd, KbdGroup } from "@/components/ui/kbd";
 import { Separator } from "@/components/ui/separator";
+import { hotkeyParts } from "@/lib/hotkey";
 import { cn } from "@/lib/utils";
-import { useCallback, useMemo } from "react";
+import { useCallback, useEffect, useMemo, useState } from "react";
 import { useTranslation } from "react-i18next";
 
 const KEY_LABELS: Record<string, string> = {
@@ -18,10 +29,79 @@ const KEY_LABELS: Record<string, string> = {
 };
 const formatKey = (key: string) => KEY_LABELS[key] ?? key;
 
+const AUDIO_ERROR_LABELS: Record<AudioErrorEvent["category"], string> = {
+  device: "audioErrorDevice",
+  controller: "audioErrorController",
+  listener: "audioErrorListener",
+  tray: "audioErrorTray",
+  logging: "audioErrorLogging",
+};
+
 export function DashboardPage() {
   const { t } = useTranslation();
   const { activeProfile, devices } = useApp();
   const { isMuted } = useMuteState();
+  const { settings } = useSettings();
+  const [micConsumers, setMicConsumers] = useState<MicConsumer[]>([]);
+  const [audioError, setAudioError] = useState<AudioErrorEvent | null>(null);
+  const [soundLoadError, setSoundLoadError] = useState<SoundLoadErrorEvent | null>(null);
+  const [syncFailure, setSyncFailure] = useState<SyncThreadStatus | null>(null);
+
+  // Background failures show as a badge until dismissed
+  useEffect(() => {
+    const unlisten = listen<AudioErrorEvent>("audio-error", (event) => {
+      setAudioError(event.payload);
+    });
+    const unlistenSound = listen<SoundLoadErrorEvent>("sound-load-error", (event) => {
+      setSoundLoadError(event.payload);
+    });
+    return () => {
+      void unlisten.then((fn) => fn());
+      void unlistenSound.then((fn) => fn());
+    };
+  }, []);
+
+  // Unlike the badges above, a sync thread that was given up on stays until restart
+  useEffect(() => {
+    let mounted = true;
+    invoke<SyncThreadStatus>("get_sync_thread_status")
+      .then((status) => {
+        if (mounted && status.gaveUp) setSyncFailure(status);
+      })
+      .catch(console.error);
+    const unlisten = listen<SyncThreadStatus>("sync-thread-failed", (event) => {
+      if (mounted) setSyncFailure(event.payload);
+    });
+    return () => {
+      mounted = false;
+      void unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!settings.trackMicUsage) {
+      setMicConsumers([]);
+      return;
+    }
+    let mounted = true;
+    invoke<MicConsumer[]>("get_mic_consumers")
+      .then((consumers) => {
+        if (mounted) setMicConsumers(consumers);
+      })
+      .catch(console.error);
+    const unlisten = listen<MicConsumersChangedEvent>("mic-consumers-changed", (event) => {
+      if (mounted) setMicConsumers(event.payload.consumers);
+    });
+    return () => {
+      mounted = false;
+      void unlisten.then((fn) => fn());
+    };
+  }, [settings.trackMicUsage]);
+
+  const micConsumerNames = useMemo(
+    () => [...new Set(micConsumers.map((consumer) => consumer.processName))],
+    [micConsumers],
+  );
 
   const defaultDeviceId = "default-mic";
   const allDevicesId = "all-mics";
@@ -32,17 +112,25 @@ export function DashboardPage() {
     return devices.find((device) => device.id === deviceId)?.name ?? t("unknownDevice");
   }, [devices, t]);
 
+  // A scene lists its devices in deviceStates
+  const sceneDeviceIds = useMemo(() =>
+    activeProfile?.deviceStates?.map((d) => d.deviceId) ?? [],
+  [activeProfile]);
+
+  const usesAllDevices = sceneDeviceIds.length === 0 &&
+    ((activeProfile?.deviceIds.length ?? 0) > 1 || !!activeProfile?.deviceIds.includes(allDevicesId));
+
   const deviceCount = useMemo(() =>
-    (activeProfile?.deviceIds.length ?? 0) > 1 || activeProfile?.deviceIds.includes(allDevicesId)
+    usesAllDevices
       ? devices.length
-      : activeProfile?.deviceIds.length ?? 0,
-  [activeProfile, devices]);
+      : sceneDeviceIds.length || (activeProfile?.deviceIds.length ?? 0),
+  [activeProfile, devices, sceneDeviceIds, usesAllDevices]);
 
   const displayDeviceIds = useMemo(() =>
-    (activeProfile?.deviceIds.length ?? 0) > 1 || activeProfile?.deviceIds.includes(allDevicesId)
+    usesAllDevices
       ? [allDevicesId]
-      : (activeProfile?.deviceIds ?? []),
-  [activeProfile]);
+      : sceneDeviceIds.length > 0 ? sceneDeviceIds : (activeProfile?.deviceIds ?? []),
+  [activeProfile, sceneDeviceIds, usesAllDevices]);
 
   return (
     <div className="space-y-6">
@@ -104,7 +192,7 @@ export function DashboardPage() {
                   <div className="flex items-center justify-between gap-4">
                     <span className="text-sm text-muted-foreground shrink-0">{t("hotkey")}</span>
                     <KbdGroup className="flex-wrap justify-end">
-                      {activeProfile.toggleKey.split("+").map((key, i, arr) => (
+                      {hotkeyParts(activeProfile.toggleKey).map((key, i, arr) => (
                         <span key={key} className="inline-flex items-center gap-1">
                           <Kbd>{formatKey(key)}</Kbd>
                           {i < arr.length - 1 && (
@@ -120,6 +208,11 @@ export function DashboardPage() {
                       {t("devicesCount", { count: deviceCount })}
                     </span>
                     <div className="flex flex-wrap gap-1 justify-end">
+                      {activeProfile.solo && (
+                        <Badge variant="outline" className="text-xs" title={t("soloActiveDesc")}>
+                          {t("soloActive")}
+                        </Badge>
+                      )}
                       {displayDeviceIds.map((id) => (
                         <Badge key={id} variant="secondary" className="text-xs"
                           style={{ boxShadow: "var(--shadow-btn)" }}>
@@ -128,6 +221,54 @@ export function DashboardPage() {
                       ))}
                     </div>
                   </div>
+
+                  {audioError && (
+                    <div className="flex items-center justify-between gap-4">
+                      <span className="text-sm text-muted-foreground shrink-0">{t("audioError")}</span>
+                      <Badge
+                        variant="destructive"
+                        className="text-xs cursor-pointer"
+                        title={`${audioError.message}\n${t("audioErrorDismiss")}`}
+                        onClick={() => setAudioError(null)}
+                      >
+                        {t(AUDIO_ERROR_LABELS[audioError.category])}
+                      </Badge>
+                    </div>
+                  )}
+
+                  {syncFailure && (
+                    <div className="flex items-center justify-between gap-4">
+                      <span className="text-sm text-muted-foreground shrink-0">{t("syncThreadFailed")}</span>
+                      <Badge
+                        variant="destructive"
+                        className="text-xs"
+                        title={syncFailure.lastFailure ?? undefined}
+                      >
+                        {t("syncThreadFailedHint")}
+                      </Badge>
+                    </div>
+                  )}
+
+                  {soundLoadError && (
+                    <div className="flex items-center justify-between gap-4">
+                      <span className="text-sm text-muted-foreground shrink-0">{t("soundLoadError")}</span>
+                      <Badge
+                        variant="destructive"
+                        className="text-xs cursor-pointer"
+                        title={`${soundLoadError.error}\n${t("audioErrorDismiss")}`}
+                        onClick={() => setSoundLoadError(null)}
+                      >
+                        {soundLoadError.file.split(/[\\/]/).pop()}
+                      </Badge>
+                    </div>
+                  )}
+
+                  {micConsumerNames.length > 0 && (
+                    <div className="flex items-center justify-between gap-4">
+                      <span className="text-sm text-muted-foreground shrink-0">{t("micInUseBy")}</span>
+                      <span className="text-sm text-right">{micConsumerNames.join(", ")}</span>
+                    </div>
+                  )}
                 </div>
               </div>
             ) : (
@@ -10,6 +10,7 @@ import {
 import { Button } from "@/components/ui/button";
 import { Kbd, KbdGroup } from "@/components/ui/kbd";
 import { Separator } from "@/components/ui/separator";
+import { hotkeyParts } from "@/lib/hotkey";
 import { cn } from "@/lib/utils";
 import { Play, Square, Edit, Trash2 } from "lucide-react";
 import { memo, useState, useCallback, useMemo } from "react";
@@ -72,18 +73,22 @@ export const ProfileCard = memo(function ProfileCard({ profile, isActive, onEdit
   }, [deleteProfile, profile.id]);
 
   const deviceNames = useMemo(() => {
-    if (profile.deviceIds.length > 1 || profile.deviceIds.includes(allDevicesId)) {
+    const sceneIds = profile.deviceStates?.map((d) => d.deviceId) ?? [];
+    if (
+      sceneIds.length === 0 &&
+      (profile.deviceIds.length > 1 || profile.deviceIds.includes(allDevicesId))
+    ) {
       return t("allDevices");
     }
 
-    return profile.deviceIds
+    return (sceneIds.length > 0 ? sceneIds : profile.deviceIds)
       .map((id) => {
         if (id === defaultDeviceId) return t("defaultDevice");
         if (id === allDevicesId) return t("allDevices");
         return devices.find((d) => d.id === id)?.name ?? t("unknownDevice");
       })
       .join(", ");
-  }, [profile.deviceIds, devices, t]);
+  }, [profile.deviceIds, profile.deviceStates, devices, t]);
 
   return (
     <Card
@@ -121,7 +126,7 @@ export const ProfileCard = memo(function ProfileCard({ profile, isActive, onEdit
         <div className="flex items-center justify-between">
           <p className="font-mono text-[9px] tracking-[0.12em] uppercase text-muted-foreground">{t("hotkey")}</p>
           <KbdGroup className="flex-wrap justify-end">
-            {profile.toggleKey.split("+").map((key, i, arr) => (
+            {hotkeyParts(profile.toggleKey).map((key, i, arr) => (
               <span key={key} className="inline-flex items-center gap-1">
                 <Kbd>{formatKey(key)}</Kbd>
                 {i < arr.length - 1 && <span className="text-muted-foreground text-xs">+</span>}
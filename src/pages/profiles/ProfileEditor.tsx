@@ -1,6 +1,8 @@
 import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { useApp } from "@/contexts/useApp";
-import type { HotkeyProfile } from "@/contexts/AppContext";
+import type { HidButton, HotkeyProfile, MuteStrategy } from "@/contexts/AppContext";
+import { formatHotkey, parseHotkey } from "@/lib/hotkey";
 import { HotkeyInput } from "@/components/HotkeyInput";
 import {
   Dialog,
@@ -22,10 +24,16 @@ import {
   DropdownMenuSeparator,
   DropdownMenuTrigger,
 } from "@/components/ui/dropdown-menu";
-import { ChevronDown } from "lucide-react";
+import { ChevronDown, X } from "lucide-react";
 import { Switch } from "@/components/ui/switch";
 import { useTranslation } from "react-i18next";
 
+const muteStrategyLabels: Record<MuteStrategy, string> = {
+  flag: "muteStrategyFlag",
+  volume: "muteStrategyVolume",
+  both: "muteStrategyBoth",
+};
+
 interface ProfileEditorProps {
   profile?: HotkeyProfile | null;
   onSave: () => void;
@@ -46,7 +54,9 @@ export function ProfileEditor({
   const allDevicesId = "all-mics";
 
   const [name, setName] = useState(profile?.name ?? "");
-  const [toggleKey, setToggleKey] = useState(profile?.toggleKey ?? "");
+  const [toggleKey, setToggleKey] = useState(
+    profile ? formatHotkey(profile.toggleKey) : "",
+  );
   const [selectedDeviceId, setSelectedDeviceId] = useState<string>(() => {
     if (!profile?.deviceIds || profile.deviceIds.length === 0) {
       return defaultDeviceId;
@@ -61,6 +71,19 @@ export function ProfileEditor({
   const [ignoreModifiers, setIgnoreModifiers] = useState(
     profile?.ignoreModifiers ?? false,
   );
+  const [muteLock, setMuteLock] = useState(profile?.muteLock ?? false);
+  const [solo, setSolo] = useState(profile?.solo ?? false);
+  const [hidDevice, setHidDevice] = useState<string | null>(profile?.hidDevice ?? null);
+  const [hidButtons, setHidButtons] = useState<HidButton[]>([]);
+  const [muteStrategy, setMuteStrategy] = useState<MuteStrategy>(
+    profile?.muteStrategy ?? "flag",
+  );
+  const [temporaryUnmuteKey, setTemporaryUnmuteKey] = useState(
+    profile?.temporaryUnmuteKey ? formatHotkey(profile.temporaryUnmuteKey) : "",
+  );
+  const [temporaryUnmuteSeconds, setTemporaryUnmuteSeconds] = useState(
+    profile?.temporaryUnmuteSeconds ?? 30,
+  );
   const [error, setError] = useState("");
   const [dropdownOpen, setDropdownOpen] = useState(false);
 
@@ -85,6 +108,15 @@ export function ProfileEditor({
       return;
     }
 
+    const binding = parseHotkey(toggleKey);
+    const temporaryBinding = temporaryUnmuteKey
+      ? parseHotkey(temporaryUnmuteKey)
+      : null;
+    if (!binding || (temporaryUnmuteKey && !temporaryBinding)) {
+      setError(t("invalidHotkey"));
+      return;
+    }
+
     if (!selectedDeviceId) {
       setError(t("deviceRequired"));
       return;
@@ -94,9 +126,17 @@ export function ProfileEditor({
       const newProfile: HotkeyProfile = {
         id: profile?.id ?? `profile-${Date.now()}`,
         name: name.trim(),
-        toggleKey: toggleKey,
+        toggleKey: binding,
         deviceIds: [selectedDeviceId],
+        // Scenes are edited in the config file; keep them through other changes
+        deviceStates: profile?.deviceStates,
         ignoreModifiers,
+        temporaryUnmuteKey: temporaryBinding,
+        temporaryUnmuteSeconds,
+        muteLock,
+        muteStrategy,
+        solo,
+        hidDevice,
       };
 
       await saveProfile(newProfile);
@@ -104,7 +144,17 @@ export function ProfileEditor({
     } catch (err) {
       setError(err instanceof Error ? err.message : t("failedToSave"));
     }
-  }, [name, toggleKey, selectedDeviceId, ignoreModifiers, profile, saveProfile, onSave, t]);
+  }, [name, toggleKey, selectedDeviceId, ignoreModifiers, temporaryUnmuteKey, temporaryUnmuteSeconds, muteLock, muteStrategy, solo, hidDevice, profile, saveProfile, onSave, t]);
+
+  const handleHidDropdownOpenChange = useCallback((isOpen: boolean) => {
+    if (!isOpen) return;
+    invoke<HidButton[]>("list_hid_buttons")
+      .then(setHidButtons)
+      .catch((err) => {
+        console.error("Failed to list HID buttons:", err);
+        setHidButtons([]);
+      });
+  }, []);
 
   const handleDropdownOpenChange = useCallback(async (isOpen: boolean) => {
     setDropdownOpen(isOpen);
@@ -161,6 +211,131 @@ export function ProfileEditor({
             />
           </div>
 
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label htmlFor="mute-lock" className="text-sm font-medium">
+                {t("muteLock")}
+              </Label>
+              <p className="text-xs text-muted-foreground">
+                {t("muteLockDesc")}
+              </p>
+            </div>
+            <Switch
+              id="mute-lock"
+              checked={muteLock}
+              onCheckedChange={setMuteLock}
+            />
+          </div>
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label htmlFor="solo" className="text-sm font-medium">
+                {t("solo")}
+              </Label>
+              <p className="text-xs text-muted-foreground">
+                {t("soloDesc")}
+              </p>
+            </div>
+            <Switch
+              id="solo"
+              checked={solo}
+              onCheckedChange={setSolo}
+            />
+          </div>
+
+          <div className="space-y-2">
+            <Label>{t("hidDevice")}</Label>
+            <DropdownMenu onOpenChange={handleHidDropdownOpenChange}>
+              <DropdownMenuTrigger render={<Button type="button" variant="outline" className="w-full justify-between" />}>
+                <span className="truncate">
+                  {hidDevice
+                    ? (hidButtons.find((b) => b.id === hidDevice)?.name ?? hidDevice)
+                    : t("hidDeviceNone")}
+                </span>
+                <ChevronDown className="h-4 w-4 opacity-70" />
+              </DropdownMenuTrigger>
+              <DropdownMenuContent>
+                <DropdownMenuRadioGroup
+                  value={hidDevice ?? ""}
+                  onValueChange={(value) => setHidDevice(value ? String(value) : null)}
+                >
+                  <DropdownMenuRadioItem value="">
+                    {t("hidDeviceNone")}
+                  </DropdownMenuRadioItem>
+                  {hidButtons.map((button) => (
+                    <DropdownMenuRadioItem key={button.id} value={button.id}>
+                      {button.name}
+                    </DropdownMenuRadioItem>
+                  ))}
+                </DropdownMenuRadioGroup>
+              </DropdownMenuContent>
+            </DropdownMenu>
+            <p className="text-xs text-muted-foreground">
+              {t("hidDeviceDesc")}
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label>{t("muteStrategy")}</Label>
+            <DropdownMenu>
+              <DropdownMenuTrigger render={<Button type="button" variant="outline" className="w-full justify-between" />}>
+                <span className="truncate">
+                  {t(muteStrategyLabels[muteStrategy])}
+                </span>
+                <ChevronDown className="h-4 w-4 opacity-70" />
+              </DropdownMenuTrigger>
+              <DropdownMenuContent>
+                <DropdownMenuRadioGroup
+                  value={muteStrategy}
+                  onValueChange={(value) => setMuteStrategy(value as MuteStrategy)}
+                >
+                  {(Object.keys(muteStrategyLabels) as MuteStrategy[]).map((strategy) => (
+                    <DropdownMenuRadioItem key={strategy} value={strategy}>
+                      {t(muteStrategyLabels[strategy])}
+                    </DropdownMenuRadioItem>
+                  ))}
+                </DropdownMenuRadioGroup>
+              </DropdownMenuContent>
+            </DropdownMenu>
+            <p className="text-xs text-muted-foreground">
+              {t("muteStrategyDesc")}
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="temporary-unmute-key">{t("temporaryUnmuteKey")}</Label>
+            <div className="flex items-center gap-2">
+              <HotkeyInput value={temporaryUnmuteKey} onChange={setTemporaryUnmuteKey} />
+              {temporaryUnmuteKey && (
+                <Button
+                  type="button"
+                  variant="ghost"
+                  size="icon"
+                  aria-label={t("clearHotkey")}
+                  onClick={() => setTemporaryUnmuteKey("")}
+                >
+                  <X className="h-4 w-4" />
+                </Button>
+              )}
+            </div>
+            <div className="flex items-center justify-between gap-4">
+              <p className="text-xs text-muted-foreground">
+                {t("temporaryUnmuteKeyDesc")}
+              </p>
+              <Input
+                id="temporary-unmute-seconds"
+                type="number"
+                min={1}
+                max={3600}
+                className="w-24"
+                value={temporaryUnmuteSeconds}
+                onChange={(e) =>
+                  setTemporaryUnmuteSeconds(Math.max(1, Number(e.target.value) || 1))
+                }
+              />
+            </div>
+          </div>
+
           <div className="space-y-2">
             <Label>{t("devices")}</Label>
             <div className="relative">
@@ -2,7 +2,8 @@ import { useTranslation } from "react-i18next";
 import { useTheme } from "@/components/theme-context";
 import { useSettings } from "@/contexts/useSettings";
 import { useState, useEffect, useCallback, useMemo } from "react";
-import { check } from "@tauri-apps/plugin-updater";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { relaunch } from "@tauri-apps/plugin-process";
 import {
   Card,
@@ -18,6 +19,36 @@ import { Download } from "lucide-react";
 import { ConfirmDialog } from "@/components/ConfirmDialog";
 import { cn } from "@/lib/utils";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
+import type {
+  BackendInfo,
+  BusylightSettings,
+  ConfigPaths,
+  HidLight,
+  LedKey,
+  MicPermission,
+  SoundCategory,
+  TrayIconStyle,
+  UpdateDownloadProgress,
+  UpdateInfo,
+} from "@/contexts/AppContext";
+
+const ledKeyLabels: Record<LedKey, string> = {
+  "scroll-lock": "ledKeyScrollLock",
+  "num-lock": "ledKeyNumLock",
+  "caps-lock": "ledKeyCapsLock",
+};
+
+const trayIconStyleLabels: Record<TrayIconStyle, string> = {
+  default: "trayIconStyleDefault",
+  "high-contrast": "trayIconStyleHighContrast",
+  symbols: "trayIconStyleSymbols",
+};
+
+const soundCategoryLabels: Record<SoundCategory, string> = {
+  standard: "soundCategoryStandard",
+  communications: "soundCategoryCommunications",
+  soundEffects: "soundCategorySoundEffects",
+};
 
 function ThemePreview({ value }: { value: "light" | "dark" | "system" }) {
   if (value === "light")
@@ -290,18 +321,197 @@ export function SettingsPage({
     [updateSettings],
   );
 
+  const handleMinimizeToTrayChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ minimizeToTray: checked });
+      } catch (error) {
+        console.error("Failed to update minimize to tray setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleTrayLeftClickChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ trayLeftClickToggles: checked });
+      } catch (error) {
+        console.error("Failed to update tray left click setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleClassicTrayIconsChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ classicTrayIcons: checked });
+      } catch (error) {
+        console.error("Failed to update classic tray icons setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleTrayConfigFolderChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ trayConfigFolder: checked });
+      } catch (error) {
+        console.error("Failed to update tray config folder setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const [configPaths, setConfigPaths] = useState<ConfigPaths | null>(null);
+  useEffect(() => {
+    invoke<ConfigPaths>("get_config_path")
+      .then(setConfigPaths)
+      .catch(console.error);
+  }, []);
+
+  const handleOpenConfigFolder = useCallback(() => {
+    invoke("open_config_folder").catch(console.error);
+  }, []);
+
+  const handleNotifyUnknownCaptureChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ onUnknownCapture: checked ? "notify" : "ignore" });
+      } catch (error) {
+        console.error("Failed to update unknown capture setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleLedIndicatorChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ ledIndicator: checked });
+      } catch (error) {
+        console.error("Failed to update LED indicator setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const busylight: BusylightSettings = settings.busylight ?? {
+    enabled: false,
+    mutedColor: "#00ff00",
+    unmutedColor: "#ff0000",
+  };
+  const [hidLights, setHidLights] = useState<HidLight[] | null>(null);
+
+  const handleBusylightChange = useCallback(
+    async (patch: Partial<BusylightSettings>) => {
+      try {
+        await updateSettings({ busylight: { ...busylight, ...patch } });
+      } catch (error) {
+        console.error("Failed to update busylight setting:", error);
+      }
+    },
+    [busylight, updateSettings],
+  );
+
+  const handleDetectLights = useCallback(async () => {
+    try {
+      setHidLights(await invoke<HidLight[]>("list_hid_lights"));
+    } catch (error) {
+      console.error("Failed to list busylights:", error);
+      setHidLights([]);
+    }
+  }, []);
+
+  const [backendInfo, setBackendInfo] = useState<BackendInfo | null>(null);
+  useEffect(() => {
+    invoke<BackendInfo>("get_backend_info")
+      .then(setBackendInfo)
+      .catch(console.error);
+  }, [settings.respectFocusAssist]);
+
+  const handleRequestMicPermission = useCallback(async () => {
+    try {
+      const micPermission = await invoke<MicPermission>("request_mic_permission");
+      setBackendInfo((info) => (info ? { ...info, micPermission } : info));
+    } catch (error) {
+      console.error("Failed to request microphone access:", error);
+    }
+  }, []);
+
+  const handleOpenMicPrivacySettings = useCallback(() => {
+    invoke("open_mic_privacy_settings").catch(console.error);
+  }, []);
+
+  const handleNotifyMuteChangesChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ notifyMuteChanges: checked });
+      } catch (error) {
+        console.error("Failed to update mute notification setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleSystemSoundThemeChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ soundTheme: checked ? "system" : "bundled" });
+      } catch (error) {
+        console.error("Failed to update sound theme setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleMultiProfileHotkeysChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ multiProfileHotkeys: checked });
+      } catch (error) {
+        console.error("Failed to update multi-profile hotkey setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleRespectFocusAssistChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ respectFocusAssist: checked });
+      } catch (error) {
+        console.error("Failed to update Focus Assist setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
+  const handleTrackMicUsageChange = useCallback(
+    async (checked: boolean) => {
+      try {
+        await updateSettings({ trackMicUsage: checked });
+      } catch (error) {
+        console.error("Failed to update mic usage tracking setting:", error);
+      }
+    },
+    [updateSettings],
+  );
+
   const handleCheckForUpdates = async () => {
     setIsCheckingUpdate(true);
     setUpdateStatus(t("checkingForUpdates"));
 
     try {
-      const update = await check();
+      const update = await invoke<UpdateInfo>("check_for_updates");
 
-      if (update) {
+      if (update.available && update.version) {
         setUpdateStatus(t("updateAvailable", { version: update.version }));
         onUpdateFound?.({
           version: update.version,
-          body: update.body ?? undefined,
+          body: update.notes ?? undefined,
           date: update.date ?? undefined,
         });
       } else {
@@ -318,17 +528,27 @@ export function SettingsPage({
   const handleConfirmUpdate = async () => {
     if (!updateInfo) return;
 
+    setUpdateStatus(t("downloadingUpdate"));
+    const unlisten = await listen<UpdateDownloadProgress>(
+      "update-download-progress",
+      (event) => {
+        const { downloaded, total } = event.payload;
+        if (total) {
+          setUpdateStatus(
+            `${t("downloadingUpdate")} ${Math.floor((downloaded / total) * 100)}%`,
+          );
+        }
+      },
+    );
     try {
-      const update = await check();
-      if (update) {
-        setUpdateStatus(t("downloadingUpdate"));
-        await update.downloadAndInstall();
-        setUpdateStatus(t("updateInstalled"));
-        await relaunch();
-      }
+      await invoke("install_update");
+      setUpdateStatus(t("updateInstalled"));
+      await relaunch();
     } catch (error) {
       console.error("Failed to install update:", error);
       setUpdateStatus(t("failedToInstallUpdate"));
+    } finally {
+      unlisten();
     }
   };
 
@@ -525,6 +745,405 @@ export function SettingsPage({
                   onCheckedChange={(v) => { void handleCloseToTrayChange(v); }}
                 />
               </div>
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="minimize-to-tray"
+                    className="text-base font-medium"
+                  >
+                    {t("minimizeToTray")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("minimizeToTrayDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="minimize-to-tray"
+                  checked={settings.minimizeToTray ?? false}
+                  onCheckedChange={(v) => { void handleMinimizeToTrayChange(v); }}
+                />
+              </div>
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="tray-config-folder"
+                    className="text-base font-medium"
+                  >
+                    {t("trayConfigFolder")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("trayConfigFolderDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="tray-config-folder"
+                  checked={settings.trayConfigFolder ?? false}
+                  onCheckedChange={(v) => { void handleTrayConfigFolderChange(v); }}
+                />
+              </div>
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="tray-left-click"
+                    className="text-base font-medium"
+                  >
+                    {t("trayLeftClickToggles")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("trayLeftClickTogglesDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="tray-left-click"
+                  checked={settings.trayLeftClickToggles ?? backendInfo?.os !== "macos"}
+                  onCheckedChange={(v) => { void handleTrayLeftClickChange(v); }}
+                />
+              </div>
+
+              {backendInfo && backendInfo.os !== "macos" && (
+                <div className="flex items-center justify-between py-4">
+                  <div className="space-y-0.5">
+                    <Label
+                      htmlFor="classic-tray-icons"
+                      className="text-base font-medium"
+                    >
+                      {t("classicTrayIcons")}
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t("classicTrayIconsDescription")}
+                    </p>
+                  </div>
+                  <Switch
+                    id="classic-tray-icons"
+                    checked={settings.classicTrayIcons ?? false}
+                    onCheckedChange={(v) => { void handleClassicTrayIconsChange(v); }}
+                  />
+                </div>
+              )}
+
+              <div className="space-y-2 py-4">
+                <div className="space-y-0.5">
+                  <Label className="text-base font-medium">
+                    {t("trayIconStyle")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("trayIconStyleDescription")}
+                  </p>
+                </div>
+                <div className="flex gap-2">
+                  {(Object.keys(trayIconStyleLabels) as TrayIconStyle[]).map((style) => (
+                    <Button
+                      key={style}
+                      onClick={() => {
+                        void updateSettings({ trayIconStyle: style }).catch(console.error);
+                      }}
+                      variant={
+                        (settings.trayIconStyle ?? "default") === style
+                          ? "default"
+                          : "outline"
+                      }
+                      className="flex-1"
+                    >
+                      {t(trayIconStyleLabels[style])}
+                    </Button>
+                  ))}
+                </div>
+              </div>
+
+              <div className="flex items-center justify-between gap-4 py-4">
+                <div className="space-y-0.5 min-w-0">
+                  <Label className="text-base font-medium">{t("configFolder")}</Label>
+                  <p className="text-sm text-muted-foreground font-mono break-all">
+                    {configPaths?.configDir ?? t("configFolderUnknown")}
+                  </p>
+                </div>
+                <Button variant="outline" size="sm" onClick={handleOpenConfigFolder}>
+                  {t("open")}
+                </Button>
+              </div>
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="track-mic-usage"
+                    className="text-base font-medium"
+                  >
+                    {t("trackMicUsage")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("trackMicUsageDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="track-mic-usage"
+                  checked={settings.trackMicUsage ?? false}
+                  onCheckedChange={(v) => { void handleTrackMicUsageChange(v); }}
+                />
+              </div>
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="notify-unknown-capture"
+                    className="text-base font-medium"
+                  >
+                    {t("notifyUnknownCapture")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("notifyUnknownCaptureDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="notify-unknown-capture"
+                  checked={settings.onUnknownCapture === "notify"}
+                  disabled={!settings.trackMicUsage}
+                  onCheckedChange={(v) => { void handleNotifyUnknownCaptureChange(v); }}
+                />
+              </div>
+
+              {(backendInfo?.micPermission === "notDetermined" ||
+                backendInfo?.micPermission === "denied" ||
+                backendInfo?.micPermission === "restricted") && (
+                <div className="flex items-center justify-between py-4">
+                  <div className="space-y-0.5">
+                    <Label className="text-base font-medium">{t("micPermission")}</Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t(
+                        backendInfo.micPermission === "notDetermined"
+                          ? "micPermissionNotDetermined"
+                          : "micPermissionDenied",
+                      )}
+                    </p>
+                  </div>
+                  {backendInfo.micPermission === "notDetermined" ? (
+                    <Button variant="outline" size="sm" onClick={() => { void handleRequestMicPermission(); }}>
+                      {t("micPermissionRequest")}
+                    </Button>
+                  ) : (
+                    <Button variant="outline" size="sm" onClick={handleOpenMicPrivacySettings}>
+                      {t("micPermissionOpenSettings")}
+                    </Button>
+                  )}
+                </div>
+              )}
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="notify-mute-changes"
+                    className="text-base font-medium"
+                  >
+                    {t("notifyMuteChanges")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("notifyMuteChangesDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="notify-mute-changes"
+                  checked={settings.notifyMuteChanges ?? false}
+                  onCheckedChange={(v) => { void handleNotifyMuteChangesChange(v); }}
+                />
+              </div>
+
+              {backendInfo?.os === "linux" && (
+                <div className="flex items-center justify-between py-4">
+                  <div className="space-y-0.5">
+                    <Label
+                      htmlFor="system-sound-theme"
+                      className="text-base font-medium"
+                    >
+                      {t("systemSoundTheme")}
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t("systemSoundThemeDescription")}
+                    </p>
+                  </div>
+                  <Switch
+                    id="system-sound-theme"
+                    checked={settings.soundTheme === "system"}
+                    onCheckedChange={(v) => { void handleSystemSoundThemeChange(v); }}
+                  />
+                </div>
+              )}
+
+              {backendInfo?.os === "windows" && (
+                <div className="space-y-2 py-4">
+                  <div className="space-y-0.5">
+                    <Label className="text-base font-medium">
+                      {t("soundCategory")}
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t("soundCategoryDescription")}
+                    </p>
+                  </div>
+                  <div className="flex gap-2">
+                    {(Object.keys(soundCategoryLabels) as SoundCategory[]).map((category) => (
+                      <Button
+                        key={category}
+                        onClick={() => {
+                          void updateSettings({ soundCategory: category }).catch(
+                            console.error,
+                          );
+                        }}
+                        variant={
+                          (settings.soundCategory ?? "standard") === category
+                            ? "default"
+                            : "outline"
+                        }
+                        className="flex-1"
+                      >
+                        {t(soundCategoryLabels[category])}
+                      </Button>
+                    ))}
+                  </div>
+                </div>
+              )}
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="respect-focus-assist"
+                    className="text-base font-medium"
+                  >
+                    {t("respectFocusAssist")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("respectFocusAssistDescription")}
+                    {backendInfo?.focusAssist && backendInfo.focusAssist !== "unknown" && (
+                      <> {t(backendInfo.focusAssist === "off" ? "focusAssistOff" : "focusAssistActive")}</>
+                    )}
+                  </p>
+                </div>
+                <Switch
+                  id="respect-focus-assist"
+                  checked={settings.respectFocusAssist ?? false}
+                  onCheckedChange={(v) => { void handleRespectFocusAssistChange(v); }}
+                />
+              </div>
+
+              <div className="flex items-center justify-between py-4">
+                <div className="space-y-0.5">
+                  <Label
+                    htmlFor="multi-profile-hotkeys"
+                    className="text-base font-medium"
+                  >
+                    {t("multiProfileHotkeys")}
+                  </Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t("multiProfileHotkeysDescription")}
+                  </p>
+                </div>
+                <Switch
+                  id="multi-profile-hotkeys"
+                  checked={settings.multiProfileHotkeys ?? false}
+                  onCheckedChange={(v) => { void handleMultiProfileHotkeysChange(v); }}
+                />
+              </div>
+
+              <div className="py-4 space-y-3">
+                <div className="flex items-center justify-between">
+                  <div className="space-y-0.5">
+                    <Label
+                      htmlFor="led-indicator"
+                      className="text-base font-medium"
+                    >
+                      {t("ledIndicator")}
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t("ledIndicatorDescription")}
+                    </p>
+                  </div>
+                  <Switch
+                    id="led-indicator"
+                    checked={settings.ledIndicator ?? false}
+                    onCheckedChange={(v) => { void handleLedIndicatorChange(v); }}
+                  />
+                </div>
+                {settings.ledIndicator && (
+                  <div className="flex gap-2">
+                    {(["scroll-lock", "num-lock", "caps-lock"] as const).map((key) => (
+                      <Button
+                        key={key}
+                        onClick={() => {
+                          void updateSettings({ ledIndicatorKey: key }).catch(
+                            console.error,
+                          );
+                        }}
+                        variant={
+                          (settings.ledIndicatorKey ?? "scroll-lock") === key
+                            ? "default"
+                            : "outline"
+                        }
+                        className="flex-1"
+                      >
+                        {t(ledKeyLabels[key])}
+                      </Button>
+                    ))}
+                  </div>
+                )}
+              </div>
+
+              <div className="py-4 space-y-3">
+                <div className="flex items-center justify-between">
+                  <div className="space-y-0.5">
+                    <Label
+                      htmlFor="busylight"
+                      className="text-base font-medium"
+                    >
+                      {t("busylight")}
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t("busylightDescription")}
+                    </p>
+                  </div>
+                  <Switch
+                    id="busylight"
+                    checked={busylight.enabled}
+                    onCheckedChange={(v) => { void handleBusylightChange({ enabled: v }); }}
+                  />
+                </div>
+                {busylight.enabled && (
+                  <div className="space-y-3">
+                    <div className="flex items-center gap-4">
+                      <Label className="flex items-center gap-2">
+                        <input
+                          type="color"
+                          value={busylight.mutedColor}
+                          onChange={(e) => { void handleBusylightChange({ mutedColor: e.target.value }); }}
+                        />
+                        {t("busylightMutedColor")}
+                      </Label>
+                      <Label className="flex items-center gap-2">
+                        <input
+                          type="color"
+                          value={busylight.unmutedColor}
+                          onChange={(e) => { void handleBusylightChange({ unmutedColor: e.target.value }); }}
+                        />
+                        {t("busylightUnmutedColor")}
+                      </Label>
+                      <Button
+                        variant="outline"
+                        size="sm"
+                        className="ml-auto"
+                        onClick={() => { void handleDetectLights(); }}
+                      >
+                        {t("busylightDetect")}
+                      </Button>
+                    </div>
+                    {hidLights && (
+                      <p className="text-sm text-muted-foreground">
+                        {hidLights.length > 0
+                          ? t("busylightFound", { names: hidLights.map((l) => l.name).join(", ") })
+                          : t("busylightNotFound")}
+                      </p>
+                    )}
+                  </div>
+                )}
+              </div>
             </CardContent>
           </Card>
         </TabsContent>
@@ -550,6 +1169,31 @@ export function SettingsPage({
                   {updateStatus}
                 </p>
               )}
+              <div className="space-y-2 pt-2">
+                <Label className="text-base font-medium">
+                  {t("updateChannel")}
+                </Label>
+                <div className="flex gap-2">
+                  {(["stable", "beta"] as const).map((channel) => (
+                    <Button
+                      key={channel}
+                      onClick={() => {
+                        void updateSettings({ updateChannel: channel }).catch(
+                          console.error,
+                        );
+                      }}
+                      variant={
+                        (settings.updateChannel ?? "stable") === channel
+                          ? "default"
+                          : "outline"
+                      }
+                      className="flex-1"
+                    >
+                      {t(channel === "stable" ? "updateChannelStable" : "updateChannelBeta")}
+                    </Button>
+                  ))}
+                </div>
+              </div>
             </CardContent>
           </Card>
 
@@ -0,0 +1,82 @@
+import { describe, it, expect } from "vitest";
+import type { HotkeyModifier } from "@/contexts/AppContext";
+import {
+  formatHotkey,
+  hotkeyParts,
+  normalizeHotkey,
+  parseHotkey,
+} from "@/lib/hotkey";
+
+const MODIFIERS: HotkeyModifier[] = ["commandOrControl", "shift", "alt", "super"];
+
+// Every subset of the modifiers, in canonical order
+const combinations = Array.from({ length: 1 << MODIFIERS.length }, (_, mask) =>
+  MODIFIERS.filter((_, i) => mask & (1 << i)),
+);
+
+describe("parseHotkey / formatHotkey", () => {
+  it.each(combinations)("round-trips modifiers %j", (...modifiers) => {
+    const binding = { modifiers, key: { type: "named" as const, value: "M" } };
+    const text = formatHotkey(binding);
+    expect(parseHotkey(text)).toEqual(binding);
+    expect(formatHotkey(parseHotkey(text)!)).toBe(text);
+  });
+
+  it.each(["Space", "Plus", "NumpadAdd", "Numpad0", "NumpadDecimal", "+"])(
+    "round-trips the key %s",
+    (value) => {
+      for (const modifiers of combinations) {
+        const binding = { modifiers, key: { type: "named" as const, value } };
+        expect(parseHotkey(formatHotkey(binding))).toEqual(binding);
+      }
+    },
+  );
+
+  it("round-trips virtual-key and scancode keys", () => {
+    expect(parseHotkey("vk:0x13")).toEqual({
+      modifiers: [],
+      key: { type: "vk", value: 0x13 },
+    });
+    expect(formatHotkey(parseHotkey("CommandOrControl+sc:0xE04D")!)).toBe(
+      "CommandOrControl+sc:0xE04D",
+    );
+    expect(formatHotkey(parseHotkey("vk:19")!)).toBe("vk:0x13");
+  });
+
+  it("sorts modifiers and accepts aliases", () => {
+    expect(formatHotkey(parseHotkey("alt+Ctrl+shift+F13")!)).toBe(
+      "CommandOrControl+Shift+Alt+F13",
+    );
+  });
+
+  it("rejects unknown modifiers and missing keys", () => {
+    expect(parseHotkey("Hyper+M")).toBeNull();
+    expect(parseHotkey("Shift+")).toBeNull();
+    expect(parseHotkey("vk:0x1FF")).toBeNull();
+    expect(parseHotkey("sc:0x80")).toBeNull();
+  });
+});
+
+describe("normalizeHotkey", () => {
+  it("parses the string form of older configs", () => {
+    expect(normalizeHotkey("CommandOrControl+Shift+M")).toEqual({
+      modifiers: ["commandOrControl", "shift"],
+      key: { type: "named", value: "M" },
+    });
+  });
+
+  it("passes typed bindings through", () => {
+    const binding = { modifiers: [], key: { type: "vk" as const, value: 0x13 } };
+    expect(normalizeHotkey(binding)).toBe(binding);
+  });
+});
+
+describe("hotkeyParts", () => {
+  it("lists modifiers before the key", () => {
+    expect(hotkeyParts(parseHotkey("Shift+CommandOrControl+Plus")!)).toEqual([
+      "CommandOrControl",
+      "Shift",
+      "Plus",
+    ]);
+  });
+});